@@ -0,0 +1,48 @@
+//! Test-only CPI wrapper.
+//!
+//! `forward(data)` invokes `target_program` with `data` and the remaining
+//! accounts, keeping their signer and writable flags. Tests use it to check
+//! that nova_staking rejects user instructions that are not top-level.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke;
+
+declare_id!("9hH8xtHTJYkhyLuy7uH7Nt8x5sWvFBXMYeue7WjFSa7P");
+
+#[program]
+pub mod cpi_probe {
+    use super::*;
+
+    pub fn forward<'info>(
+        ctx: Context<'_, '_, '_, 'info, Forward<'info>>,
+        data: Vec<u8>,
+    ) -> Result<()> {
+        let accounts = ctx
+            .remaining_accounts
+            .iter()
+            .map(|account| AccountMeta {
+                pubkey: account.key(),
+                is_signer: account.is_signer,
+                is_writable: account.is_writable,
+            })
+            .collect();
+        let ix = Instruction {
+            program_id: ctx.accounts.target_program.key(),
+            accounts,
+            data,
+        };
+
+        let mut infos = ctx.remaining_accounts.to_vec();
+        infos.push(ctx.accounts.target_program.to_account_info());
+        invoke(&ix, &infos)?;
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct Forward<'info> {
+    /// CHECK: Any program; the probe only forwards to it.
+    #[account(executable)]
+    pub target_program: UncheckedAccount<'info>,
+}
@@ -2,7 +2,68 @@ pub const STAKE_POOL_SEED: &[u8] = b"stake_pool";
 pub const USER_STAKE_SEED: &[u8] = b"user_stake";
 pub const POOL_VAULT_SEED: &[u8] = b"pool_vault";
 pub const TREASURY_VAULT_SEED: &[u8] = b"treasury_vault";
+pub const RESERVE_VAULT_SEED: &[u8] = b"reserve_vault";
+pub const CLAIM_HISTORY_SEED: &[u8] = b"claim_history";
+pub const POSITION_HISTORY_SEED: &[u8] = b"position_history";
+pub const REFERRAL_SEED: &[u8] = b"referral";
+pub const ADMIN_MULTISIG_SEED: &[u8] = b"admin_multisig";
+pub const ADMIN_PROPOSAL_SEED: &[u8] = b"admin_proposal";
+pub const POOL_REGISTRY_SEED: &[u8] = b"pool_registry";
+pub const SNAPSHOT_SEED: &[u8] = b"snapshot";
+pub const USER_SNAPSHOT_SEED: &[u8] = b"user_snapshot";
+pub const RECEIPT_MINT_SEED: &[u8] = b"receipt_mint";
+pub const FEE_VAULT_SEED: &[u8] = b"fee_vault";
+pub const WHITELIST_SEED: &[u8] = b"whitelist";
+pub const STAKER_RECORD_SEED: &[u8] = b"staker_record";
 
+pub const STAKE_POOL_VERSION: u8 = 29;
+pub const USER_STAKE_VERSION: u8 = 9;
+
+pub const SECONDS_PER_DAY: i64 = 86_400;
 pub const SECONDS_PER_YEAR: i64 = 31_536_000;
+pub const CORE_LOCK_PERIOD: i64 = 90 * SECONDS_PER_DAY; // 7,776,000 seconds
+pub const PRIME_LOCK_PERIOD: i64 = 180 * SECONDS_PER_DAY; // 15,552,000 seconds
+pub const MAX_LOCK_PERIOD: i64 = 730 * SECONDS_PER_DAY; // 2 years
+
 pub const BASIS_POINTS_DENOMINATOR: u64 = 10_000;
 pub const MAX_APY: u16 = 2_000; // 20% max
+pub const MAX_RATE_SETTER_APY_DELTA: u16 = 200; // per tier, per RATE_SETTER_WINDOW
+pub const RATE_SETTER_WINDOW: i64 = SECONDS_PER_DAY;
+pub const MAX_RELOCK_BONUS_BPS: u16 = 500; // 5% max per completed cycle
+pub const MIN_SUNSET_NOTICE: i64 = 30 * SECONDS_PER_DAY;
+pub const MIN_GC_INACTIVITY: i64 = 30 * SECONDS_PER_DAY;
+pub const GC_BOUNTY_LAMPORTS: u64 = 10_000; // paid to the cranker out of the closed account's rent
+pub const MAX_REFERRAL_SHARE_BPS: u16 = 1_000; // 10% of each referee claim
+pub const MAX_CLAIM_FEE_BPS: u16 = 500; // 5% of each claim
+pub const MAX_PARAM_TIMELOCK: i64 = 30 * SECONDS_PER_DAY;
+pub const MAX_CLAIM_EPOCH: i64 = 7 * SECONDS_PER_DAY;
+pub const MAX_EMISSION_EPOCH: i64 = 30 * SECONDS_PER_DAY;
+pub const MAX_UNSTAKE_FEE_BPS: u16 = 100; // 1% of withdrawn principal
+pub const MAX_FLEX_MIN_STAKE: i64 = 7 * SECONDS_PER_DAY;
+pub const MAX_FLEX_COOLDOWN: i64 = 7 * SECONDS_PER_DAY;
+pub const MAX_MIN_ACCRUAL: i64 = SECONDS_PER_DAY;
+pub const MAX_MIN_STAKE_DURATION: i64 = 7 * SECONDS_PER_DAY;
+pub const MAX_UNLOCK_GRACE: i64 = SECONDS_PER_DAY;
+pub const MAX_MIN_CLAIM_INTERVAL: i64 = 7 * SECONDS_PER_DAY;
+pub const MIN_ORACLE_UPDATE_INTERVAL: i64 = 60 * 60; // 1 hour; each update can use an APY checkpoint
+pub const MAX_EARLY_UNSTAKE_PENALTY_BPS: u16 = 3_000; // 30% of the early-exited principal
+pub const MAX_YIELD_ADAPTERS: usize = 4;
+pub const MAX_APY_CHECKPOINTS: usize = 8;
+pub const MAX_APY_BOOSTS: usize = 4;
+pub const MAX_APY_BOOST_MULTIPLIER_BPS: u16 = 30_000; // 3x the base APY
+pub const MAX_APY_BOOST_DURATION: i64 = 30 * SECONDS_PER_DAY;
+pub const MAX_LOYALTY_LEVELS: usize = 4;
+pub const MAX_DECAY_STEPS: u16 = 100; // decay from MAX_APY to zero in at most this many steps
+pub const MAX_BONUS_BATCH: usize = 32;
+pub const MAX_TREASURY_DEPLOY_BPS: u16 = 5_000; // at most half the treasury
+pub const PRECISION: u128 = 1_000_000_000_000; // scale of reward_per_token_stored
+pub const MAX_MULTISIG_SIGNERS: usize = 10; // approvals are tracked in a u16 bitmask
+pub const MAX_PROPOSAL_TTL: i64 = 30 * SECONDS_PER_DAY;
+pub const REGISTRY_INITIAL_CAPACITY: u32 = 8;
+pub const REGISTRY_GROWTH: u32 = 8; // entries added per realloc
+pub const MAX_REGISTRY_ENTRIES: u32 = 256;
+
+pub const CLAIM_HISTORY_LEN: usize = 64;
+pub const CLAIM_HISTORY_PAGE_SIZE: usize = 16; // keeps a page well under the return data limit
+pub const POSITION_HISTORY_LEN: usize = 64;
+pub const POSITION_HISTORY_PAGE_SIZE: usize = 16;
@@ -55,4 +55,451 @@ pub enum StakingError {
 
     #[msg("No active stake")]
     NoActiveStake,
+
+    #[msg("Invalid accrual horizon")]
+    InvalidAccrualHorizon,
+
+    #[msg("Daily stake inflow cap exceeded")]
+    DailyInflowCapExceeded,
+
+    #[msg("Pool is in emergency mode")]
+    EmergencyMode,
+
+    #[msg("Top-up lock policy not allowed by pool")]
+    TopUpPolicyNotAllowed,
+
+    #[msg("Top-up lock policy requires lot accounting")]
+    TopUpPolicyUnavailable,
+
+    #[msg("Invalid top-up policy mask")]
+    InvalidTopUpPolicyMask,
+
+    #[msg("Invalid relock bonus configuration")]
+    InvalidRelockBonus,
+
+    #[msg("Invalid early-bird configuration")]
+    InvalidEarlyBirdConfig,
+
+    #[msg("Invalid claim streak configuration")]
+    InvalidStreakConfig,
+
+    #[msg("Release schedule must add up to the tier lock period")]
+    InvalidReleaseSchedule,
+
+    #[msg("Position is younger than the reward cliff")]
+    RewardCliffNotReached,
+
+    #[msg("Invalid reward cliff")]
+    InvalidRewardCliff,
+
+    #[msg("Permanent tier is not enabled")]
+    PermanentTierDisabled,
+
+    #[msg("Permanent tier is locked until the sunset")]
+    SunsetNotReached,
+
+    #[msg("Sunset has already been declared")]
+    SunsetAlreadyDeclared,
+
+    #[msg("Sunset must be at least 30 days out")]
+    SunsetTooSoon,
+
+    #[msg("Reward boosting is disabled")]
+    BoostDisabled,
+
+    #[msg("Invalid boost duration")]
+    InvalidBoostDuration,
+
+    #[msg("A boost is already active")]
+    BoostAlreadyActive,
+
+    #[msg("Not enough pending rewards")]
+    InsufficientPendingRewards,
+
+    #[msg("Invalid referrer")]
+    InvalidReferrer,
+
+    #[msg("Referrer has already been set")]
+    ReferrerAlreadySet,
+
+    #[msg("Referral accounts are required for this claim")]
+    MissingReferralAccounts,
+
+    #[msg("Referral share exceeds maximum")]
+    InvalidReferralShare,
+
+    #[msg("Claim fee exceeds maximum")]
+    InvalidClaimFee,
+
+    #[msg("Fee vault is required while a claim fee is set")]
+    MissingFeeVault,
+
+    #[msg("Fee vault does not match the pool")]
+    FeeVaultMismatch,
+
+    #[msg("No pending parameter change")]
+    NoPendingChange,
+
+    #[msg("Parameter timelock has not elapsed")]
+    ParamTimelockActive,
+
+    #[msg("Invalid parameter timelock")]
+    InvalidParamTimelock,
+
+    #[msg("Pool claim limit reached for this epoch")]
+    ClaimRateLimited,
+
+    #[msg("Invalid claim rate limit")]
+    InvalidClaimRateLimit,
+
+    #[msg("Tier APY is below the quoted rate")]
+    ApyChangedSinceQuote,
+
+    #[msg("Claim payout is below the expected minimum")]
+    PayoutBelowExpected,
+
+    #[msg("Unstake fee exceeds maximum")]
+    InvalidUnstakeFee,
+
+    #[msg("Treasury vault is required while an unstake fee is set")]
+    MissingTreasuryVault,
+
+    #[msg("Staking mint does not support burning penalties")]
+    BurnNotSupported,
+
+    #[msg("Flex minimum stake duration not reached")]
+    FlexCooldownActive,
+
+    #[msg("Invalid Flex minimum stake duration")]
+    InvalidFlexMinStake,
+
+    #[msg("Treasury funds are deployed; recall before claiming")]
+    TreasuryIlliquid,
+
+    #[msg("Yield adapter is not whitelisted")]
+    YieldAdapterNotWhitelisted,
+
+    #[msg("Invalid yield adapter configuration")]
+    InvalidYieldAdapter,
+
+    #[msg("Treasury deployment cap exceeded")]
+    TreasuryDeployCapExceeded,
+
+    #[msg("Treasury funds are deployed to another adapter")]
+    TreasuryAlreadyDeployed,
+
+    #[msg("Yield adapter moved an unexpected amount")]
+    AdapterTransferMismatch,
+
+    #[msg("Too many accounts in bonus batch")]
+    BonusBatchTooLarge,
+
+    #[msg("Duplicate account in batch")]
+    DuplicateAccount,
+
+    #[msg("Invalid unlock grace window")]
+    InvalidUnlockGrace,
+
+    #[msg("Early unstake is not enabled for this tier")]
+    EarlyUnstakeDisabled,
+
+    #[msg("Lock has ended; use unstake instead")]
+    LockAlreadyEnded,
+
+    #[msg("Invalid early unstake penalty")]
+    InvalidEarlyUnstakePenalty,
+
+    #[msg("Position still holds staked principal")]
+    PositionNotEmpty,
+
+    #[msg("Claim outstanding rewards before closing the position")]
+    RewardsOutstanding,
+
+    #[msg("Pool is not in emergency mode")]
+    EmergencyModeInactive,
+
+    #[msg("Stake amount is below the pool minimum for a new position")]
+    StakeBelowMinimum,
+
+    #[msg("Stake would exceed the pool-wide staking cap")]
+    PoolCapReached,
+
+    #[msg("Invalid Flex withdrawal cooldown")]
+    InvalidFlexCooldown,
+
+    #[msg("Flex withdrawals go through request_unstake while a cooldown is set")]
+    UnstakeRequestRequired,
+
+    #[msg("Only Flex positions can queue a withdrawal")]
+    UnstakeRequestFlexOnly,
+
+    #[msg("A withdrawal request is already pending")]
+    UnstakeRequestPending,
+
+    #[msg("No pending withdrawal request")]
+    NoUnstakeRequest,
+
+    #[msg("Withdrawal cooldown has not elapsed")]
+    WithdrawalCooldownActive,
+
+    #[msg("A referrer can only be given on the first stake")]
+    ReferrerOnlyOnFirstStake,
+
+    #[msg("Auto-renew is only available on Core and Prime")]
+    AutoRenewRequiresLock,
+
+    #[msg("Pool must be paused")]
+    PoolNotPaused,
+
+    #[msg("Withdrawal would leave the treasury below outstanding liabilities")]
+    TreasuryBelowLiabilities,
+
+    #[msg("Only available when rewards are paid in the staking token")]
+    SingleTokenPoolOnly,
+
+    #[msg("Invalid lock period")]
+    InvalidLockPeriod,
+
+    #[msg("Invalid APY decay schedule")]
+    InvalidDecaySchedule,
+
+    #[msg("Cannot change tier")]
+    CannotChangeTier,
+
+    #[msg("Invalid claim delegate")]
+    InvalidClaimDelegate,
+
+    #[msg("A claim delegate can only pay the stake owner")]
+    DelegateCannotRedirect,
+
+    #[msg("Stake would exceed the pool's projected reward capacity")]
+    PoolInsolvent,
+
+    #[msg("Solvency treasury multiple must be greater than zero")]
+    InvalidSolvencyMultiple,
+
+    #[msg("Not available in this pool's reward mode")]
+    RewardModeMismatch,
+
+    #[msg("Claims are paused")]
+    ClaimsPaused,
+
+    #[msg("Unstaking is paused")]
+    UnstakingPaused,
+
+    #[msg("Invalid pause flags")]
+    InvalidPauseFlags,
+
+    #[msg("Claims and unstaking can only both be paused in emergency mode")]
+    CannotPauseAllExits,
+
+    #[msg("Tokens in this account cannot be recovered")]
+    RecoveryNotAllowed,
+
+    #[msg("Amount exceeds the recoverable surplus")]
+    RecoveryExceedsSurplus,
+
+    #[msg("This action requires multisig approval")]
+    MultisigRequired,
+
+    #[msg("Invalid multisig signers, threshold or proposal lifetime")]
+    InvalidMultisigConfig,
+
+    #[msg("Signer is not a member of the admin multisig")]
+    NotMultisigSigner,
+
+    #[msg("Admin proposal has expired")]
+    ProposalExpired,
+
+    #[msg("Admin proposal was already executed")]
+    ProposalAlreadyExecuted,
+
+    #[msg("Signer already approved this proposal")]
+    AlreadyApproved,
+
+    #[msg("Position was deposited into too recently to unstake")]
+    StakeTooRecent,
+
+    #[msg("Invalid minimum accrual or stake duration")]
+    InvalidMinDuration,
+
+    #[msg("Account layout version is not supported; migrate it first")]
+    AccountVersionMismatch,
+
+    #[msg("Account is not a migratable program account")]
+    AccountNotMigratable,
+
+    #[msg("Invalid loyalty schedule")]
+    InvalidLoyaltySchedule,
+
+    #[msg("Distributed rewards exceed recorded funding")]
+    TreasuryAccountingMismatch,
+
+    #[msg("Emission limit for this epoch reached")]
+    EpochEmissionExceeded,
+
+    #[msg("Invalid epoch emission limit")]
+    InvalidEpochEmissionLimit,
+
+    #[msg("Pool registry is full")]
+    RegistryFull,
+
+    #[msg("Pool is already registered")]
+    PoolAlreadyRegistered,
+
+    #[msg("Pool is not in the registry")]
+    PoolNotRegistered,
+
+    #[msg("Instruction must be called directly, not through CPI")]
+    CpiNotAllowed,
+
+    #[msg("Nothing is staked in the pool")]
+    NoStakers,
+
+    #[msg("APY change exceeds the rate setter limit")]
+    ApyChangeTooLarge,
+
+    #[msg("Role holder cannot be the default pubkey")]
+    InvalidRoleHolder,
+
+    #[msg("Pool has ended; rewards stopped and no new stakes are accepted")]
+    PoolEnded,
+
+    #[msg("Rewards end time must be in the future")]
+    InvalidRewardsEndTime,
+
+    #[msg("A label can only be given on the first stake; use set_stake_label")]
+    LabelOnlyOnFirstStake,
+
+    #[msg("Stake label must be zero-padded UTF-8 text")]
+    InvalidStakeLabel,
+
+    #[msg("Pause expiry must be in the future and needs paused = true")]
+    InvalidPauseExpiry,
+
+    #[msg("Claims on this position must pay its reward_destination")]
+    RewardDestinationMismatch,
+
+    #[msg("Snapshot interval cannot be negative")]
+    InvalidSnapshotInterval,
+
+    #[msg("Snapshots are disabled for this pool")]
+    SnapshotsDisabled,
+
+    #[msg("The snapshot interval has not passed since the last snapshot")]
+    SnapshotNotDue,
+
+    #[msg("Snapshot epoch is not the next one (take) or the latest one (record)")]
+    SnapshotEpochMismatch,
+
+    #[msg("The position took a deposit after this snapshot")]
+    StakedAfterSnapshot,
+
+    #[msg("Invalid oracle configuration")]
+    InvalidOracleConfig,
+
+    #[msg("Oracle mode is off for this pool")]
+    OracleNotConfigured,
+
+    #[msg("Price account does not match the pool's oracle")]
+    OracleMismatch,
+
+    #[msg("Oracle account is not a usable Pyth price")]
+    OraclePriceInvalid,
+
+    #[msg("Oracle price is older than max_staleness_secs")]
+    OracleStale,
+
+    #[msg("Oracle confidence interval is too wide")]
+    OracleConfidenceTooWide,
+
+    #[msg("The oracle update interval has not passed")]
+    OracleUpdateTooSoon,
+
+    #[msg("Receipt mint and receipt token account are required in receipt mode")]
+    MissingReceiptAccounts,
+
+    #[msg("Not enough receipt tokens to release this principal")]
+    InsufficientReceipts,
+
+    #[msg("New owner must differ from the current owner")]
+    InvalidNewOwner,
+
+    #[msg("Invalid recovery address or delay")]
+    InvalidRecoveryConfig,
+
+    #[msg("Signer is not the position's recovery address")]
+    NotRecoveryAddress,
+
+    #[msg("A recovery is already pending")]
+    RecoveryAlreadyPending,
+
+    #[msg("No recovery is pending")]
+    NoPendingRecovery,
+
+    #[msg("The recovery delay has not passed")]
+    RecoveryDelayNotElapsed,
+
+    #[msg("Fee vault holds no fees to collect")]
+    NoFeesToCollect,
+
+    #[msg("Invalid APY boost multiplier or window")]
+    InvalidApyBoost,
+
+    #[msg("APY boost overlaps the previous campaign")]
+    ApyBoostOverlap,
+
+    #[msg("Garbage collection of idle positions is disabled")]
+    GcDisabled,
+
+    #[msg("Position was active too recently to be collected")]
+    PositionRecentlyActive,
+
+    #[msg("Invalid garbage collection inactivity period")]
+    InvalidGcInactivity,
+
+    #[msg("Claimed too soon after the last settlement")]
+    ClaimTooSoon,
+
+    #[msg("Invalid minimum claim interval")]
+    InvalidClaimInterval,
+
+    #[msg("Wallet is not on the pool's staking whitelist")]
+    NotWhitelisted,
+
+    #[msg("Adapter vault must not be owned by the pool")]
+    InvalidAdapterVault,
+
+    #[msg("Unauthorized")]
+    Unauthorized,
+
+    #[msg("Treasury vault mismatch")]
+    TreasuryMismatch,
+
+    #[msg("Treasury vault must be owned by the pool")]
+    InvalidTreasuryOwner,
+
+    #[msg("APY exceeds the maximum allowed")]
+    ApyTooHigh,
+
+    #[msg("Division by zero")]
+    DivisionByZero,
+
+    #[msg("Emission cap cannot be zero")]
+    ZeroEmissionCap,
+
+    #[msg("Emission cap cannot be below rewards already distributed")]
+    InvalidEmissionCap,
+
+    #[msg("Emission cap exceeded")]
+    EmissionCapExceeded,
+
+    #[msg("Insufficient treasury funds")]
+    InsufficientTreasuryFunds,
+
+    #[msg("No rewards available to claim")]
+    NoRewardsAvailable,
+
+    #[msg("Unstake fees and penalties need a penalty destination")]
+    NoPenaltyDestination,
 }
@@ -0,0 +1,520 @@
+use anchor_lang::prelude::*;
+
+use crate::state::AdminRole;
+
+/// Emitted once, when the authority schedules the sunset that releases the
+/// permanent tier.
+#[event]
+pub struct SunsetDeclared {
+    pub stake_pool: Pubkey,
+    pub authority: Pubkey,
+    pub sunset_at: i64,
+    pub sequence: u64,
+    pub declared_at: i64,
+}
+
+/// Emitted on every stake, with the position and pool totals afterwards.
+#[event]
+pub struct StakeEvent {
+    pub stake_pool: Pubkey,
+    pub user: Pubkey,
+    pub tier: u8,
+    pub amount: u64,
+    pub staked_after: u64,
+    pub total_staked: u64,
+    /// `tier_staked` and `tier_staker_count` of `tier` afterwards
+    pub tier_total_staked: u64,
+    pub tier_staker_count: u64,
+    /// Room left under `max_total_staked`; `None` when the pool is uncapped
+    pub remaining_capacity: Option<u64>,
+    /// Lifetime pool counters afterwards
+    pub cumulative_staked: u64,
+    pub unique_stakers_ever: u64,
+    /// The position's `label`
+    pub label: [u8; 32],
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted on every reward claim; `gross` counts against the emission cap and
+/// is split into the claimer's `net` and the pool's `fee`.
+#[event]
+pub struct ClaimEvent {
+    pub stake_pool: Pubkey,
+    pub user: Pubkey,
+    pub tier: u8,
+    pub gross: u64,
+    pub fee: u64,
+    pub net: u64,
+    pub fee_bps: u16,
+    pub referral_share: u64,
+    /// Claimable rewards left unpaid by the cap or treasury; still pending
+    pub deferred: u64,
+    /// Token account the net payout was sent to
+    pub destination: Pubkey,
+    pub total_claimed: u64,
+    pub total_distributed: u64,
+    /// Pool's `cumulative_claims_count`, including this claim
+    pub claims_count: u64,
+    /// The position's `label`
+    pub label: [u8; 32],
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when a referrer withdraws their credited referral share.
+#[event]
+pub struct ReferralRewardsClaimed {
+    pub stake_pool: Pubkey,
+    pub referrer: Pubkey,
+    pub amount: u64,
+    pub lifetime_earnings: u64,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted on every unstake; `amount` leaves the position and is split into
+/// the user's `net` and the `fee` (tier fee plus any early-exit penalty), which
+/// goes to the treasury or is burned.
+#[event]
+pub struct UnstakeEvent {
+    pub stake_pool: Pubkey,
+    pub owner: Pubkey,
+    pub tier: u8,
+    pub amount: u64,
+    pub fee: u64,
+    pub net: u64,
+    pub remaining: u64,
+    pub total_staked: u64,
+    /// `tier_staked` and `tier_staker_count` of `tier` afterwards
+    pub tier_total_staked: u64,
+    pub tier_staker_count: u64,
+    pub cumulative_unstaked: u64,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted on every compound; `gross` counts against the emission cap and
+/// `compounded` is what was added to the stake after the claim fee.
+#[event]
+pub struct CompoundEvent {
+    pub stake_pool: Pubkey,
+    pub user: Pubkey,
+    pub tier: u8,
+    pub gross: u64,
+    pub fee: u64,
+    pub compounded: u64,
+    pub staked_after: u64,
+    pub total_staked: u64,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when principal leaves a running lock through `early_unstake`.
+#[event]
+pub struct EarlyUnstaked {
+    pub stake_pool: Pubkey,
+    pub owner: Pubkey,
+    pub tier: u8,
+    pub amount: u64,
+    pub penalty: u64,
+    pub penalty_bps: u16,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when a position exits through `emergency_withdraw`; the
+/// unclaimed rewards are forfeited.
+#[event]
+pub struct EmergencyWithdrawn {
+    pub stake_pool: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub forfeited_rewards: u64,
+    pub cumulative_unstaked: u64,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when Flex principal enters the withdrawal queue; it stops
+/// accruing immediately and `withdraw` opens at `available_at`.
+#[event]
+pub struct UnstakeRequested {
+    pub stake_pool: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub staked_after: u64,
+    pub available_at: i64,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when a queued withdrawal returns to the position.
+#[event]
+pub struct UnstakeRequestCancelled {
+    pub stake_pool: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub staked_after: u64,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when `upgrade_tier` moves a position to a higher tier; the
+/// new lock runs from `timestamp` to `lock_end`.
+#[event]
+pub struct TierUpgraded {
+    pub stake_pool: Pubkey,
+    pub owner: Pubkey,
+    pub old_tier: u8,
+    pub new_tier: u8,
+    pub settled_rewards: u64,
+    pub lock_end: i64,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when the authority takes surplus out of the treasury; at least
+/// `liability_floor` stays behind.
+#[event]
+pub struct TreasuryWithdrawn {
+    pub stake_pool: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+    pub remaining: u64,
+    pub liability_floor: u64,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when `gc_user_stake` closes an idle, empty position.
+#[event]
+pub struct UserStakeCollected {
+    pub stake_pool: Pubkey,
+    pub owner: Pubkey,
+    pub cranker: Pubkey,
+    pub bounty: u64,
+    pub last_claim_time: i64,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when a boost campaign is scheduled.
+#[event]
+pub struct ApyBoostScheduled {
+    pub stake_pool: Pubkey,
+    pub multiplier_bps: u16,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when the authority sweeps the pool's fee vault.
+#[event]
+pub struct FeesCollected {
+    pub stake_pool: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted once per `credit_bonus` batch.
+#[event]
+pub struct BonusCredited {
+    pub stake_pool: Pubkey,
+    pub count: u32,
+    pub amount_per_user: u64,
+    pub total: u64,
+    pub bonus_liability: u64,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when an unstake fee is burned instead of sent to the treasury.
+#[event]
+pub struct PenaltyBurned {
+    pub stake_pool: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when principal leaves the permanent tier after the sunset.
+#[event]
+pub struct PermanentUnstaked {
+    pub stake_pool: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub remaining: u64,
+    pub sequence: u64,
+}
+
+/// Emitted by `refresh_pool` with the stored snapshot.
+#[event]
+pub struct PoolSnapshot {
+    pub stake_pool: Pubkey,
+    pub total_staked: u64,
+    pub staker_count: u64,
+    pub tier_staked: [u64; 4],
+    pub tier_staker_count: [u64; 4],
+    pub projected_outstanding_rewards: u64,
+    pub remaining_emission_capacity: u64,
+    pub treasury_balance: u64,
+    pub total_funded: u64,
+    pub total_distributed: u64,
+    /// Result of `StakePool::treasury_consistency`.
+    pub treasury_consistent: bool,
+    pub cumulative_staked: u64,
+    pub cumulative_unstaked: u64,
+    pub cumulative_claims_count: u64,
+    pub unique_stakers_ever: u64,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted on every `fund_treasury` deposit.
+#[event]
+pub struct FundTreasuryEvent {
+    pub stake_pool: Pubkey,
+    pub funder: Pubkey,
+    pub amount: u64,
+    /// Pool's `total_funded` after this deposit.
+    pub total_funded: u64,
+    pub treasury_balance: u64,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when an owner sets or revokes their claim delegate; a default
+/// `delegate` means revoked.
+#[event]
+pub struct ClaimDelegateChanged {
+    pub stake_pool: Pubkey,
+    pub owner: Pubkey,
+    pub delegate: Pubkey,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when an owner sets or clears their reward destination; a default
+/// `destination` means cleared.
+#[event]
+pub struct RewardDestinationChanged {
+    pub stake_pool: Pubkey,
+    pub owner: Pubkey,
+    pub old_destination: Pubkey,
+    pub destination: Pubkey,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted by `transfer_stake_ownership`; `pending_rewards` is the settled
+/// amount that moved with the position.
+#[event]
+pub struct StakeOwnershipTransferred {
+    pub stake_pool: Pubkey,
+    pub old_owner: Pubkey,
+    pub new_owner: Pubkey,
+    pub tier: u8,
+    pub staked_amount: u64,
+    pub pending_rewards: u64,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when an owner sets or clears their recovery address; a default
+/// `recovery_address` means cleared.
+#[event]
+pub struct RecoveryAddressSet {
+    pub stake_pool: Pubkey,
+    pub owner: Pubkey,
+    pub recovery_address: Pubkey,
+    pub delay_seconds: i64,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted by `initiate_recovery`; the owner has until `ready_at` to sign for
+/// the position and cancel it.
+#[event]
+pub struct RecoveryInitiated {
+    pub stake_pool: Pubkey,
+    pub owner: Pubkey,
+    pub recovery_address: Pubkey,
+    pub ready_at: i64,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when an owner-signed instruction cancels a pending recovery.
+#[event]
+pub struct RecoveryCancelled {
+    pub stake_pool: Pubkey,
+    pub owner: Pubkey,
+    pub recovery_address: Pubkey,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted by `complete_recovery`, once the position belongs to
+/// `recovery_address`.
+#[event]
+pub struct RecoveryCompleted {
+    pub stake_pool: Pubkey,
+    pub old_owner: Pubkey,
+    pub recovery_address: Pubkey,
+    pub staked_amount: u64,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted by `update_apy_from_oracle`. `price` and `conf` are at
+/// `oracle::PRICE_DECIMALS`; `apys` are Flex, Core and Prime, queued rather
+/// than live while a parameter timelock is set.
+#[event]
+pub struct ApyUpdatedFromOracle {
+    pub stake_pool: Pubkey,
+    pub price: u64,
+    pub conf: u64,
+    pub publish_time: i64,
+    pub apys: [u16; 3],
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted by `take_snapshot`; per-tier totals are in the `Snapshot` account.
+#[event]
+pub struct SnapshotTaken {
+    pub stake_pool: Pubkey,
+    pub epoch: u64,
+    pub total_staked: u64,
+    pub staker_count: u64,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when an owner relabels their position; an all-zero `label` means
+/// cleared.
+#[event]
+pub struct StakeLabelChanged {
+    pub stake_pool: Pubkey,
+    pub owner: Pubkey,
+    pub old_label: [u8; 32],
+    pub label: [u8; 32],
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted by `stake` while `require_solvency` is set, with the yearly
+/// rewards projected after the deposit and the limits they are checked against.
+#[event]
+pub struct SolvencyProjection {
+    pub stake_pool: Pubkey,
+    pub projected_annual_rewards: u64,
+    pub remaining_emission_capacity: u64,
+    pub treasury_limit: u64,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when the authority takes stray tokens out of a pool-owned account.
+#[event]
+pub struct TokensRecovered {
+    pub stake_pool: Pubkey,
+    pub source: Pubkey,
+    pub destination: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when someone donates to the pool's current stakers.
+#[event]
+pub struct DonationReceived {
+    pub stake_pool: Pubkey,
+    pub donor: Pubkey,
+    pub amount: u64,
+    /// Part of `amount` owed to stakers; the rest is rounding dust.
+    pub distributed: u64,
+    pub bonus_per_token: u128,
+    pub total_staked: u64,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted by `sync_vault`.
+#[event]
+pub struct VaultSynced {
+    pub stake_pool: Pubkey,
+    /// Staking vault balance after any sweep.
+    pub vault_balance: u64,
+    pub total_staked: u64,
+    /// Surplus left in the vault.
+    pub unaccounted_principal: u64,
+    /// Surplus moved to the treasury.
+    pub swept: u64,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when a multisig signer proposes an admin action.
+#[event]
+pub struct AdminActionProposed {
+    pub stake_pool: Pubkey,
+    pub proposal: Pubkey,
+    pub nonce: u64,
+    pub proposer: Pubkey,
+    pub expires_at: i64,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted for every approval, including the one that executes the action.
+#[event]
+pub struct AdminActionApproved {
+    pub stake_pool: Pubkey,
+    pub proposal: Pubkey,
+    pub approver: Pubkey,
+    pub approvals: u8,
+    pub threshold: u8,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when a proposal reaches its threshold and is applied.
+#[event]
+pub struct AdminActionExecuted {
+    pub stake_pool: Pubkey,
+    pub proposal: Pubkey,
+    pub nonce: u64,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when the authority hands a role to a new key.
+#[event]
+pub struct RoleUpdated {
+    pub stake_pool: Pubkey,
+    pub role: AdminRole,
+    pub old_holder: Pubkey,
+    pub new_holder: Pubkey,
+    pub authority: Pubkey,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when a wallet is added to or removed from a pool's staking whitelist.
+#[event]
+pub struct WhitelistUpdated {
+    pub stake_pool: Pubkey,
+    pub wallet: Pubkey,
+    /// True when added, false when removed.
+    pub whitelisted: bool,
+    pub authority: Pubkey,
+    pub sequence: u64,
+    pub timestamp: i64,
+}
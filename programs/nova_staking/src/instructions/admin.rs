@@ -3,15 +3,20 @@
 /// Handles admin-only operations for the staking pool.
 ///
 /// ## Security Guarantees
-/// - All admin functions require signer == pool.authority
+/// - All admin functions require signer == pool.authority, except that the
+///   pause instructions and `adjust_apy` also accept the pauser and rate setter
 /// - PDA validation ensures correct pool
 /// - Parameter bounds checking
 
 use anchor_lang::prelude::*;
+use anchor_spl::token::Mint;
 
 use crate::constants::*;
 use crate::error::StakingError;
-use crate::state::StakePool;
+use crate::events::{ApyBoostScheduled, RoleUpdated, SunsetDeclared};
+use crate::instructions::stake::TopUpLockPolicy;
+use crate::state::{AdminRole, ApyBoost, OracleApyConfig, PenaltyDestination, RewardMode, StakePool};
+use crate::tier::Tier;
 
 /// Accounts required for admin operations.
 ///
@@ -34,32 +39,110 @@ pub struct AdminControl<'info> {
     #[account(
         mut,
         seeds = [STAKE_POOL_SEED, stake_pool.staking_mint.as_ref()],
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ StakingError::AccountVersionMismatch,
         bump = stake_pool.bump,
         has_one = authority @ StakingError::Unauthorized
     )]
     pub stake_pool: Account<'info, StakePool>,
 }
 
+/// Accounts for the pause instructions.
+///
+/// ## Security Notes
+/// - Signer must be pool.authority or pool.pauser
+/// - Pool PDA validated via seeds
+#[derive(Accounts)]
+pub struct PauserControl<'info> {
+    /// The authority or the pauser.
+    /// SECURITY: Must be signer AND hold `AdminRole::Pauser`.
+    #[account(
+        constraint = stake_pool.has_role(AdminRole::Pauser, &authority.key()) @ StakingError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    /// The stake pool to modify.
+    /// SECURITY: PDA validation.
+    #[account(
+        mut,
+        seeds = [STAKE_POOL_SEED, stake_pool.staking_mint.as_ref()],
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ StakingError::AccountVersionMismatch,
+        bump = stake_pool.bump
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+}
+
+/// Accounts for `adjust_apy`.
+///
+/// ## Security Notes
+/// - Signer must be pool.authority or pool.rate_setter
+/// - Pool PDA validated via seeds
+#[derive(Accounts)]
+pub struct RateSetterControl<'info> {
+    /// The authority or the rate setter.
+    /// SECURITY: Must be signer AND hold `AdminRole::RateSetter`.
+    #[account(
+        constraint = stake_pool.has_role(AdminRole::RateSetter, &authority.key()) @ StakingError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    /// The stake pool to modify.
+    /// SECURITY: PDA validation.
+    #[account(
+        mut,
+        seeds = [STAKE_POOL_SEED, stake_pool.staking_mint.as_ref()],
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ StakingError::AccountVersionMismatch,
+        bump = stake_pool.bump
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+}
+
 /// Set the paused state of the staking pool.
 ///
 /// # Security
-/// - Only pool.authority can call this
-/// - When paused, new stakes are blocked
-/// - Unstaking and claiming remain available (user funds not locked)
+/// - Only pool.authority or pool.pauser can call this
+/// - Shorthand for the stake bit of `set_pause_flags`: when paused, new
+///   stakes are blocked
+/// - Leaves the claim and unstake bits as they are
+/// - A pause with an expiry lifts itself from that second on; without one
+///   it lasts until unpaused
+/// - Multisig pools must go through `propose_admin_action`
 ///
 /// # Arguments
-/// * `ctx` - AdminControl accounts context
+/// * `ctx` - PauserControl accounts context
 /// * `paused` - True to pause, false to unpause
+/// * `pause_expiry` - When the pause ends by itself; only with `paused`
 ///
 /// # Returns
 /// Result indicating success or error
-pub fn set_paused_handler(ctx: Context<AdminControl>, paused: bool) -> Result<()> {
+pub fn set_paused_handler(
+    ctx: Context<PauserControl>,
+    paused: bool,
+    pause_expiry: Option<i64>,
+) -> Result<()> {
     let stake_pool = &mut ctx.accounts.stake_pool;
     let clock = Clock::get()?;
 
-    let previous_state = stake_pool.paused;
-    stake_pool.paused = paused;
+    require!(!stake_pool.has_admin_multisig(), StakingError::MultisigRequired);
+    if let Some(expiry) = pause_expiry {
+        require!(
+            paused && expiry > clock.unix_timestamp,
+            StakingError::InvalidPauseExpiry
+        );
+    }
+
+    // An expired pause ends at its expiry, not now
+    stake_pool.expire_pause(clock.unix_timestamp)?;
+
+    let previous_state = stake_pool.is_stake_paused();
+    if paused {
+        stake_pool.pause_flags |= StakePool::PAUSE_STAKE_BIT;
+    } else {
+        stake_pool.pause_flags &= !StakePool::PAUSE_STAKE_BIT;
+    }
+    stake_pool.pause_expiry = pause_expiry.unwrap_or(0);
+    stake_pool.sync_pause_clock(clock.unix_timestamp)?;
     stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
 
     msg!(
         "Staking {} (was {})",
@@ -71,15 +154,122 @@ pub fn set_paused_handler(ctx: Context<AdminControl>, paused: bool) -> Result<()
     Ok(())
 }
 
+/// Set which actions are paused.
+///
+/// # Security
+/// - Only pool.authority or pool.pauser can call this
+/// - Unknown bits are rejected
+/// - Claims and unstakes can only be paused together in emergency mode,
+///   where `emergency_withdraw` still returns principal
+/// - Multisig pools must go through `propose_admin_action`
+///
+/// # Arguments
+/// * `ctx` - PauserControl accounts context
+/// * `flags` - Bitmask: 1 = stake, 2 = claim, 4 = unstake
+///
+/// # Returns
+/// Result indicating success or error
+pub fn set_pause_flags_handler(ctx: Context<PauserControl>, flags: u8) -> Result<()> {
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    require!(!stake_pool.has_admin_multisig(), StakingError::MultisigRequired);
+    stake_pool.next_sequence()?;
+    apply_pause_flags(stake_pool, flags, clock.unix_timestamp)?;
+    msg!("Admin: {}", ctx.accounts.authority.key());
+
+    Ok(())
+}
+
+/// Validate and store new pause flags; shared with the multisig path.
+/// Explicit flags never expire, so any `pause_expiry` is dropped.
+pub fn apply_pause_flags(stake_pool: &mut StakePool, flags: u8, now: i64) -> Result<()> {
+    require!(
+        flags & !StakePool::PAUSE_ALL_BITS == 0,
+        StakingError::InvalidPauseFlags
+    );
+    require!(
+        StakePool::allows_pause_flags(flags, stake_pool.emergency),
+        StakingError::CannotPauseAllExits
+    );
+
+    stake_pool.expire_pause(now)?;
+    let old_flags = stake_pool.pause_flags;
+    stake_pool.pause_flags = flags;
+    stake_pool.pause_expiry = 0;
+    stake_pool.sync_pause_clock(now)?;
+    stake_pool.last_updated = now;
+
+    msg!("Pause flags: {:#05b} -> {:#05b}", old_flags, flags);
+
+    Ok(())
+}
+
+/// Pause or resume new stakes into a single tier.
+///
+/// # Security
+/// - Only pool.authority or pool.pauser can call this
+/// - The pool-wide stake pause still overrides every tier
+/// - Only blocks stake, change_tier into the tier and compounding; unstaking
+///   and claiming stay available
+/// - Multisig pools must go through `propose_admin_action`
+///
+/// # Arguments
+/// * `ctx` - PauserControl accounts context
+/// * `tier` - Tier to configure (0=Flex, 1=Core, 2=Prime)
+/// * `paused` - True to pause, false to resume
+///
+/// # Returns
+/// Result indicating success or error
+pub fn set_tier_paused_handler(ctx: Context<PauserControl>, tier: u8, paused: bool) -> Result<()> {
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    require!(!stake_pool.has_admin_multisig(), StakingError::MultisigRequired);
+    stake_pool.next_sequence()?;
+    apply_tier_paused(stake_pool, tier, paused, clock.unix_timestamp)?;
+    msg!("Admin: {}", ctx.accounts.authority.key());
+
+    Ok(())
+}
+
+/// Pause or resume one tier; shared with the multisig path.
+pub fn apply_tier_paused(stake_pool: &mut StakePool, tier: u8, paused: bool, now: i64) -> Result<()> {
+    match Tier::try_from(tier)? {
+        Tier::Flex => stake_pool.flex_paused = paused,
+        Tier::Core => stake_pool.core_paused = paused,
+        Tier::Prime => stake_pool.prime_paused = paused,
+        Tier::Permanent => return err!(StakingError::InvalidTier),
+    }
+    stake_pool.last_updated = now;
+
+    msg!(
+        "Tier {} staking {}",
+        tier,
+        if paused { "PAUSED" } else { "RESUMED" }
+    );
+
+    Ok(())
+}
+
 /// Adjust APY rates for all tiers.
 ///
 /// # Security
-/// - Only pool.authority can call this
+/// - Only pool.authority or pool.rate_setter can call this
 /// - APY values capped at MAX_APY (50%)
-/// - Changes only affect future reward calculations
+/// - The rate setter can move each tier by at most
+///   MAX_RATE_SETTER_APY_DELTA per RATE_SETTER_WINDOW, measured from the
+///   live APY when the window opened, so repeated calls cannot walk a rate
+/// - An authority change closes the window; the rate setter's next call
+///   opens a new one from the live rates
+/// - The old rates are checkpointed, so time already elapsed keeps
+///   accruing at them; changes only affect future reward calculations
+/// - While `param_timelock_seconds` is set, the rates are queued and only
+///   take effect through `apply_pending_params`
+/// - Multisig pools must go through `propose_admin_action`
 ///
 /// # Arguments
-/// * `ctx` - AdminControl accounts context
+/// * `ctx` - RateSetterControl accounts context
 /// * `flex_apy` - New Flex tier APY (basis points)
 /// * `core_apy` - New Core tier APY (basis points)
 /// * `prime_apy` - New Prime tier APY (basis points)
@@ -87,10 +277,74 @@ pub fn set_paused_handler(ctx: Context<AdminControl>, paused: bool) -> Result<()
 /// # Returns
 /// Result indicating success or error
 pub fn adjust_apy_handler(
-    ctx: Context<AdminControl>,
+    ctx: Context<RateSetterControl>,
+    flex_apy: u16,
+    core_apy: u16,
+    prime_apy: u16,
+) -> Result<()> {
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    require!(!stake_pool.has_admin_multisig(), StakingError::MultisigRequired);
+    stake_pool.next_sequence()?;
+    if ctx.accounts.authority.key() != stake_pool.authority {
+        let baseline = stake_pool.rate_setter_baseline(clock.unix_timestamp);
+        for (new_apy, old_apy) in [flex_apy, core_apy, prime_apy].into_iter().zip(baseline) {
+            require!(
+                new_apy.abs_diff(old_apy) <= MAX_RATE_SETTER_APY_DELTA,
+                StakingError::ApyChangeTooLarge
+            );
+        }
+    } else {
+        stake_pool.rate_setter_window_start = 0;
+    }
+    schedule_apy(stake_pool, flex_apy, core_apy, prime_apy, clock.unix_timestamp)?;
+    msg!("Admin: {}", ctx.accounts.authority.key());
+
+    Ok(())
+}
+
+/// Apply new tier APYs now, or queue them behind the parameter timelock;
+/// shared with the multisig path.
+pub fn schedule_apy(
+    stake_pool: &mut StakePool,
+    flex_apy: u16,
+    core_apy: u16,
+    prime_apy: u16,
+    now: i64,
+) -> Result<()> {
+    if stake_pool.param_timelock_seconds == 0 {
+        return apply_apy(stake_pool, flex_apy, core_apy, prime_apy, now);
+    }
+
+    for apy in [flex_apy, core_apy, prime_apy] {
+        require!(apy <= MAX_APY, StakingError::ApyTooHigh);
+    }
+
+    stake_pool.pending_apy = [flex_apy, core_apy, prime_apy];
+    stake_pool.pending_apy_effective_at = now
+        .checked_add(stake_pool.param_timelock_seconds)
+        .ok_or(StakingError::MathOverflow)?;
+    stake_pool.last_updated = now;
+
+    msg!(
+        "APY change queued - Flex={}bp, Core={}bp, Prime={}bp, effective at {}",
+        flex_apy,
+        core_apy,
+        prime_apy,
+        stake_pool.pending_apy_effective_at
+    );
+
+    Ok(())
+}
+
+/// Validate and store new tier APYs immediately.
+pub fn apply_apy(
+    stake_pool: &mut StakePool,
     flex_apy: u16,
     core_apy: u16,
     prime_apy: u16,
+    now: i64,
 ) -> Result<()> {
     // === INPUT VALIDATION ===
     
@@ -99,9 +353,6 @@ pub fn adjust_apy_handler(
     require!(core_apy <= MAX_APY, StakingError::ApyTooHigh);
     require!(prime_apy <= MAX_APY, StakingError::ApyTooHigh);
 
-    let stake_pool = &mut ctx.accounts.stake_pool;
-    let clock = Clock::get()?;
-
     msg!(
         "Adjusting APY rates - Old: Flex={}bp, Core={}bp, Prime={}bp",
         stake_pool.flex_apy,
@@ -109,11 +360,14 @@ pub fn adjust_apy_handler(
         stake_pool.prime_apy
     );
 
-    // Update APY values
-    stake_pool.flex_apy = flex_apy;
-    stake_pool.core_apy = core_apy;
-    stake_pool.prime_apy = prime_apy;
-    stake_pool.last_updated = clock.unix_timestamp;
+    // Checkpoint the old rates, then update APY values
+    let decay = stake_pool.decay_schedule();
+    stake_pool.set_apy_schedule(
+        [flex_apy, core_apy, prime_apy, stake_pool.permanent_apy],
+        decay,
+        now,
+    )?;
+    stake_pool.last_updated = now;
 
     msg!(
         "New APY rates - Flex={}bp, Core={}bp, Prime={}bp",
@@ -121,7 +375,6 @@ pub fn adjust_apy_handler(
         core_apy,
         prime_apy
     );
-    msg!("Admin: {}", ctx.accounts.authority.key());
 
     Ok(())
 }
@@ -132,6 +385,9 @@ pub fn adjust_apy_handler(
 /// - Only pool.authority can call this
 /// - New cap cannot be less than already distributed rewards
 /// - Prevents admin from stranding user rewards
+/// - While `param_timelock_seconds` is set, the cap is queued and only
+///   takes effect through `apply_pending_params`
+/// - Multisig pools must go through `propose_admin_action`
 ///
 /// # Arguments
 /// * `ctx` - AdminControl accounts context
@@ -143,6 +399,46 @@ pub fn update_emission_cap_handler(ctx: Context<AdminControl>, new_cap: u64) ->
     let stake_pool = &mut ctx.accounts.stake_pool;
     let clock = Clock::get()?;
 
+    require!(!stake_pool.has_admin_multisig(), StakingError::MultisigRequired);
+    stake_pool.next_sequence()?;
+    schedule_emission_cap(stake_pool, new_cap, clock.unix_timestamp)?;
+    msg!("Admin: {}", ctx.accounts.authority.key());
+
+    Ok(())
+}
+
+/// Apply a new emission cap now, or queue it behind the parameter timelock;
+/// shared with the multisig path. A queued cap is checked against
+/// `total_distributed` again when it is applied.
+pub fn schedule_emission_cap(stake_pool: &mut StakePool, new_cap: u64, now: i64) -> Result<()> {
+    if stake_pool.param_timelock_seconds == 0 {
+        return apply_emission_cap(stake_pool, new_cap, now);
+    }
+
+    require!(new_cap > 0, StakingError::ZeroEmissionCap);
+    require!(
+        new_cap >= stake_pool.total_distributed,
+        StakingError::InvalidEmissionCap
+    );
+
+    stake_pool.pending_emission_cap = new_cap;
+    stake_pool.pending_emission_cap_effective_at = now
+        .checked_add(stake_pool.param_timelock_seconds)
+        .ok_or(StakingError::MathOverflow)?;
+    stake_pool.last_updated = now;
+
+    msg!(
+        "Emission cap change queued: {} -> {}, effective at {}",
+        stake_pool.emission_cap,
+        new_cap,
+        stake_pool.pending_emission_cap_effective_at
+    );
+
+    Ok(())
+}
+
+/// Validate and store a new emission cap immediately.
+pub fn apply_emission_cap(stake_pool: &mut StakePool, new_cap: u64, now: i64) -> Result<()> {
     // === INPUT VALIDATION ===
     
     // New cap must be non-zero
@@ -157,52 +453,2115 @@ pub fn update_emission_cap_handler(ctx: Context<AdminControl>, new_cap: u64) ->
 
     let old_cap = stake_pool.emission_cap;
     stake_pool.emission_cap = new_cap;
-    stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.last_updated = now;
 
-    // Calculate remaining capacity (checked sub for safety)
-    let remaining = new_cap
-        .checked_sub(stake_pool.total_distributed)
-        .unwrap_or(0);
+    // Calculate remaining capacity
+    let remaining = new_cap.saturating_sub(stake_pool.total_distributed);
 
     msg!("Emission cap updated: {} -> {}", old_cap, new_cap);
     msg!("Total distributed: {}", stake_pool.total_distributed);
     msg!("Remaining capacity: {}", remaining);
-    msg!("Admin: {}", ctx.accounts.authority.key());
 
     Ok(())
 }
 
-/// Transfer admin authority to a new address.
+/// Accounts required for accepting a proposed authority transfer.
+///
+/// ## Security Notes
+/// - New authority must be signer AND match pool.pending_authority
+/// - Pool PDA validated via seeds
+#[derive(Accounts)]
+pub struct AcceptAuthority<'info> {
+    /// The proposed authority.
+    /// SECURITY: Must be signer AND match pool.pending_authority.
+    #[account(
+        constraint = new_authority.key() == stake_pool.pending_authority @ StakingError::Unauthorized
+    )]
+    pub new_authority: Signer<'info>,
+
+    /// The stake pool to modify.
+    /// SECURITY: PDA validation.
+    #[account(
+        mut,
+        seeds = [STAKE_POOL_SEED, stake_pool.staking_mint.as_ref()],
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ StakingError::AccountVersionMismatch,
+        bump = stake_pool.bump
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+}
+
+/// Propose a new admin authority.
 ///
 /// # Security
 /// - Only current authority can call this
 /// - New authority must be a valid pubkey (non-zero)
-/// - Two-step transfer recommended for production
+/// - Nothing changes until the proposed key signs `accept_authority`,
+///   so a mistyped key can simply be replaced or cancelled
+/// - Multisig pools must go through `propose_admin_action`
 ///
 /// # Arguments
 /// * `ctx` - AdminControl accounts context
-/// * `new_authority` - New admin pubkey
+/// * `new_authority` - Proposed admin pubkey
 ///
 /// # Returns
 /// Result indicating success or error
-pub fn transfer_authority_handler(
+pub fn propose_authority_handler(
     ctx: Context<AdminControl>,
     new_authority: Pubkey,
 ) -> Result<()> {
     let stake_pool = &mut ctx.accounts.stake_pool;
     let clock = Clock::get()?;
 
+    require!(!stake_pool.has_admin_multisig(), StakingError::MultisigRequired);
+    stake_pool.next_sequence()?;
+    apply_authority_proposal(stake_pool, new_authority, clock.unix_timestamp)
+}
+
+/// Record `new_authority` as pending; shared with the multisig path.
+pub fn apply_authority_proposal(
+    stake_pool: &mut StakePool,
+    new_authority: Pubkey,
+    now: i64,
+) -> Result<()> {
     // Validate new authority is not zero address
     require!(
         new_authority != Pubkey::default(),
         StakingError::Unauthorized
     );
 
+    stake_pool.pending_authority = new_authority;
+    stake_pool.last_updated = now;
+
+    msg!("Authority transfer proposed: {} -> {}", stake_pool.authority, new_authority);
+
+    Ok(())
+}
+
+/// Accept a proposed authority transfer.
+///
+/// # Security
+/// - Only the pending authority can call this
+/// - Clears the pending authority so the proposal cannot be replayed
+///
+/// # Arguments
+/// * `ctx` - AcceptAuthority accounts context
+///
+/// # Returns
+/// Result indicating success or error
+pub fn accept_authority_handler(ctx: Context<AcceptAuthority>) -> Result<()> {
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    require!(
+        stake_pool.pending_authority != Pubkey::default(),
+        StakingError::NoPendingChange
+    );
+
     let old_authority = stake_pool.authority;
-    stake_pool.authority = new_authority;
+    stake_pool.authority = stake_pool.pending_authority;
+    stake_pool.pending_authority = Pubkey::default();
+
+    // Roles never handed out stay with whoever holds the authority
+    let new_authority = stake_pool.authority;
+    for role in [AdminRole::Pauser, AdminRole::RateSetter] {
+        if stake_pool.role_holder(role) == old_authority {
+            stake_pool.set_role(role, new_authority);
+        }
+    }
+    stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+
+    msg!("Authority transferred: {} -> {}", old_authority, stake_pool.authority);
+
+    Ok(())
+}
+
+/// Cancel a proposed authority transfer.
+///
+/// # Security
+/// - Only current authority can call this
+///
+/// # Arguments
+/// * `ctx` - AdminControl accounts context
+///
+/// # Returns
+/// Result indicating success or error
+pub fn cancel_authority_transfer_handler(ctx: Context<AdminControl>) -> Result<()> {
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    require!(
+        stake_pool.pending_authority != Pubkey::default(),
+        StakingError::NoPendingChange
+    );
+
+    let cancelled = stake_pool.pending_authority;
+    stake_pool.pending_authority = Pubkey::default();
+    stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+
+    msg!("Authority transfer to {} cancelled", cancelled);
+    msg!("Admin: {}", ctx.accounts.authority.key());
+
+    Ok(())
+}
+
+/// Toggle funded-only reward accrual.
+///
+/// # Security
+/// - Only pool.authority can call this
+/// - When enabled, time spent with an empty treasury earns nothing
+/// - Applies to every interval not yet settled into `pending_rewards`
+///
+/// # Arguments
+/// * `ctx` - AdminControl accounts context
+/// * `enabled` - True to stop accrual while the treasury is empty
+///
+/// # Returns
+/// Result indicating success or error
+pub fn set_accrue_only_when_funded_handler(
+    ctx: Context<AdminControl>,
+    enabled: bool,
+) -> Result<()> {
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    stake_pool.accrue_only_when_funded = enabled;
+    stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+
+    msg!("Accrue only when funded: {}", enabled);
+    msg!("Admin: {}", ctx.accounts.authority.key());
+
+    Ok(())
+}
+
+/// Choose whether Core and Prime positions drop to the Flex APY once their
+/// lock ends.
+///
+/// # Security
+/// - Only pool.authority can call this
+/// - Applies to every interval not yet settled into `pending_rewards`
+/// - Positions on auto-renew are still locked and keep their tier APY; an
+///   ended lock regains it by relocking through `change_tier`
+///
+/// # Arguments
+/// * `ctx` - AdminControl accounts context
+/// * `enabled` - True to pay Flex's APY after the lock end
+///
+/// # Returns
+/// Result indicating success or error
+pub fn set_downgrade_after_lock_handler(ctx: Context<AdminControl>, enabled: bool) -> Result<()> {
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    stake_pool.downgrade_after_lock = enabled;
     stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+
+    msg!("Downgrade after lock: {}", enabled);
+    msg!("Admin: {}", ctx.accounts.authority.key());
+
+    Ok(())
+}
+
+/// Turn whitelist-gated staking on or off.
+///
+/// # Security
+/// - Only pool.authority can call this
+/// - While on, `stake` and `stake_for` require the position owner's
+///   `WhitelistEntry`; unstaking and claiming are never gated
+///
+/// # Arguments
+/// * `ctx` - AdminControl accounts context
+/// * `enabled` - True to accept deposits from whitelisted wallets only
+///
+/// # Returns
+/// Result indicating success or error
+pub fn set_whitelist_enabled_handler(ctx: Context<AdminControl>, enabled: bool) -> Result<()> {
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
 
-    msg!("Authority transferred: {} -> {}", old_authority, new_authority);
+    stake_pool.whitelist_enabled = enabled;
+    stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+
+    msg!("Whitelist enabled: {}", enabled);
+    msg!("Admin: {}", ctx.accounts.authority.key());
+
+    Ok(())
+}
+
+/// Choose whether rewards keep accruing while staking is paused.
+///
+/// # Security
+/// - Only pool.authority can call this
+/// - When disabled, time with the stake pause bit set earns nothing, so a
+///   long emergency pause does not run down the emission cap
+/// - Changing it mid-pause starts or stops the halt from now; time already
+///   elapsed keeps the setting it accrued under
+///
+/// # Arguments
+/// * `ctx` - AdminControl accounts context
+/// * `enabled` - True to keep accruing while paused
+///
+/// # Returns
+/// Result indicating success or error
+pub fn set_accrue_while_paused_handler(ctx: Context<AdminControl>, enabled: bool) -> Result<()> {
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    stake_pool.expire_pause(clock.unix_timestamp)?;
+    stake_pool.accrue_while_paused = enabled;
+    stake_pool.sync_pause_clock(clock.unix_timestamp)?;
+    stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+
+    msg!("Accrue while paused: {}", enabled);
+    msg!("Admin: {}", ctx.accounts.authority.key());
+
+    Ok(())
+}
+
+/// Set the maximum accrual horizon for untouched positions.
+///
+/// # Security
+/// - Only pool.authority can call this
+/// - A position earns at most `horizon_secs` worth of rewards between touches
+/// - Zero disables the horizon (unlimited accrual)
+///
+/// # Arguments
+/// * `ctx` - AdminControl accounts context
+/// * `horizon_secs` - Horizon in seconds (0 = unlimited)
+///
+/// # Returns
+/// Result indicating success or error
+pub fn set_max_accrual_horizon_handler(
+    ctx: Context<AdminControl>,
+    horizon_secs: i64,
+) -> Result<()> {
+    require!(horizon_secs >= 0, StakingError::InvalidAccrualHorizon);
+
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    let old_horizon = stake_pool.max_accrual_horizon_secs;
+    stake_pool.max_accrual_horizon_secs = horizon_secs;
+    stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+
+    msg!("Accrual horizon updated: {}s -> {}s", old_horizon, horizon_secs);
+    msg!("Admin: {}", ctx.accounts.authority.key());
+
+    Ok(())
+}
+
+/// Set the daily stake inflow cap.
+///
+/// # Security
+/// - Only pool.authority can call this
+/// - Limits total deposits per rolling 24h window
+/// - Unstakes do not restore headroom within the same window
+///
+/// # Arguments
+/// * `ctx` - AdminControl accounts context
+/// * `max_daily_inflow` - Maximum deposits per window (0 = disabled)
+///
+/// # Returns
+/// Result indicating success or error
+pub fn set_max_daily_inflow_handler(
+    ctx: Context<AdminControl>,
+    max_daily_inflow: u64,
+) -> Result<()> {
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    let old_cap = stake_pool.max_daily_inflow;
+    stake_pool.max_daily_inflow = max_daily_inflow;
+    stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+
+    msg!("Daily inflow cap updated: {} -> {}", old_cap, max_daily_inflow);
+    msg!("Admin: {}", ctx.accounts.authority.key());
+
+    Ok(())
+}
+
+/// Enter emergency mode.
+///
+/// # Security
+/// - Only pool.authority can call this
+/// - Blocks stake, claim, and treasury funding
+/// - Unstake stays available and skips lock periods
+/// - Leaving emergency mode requires the separate `clear_emergency` instruction
+/// - Multisig pools must go through `propose_admin_action`
+///
+/// # Arguments
+/// * `ctx` - AdminControl accounts context
+///
+/// # Returns
+/// Result indicating success or error
+pub fn set_emergency_handler(ctx: Context<AdminControl>) -> Result<()> {
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    require!(!stake_pool.has_admin_multisig(), StakingError::MultisigRequired);
+    stake_pool.next_sequence()?;
+    apply_emergency(stake_pool, true, clock.unix_timestamp)?;
+    msg!("Admin: {}", ctx.accounts.authority.key());
+
+    Ok(())
+}
+
+/// Leave emergency mode.
+///
+/// # Security
+/// - Only pool.authority can call this
+/// - Kept separate from `set_emergency` so it cannot be toggled by accident
+/// - Rejected while both claims and unstakes are paused, since that would
+///   leave stakers no exit
+/// - Multisig pools must go through `propose_admin_action`
+///
+/// # Arguments
+/// * `ctx` - AdminControl accounts context
+///
+/// # Returns
+/// Result indicating success or error
+pub fn clear_emergency_handler(ctx: Context<AdminControl>) -> Result<()> {
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    require!(!stake_pool.has_admin_multisig(), StakingError::MultisigRequired);
+    stake_pool.next_sequence()?;
+    apply_emergency(stake_pool, false, clock.unix_timestamp)?;
+    msg!("Admin: {}", ctx.accounts.authority.key());
+
+    Ok(())
+}
+
+/// Enter or leave emergency mode; shared with the multisig path.
+pub fn apply_emergency(stake_pool: &mut StakePool, enabled: bool, now: i64) -> Result<()> {
+    if !enabled {
+        require!(
+            StakePool::allows_pause_flags(stake_pool.pause_flags, false),
+            StakingError::CannotPauseAllExits
+        );
+    }
+
+    stake_pool.emergency = enabled;
+    stake_pool.last_updated = now;
+
+    if enabled {
+        msg!("EMERGENCY MODE ENABLED - principal-only exits");
+    } else {
+        msg!("Emergency mode cleared");
+    }
+
+    Ok(())
+}
+
+/// Set which top-up lock policies stakers may choose.
+///
+/// # Security
+/// - Only pool.authority can call this
+/// - Unknown bits are rejected
+/// - NewLotOnly cannot be enabled until per-deposit lots are tracked
+///
+/// # Arguments
+/// * `ctx` - AdminControl accounts context
+/// * `mask` - Bitmask of allowed `TopUpLockPolicy` values
+///
+/// # Returns
+/// Result indicating success or error
+pub fn set_allowed_topup_policies_handler(ctx: Context<AdminControl>, mask: u8) -> Result<()> {
+    TopUpLockPolicy::validate_mask(mask)?;
+
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    let old_mask = stake_pool.allowed_topup_policies;
+    stake_pool.allowed_topup_policies = mask;
+    stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+
+    msg!("Allowed top-up policies: {:#05b} -> {:#05b}", old_mask, mask);
+    msg!("Admin: {}", ctx.accounts.authority.key());
+
+    Ok(())
+}
+
+/// Configure the lock anniversary bonus.
+///
+/// # Security
+/// - Only pool.authority can call this
+/// - Bonus capped at MAX_RELOCK_BONUS_BPS of the staked amount per cycle
+/// - Bonuses are credited to pending rewards and bounded by the emission cap
+///
+/// # Arguments
+/// * `ctx` - AdminControl accounts context
+/// * `bonus_bps` - Bonus per completed cycle (basis points, 0 = disabled)
+/// * `grace_secs` - Window after lock end in which a relock still counts
+///
+/// # Returns
+/// Result indicating success or error
+pub fn set_relock_bonus_handler(
+    ctx: Context<AdminControl>,
+    bonus_bps: u16,
+    grace_secs: i64,
+) -> Result<()> {
+    require!(bonus_bps <= MAX_RELOCK_BONUS_BPS, StakingError::InvalidRelockBonus);
+    require!(grace_secs >= 0, StakingError::InvalidRelockBonus);
+
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    stake_pool.relock_bonus_bps = bonus_bps;
+    stake_pool.relock_grace_secs = grace_secs;
+    stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+
+    msg!("Relock bonus: {}bp, grace window: {}s", bonus_bps, grace_secs);
+    msg!("Admin: {}", ctx.accounts.authority.key());
+
+    Ok(())
+}
+
+/// Configure the early-bird bonus for first-time stakers.
+///
+/// # Security
+/// - Only pool.authority can call this
+/// - Slot count cannot drop below slots already assigned
+/// - Bonus capped at MAX_APY
+///
+/// # Arguments
+/// * `ctx` - AdminControl accounts context
+/// * `slots` - Total early-bird slots (including already used)
+/// * `bonus_bps` - Extra APY while a slot is active (basis points)
+/// * `duration_secs` - How long each slot's bonus lasts after the first stake
+///
+/// # Returns
+/// Result indicating success or error
+pub fn set_early_bird_handler(
+    ctx: Context<AdminControl>,
+    slots: u64,
+    bonus_bps: u16,
+    duration_secs: i64,
+) -> Result<()> {
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    require!(bonus_bps <= MAX_APY, StakingError::ApyTooHigh);
+    require!(duration_secs >= 0, StakingError::InvalidEarlyBirdConfig);
+    require!(
+        slots >= stake_pool.early_bird_slots_used,
+        StakingError::InvalidEarlyBirdConfig
+    );
+
+    stake_pool.early_bird_slots = slots;
+    stake_pool.early_bird_bonus_bps = bonus_bps;
+    stake_pool.early_bird_duration_secs = duration_secs;
+    stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+
+    msg!(
+        "Early-bird: {} slots ({} used), +{}bp for {}s",
+        slots,
+        stake_pool.early_bird_slots_used,
+        bonus_bps,
+        duration_secs
+    );
+    msg!("Admin: {}", ctx.accounts.authority.key());
+
+    Ok(())
+}
+
+/// Configure the claim-streak payout bonus.
+///
+/// # Security
+/// - Only pool.authority can call this
+/// - Cap limited to BASIS_POINTS_DENOMINATOR (payout at most doubled)
+///
+/// # Arguments
+/// * `ctx` - AdminControl accounts context
+/// * `step_bps` - Bonus added per consecutive on-time claim (0 = disabled)
+/// * `cap_bps` - Maximum streak bonus (basis points of the claim)
+/// * `window_secs` - Length of each streak window (0 = disabled)
+///
+/// # Returns
+/// Result indicating success or error
+pub fn set_claim_streak_handler(
+    ctx: Context<AdminControl>,
+    step_bps: u16,
+    cap_bps: u16,
+    window_secs: i64,
+) -> Result<()> {
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    require!(
+        cap_bps as u64 <= BASIS_POINTS_DENOMINATOR,
+        StakingError::InvalidStreakConfig
+    );
+    require!(step_bps <= cap_bps, StakingError::InvalidStreakConfig);
+    require!(window_secs >= 0, StakingError::InvalidStreakConfig);
+
+    stake_pool.streak_step_bps = step_bps;
+    stake_pool.streak_cap_bps = cap_bps;
+    stake_pool.streak_window_secs = window_secs;
+    stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+
+    msg!(
+        "Claim streak: +{}bp per claim, cap {}bp, window {}s",
+        step_bps,
+        cap_bps,
+        window_secs
+    );
+    msg!("Admin: {}", ctx.accounts.authority.key());
+
+    Ok(())
+}
+
+/// Set the principal release schedule for a locked tier.
+///
+/// # Security
+/// - Only pool.authority can call this
+/// - Only Core and Prime have a lock to configure
+/// - `cliff_secs + linear_secs` must equal the tier's current lock period
+/// - Applies immediately to existing positions on that lock; positions that
+///   started under another lock keep a hard cliff at their own lock end
+///
+/// # Arguments
+/// * `ctx` - AdminControl accounts context
+/// * `tier` - Locked tier (1=Core, 2=Prime)
+/// * `cliff_secs` - Time after staking before any principal unlocks
+/// * `linear_secs` - Time over which principal then unlocks linearly
+///
+/// # Returns
+/// Result indicating success or error
+pub fn set_release_schedule_handler(
+    ctx: Context<AdminControl>,
+    tier: u8,
+    cliff_secs: i64,
+    linear_secs: i64,
+) -> Result<()> {
+    let tier = Tier::try_from(tier)?;
+    let lock_period = ctx
+        .accounts
+        .stake_pool
+        .lock_period_for_tier(tier)
+        .ok_or(StakingError::InvalidTier)?;
+    require!(
+        cliff_secs >= 0 && linear_secs >= 0,
+        StakingError::InvalidReleaseSchedule
+    );
+    require!(
+        cliff_secs.checked_add(linear_secs) == Some(lock_period),
+        StakingError::InvalidReleaseSchedule
+    );
+
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    if tier == Tier::Core {
+        stake_pool.core_cliff_secs = cliff_secs;
+        stake_pool.core_linear_secs = linear_secs;
+    } else {
+        stake_pool.prime_cliff_secs = cliff_secs;
+        stake_pool.prime_linear_secs = linear_secs;
+    }
+    stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+
+    msg!(
+        "Tier {:?} release: {}s cliff + {}s linear",
+        tier,
+        cliff_secs,
+        linear_secs
+    );
+    msg!("Admin: {}", ctx.accounts.authority.key());
+
+    Ok(())
+}
+
+/// Set the lock each tier gives to new positions.
+///
+/// # Security
+/// - Only pool.authority can call this
+/// - Core and Prime must keep a lock; Flex may have none
+/// - All values are capped at MAX_LOCK_PERIOD
+/// - Only positions whose lock starts afterwards (new stakes, `ExtendAll`
+///   top-ups, tier changes and relocks) get the new values; open locks keep
+///   the length stored on their position
+/// - A changed Core/Prime lock resets that tier's release schedule to a hard
+///   cliff, since a schedule must add up to the lock
+///
+/// # Arguments
+/// * `ctx` - AdminControl accounts context
+/// * `flex_lock_secs` - Flex lock in seconds (0 = none)
+/// * `core_lock_secs` - Core lock in seconds
+/// * `prime_lock_secs` - Prime lock in seconds
+///
+/// # Returns
+/// Result indicating success or error
+pub fn set_lock_periods_handler(
+    ctx: Context<AdminControl>,
+    flex_lock_secs: i64,
+    core_lock_secs: i64,
+    prime_lock_secs: i64,
+) -> Result<()> {
+    require!(
+        (0..=MAX_LOCK_PERIOD).contains(&flex_lock_secs),
+        StakingError::InvalidLockPeriod
+    );
+    require!(
+        core_lock_secs > 0 && core_lock_secs <= MAX_LOCK_PERIOD,
+        StakingError::InvalidLockPeriod
+    );
+    require!(
+        prime_lock_secs > 0 && prime_lock_secs <= MAX_LOCK_PERIOD,
+        StakingError::InvalidLockPeriod
+    );
+
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    if stake_pool.core_lock_seconds != core_lock_secs {
+        stake_pool.core_cliff_secs = core_lock_secs;
+        stake_pool.core_linear_secs = 0;
+    }
+    if stake_pool.prime_lock_seconds != prime_lock_secs {
+        stake_pool.prime_cliff_secs = prime_lock_secs;
+        stake_pool.prime_linear_secs = 0;
+    }
+
+    stake_pool.flex_lock_seconds = flex_lock_secs;
+    stake_pool.core_lock_seconds = core_lock_secs;
+    stake_pool.prime_lock_seconds = prime_lock_secs;
+    stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+
+    msg!(
+        "Lock periods updated: Flex={}s, Core={}s, Prime={}s",
+        flex_lock_secs,
+        core_lock_secs,
+        prime_lock_secs
+    );
+    msg!("Admin: {}", ctx.accounts.authority.key());
+
+    Ok(())
+}
+
+/// Set or clear the scheduled APY decay.
+///
+/// # Security
+/// - Only pool.authority can call this
+/// - Decay counts whole intervals since `created_at`, so a schedule set
+///   mid-campaign starts at the step already reached
+/// - The step must be large enough to reach zero from MAX_APY within
+///   MAX_DECAY_STEPS, which bounds the reward math per claim
+/// - The old schedule is checkpointed, so elapsed time keeps its rate
+/// - While `param_timelock_seconds` is set, the schedule is queued and only
+///   takes effect through `apply_pending_params`
+/// - Multisig pools must go through `propose_admin_action`
+///
+/// # Arguments
+/// * `ctx` - AdminControl accounts context
+/// * `interval_secs` - Length of one decay step (0 = no decay)
+/// * `bps_per_interval` - APY reduction per step (0 = no decay)
+/// * `min_apy_floor` - APY below which decay stops (basis points)
+///
+/// # Returns
+/// Result indicating success or error
+pub fn set_apy_decay_handler(
+    ctx: Context<AdminControl>,
+    interval_secs: i64,
+    bps_per_interval: u16,
+    min_apy_floor: u16,
+) -> Result<()> {
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    require!(!stake_pool.has_admin_multisig(), StakingError::MultisigRequired);
+    stake_pool.next_sequence()?;
+    schedule_apy_decay(
+        stake_pool,
+        interval_secs,
+        bps_per_interval,
+        min_apy_floor,
+        clock.unix_timestamp,
+    )?;
+    msg!("Admin: {}", ctx.accounts.authority.key());
+
+    Ok(())
+}
+
+/// Apply a new decay schedule now, or queue it behind the parameter
+/// timelock; shared with the multisig path.
+pub fn schedule_apy_decay(
+    stake_pool: &mut StakePool,
+    interval_secs: i64,
+    bps_per_interval: u16,
+    min_apy_floor: u16,
+    now: i64,
+) -> Result<()> {
+    if stake_pool.param_timelock_seconds == 0 {
+        return apply_apy_decay(stake_pool, interval_secs, bps_per_interval, min_apy_floor, now);
+    }
+
+    validate_apy_decay(interval_secs, bps_per_interval, min_apy_floor)?;
+
+    stake_pool.pending_decay_interval_seconds = interval_secs;
+    stake_pool.pending_decay_bps_per_interval = bps_per_interval;
+    stake_pool.pending_min_apy_floor = min_apy_floor;
+    stake_pool.pending_decay_effective_at = now
+        .checked_add(stake_pool.param_timelock_seconds)
+        .ok_or(StakingError::MathOverflow)?;
+    stake_pool.last_updated = now;
+
+    msg!(
+        "APY decay change queued: -{}bp every {}s, floor {}bp, effective at {}",
+        bps_per_interval,
+        interval_secs,
+        min_apy_floor,
+        stake_pool.pending_decay_effective_at
+    );
+
+    Ok(())
+}
+
+fn validate_apy_decay(interval_secs: i64, bps_per_interval: u16, min_apy_floor: u16) -> Result<()> {
+    require!(interval_secs >= 0, StakingError::InvalidDecaySchedule);
+    require!(
+        (interval_secs == 0) == (bps_per_interval == 0),
+        StakingError::InvalidDecaySchedule
+    );
+    require!(
+        bps_per_interval == 0
+            || (bps_per_interval >= MAX_APY.div_ceil(MAX_DECAY_STEPS)
+                && bps_per_interval <= MAX_APY),
+        StakingError::InvalidDecaySchedule
+    );
+    require!(min_apy_floor <= MAX_APY, StakingError::ApyTooHigh);
+    Ok(())
+}
+
+/// Validate and store a new decay schedule immediately.
+pub fn apply_apy_decay(
+    stake_pool: &mut StakePool,
+    interval_secs: i64,
+    bps_per_interval: u16,
+    min_apy_floor: u16,
+    now: i64,
+) -> Result<()> {
+    validate_apy_decay(interval_secs, bps_per_interval, min_apy_floor)?;
+
+    let apys = [
+        stake_pool.flex_apy,
+        stake_pool.core_apy,
+        stake_pool.prime_apy,
+        stake_pool.permanent_apy,
+    ];
+    let min_apy_floor = if interval_secs == 0 { 0 } else { min_apy_floor };
+    stake_pool.set_apy_schedule(apys, (interval_secs, bps_per_interval, min_apy_floor), now)?;
+    stake_pool.last_updated = now;
+
+    if stake_pool.is_apy_decaying() {
+        msg!(
+            "APY decay: -{}bp every {}s, floor {}bp",
+            bps_per_interval,
+            interval_secs,
+            stake_pool.min_apy_floor
+        );
+    } else {
+        msg!("APY decay cleared");
+    }
+    msg!(
+        "Effective APY - Flex={}bp, Core={}bp, Prime={}bp",
+        stake_pool.get_apy_for_tier(Tier::Flex, now),
+        stake_pool.get_apy_for_tier(Tier::Core, now),
+        stake_pool.get_apy_for_tier(Tier::Prime, now)
+    );
+
+    Ok(())
+}
+
+/// Set the reward cliff for a tier.
+///
+/// # Security
+/// - Only pool.authority can call this
+/// - Does not delay accrual, only when open positions may claim
+/// - Applies to existing positions immediately
+///
+/// # Arguments
+/// * `ctx` - AdminControl accounts context
+/// * `tier` - Tier to configure (0=Flex, 1=Core, 2=Prime)
+/// * `cliff_secs` - Minimum position age before claiming (0 = disabled)
+///
+/// # Returns
+/// Result indicating success or error
+pub fn set_reward_cliff_handler(
+    ctx: Context<AdminControl>,
+    tier: u8,
+    cliff_secs: i64,
+) -> Result<()> {
+    require!(cliff_secs >= 0, StakingError::InvalidRewardCliff);
+
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    match Tier::try_from(tier)? {
+        Tier::Flex => stake_pool.flex_reward_cliff_secs = cliff_secs,
+        Tier::Core => stake_pool.core_reward_cliff_secs = cliff_secs,
+        Tier::Prime => stake_pool.prime_reward_cliff_secs = cliff_secs,
+        Tier::Permanent => return err!(StakingError::InvalidTier),
+    }
+    stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+
+    msg!("Tier {} reward cliff: {}s", tier, cliff_secs);
+    msg!("Admin: {}", ctx.accounts.authority.key());
+
+    Ok(())
+}
+
+/// Set the APY for the permanent tier.
+///
+/// # Security
+/// - Only pool.authority can call this
+/// - APY capped at MAX_APY
+/// - Zero closes the tier to new stakes; existing positions stay locked
+/// - The old rate is checkpointed, so elapsed time keeps its rate
+/// - While `param_timelock_seconds` is set, the rate is queued and only
+///   takes effect through `apply_pending_params`
+/// - Multisig pools must go through `propose_admin_action`
+///
+/// # Arguments
+/// * `ctx` - AdminControl accounts context
+/// * `permanent_apy` - New permanent tier APY (basis points)
+///
+/// # Returns
+/// Result indicating success or error
+pub fn set_permanent_apy_handler(ctx: Context<AdminControl>, permanent_apy: u16) -> Result<()> {
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    require!(!stake_pool.has_admin_multisig(), StakingError::MultisigRequired);
+    stake_pool.next_sequence()?;
+    schedule_permanent_apy(stake_pool, permanent_apy, clock.unix_timestamp)?;
+    msg!("Admin: {}", ctx.accounts.authority.key());
+
+    Ok(())
+}
+
+/// Apply a new Permanent tier APY now, or queue it behind the parameter
+/// timelock; shared with the multisig path.
+pub fn schedule_permanent_apy(stake_pool: &mut StakePool, permanent_apy: u16, now: i64) -> Result<()> {
+    if stake_pool.param_timelock_seconds == 0 {
+        return apply_permanent_apy(stake_pool, permanent_apy, now);
+    }
+
+    require!(permanent_apy <= MAX_APY, StakingError::ApyTooHigh);
+
+    stake_pool.pending_permanent_apy = permanent_apy;
+    stake_pool.pending_permanent_apy_effective_at = now
+        .checked_add(stake_pool.param_timelock_seconds)
+        .ok_or(StakingError::MathOverflow)?;
+    stake_pool.last_updated = now;
+
+    msg!(
+        "Permanent APY change queued - {}bp, effective at {}",
+        permanent_apy,
+        stake_pool.pending_permanent_apy_effective_at
+    );
+
+    Ok(())
+}
+
+/// Validate and store a new Permanent tier APY immediately.
+pub fn apply_permanent_apy(stake_pool: &mut StakePool, permanent_apy: u16, now: i64) -> Result<()> {
+    require!(permanent_apy <= MAX_APY, StakingError::ApyTooHigh);
+
+    let old_apy = stake_pool.permanent_apy;
+    let apys = [stake_pool.flex_apy, stake_pool.core_apy, stake_pool.prime_apy, permanent_apy];
+    let decay = stake_pool.decay_schedule();
+    stake_pool.set_apy_schedule(apys, decay, now)?;
+    stake_pool.last_updated = now;
+
+    msg!("Permanent APY: {}bp -> {}bp", old_apy, permanent_apy);
+
+    Ok(())
+}
+
+/// Declare the program-wide sunset that releases the permanent tier.
+///
+/// # Security
+/// - Only pool.authority can call this
+/// - Can only be declared once and never rescinded
+/// - Must give at least MIN_SUNSET_NOTICE
+///
+/// # Arguments
+/// * `ctx` - AdminControl accounts context
+/// * `sunset_at` - Unix timestamp from which permanent positions may exit
+///
+/// # Returns
+/// Result indicating success or error
+pub fn declare_sunset_handler(ctx: Context<AdminControl>, sunset_at: i64) -> Result<()> {
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    require!(stake_pool.sunset_at == 0, StakingError::SunsetAlreadyDeclared);
+    require!(
+        sunset_at >= clock.unix_timestamp.saturating_add(MIN_SUNSET_NOTICE),
+        StakingError::SunsetTooSoon
+    );
+
+    stake_pool.sunset_at = sunset_at;
+    stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+
+    emit!(SunsetDeclared {
+        stake_pool: stake_pool.key(),
+        authority: ctx.accounts.authority.key(),
+        sunset_at,
+        sequence: stake_pool.sequence,
+        declared_at: clock.unix_timestamp,
+    });
+
+    msg!("SUNSET DECLARED: permanent tier unlocks at {}", sunset_at);
+    msg!("Admin: {}", ctx.accounts.authority.key());
+
+    Ok(())
+}
+
+/// Schedule when the pool stops accruing rewards and accepting stakes.
+///
+/// # Security
+/// - Only pool.authority can call this
+/// - Must be in the future; can be moved while the pool is running but not
+///   once it has ended, so time that stopped accruing never accrues again
+/// - Unstaking and claiming stay available after the end
+/// - While `param_timelock_seconds` is set, the end time is queued and only
+///   takes effect through `apply_pending_params`; it must fall after the
+///   timelock
+/// - Multisig pools must go through `propose_admin_action`
+///
+/// # Arguments
+/// * `ctx` - AdminControl accounts context
+/// * `end_time` - Unix timestamp at which accrual stops
+///
+/// # Returns
+/// Result indicating success or error
+pub fn set_rewards_end_time_handler(ctx: Context<AdminControl>, end_time: i64) -> Result<()> {
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    require!(!stake_pool.has_admin_multisig(), StakingError::MultisigRequired);
+    stake_pool.next_sequence()?;
+    schedule_rewards_end_time(stake_pool, end_time, clock.unix_timestamp)?;
+    msg!("Admin: {}", ctx.accounts.authority.key());
+
+    Ok(())
+}
+
+/// Apply a new rewards end time now, or queue it behind the parameter
+/// timelock; shared with the multisig path.
+pub fn schedule_rewards_end_time(stake_pool: &mut StakePool, end_time: i64, now: i64) -> Result<()> {
+    if stake_pool.param_timelock_seconds == 0 {
+        return apply_rewards_end_time(stake_pool, end_time, now);
+    }
+
+    let effective_at = now
+        .checked_add(stake_pool.param_timelock_seconds)
+        .ok_or(StakingError::MathOverflow)?;
+    require!(!stake_pool.has_ended(now), StakingError::PoolEnded);
+    require!(end_time > effective_at, StakingError::InvalidRewardsEndTime);
+
+    stake_pool.pending_rewards_end_time = end_time;
+    stake_pool.pending_rewards_end_time_effective_at = effective_at;
+    stake_pool.last_updated = now;
+
+    msg!(
+        "Rewards end time change queued - {}, effective at {}",
+        end_time,
+        effective_at
+    );
+
+    Ok(())
+}
+
+/// Validate and store a new rewards end time immediately.
+pub fn apply_rewards_end_time(stake_pool: &mut StakePool, end_time: i64, now: i64) -> Result<()> {
+    require!(!stake_pool.has_ended(now), StakingError::PoolEnded);
+    require!(end_time > now, StakingError::InvalidRewardsEndTime);
+
+    let old_end = stake_pool.rewards_end_time;
+    stake_pool.rewards_end_time = end_time;
+    stake_pool.last_updated = now;
+
+    msg!("Rewards end time: {} -> {}", old_end, end_time);
+
+    Ok(())
+}
+
+/// Configure boost-locking of pending rewards.
+///
+/// # Security
+/// - Only pool.authority can call this
+/// - Boost capped at MAX_APY
+/// - Applies to running boosts immediately
+///
+/// # Arguments
+/// * `ctx` - AdminControl accounts context
+/// * `boost_bps` - Extra APY on boost-locked rewards (0 = disabled)
+/// * `max_duration_secs` - Longest allowed boost lock
+///
+/// # Returns
+/// Result indicating success or error
+pub fn set_boost_handler(
+    ctx: Context<AdminControl>,
+    boost_bps: u16,
+    max_duration_secs: i64,
+) -> Result<()> {
+    require!(boost_bps <= MAX_APY, StakingError::ApyTooHigh);
+    require!(max_duration_secs >= 0, StakingError::InvalidBoostDuration);
+
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    stake_pool.boost_bps = boost_bps;
+    stake_pool.max_boost_duration_secs = max_duration_secs;
+    stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+
+    msg!("Boost: +{}bp, max {}s", boost_bps, max_duration_secs);
+    msg!("Admin: {}", ctx.accounts.authority.key());
+
+    Ok(())
+}
+
+/// Set the ongoing referral share paid on referee claims.
+///
+/// # Security
+/// - Only pool.authority can call this
+/// - Share capped at MAX_REFERRAL_SHARE_BPS
+/// - Paid from the treasury on top of the claimer's payout, within the emission cap
+///
+/// # Arguments
+/// * `ctx` - AdminControl accounts context
+/// * `share_bps` - Referrer share of each referee claim (0 = disabled)
+///
+/// # Returns
+/// Result indicating success or error
+pub fn set_referral_share_handler(ctx: Context<AdminControl>, share_bps: u16) -> Result<()> {
+    require!(
+        share_bps <= MAX_REFERRAL_SHARE_BPS,
+        StakingError::InvalidReferralShare
+    );
+
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    let old_share = stake_pool.referral_share_bps;
+    stake_pool.referral_share_bps = share_bps;
+    stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+
+    msg!("Referral share: {}bp -> {}bp", old_share, share_bps);
+    msg!("Admin: {}", ctx.accounts.authority.key());
+
+    Ok(())
+}
+
+/// Set the delay applied to timelocked parameter changes.
+///
+/// # Security
+/// - Only pool.authority can call this
+/// - Can only be lengthened, so it cannot be used to skip a pending delay
+/// - Capped at MAX_PARAM_TIMELOCK
+///
+/// # Arguments
+/// * `ctx` - AdminControl accounts context
+/// * `timelock_seconds` - New delay between proposal and application
+///
+/// # Returns
+/// Result indicating success or error
+pub fn set_param_timelock_handler(ctx: Context<AdminControl>, timelock_seconds: i64) -> Result<()> {
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    require!(
+        timelock_seconds >= stake_pool.param_timelock_seconds
+            && timelock_seconds <= MAX_PARAM_TIMELOCK,
+        StakingError::InvalidParamTimelock
+    );
+
+    let old_timelock = stake_pool.param_timelock_seconds;
+    stake_pool.param_timelock_seconds = timelock_seconds;
+    stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+
+    msg!("Parameter timelock: {}s -> {}s", old_timelock, timelock_seconds);
+    msg!("Admin: {}", ctx.accounts.authority.key());
+
+    Ok(())
+}
+
+/// Set the pool-wide limit on claim transactions per epoch.
+///
+/// # Security
+/// - Only pool.authority can call this
+/// - Only `claim_rewards` is limited; unstake stays available so no position
+///   can be trapped by the limit
+/// - Zero `max_claims` disables the limit
+///
+/// # Arguments
+/// * `ctx` - AdminControl accounts context
+/// * `max_claims` - Claims allowed per epoch (0 = unlimited)
+/// * `epoch_secs` - Epoch length in seconds (1..=MAX_CLAIM_EPOCH when enabled)
+///
+/// # Returns
+/// Result indicating success or error
+pub fn set_claim_rate_limit_handler(
+    ctx: Context<AdminControl>,
+    max_claims: u32,
+    epoch_secs: i64,
+) -> Result<()> {
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    if max_claims > 0 {
+        require!(
+            epoch_secs > 0 && epoch_secs <= MAX_CLAIM_EPOCH,
+            StakingError::InvalidClaimRateLimit
+        );
+    }
+
+    stake_pool.max_claims_per_epoch = max_claims;
+    stake_pool.claim_epoch_secs = if max_claims > 0 { epoch_secs } else { 0 };
+    stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+
+    msg!(
+        "Claim rate limit updated: {} claims per {}s",
+        stake_pool.max_claims_per_epoch,
+        stake_pool.claim_epoch_secs
+    );
+    msg!("Admin: {}", ctx.accounts.authority.key());
+
+    Ok(())
+}
+
+/// Set the pool-wide limit on rewards paid out per epoch.
+///
+/// # Security
+/// - Only pool.authority can call this
+/// - Claims are filled up to the remaining budget, the rest stays pending;
+///   compounding needs the whole amount to fit
+/// - Unstake is never limited
+/// - Zero `max_emission` disables the limit
+///
+/// # Arguments
+/// * `ctx` - AdminControl accounts context
+/// * `max_emission` - Reward tokens payable per epoch (0 = unlimited)
+/// * `epoch_secs` - Epoch length in seconds (1..=MAX_EMISSION_EPOCH when enabled)
+///
+/// # Returns
+/// Result indicating success or error
+pub fn set_epoch_emission_limit_handler(
+    ctx: Context<AdminControl>,
+    max_emission: u64,
+    epoch_secs: i64,
+) -> Result<()> {
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    if max_emission > 0 {
+        require!(
+            epoch_secs > 0 && epoch_secs <= MAX_EMISSION_EPOCH,
+            StakingError::InvalidEpochEmissionLimit
+        );
+    }
+
+    stake_pool.max_emission_per_epoch = max_emission;
+    stake_pool.epoch_length_seconds = if max_emission > 0 { epoch_secs } else { 0 };
+    stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+
+    msg!(
+        "Epoch emission limit updated: {} per {}s",
+        stake_pool.max_emission_per_epoch,
+        stake_pool.epoch_length_seconds
+    );
+    msg!("Admin: {}", ctx.accounts.authority.key());
+
+    Ok(())
+}
+
+/// Set the unstake fee charged on principal withdrawn from a tier.
+///
+/// # Security
+/// - Only pool.authority can call this
+/// - Fee capped at MAX_UNSTAKE_FEE_BPS and routed by `penalty_destination`
+/// - Permanent tier has no unstake fee
+/// - A pool with no penalty destination can only set 0
+///
+/// # Arguments
+/// * `ctx` - AdminControl accounts context
+/// * `tier` - Tier to configure (0=Flex, 1=Core, 2=Prime)
+/// * `fee_bps` - Fee in basis points of the withdrawn principal
+///
+/// # Returns
+/// Result indicating success or error
+pub fn set_unstake_fee_handler(ctx: Context<AdminControl>, tier: u8, fee_bps: u16) -> Result<()> {
+    require!(Tier::try_from(tier)? != Tier::Permanent, StakingError::InvalidTier);
+    require!(fee_bps <= MAX_UNSTAKE_FEE_BPS, StakingError::InvalidUnstakeFee);
+
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+    require!(
+        fee_bps == 0 || stake_pool.penalty_destination != PenaltyDestination::Unset,
+        StakingError::NoPenaltyDestination
+    );
+
+    let old_fee = stake_pool.unstake_fee_bps[tier as usize];
+    stake_pool.unstake_fee_bps[tier as usize] = fee_bps;
+    stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+
+    msg!("Unstake fee for tier {} updated: {}bp -> {}bp", tier, old_fee, fee_bps);
+    msg!("Admin: {}", ctx.accounts.authority.key());
+
+    Ok(())
+}
+
+/// Accounts required for setting the penalty destination.
+///
+/// ## Security Notes
+/// - Same authority checks as AdminControl
+/// - Staking mint is inspected to decide whether burning is safe
+#[derive(Accounts)]
+pub struct SetPenaltyDestination<'info> {
+    /// The admin authority.
+    /// SECURITY: Must be signer AND match pool.authority.
+    #[account(
+        mut,
+        constraint = authority.key() == stake_pool.authority @ StakingError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    /// The stake pool to modify.
+    /// SECURITY: PDA validation + has_one authority and mint.
+    #[account(
+        mut,
+        seeds = [STAKE_POOL_SEED, stake_pool.staking_mint.as_ref()],
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ StakingError::AccountVersionMismatch,
+        bump = stake_pool.bump,
+        has_one = authority @ StakingError::Unauthorized,
+        has_one = staking_mint @ StakingError::MintMismatch
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+
+    /// The staking token mint.
+    pub staking_mint: Account<'info, Mint>,
+}
+
+/// Choose whether unstake fees go to the treasury or are burned.
+///
+/// # Security
+/// - Only pool.authority can call this
+/// - Burning is refused while the mint has a freeze authority: a frozen
+///   staking vault could not burn, which would block every fee-paying unstake
+/// - Dual-token pools can only burn; their treasury holds the reward mint
+/// - Unset is only accepted while no tier charges a fee or penalty
+///
+/// # Arguments
+/// * `ctx` - SetPenaltyDestination accounts context
+/// * `destination` - Treasury, Burn or Unset
+///
+/// # Returns
+/// Result indicating success or error
+pub fn set_penalty_destination_handler(
+    ctx: Context<SetPenaltyDestination>,
+    destination: PenaltyDestination,
+) -> Result<()> {
+    match destination {
+        PenaltyDestination::Burn => require!(
+            ctx.accounts.staking_mint.freeze_authority.is_none(),
+            StakingError::BurnNotSupported
+        ),
+        // Fees are staking tokens; a dual-token treasury holds the reward mint
+        PenaltyDestination::Treasury => require!(
+            !ctx.accounts.stake_pool.is_dual_token(),
+            StakingError::SingleTokenPoolOnly
+        ),
+        PenaltyDestination::Unset => require!(
+            !ctx.accounts.stake_pool.charges_principal_fees(),
+            StakingError::NoPenaltyDestination
+        ),
+    }
+
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    let old_destination = stake_pool.penalty_destination;
+    stake_pool.penalty_destination = destination;
+    stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+
+    msg!("Penalty destination updated: {:?} -> {:?}", old_destination, destination);
+    msg!("Admin: {}", ctx.accounts.authority.key());
+
+    Ok(())
+}
+
+/// Set the minimum time a Flex position must stay staked before unstaking.
+///
+/// # Security
+/// - Only pool.authority can call this
+/// - Applies to Flex only; Core/Prime locks already dominate
+/// - Measured from each position's stake_start_time, so raising it never
+///   holds an existing staker longer than the new value from their stake
+///
+/// # Arguments
+/// * `ctx` - AdminControl accounts context
+/// * `min_stake_secs` - Minimum Flex stake duration (0 = disabled, max 7 days)
+///
+/// # Returns
+/// Result indicating success or error
+pub fn set_flex_min_stake_handler(ctx: Context<AdminControl>, min_stake_secs: i64) -> Result<()> {
+    require!(
+        (0..=MAX_FLEX_MIN_STAKE).contains(&min_stake_secs),
+        StakingError::InvalidFlexMinStake
+    );
+
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    let old_min = stake_pool.flex_min_stake_secs;
+    stake_pool.flex_min_stake_secs = min_stake_secs;
+    stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+
+    msg!("Flex minimum stake updated: {}s -> {}s", old_min, min_stake_secs);
+    msg!("Admin: {}", ctx.accounts.authority.key());
+
+    Ok(())
+}
+
+/// Set the flash-staking guards.
+///
+/// # Security
+/// - Only pool.authority can call this
+/// - `min_accrual_seconds` (max MAX_MIN_ACCRUAL) applies to APY accrual:
+///   any settlement interval shorter than it earns nothing
+/// - `min_stake_duration_seconds` (max MAX_MIN_STAKE_DURATION) blocks every
+///   unstake path except emergency exits until that long after the
+///   position's latest deposit
+///
+/// # Arguments
+/// * `ctx` - AdminControl accounts context
+/// * `min_accrual_seconds` - Shortest interval that accrues (0 = disabled)
+/// * `min_stake_duration_seconds` - Hold time after a deposit (0 = disabled)
+///
+/// # Returns
+/// Result indicating success or error
+pub fn set_min_durations_handler(
+    ctx: Context<AdminControl>,
+    min_accrual_seconds: i64,
+    min_stake_duration_seconds: i64,
+) -> Result<()> {
+    require!(
+        (0..=MAX_MIN_ACCRUAL).contains(&min_accrual_seconds)
+            && (0..=MAX_MIN_STAKE_DURATION).contains(&min_stake_duration_seconds),
+        StakingError::InvalidMinDuration
+    );
+
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    stake_pool.min_accrual_seconds = min_accrual_seconds;
+    stake_pool.min_stake_duration_seconds = min_stake_duration_seconds;
+    stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+
+    msg!(
+        "Minimum accrual {}s, minimum stake duration {}s",
+        min_accrual_seconds,
+        min_stake_duration_seconds
+    );
+    msg!("Admin: {}", ctx.accounts.authority.key());
+
+    Ok(())
+}
+
+/// Replace the loyalty schedule.
+///
+/// # Security
+/// - Only pool.authority can call this
+/// - At most MAX_LOYALTY_LEVELS levels; thresholds positive and bonuses
+///   non-zero, both strictly increasing, bonuses capped at MAX_APY
+/// - Applies to elapsed time on the next settlement of each position, like
+///   any other rate change
+///
+/// # Arguments
+/// * `ctx` - AdminControl accounts context
+/// * `threshold_secs` - Continuous stake needed for each level
+/// * `bonus_bps` - Extra APY once each level is reached (empty = disabled)
+///
+/// # Returns
+/// Result indicating success or error
+pub fn set_loyalty_schedule_handler(
+    ctx: Context<AdminControl>,
+    threshold_secs: Vec<i64>,
+    bonus_bps: Vec<u16>,
+) -> Result<()> {
+    require!(
+        threshold_secs.len() == bonus_bps.len() && bonus_bps.len() <= MAX_LOYALTY_LEVELS,
+        StakingError::InvalidLoyaltySchedule
+    );
+    let mut previous = (0i64, 0u16);
+    for (&threshold, &bonus) in threshold_secs.iter().zip(bonus_bps.iter()) {
+        require!(
+            threshold > previous.0 && bonus > previous.1,
+            StakingError::InvalidLoyaltySchedule
+        );
+        require!(bonus <= MAX_APY, StakingError::ApyTooHigh);
+        previous = (threshold, bonus);
+    }
+
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    stake_pool.loyalty_threshold_secs = [0; MAX_LOYALTY_LEVELS];
+    stake_pool.loyalty_bonus_bps = [0; MAX_LOYALTY_LEVELS];
+    stake_pool.loyalty_threshold_secs[..threshold_secs.len()].copy_from_slice(&threshold_secs);
+    stake_pool.loyalty_bonus_bps[..bonus_bps.len()].copy_from_slice(&bonus_bps);
+    stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+
+    msg!(
+        "Loyalty schedule: thresholds {:?}s, bonuses {:?}bp",
+        threshold_secs,
+        bonus_bps
+    );
+    msg!("Admin: {}", ctx.accounts.authority.key());
+
+    Ok(())
+}
+
+/// Set the Flex withdrawal cooldown.
+///
+/// # Security
+/// - Only pool.authority can call this
+/// - While non-zero, Flex principal leaves only through `request_unstake`
+///   followed by `withdraw`; zero restores the direct `unstake`
+/// - Queued requests are measured against the current value, so changes
+///   also apply to them
+///
+/// # Arguments
+/// * `ctx` - AdminControl accounts context
+/// * `cooldown_secs` - Delay between request and withdrawal (0 = disabled, max 7 days)
+///
+/// # Returns
+/// Result indicating success or error
+pub fn set_flex_cooldown_handler(ctx: Context<AdminControl>, cooldown_secs: i64) -> Result<()> {
+    require!(
+        (0..=MAX_FLEX_COOLDOWN).contains(&cooldown_secs),
+        StakingError::InvalidFlexCooldown
+    );
+
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    let old_cooldown = stake_pool.flex_cooldown_seconds;
+    stake_pool.flex_cooldown_seconds = cooldown_secs;
+    stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+
+    msg!("Flex withdrawal cooldown updated: {}s -> {}s", old_cooldown, cooldown_secs);
+    msg!("Admin: {}", ctx.accounts.authority.key());
+
+    Ok(())
+}
+
+/// Turn oracle-driven APY on, reconfigure it, or turn it off.
+///
+/// # Security
+/// - Only pool.authority can call this
+/// - The stored `price_account` is the only account `update_apy_from_oracle`
+///   will read, so it must be the staking token's Pyth price feed
+/// - Base APYs and the floor are capped at MAX_APY
+/// - Updates are at least MIN_ORACLE_UPDATE_INTERVAL apart, so the APY
+///   checkpoints cannot be churned by a permissionless caller
+/// - Turning it off leaves the current APYs in place
+/// - Multisig pools must go through `propose_admin_action`, which has no
+///   oracle action yet
+///
+/// # Arguments
+/// * `ctx` - AdminControl accounts context
+/// * `config` - New configuration; a default `price_account` turns oracle mode off
+///
+/// # Returns
+/// Result indicating success or error
+pub fn set_oracle_config_handler(ctx: Context<AdminControl>, config: OracleApyConfig) -> Result<()> {
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    require!(!stake_pool.has_admin_multisig(), StakingError::MultisigRequired);
+
+    let config = if config.is_enabled() {
+        require!(
+            config.reference_price > 0
+                && config.base_apys.iter().all(|&apy| apy <= MAX_APY)
+                && config.min_apy <= MAX_APY
+                && config.max_staleness_secs > 0
+                && config.max_confidence_bps > 0
+                && u64::from(config.max_confidence_bps) <= BASIS_POINTS_DENOMINATOR
+                && config.update_interval_secs >= MIN_ORACLE_UPDATE_INTERVAL,
+            StakingError::InvalidOracleConfig
+        );
+        config
+    } else {
+        OracleApyConfig::default()
+    };
+
+    stake_pool.oracle = config;
+    stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+
+    if config.is_enabled() {
+        msg!(
+            "Oracle APY: feed {}, reference price {}, base {:?}bp, floor {}bp",
+            config.price_account,
+            config.reference_price,
+            config.base_apys,
+            config.min_apy
+        );
+    } else {
+        msg!("Oracle APY disabled");
+    }
+    msg!("Admin: {}", ctx.accounts.authority.key());
+
+    Ok(())
+}
+
+/// Set how often `take_snapshot` may run.
+///
+/// # Security
+/// - Only pool.authority can call this
+/// - Zero disables new snapshots; existing ones and the epoch count are kept
+/// - Measured from the last snapshot, so a change applies to the next one
+///
+/// # Arguments
+/// * `ctx` - AdminControl accounts context
+/// * `interval_secs` - Minimum seconds between snapshots (0 = disabled)
+///
+/// # Returns
+/// Result indicating success or error
+pub fn set_snapshot_interval_handler(ctx: Context<AdminControl>, interval_secs: i64) -> Result<()> {
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    require!(interval_secs >= 0, StakingError::InvalidSnapshotInterval);
+
+    let old_interval = stake_pool.snapshot_interval_secs;
+    stake_pool.snapshot_interval_secs = interval_secs;
+    stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+
+    msg!("Snapshot interval updated: {}s -> {}s", old_interval, interval_secs);
+    msg!("Admin: {}", ctx.accounts.authority.key());
+
+    Ok(())
+}
+
+/// Set the smallest amount that may open a new position.
+///
+/// # Security
+/// - Only pool.authority can call this
+/// - Only checked when a position is opened; top-ups and existing
+///   positions are unaffected
+///
+/// # Arguments
+/// * `ctx` - AdminControl accounts context
+/// * `min_stake_amount` - Minimum opening stake (0 = no minimum)
+///
+/// # Returns
+/// Result indicating success or error
+pub fn set_min_stake_amount_handler(ctx: Context<AdminControl>, min_stake_amount: u64) -> Result<()> {
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    let old_min = stake_pool.min_stake_amount;
+    stake_pool.min_stake_amount = min_stake_amount;
+    stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+
+    msg!("Minimum stake amount updated: {} -> {}", old_min, min_stake_amount);
+    msg!("Admin: {}", ctx.accounts.authority.key());
+
+    Ok(())
+}
+
+/// Set the pool-wide cap on total staked tokens.
+///
+/// # Security
+/// - Only pool.authority can call this
+/// - Only new stakes are checked; a cap below the current total blocks
+///   deposits but never touches existing positions or unstakes
+///
+/// # Arguments
+/// * `ctx` - AdminControl accounts context
+/// * `max_total_staked` - Cap on total_staked (0 = unlimited)
+///
+/// # Returns
+/// Result indicating success or error
+pub fn set_max_total_staked_handler(ctx: Context<AdminControl>, max_total_staked: u64) -> Result<()> {
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    let old_cap = stake_pool.max_total_staked;
+    stake_pool.max_total_staked = max_total_staked;
+    stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+
+    msg!("Pool staking cap updated: {} -> {}", old_cap, max_total_staked);
+    if max_total_staked > 0 && max_total_staked < stake_pool.total_staked {
+        msg!("Cap is below current total staked ({}); new stakes blocked", stake_pool.total_staked);
+    }
+    msg!("Admin: {}", ctx.accounts.authority.key());
+
+    Ok(())
+}
+
+/// Set the emission rate shared by a reward-per-token pool.
+///
+/// # Security
+/// - Only pool.authority can call this
+/// - Only for `RewardPerToken` pools
+/// - The accumulator is settled at the old rate first, so the change is
+///   never retroactive
+/// - Payouts stay bounded by the emission cap and the treasury balance
+/// - Multisig pools must go through `propose_admin_action`
+///
+/// # Arguments
+/// * `ctx` - AdminControl accounts context
+/// * `reward_rate_per_second` - Tokens emitted per second across all stakers
+///
+/// # Returns
+/// Result indicating success or error
+pub fn set_reward_rate_handler(ctx: Context<AdminControl>, reward_rate_per_second: u64) -> Result<()> {
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    require!(!stake_pool.has_admin_multisig(), StakingError::MultisigRequired);
+    stake_pool.next_sequence()?;
+    apply_reward_rate(stake_pool, reward_rate_per_second, clock.unix_timestamp)?;
+    msg!("Admin: {}", ctx.accounts.authority.key());
+
+    Ok(())
+}
+
+/// Settle the accumulator and store a new reward rate; shared with the
+/// multisig path.
+pub fn apply_reward_rate(stake_pool: &mut StakePool, reward_rate_per_second: u64, now: i64) -> Result<()> {
+    require!(
+        stake_pool.reward_mode == RewardMode::RewardPerToken,
+        StakingError::RewardModeMismatch
+    );
+
+    stake_pool.update_reward_per_token(now)?;
+    stake_pool.accrue_liability(now);
+    let old_rate = stake_pool.reward_rate_per_second;
+    stake_pool.reward_rate_per_second = reward_rate_per_second;
+    stake_pool.last_updated = now;
+
+    msg!("Reward rate updated: {} -> {} per second", old_rate, reward_rate_per_second);
+
+    Ok(())
+}
+
+/// Enable or disable the treasury solvency check at stake time.
+///
+/// # Security
+/// - Only pool.authority can call this
+/// - Only new stakes are checked; existing positions keep earning even if
+///   the pool is already over the limit
+/// - Stakes must pass the treasury account while the check is enabled
+///
+/// # Arguments
+/// * `ctx` - AdminControl accounts context
+/// * `require_solvency` - Whether `stake` runs the check
+/// * `treasury_multiple_bps` - Projected yearly rewards allowed per treasury
+///   token, in basis points (10000 = 1x); ignored when disabled
+///
+/// # Returns
+/// Result indicating success or error
+pub fn set_solvency_check_handler(
+    ctx: Context<AdminControl>,
+    require_solvency: bool,
+    treasury_multiple_bps: u32,
+) -> Result<()> {
+    require!(
+        !require_solvency || treasury_multiple_bps > 0,
+        StakingError::InvalidSolvencyMultiple
+    );
+
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    stake_pool.require_solvency = require_solvency;
+    stake_pool.solvency_treasury_multiple_bps = treasury_multiple_bps;
+    stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+
+    if require_solvency {
+        msg!(
+            "Solvency check enabled, treasury multiple: {} bps",
+            treasury_multiple_bps
+        );
+    } else {
+        msg!("Solvency check disabled");
+    }
+    msg!("Admin: {}", ctx.accounts.authority.key());
+
+    Ok(())
+}
+
+/// Set the early-exit penalty for a locked tier.
+///
+/// # Security
+/// - Only pool.authority can call this
+/// - Capped at 30% of the early-exited principal
+/// - 0 disables `early_unstake` for the tier
+/// - A pool with no penalty destination can only set 0
+///
+/// # Arguments
+/// * `ctx` - AdminControl accounts context
+/// * `tier` - Locked tier (1=Core, 2=Prime)
+/// * `penalty_bps` - Penalty in basis points of the early-exited principal
+///
+/// # Returns
+/// Result indicating success or error
+pub fn set_early_unstake_penalty_handler(
+    ctx: Context<AdminControl>,
+    tier: u8,
+    penalty_bps: u16,
+) -> Result<()> {
+    let slot = match Tier::try_from(tier)? {
+        Tier::Core => 0,
+        Tier::Prime => 1,
+        Tier::Flex | Tier::Permanent => return err!(StakingError::InvalidTier),
+    };
+    require!(
+        penalty_bps <= MAX_EARLY_UNSTAKE_PENALTY_BPS,
+        StakingError::InvalidEarlyUnstakePenalty
+    );
+
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+    require!(
+        penalty_bps == 0 || stake_pool.penalty_destination != PenaltyDestination::Unset,
+        StakingError::NoPenaltyDestination
+    );
+
+    let old_penalty = stake_pool.early_unstake_penalty_bps[slot];
+    stake_pool.early_unstake_penalty_bps[slot] = penalty_bps;
+    stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+
+    msg!(
+        "Early unstake penalty for tier {} updated: {}bp -> {}bp",
+        tier,
+        old_penalty,
+        penalty_bps
+    );
+    msg!("Admin: {}", ctx.accounts.authority.key());
+
+    Ok(())
+}
+
+/// Set the grace window before a lock's exact end during which it counts as ended.
+///
+/// # Security
+/// - Only pool.authority can call this
+/// - Capped at one day so it can only absorb clock drift, not shorten locks
+/// - Applies to unstake and change_tier for Core and Prime
+///
+/// # Arguments
+/// * `ctx` - AdminControl accounts context
+/// * `grace_secs` - Grace window in seconds (0 = exact lock end, max 1 day)
+///
+/// # Returns
+/// Result indicating success or error
+pub fn set_unlock_grace_handler(ctx: Context<AdminControl>, grace_secs: i64) -> Result<()> {
+    require!(
+        (0..=MAX_UNLOCK_GRACE).contains(&grace_secs),
+        StakingError::InvalidUnlockGrace
+    );
+
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    let old_grace = stake_pool.unlock_grace_secs;
+    stake_pool.unlock_grace_secs = grace_secs;
+    stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+
+    msg!("Unlock grace updated: {}s -> {}s", old_grace, grace_secs);
+    msg!("Admin: {}", ctx.accounts.authority.key());
+
+    Ok(())
+}
+
+/// Set how long an empty position must sit idle before anyone may close it.
+///
+/// # Security
+/// - Only pool.authority can call this
+/// - At least MIN_GC_INACTIVITY, so an owner always has time to come back
+///   before a third party closes their account
+/// - Only ever closes positions that hold nothing; rent goes to the owner
+///
+/// # Arguments
+/// * `ctx` - AdminControl accounts context
+/// * `inactivity_secs` - Idle period before `gc_user_stake` (0 = disabled)
+///
+/// # Returns
+/// Result indicating success or error
+pub fn set_gc_inactivity_handler(ctx: Context<AdminControl>, inactivity_secs: i64) -> Result<()> {
+    require!(
+        inactivity_secs == 0 || inactivity_secs >= MIN_GC_INACTIVITY,
+        StakingError::InvalidGcInactivity
+    );
+
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    let old_inactivity = stake_pool.gc_inactivity_secs;
+    stake_pool.gc_inactivity_secs = inactivity_secs;
+    stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+
+    msg!("GC inactivity updated: {}s -> {}s", old_inactivity, inactivity_secs);
+    msg!("Admin: {}", ctx.accounts.authority.key());
+
+    Ok(())
+}
+
+/// Set the minimum time between a position's settlement and its next claim.
+///
+/// # Security
+/// - Only pool.authority can call this
+/// - Capped at MAX_MIN_CLAIM_INTERVAL so rewards are never held back for long
+/// - Only `claim_rewards` checks it; settlement inside stake and unstake is
+///   never blocked
+///
+/// # Arguments
+/// * `ctx` - AdminControl accounts context
+/// * `interval_secs` - Minimum seconds between claims (0 = no limit)
+///
+/// # Returns
+/// Result indicating success or error
+pub fn set_min_claim_interval_handler(ctx: Context<AdminControl>, interval_secs: i64) -> Result<()> {
+    require!(
+        (0..=MAX_MIN_CLAIM_INTERVAL).contains(&interval_secs),
+        StakingError::InvalidClaimInterval
+    );
+
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    let old_interval = stake_pool.min_claim_interval_seconds;
+    stake_pool.min_claim_interval_seconds = interval_secs;
+    stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+
+    msg!("Min claim interval updated: {}s -> {}s", old_interval, interval_secs);
+    msg!("Admin: {}", ctx.accounts.authority.key());
+
+    Ok(())
+}
+
+/// Set or clear one slot of the yield adapter whitelist.
+///
+/// # Security
+/// - Only pool.authority can call this
+/// - The adapter currently holding deployed funds cannot be removed
+///
+/// # Arguments
+/// * `ctx` - AdminControl accounts context
+/// * `slot` - Whitelist slot (0..MAX_YIELD_ADAPTERS)
+/// * `adapter` - Adapter program id, or the default key to clear the slot
+///
+/// # Returns
+/// Result indicating success or error
+pub fn set_yield_adapter_handler(
+    ctx: Context<AdminControl>,
+    slot: u8,
+    adapter: Pubkey,
+) -> Result<()> {
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    require!((slot as usize) < MAX_YIELD_ADAPTERS, StakingError::InvalidYieldAdapter);
+    let old_adapter = stake_pool.yield_adapters[slot as usize];
+    require!(
+        stake_pool.treasury_deployed == 0 || old_adapter != stake_pool.deployed_adapter,
+        StakingError::TreasuryAlreadyDeployed
+    );
+
+    stake_pool.yield_adapters[slot as usize] = adapter;
+    stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+
+    msg!("Yield adapter slot {} updated: {} -> {}", slot, old_adapter, adapter);
+    msg!("Admin: {}", ctx.accounts.authority.key());
+
+    Ok(())
+}
+
+/// Set the share of the treasury that may be deployed to yield adapters.
+///
+/// # Security
+/// - Only pool.authority can call this
+/// - Capped at MAX_TREASURY_DEPLOY_BPS; lowering it does not force a recall
+///
+/// # Arguments
+/// * `ctx` - AdminControl accounts context
+/// * `max_deploy_bps` - Deployable share of liquid + deployed treasury
+///
+/// # Returns
+/// Result indicating success or error
+pub fn set_max_treasury_deploy_handler(
+    ctx: Context<AdminControl>,
+    max_deploy_bps: u16,
+) -> Result<()> {
+    require!(
+        max_deploy_bps <= MAX_TREASURY_DEPLOY_BPS,
+        StakingError::TreasuryDeployCapExceeded
+    );
+
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    let old_bps = stake_pool.max_treasury_deploy_bps;
+    stake_pool.max_treasury_deploy_bps = max_deploy_bps;
+    stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+
+    msg!("Treasury deploy cap updated: {}bp -> {}bp", old_bps, max_deploy_bps);
+    msg!("Admin: {}", ctx.accounts.authority.key());
+
+    Ok(())
+}
+
+/// Hand an admin role to another key.
+///
+/// # Security
+/// - Only pool.authority can call this
+/// - The authority keeps every role; setting a role back to the authority
+///   revokes it from the previous holder
+/// - Roles still held by the authority move with it in `accept_authority`
+///
+/// # Arguments
+/// * `ctx` - AdminControl accounts context
+/// * `role` - Role to reassign
+/// * `holder` - New holder (non-zero)
+///
+/// # Returns
+/// Result indicating success or error
+pub fn set_role_handler(ctx: Context<AdminControl>, role: AdminRole, holder: Pubkey) -> Result<()> {
+    require!(holder != Pubkey::default(), StakingError::InvalidRoleHolder);
+
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    let old_holder = stake_pool.set_role(role, holder);
+    stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+
+    emit!(RoleUpdated {
+        stake_pool: stake_pool.key(),
+        role,
+        old_holder,
+        new_holder: holder,
+        authority: ctx.accounts.authority.key(),
+        sequence: stake_pool.sequence,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!("Role {:?}: {} -> {}", role, old_holder, holder);
+    msg!("Admin: {}", ctx.accounts.authority.key());
+
+    Ok(())
+}
+
+/// Schedule a time-limited APY boost campaign.
+///
+/// # Security
+/// - Only pool.authority can call this
+/// - Multiplier above 1x and at most MAX_APY_BOOST_MULTIPLIER_BPS, lasting
+///   at most MAX_APY_BOOST_DURATION
+/// - May not start in the past or before the previous campaign ends, so
+///   elapsed time is never repriced and campaigns never stack
+/// - Ends by itself; no change to the tier APYs or their checkpoints
+/// - Multisig pools must go through `propose_admin_action`
+///
+/// # Arguments
+/// * `ctx` - AdminControl accounts context
+/// * `multiplier_bps` - Base APY multiplier while the boost runs (20_000 = 2x)
+/// * `start_time` - Unix timestamp the boost starts at
+/// * `end_time` - Unix timestamp the boost ends at
+///
+/// # Returns
+/// Result indicating success or error
+pub fn set_apy_boost_handler(
+    ctx: Context<AdminControl>,
+    multiplier_bps: u16,
+    start_time: i64,
+    end_time: i64,
+) -> Result<()> {
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    require!(!stake_pool.has_admin_multisig(), StakingError::MultisigRequired);
+    stake_pool.next_sequence()?;
+    apply_apy_boost(
+        stake_pool,
+        ApyBoost {
+            multiplier_bps,
+            start_time,
+            end_time,
+        },
+        clock.unix_timestamp,
+    )?;
+    msg!("Admin: {}", ctx.accounts.authority.key());
+
+    Ok(())
+}
+
+/// Schedule an APY boost campaign and emit `ApyBoostScheduled`; shared with
+/// the multisig path.
+pub fn apply_apy_boost(stake_pool: &mut Account<StakePool>, boost: ApyBoost, now: i64) -> Result<()> {
+    stake_pool.schedule_apy_boost(boost, now)?;
+    stake_pool.last_updated = now;
+
+    emit!(ApyBoostScheduled {
+        stake_pool: stake_pool.key(),
+        multiplier_bps: boost.multiplier_bps,
+        start_time: boost.start_time,
+        end_time: boost.end_time,
+        sequence: stake_pool.sequence,
+        timestamp: now,
+    });
+
+    msg!(
+        "APY boost: {}bp from {} to {}",
+        boost.multiplier_bps,
+        boost.start_time,
+        boost.end_time
+    );
 
     Ok(())
 }
@@ -0,0 +1,415 @@
+/// Admin multisig instruction handlers.
+///
+/// Optional threshold approval for the most sensitive admin actions: pause
+/// flags and tier pauses, emergency mode, APYs (Permanent included) and their
+/// decay and boosts, the reward rate, the emission cap and rewards end time,
+/// cancelling queued changes and authority transfers. Pools that never call
+/// `create_admin_multisig` keep the single-key flow.
+///
+/// ## Security Guarantees
+/// - Once created, the single-key instructions for those actions fail with
+///   `MultisigRequired`, and the multisig cannot be removed
+/// - Each signer approves a proposal at most once
+/// - A proposal executes exactly once, when its approvals reach the
+///   threshold, and cannot be approved after it expires
+/// - Proposal PDAs are keyed by a per-multisig nonce, so an executed
+///   proposal cannot be recreated and replayed
+
+use anchor_lang::prelude::*;
+
+use crate::constants::*;
+use crate::error::StakingError;
+use crate::events::{AdminActionApproved, AdminActionExecuted, AdminActionProposed};
+use crate::instructions::admin::{
+    apply_apy_boost, apply_authority_proposal, apply_emergency, apply_pause_flags,
+    apply_reward_rate, apply_tier_paused, schedule_apy, schedule_apy_decay, schedule_emission_cap,
+    schedule_permanent_apy, schedule_rewards_end_time,
+};
+use crate::instructions::pending_params::cancel_pending_params;
+use crate::state::{AdminAction, AdminMultisig, AdminProposal, ApyBoost, StakePool};
+
+/// Accounts required for creating the admin multisig.
+///
+/// ## Security Notes
+/// - Authority must be signer AND match stake_pool.authority
+/// - One multisig per pool: the PDA is seeded by the pool
+#[derive(Accounts)]
+pub struct CreateAdminMultisig<'info> {
+    /// The admin authority; pays rent for the multisig account.
+    /// SECURITY: Must be signer AND match pool.authority.
+    #[account(
+        mut,
+        constraint = authority.key() == stake_pool.authority @ StakingError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    /// The stake pool.
+    /// SECURITY: PDA validation + has_one authority.
+    #[account(
+        mut,
+        seeds = [STAKE_POOL_SEED, stake_pool.staking_mint.as_ref()],
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ StakingError::AccountVersionMismatch,
+        bump = stake_pool.bump,
+        has_one = authority @ StakingError::Unauthorized
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+
+    /// New multisig PDA.
+    #[account(
+        init,
+        payer = authority,
+        space = AdminMultisig::LEN,
+        seeds = [ADMIN_MULTISIG_SEED, stake_pool.key().as_ref()],
+        bump
+    )]
+    pub admin_multisig: Box<Account<'info, AdminMultisig>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required for proposing an admin action.
+///
+/// ## Security Notes
+/// - Proposer must be signer AND one of the multisig signers
+/// - Proposal PDA is seeded by the multisig's next nonce
+#[derive(Accounts)]
+pub struct ProposeAdminAction<'info> {
+    /// A multisig signer; pays rent for the proposal.
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+
+    /// The stake pool; writable for `sequence`.
+    /// SECURITY: PDA validation.
+    #[account(
+        mut,
+        seeds = [STAKE_POOL_SEED, stake_pool.staking_mint.as_ref()],
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ StakingError::AccountVersionMismatch,
+        bump = stake_pool.bump
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+
+    /// The pool's multisig.
+    /// SECURITY: PDA validation + pool binding.
+    #[account(
+        mut,
+        seeds = [ADMIN_MULTISIG_SEED, stake_pool.key().as_ref()],
+        bump = admin_multisig.bump,
+        constraint = admin_multisig.stake_pool == stake_pool.key() @ StakingError::StakePoolMismatch
+    )]
+    pub admin_multisig: Box<Account<'info, AdminMultisig>>,
+
+    /// New proposal PDA.
+    #[account(
+        init,
+        payer = proposer,
+        space = AdminProposal::LEN,
+        seeds = [
+            ADMIN_PROPOSAL_SEED,
+            admin_multisig.key().as_ref(),
+            &admin_multisig.proposal_count.to_le_bytes()
+        ],
+        bump
+    )]
+    pub proposal: Box<Account<'info, AdminProposal>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required for approving (and possibly executing) a proposal.
+///
+/// ## Security Notes
+/// - Approver must be signer AND one of the multisig signers
+/// - Proposal must belong to this pool's multisig
+#[derive(Accounts)]
+pub struct ApproveAdminAction<'info> {
+    /// A multisig signer.
+    pub approver: Signer<'info>,
+
+    /// The stake pool the action is applied to.
+    /// SECURITY: PDA validation.
+    #[account(
+        mut,
+        seeds = [STAKE_POOL_SEED, stake_pool.staking_mint.as_ref()],
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ StakingError::AccountVersionMismatch,
+        bump = stake_pool.bump
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+
+    /// The pool's multisig.
+    /// SECURITY: PDA validation + pool binding.
+    #[account(
+        seeds = [ADMIN_MULTISIG_SEED, stake_pool.key().as_ref()],
+        bump = admin_multisig.bump,
+        constraint = admin_multisig.stake_pool == stake_pool.key() @ StakingError::StakePoolMismatch
+    )]
+    pub admin_multisig: Box<Account<'info, AdminMultisig>>,
+
+    /// The proposal being approved.
+    /// SECURITY: PDA validation binds it to the multisig.
+    #[account(
+        mut,
+        seeds = [
+            ADMIN_PROPOSAL_SEED,
+            admin_multisig.key().as_ref(),
+            &proposal.nonce.to_le_bytes()
+        ],
+        bump = proposal.bump,
+        constraint = proposal.multisig == admin_multisig.key() @ StakingError::StakePoolMismatch
+    )]
+    pub proposal: Box<Account<'info, AdminProposal>>,
+}
+
+/// Put the pool's sensitive admin actions behind a multisig.
+///
+/// # Security
+/// - Only pool.authority can call this, once per pool
+/// - 1..=MAX_MULTISIG_SIGNERS distinct, non-default signers
+/// - Threshold between 1 and the number of signers
+/// - Proposal lifetime between 1 second and MAX_PROPOSAL_TTL
+///
+/// # Arguments
+/// * `ctx` - CreateAdminMultisig accounts context
+/// * `signers` - Signer pubkeys
+/// * `threshold` - Approvals required to execute
+/// * `proposal_ttl_seconds` - How long proposals stay open
+///
+/// # Returns
+/// Result indicating success or error
+pub fn create_handler(
+    ctx: Context<CreateAdminMultisig>,
+    signers: Vec<Pubkey>,
+    threshold: u8,
+    proposal_ttl_seconds: i64,
+) -> Result<()> {
+    // === INPUT VALIDATION ===
+
+    require!(
+        !signers.is_empty() && signers.len() <= MAX_MULTISIG_SIGNERS,
+        StakingError::InvalidMultisigConfig
+    );
+    require!(
+        threshold > 0 && threshold as usize <= signers.len(),
+        StakingError::InvalidMultisigConfig
+    );
+    require!(
+        proposal_ttl_seconds > 0 && proposal_ttl_seconds <= MAX_PROPOSAL_TTL,
+        StakingError::InvalidMultisigConfig
+    );
+    for (i, signer) in signers.iter().enumerate() {
+        require!(
+            *signer != Pubkey::default() && !signers[..i].contains(signer),
+            StakingError::InvalidMultisigConfig
+        );
+    }
+
+    // === STATE UPDATE ===
+
+    let clock = Clock::get()?;
+    let multisig_key = ctx.accounts.admin_multisig.key();
+
+    let admin_multisig = &mut ctx.accounts.admin_multisig;
+    admin_multisig.stake_pool = ctx.accounts.stake_pool.key();
+    admin_multisig.signers = [Pubkey::default(); MAX_MULTISIG_SIGNERS];
+    admin_multisig.signers[..signers.len()].copy_from_slice(&signers);
+    admin_multisig.signer_count = signers.len() as u8;
+    admin_multisig.threshold = threshold;
+    admin_multisig.proposal_ttl_seconds = proposal_ttl_seconds;
+    admin_multisig.proposal_count = 0;
+    admin_multisig.bump = ctx.bumps.admin_multisig;
+
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    stake_pool.admin_multisig = multisig_key;
+    stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+
+    msg!(
+        "Admin multisig created: {} of {} signers",
+        threshold,
+        signers.len()
+    );
+    msg!("Admin: {}", ctx.accounts.authority.key());
+
+    Ok(())
+}
+
+/// Propose an admin action for the multisig to approve.
+///
+/// # Security
+/// - Only multisig signers can propose
+/// - Proposing does not count as an approval; the proposer approves like
+///   any other signer
+/// - Arguments are validated when the action executes, against the pool
+///   state at that time
+///
+/// # Arguments
+/// * `ctx` - ProposeAdminAction accounts context
+/// * `action` - The change to apply once approved
+///
+/// # Returns
+/// Result indicating success or error
+pub fn propose_handler(ctx: Context<ProposeAdminAction>, action: AdminAction) -> Result<()> {
+    let clock = Clock::get()?;
+    ctx.accounts.stake_pool.next_sequence()?;
+    let admin_multisig = &mut ctx.accounts.admin_multisig;
+
+    require!(
+        admin_multisig.signer_index(&ctx.accounts.proposer.key()).is_some(),
+        StakingError::NotMultisigSigner
+    );
+
+    let nonce = admin_multisig.proposal_count;
+    admin_multisig.proposal_count = nonce
+        .checked_add(1)
+        .ok_or(StakingError::MathOverflow)?;
+    let expires_at = clock
+        .unix_timestamp
+        .checked_add(admin_multisig.proposal_ttl_seconds)
+        .ok_or(StakingError::MathOverflow)?;
+
+    let proposal = &mut ctx.accounts.proposal;
+    proposal.multisig = admin_multisig.key();
+    proposal.nonce = nonce;
+    proposal.proposer = ctx.accounts.proposer.key();
+    proposal.action = action;
+    proposal.approvals = 0;
+    proposal.created_at = clock.unix_timestamp;
+    proposal.expires_at = expires_at;
+    proposal.executed = false;
+    proposal.bump = ctx.bumps.proposal;
+
+    emit!(AdminActionProposed {
+        stake_pool: ctx.accounts.stake_pool.key(),
+        proposal: proposal.key(),
+        nonce,
+        proposer: proposal.proposer,
+        expires_at,
+        sequence: ctx.accounts.stake_pool.sequence,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!("Admin action #{} proposed: {:?}", nonce, action);
+    msg!("Expires at: {}", expires_at);
+
+    Ok(())
+}
+
+/// Approve a proposal, executing it once the threshold is reached.
+///
+/// # Security
+/// - Only multisig signers can approve, each at most once
+/// - Expired and already executed proposals are rejected
+/// - The approval that reaches the threshold applies the action, whatever
+///   order the signers approved in
+///
+/// # Arguments
+/// * `ctx` - ApproveAdminAction accounts context
+///
+/// # Returns
+/// Result indicating success or error
+pub fn approve_handler(ctx: Context<ApproveAdminAction>) -> Result<()> {
+    let clock = Clock::get()?;
+    // One number for the approval and, once the threshold is met, the execution
+    ctx.accounts.stake_pool.next_sequence()?;
+    let admin_multisig = &ctx.accounts.admin_multisig;
+    let proposal = &mut ctx.accounts.proposal;
+
+    // === INPUT VALIDATION ===
+
+    let index = admin_multisig
+        .signer_index(&ctx.accounts.approver.key())
+        .ok_or(StakingError::NotMultisigSigner)?;
+    require!(!proposal.executed, StakingError::ProposalAlreadyExecuted);
+    require!(
+        !proposal.is_expired(clock.unix_timestamp),
+        StakingError::ProposalExpired
+    );
+    require!(!proposal.has_approved(index), StakingError::AlreadyApproved);
+
+    // === APPROVAL ===
+
+    proposal.approvals |= 1 << index;
+    let approvals = proposal.approval_count();
+
+    emit!(AdminActionApproved {
+        stake_pool: ctx.accounts.stake_pool.key(),
+        proposal: proposal.key(),
+        approver: ctx.accounts.approver.key(),
+        approvals,
+        threshold: admin_multisig.threshold,
+        sequence: ctx.accounts.stake_pool.sequence,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!(
+        "Admin action #{} approved ({}/{})",
+        proposal.nonce,
+        approvals,
+        admin_multisig.threshold
+    );
+
+    if approvals < admin_multisig.threshold {
+        return Ok(());
+    }
+
+    // === EXECUTION ===
+
+    proposal.executed = true;
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let now = clock.unix_timestamp;
+    match proposal.action {
+        AdminAction::SetPaused { flags } => apply_pause_flags(stake_pool, flags, now)?,
+        AdminAction::AdjustApy {
+            flex_apy,
+            core_apy,
+            prime_apy,
+        } => schedule_apy(stake_pool, flex_apy, core_apy, prime_apy, now)?,
+        AdminAction::UpdateCap { new_cap } => schedule_emission_cap(stake_pool, new_cap, now)?,
+        AdminAction::TransferAuthority { new_authority } => {
+            apply_authority_proposal(stake_pool, new_authority, now)?
+        }
+        AdminAction::SetApyDecay {
+            interval_secs,
+            bps_per_interval,
+            min_apy_floor,
+        } => schedule_apy_decay(stake_pool, interval_secs, bps_per_interval, min_apy_floor, now)?,
+        AdminAction::SetRewardRate {
+            reward_rate_per_second,
+        } => apply_reward_rate(stake_pool, reward_rate_per_second, now)?,
+        AdminAction::SetApyBoost {
+            multiplier_bps,
+            start_time,
+            end_time,
+        } => apply_apy_boost(
+            stake_pool,
+            ApyBoost {
+                multiplier_bps,
+                start_time,
+                end_time,
+            },
+            now,
+        )?,
+        AdminAction::SetEmergency { enabled } => apply_emergency(stake_pool, enabled, now)?,
+        AdminAction::SetTierPaused { tier, paused } => {
+            apply_tier_paused(stake_pool, tier, paused, now)?
+        }
+        AdminAction::SetPermanentApy { permanent_apy } => {
+            schedule_permanent_apy(stake_pool, permanent_apy, now)?
+        }
+        AdminAction::CancelPendingParams => cancel_pending_params(stake_pool, now)?,
+        AdminAction::SetRewardsEndTime { end_time } => {
+            schedule_rewards_end_time(stake_pool, end_time, now)?
+        }
+    }
+
+    emit!(AdminActionExecuted {
+        stake_pool: stake_pool.key(),
+        proposal: proposal.key(),
+        nonce: proposal.nonce,
+        sequence: stake_pool.sequence,
+        timestamp: now,
+    });
+
+    msg!("Admin action #{} executed", proposal.nonce);
+
+    Ok(())
+}
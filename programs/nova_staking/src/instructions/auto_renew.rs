@@ -0,0 +1,119 @@
+/// Auto-renew instruction handler.
+///
+/// Lets a Core or Prime staker keep their lock rolling: while the flag is on,
+/// each ended lock becomes a new cycle of the same length, measured from
+/// `stake_start_time` in whole lock periods.
+///
+/// ## Security Guarantees
+/// - Only the position owner can toggle the flag
+/// - Cycles that rolled over while the flag was on are closed out before it
+///   changes, so turning it off unlocks at the end of the current cycle
+/// - Rewards are settled first, so accrual already earned keeps its rate
+///   when the lock (and with `downgrade_after_lock`, the rate tier) moves
+/// - No token movement
+
+use anchor_lang::prelude::*;
+
+use crate::constants::*;
+use crate::error::StakingError;
+use crate::instructions::recovery::cancel_on_owner_signature;
+use crate::instructions::stake::{calculate_pending_rewards, update_rewards};
+use crate::state::{StakePool, UserStake};
+use crate::tier::Tier;
+
+/// Accounts required for toggling auto-renew.
+///
+/// ## Security Notes
+/// - User must be signer AND match user_stake.owner
+/// - No token movement, so no vault accounts are required
+#[derive(Accounts)]
+pub struct SetAutoRenew<'info> {
+    /// The position owner.
+    /// SECURITY: Must be signer and match stake owner.
+    pub user: Signer<'info>,
+
+    /// The stake pool; writable for `sequence`.
+    /// SECURITY: PDA validation.
+    #[account(
+        mut,
+        seeds = [STAKE_POOL_SEED, stake_pool.staking_mint.as_ref()],
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ StakingError::AccountVersionMismatch,
+        bump = stake_pool.bump
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+
+    /// User's stake account.
+    /// SECURITY: PDA + owner + pool validation.
+    #[account(
+        mut,
+        seeds = [USER_STAKE_SEED, stake_pool.key().as_ref(), user.key().as_ref()],
+        bump = user_stake.bump,
+        constraint = user_stake.version == USER_STAKE_VERSION @ StakingError::AccountVersionMismatch,
+        constraint = user_stake.owner == user.key() @ StakingError::InvalidStakeOwner,
+        constraint = user_stake.stake_pool == stake_pool.key() @ StakingError::StakePoolMismatch
+    )]
+    pub user_stake: Account<'info, UserStake>,
+}
+
+/// Turn lock auto-renew on or off for the signer's position.
+///
+/// # Security
+/// - Enabling requires an open Core or Prime position
+/// - Pending rewards are settled into the position before anything moves
+/// - Rolled-over cycles are applied first: `stake_start_time` moves to the
+///   start of the running cycle and each ended lock counts as a completed cycle
+/// - Disabling is always allowed; the running cycle still has to end
+///
+/// # Arguments
+/// * `ctx` - SetAutoRenew accounts context
+/// * `enabled` - Whether the lock should keep rolling over
+///
+/// # Returns
+/// Result indicating success or error
+pub fn handler(ctx: Context<SetAutoRenew>, enabled: bool) -> Result<()> {
+    let user_stake = &mut ctx.accounts.user_stake;
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    require!(clock.unix_timestamp > 0, StakingError::InvalidTimestamp);
+
+    if enabled {
+        require!(
+            user_stake.is_active && user_stake.staked_amount > 0,
+            StakingError::NoActiveStake
+        );
+        require!(
+            matches!(user_stake.tier, Tier::Core | Tier::Prime)
+                && user_stake.get_lock_period() > 0,
+            StakingError::AutoRenewRequiresLock
+        );
+    }
+
+    // Settle at the current lock before the flag moves it: with
+    // `downgrade_after_lock` the lock end decides the rate tier
+    let pending = calculate_pending_rewards(user_stake, stake_pool, clock.unix_timestamp)?;
+    update_rewards(stake_pool, user_stake, clock.unix_timestamp)?;
+    user_stake.pending_rewards = user_stake
+        .pending_rewards
+        .checked_add(pending)
+        .ok_or(StakingError::MathOverflow)?;
+    user_stake.mark_settled(stake_pool, clock.unix_timestamp);
+    stake_pool.pending_liability = stake_pool
+        .pending_liability
+        .checked_add(pending)
+        .ok_or(StakingError::MathOverflow)?;
+
+    // Close out cycles that renewed while the flag was on, then join the
+    // cycle running now if it is being turned on
+    user_stake.roll_auto_renew(clock.unix_timestamp);
+    user_stake.auto_renew = enabled;
+    user_stake.roll_auto_renew(clock.unix_timestamp);
+    stake_pool.next_sequence()?;
+    cancel_on_owner_signature(stake_pool, user_stake, clock.unix_timestamp);
+
+    msg!("Auto-renew {}", if enabled { "enabled" } else { "disabled" });
+    msg!("Current lock ends at: {}", user_stake.lock_end_time());
+    msg!("Completed lock cycles: {}", user_stake.completed_lock_cycles);
+
+    Ok(())
+}
@@ -0,0 +1,140 @@
+/// Boost-lock instruction handler.
+///
+/// Commits liquid pending rewards for a fixed period in exchange for extra APY.
+///
+/// ## Security Guarantees
+/// - Only the position owner can lock its rewards
+/// - Accrual is settled before the boosted rate starts
+/// - Boosted rewards stay undistributed until they return to pending at expiry
+
+use anchor_lang::prelude::*;
+
+use crate::constants::*;
+use crate::error::StakingError;
+use crate::instructions::recovery::cancel_on_owner_signature;
+use crate::instructions::stake::{calculate_pending_rewards, update_rewards};
+use crate::state::{StakePool, UserStake};
+
+/// Accounts required for boost-locking rewards.
+///
+/// ## Security Notes
+/// - User must be signer AND match user_stake.owner
+/// - No token movement, so no vault accounts are required
+#[derive(Accounts)]
+pub struct BoostLock<'info> {
+    /// The position owner.
+    /// SECURITY: Must be signer and match stake owner.
+    pub user: Signer<'info>,
+
+    /// The stake pool.
+    /// SECURITY: PDA validation.
+    #[account(
+        mut,
+        seeds = [STAKE_POOL_SEED, stake_pool.staking_mint.as_ref()],
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ StakingError::AccountVersionMismatch,
+        bump = stake_pool.bump
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+
+    /// User's stake account.
+    /// SECURITY: PDA + owner + pool validation.
+    #[account(
+        mut,
+        seeds = [USER_STAKE_SEED, stake_pool.key().as_ref(), user.key().as_ref()],
+        bump = user_stake.bump,
+        constraint = user_stake.version == USER_STAKE_VERSION @ StakingError::AccountVersionMismatch,
+        constraint = user_stake.owner == user.key() @ StakingError::InvalidStakeOwner,
+        constraint = user_stake.stake_pool == stake_pool.key() @ StakingError::StakePoolMismatch
+    )]
+    pub user_stake: Account<'info, UserStake>,
+}
+
+/// Move pending rewards into the boosted bucket until `now + duration_secs`.
+///
+/// # Security
+/// - Settles accrual first so the boost only affects future time
+/// - Amount limited to settled `pending_rewards`
+/// - One boost at a time; an expired boost is returned to pending first
+/// - Boosted rewards count against the emission cap only when claimed
+///
+/// # Arguments
+/// * `ctx` - BoostLock accounts context
+/// * `amount` - Pending rewards to commit
+/// * `duration_secs` - Lock length (up to the pool's max boost duration)
+///
+/// # Returns
+/// Result indicating success or error
+pub fn handler(ctx: Context<BoostLock>, amount: u64, duration_secs: i64) -> Result<()> {
+    // An expired `set_paused` pause is lifted before the pause checks
+    ctx.accounts.stake_pool.expire_pause(Clock::get()?.unix_timestamp)?;
+    let stake_pool = &ctx.accounts.stake_pool;
+    let user_stake = &ctx.accounts.user_stake;
+    let clock = Clock::get()?;
+
+    // Emergency mode blocks everything except principal exits
+    require!(!stake_pool.emergency, StakingError::EmergencyMode);
+
+    // === INPUT VALIDATION ===
+
+    require!(!stake_pool.is_stake_paused(), StakingError::StakingPaused);
+    require!(stake_pool.boost_bps > 0, StakingError::BoostDisabled);
+    require!(amount > 0, StakingError::ZeroAmount);
+    require!(
+        duration_secs > 0 && duration_secs <= stake_pool.max_boost_duration_secs,
+        StakingError::InvalidBoostDuration
+    );
+
+    require!(
+        user_stake.is_active && user_stake.staked_amount > 0,
+        StakingError::NoActiveStake
+    );
+    require!(clock.unix_timestamp > 0, StakingError::InvalidTimestamp);
+    require!(
+        !user_stake.is_boost_active(clock.unix_timestamp),
+        StakingError::BoostAlreadyActive
+    );
+
+    // === SETTLE ACCRUAL ===
+
+    let pending = calculate_pending_rewards(user_stake, stake_pool, clock.unix_timestamp)?;
+    let released = user_stake.releasable_boost(clock.unix_timestamp);
+
+    // === STATE UPDATE ===
+
+    let user_stake = &mut ctx.accounts.user_stake;
+    let stake_pool = &mut ctx.accounts.stake_pool;
+
+    // Settle the reward-per-token accumulator before balances change
+    update_rewards(stake_pool, user_stake, clock.unix_timestamp)?;
+    stake_pool.next_sequence()?;
+    cancel_on_owner_signature(stake_pool, user_stake, clock.unix_timestamp);
+
+    let available = user_stake
+        .pending_rewards
+        .checked_add(pending)
+        .ok_or(StakingError::MathOverflow)?
+        .checked_add(released)
+        .ok_or(StakingError::MathOverflow)?;
+    require!(amount <= available, StakingError::InsufficientPendingRewards);
+
+    user_stake.pending_rewards = available
+        .checked_sub(amount)
+        .ok_or(StakingError::MathUnderflow)?;
+    user_stake.mark_settled(stake_pool, clock.unix_timestamp);
+    user_stake.boosted_amount = amount;
+    user_stake.boost_until = clock
+        .unix_timestamp
+        .checked_add(duration_secs)
+        .ok_or(StakingError::MathOverflow)?;
+
+    // Only the newly settled accrual is new; a released boost was already owed
+    stake_pool.pending_liability = stake_pool
+        .pending_liability
+        .checked_add(pending)
+        .ok_or(StakingError::MathOverflow)?;
+
+    msg!("Boost-locked {} reward tokens until {}", amount, user_stake.boost_until);
+    msg!("Liquid pending rewards: {}", user_stake.pending_rewards);
+
+    Ok(())
+}
@@ -0,0 +1,357 @@
+/// Change tier instruction handler.
+///
+/// Moves an existing position to another tier without unstaking.
+///
+/// ## Security Guarantees
+/// - Only allowed once the current lock has ended, except for upgrades
+/// - Accrual is settled at the old APY before the tier changes
+/// - A new lock starts immediately for the new tier
+
+use anchor_lang::prelude::*;
+
+use crate::constants::*;
+use crate::error::StakingError;
+use crate::events::TierUpgraded;
+use crate::instructions::recovery::cancel_on_owner_signature;
+use crate::instructions::stake::{calculate_pending_rewards, update_rewards};
+use crate::state::{PositionEventKind, PositionHistory, PositionRecord, StakePool, UserStake};
+use crate::tier::Tier;
+
+/// Accounts required for changing tier.
+///
+/// ## Security Notes
+/// - User must be signer AND match user_stake.owner
+/// - No token movement, so no vault accounts are required
+#[derive(Accounts)]
+pub struct ChangeTier<'info> {
+    /// The position owner.
+    /// SECURITY: Must be signer and match stake owner.
+    pub user: Signer<'info>,
+
+    /// The stake pool.
+    /// SECURITY: PDA validation.
+    #[account(
+        mut,
+        seeds = [STAKE_POOL_SEED, stake_pool.staking_mint.as_ref()],
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ StakingError::AccountVersionMismatch,
+        bump = stake_pool.bump
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+
+    /// User's stake account.
+    /// SECURITY: PDA + owner + pool validation.
+    #[account(
+        mut,
+        seeds = [USER_STAKE_SEED, stake_pool.key().as_ref(), user.key().as_ref()],
+        bump = user_stake.bump,
+        constraint = user_stake.version == USER_STAKE_VERSION @ StakingError::AccountVersionMismatch,
+        constraint = user_stake.owner == user.key() @ StakingError::InvalidStakeOwner,
+        constraint = user_stake.stake_pool == stake_pool.key() @ StakingError::StakePoolMismatch
+    )]
+    pub user_stake: Account<'info, UserStake>,
+
+    /// Optional position history; the change is recorded when provided.
+    /// SECURITY: PDA derived from the signer, so only the owner's own history.
+    #[account(
+        mut,
+        seeds = [POSITION_HISTORY_SEED, stake_pool.key().as_ref(), user.key().as_ref()],
+        bump = position_history.bump
+    )]
+    pub position_history: Option<Box<Account<'info, PositionHistory>>>,
+}
+
+/// Move an unlocked position to a new tier.
+///
+/// # Security
+/// - Rejects while the current lock is still running or auto-renews
+/// - Settles pending rewards at the old tier's APY
+/// - Resets `stake_start_time` so the new tier's lock, at the pool's current
+///   length, applies from now
+/// - Choosing the current tier simply starts a fresh lock (relock)
+/// - Relocking Core/Prime within the grace window after lock end completes a
+///   cycle and credits the pool's relock bonus, bounded by the emission cap
+///
+/// # Arguments
+/// * `ctx` - ChangeTier accounts context
+/// * `new_tier` - Target tier (0=Flex, 1=Core, 2=Prime, 3=Permanent)
+///
+/// # Returns
+/// Result indicating success or error
+pub fn handler(ctx: Context<ChangeTier>, new_tier: u8) -> Result<()> {
+    // An expired `set_paused` pause is lifted before the pause checks
+    ctx.accounts.stake_pool.expire_pause(Clock::get()?.unix_timestamp)?;
+    let stake_pool = &ctx.accounts.stake_pool;
+    let user_stake = &ctx.accounts.user_stake;
+    let clock = Clock::get()?;
+
+    // Emergency mode blocks everything except principal exits
+    require!(!stake_pool.emergency, StakingError::EmergencyMode);
+
+    // === INPUT VALIDATION ===
+
+    let new_tier = Tier::try_from(new_tier)?;
+
+    require!(stake_pool.is_accepting_stakes(new_tier), StakingError::StakingPaused);
+
+    require!(
+        new_tier != Tier::Permanent || stake_pool.permanent_apy > 0,
+        StakingError::PermanentTierDisabled
+    );
+
+    require!(
+        user_stake.is_active && user_stake.staked_amount > 0,
+        StakingError::NoActiveStake
+    );
+
+    // Queued Flex principal must be withdrawn or cancelled first
+    require!(
+        !user_stake.has_pending_unstake(),
+        StakingError::UnstakeRequestPending
+    );
+
+    require!(clock.unix_timestamp > 0, StakingError::InvalidTimestamp);
+
+    // === LOCK PERIOD ENFORCEMENT ===
+
+    // An auto-renewing lock never ends; it must be turned off first
+    require!(!user_stake.auto_renew, StakingError::LockPeriodNotEnded);
+
+    require!(
+        user_stake.is_lock_ended(
+            clock.unix_timestamp,
+            user_stake.get_lock_period(),
+            stake_pool.unlock_grace_secs,
+        ),
+        StakingError::LockPeriodNotEnded
+    );
+
+    // Permanent positions cannot leave the tier before the sunset
+    if user_stake.tier == Tier::Permanent {
+        require!(
+            stake_pool.is_sunset_reached(clock.unix_timestamp),
+            StakingError::SunsetNotReached
+        );
+    }
+
+    // === SETTLE AT OLD APY ===
+
+    let pending = calculate_pending_rewards(user_stake, stake_pool, clock.unix_timestamp)?;
+
+    // === RELOCK ANNIVERSARY BONUS ===
+
+    // A same-tier relock of a locked tier within the grace window completes a cycle
+    let lock_period = user_stake.get_lock_period();
+    let lock_end = user_stake
+        .stake_start_time
+        .checked_add(lock_period)
+        .ok_or(StakingError::MathOverflow)?;
+    let completes_cycle = new_tier == user_stake.tier
+        && lock_period > 0
+        && clock.unix_timestamp <= lock_end.saturating_add(stake_pool.relock_grace_secs);
+
+    let relock_bonus = if completes_cycle && stake_pool.relock_bonus_bps > 0 {
+        let bonus = (user_stake.staked_amount as u128)
+            .checked_mul(stake_pool.relock_bonus_bps as u128)
+            .ok_or(StakingError::MathOverflow)?
+            .checked_div(BASIS_POINTS_DENOMINATOR as u128)
+            .ok_or(StakingError::DivisionByZero)?;
+        let bonus = u64::try_from(bonus).map_err(|_| StakingError::ConversionOverflow)?;
+
+        // Bonus comes out of the same emission budget as regular rewards
+        bonus.min(stake_pool.remaining_emission_capacity())
+    } else {
+        0
+    };
+
+    // === STATE UPDATE ===
+
+    let user_stake = &mut ctx.accounts.user_stake;
+    let stake_pool = &mut ctx.accounts.stake_pool;
+
+    // Settle the reward-per-token accumulator before balances change
+    update_rewards(stake_pool, user_stake, clock.unix_timestamp)?;
+
+    user_stake.pending_rewards = user_stake
+        .pending_rewards
+        .checked_add(pending)
+        .ok_or(StakingError::MathOverflow)?
+        .checked_add(relock_bonus)
+        .ok_or(StakingError::MathOverflow)?;
+    user_stake.mark_settled(stake_pool, clock.unix_timestamp);
+    stake_pool.pending_liability = stake_pool
+        .pending_liability
+        .checked_add(pending)
+        .ok_or(StakingError::MathOverflow)?
+        .checked_add(relock_bonus)
+        .ok_or(StakingError::MathOverflow)?;
+
+    if completes_cycle {
+        user_stake.completed_lock_cycles = user_stake
+            .completed_lock_cycles
+            .checked_add(1)
+            .ok_or(StakingError::MathOverflow)?;
+    }
+
+    let old_tier = user_stake.tier;
+    user_stake.tier = new_tier;
+    user_stake.stake_start_time = clock.unix_timestamp;
+    user_stake.lock_seconds = stake_pool.get_lock_period_for_tier(new_tier);
+    user_stake.unlocked_withdrawn = 0;
+    stake_pool.move_staked(old_tier, new_tier, user_stake.staked_amount)?;
+    stake_pool.move_staker(old_tier, new_tier)?;
+    stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+    cancel_on_owner_signature(stake_pool, user_stake, clock.unix_timestamp);
+
+    if let Some(position_history) = ctx.accounts.position_history.as_mut() {
+        position_history.push(PositionRecord {
+            timestamp: clock.unix_timestamp,
+            kind: PositionEventKind::ChangeTier,
+            tier: new_tier as u8,
+            amount: 0,
+            staked_after: user_stake.staked_amount,
+        });
+    }
+
+    if old_tier == new_tier {
+        msg!("Relocked tier {:?}", new_tier);
+    } else {
+        msg!("Tier changed: {:?} -> {:?}", old_tier, new_tier);
+    }
+    msg!("Settled rewards: {}", pending);
+    if completes_cycle {
+        msg!(
+            "Completed lock cycle {} (bonus: {})",
+            user_stake.completed_lock_cycles,
+            relock_bonus
+        );
+    }
+    msg!("New lock ends at: {}", user_stake.lock_end_time());
+
+    Ok(())
+}
+
+/// Move a position to a higher tier, even while its current lock runs.
+///
+/// The longer commitment replaces the shorter one, so there is no reason to
+/// wait for the old lock to end.
+///
+/// # Security
+/// - Only upgrades; same-tier moves and downgrades fail with `CannotChangeTier`
+/// - Settles pending rewards at the old tier's APY
+/// - Resets `stake_start_time` so the new tier's full lock applies from now
+/// - No relock bonus; that is reserved for completed cycles in `change_tier`
+///
+/// # Arguments
+/// * `ctx` - ChangeTier accounts context
+/// * `new_tier` - Target tier (1=Core, 2=Prime, 3=Permanent)
+///
+/// # Returns
+/// Result indicating success or error
+pub fn upgrade_handler(ctx: Context<ChangeTier>, new_tier: u8) -> Result<()> {
+    // An expired `set_paused` pause is lifted before the pause checks
+    ctx.accounts.stake_pool.expire_pause(Clock::get()?.unix_timestamp)?;
+    let stake_pool = &ctx.accounts.stake_pool;
+    let user_stake = &ctx.accounts.user_stake;
+    let clock = Clock::get()?;
+
+    // Emergency mode blocks everything except principal exits
+    require!(!stake_pool.emergency, StakingError::EmergencyMode);
+
+    // === INPUT VALIDATION ===
+
+    let new_tier = Tier::try_from(new_tier)?;
+
+    require!(
+        user_stake.is_active && user_stake.staked_amount > 0,
+        StakingError::NoActiveStake
+    );
+
+    require!(
+        user_stake.tier.is_upgrade_to(new_tier),
+        StakingError::CannotChangeTier
+    );
+
+    require!(stake_pool.is_accepting_stakes(new_tier), StakingError::StakingPaused);
+
+    require!(
+        new_tier != Tier::Permanent || stake_pool.permanent_apy > 0,
+        StakingError::PermanentTierDisabled
+    );
+
+    // Queued Flex principal must be withdrawn or cancelled first
+    require!(
+        !user_stake.has_pending_unstake(),
+        StakingError::UnstakeRequestPending
+    );
+
+    require!(clock.unix_timestamp > 0, StakingError::InvalidTimestamp);
+
+    // === SETTLE AT OLD APY ===
+
+    let pending = calculate_pending_rewards(user_stake, stake_pool, clock.unix_timestamp)?;
+
+    // === STATE UPDATE ===
+
+    let user_stake = &mut ctx.accounts.user_stake;
+    let stake_pool = &mut ctx.accounts.stake_pool;
+
+    // Settle the reward-per-token accumulator before balances change
+    update_rewards(stake_pool, user_stake, clock.unix_timestamp)?;
+
+    user_stake.pending_rewards = user_stake
+        .pending_rewards
+        .checked_add(pending)
+        .ok_or(StakingError::MathOverflow)?;
+    user_stake.mark_settled(stake_pool, clock.unix_timestamp);
+    stake_pool.pending_liability = stake_pool
+        .pending_liability
+        .checked_add(pending)
+        .ok_or(StakingError::MathOverflow)?;
+
+    // Count cycles that auto-renewed before the new lock replaces them
+    user_stake.roll_auto_renew(clock.unix_timestamp);
+
+    let old_tier = user_stake.tier;
+    user_stake.tier = new_tier;
+    user_stake.stake_start_time = clock.unix_timestamp;
+    user_stake.lock_seconds = stake_pool.get_lock_period_for_tier(new_tier);
+    user_stake.unlocked_withdrawn = 0;
+    stake_pool.move_staked(old_tier, new_tier, user_stake.staked_amount)?;
+    stake_pool.move_staker(old_tier, new_tier)?;
+    if user_stake.lock_seconds == 0 {
+        user_stake.auto_renew = false;
+    }
+    stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+    cancel_on_owner_signature(stake_pool, user_stake, clock.unix_timestamp);
+
+    if let Some(position_history) = ctx.accounts.position_history.as_mut() {
+        position_history.push(PositionRecord {
+            timestamp: clock.unix_timestamp,
+            kind: PositionEventKind::ChangeTier,
+            tier: new_tier as u8,
+            amount: 0,
+            staked_after: user_stake.staked_amount,
+        });
+    }
+
+    let lock_end = user_stake.lock_end_time();
+
+    emit!(TierUpgraded {
+        stake_pool: stake_pool.key(),
+        owner: user_stake.owner,
+        old_tier: old_tier as u8,
+        new_tier: new_tier as u8,
+        settled_rewards: pending,
+        lock_end,
+        sequence: stake_pool.sequence,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!("Tier upgraded: {:?} -> {:?}", old_tier, new_tier);
+    msg!("Settled rewards: {}", pending);
+    msg!("New lock ends at: {}", lock_end);
+
+    Ok(())
+}
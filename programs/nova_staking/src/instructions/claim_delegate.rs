@@ -0,0 +1,111 @@
+/// Claim delegate instruction handlers.
+///
+/// Lets a stake owner name one key (e.g. a bot) that may call
+/// `claim_rewards` for their position without holding the owner's key.
+///
+/// ## Security Guarantees
+/// - Only the position owner can set or revoke the delegate
+/// - A delegate can only claim, and only into a token account owned by the
+///   stake owner or the owner's stored `reward_destination`; it cannot pick a
+///   `destination`, unstake or change the position
+/// - No token movement here
+
+use anchor_lang::prelude::*;
+
+use crate::constants::*;
+use crate::error::StakingError;
+use crate::events::ClaimDelegateChanged;
+use crate::instructions::recovery::cancel_on_owner_signature;
+use crate::state::{StakePool, UserStake};
+
+/// Accounts required for setting or revoking the claim delegate.
+///
+/// ## Security Notes
+/// - User must be signer AND match user_stake.owner
+#[derive(Accounts)]
+pub struct SetClaimDelegate<'info> {
+    /// The position owner.
+    /// SECURITY: Must be signer and match stake owner.
+    pub user: Signer<'info>,
+
+    /// The stake pool; writable for `sequence`.
+    /// SECURITY: PDA validation.
+    #[account(
+        mut,
+        seeds = [STAKE_POOL_SEED, stake_pool.staking_mint.as_ref()],
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ StakingError::AccountVersionMismatch,
+        bump = stake_pool.bump
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+
+    /// User's stake account.
+    /// SECURITY: PDA + owner + pool validation.
+    #[account(
+        mut,
+        seeds = [USER_STAKE_SEED, stake_pool.key().as_ref(), user.key().as_ref()],
+        bump = user_stake.bump,
+        constraint = user_stake.version == USER_STAKE_VERSION @ StakingError::AccountVersionMismatch,
+        constraint = user_stake.owner == user.key() @ StakingError::InvalidStakeOwner,
+        constraint = user_stake.stake_pool == stake_pool.key() @ StakingError::StakePoolMismatch
+    )]
+    pub user_stake: Account<'info, UserStake>,
+}
+
+/// Allow `delegate` to claim rewards for the signer's position.
+///
+/// # Security
+/// - Replaces any previous delegate
+/// - The delegate cannot be the default key or the owner
+///
+/// # Arguments
+/// * `ctx` - SetClaimDelegate accounts context
+/// * `delegate` - Key allowed to sign `claim_rewards`
+///
+/// # Returns
+/// Result indicating success or error
+pub fn set_handler(ctx: Context<SetClaimDelegate>, delegate: Pubkey) -> Result<()> {
+    require!(
+        delegate != Pubkey::default() && delegate != ctx.accounts.user.key(),
+        StakingError::InvalidClaimDelegate
+    );
+
+    update_delegate(ctx, delegate)
+}
+
+/// Remove the claim delegate from the signer's position.
+///
+/// # Returns
+/// Result indicating success or error
+pub fn revoke_handler(ctx: Context<SetClaimDelegate>) -> Result<()> {
+    require!(
+        ctx.accounts.user_stake.claim_delegate != Pubkey::default(),
+        StakingError::InvalidClaimDelegate
+    );
+
+    update_delegate(ctx, Pubkey::default())
+}
+
+fn update_delegate(ctx: Context<SetClaimDelegate>, delegate: Pubkey) -> Result<()> {
+    let user_stake = &mut ctx.accounts.user_stake;
+    let clock = Clock::get()?;
+
+    user_stake.claim_delegate = delegate;
+    ctx.accounts.stake_pool.next_sequence()?;
+    cancel_on_owner_signature(&ctx.accounts.stake_pool, user_stake, clock.unix_timestamp);
+
+    emit!(ClaimDelegateChanged {
+        stake_pool: ctx.accounts.stake_pool.key(),
+        owner: user_stake.owner,
+        delegate,
+        sequence: ctx.accounts.stake_pool.sequence,
+        timestamp: clock.unix_timestamp,
+    });
+
+    if delegate == Pubkey::default() {
+        msg!("Claim delegate revoked");
+    } else {
+        msg!("Claim delegate set to {}", delegate);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,351 @@
+/// Claim fee instruction handlers.
+///
+/// The claim fee and its fee vault only change through a timelocked
+/// propose/apply path, so stakers see a fee coming before it applies. The
+/// fee vault can be any reward-mint account, or the pool-owned PDA created by
+/// `initialize_fee_vault` and swept with `collect_fees`.
+///
+/// ## Security Guarantees
+/// - Only pool.authority can propose or cancel a change
+/// - Anyone can apply a change once `param_timelock_seconds` has elapsed
+/// - Fee capped at MAX_CLAIM_FEE_BPS; fee vault must hold the reward mint
+/// - Only pool.authority can sweep the fee vault PDA
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+
+use crate::constants::*;
+use crate::error::StakingError;
+use crate::events::FeesCollected;
+use crate::instructions::admin::AdminControl;
+use crate::state::StakePool;
+
+/// Accounts required for proposing a claim fee.
+///
+/// ## Security Notes
+/// - Authority must be signer AND match pool.authority
+/// - Fee vault may be any token account for the reward mint
+#[derive(Accounts)]
+pub struct ProposeClaimFee<'info> {
+    /// The admin authority.
+    /// SECURITY: Must be signer AND match pool.authority.
+    #[account(
+        mut,
+        constraint = authority.key() == stake_pool.authority @ StakingError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    /// The stake pool to modify.
+    /// SECURITY: PDA validation + has_one authority.
+    #[account(
+        mut,
+        seeds = [STAKE_POOL_SEED, stake_pool.staking_mint.as_ref()],
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ StakingError::AccountVersionMismatch,
+        bump = stake_pool.bump,
+        has_one = authority @ StakingError::Unauthorized
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+
+    /// Token account that will receive claim fees.
+    /// SECURITY: Must hold the reward mint.
+    #[account(
+        constraint = fee_vault.mint == stake_pool.reward_mint @ StakingError::MintMismatch
+    )]
+    pub fee_vault: Account<'info, TokenAccount>,
+}
+
+/// Accounts required for applying a pending claim fee.
+///
+/// ## Security Notes
+/// - Permissionless; the timelock is the only gate
+#[derive(Accounts)]
+pub struct ApplyClaimFee<'info> {
+    /// The stake pool.
+    /// SECURITY: PDA validation.
+    #[account(
+        mut,
+        seeds = [STAKE_POOL_SEED, stake_pool.staking_mint.as_ref()],
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ StakingError::AccountVersionMismatch,
+        bump = stake_pool.bump
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+}
+
+/// Accounts required for creating the pool's fee vault.
+///
+/// ## Security Notes
+/// - Authority must be signer AND match pool.authority
+/// - Fee vault is a PDA token account with the pool as authority
+#[derive(Accounts)]
+pub struct InitializeFeeVault<'info> {
+    /// The admin authority; pays for the fee vault.
+    /// SECURITY: Must be signer AND match pool.authority.
+    #[account(
+        mut,
+        constraint = authority.key() == stake_pool.authority @ StakingError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    /// The stake pool.
+    /// SECURITY: PDA validation + has_one authority and reward mint.
+    #[account(
+        seeds = [STAKE_POOL_SEED, stake_pool.staking_mint.as_ref()],
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ StakingError::AccountVersionMismatch,
+        bump = stake_pool.bump,
+        has_one = authority @ StakingError::Unauthorized,
+        has_one = reward_mint @ StakingError::MintMismatch
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+
+    /// The reward token mint; fees are skimmed from rewards.
+    pub reward_mint: Account<'info, Mint>,
+
+    /// Pool's fee vault.
+    /// SECURITY: PDA with the pool as authority.
+    #[account(
+        init,
+        payer = authority,
+        seeds = [FEE_VAULT_SEED, stake_pool.key().as_ref()],
+        bump,
+        token::mint = reward_mint,
+        token::authority = stake_pool
+    )]
+    pub fee_vault: Account<'info, TokenAccount>,
+
+    /// System program.
+    pub system_program: Program<'info, System>,
+
+    /// Token program.
+    pub token_program: Program<'info, Token>,
+
+    /// Rent sysvar.
+    pub rent: Sysvar<'info, Rent>,
+}
+
+/// Accounts required for sweeping the fee vault.
+///
+/// ## Security Notes
+/// - Authority must be signer AND match pool.authority
+/// - Destination only needs the reward mint; the authority picks the owner
+#[derive(Accounts)]
+pub struct CollectFees<'info> {
+    /// The admin authority.
+    /// SECURITY: Must be signer AND match pool.authority.
+    #[account(
+        constraint = authority.key() == stake_pool.authority @ StakingError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    /// The stake pool; writable for `sequence`.
+    /// SECURITY: PDA validation + has_one authority.
+    #[account(
+        mut,
+        seeds = [STAKE_POOL_SEED, stake_pool.staking_mint.as_ref()],
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ StakingError::AccountVersionMismatch,
+        bump = stake_pool.bump,
+        has_one = authority @ StakingError::Unauthorized
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+
+    /// Pool's fee vault.
+    /// SECURITY: PDA derived from the pool.
+    #[account(
+        mut,
+        seeds = [FEE_VAULT_SEED, stake_pool.key().as_ref()],
+        bump
+    )]
+    pub fee_vault: Account<'info, TokenAccount>,
+
+    /// Token account receiving the fees.
+    /// SECURITY: Reward mint validation.
+    #[account(
+        mut,
+        constraint = destination.mint == stake_pool.reward_mint @ StakingError::InvalidTokenAccountMint
+    )]
+    pub destination: Account<'info, TokenAccount>,
+
+    /// Token program.
+    pub token_program: Program<'info, Token>,
+}
+
+/// Queue a new claim fee and fee vault behind the parameter timelock.
+///
+/// # Security
+/// - Only pool.authority can call this
+/// - Fee capped at MAX_CLAIM_FEE_BPS
+/// - Replaces any pending proposal and restarts the timelock
+///
+/// # Arguments
+/// * `ctx` - ProposeClaimFee accounts context
+/// * `fee_bps` - New claim fee (basis points of the gross claim)
+///
+/// # Returns
+/// Result indicating success or error
+pub fn propose_claim_fee_handler(ctx: Context<ProposeClaimFee>, fee_bps: u16) -> Result<()> {
+    require!(fee_bps <= MAX_CLAIM_FEE_BPS, StakingError::InvalidClaimFee);
+
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    stake_pool.pending_claim_fee_bps = fee_bps;
+    stake_pool.pending_fee_vault = ctx.accounts.fee_vault.key();
+    stake_pool.pending_claim_fee_effective_at = clock
+        .unix_timestamp
+        .checked_add(stake_pool.param_timelock_seconds)
+        .ok_or(StakingError::MathOverflow)?;
+    stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+
+    msg!(
+        "Claim fee proposed: {}bp -> {}bp, effective at {}",
+        stake_pool.claim_fee_bps,
+        fee_bps,
+        stake_pool.pending_claim_fee_effective_at
+    );
+    msg!("Fee vault: {}", stake_pool.pending_fee_vault);
+    msg!("Admin: {}", ctx.accounts.authority.key());
+
+    Ok(())
+}
+
+/// Apply the pending claim fee once its timelock has elapsed.
+///
+/// # Arguments
+/// * `ctx` - ApplyClaimFee accounts context
+///
+/// # Returns
+/// Result indicating success or error
+pub fn apply_claim_fee_handler(ctx: Context<ApplyClaimFee>) -> Result<()> {
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    require!(
+        stake_pool.pending_claim_fee_effective_at != 0,
+        StakingError::NoPendingChange
+    );
+    require!(
+        clock.unix_timestamp >= stake_pool.pending_claim_fee_effective_at,
+        StakingError::ParamTimelockActive
+    );
+
+    stake_pool.claim_fee_bps = stake_pool.pending_claim_fee_bps;
+    stake_pool.fee_vault = stake_pool.pending_fee_vault;
+    stake_pool.pending_claim_fee_bps = 0;
+    stake_pool.pending_fee_vault = Pubkey::default();
+    stake_pool.pending_claim_fee_effective_at = 0;
+    stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+
+    msg!("Claim fee applied: {}bp to {}", stake_pool.claim_fee_bps, stake_pool.fee_vault);
+
+    Ok(())
+}
+
+/// Drop the pending claim fee proposal.
+///
+/// # Security
+/// - Only pool.authority can call this
+///
+/// # Arguments
+/// * `ctx` - AdminControl accounts context
+///
+/// # Returns
+/// Result indicating success or error
+pub fn cancel_claim_fee_handler(ctx: Context<AdminControl>) -> Result<()> {
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    require!(
+        stake_pool.pending_claim_fee_effective_at != 0,
+        StakingError::NoPendingChange
+    );
+
+    stake_pool.pending_claim_fee_bps = 0;
+    stake_pool.pending_fee_vault = Pubkey::default();
+    stake_pool.pending_claim_fee_effective_at = 0;
+    stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+
+    msg!("Pending claim fee cancelled");
+    msg!("Admin: {}", ctx.accounts.authority.key());
+
+    Ok(())
+}
+
+/// Create the pool-owned fee vault PDA.
+///
+/// # Security
+/// - Only pool.authority can call this
+/// - Creating it does not route fees there; propose it as the fee vault
+///   through `propose_claim_fee` like any other account
+///
+/// # Arguments
+/// * `ctx` - InitializeFeeVault accounts context
+///
+/// # Returns
+/// Result indicating success or error
+pub fn initialize_fee_vault_handler(ctx: Context<InitializeFeeVault>) -> Result<()> {
+    msg!("Fee vault created: {}", ctx.accounts.fee_vault.key());
+    msg!("Admin: {}", ctx.accounts.authority.key());
+
+    Ok(())
+}
+
+/// Sweep the whole fee vault PDA to `destination`.
+///
+/// # Security
+/// - Only pool.authority can call this
+/// - Fees were already counted against the emission cap when claimed, and
+///   no liability is held against them, so the vault can always be emptied
+/// - PDA signer for the vault transfer
+///
+/// # Arguments
+/// * `ctx` - CollectFees accounts context
+///
+/// # Returns
+/// Result indicating success or error
+pub fn collect_fees_handler(ctx: Context<CollectFees>) -> Result<()> {
+    let stake_pool = &ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    let amount = ctx.accounts.fee_vault.amount;
+    require!(amount > 0, StakingError::NoFeesToCollect);
+
+    // === PDA SIGNER TRANSFER ===
+
+    let staking_mint_key = stake_pool.staking_mint;
+    let seeds = &[
+        STAKE_POOL_SEED,
+        staking_mint_key.as_ref(),
+        &[stake_pool.bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+
+    let cpi_accounts = Transfer {
+        from: ctx.accounts.fee_vault.to_account_info(),
+        to: ctx.accounts.destination.to_account_info(),
+        authority: ctx.accounts.stake_pool.to_account_info(),
+    };
+    let cpi_program = ctx.accounts.token_program.to_account_info();
+    let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+    token::transfer(cpi_ctx, amount)?;
+
+    // === STATE UPDATE ===
+
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+
+    emit!(FeesCollected {
+        stake_pool: stake_pool.key(),
+        destination: ctx.accounts.destination.key(),
+        amount,
+        sequence: stake_pool.sequence,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!("Collected {} in claim fees", amount);
+    msg!("Admin: {}", ctx.accounts.authority.key());
+
+    Ok(())
+}
@@ -0,0 +1,115 @@
+/// Claim history instruction handlers.
+///
+/// Opt-in per-user ring buffer of recent claims for tax reporting.
+///
+/// ## Security Guarantees
+/// - Only the position owner can create their history, and pays its rent
+/// - History is written only by claim_rewards
+/// - Viewing is read-only
+
+use anchor_lang::prelude::*;
+
+use crate::constants::*;
+use crate::error::StakingError;
+use crate::state::{ClaimHistory, ClaimHistoryPage, StakePool, UserStake};
+
+/// Accounts required for enabling claim history.
+///
+/// ## Security Notes
+/// - User must be signer AND own the stake position
+/// - PDA seeds bind the history to (pool, user)
+#[derive(Accounts)]
+pub struct EnableClaimHistory<'info> {
+    /// The position owner; pays rent for the history account.
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// The stake pool.
+    /// SECURITY: PDA validation.
+    #[account(
+        seeds = [STAKE_POOL_SEED, stake_pool.staking_mint.as_ref()],
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ StakingError::AccountVersionMismatch,
+        bump = stake_pool.bump
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+
+    /// User's stake account.
+    /// SECURITY: PDA + owner + pool validation.
+    #[account(
+        seeds = [USER_STAKE_SEED, stake_pool.key().as_ref(), user.key().as_ref()],
+        bump = user_stake.bump,
+        constraint = user_stake.version == USER_STAKE_VERSION @ StakingError::AccountVersionMismatch,
+        constraint = user_stake.owner == user.key() @ StakingError::InvalidStakeOwner,
+        constraint = user_stake.stake_pool == stake_pool.key() @ StakingError::StakePoolMismatch
+    )]
+    pub user_stake: Account<'info, UserStake>,
+
+    /// New claim history PDA.
+    #[account(
+        init,
+        payer = user,
+        space = ClaimHistory::LEN,
+        seeds = [CLAIM_HISTORY_SEED, stake_pool.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub claim_history: Box<Account<'info, ClaimHistory>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required for reading claim history.
+#[derive(Accounts)]
+pub struct ViewClaimHistory<'info> {
+    /// The stake pool.
+    /// SECURITY: PDA validation.
+    #[account(
+        seeds = [STAKE_POOL_SEED, stake_pool.staking_mint.as_ref()],
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ StakingError::AccountVersionMismatch,
+        bump = stake_pool.bump
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+
+    /// History to read.
+    #[account(
+        constraint = claim_history.stake_pool == stake_pool.key() @ StakingError::StakePoolMismatch
+    )]
+    pub claim_history: Box<Account<'info, ClaimHistory>>,
+}
+
+/// Create the caller's claim history account.
+///
+/// # Security
+/// - Rent paid by the user, so claims stay cheap for everyone else
+/// - Records start with the next claim that passes this account
+///
+/// # Arguments
+/// * `ctx` - EnableClaimHistory accounts context
+///
+/// # Returns
+/// Result indicating success or error
+pub fn enable_handler(ctx: Context<EnableClaimHistory>) -> Result<()> {
+    let claim_history = &mut ctx.accounts.claim_history;
+
+    claim_history.owner = ctx.accounts.user.key();
+    claim_history.stake_pool = ctx.accounts.stake_pool.key();
+    claim_history.head = 0;
+    claim_history.count = 0;
+    claim_history.total_recorded = 0;
+    claim_history.bump = ctx.bumps.claim_history;
+
+    msg!("Claim history enabled for {}", claim_history.owner);
+
+    Ok(())
+}
+
+/// Return one page of claim records, oldest first.
+///
+/// # Arguments
+/// * `ctx` - ViewClaimHistory accounts context
+/// * `page` - Page index (CLAIM_HISTORY_PAGE_SIZE records per page)
+///
+/// # Returns
+/// The requested page, delivered via return data
+pub fn view_handler(ctx: Context<ViewClaimHistory>, page: u16) -> Result<ClaimHistoryPage> {
+    Ok(ctx.accounts.claim_history.page(page))
+}
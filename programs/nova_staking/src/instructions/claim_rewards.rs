@@ -3,28 +3,42 @@
 /// Handles claiming accumulated rewards without unstaking.
 ///
 /// ## Security Guarantees
-/// - Owner validation ensures only stake owner can claim
+/// - Owner validation ensures only stake owner (or their claim delegate) can claim
 /// - Treasury validation prevents fund theft
 /// - Emission cap enforcement prevents unlimited minting
+/// - Must be a top-level instruction unless the pool sets `allow_cpi`
+/// - Rewards go to the owner's token account or, when the owner passes one,
+///   an explicit `destination` for the reward mint owned by anyone
+/// - A `reward_destination` stored by the owner overrides both: every claim
+///   must pay it, whoever signs
 
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::set_return_data;
+use anchor_lang::solana_program::sysvar::instructions as sysvar_instructions;
+use anchor_spl::associated_token::AssociatedToken;
 use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
 
 use crate::constants::*;
 use crate::error::StakingError;
-use crate::instructions::stake::calculate_pending_rewards;
-use crate::state::{StakePool, UserStake};
+use crate::events::ClaimEvent;
+use crate::instructions::recovery::cancel_on_owner_signature;
+use crate::instructions::stake::{calculate_pending_rewards, update_rewards};
+use crate::state::{ClaimHistory, ClaimRecord, ReferralAccount, StakePool, UserStake};
 
 /// Accounts required for claiming rewards.
 ///
 /// ## Security Notes
-/// - User must be signer AND match user_stake.owner
+/// - User must be signer AND be user_stake.owner or its claim delegate
+/// - `user_token_account` must be the canonical ATA of the stake owner for
+///   the reward mint, whoever signs; it is created if missing
 /// - Treasury must match pool's treasury vault
 /// - Emission cap checked before transfer
+/// - `destination` is deliberately not owner-checked; only the owner may
+///   pass one, so only the owner can choose where their rewards go
 #[derive(Accounts)]
 pub struct ClaimRewards<'info> {
-    /// The user claiming rewards.
-    /// SECURITY: Must be signer and match stake owner.
+    /// The stake owner, or their claim delegate.
+    /// SECURITY: Must be signer and allowed by `UserStake::can_claim`.
     #[account(mut)]
     pub user: Signer<'info>,
 
@@ -33,6 +47,7 @@ pub struct ClaimRewards<'info> {
     #[account(
         mut,
         seeds = [STAKE_POOL_SEED, stake_pool.staking_mint.as_ref()],
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ StakingError::AccountVersionMismatch,
         bump = stake_pool.bump,
         has_one = treasury_vault @ StakingError::TreasuryMismatch,
         has_one = staking_mint @ StakingError::MintMismatch
@@ -40,12 +55,13 @@ pub struct ClaimRewards<'info> {
     pub stake_pool: Account<'info, StakePool>,
 
     /// User's stake account.
-    /// SECURITY: PDA + owner + pool validation.
+    /// SECURITY: PDA + owner/delegate + pool validation.
     #[account(
         mut,
-        seeds = [USER_STAKE_SEED, stake_pool.key().as_ref(), user.key().as_ref()],
+        seeds = [USER_STAKE_SEED, stake_pool.key().as_ref(), user_stake.owner.as_ref()],
         bump = user_stake.bump,
-        constraint = user_stake.owner == user.key() @ StakingError::InvalidStakeOwner,
+        constraint = user_stake.version == USER_STAKE_VERSION @ StakingError::AccountVersionMismatch,
+        constraint = user_stake.can_claim(&user.key()) @ StakingError::InvalidStakeOwner,
         constraint = user_stake.stake_pool == stake_pool.key() @ StakingError::StakePoolMismatch
     )]
     pub user_stake: Account<'info, UserStake>,
@@ -57,14 +73,26 @@ pub struct ClaimRewards<'info> {
     )]
     pub staking_mint: Account<'info, Mint>,
 
-    /// User's token account for receiving rewards.
-    /// SECURITY: Mint and owner validation.
+    /// The stake owner's wallet, authority of `user_token_account`.
+    /// CHECK: Only its address is used; must be user_stake.owner.
+    #[account(address = user_stake.owner @ StakingError::InvalidStakeOwner)]
+    pub owner: UncheckedAccount<'info>,
+
+    /// The reward token mint.
+    /// SECURITY: Must match pool's reward mint.
+    #[account(address = stake_pool.reward_mint @ StakingError::MintMismatch)]
+    pub reward_mint: Box<Account<'info, Mint>>,
+
+    /// Stake owner's associated token account for receiving rewards.
+    /// Created in the same transaction (rent paid by the signer) if it was closed.
+    /// SECURITY: Canonical ATA for (owner, reward_mint).
     #[account(
-        mut,
-        constraint = user_token_account.mint == staking_mint.key() @ StakingError::MintMismatch,
-        constraint = user_token_account.owner == user.key() @ StakingError::UnauthorizedStakeAccess
+        init_if_needed,
+        payer = user,
+        associated_token::mint = reward_mint,
+        associated_token::authority = owner
     )]
-    pub user_token_account: Account<'info, TokenAccount>,
+    pub user_token_account: Box<Account<'info, TokenAccount>>,
 
     /// Pool's treasury vault holding rewards.
     /// SECURITY: Must match pool's stored treasury + owner validation.
@@ -72,72 +100,224 @@ pub struct ClaimRewards<'info> {
         mut,
         constraint = treasury_vault.key() == stake_pool.treasury_vault @ StakingError::TreasuryMismatch,
         constraint = treasury_vault.owner == stake_pool.key() @ StakingError::InvalidTreasuryOwner,
-        constraint = treasury_vault.mint == staking_mint.key() @ StakingError::InvalidTokenAccountMint
+        constraint = treasury_vault.mint == stake_pool.reward_mint @ StakingError::InvalidTokenAccountMint
     )]
     pub treasury_vault: Account<'info, TokenAccount>,
 
     /// Token program.
     pub token_program: Program<'info, Token>,
+
+    /// Associated token program, for creating `user_token_account`.
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    /// Instructions sysvar, read to reject CPI calls unless the pool allows them.
+    /// CHECK: Address-checked against the sysvar id.
+    #[account(address = sysvar_instructions::ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
+
+    /// Optional claim history; the claim is recorded when provided.
+    /// SECURITY: PDA bound to (pool, stake owner).
+    #[account(
+        mut,
+        seeds = [CLAIM_HISTORY_SEED, stake_pool.key().as_ref(), user_stake.owner.as_ref()],
+        bump = claim_history.bump
+    )]
+    pub claim_history: Option<Box<Account<'info, ClaimHistory>>>,
+
+    /// Referrer's balance in this pool; required when a referral share is due.
+    /// Created by the claimer if missing.
+    /// SECURITY: PDA bound to (pool, user_stake.referrer).
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = ReferralAccount::LEN,
+        seeds = [REFERRAL_SEED, stake_pool.key().as_ref(), user_stake.referrer.as_ref()],
+        bump
+    )]
+    pub referral_account: Option<Box<Account<'info, ReferralAccount>>>,
+
+    /// System program, for creating `user_token_account` and the referral account.
+    pub system_program: Program<'info, System>,
+
+    /// Pool fee vault; required while a claim fee is set.
+    /// SECURITY: Must match the fee vault recorded on the pool.
+    #[account(
+        mut,
+        address = stake_pool.fee_vault @ StakingError::FeeVaultMismatch
+    )]
+    pub fee_vault: Option<Box<Account<'info, TokenAccount>>>,
+
+    /// Optional token account receiving the net payout instead of
+    /// `user_token_account`, e.g. a hot wallet's ATA.
+    /// SECURITY: Reward mint validation only; any owner is accepted.
+    #[account(
+        mut,
+        constraint = destination.mint == stake_pool.reward_mint @ StakingError::MintMismatch
+    )]
+    pub destination: Option<Box<Account<'info, TokenAccount>>>,
 }
 
 /// Claim accumulated rewards.
 ///
 /// # Security
-/// - Validates signer is stake owner
-/// - Checks treasury has sufficient funds
-/// - Enforces emission cap
+/// - Validates signer is stake owner or its claim delegate; a delegate's
+///   claim always pays the owner's `user_token_account`
+/// - Enforces the pool-wide claim rate limit
+/// - Enforces the pool's minimum interval since the position's last
+///   settlement; the remaining wait is written to return data on failure
+/// - Enforces the tier's reward cliff for open positions
+/// - Pays `min(claimable, remaining emission cap, liquid treasury)`; the
+///   unpaid remainder stays in `pending_rewards`, and the claim only fails
+///   when nothing at all is payable
+/// - Enforces emission cap (streak bonus, referral share and gross fee included)
 /// - Uses checked math throughout
 /// - PDA signer for treasury transfer
+/// - Net payout goes to `destination` when provided, else `user_token_account`;
+///   a stored `reward_destination` must be the `destination` passed
 ///
 /// # Arguments
 /// * `ctx` - ClaimRewards accounts context
+/// * `min_expected` - Optional floor on the net payout; the claim fails below it
 ///
 /// # Returns
-/// Result indicating success or error
-pub fn handler(ctx: Context<ClaimRewards>) -> Result<()> {
+/// Result indicating success or error; the net payout is written to return data
+pub fn handler(ctx: Context<ClaimRewards>, min_expected: Option<u64>) -> Result<()> {
     let user_stake = &ctx.accounts.user_stake;
     let stake_pool = &ctx.accounts.stake_pool;
     let treasury_vault = &ctx.accounts.treasury_vault;
     let clock = Clock::get()?;
 
-    // === TIMESTAMP VALIDATION ===
-    require!(clock.unix_timestamp > 0, StakingError::InvalidTimestamp);
+    stake_pool.require_top_level(&ctx.accounts.instructions_sysvar)?;
 
-    // === CALCULATE REWARDS ===
-    
-    // Calculate newly accrued rewards (uses checked math internally)
-    let newly_accrued = calculate_pending_rewards(user_stake, stake_pool, clock.unix_timestamp)?;
+    // Emergency mode blocks everything except principal exits
+    require!(!stake_pool.emergency, StakingError::EmergencyMode);
+    require!(!stake_pool.is_claim_paused(), StakingError::ClaimsPaused);
 
-    // Total claimable = stored pending + newly accrued
-    let total_claimable = user_stake
-        .pending_rewards
-        .checked_add(newly_accrued)
-        .ok_or(StakingError::MathOverflow)?;
+    // A stored preference decides where every claim pays; otherwise only
+    // the owner may send rewards anywhere but their own account
+    let is_delegate = ctx.accounts.user.key() != user_stake.owner;
+    let passed_destination = ctx.accounts.destination.as_ref().map(|destination| destination.key());
+    match user_stake.reward_destination() {
+        Some(stored) => require!(
+            passed_destination == Some(stored),
+            StakingError::RewardDestinationMismatch
+        ),
+        None => require!(
+            !is_delegate || passed_destination.is_none(),
+            StakingError::DelegateCannotRedirect
+        ),
+    }
 
-    // === CLAIM VALIDATION ===
-    
-    // Validate there are rewards to claim
-    require!(total_claimable > 0, StakingError::NoRewardsAvailable);
+    // === TIMING ===
+    require_claim_timing(user_stake, stake_pool, clock.unix_timestamp)?;
+
+    // === CALCULATE REWARDS ===
 
-    // Check treasury has sufficient funds
-    require!(
-        treasury_vault.amount >= total_claimable,
-        StakingError::InsufficientTreasuryFunds
-    );
+    let quote = quote_claim(user_stake, stake_pool, treasury_vault.amount, clock.unix_timestamp)?;
+    let ClaimQuote {
+        payable,
+        deferred,
+        released_boost,
+        emission_cap,
+        epoch_headroom,
+        available_treasury,
+        ..
+    } = quote;
 
     // === EMISSION CAP ENFORCEMENT ===
     
     // Calculate new total distributed
     let new_total_distributed = stake_pool
         .total_distributed
-        .checked_add(total_claimable)
+        .checked_add(payable)
         .ok_or(StakingError::MathOverflow)?;
-    
-    // Enforce emission cap
-    require!(
-        new_total_distributed <= stake_pool.emission_cap,
-        StakingError::EmissionCapExceeded
-    );
+
+    // === CLAIM STREAK ===
+
+    // The first claim in each new window extends the streak; a missed
+    // window restarts it at zero
+    let streak_enabled = stake_pool.streak_enabled();
+    let (streak_count, streak_deadline) = if streak_enabled {
+        stake_pool.next_streak(
+            user_stake.streak_count,
+            user_stake.streak_deadline,
+            clock.unix_timestamp,
+        )?
+    } else {
+        (user_stake.streak_count, user_stake.streak_deadline)
+    };
+
+    // Bonus multiplies the payout, limited by what treasury and emission cap have left
+    let streak_bonus = if streak_enabled {
+        let bonus = (payable as u128)
+            .checked_mul(stake_pool.streak_bonus_bps(streak_count) as u128)
+            .ok_or(StakingError::MathOverflow)?
+            .checked_div(BASIS_POINTS_DENOMINATOR as u128)
+            .ok_or(StakingError::DivisionByZero)?;
+        let bonus = u64::try_from(bonus).map_err(|_| StakingError::ConversionOverflow)?;
+        bonus
+            .min(emission_cap.saturating_sub(new_total_distributed))
+            .min(epoch_headroom.saturating_sub(payable))
+            .min(available_treasury.saturating_sub(payable))
+    } else {
+        0
+    };
+
+    let payout = payable
+        .checked_add(streak_bonus)
+        .ok_or(StakingError::MathOverflow)?;
+    let new_total_distributed = new_total_distributed
+        .checked_add(streak_bonus)
+        .ok_or(StakingError::MathOverflow)?;
+
+    // === REFERRAL SHARE ===
+
+    // Referrers earn a share on top of the payout, never deducted from it;
+    // it is credited to their referral account and withdrawn separately
+    let pays_referral = user_stake.referrer != Pubkey::default() && stake_pool.referral_share_bps > 0;
+    if pays_referral {
+        require!(
+            ctx.accounts.referral_account.is_some(),
+            StakingError::MissingReferralAccounts
+        );
+    }
+
+    let referral_share = if pays_referral {
+        let share = (payout as u128)
+            .checked_mul(stake_pool.referral_share_bps as u128)
+            .ok_or(StakingError::MathOverflow)?
+            .checked_div(BASIS_POINTS_DENOMINATOR as u128)
+            .ok_or(StakingError::DivisionByZero)?;
+        let share = u64::try_from(share).map_err(|_| StakingError::ConversionOverflow)?;
+        share
+            .min(emission_cap.saturating_sub(new_total_distributed))
+            .min(epoch_headroom.saturating_sub(payout))
+            .min(available_treasury.saturating_sub(payout))
+    } else {
+        0
+    };
+
+    let new_total_distributed = new_total_distributed
+        .checked_add(referral_share)
+        .ok_or(StakingError::MathOverflow)?;
+
+    // === CLAIM FEE ===
+
+    // The gross payout counts against the cap; the fee is skimmed from it
+    let (net_payout, claim_fee) = stake_pool.split_claim_fee(payout)?;
+    if claim_fee > 0 {
+        require!(ctx.accounts.fee_vault.is_some(), StakingError::MissingFeeVault);
+    }
+
+    // === PAYOUT GUARD ===
+
+    // Fail rather than pay less than the caller pre-computed
+    if let Some(min_expected) = min_expected {
+        if net_payout < min_expected {
+            msg!("Payout {} below expected {}", net_payout, min_expected);
+            return err!(StakingError::PayoutBelowExpected);
+        }
+    }
 
     // === PDA SIGNER TRANSFER ===
     
@@ -150,39 +330,334 @@ pub fn handler(ctx: Context<ClaimRewards>) -> Result<()> {
     ];
     let signer_seeds = &[&seeds[..]];
 
-    // Transfer rewards from treasury to user
+    // Transfer rewards from treasury to the chosen destination
+    let destination = match ctx.accounts.destination.as_ref() {
+        Some(destination) => destination.to_account_info(),
+        None => ctx.accounts.user_token_account.to_account_info(),
+    };
     let cpi_accounts = Transfer {
         from: ctx.accounts.treasury_vault.to_account_info(),
-        to: ctx.accounts.user_token_account.to_account_info(),
+        to: destination.clone(),
         authority: ctx.accounts.stake_pool.to_account_info(),
     };
     let cpi_program = ctx.accounts.token_program.to_account_info();
     let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
-    token::transfer(cpi_ctx, total_claimable)?;
+    token::transfer(cpi_ctx, net_payout)?;
+
+    // Transfer claim fee from treasury to fee vault
+    if claim_fee > 0 {
+        if let Some(fee_vault) = ctx.accounts.fee_vault.as_ref() {
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.treasury_vault.to_account_info(),
+                to: fee_vault.to_account_info(),
+                authority: ctx.accounts.stake_pool.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+            token::transfer(cpi_ctx, claim_fee)?;
+        }
+    }
 
     // === STATE UPDATE ===
     
     let user_stake = &mut ctx.accounts.user_stake;
     let stake_pool = &mut ctx.accounts.stake_pool;
 
-    // Reset pending rewards
-    user_stake.pending_rewards = 0;
-    user_stake.last_claim_time = clock.unix_timestamp;
+    let emitted = new_total_distributed
+        .checked_sub(stake_pool.total_distributed)
+        .ok_or(StakingError::MathUnderflow)?;
+    settle_claim(stake_pool, user_stake, &quote, emitted, clock.unix_timestamp)?;
+    if released_boost > 0 {
+        msg!("Released {} boost-locked reward tokens", released_boost);
+    }
     
     // Update total rewards claimed (checked add)
     user_stake.total_rewards_claimed = user_stake
         .total_rewards_claimed
-        .checked_add(total_claimable)
+        .checked_add(net_payout)
         .ok_or(StakingError::MathOverflow)?;
 
-    // Update pool distribution total
-    stake_pool.total_distributed = new_total_distributed;
+    if streak_enabled {
+        user_stake.streak_count = streak_count;
+        user_stake.streak_deadline = streak_deadline;
+    }
+
     stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+    if !is_delegate {
+        cancel_on_owner_signature(stake_pool, user_stake, clock.unix_timestamp);
+    }
 
-    msg!("Claimed {} reward tokens", total_claimable);
+    if let Some(referral_account) = ctx.accounts.referral_account.as_mut() {
+        if referral_account.referrer == Pubkey::default() {
+            referral_account.referrer = user_stake.referrer;
+            referral_account.stake_pool = stake_pool.key();
+            referral_account.bump = ctx.bumps.referral_account;
+        }
+        referral_account.lifetime_earnings = referral_account
+            .lifetime_earnings
+            .checked_add(referral_share)
+            .ok_or(StakingError::MathOverflow)?;
+        referral_account.unclaimed = referral_account
+            .unclaimed
+            .checked_add(referral_share)
+            .ok_or(StakingError::MathOverflow)?;
+        stake_pool.referral_liability = stake_pool
+            .referral_liability
+            .checked_add(referral_share)
+            .ok_or(StakingError::MathOverflow)?;
+    }
+
+    if let Some(claim_history) = ctx.accounts.claim_history.as_mut() {
+        claim_history.push(ClaimRecord {
+            timestamp: clock.unix_timestamp,
+            amount: net_payout,
+            running_total: user_stake.total_rewards_claimed,
+        });
+    }
+
+    // Record when a claim drains the treasury so funded-only accrual can pause
+    ctx.accounts.treasury_vault.reload()?;
+    if ctx.accounts.treasury_vault.amount == 0
+        && stake_pool.treasury_deployed == 0
+        && stake_pool.treasury_empty_since.is_none()
+    {
+        stake_pool.treasury_empty_since = Some(clock.unix_timestamp);
+        msg!("Treasury drained to zero");
+    }
+
+    stake_pool.record_lifetime_claim()?;
+
+    emit!(ClaimEvent {
+        stake_pool: stake_pool.key(),
+        user: user_stake.owner,
+        tier: user_stake.tier as u8,
+        gross: payout,
+        fee: claim_fee,
+        net: net_payout,
+        fee_bps: stake_pool.claim_fee_bps,
+        referral_share,
+        deferred,
+        destination: *destination.key,
+        total_claimed: user_stake.total_rewards_claimed,
+        total_distributed: stake_pool.total_distributed,
+        claims_count: stake_pool.cumulative_claims_count,
+        label: user_stake.label,
+        sequence: stake_pool.sequence,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!("Claimed {} reward tokens", net_payout);
+    if is_delegate {
+        msg!("Claimed by delegate {}", ctx.accounts.user.key());
+    }
+    if deferred > 0 {
+        msg!("Deferred {} reward tokens (kept as pending rewards)", deferred);
+    }
+    if ctx.accounts.destination.is_some() {
+        msg!("Rewards sent to: {}", destination.key);
+    }
+    if claim_fee > 0 {
+        msg!("Claim fee: {} ({}bp)", claim_fee, stake_pool.claim_fee_bps);
+    }
+    if streak_enabled {
+        msg!("Claim streak: {} (bonus: {})", user_stake.streak_count, streak_bonus);
+    }
+    if pays_referral {
+        msg!("Referral share: {} credited to {}", referral_share, user_stake.referrer);
+    }
     msg!("Total rewards claimed by user: {}", user_stake.total_rewards_claimed);
     msg!("Total distributed from pool: {}", stake_pool.total_distributed);
     msg!("Remaining emission cap: {}", stake_pool.emission_cap.saturating_sub(stake_pool.total_distributed));
 
+    set_return_data(&net_payout.to_le_bytes());
+
+    Ok(())
+}
+
+/// What a claim or compound at `now` can settle and pay, from
+/// `quote_claim`.
+#[derive(Clone, Copy, Debug)]
+pub struct ClaimQuote {
+    /// Stored pending rewards plus new accrual, released boost and donations.
+    pub total_claimable: u64,
+    /// Part of `total_claimable` paid now.
+    pub payable: u64,
+    /// Part of `total_claimable` left in `pending_rewards`.
+    pub deferred: u64,
+    /// Boost-locked rewards whose lock has expired, included above.
+    pub released_boost: u64,
+    /// Emission cap net of what other positions' bonuses reserve.
+    pub emission_cap: u64,
+    /// What this epoch's emission budget has left.
+    pub epoch_headroom: u64,
+    /// Treasury balance net of reserved bonuses and referral shares.
+    pub available_treasury: u64,
+}
+
+/// Fail unless the position may settle rewards through a claim now: the
+/// pool-wide claim rate limit, the minimum interval since the position's
+/// last settlement and the tier's reward cliff. Shared with
+/// `compound_rewards`.
+///
+/// The interval is measured from the last settlement, so stake, unstake and
+/// the other instructions that settle rewards are never held back by it; on
+/// failure the remaining wait is written to return data.
+pub fn require_claim_timing(user_stake: &UserStake, stake_pool: &StakePool, now: i64) -> Result<()> {
+    require!(now > 0, StakingError::InvalidTimestamp);
+    require!(stake_pool.is_claim_allowed(now), StakingError::ClaimRateLimited);
+
+    let wait = stake_pool.claim_interval_remaining(user_stake.last_claim_time, now);
+    if wait > 0 {
+        set_return_data(&wait.to_le_bytes());
+        msg!("Next claim allowed in {}s", wait);
+        return err!(StakingError::ClaimTooSoon);
+    }
+
+    // Accrual runs from day one, but open positions cannot claim until the cliff
+    let reward_cliff = stake_pool.get_reward_cliff_for_tier(user_stake.tier);
+    if !user_stake.is_reward_cliff_passed(now, reward_cliff) {
+        msg!(
+            "Rewards claimable from {}",
+            user_stake.stake_start_time.saturating_add(reward_cliff)
+        );
+        return err!(StakingError::RewardCliffNotReached);
+    }
+    Ok(())
+}
+
+/// Price a claim or compound at `now` against a treasury holding
+/// `treasury_balance`. Shared with `compound_rewards`.
+///
+/// Pays as much as the emission cap, this epoch's emission budget and the
+/// liquid treasury allow; the rest is deferred to a later claim. Fails only
+/// when nothing is claimable or nothing at all is payable.
+pub fn quote_claim(
+    user_stake: &UserStake,
+    stake_pool: &StakePool,
+    treasury_balance: u64,
+    now: i64,
+) -> Result<ClaimQuote> {
+    if stake_pool.is_accrual_clamped(user_stake.last_claim_time, now) {
+        msg!(
+            "Accrual clamped to horizon of {}s",
+            stake_pool.max_accrual_horizon_secs
+        );
+    }
+
+    let newly_accrued = calculate_pending_rewards(user_stake, stake_pool, now)?;
+
+    // Expired boost-locked rewards return to the claimable balance
+    let released_boost = user_stake.releasable_boost(now);
+
+    // Donations since the position last settled
+    let donated = user_stake.pending_donation(stake_pool.bonus_per_token)?;
+
+    let total_claimable = user_stake
+        .pending_rewards
+        .checked_add(newly_accrued)
+        .ok_or(StakingError::MathOverflow)?
+        .checked_add(released_boost)
+        .ok_or(StakingError::MathOverflow)?
+        .checked_add(donated)
+        .ok_or(StakingError::MathOverflow)?;
+    require!(total_claimable > 0, StakingError::NoRewardsAvailable);
+
+    // Bonuses credited to other positions and their unsettled donations stay
+    // reserved in the treasury and against the emission cap until their
+    // owners claim; credited referral shares are already distributed, so
+    // only the treasury holds them
+    let reserved_for_bonuses = stake_pool
+        .bonus_liability
+        .saturating_sub(user_stake.bonus_rewards)
+        .saturating_add(stake_pool.donation_liability.saturating_sub(donated));
+    let available_treasury = treasury_balance
+        .saturating_sub(reserved_for_bonuses)
+        .saturating_sub(stake_pool.referral_liability);
+    let emission_cap = stake_pool.emission_cap.saturating_sub(reserved_for_bonuses);
+
+    let cap_headroom = emission_cap.saturating_sub(stake_pool.total_distributed);
+    let epoch_headroom = stake_pool.epoch_emission_headroom(now);
+    let payable = total_claimable
+        .min(cap_headroom)
+        .min(epoch_headroom)
+        .min(available_treasury);
+
+    if payable == 0 {
+        if cap_headroom == 0 {
+            return err!(StakingError::EmissionCapExceeded);
+        }
+        if epoch_headroom == 0 {
+            msg!(
+                "Epoch emission budget resets at {}",
+                stake_pool
+                    .current_epoch_start
+                    .saturating_add(stake_pool.epoch_length_seconds)
+            );
+            return err!(StakingError::EpochEmissionExceeded);
+        }
+        // Deployed funds back the treasury but cannot pay until recalled
+        if stake_pool.treasury_deployed > 0 {
+            return err!(StakingError::TreasuryIlliquid);
+        }
+        return err!(StakingError::InsufficientTreasuryFunds);
+    }
+
+    Ok(ClaimQuote {
+        total_claimable,
+        payable,
+        deferred: total_claimable - payable,
+        released_boost,
+        emission_cap,
+        epoch_headroom,
+        available_treasury,
+    })
+}
+
+/// Settle a claim or compound priced by `quote` that emitted `emitted`
+/// tokens in all (the payout plus any streak bonus or referral share).
+/// Shared with `compound_rewards`.
+///
+/// Runs after the transfers and before any balance changes. Pending rewards
+/// drop to the deferred remainder, with credited bonus counted as paid
+/// first, and `emitted` is added to `total_distributed`.
+pub fn settle_claim(
+    stake_pool: &mut StakePool,
+    user_stake: &mut UserStake,
+    quote: &ClaimQuote,
+    emitted: u64,
+    now: i64,
+) -> Result<()> {
+    // Settle the reward-per-token accumulator before balances change
+    update_rewards(stake_pool, user_stake, now)?;
+
+    if stake_pool.max_claims_per_epoch > 0 {
+        stake_pool.record_claim(now)?;
+    }
+    if stake_pool.max_emission_per_epoch > 0 {
+        stake_pool.record_epoch_emission(now, emitted)?;
+    }
+
+    stake_pool.pending_liability = stake_pool
+        .pending_liability
+        .saturating_sub(user_stake.pending_rewards)
+        .saturating_sub(quote.released_boost)
+        .checked_add(quote.deferred)
+        .ok_or(StakingError::MathOverflow)?;
+    user_stake.pending_rewards = quote.deferred;
+    let bonus_paid = user_stake.bonus_rewards.min(quote.payable);
+    stake_pool.bonus_liability = stake_pool.bonus_liability.saturating_sub(bonus_paid);
+    user_stake.bonus_rewards = user_stake.bonus_rewards.saturating_sub(bonus_paid);
+    user_stake.mark_settled(stake_pool, now);
+
+    if quote.released_boost > 0 {
+        user_stake.boosted_amount = 0;
+        user_stake.boost_until = 0;
+    }
+
+    stake_pool.total_distributed = stake_pool
+        .total_distributed
+        .checked_add(emitted)
+        .ok_or(StakingError::MathOverflow)?;
     Ok(())
 }
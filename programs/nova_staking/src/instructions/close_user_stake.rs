@@ -0,0 +1,99 @@
+/// Close user stake instruction handler.
+///
+/// Closes a fully exited position and returns its rent to the owner.
+///
+/// ## Security Guarantees
+/// - Only the position owner can close it, and the rent goes back to them
+/// - Refused while any principal or reward is still owed to the position
+/// - Pool totals are untouched; `unstake` already removed the staker
+
+use anchor_lang::prelude::*;
+
+use crate::constants::*;
+use crate::error::StakingError;
+use crate::instructions::stake::calculate_pending_rewards;
+use crate::state::{StakePool, UserStake};
+
+/// Accounts required for closing a stake position.
+///
+/// ## Security Notes
+/// - User must be signer AND match user_stake.owner
+/// - Rent is returned to the signer via `close = user`
+#[derive(Accounts)]
+pub struct CloseUserStake<'info> {
+    /// The position owner; receives the rent.
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// The stake pool; writable for `sequence`.
+    /// SECURITY: PDA validation.
+    #[account(
+        mut,
+        seeds = [STAKE_POOL_SEED, stake_pool.staking_mint.as_ref()],
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ StakingError::AccountVersionMismatch,
+        bump = stake_pool.bump
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+
+    /// User's stake account, closed to the owner.
+    /// SECURITY: PDA + owner + pool validation.
+    #[account(
+        mut,
+        close = user,
+        seeds = [USER_STAKE_SEED, stake_pool.key().as_ref(), user.key().as_ref()],
+        bump = user_stake.bump,
+        constraint = user_stake.version == USER_STAKE_VERSION @ StakingError::AccountVersionMismatch,
+        constraint = user_stake.owner == user.key() @ StakingError::InvalidStakeOwner,
+        constraint = user_stake.stake_pool == stake_pool.key() @ StakingError::StakePoolMismatch
+    )]
+    pub user_stake: Account<'info, UserStake>,
+}
+
+/// Close a fully exited stake position.
+///
+/// # Security
+/// - Requires no principal (staked or queued), no pending or newly accrued rewards, no
+///   boost-locked rewards and no uncredited bonus
+/// - Does not touch `staker_count`, which `unstake` decrements when the
+///   balance reaches zero
+/// - Staking again re-creates a fresh account through `init_if_needed`
+///
+/// # Arguments
+/// * `ctx` - CloseUserStake accounts context
+///
+/// # Returns
+/// Result indicating success or error
+pub fn handler(ctx: Context<CloseUserStake>) -> Result<()> {
+    let user_stake = &ctx.accounts.user_stake;
+    let clock = Clock::get()?;
+
+    require_fully_exited(user_stake, &ctx.accounts.stake_pool, clock.unix_timestamp)?;
+    ctx.accounts.stake_pool.next_sequence()?;
+
+    msg!("Closed stake position of {}", user_stake.owner);
+    msg!("Lifetime rewards claimed: {}", user_stake.total_rewards_claimed);
+
+    Ok(())
+}
+
+/// Fail unless the position holds nothing: no principal (staked or queued),
+/// no pending or newly accrued rewards, no boost-locked rewards, no
+/// uncredited bonus and nothing held back by the emission cap. Shared with
+/// `gc_user_stake`.
+pub fn require_fully_exited(user_stake: &UserStake, stake_pool: &StakePool, now: i64) -> Result<()> {
+    require!(
+        user_stake.staked_amount == 0 && !user_stake.has_pending_unstake(),
+        StakingError::PositionNotEmpty
+    );
+
+    let newly_accrued = calculate_pending_rewards(user_stake, stake_pool, now)?;
+    require!(
+        user_stake.pending_rewards == 0
+            && newly_accrued == 0
+            && user_stake.boosted_amount == 0
+            && user_stake.bonus_rewards == 0
+            && user_stake.over_cap_rewards == 0,
+        StakingError::RewardsOutstanding
+    );
+    Ok(())
+}
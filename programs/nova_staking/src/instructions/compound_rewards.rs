@@ -0,0 +1,288 @@
+/// Compound rewards instruction handler.
+///
+/// Restakes accumulated rewards into the position in one transaction,
+/// moving them from the treasury straight into the staking vault.
+///
+/// ## Security Guarantees
+/// - Owner validation ensures only stake owner can compound
+/// - Timing checks, claimable amount, partial payout and settlement are
+///   shared with `claim_rewards`
+/// - The compounded amount joins the running lock; it never shortens it
+/// - In receipt mode the compounded amount mints receipts like a stake
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::set_return_data;
+use anchor_lang::solana_program::sysvar::instructions as sysvar_instructions;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+
+use crate::constants::*;
+use crate::error::StakingError;
+use crate::events::CompoundEvent;
+use crate::instructions::receipt::mint_receipts;
+use crate::instructions::recovery::cancel_on_owner_signature;
+use crate::instructions::claim_rewards::{quote_claim, require_claim_timing, settle_claim};
+use crate::state::{StakePool, UserStake};
+
+/// Accounts required for compounding rewards.
+///
+/// ## Security Notes
+/// - User must be signer AND match user_stake.owner
+/// - Treasury and staking vault must match the pool's stored vaults
+#[derive(Accounts)]
+pub struct CompoundRewards<'info> {
+    /// The user compounding rewards.
+    /// SECURITY: Must be signer and match stake owner.
+    pub user: Signer<'info>,
+
+    /// The stake pool.
+    /// SECURITY: PDA + has_one validations.
+    #[account(
+        mut,
+        seeds = [STAKE_POOL_SEED, stake_pool.staking_mint.as_ref()],
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ StakingError::AccountVersionMismatch,
+        bump = stake_pool.bump,
+        has_one = treasury_vault @ StakingError::TreasuryMismatch,
+        has_one = staking_vault @ StakingError::VaultMismatch,
+        has_one = staking_mint @ StakingError::MintMismatch
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+
+    /// User's stake account.
+    /// SECURITY: PDA + owner + pool validation.
+    #[account(
+        mut,
+        seeds = [USER_STAKE_SEED, stake_pool.key().as_ref(), user.key().as_ref()],
+        bump = user_stake.bump,
+        constraint = user_stake.version == USER_STAKE_VERSION @ StakingError::AccountVersionMismatch,
+        constraint = user_stake.owner == user.key() @ StakingError::InvalidStakeOwner,
+        constraint = user_stake.stake_pool == stake_pool.key() @ StakingError::StakePoolMismatch
+    )]
+    pub user_stake: Account<'info, UserStake>,
+
+    /// The staking token mint.
+    /// SECURITY: Must match pool's locked mint.
+    #[account(
+        constraint = staking_mint.key() == stake_pool.staking_mint @ StakingError::MintMismatch
+    )]
+    pub staking_mint: Account<'info, Mint>,
+
+    /// Pool's staking vault receiving the compounded rewards.
+    /// SECURITY: Must match pool's stored vault address.
+    #[account(
+        mut,
+        constraint = staking_vault.key() == stake_pool.staking_vault @ StakingError::VaultMismatch,
+        constraint = staking_vault.owner == stake_pool.key() @ StakingError::InvalidVaultOwner,
+        constraint = staking_vault.mint == staking_mint.key() @ StakingError::InvalidTokenAccountMint
+    )]
+    pub staking_vault: Account<'info, TokenAccount>,
+
+    /// Pool's treasury vault holding rewards.
+    /// SECURITY: Must match pool's stored treasury + owner validation.
+    #[account(
+        mut,
+        constraint = treasury_vault.key() == stake_pool.treasury_vault @ StakingError::TreasuryMismatch,
+        constraint = treasury_vault.owner == stake_pool.key() @ StakingError::InvalidTreasuryOwner,
+        constraint = treasury_vault.mint == stake_pool.reward_mint @ StakingError::InvalidTokenAccountMint
+    )]
+    pub treasury_vault: Account<'info, TokenAccount>,
+
+    /// Token program.
+    pub token_program: Program<'info, Token>,
+
+    /// Instructions sysvar, read to reject CPI calls unless the pool allows them.
+    /// CHECK: Address-checked against the sysvar id.
+    #[account(address = sysvar_instructions::ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
+
+    /// Pool fee vault; required while a claim fee is set.
+    /// SECURITY: Must match the fee vault recorded on the pool.
+    #[account(
+        mut,
+        address = stake_pool.fee_vault @ StakingError::FeeVaultMismatch
+    )]
+    pub fee_vault: Option<Box<Account<'info, TokenAccount>>>,
+
+    /// Receipt mint; required in receipt mode.
+    /// SECURITY: Must match the pool's receipt mint.
+    #[account(
+        mut,
+        address = stake_pool.receipt_mint @ StakingError::MintMismatch
+    )]
+    pub receipt_mint: Option<Box<Account<'info, Mint>>>,
+
+    /// Receives receipts for the compounded amount; required in receipt mode.
+    /// SECURITY: Receipt mint and owner validation.
+    #[account(
+        mut,
+        constraint = receipt_account.mint == stake_pool.receipt_mint @ StakingError::MintMismatch,
+        constraint = receipt_account.owner == user.key() @ StakingError::UnauthorizedStakeAccess
+    )]
+    pub receipt_account: Option<Box<Account<'info, TokenAccount>>>,
+}
+
+/// Restake accumulated rewards into the position.
+///
+/// Claimable rewards are priced and settled as in `claim_rewards` and pay
+/// the same claim fee, but the net amount is added to the stake instead of
+/// being sent to the user. Streak bonuses and referral shares are
+/// claim-only and are not paid here.
+///
+/// # Security
+/// - Validates signer is stake owner
+/// - Requires an open position, unpaused claims and an unpaused pool and tier
+/// - Single-token pools only
+/// - Enforces the claim rate limit, the minimum claim interval and the
+///   tier's reward cliff
+/// - Compounds `min(claimable, remaining emission cap, epoch budget, liquid
+///   treasury)`; the rest stays in `pending_rewards`, and the call only
+///   fails when nothing at all is payable
+/// - Uses checked math throughout
+///
+/// # Arguments
+/// * `ctx` - CompoundRewards accounts context
+///
+/// # Returns
+/// Result indicating success or error; the compounded amount is written to return data
+pub fn handler(ctx: Context<CompoundRewards>) -> Result<()> {
+    ctx.accounts
+        .stake_pool
+        .require_top_level(&ctx.accounts.instructions_sysvar)?;
+
+    // An expired `set_paused` pause is lifted before the pause checks
+    ctx.accounts.stake_pool.expire_pause(Clock::get()?.unix_timestamp)?;
+    let user_stake = &ctx.accounts.user_stake;
+    let stake_pool = &ctx.accounts.stake_pool;
+    let treasury_vault = &ctx.accounts.treasury_vault;
+    let clock = Clock::get()?;
+
+    // Emergency mode blocks everything except principal exits
+    require!(!stake_pool.emergency, StakingError::EmergencyMode);
+
+    // Rewards in another token cannot be added to the stake
+    require!(!stake_pool.is_dual_token(), StakingError::SingleTokenPoolOnly);
+
+    // Compounding is a claim and a stake, so it follows both pauses
+    require!(!stake_pool.is_claim_paused(), StakingError::ClaimsPaused);
+    require!(
+        stake_pool.is_accepting_stakes(user_stake.tier),
+        StakingError::StakingPaused
+    );
+
+    require!(!stake_pool.has_ended(clock.unix_timestamp), StakingError::PoolEnded);
+
+    require!(
+        user_stake.is_active && user_stake.staked_amount > 0,
+        StakingError::NoActiveStake
+    );
+
+    require_claim_timing(user_stake, stake_pool, clock.unix_timestamp)?;
+
+    // === CALCULATE REWARDS ===
+
+    let quote = quote_claim(user_stake, stake_pool, treasury_vault.amount, clock.unix_timestamp)?;
+
+    // The claim fee applies, so compounding is not a way around it
+    let (compounded, claim_fee) = stake_pool.split_claim_fee(quote.payable)?;
+    if claim_fee > 0 {
+        require!(ctx.accounts.fee_vault.is_some(), StakingError::MissingFeeVault);
+    }
+
+    // === PDA SIGNER TRANSFER ===
+
+    let staking_mint_key = stake_pool.staking_mint;
+    let seeds = &[
+        STAKE_POOL_SEED,
+        staking_mint_key.as_ref(),
+        &[stake_pool.bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+
+    // Move rewards from the treasury straight into the staking vault
+    let cpi_accounts = Transfer {
+        from: ctx.accounts.treasury_vault.to_account_info(),
+        to: ctx.accounts.staking_vault.to_account_info(),
+        authority: ctx.accounts.stake_pool.to_account_info(),
+    };
+    let cpi_program = ctx.accounts.token_program.to_account_info();
+    let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+    token::transfer(cpi_ctx, compounded)?;
+
+    if claim_fee > 0 {
+        if let Some(fee_vault) = ctx.accounts.fee_vault.as_ref() {
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.treasury_vault.to_account_info(),
+                to: fee_vault.to_account_info(),
+                authority: ctx.accounts.stake_pool.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+            token::transfer(cpi_ctx, claim_fee)?;
+        }
+    }
+
+    mint_receipts(
+        &ctx.accounts.stake_pool,
+        ctx.accounts.receipt_mint.as_deref(),
+        ctx.accounts.receipt_account.as_deref(),
+        &ctx.accounts.token_program,
+        compounded,
+    )?;
+
+    // === STATE UPDATE ===
+
+    let user_stake = &mut ctx.accounts.user_stake;
+    let stake_pool = &mut ctx.accounts.stake_pool;
+
+    // Rewards are settled up to now, so the larger stake accrues from here on
+    settle_claim(stake_pool, user_stake, &quote, quote.payable, clock.unix_timestamp)?;
+
+    user_stake.total_rewards_claimed = user_stake
+        .total_rewards_claimed
+        .checked_add(compounded)
+        .ok_or(StakingError::MathOverflow)?;
+    user_stake.staked_amount = user_stake
+        .staked_amount
+        .checked_add(compounded)
+        .ok_or(StakingError::MathOverflow)?;
+    user_stake.sync_bonus_debt(stake_pool.bonus_per_token)?;
+
+    stake_pool.add_staked(user_stake.tier, compounded)?;
+    stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+    cancel_on_owner_signature(stake_pool, user_stake, clock.unix_timestamp);
+
+    ctx.accounts.treasury_vault.reload()?;
+    if ctx.accounts.treasury_vault.amount == 0
+        && stake_pool.treasury_deployed == 0
+        && stake_pool.treasury_empty_since.is_none()
+    {
+        stake_pool.treasury_empty_since = Some(clock.unix_timestamp);
+        msg!("Treasury drained to zero");
+    }
+
+    emit!(CompoundEvent {
+        stake_pool: stake_pool.key(),
+        user: user_stake.owner,
+        tier: user_stake.tier as u8,
+        gross: quote.payable,
+        fee: claim_fee,
+        compounded,
+        staked_after: user_stake.staked_amount,
+        total_staked: stake_pool.total_staked,
+        sequence: stake_pool.sequence,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!("Compounded {} reward tokens", compounded);
+    if quote.deferred > 0 {
+        msg!("Deferred {} reward tokens (kept as pending rewards)", quote.deferred);
+    }
+    if claim_fee > 0 {
+        msg!("Claim fee: {} ({}bp)", claim_fee, stake_pool.claim_fee_bps);
+    }
+    msg!("Total staked by user: {}", user_stake.staked_amount);
+
+    set_return_data(&compounded.to_le_bytes());
+
+    Ok(())
+}
@@ -0,0 +1,154 @@
+/// Credit bonus instruction handler.
+///
+/// Lets the authority push a flat bonus into the pending rewards of many
+/// positions at once, passed as `UserStake` accounts in `remaining_accounts`.
+///
+/// ## Security Guarantees
+/// - Only pool.authority can credit bonuses
+/// - Every account must be an active position of this pool, listed once
+/// - Credited bonuses are reserved against the emission cap and treasury
+///   (`bonus_liability`) until they are claimed
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::TokenAccount;
+
+use crate::constants::*;
+use crate::error::StakingError;
+use crate::events::BonusCredited;
+use crate::state::{StakePool, UserStake};
+
+/// Accounts required for crediting bonuses.
+///
+/// ## Security Notes
+/// - Authority must be signer AND match pool.authority
+/// - Treasury is read to check the bonuses stay covered
+#[derive(Accounts)]
+pub struct CreditBonus<'info> {
+    /// The admin authority.
+    /// SECURITY: Must be signer AND match pool.authority.
+    #[account(
+        mut,
+        constraint = authority.key() == stake_pool.authority @ StakingError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    /// The stake pool.
+    /// SECURITY: PDA validation + has_one authority and treasury.
+    #[account(
+        mut,
+        seeds = [STAKE_POOL_SEED, stake_pool.staking_mint.as_ref()],
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ StakingError::AccountVersionMismatch,
+        bump = stake_pool.bump,
+        has_one = authority @ StakingError::Unauthorized,
+        has_one = treasury_vault @ StakingError::TreasuryMismatch
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+
+    /// Pool's treasury vault.
+    pub treasury_vault: Account<'info, TokenAccount>,
+}
+
+/// Add `amount_per_user` to the pending rewards of every listed position.
+///
+/// # Security
+/// - Only pool.authority can call this
+/// - Batch bounded by MAX_BONUS_BATCH
+/// - Rejects foreign-pool, inactive, or repeated positions
+/// - Outstanding bonuses must fit under the emission cap and the treasury
+///
+/// # Arguments
+/// * `ctx` - CreditBonus accounts context; positions in remaining_accounts
+/// * `amount_per_user` - Bonus credited to each position
+///
+/// # Returns
+/// Result indicating success or error
+pub fn handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, CreditBonus<'info>>,
+    amount_per_user: u64,
+) -> Result<()> {
+    require!(amount_per_user > 0, StakingError::ZeroAmount);
+
+    let positions = ctx.remaining_accounts;
+    require!(!positions.is_empty(), StakingError::ZeroAmount);
+    require!(positions.len() <= MAX_BONUS_BATCH, StakingError::BonusBatchTooLarge);
+
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    // === SOLVENCY ===
+
+    let total = amount_per_user
+        .checked_mul(positions.len() as u64)
+        .ok_or(StakingError::MathOverflow)?;
+    let new_liability = stake_pool
+        .bonus_liability
+        .checked_add(total)
+        .ok_or(StakingError::MathOverflow)?;
+    require!(
+        stake_pool
+            .total_distributed
+            .checked_add(new_liability)
+            .ok_or(StakingError::MathOverflow)?
+            <= stake_pool.emission_cap,
+        StakingError::EmissionCapExceeded
+    );
+    require!(
+        ctx.accounts.treasury_vault.amount
+            >= new_liability.saturating_add(stake_pool.referral_liability),
+        StakingError::InsufficientTreasuryFunds
+    );
+
+    // === CREDIT ===
+
+    for (i, info) in positions.iter().enumerate() {
+        require!(
+            positions[..i].iter().all(|seen| seen.key != info.key),
+            StakingError::DuplicateAccount
+        );
+
+        let mut user_stake: Account<UserStake> = Account::try_from(info)?;
+        require!(
+            user_stake.stake_pool == stake_pool.key(),
+            StakingError::StakePoolMismatch
+        );
+        require!(
+            user_stake.version == USER_STAKE_VERSION,
+            StakingError::AccountVersionMismatch
+        );
+        require!(user_stake.is_active, StakingError::NoActiveStake);
+
+        user_stake.pending_rewards = user_stake
+            .pending_rewards
+            .checked_add(amount_per_user)
+            .ok_or(StakingError::MathOverflow)?;
+        user_stake.bonus_rewards = user_stake
+            .bonus_rewards
+            .checked_add(amount_per_user)
+            .ok_or(StakingError::MathOverflow)?;
+        user_stake.exit(&crate::ID)?;
+    }
+
+    stake_pool.bonus_liability = new_liability;
+    stake_pool.pending_liability = stake_pool
+        .pending_liability
+        .checked_add(total)
+        .ok_or(StakingError::MathOverflow)?;
+    stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+
+    emit!(BonusCredited {
+        stake_pool: stake_pool.key(),
+        count: positions.len() as u32,
+        amount_per_user,
+        total,
+        bonus_liability: new_liability,
+        sequence: stake_pool.sequence,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!("Credited {} to {} positions", amount_per_user, positions.len());
+    msg!("Outstanding bonus liability: {}", new_liability);
+    msg!("Admin: {}", ctx.accounts.authority.key());
+
+    Ok(())
+}
@@ -0,0 +1,126 @@
+/// Donate to stakers instruction handler.
+///
+/// Lets anyone drop a one-time bonus onto everyone staked right now, split
+/// pro-rata by stake. Shares are settled lazily through a global
+/// `bonus_per_token` accumulator and each position's `bonus_debt`.
+///
+/// ## Security Guarantees
+/// - Only positions staked at donation time share in it
+/// - Donated tokens are reserved in the treasury (`donation_liability`)
+///   until settled into a position, then as credited bonus
+/// - Reserved against the emission cap, like `credit_bonus`
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+
+use crate::constants::*;
+use crate::error::StakingError;
+use crate::events::DonationReceived;
+use crate::state::StakePool;
+
+/// Accounts required for donating to stakers.
+///
+/// ## Security Notes
+/// - Permissionless; the donor only moves their own tokens
+/// - Treasury must match pool's treasury vault
+/// - Donor token account must be for the reward mint
+#[derive(Accounts)]
+pub struct DonateToStakers<'info> {
+    /// The donor.
+    #[account(mut)]
+    pub donor: Signer<'info>,
+
+    /// The stake pool.
+    /// SECURITY: PDA + has_one validation.
+    #[account(
+        mut,
+        seeds = [STAKE_POOL_SEED, stake_pool.staking_mint.as_ref()],
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ StakingError::AccountVersionMismatch,
+        bump = stake_pool.bump,
+        has_one = treasury_vault @ StakingError::TreasuryMismatch
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+
+    /// Donor's token account.
+    /// SECURITY: Reward mint and owner validation.
+    #[account(
+        mut,
+        constraint = donor_token_account.mint == stake_pool.reward_mint @ StakingError::MintMismatch,
+        constraint = donor_token_account.owner == donor.key() @ StakingError::UnauthorizedStakeAccess
+    )]
+    pub donor_token_account: Account<'info, TokenAccount>,
+
+    /// Pool's treasury vault.
+    #[account(mut)]
+    pub treasury_vault: Account<'info, TokenAccount>,
+
+    /// Token program.
+    pub token_program: Program<'info, Token>,
+}
+
+/// Donate `amount` reward tokens to current stakers, pro-rata by stake.
+///
+/// # Security
+/// - Validates amount > 0 and that something is staked
+/// - Fails with `EmissionCapExceeded` if the donation, on top of what is
+///   distributed and reserved, would pass the emission cap
+/// - Rounding dust stays in the treasury unreserved
+///
+/// # Arguments
+/// * `ctx` - DonateToStakers accounts context
+/// * `amount` - Amount of reward tokens to donate
+///
+/// # Returns
+/// Result indicating success or error
+pub fn handler(ctx: Context<DonateToStakers>, amount: u64) -> Result<()> {
+    let stake_pool = &ctx.accounts.stake_pool;
+    require!(!stake_pool.emergency, StakingError::EmergencyMode);
+    require!(amount > 0, StakingError::ZeroAmount);
+    require!(stake_pool.total_staked > 0, StakingError::NoStakers);
+
+    let reserved = stake_pool
+        .total_distributed
+        .checked_add(stake_pool.bonus_liability)
+        .and_then(|total| total.checked_add(stake_pool.donation_liability))
+        .and_then(|total| total.checked_add(amount))
+        .ok_or(StakingError::MathOverflow)?;
+    require!(
+        reserved <= stake_pool.emission_cap,
+        StakingError::EmissionCapExceeded
+    );
+
+    // === TOKEN TRANSFER ===
+
+    let cpi_accounts = Transfer {
+        from: ctx.accounts.donor_token_account.to_account_info(),
+        to: ctx.accounts.treasury_vault.to_account_info(),
+        authority: ctx.accounts.donor.to_account_info(),
+    };
+    let cpi_program = ctx.accounts.token_program.to_account_info();
+    let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+    token::transfer(cpi_ctx, amount)?;
+
+    // === STATE UPDATE ===
+
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+    let distributed = stake_pool.record_donation(amount)?;
+    stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+
+    emit!(DonationReceived {
+        stake_pool: stake_pool.key(),
+        donor: ctx.accounts.donor.key(),
+        amount,
+        distributed,
+        bonus_per_token: stake_pool.bonus_per_token,
+        total_staked: stake_pool.total_staked,
+        sequence: stake_pool.sequence,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!("Donated {} tokens across {} staked", amount, stake_pool.total_staked);
+    msg!("Donor: {}", ctx.accounts.donor.key());
+
+    Ok(())
+}
@@ -12,6 +12,7 @@ use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
 
 use crate::constants::*;
 use crate::error::StakingError;
+use crate::events::FundTreasuryEvent;
 use crate::state::StakePool;
 
 /// Accounts required for funding the treasury.
@@ -19,7 +20,7 @@ use crate::state::StakePool;
 /// ## Security Notes
 /// - Treasury must match pool's treasury vault
 /// - Treasury must be owned by stake pool PDA
-/// - Funder token account must be for correct mint
+/// - Funder token account must be for the reward mint
 #[derive(Accounts)]
 pub struct FundTreasury<'info> {
     /// The funder (anyone can fund - no admin restriction).
@@ -31,6 +32,7 @@ pub struct FundTreasury<'info> {
     #[account(
         mut,
         seeds = [STAKE_POOL_SEED, stake_pool.staking_mint.as_ref()],
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ StakingError::AccountVersionMismatch,
         bump = stake_pool.bump,
         has_one = treasury_vault @ StakingError::TreasuryMismatch,
         has_one = staking_mint @ StakingError::MintMismatch
@@ -45,10 +47,10 @@ pub struct FundTreasury<'info> {
     pub staking_mint: Account<'info, Mint>,
 
     /// Funder's token account.
-    /// SECURITY: Mint and owner validation.
+    /// SECURITY: Reward mint and owner validation.
     #[account(
         mut,
-        constraint = funder_token_account.mint == staking_mint.key() @ StakingError::MintMismatch,
+        constraint = funder_token_account.mint == stake_pool.reward_mint @ StakingError::MintMismatch,
         constraint = funder_token_account.owner == funder.key() @ StakingError::UnauthorizedStakeAccess
     )]
     pub funder_token_account: Account<'info, TokenAccount>,
@@ -59,7 +61,7 @@ pub struct FundTreasury<'info> {
         mut,
         constraint = treasury_vault.key() == stake_pool.treasury_vault @ StakingError::TreasuryMismatch,
         constraint = treasury_vault.owner == stake_pool.key() @ StakingError::InvalidTreasuryOwner,
-        constraint = treasury_vault.mint == staking_mint.key() @ StakingError::InvalidTokenAccountMint
+        constraint = treasury_vault.mint == stake_pool.reward_mint @ StakingError::InvalidTokenAccountMint
     )]
     pub treasury_vault: Account<'info, TokenAccount>,
 
@@ -74,6 +76,7 @@ pub struct FundTreasury<'info> {
 /// - Validates correct mint
 /// - Validates treasury PDA ownership
 /// - Anyone can fund (permissionless)
+/// - Adds `amount` to `total_funded`
 ///
 /// # Arguments
 /// * `ctx` - FundTreasury accounts context
@@ -82,6 +85,9 @@ pub struct FundTreasury<'info> {
 /// # Returns
 /// Result indicating success or error
 pub fn handler(ctx: Context<FundTreasury>, amount: u64) -> Result<()> {
+    // Emergency mode blocks everything except principal exits
+    require!(!ctx.accounts.stake_pool.emergency, StakingError::EmergencyMode);
+
     // === INPUT VALIDATION ===
     
     // Validate amount is non-zero
@@ -103,12 +109,32 @@ pub fn handler(ctx: Context<FundTreasury>, amount: u64) -> Result<()> {
     
     let stake_pool = &mut ctx.accounts.stake_pool;
     let clock = Clock::get()?;
+    stake_pool.total_funded = stake_pool
+        .total_funded
+        .checked_add(amount)
+        .ok_or(StakingError::MathOverflow)?;
     stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+
+    // Close the open empty-treasury window so accrual resumes from now
+    if let Some(empty_since) = stake_pool.close_empty_window(clock.unix_timestamp)? {
+        msg!("Treasury refilled after being empty since {}", empty_since);
+    }
 
     // Reload treasury to get updated balance
     ctx.accounts.treasury_vault.reload()?;
     let treasury_balance = ctx.accounts.treasury_vault.amount;
 
+    emit!(FundTreasuryEvent {
+        stake_pool: ctx.accounts.stake_pool.key(),
+        funder: ctx.accounts.funder.key(),
+        amount,
+        total_funded: ctx.accounts.stake_pool.total_funded,
+        treasury_balance,
+        sequence: ctx.accounts.stake_pool.sequence,
+        timestamp: clock.unix_timestamp,
+    });
+
     msg!("Treasury funded with {} tokens", amount);
     msg!("New treasury balance: {}", treasury_balance);
     msg!("Funder: {}", ctx.accounts.funder.key());
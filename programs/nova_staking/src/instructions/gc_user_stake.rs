@@ -0,0 +1,118 @@
+/// Garbage-collect user stake instruction handler.
+///
+/// Lets anyone close an abandoned, empty position so it stops showing up in
+/// account scans. The rent goes back to the owner, less a small fixed bounty
+/// for whoever ran the cleanup.
+///
+/// ## Security Guarantees
+/// - Only positions holding nothing can be closed, exactly as with
+///   `close_user_stake`
+/// - The position must be closed out and idle for `gc_inactivity_secs`
+/// - Rent goes to the owner, never the cranker, apart from GC_BOUNTY_LAMPORTS
+
+use anchor_lang::prelude::*;
+
+use crate::constants::*;
+use crate::error::StakingError;
+use crate::events::UserStakeCollected;
+use crate::instructions::close_user_stake::require_fully_exited;
+use crate::state::{StakePool, UserStake};
+
+/// Accounts required for collecting an abandoned position.
+///
+/// ## Security Notes
+/// - Permissionless; the cranker only receives the bounty
+/// - Rent is returned to the position owner via `close = owner`
+#[derive(Accounts)]
+pub struct GcUserStake<'info> {
+    /// Whoever runs the cleanup; receives the bounty.
+    #[account(mut)]
+    pub cranker: Signer<'info>,
+
+    /// The position owner; receives the rest of the rent.
+    /// CHECK: Address-checked against user_stake.owner.
+    #[account(mut, address = user_stake.owner @ StakingError::InvalidStakeOwner)]
+    pub owner: UncheckedAccount<'info>,
+
+    /// The stake pool; writable for `sequence`.
+    /// SECURITY: PDA validation.
+    #[account(
+        mut,
+        seeds = [STAKE_POOL_SEED, stake_pool.staking_mint.as_ref()],
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ StakingError::AccountVersionMismatch,
+        bump = stake_pool.bump
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+
+    /// Position being collected, closed to its owner.
+    /// SECURITY: PDA + pool validation.
+    #[account(
+        mut,
+        close = owner,
+        seeds = [USER_STAKE_SEED, stake_pool.key().as_ref(), user_stake.owner.as_ref()],
+        bump = user_stake.bump,
+        constraint = user_stake.version == USER_STAKE_VERSION @ StakingError::AccountVersionMismatch,
+        constraint = user_stake.stake_pool == stake_pool.key() @ StakingError::StakePoolMismatch
+    )]
+    pub user_stake: Account<'info, UserStake>,
+}
+
+/// Close an empty position that has been idle for the pool's GC period.
+///
+/// # Security
+/// - Fails with `GcDisabled` while `gc_inactivity_secs` is zero
+/// - The position must be closed out (`PositionNotEmpty` otherwise) and hold
+///   no rewards of any kind (`RewardsOutstanding`)
+/// - `last_claim_time` must be at least `gc_inactivity_secs` old
+///   (`PositionRecentlyActive`)
+/// - Takes GC_BOUNTY_LAMPORTS out of the rent for the cranker; the rest is
+///   returned to the owner
+///
+/// # Arguments
+/// * `ctx` - GcUserStake accounts context
+///
+/// # Returns
+/// Result indicating success or error
+pub fn handler(ctx: Context<GcUserStake>) -> Result<()> {
+    let user_stake = &ctx.accounts.user_stake;
+    let stake_pool = &ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    require!(stake_pool.gc_inactivity_secs > 0, StakingError::GcDisabled);
+    require!(!user_stake.is_active, StakingError::PositionNotEmpty);
+    require_fully_exited(user_stake, stake_pool, clock.unix_timestamp)?;
+    require!(
+        stake_pool.is_gc_due(user_stake.last_claim_time, clock.unix_timestamp),
+        StakingError::PositionRecentlyActive
+    );
+
+    // === BOUNTY ===
+
+    // The program owns the position account, so it can move lamports out
+    // directly; `close = owner` then sends the rest to the owner
+    let position_info = ctx.accounts.user_stake.to_account_info();
+    let bounty = GC_BOUNTY_LAMPORTS.min(position_info.lamports());
+    **position_info.try_borrow_mut_lamports()? -= bounty;
+    **ctx.accounts.cranker.to_account_info().try_borrow_mut_lamports()? += bounty;
+
+    // === STATE UPDATE ===
+
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    stake_pool.next_sequence()?;
+
+    let owner = ctx.accounts.user_stake.owner;
+    emit!(UserStakeCollected {
+        stake_pool: stake_pool.key(),
+        owner,
+        cranker: ctx.accounts.cranker.key(),
+        bounty,
+        last_claim_time: ctx.accounts.user_stake.last_claim_time,
+        sequence: stake_pool.sequence,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!("Collected idle stake position of {}", owner);
+    msg!("Bounty paid to {}: {} lamports", ctx.accounts.cranker.key(), bounty);
+
+    Ok(())
+}
@@ -5,14 +5,24 @@
 /// ## Security Guarantees
 /// - Vault and treasury are PDAs owned by the stake pool
 /// - Mint address is locked to pool state permanently
+/// - The treasury is created for the reward mint, which may differ from the
+///   staking mint
 /// - All parameters validated before storage
+/// - Every pool is appended to the pool registry
+/// - In receipt mode the receipt mint is a PDA whose only mint authority is
+///   the stake pool
 
 use anchor_lang::prelude::*;
 use anchor_spl::token::{Mint, Token, TokenAccount};
 
 use crate::constants::*;
 use crate::error::StakingError;
-use crate::state::StakePool;
+use crate::instructions::stake::TopUpLockPolicy;
+use crate::state::{
+    ApyBoost, ApyCheckpoint, OracleApyConfig, PenaltyDestination, PoolRegistry, RewardMode,
+    StakePool,
+};
+use crate::tier::Tier;
 
 /// Accounts required for pool initialization.
 ///
@@ -42,6 +52,11 @@ pub struct Initialize<'info> {
     /// SECURITY: Validated as Account<Mint> - cannot be arbitrary account.
     pub staking_mint: Account<'info, Mint>,
 
+    /// The mint rewards are paid in. Pass `staking_mint` again for a
+    /// single-token pool.
+    /// SECURITY: Validated as Account<Mint>; locked to pool state.
+    pub reward_mint: Account<'info, Mint>,
+
     /// The vault that will hold staked tokens.
     /// SECURITY: 
     /// - PDA derived from POOL_VAULT_SEED + stake_pool
@@ -58,17 +73,44 @@ pub struct Initialize<'info> {
     pub staking_vault: Account<'info, TokenAccount>,
 
     /// The treasury vault that will hold reward tokens.
-    /// SECURITY: Same protections as staking_vault.
+    /// SECURITY: Same protections as staking_vault, for the reward mint.
     #[account(
         init,
         payer = authority,
         seeds = [TREASURY_VAULT_SEED, stake_pool.key().as_ref()],
         bump,
-        token::mint = staking_mint,
+        token::mint = reward_mint,
         token::authority = stake_pool
     )]
     pub treasury_vault: Account<'info, TokenAccount>,
 
+    /// Registry the new pool is appended to.
+    /// SECURITY: Singleton PDA; grows by `REGISTRY_GROWTH` entries, paid by
+    /// the authority, when full.
+    #[account(
+        mut,
+        seeds = [POOL_REGISTRY_SEED],
+        bump = pool_registry.bump,
+        realloc = PoolRegistry::space_for(pool_registry.capacity_after_push()),
+        realloc::payer = authority,
+        realloc::zero = false
+    )]
+    pub pool_registry: Box<Account<'info, PoolRegistry>>,
+
+    /// Receipt mint; passing it turns on receipt mode for good.
+    /// SECURITY: PDA derived from RECEIPT_MINT_SEED + stake_pool, with the
+    /// staking mint's decimals, the stake pool as mint authority and no
+    /// freeze authority.
+    #[account(
+        init,
+        payer = authority,
+        seeds = [RECEIPT_MINT_SEED, stake_pool.key().as_ref()],
+        bump,
+        mint::decimals = staking_mint.decimals,
+        mint::authority = stake_pool
+    )]
+    pub receipt_mint: Option<Box<Account<'info, Mint>>>,
+
     /// System program for account creation.
     pub system_program: Program<'info, System>,
 
@@ -86,6 +128,13 @@ pub struct Initialize<'info> {
 /// - Validates emission cap is non-zero
 /// - Stores vault/treasury PDAs in pool state (immutable references)
 /// - Stores mint in pool state (locked permanently)
+/// - Only SPL Token mints: the mints are typed for the token program, so a
+///   Token-2022 mint fails the owner check
+/// - Dual-token pools start with unstake fees burned, since their treasury
+///   holds the reward mint; if the staking mint has a freeze authority they
+///   start with no penalty destination and cannot charge fees at all
+/// - Receipt mode is on exactly when `receipt_mint` is passed, and cannot be
+///   changed later
 ///
 /// # Arguments
 /// * `ctx` - Initialize accounts context
@@ -93,20 +142,34 @@ pub struct Initialize<'info> {
 /// * `flex_apy` - Flex tier APY (basis points)
 /// * `core_apy` - Core tier APY (basis points)
 /// * `prime_apy` - Prime tier APY (basis points)
+/// * `min_stake_amount` - Smallest amount that may open a position (0 = none)
+/// * `reward_mode` - Per-position APYs or a shared reward-per-token rate;
+///   cannot be changed later
+/// * `allow_cpi` - Let other programs call `stake`, `unstake` and
+///   `claim_rewards` through CPI; cannot be changed later
+/// * `allowed_topup_policies` - Bitmask of `TopUpLockPolicy` values stakers
+///   may use when adding to a position (see `set_allowed_topup_policies`)
 ///
 /// # Returns
 /// Result indicating success or error
+#[allow(clippy::too_many_arguments)]
 pub fn handler(
     ctx: Context<Initialize>,
     emission_cap: u64,
     flex_apy: u16,
     core_apy: u16,
     prime_apy: u16,
+    min_stake_amount: u64,
+    reward_mode: RewardMode,
+    allow_cpi: bool,
+    allowed_topup_policies: u8,
 ) -> Result<()> {
     // === INPUT VALIDATION ===
     
     // Validate emission cap is non-zero
     require!(emission_cap > 0, StakingError::ZeroEmissionCap);
+
+    TopUpLockPolicy::validate_mask(allowed_topup_policies)?;
     
     // Validate APY values don't exceed maximum (50%)
     require!(flex_apy <= MAX_APY, StakingError::ApyTooHigh);
@@ -134,7 +197,7 @@ pub fn handler(
     );
     
     require!(
-        ctx.accounts.treasury_vault.mint == ctx.accounts.staking_mint.key(),
+        ctx.accounts.treasury_vault.mint == ctx.accounts.reward_mint.key(),
         StakingError::InvalidTokenAccountMint
     );
 
@@ -148,6 +211,10 @@ pub fn handler(
     stake_pool.staking_mint = ctx.accounts.staking_mint.key();  // LOCKED - never changes
     stake_pool.staking_vault = ctx.accounts.staking_vault.key(); // LOCKED - PDA reference
     stake_pool.treasury_vault = ctx.accounts.treasury_vault.key(); // LOCKED - PDA reference
+    stake_pool.reward_mint = ctx.accounts.reward_mint.key(); // LOCKED - never changes
+    stake_pool.flex_lock_seconds = Tier::Flex.lock_period();
+    stake_pool.core_lock_seconds = Tier::Core.lock_period();
+    stake_pool.prime_lock_seconds = Tier::Prime.lock_period();
     stake_pool.flex_apy = flex_apy;
     stake_pool.core_apy = core_apy;
     stake_pool.prime_apy = prime_apy;
@@ -155,20 +222,181 @@ pub fn handler(
     stake_pool.total_distributed = 0;
     stake_pool.total_staked = 0;
     stake_pool.staker_count = 0;
-    stake_pool.paused = false;
+    stake_pool.pause_flags = 0;
     stake_pool.created_at = clock.unix_timestamp;
     stake_pool.last_updated = clock.unix_timestamp;
+
+    // Treasury starts empty; only consulted when accrue_only_when_funded is set
+    stake_pool.accrue_only_when_funded = false;
+    stake_pool.treasury_empty_since = Some(clock.unix_timestamp);
+    stake_pool.cumulative_unfunded_secs = 0;
+    stake_pool.last_empty_window_end = 0;
+    stake_pool.max_accrual_horizon_secs = 0;
+    stake_pool.max_daily_inflow = 0;
+    stake_pool.inflow_window_start = clock.unix_timestamp;
+    stake_pool.inflow_in_window = 0;
+    stake_pool.emergency = false;
+    stake_pool.allowed_topup_policies = allowed_topup_policies;
+    stake_pool.relock_bonus_bps = 0;
+    stake_pool.relock_grace_secs = 0;
+    stake_pool.early_bird_slots = 0;
+    stake_pool.early_bird_slots_used = 0;
+    stake_pool.early_bird_bonus_bps = 0;
+    stake_pool.early_bird_duration_secs = 0;
+    stake_pool.streak_step_bps = 0;
+    stake_pool.streak_cap_bps = 0;
+    stake_pool.streak_window_secs = 0;
+    // Default schedules are a hard cliff at the end of each lock
+    stake_pool.core_cliff_secs = CORE_LOCK_PERIOD;
+    stake_pool.core_linear_secs = 0;
+    stake_pool.prime_cliff_secs = PRIME_LOCK_PERIOD;
+    stake_pool.prime_linear_secs = 0;
+    stake_pool.flex_reward_cliff_secs = 0;
+    stake_pool.core_reward_cliff_secs = 0;
+    stake_pool.prime_reward_cliff_secs = 0;
+    stake_pool.permanent_apy = 0;
+    stake_pool.sunset_at = 0;
+    stake_pool.boost_bps = 0;
+    stake_pool.max_boost_duration_secs = 0;
+    stake_pool.referral_share_bps = 0;
+    stake_pool.param_timelock_seconds = 0;
+    stake_pool.claim_fee_bps = 0;
+    stake_pool.fee_vault = Pubkey::default();
+    stake_pool.pending_claim_fee_bps = 0;
+    stake_pool.pending_fee_vault = Pubkey::default();
+    stake_pool.pending_claim_fee_effective_at = 0;
+    stake_pool.pending_apy = [0; 3];
+    stake_pool.pending_apy_effective_at = 0;
+    stake_pool.pending_permanent_apy = 0;
+    stake_pool.pending_permanent_apy_effective_at = 0;
+    stake_pool.pending_emission_cap = 0;
+    stake_pool.pending_emission_cap_effective_at = 0;
+    stake_pool.min_accrual_seconds = 0;
+    stake_pool.min_stake_duration_seconds = 0;
+    stake_pool.version = STAKE_POOL_VERSION;
+    stake_pool.loyalty_threshold_secs = [0; MAX_LOYALTY_LEVELS];
+    stake_pool.loyalty_bonus_bps = [0; MAX_LOYALTY_LEVELS];
+    stake_pool.total_funded = 0;
+    stake_pool.max_emission_per_epoch = 0;
+    stake_pool.epoch_length_seconds = 0;
+    stake_pool.current_epoch_start = 0;
+    stake_pool.distributed_this_epoch = 0;
+    stake_pool.tier_staker_count = [0; 4];
+    stake_pool.allow_cpi = allow_cpi;
+    stake_pool.unaccounted_principal = 0;
+    stake_pool.bonus_per_token = 0;
+    stake_pool.donation_liability = 0;
+    stake_pool.total_donated = 0;
+    stake_pool.pauser = ctx.accounts.authority.key();
+    stake_pool.rate_setter = ctx.accounts.authority.key();
+    stake_pool.cumulative_staked = 0;
+    stake_pool.cumulative_unstaked = 0;
+    stake_pool.cumulative_claims_count = 0;
+    stake_pool.unique_stakers_ever = 0;
+    stake_pool.accrue_while_paused = true;
+    stake_pool.paused_at = None;
+    stake_pool.cumulative_paused_secs = 0;
+    stake_pool.rewards_end_time = 0;
+    stake_pool.pending_rewards_end_time = 0;
+    stake_pool.pending_rewards_end_time_effective_at = 0;
+    stake_pool.sequence = 0;
+    stake_pool.pause_expiry = 0;
+    stake_pool.snapshot_interval_secs = 0;
+    stake_pool.snapshot_count = 0;
+    stake_pool.last_snapshot_at = 0;
+    stake_pool.oracle = OracleApyConfig::default();
+    stake_pool.last_oracle_update = 0;
+    stake_pool.apy_boosts = [ApyBoost::default(); MAX_APY_BOOSTS];
+    stake_pool.apy_boost_count = 0;
+    stake_pool.gc_inactivity_secs = 0;
+    stake_pool.min_claim_interval_seconds = 0;
+    stake_pool.downgrade_after_lock = false;
+    stake_pool.whitelist_enabled = false;
+    stake_pool.clear_pending_decay();
+    stake_pool.rate_setter_window_start = 0;
+    stake_pool.rate_setter_window_apys = [0; 3];
+    stake_pool.accrued_liability = 0;
+    stake_pool.liability_accrued_at = clock.unix_timestamp;
+    stake_pool.receipt_mint = ctx
+        .accounts
+        .receipt_mint
+        .as_ref()
+        .map_or(Pubkey::default(), |mint| mint.key());
+    stake_pool.max_claims_per_epoch = 0;
+    stake_pool.claim_epoch_secs = 0;
+    stake_pool.epoch_start = 0;
+    stake_pool.claims_this_epoch = 0;
+    stake_pool.unstake_fee_bps = [0; 3];
+    // The treasury of a dual-token pool cannot take staking tokens, and
+    // burning is refused while the staking mint can be frozen, as in
+    // `set_penalty_destination`
+    stake_pool.penalty_destination = if !stake_pool.is_dual_token() {
+        PenaltyDestination::Treasury
+    } else if ctx.accounts.staking_mint.freeze_authority.is_none() {
+        PenaltyDestination::Burn
+    } else {
+        PenaltyDestination::Unset
+    };
+    stake_pool.flex_min_stake_secs = 0;
+    stake_pool.yield_adapters = [Pubkey::default(); MAX_YIELD_ADAPTERS];
+    stake_pool.max_treasury_deploy_bps = 0;
+    stake_pool.treasury_deployed = 0;
+    stake_pool.deployed_adapter = Pubkey::default();
+    stake_pool.drip_rate_per_sec = 0;
+    stake_pool.last_drip_at = 0;
+    stake_pool.bonus_liability = 0;
+    stake_pool.unlock_grace_secs = 0;
+    stake_pool.early_unstake_penalty_bps = [0; 2];
+    stake_pool.pending_authority = Pubkey::default();
+    stake_pool.flex_paused = false;
+    stake_pool.core_paused = false;
+    stake_pool.prime_paused = false;
+    stake_pool.min_stake_amount = min_stake_amount;
+    stake_pool.max_total_staked = 0;
+    stake_pool.flex_cooldown_seconds = 0;
+    stake_pool.referral_liability = 0;
+    stake_pool.apy_epoch = 0;
+    stake_pool.apy_history = [ApyCheckpoint::default(); MAX_APY_CHECKPOINTS];
+    stake_pool.folded_apy_seconds = [0; 4];
+    stake_pool.folded_accruing_seconds = 0;
+    stake_pool.apy_folded_until = 0;
+    stake_pool.pending_liability = 0;
+    stake_pool.decay_interval_seconds = 0;
+    stake_pool.decay_bps_per_interval = 0;
+    stake_pool.min_apy_floor = 0;
+    stake_pool.last_refresh_at = 0;
+    stake_pool.projected_outstanding_rewards = 0;
+    stake_pool.snapshot_emission_capacity = 0;
+    stake_pool.snapshot_treasury_balance = 0;
+    stake_pool.tier_staked = [0; 4];
+    stake_pool.require_solvency = false;
+    stake_pool.solvency_treasury_multiple_bps = 0;
+    stake_pool.reward_mode = reward_mode;
+    stake_pool.reward_per_token_stored = 0;
+    stake_pool.reward_rate_per_second = 0;
+    stake_pool.last_update_time = clock.unix_timestamp;
+    stake_pool.total_unstake_requested = 0;
+    stake_pool.admin_multisig = Pubkey::default();
     
     // Store bumps for PDA verification in future instructions
     stake_pool.bump = ctx.bumps.stake_pool;
     stake_pool.vault_bump = ctx.bumps.staking_vault;
     stake_pool.treasury_bump = ctx.bumps.treasury_vault;
 
+    let pool_key = stake_pool.key();
+    ctx.accounts
+        .pool_registry
+        .register(pool_key, ctx.accounts.staking_mint.key())?;
+
     msg!("Nova Staking Pool initialized successfully");
     msg!("Admin: {}", ctx.accounts.authority.key());
     msg!("Mint: {}", ctx.accounts.staking_mint.key());
     msg!("Emission Cap: {}", emission_cap);
     msg!("APY - Flex: {}bp, Core: {}bp, Prime: {}bp", flex_apy, core_apy, prime_apy);
+    msg!("Registered pools: {}", ctx.accounts.pool_registry.entries.len());
+    if let Some(receipt_mint) = ctx.accounts.receipt_mint.as_ref() {
+        msg!("Receipt mint: {}", receipt_mint.key());
+    }
 
     Ok(())
 }
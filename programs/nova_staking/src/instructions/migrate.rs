@@ -0,0 +1,152 @@
+/// Account migration instruction handlers.
+///
+/// Upgrades `StakePool` and `UserStake` accounts written by an older program
+/// version to the current layout, growing them with `realloc` when needed.
+///
+/// ## Security Guarantees
+/// - Permissionless: migration only appends zeroed fields and bumps
+///   `version`, so no balance or setting can change
+/// - Only accounts owned by this program with the matching discriminator
+///   are touched
+/// - The payer tops up rent for any added space
+/// - Every other instruction rejects accounts that are not on the current
+///   version with `AccountVersionMismatch`
+
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+use anchor_lang::Discriminator;
+
+use crate::error::StakingError;
+use crate::state::{StakePool, UserStake};
+
+/// Accounts required for migrating a stake pool.
+///
+/// ## Security Notes
+/// - The pool is read unchecked because an old layout may not deserialize;
+///   owner and discriminator are checked in the handler
+#[derive(Accounts)]
+pub struct MigrateStakePool<'info> {
+    /// Pays rent for any added space.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: may predate the current layout; validated by `prepare_account`.
+    #[account(mut)]
+    pub stake_pool: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required for migrating a stake position.
+///
+/// ## Security Notes
+/// - Same checks as `MigrateStakePool`, against the `UserStake` layout
+#[derive(Accounts)]
+pub struct MigrateUserStake<'info> {
+    /// Pays rent for any added space.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: may predate the current layout; validated by `prepare_account`.
+    #[account(mut)]
+    pub user_stake: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Upgrade a stake pool to `STAKE_POOL_VERSION`; a no-op if it is current.
+///
+/// # Arguments
+/// * `ctx` - MigrateStakePool accounts context
+///
+/// # Returns
+/// Result indicating success or error
+pub fn migrate_stake_pool_handler(ctx: Context<MigrateStakePool>) -> Result<()> {
+    let info = ctx.accounts.stake_pool.to_account_info();
+    prepare_account(
+        &info,
+        &StakePool::DISCRIMINATOR,
+        StakePool::LEN,
+        &ctx.accounts.payer,
+        &ctx.accounts.system_program,
+    )?;
+
+    let mut stake_pool = StakePool::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+    let old_version = stake_pool.version;
+    stake_pool.migrate()?;
+
+    let mut data = info.try_borrow_mut_data()?;
+    let mut writer: &mut [u8] = &mut data[..];
+    stake_pool.try_serialize(&mut writer)?;
+
+    msg!("Stake pool migrated: v{} -> v{}", old_version, stake_pool.version);
+
+    Ok(())
+}
+
+/// Upgrade a stake position to `USER_STAKE_VERSION`; a no-op if it is current.
+///
+/// # Arguments
+/// * `ctx` - MigrateUserStake accounts context
+///
+/// # Returns
+/// Result indicating success or error
+pub fn migrate_user_stake_handler(ctx: Context<MigrateUserStake>) -> Result<()> {
+    let info = ctx.accounts.user_stake.to_account_info();
+    prepare_account(
+        &info,
+        &UserStake::DISCRIMINATOR,
+        UserStake::LEN,
+        &ctx.accounts.payer,
+        &ctx.accounts.system_program,
+    )?;
+
+    let mut user_stake = UserStake::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+    let old_version = user_stake.version;
+    user_stake.migrate()?;
+
+    let mut data = info.try_borrow_mut_data()?;
+    let mut writer: &mut [u8] = &mut data[..];
+    user_stake.try_serialize(&mut writer)?;
+
+    msg!("User stake migrated: v{} -> v{}", old_version, user_stake.version);
+
+    Ok(())
+}
+
+/// Check ownership and type, then grow the account to `len` bytes with a
+/// zeroed tail, topping up rent from `payer`.
+fn prepare_account<'info>(
+    info: &AccountInfo<'info>,
+    discriminator: &[u8; 8],
+    len: usize,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+) -> Result<()> {
+    require!(info.owner == &crate::ID, StakingError::AccountNotMigratable);
+    require!(
+        info.try_borrow_data()?.starts_with(discriminator),
+        StakingError::AccountNotMigratable
+    );
+
+    if info.data_len() >= len {
+        return Ok(());
+    }
+
+    let shortfall = Rent::get()?
+        .minimum_balance(len)
+        .saturating_sub(info.lamports());
+    if shortfall > 0 {
+        let cpi_accounts = Transfer {
+            from: payer.to_account_info(),
+            to: info.clone(),
+        };
+        let cpi_ctx = CpiContext::new(system_program.to_account_info(), cpi_accounts);
+        system_program::transfer(cpi_ctx, shortfall)?;
+    }
+    info.realloc(len, true)?;
+
+    msg!("Account grown to {} bytes", len);
+
+    Ok(())
+}
@@ -1,7 +1,39 @@
 pub mod admin;
+pub mod admin_multisig;
+pub mod auto_renew;
+pub mod boost_lock;
+pub mod change_tier;
+pub mod claim_delegate;
+pub mod claim_fee;
+pub mod claim_history;
 pub mod claim_rewards;
+pub mod close_user_stake;
+pub mod compound_rewards;
+pub mod credit_bonus;
+pub mod donate_to_stakers;
 pub mod fund_treasury;
+pub mod gc_user_stake;
 pub mod initialize;
+pub mod migrate;
+pub mod oracle_apy;
+pub mod pool_registry;
+pub mod position_history;
+pub mod pending_params;
+pub mod receipt;
+pub mod referral;
+pub mod refresh_pool;
+pub mod recover_tokens;
+pub mod recovery;
+pub mod reserve;
+pub mod reward_destination;
+pub mod snapshot;
 pub mod stake;
+pub mod stake_label;
+pub mod sync_vault;
+pub mod transfer_stake;
+pub mod treasury_yield;
 pub mod unstake;
-
+pub mod unstake_request;
+pub mod view_pending_rewards;
+pub mod whitelist;
+pub mod withdraw_treasury;
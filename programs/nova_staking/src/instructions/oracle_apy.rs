@@ -0,0 +1,106 @@
+/// Oracle APY instruction handler.
+///
+/// Moves the Flex, Core and Prime APYs against the staking token's Pyth
+/// price, so the yield stays roughly constant in quote terms: a tier earns
+/// its base APY at the reference price, half of it at twice the price.
+///
+/// ## Security Guarantees
+/// - Permissionless, but only reads the price account the authority stored
+/// - Stale, wide-confidence or non-trading prices are refused
+/// - New APYs stay within `[min_apy, MAX_APY]` and go through the same
+///   checkpoint (and timelock) path as `adjust_apy`
+/// - At most one update per `update_interval_secs`
+/// - Unchanged APYs use no checkpoint
+/// - No token movement here
+
+use anchor_lang::prelude::*;
+
+use crate::constants::*;
+use crate::error::StakingError;
+use crate::events::ApyUpdatedFromOracle;
+use crate::instructions::admin::schedule_apy;
+use crate::math::oracle_apy;
+use crate::oracle::PythPrice;
+use crate::state::StakePool;
+
+/// Accounts required for an oracle APY update.
+///
+/// ## Security Notes
+/// - `price_account` must be the pool's stored oracle
+#[derive(Accounts)]
+pub struct UpdateApyFromOracle<'info> {
+    /// The stake pool.
+    /// SECURITY: PDA validation; oracle mode must be on.
+    #[account(
+        mut,
+        seeds = [STAKE_POOL_SEED, stake_pool.staking_mint.as_ref()],
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ StakingError::AccountVersionMismatch,
+        constraint = stake_pool.oracle.is_enabled() @ StakingError::OracleNotConfigured,
+        bump = stake_pool.bump
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+
+    /// Pyth price account of the staking token.
+    /// CHECK: Address-checked against the pool; decoded by `PythPrice::parse`.
+    #[account(address = stake_pool.oracle.price_account @ StakingError::OracleMismatch)]
+    pub price_account: UncheckedAccount<'info>,
+}
+
+/// Reprice the tier APYs from the oracle.
+///
+/// # Security
+/// - Fails with `OracleStale` when the price is older than
+///   `max_staleness_secs`, and `OracleConfidenceTooWide` when its confidence
+///   exceeds `max_confidence_bps` of the price
+/// - Fails with `OracleUpdateTooSoon` inside `update_interval_secs`
+/// - Rates equal to the live (or queued) ones are not rescheduled, so a
+///   flat price never spends an APY checkpoint
+///
+/// # Arguments
+/// * `ctx` - UpdateApyFromOracle accounts context
+///
+/// # Returns
+/// Result indicating success or error
+pub fn handler(ctx: Context<UpdateApyFromOracle>) -> Result<()> {
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+    let config = stake_pool.oracle;
+
+    require!(
+        stake_pool.is_oracle_update_due(clock.unix_timestamp),
+        StakingError::OracleUpdateTooSoon
+    );
+
+    let quote = PythPrice::parse(&ctx.accounts.price_account.try_borrow_data()?)?;
+    let (price, conf) = quote.checked(
+        clock.unix_timestamp,
+        config.max_staleness_secs,
+        config.max_confidence_bps,
+    )?;
+
+    let [flex_apy, core_apy, prime_apy] = config
+        .base_apys
+        .map(|base| oracle_apy(base, config.reference_price, price, config.min_apy));
+
+    stake_pool.last_oracle_update = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+    if stake_pool.scheduled_apys() == [flex_apy, core_apy, prime_apy] {
+        msg!("Oracle APYs unchanged");
+    } else {
+        schedule_apy(stake_pool, flex_apy, core_apy, prime_apy, clock.unix_timestamp)?;
+    }
+
+    emit!(ApyUpdatedFromOracle {
+        stake_pool: stake_pool.key(),
+        price,
+        conf,
+        publish_time: quote.publish_time,
+        apys: [flex_apy, core_apy, prime_apy],
+        sequence: stake_pool.sequence,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!("Oracle price {} (conf {}) at {}", price, conf, quote.publish_time);
+
+    Ok(())
+}
@@ -0,0 +1,154 @@
+/// Pending parameter instruction handlers.
+///
+/// While `param_timelock_seconds` is set, `adjust_apy`, `set_permanent_apy`,
+/// `set_apy_decay`, `update_emission_cap` and `set_rewards_end_time` only
+/// queue their values on the pool, so stakers can see a cut coming before it
+/// applies.
+///
+/// ## Security Guarantees
+/// - Anyone can apply a queued change once its `effective_at` has passed
+/// - Only pool.authority can cancel a queued change, and on multisig pools
+///   only through `propose_admin_action`
+/// - Applied values go through the same validation as an immediate change
+
+use anchor_lang::prelude::*;
+
+use crate::constants::*;
+use crate::error::StakingError;
+use crate::instructions::admin::{
+    apply_apy, apply_apy_decay, apply_emission_cap, apply_permanent_apy, apply_rewards_end_time,
+    AdminControl,
+};
+use crate::state::StakePool;
+
+/// Accounts required for applying pending parameters.
+///
+/// ## Security Notes
+/// - Permissionless; the timelock is the only gate
+#[derive(Accounts)]
+pub struct ApplyPendingParams<'info> {
+    /// The stake pool.
+    /// SECURITY: PDA validation.
+    #[account(
+        mut,
+        seeds = [STAKE_POOL_SEED, stake_pool.staking_mint.as_ref()],
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ StakingError::AccountVersionMismatch,
+        bump = stake_pool.bump
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+}
+
+/// Apply every queued APY, Permanent APY, decay, emission cap or rewards end
+/// time change whose timelock has elapsed.
+///
+/// # Security
+/// - Changes still inside their timelock stay queued
+/// - A queued cap below what has since been distributed fails with
+///   `InvalidEmissionCap` and stays queued until cancelled
+///
+/// # Arguments
+/// * `ctx` - ApplyPendingParams accounts context
+///
+/// # Returns
+/// Result indicating success or error
+pub fn apply_handler(ctx: Context<ApplyPendingParams>) -> Result<()> {
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let now = Clock::get()?.unix_timestamp;
+
+    require!(stake_pool.has_pending_params(), StakingError::NoPendingChange);
+
+    let apy_due = stake_pool.pending_apy_effective_at != 0
+        && now >= stake_pool.pending_apy_effective_at;
+    let permanent_due = stake_pool.pending_permanent_apy_effective_at != 0
+        && now >= stake_pool.pending_permanent_apy_effective_at;
+    let decay_due = stake_pool.pending_decay_effective_at != 0
+        && now >= stake_pool.pending_decay_effective_at;
+    let cap_due = stake_pool.pending_emission_cap_effective_at != 0
+        && now >= stake_pool.pending_emission_cap_effective_at;
+    let end_due = stake_pool.pending_rewards_end_time_effective_at != 0
+        && now >= stake_pool.pending_rewards_end_time_effective_at;
+    require!(
+        apy_due || permanent_due || decay_due || cap_due || end_due,
+        StakingError::ParamTimelockActive
+    );
+    stake_pool.next_sequence()?;
+
+    if apy_due {
+        let [flex_apy, core_apy, prime_apy] = stake_pool.pending_apy;
+        apply_apy(stake_pool, flex_apy, core_apy, prime_apy, now)?;
+        stake_pool.pending_apy = [0; 3];
+        stake_pool.pending_apy_effective_at = 0;
+    }
+    if permanent_due {
+        let permanent_apy = stake_pool.pending_permanent_apy;
+        apply_permanent_apy(stake_pool, permanent_apy, now)?;
+        stake_pool.pending_permanent_apy = 0;
+        stake_pool.pending_permanent_apy_effective_at = 0;
+    }
+    if decay_due {
+        let interval_secs = stake_pool.pending_decay_interval_seconds;
+        let bps_per_interval = stake_pool.pending_decay_bps_per_interval;
+        let min_apy_floor = stake_pool.pending_min_apy_floor;
+        apply_apy_decay(stake_pool, interval_secs, bps_per_interval, min_apy_floor, now)?;
+        stake_pool.clear_pending_decay();
+    }
+    if cap_due {
+        let new_cap = stake_pool.pending_emission_cap;
+        apply_emission_cap(stake_pool, new_cap, now)?;
+        stake_pool.pending_emission_cap = 0;
+        stake_pool.pending_emission_cap_effective_at = 0;
+    }
+    if end_due {
+        let end_time = stake_pool.pending_rewards_end_time;
+        apply_rewards_end_time(stake_pool, end_time, now)?;
+        stake_pool.pending_rewards_end_time = 0;
+        stake_pool.pending_rewards_end_time_effective_at = 0;
+    }
+
+    Ok(())
+}
+
+/// Drop every queued APY, Permanent APY, decay, emission cap and rewards end
+/// time change.
+///
+/// # Security
+/// - Only pool.authority can call this
+/// - Multisig pools must go through `propose_admin_action`, so one key
+///   cannot undo a change the multisig approved
+///
+/// # Arguments
+/// * `ctx` - AdminControl accounts context
+///
+/// # Returns
+/// Result indicating success or error
+pub fn cancel_handler(ctx: Context<AdminControl>) -> Result<()> {
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    require!(!stake_pool.has_admin_multisig(), StakingError::MultisigRequired);
+    stake_pool.next_sequence()?;
+    cancel_pending_params(stake_pool, clock.unix_timestamp)?;
+    msg!("Admin: {}", ctx.accounts.authority.key());
+
+    Ok(())
+}
+
+/// Clear every queued change; shared with the multisig path.
+pub fn cancel_pending_params(stake_pool: &mut StakePool, now: i64) -> Result<()> {
+    require!(stake_pool.has_pending_params(), StakingError::NoPendingChange);
+
+    stake_pool.pending_apy = [0; 3];
+    stake_pool.pending_apy_effective_at = 0;
+    stake_pool.pending_permanent_apy = 0;
+    stake_pool.pending_permanent_apy_effective_at = 0;
+    stake_pool.clear_pending_decay();
+    stake_pool.pending_emission_cap = 0;
+    stake_pool.pending_emission_cap_effective_at = 0;
+    stake_pool.pending_rewards_end_time = 0;
+    stake_pool.pending_rewards_end_time_effective_at = 0;
+    stake_pool.last_updated = now;
+
+    msg!("Pending APY, Permanent APY, decay, emission cap and end time changes cancelled");
+
+    Ok(())
+}
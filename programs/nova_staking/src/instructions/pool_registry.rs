@@ -0,0 +1,86 @@
+/// Pool registry instruction handlers.
+///
+/// A singleton PDA listing every pool, appended to by `initialize`. Frontends
+/// read it to enumerate pools, since pool PDAs are keyed by mint.
+///
+/// ## Security Guarantees
+/// - The registry PDA has fixed seeds, so only one can exist
+/// - Only the registry authority can remove entries
+/// - Removing an entry does not touch the pool itself
+
+use anchor_lang::prelude::*;
+
+use crate::constants::*;
+use crate::error::StakingError;
+use crate::state::PoolRegistry;
+
+/// Accounts required for creating the registry.
+#[derive(Accounts)]
+pub struct InitializeRegistry<'info> {
+    /// Becomes the registry authority; pays rent.
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// The registry PDA.
+    /// SECURITY: Fixed seeds; `init` fails if it already exists.
+    #[account(
+        init,
+        payer = authority,
+        space = PoolRegistry::INITIAL_LEN,
+        seeds = [POOL_REGISTRY_SEED],
+        bump
+    )]
+    pub pool_registry: Box<Account<'info, PoolRegistry>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required for registry maintenance.
+#[derive(Accounts)]
+pub struct RegistryControl<'info> {
+    /// SECURITY: Must be signer AND match pool_registry.authority.
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [POOL_REGISTRY_SEED],
+        bump = pool_registry.bump,
+        has_one = authority @ StakingError::Unauthorized
+    )]
+    pub pool_registry: Box<Account<'info, PoolRegistry>>,
+}
+
+/// Create the pool registry. One-time; pools created before it exists
+/// cannot be initialized, so run this right after deploying.
+pub fn initialize_registry_handler(ctx: Context<InitializeRegistry>) -> Result<()> {
+    let registry = &mut ctx.accounts.pool_registry;
+    registry.authority = ctx.accounts.authority.key();
+    registry.capacity = REGISTRY_INITIAL_CAPACITY;
+    registry.entries = Vec::new();
+    registry.bump = ctx.bumps.pool_registry;
+
+    msg!("Pool registry created with capacity {}", REGISTRY_INITIAL_CAPACITY);
+    msg!("Admin: {}", ctx.accounts.authority.key());
+
+    Ok(())
+}
+
+/// Remove a decommissioned pool from the registry.
+///
+/// # Arguments
+/// * `stake_pool` - Pool to drop; fails with `PoolNotRegistered` if absent
+pub fn remove_from_registry_handler(
+    ctx: Context<RegistryControl>,
+    stake_pool: Pubkey,
+) -> Result<()> {
+    let removed = ctx.accounts.pool_registry.remove(&stake_pool)?;
+
+    msg!(
+        "Pool {} (mint {}) removed from registry",
+        removed.stake_pool,
+        removed.staking_mint
+    );
+    msg!("Admin: {}", ctx.accounts.authority.key());
+
+    Ok(())
+}
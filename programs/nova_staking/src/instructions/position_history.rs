@@ -0,0 +1,117 @@
+/// Position history instruction handlers.
+///
+/// Opt-in per-user ring buffer of principal movements (stake, unstake, tier
+/// changes), giving users an audit trail without an indexer.
+///
+/// ## Security Guarantees
+/// - Only the position owner can create their history, and pays its rent
+/// - Handlers derive the history PDA from the signer, so nobody can append
+///   to someone else's history
+/// - Viewing is read-only
+
+use anchor_lang::prelude::*;
+
+use crate::constants::*;
+use crate::error::StakingError;
+use crate::state::{PositionHistory, PositionHistoryPage, StakePool, UserStake};
+
+/// Accounts required for enabling position history.
+///
+/// ## Security Notes
+/// - User must be signer AND own the stake position
+/// - PDA seeds bind the history to (pool, user)
+#[derive(Accounts)]
+pub struct EnablePositionHistory<'info> {
+    /// The position owner; pays rent for the history account.
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// The stake pool.
+    /// SECURITY: PDA validation.
+    #[account(
+        seeds = [STAKE_POOL_SEED, stake_pool.staking_mint.as_ref()],
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ StakingError::AccountVersionMismatch,
+        bump = stake_pool.bump
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+
+    /// User's stake account.
+    /// SECURITY: PDA + owner + pool validation.
+    #[account(
+        seeds = [USER_STAKE_SEED, stake_pool.key().as_ref(), user.key().as_ref()],
+        bump = user_stake.bump,
+        constraint = user_stake.version == USER_STAKE_VERSION @ StakingError::AccountVersionMismatch,
+        constraint = user_stake.owner == user.key() @ StakingError::InvalidStakeOwner,
+        constraint = user_stake.stake_pool == stake_pool.key() @ StakingError::StakePoolMismatch
+    )]
+    pub user_stake: Account<'info, UserStake>,
+
+    /// New position history PDA.
+    #[account(
+        init,
+        payer = user,
+        space = PositionHistory::LEN,
+        seeds = [POSITION_HISTORY_SEED, stake_pool.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub position_history: Box<Account<'info, PositionHistory>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required for reading position history.
+#[derive(Accounts)]
+pub struct ViewPositionHistory<'info> {
+    /// The stake pool.
+    /// SECURITY: PDA validation.
+    #[account(
+        seeds = [STAKE_POOL_SEED, stake_pool.staking_mint.as_ref()],
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ StakingError::AccountVersionMismatch,
+        bump = stake_pool.bump
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+
+    /// History to read.
+    #[account(
+        constraint = position_history.stake_pool == stake_pool.key() @ StakingError::StakePoolMismatch
+    )]
+    pub position_history: Box<Account<'info, PositionHistory>>,
+}
+
+/// Create the caller's position history account.
+///
+/// # Security
+/// - Rent paid by the user, so other stakers' costs are unchanged
+/// - Records start with the next stake/unstake/change_tier that passes this account
+///
+/// # Arguments
+/// * `ctx` - EnablePositionHistory accounts context
+///
+/// # Returns
+/// Result indicating success or error
+pub fn enable_handler(ctx: Context<EnablePositionHistory>) -> Result<()> {
+    let position_history = &mut ctx.accounts.position_history;
+
+    position_history.owner = ctx.accounts.user.key();
+    position_history.stake_pool = ctx.accounts.stake_pool.key();
+    position_history.head = 0;
+    position_history.count = 0;
+    position_history.total_recorded = 0;
+    position_history.bump = ctx.bumps.position_history;
+
+    msg!("Position history enabled for {}", position_history.owner);
+
+    Ok(())
+}
+
+/// Return one page of position records, oldest first.
+///
+/// # Arguments
+/// * `ctx` - ViewPositionHistory accounts context
+/// * `page` - Page index (POSITION_HISTORY_PAGE_SIZE records per page)
+///
+/// # Returns
+/// The requested page, delivered via return data
+pub fn view_handler(ctx: Context<ViewPositionHistory>, page: u16) -> Result<PositionHistoryPage> {
+    Ok(ctx.accounts.position_history.page(page))
+}
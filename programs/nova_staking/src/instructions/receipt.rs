@@ -0,0 +1,93 @@
+/// Receipt token helpers.
+///
+/// In receipt mode every token of principal that enters a position mints one
+/// receipt token, and every token that leaves burns one. Receipts are plain
+/// SPL tokens, so they can be transferred and used as collateral elsewhere;
+/// the position, its rewards and its claims stay with the `UserStake` owner.
+///
+/// ## Security Guarantees
+/// - Only the stake pool PDA can mint receipts
+/// - Burns are signed by the holder presenting the receipts, before any
+///   principal leaves the vault
+/// - Without receipts the principal stays locked, whoever owns the position
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Burn, Mint, MintTo, Token, TokenAccount};
+
+use crate::constants::*;
+use crate::error::StakingError;
+use crate::state::StakePool;
+
+/// Receipt accounts an instruction was given, when the pool is in receipt
+/// mode. Account constraints have already tied `mint` to the pool.
+fn receipt_accounts<'a, 'info>(
+    stake_pool: &StakePool,
+    receipt_mint: Option<&'a Account<'info, Mint>>,
+    receipt_account: Option<&'a Account<'info, TokenAccount>>,
+) -> Result<Option<(&'a Account<'info, Mint>, &'a Account<'info, TokenAccount>)>> {
+    if !stake_pool.has_receipt_mint() {
+        return Ok(None);
+    }
+    match (receipt_mint, receipt_account) {
+        (Some(mint), Some(account)) => Ok(Some((mint, account))),
+        _ => err!(StakingError::MissingReceiptAccounts),
+    }
+}
+
+/// Mint `amount` receipts into `receipt_account`; a no-op outside receipt mode.
+pub fn mint_receipts<'info>(
+    stake_pool: &Account<'info, StakePool>,
+    receipt_mint: Option<&Account<'info, Mint>>,
+    receipt_account: Option<&Account<'info, TokenAccount>>,
+    token_program: &Program<'info, Token>,
+    amount: u64,
+) -> Result<()> {
+    let Some((mint, account)) = receipt_accounts(stake_pool, receipt_mint, receipt_account)? else {
+        return Ok(());
+    };
+
+    let staking_mint_key = stake_pool.staking_mint;
+    let seeds = &[
+        STAKE_POOL_SEED,
+        staking_mint_key.as_ref(),
+        &[stake_pool.bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+
+    let cpi_accounts = MintTo {
+        mint: mint.to_account_info(),
+        to: account.to_account_info(),
+        authority: stake_pool.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(token_program.to_account_info(), cpi_accounts, signer_seeds);
+    token::mint_to(cpi_ctx, amount)?;
+
+    msg!("Minted {} receipt tokens", amount);
+    Ok(())
+}
+
+/// Burn `amount` receipts from `receipt_account`, signed by `holder`; a no-op
+/// outside receipt mode.
+pub fn burn_receipts<'info>(
+    stake_pool: &StakePool,
+    receipt_mint: Option<&Account<'info, Mint>>,
+    receipt_account: Option<&Account<'info, TokenAccount>>,
+    holder: AccountInfo<'info>,
+    token_program: &Program<'info, Token>,
+    amount: u64,
+) -> Result<()> {
+    let Some((mint, account)) = receipt_accounts(stake_pool, receipt_mint, receipt_account)? else {
+        return Ok(());
+    };
+    require!(account.amount >= amount, StakingError::InsufficientReceipts);
+
+    let cpi_accounts = Burn {
+        mint: mint.to_account_info(),
+        from: account.to_account_info(),
+        authority: holder,
+    };
+    token::burn(CpiContext::new(token_program.to_account_info(), cpi_accounts), amount)?;
+
+    msg!("Burned {} receipt tokens", amount);
+    Ok(())
+}
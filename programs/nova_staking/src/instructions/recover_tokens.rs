@@ -0,0 +1,158 @@
+/// Recover tokens instruction handler.
+///
+/// Lets the authority return tokens sent to the pool by mistake: any token
+/// account owned by the stake_pool PDA, plus anything donated to the staking
+/// vault on top of staker principal.
+///
+/// ## Security Guarantees
+/// - Admin-only
+/// - Reward-mint accounts are never touched; the treasury has
+///   `withdraw_treasury` and its liability floor
+/// - Staking-mint tokens only come out of the staking vault, and only above
+///   `total_staked` plus queued withdrawals, so principal stays in place
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::set_return_data;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+
+use crate::constants::*;
+use crate::error::StakingError;
+use crate::events::TokensRecovered;
+use crate::state::StakePool;
+
+/// Accounts required for recovering stray tokens.
+///
+/// ## Security Notes
+/// - Authority must be signer AND match stake_pool.authority
+/// - Source must be owned by the stake_pool PDA; which ones may be drained
+///   is checked in the handler
+/// - Destination only needs the source's mint; the authority picks the owner
+#[derive(Accounts)]
+pub struct RecoverTokens<'info> {
+    /// The admin authority.
+    /// SECURITY: Must be signer AND match pool.authority.
+    #[account(
+        constraint = authority.key() == stake_pool.authority @ StakingError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    /// The stake pool.
+    /// SECURITY: PDA + has_one validations.
+    #[account(
+        mut,
+        seeds = [STAKE_POOL_SEED, stake_pool.staking_mint.as_ref()],
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ StakingError::AccountVersionMismatch,
+        bump = stake_pool.bump,
+        has_one = authority @ StakingError::Unauthorized
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+
+    /// Pool-owned token account holding the tokens.
+    /// SECURITY: Owner validation.
+    #[account(
+        mut,
+        constraint = source.owner == stake_pool.key() @ StakingError::InvalidVaultOwner
+    )]
+    pub source: Account<'info, TokenAccount>,
+
+    /// Token account receiving the tokens.
+    /// SECURITY: Mint validation.
+    #[account(
+        mut,
+        constraint = destination.mint == source.mint @ StakingError::InvalidTokenAccountMint
+    )]
+    pub destination: Account<'info, TokenAccount>,
+
+    /// Token program.
+    pub token_program: Program<'info, Token>,
+}
+
+/// Transfer stray tokens out of a pool-owned account.
+///
+/// # Security
+/// - The treasury fails with `RecoveryNotAllowed`, and so does every
+///   reward-mint account in a dual-token pool
+/// - Staking-mint accounts other than the staking vault (the reserve, and
+///   the fee vault in single-mint pools) fail with `RecoveryNotAllowed`
+/// - The staking vault must keep `StakePool::staking_vault_principal`; on
+///   rejection the recoverable surplus is written to return data
+/// - PDA signer for the transfer
+///
+/// # Arguments
+/// * `ctx` - RecoverTokens accounts context
+/// * `amount` - Amount of tokens to recover
+///
+/// # Returns
+/// Result indicating success or error
+pub fn handler(ctx: Context<RecoverTokens>, amount: u64) -> Result<()> {
+    let stake_pool = &ctx.accounts.stake_pool;
+    let source = &ctx.accounts.source;
+    let clock = Clock::get()?;
+
+    // === INPUT VALIDATION ===
+
+    require!(amount > 0, StakingError::ZeroAmount);
+    // In a single-mint pool the reward mint is the staking mint, whose
+    // surplus in the staking vault is exactly what may be recovered
+    require!(
+        source.key() != stake_pool.treasury_vault
+            && !(stake_pool.is_dual_token() && source.mint == stake_pool.reward_mint),
+        StakingError::RecoveryNotAllowed
+    );
+
+    // === PRINCIPAL FLOOR ===
+
+    let recoverable = if source.mint == stake_pool.staking_mint {
+        require!(
+            source.key() == stake_pool.staking_vault,
+            StakingError::RecoveryNotAllowed
+        );
+        source.amount.saturating_sub(stake_pool.staking_vault_principal())
+    } else {
+        source.amount
+    };
+    if amount > recoverable {
+        set_return_data(&recoverable.to_le_bytes());
+        msg!("Recoverable balance: {}", recoverable);
+        return err!(StakingError::RecoveryExceedsSurplus);
+    }
+
+    // === PDA SIGNER TRANSFER ===
+
+    let staking_mint_key = stake_pool.staking_mint;
+    let seeds = &[
+        STAKE_POOL_SEED,
+        staking_mint_key.as_ref(),
+        &[stake_pool.bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+
+    let cpi_accounts = Transfer {
+        from: ctx.accounts.source.to_account_info(),
+        to: ctx.accounts.destination.to_account_info(),
+        authority: ctx.accounts.stake_pool.to_account_info(),
+    };
+    let cpi_program = ctx.accounts.token_program.to_account_info();
+    let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+    token::transfer(cpi_ctx, amount)?;
+
+    // === STATE UPDATE ===
+
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+
+    emit!(TokensRecovered {
+        stake_pool: stake_pool.key(),
+        source: ctx.accounts.source.key(),
+        destination: ctx.accounts.destination.key(),
+        mint: ctx.accounts.source.mint,
+        amount,
+        sequence: stake_pool.sequence,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!("Recovered {} tokens of mint {}", amount, ctx.accounts.source.mint);
+
+    Ok(())
+}
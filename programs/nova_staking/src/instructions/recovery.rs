@@ -0,0 +1,329 @@
+/// Recovery address instruction handlers.
+///
+/// An owner can name a recovery address that may take over the position if
+/// the owner's key is lost. The recovery address starts a timer with
+/// `initiate_recovery` and, once `recovery_delay_seconds` have passed, moves
+/// the position to itself with `complete_recovery`. Any owner-signed
+/// instruction on the position in the meantime cancels the attempt, so a
+/// recovery address can only take over a position whose owner stays silent.
+///
+/// ## Security Guarantees
+/// - Only the owner sets, clears or cancels; only the recovery address
+///   initiates and completes
+/// - Every step emits an event so the owner can be alerted
+/// - Completion moves the position like `transfer_stake_ownership`; no
+///   tokens move and pool totals are untouched
+
+use anchor_lang::prelude::*;
+
+use crate::constants::*;
+use crate::error::StakingError;
+use crate::events::{RecoveryAddressSet, RecoveryCancelled, RecoveryCompleted, RecoveryInitiated};
+use crate::instructions::transfer_stake::move_position;
+use crate::state::{StakePool, UserStake};
+
+/// Accounts required for the owner's recovery settings.
+///
+/// ## Security Notes
+/// - User must be signer AND match user_stake.owner
+#[derive(Accounts)]
+pub struct SetRecoveryAddress<'info> {
+    /// The position owner.
+    /// SECURITY: Must be signer and match stake owner.
+    pub user: Signer<'info>,
+
+    /// The stake pool; writable for `sequence`.
+    /// SECURITY: PDA validation.
+    #[account(
+        mut,
+        seeds = [STAKE_POOL_SEED, stake_pool.staking_mint.as_ref()],
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ StakingError::AccountVersionMismatch,
+        bump = stake_pool.bump
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+
+    /// User's stake account.
+    /// SECURITY: PDA + owner + pool validation.
+    #[account(
+        mut,
+        seeds = [USER_STAKE_SEED, stake_pool.key().as_ref(), user.key().as_ref()],
+        bump = user_stake.bump,
+        constraint = user_stake.version == USER_STAKE_VERSION @ StakingError::AccountVersionMismatch,
+        constraint = user_stake.owner == user.key() @ StakingError::InvalidStakeOwner,
+        constraint = user_stake.stake_pool == stake_pool.key() @ StakingError::StakePoolMismatch
+    )]
+    pub user_stake: Account<'info, UserStake>,
+}
+
+/// Accounts required for starting a recovery.
+///
+/// ## Security Notes
+/// - Signer must be the position's stored recovery address
+#[derive(Accounts)]
+pub struct InitiateRecovery<'info> {
+    /// The recovery address.
+    /// SECURITY: Must be signer and match user_stake.recovery_address.
+    pub recovery_authority: Signer<'info>,
+
+    /// The stake pool; writable for `sequence`.
+    /// SECURITY: PDA validation.
+    #[account(
+        mut,
+        seeds = [STAKE_POOL_SEED, stake_pool.staking_mint.as_ref()],
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ StakingError::AccountVersionMismatch,
+        bump = stake_pool.bump
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+
+    /// Position being recovered.
+    /// SECURITY: PDA + pool + recovery address validation.
+    #[account(
+        mut,
+        seeds = [USER_STAKE_SEED, stake_pool.key().as_ref(), user_stake.owner.as_ref()],
+        bump = user_stake.bump,
+        constraint = user_stake.version == USER_STAKE_VERSION @ StakingError::AccountVersionMismatch,
+        constraint = user_stake.stake_pool == stake_pool.key() @ StakingError::StakePoolMismatch,
+        constraint = user_stake.recovery_address == recovery_authority.key() @ StakingError::NotRecoveryAddress
+    )]
+    pub user_stake: Account<'info, UserStake>,
+}
+
+/// Accounts required for completing a recovery.
+///
+/// ## Security Notes
+/// - Signer must be the position's stored recovery address
+/// - The old position is closed to its owner, who need not sign
+#[derive(Accounts)]
+pub struct CompleteRecovery<'info> {
+    /// The recovery address; pays for its new position account.
+    /// SECURITY: Must be signer and match user_stake.recovery_address.
+    #[account(mut)]
+    pub recovery_authority: Signer<'info>,
+
+    /// The old owner; receives the old account's rent.
+    /// CHECK: Address-checked against user_stake.owner.
+    #[account(mut, address = user_stake.owner @ StakingError::InvalidStakeOwner)]
+    pub owner: UncheckedAccount<'info>,
+
+    /// The stake pool; writable for `sequence`.
+    /// SECURITY: PDA validation.
+    #[account(
+        mut,
+        seeds = [STAKE_POOL_SEED, stake_pool.staking_mint.as_ref()],
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ StakingError::AccountVersionMismatch,
+        bump = stake_pool.bump
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+
+    /// Position being recovered, closed to the old owner.
+    /// SECURITY: PDA + pool + recovery address validation.
+    #[account(
+        mut,
+        close = owner,
+        seeds = [USER_STAKE_SEED, stake_pool.key().as_ref(), user_stake.owner.as_ref()],
+        bump = user_stake.bump,
+        constraint = user_stake.version == USER_STAKE_VERSION @ StakingError::AccountVersionMismatch,
+        constraint = user_stake.stake_pool == stake_pool.key() @ StakingError::StakePoolMismatch,
+        constraint = user_stake.recovery_address == recovery_authority.key() @ StakingError::NotRecoveryAddress
+    )]
+    pub user_stake: Account<'info, UserStake>,
+
+    /// The recovery address's position, created if it does not exist yet.
+    /// SECURITY: PDA derived from pool + recovery address.
+    #[account(
+        init_if_needed,
+        payer = recovery_authority,
+        space = UserStake::LEN,
+        seeds = [USER_STAKE_SEED, stake_pool.key().as_ref(), recovery_authority.key().as_ref()],
+        bump
+    )]
+    pub new_user_stake: Account<'info, UserStake>,
+
+    /// System program.
+    pub system_program: Program<'info, System>,
+}
+
+/// Set or clear the signer's recovery address.
+///
+/// # Security
+/// - A default `recovery_address` clears it; otherwise it must differ from
+///   the owner and `delay_seconds` must be positive (`InvalidRecoveryConfig`)
+/// - Cancels any pending recovery, like every owner-signed instruction
+///
+/// # Arguments
+/// * `ctx` - SetRecoveryAddress accounts context
+/// * `recovery_address` - Key allowed to recover the position
+/// * `delay_seconds` - Time the owner has to cancel a started recovery
+///
+/// # Returns
+/// Result indicating success or error
+pub fn set_handler(
+    ctx: Context<SetRecoveryAddress>,
+    recovery_address: Pubkey,
+    delay_seconds: i64,
+) -> Result<()> {
+    let user_stake = &mut ctx.accounts.user_stake;
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    let delay_seconds = if recovery_address == Pubkey::default() {
+        0
+    } else {
+        require!(
+            recovery_address != user_stake.owner && delay_seconds > 0,
+            StakingError::InvalidRecoveryConfig
+        );
+        delay_seconds
+    };
+
+    stake_pool.next_sequence()?;
+    cancel_on_owner_signature(stake_pool, user_stake, clock.unix_timestamp);
+    user_stake.recovery_address = recovery_address;
+    user_stake.recovery_delay_seconds = delay_seconds;
+
+    emit!(RecoveryAddressSet {
+        stake_pool: stake_pool.key(),
+        owner: user_stake.owner,
+        recovery_address,
+        delay_seconds,
+        sequence: stake_pool.sequence,
+        timestamp: clock.unix_timestamp,
+    });
+
+    if recovery_address == Pubkey::default() {
+        msg!("Recovery address cleared");
+    } else {
+        msg!("Recovery address: {} after {}s", recovery_address, delay_seconds);
+    }
+
+    Ok(())
+}
+
+/// Cancel a pending recovery on the signer's position.
+///
+/// # Security
+/// - Fails with `NoPendingRecovery` when nothing is pending
+/// - The recovery address stays set; it may start a new recovery later
+///
+/// # Arguments
+/// * `ctx` - SetRecoveryAddress accounts context
+///
+/// # Returns
+/// Result indicating success or error
+pub fn cancel_handler(ctx: Context<SetRecoveryAddress>) -> Result<()> {
+    let user_stake = &mut ctx.accounts.user_stake;
+    let stake_pool = &mut ctx.accounts.stake_pool;
+
+    require!(user_stake.has_pending_recovery(), StakingError::NoPendingRecovery);
+
+    stake_pool.next_sequence()?;
+    cancel_on_owner_signature(stake_pool, user_stake, Clock::get()?.unix_timestamp);
+
+    Ok(())
+}
+
+/// Start recovering a position.
+///
+/// # Security
+/// - Only the stored recovery address can call this
+/// - Fails with `RecoveryAlreadyPending` while one is running, so the
+///   deadline the owner was alerted to never moves
+///
+/// # Arguments
+/// * `ctx` - InitiateRecovery accounts context
+///
+/// # Returns
+/// Result indicating success or error
+pub fn initiate_handler(ctx: Context<InitiateRecovery>) -> Result<()> {
+    let user_stake = &mut ctx.accounts.user_stake;
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    require!(!user_stake.has_pending_recovery(), StakingError::RecoveryAlreadyPending);
+
+    user_stake.recovery_started_at = clock.unix_timestamp;
+    let ready_at = clock
+        .unix_timestamp
+        .checked_add(user_stake.recovery_delay_seconds)
+        .ok_or(StakingError::MathOverflow)?;
+    stake_pool.next_sequence()?;
+
+    emit!(RecoveryInitiated {
+        stake_pool: stake_pool.key(),
+        owner: user_stake.owner,
+        recovery_address: user_stake.recovery_address,
+        ready_at,
+        sequence: stake_pool.sequence,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!("Recovery of {}'s position started, ready at {}", user_stake.owner, ready_at);
+
+    Ok(())
+}
+
+/// Move a position to its recovery address once the delay has passed.
+///
+/// # Security
+/// - Fails with `NoPendingRecovery` if the owner cancelled in the meantime,
+///   and `RecoveryDelayNotElapsed` before `recovery_delay_seconds`
+/// - The recovery address is treated like a new owner in
+///   `transfer_stake_ownership`: it may not hold an open position itself
+///
+/// # Arguments
+/// * `ctx` - CompleteRecovery accounts context
+///
+/// # Returns
+/// Result indicating success or error
+pub fn complete_handler(ctx: Context<CompleteRecovery>) -> Result<()> {
+    let clock = Clock::get()?;
+    let source = &ctx.accounts.user_stake;
+
+    require!(source.has_pending_recovery(), StakingError::NoPendingRecovery);
+    require!(
+        source.is_recovery_ready(clock.unix_timestamp),
+        StakingError::RecoveryDelayNotElapsed
+    );
+
+    let old_owner = source.owner;
+    let recovery_address = source.recovery_address;
+    move_position(
+        source,
+        &mut ctx.accounts.new_user_stake,
+        recovery_address,
+        ctx.bumps.new_user_stake,
+        clock.unix_timestamp,
+    )?;
+
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    stake_pool.next_sequence()?;
+
+    emit!(RecoveryCompleted {
+        stake_pool: stake_pool.key(),
+        old_owner,
+        recovery_address,
+        staked_amount: ctx.accounts.new_user_stake.staked_amount,
+        sequence: stake_pool.sequence,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!("Position of {} recovered by {}", old_owner, recovery_address);
+
+    Ok(())
+}
+
+/// Cancel a pending recovery because the owner just signed for the
+/// position. Call after the instruction's `next_sequence`, so the event
+/// carries its number.
+pub fn cancel_on_owner_signature(stake_pool: &Account<StakePool>, user_stake: &mut UserStake, now: i64) {
+    if let Some(recovery_address) = user_stake.cancel_recovery() {
+        emit!(RecoveryCancelled {
+            stake_pool: stake_pool.key(),
+            owner: user_stake.owner,
+            recovery_address,
+            sequence: stake_pool.sequence,
+            timestamp: now,
+        });
+        msg!("Pending recovery by {} cancelled", recovery_address);
+    }
+}
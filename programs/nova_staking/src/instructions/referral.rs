@@ -0,0 +1,157 @@
+/// Referral instruction handlers.
+///
+/// A staker names their referrer on their first `stake`. Each later claim
+/// credits the referrer a share of the payout in their `ReferralAccount`,
+/// which the referrer withdraws here.
+///
+/// ## Security Guarantees
+/// - Only the referrer can withdraw their credited share
+/// - Credited shares were counted against the emission cap when credited
+/// - The treasury keeps `referral_liability` back from other claims, so
+///   credited shares stay payable
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::set_return_data;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+
+use crate::constants::*;
+use crate::error::StakingError;
+use crate::events::ReferralRewardsClaimed;
+use crate::state::{ReferralAccount, StakePool};
+
+/// Accounts required for withdrawing referral rewards.
+///
+/// ## Security Notes
+/// - Referrer must be signer; the referral account is derived from their key
+/// - Treasury must match pool's treasury vault
+#[derive(Accounts)]
+pub struct ClaimReferralRewards<'info> {
+    /// The referrer withdrawing their share.
+    pub referrer: Signer<'info>,
+
+    /// The stake pool.
+    /// SECURITY: PDA + has_one validations.
+    #[account(
+        mut,
+        seeds = [STAKE_POOL_SEED, stake_pool.staking_mint.as_ref()],
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ StakingError::AccountVersionMismatch,
+        bump = stake_pool.bump,
+        has_one = treasury_vault @ StakingError::TreasuryMismatch,
+        has_one = staking_mint @ StakingError::MintMismatch
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+
+    /// Referrer's balance in this pool.
+    /// SECURITY: PDA bound to (pool, signer).
+    #[account(
+        mut,
+        seeds = [REFERRAL_SEED, stake_pool.key().as_ref(), referrer.key().as_ref()],
+        bump = referral_account.bump
+    )]
+    pub referral_account: Account<'info, ReferralAccount>,
+
+    /// The staking token mint.
+    /// SECURITY: Must match pool's locked mint.
+    #[account(
+        constraint = staking_mint.key() == stake_pool.staking_mint @ StakingError::MintMismatch
+    )]
+    pub staking_mint: Account<'info, Mint>,
+
+    /// Referrer's token account for receiving the share.
+    /// SECURITY: Reward mint and owner validation.
+    #[account(
+        mut,
+        constraint = referrer_token_account.mint == stake_pool.reward_mint @ StakingError::MintMismatch,
+        constraint = referrer_token_account.owner == referrer.key() @ StakingError::UnauthorizedStakeAccess
+    )]
+    pub referrer_token_account: Account<'info, TokenAccount>,
+
+    /// Pool's treasury vault holding rewards.
+    /// SECURITY: Must match pool's stored treasury + owner validation.
+    #[account(
+        mut,
+        constraint = treasury_vault.key() == stake_pool.treasury_vault @ StakingError::TreasuryMismatch,
+        constraint = treasury_vault.owner == stake_pool.key() @ StakingError::InvalidTreasuryOwner,
+        constraint = treasury_vault.mint == stake_pool.reward_mint @ StakingError::InvalidTokenAccountMint
+    )]
+    pub treasury_vault: Account<'info, TokenAccount>,
+
+    /// Token program.
+    pub token_program: Program<'info, Token>,
+}
+
+/// Withdraw the referral share credited to the signer.
+///
+/// # Security
+/// - Pays the full unclaimed balance; nothing new counts against the emission cap
+/// - Blocked in emergency mode like `claim_rewards`
+/// - PDA signer for treasury transfer
+///
+/// # Arguments
+/// * `ctx` - ClaimReferralRewards accounts context
+///
+/// # Returns
+/// Result indicating success or error; the amount paid is written to return data
+pub fn claim_referral_rewards_handler(ctx: Context<ClaimReferralRewards>) -> Result<()> {
+    let stake_pool = &ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    // Emergency mode blocks everything except principal exits
+    require!(!stake_pool.emergency, StakingError::EmergencyMode);
+    require!(!stake_pool.is_claim_paused(), StakingError::ClaimsPaused);
+
+    let amount = ctx.accounts.referral_account.unclaimed;
+    require!(amount > 0, StakingError::NoRewardsAvailable);
+    require!(
+        ctx.accounts.treasury_vault.amount >= amount,
+        StakingError::InsufficientTreasuryFunds
+    );
+
+    // === PDA SIGNER TRANSFER ===
+
+    let staking_mint_key = stake_pool.staking_mint;
+    let seeds = &[
+        STAKE_POOL_SEED,
+        staking_mint_key.as_ref(),
+        &[stake_pool.bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+
+    let cpi_accounts = Transfer {
+        from: ctx.accounts.treasury_vault.to_account_info(),
+        to: ctx.accounts.referrer_token_account.to_account_info(),
+        authority: ctx.accounts.stake_pool.to_account_info(),
+    };
+    let cpi_program = ctx.accounts.token_program.to_account_info();
+    let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+    token::transfer(cpi_ctx, amount)?;
+
+    // === STATE UPDATE ===
+
+    let referral_account = &mut ctx.accounts.referral_account;
+    let stake_pool = &mut ctx.accounts.stake_pool;
+
+    referral_account.unclaimed = 0;
+    stake_pool.referral_liability = stake_pool
+        .referral_liability
+        .checked_sub(amount)
+        .ok_or(StakingError::MathUnderflow)?;
+    stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+
+    emit!(ReferralRewardsClaimed {
+        stake_pool: stake_pool.key(),
+        referrer: referral_account.referrer,
+        amount,
+        lifetime_earnings: referral_account.lifetime_earnings,
+        sequence: stake_pool.sequence,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!("Referral rewards claimed: {}", amount);
+    msg!("Lifetime referral earnings: {}", referral_account.lifetime_earnings);
+
+    set_return_data(&amount.to_le_bytes());
+
+    Ok(())
+}
@@ -0,0 +1,103 @@
+/// Refresh pool instruction handler.
+///
+/// Pool aggregates otherwise only move when someone stakes, claims or
+/// unstakes. This crank stores a fresh snapshot of them so dashboards and
+/// liability projections stay current on quiet days.
+///
+/// ## Security Guarantees
+/// - Permissionless; no signer beyond the fee payer
+/// - Only writes the snapshot fields and `last_updated`; no token movement,
+///   no reward accrual change
+/// - Constant cost, so it can run every few minutes
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::TokenAccount;
+
+use crate::constants::*;
+use crate::error::StakingError;
+use crate::events::PoolSnapshot;
+use crate::state::StakePool;
+
+/// Accounts required for refreshing pool statistics.
+///
+/// ## Security Notes
+/// - Permissionless; the snapshot is derived from on-chain state only
+/// - Treasury must match pool's treasury vault
+#[derive(Accounts)]
+pub struct RefreshPool<'info> {
+    /// The stake pool.
+    /// SECURITY: PDA + has_one validation.
+    #[account(
+        mut,
+        seeds = [STAKE_POOL_SEED, stake_pool.staking_mint.as_ref()],
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ StakingError::AccountVersionMismatch,
+        bump = stake_pool.bump,
+        has_one = treasury_vault @ StakingError::TreasuryMismatch
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+
+    /// Pool's treasury vault, read for the balance snapshot.
+    pub treasury_vault: Account<'info, TokenAccount>,
+}
+
+/// Recompute and store the pool snapshot.
+///
+/// # Security
+/// - Anyone can call this
+/// - The projection prices each tier's stake at its current APY; bonuses
+///   are not included
+///
+/// # Arguments
+/// * `ctx` - RefreshPool accounts context
+///
+/// # Returns
+/// Result indicating success or error
+pub fn handler(ctx: Context<RefreshPool>) -> Result<()> {
+    let clock = Clock::get()?;
+    let treasury_balance = ctx.accounts.treasury_vault.amount;
+    let stake_pool = &mut ctx.accounts.stake_pool;
+
+    let projected = stake_pool.projected_outstanding_rewards(clock.unix_timestamp)?;
+    let remaining_emission_capacity = stake_pool.remaining_emission_capacity();
+    let treasury_consistent = stake_pool.treasury_consistency().is_ok();
+
+    stake_pool.projected_outstanding_rewards = projected;
+    stake_pool.snapshot_emission_capacity = remaining_emission_capacity;
+    stake_pool.snapshot_treasury_balance = treasury_balance;
+    stake_pool.last_refresh_at = clock.unix_timestamp;
+    stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+
+    emit!(PoolSnapshot {
+        stake_pool: stake_pool.key(),
+        total_staked: stake_pool.total_staked,
+        staker_count: stake_pool.staker_count,
+        tier_staked: stake_pool.tier_staked,
+        tier_staker_count: stake_pool.tier_staker_count,
+        projected_outstanding_rewards: projected,
+        remaining_emission_capacity,
+        treasury_balance,
+        total_funded: stake_pool.total_funded,
+        total_distributed: stake_pool.total_distributed,
+        treasury_consistent,
+        cumulative_staked: stake_pool.cumulative_staked,
+        cumulative_unstaked: stake_pool.cumulative_unstaked,
+        cumulative_claims_count: stake_pool.cumulative_claims_count,
+        unique_stakers_ever: stake_pool.unique_stakers_ever,
+        sequence: stake_pool.sequence,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!("Projected outstanding rewards: {}", projected);
+    msg!("Remaining emission capacity: {}", remaining_emission_capacity);
+    msg!("Treasury balance: {}", treasury_balance);
+    if !treasury_consistent {
+        msg!(
+            "Distributed {} exceeds recorded funding {}",
+            stake_pool.total_distributed,
+            stake_pool.total_funded
+        );
+    }
+
+    Ok(())
+}
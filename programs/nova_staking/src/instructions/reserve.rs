@@ -0,0 +1,305 @@
+/// Reserve instruction handlers.
+///
+/// Reward tokens can be parked in a reserve vault that drips into the
+/// treasury at a fixed rate, so claims are bounded by the drip schedule.
+///
+/// ## Security Guarantees
+/// - Reserve is a PDA owned by the pool; only `drip_treasury` moves funds out
+/// - Anyone can fund the reserve and crank the drip
+/// - Rate changes settle the drip at the old rate first, so a new rate never
+///   applies to time that has already passed
+/// - Top-ups forfeit the drip time the reserve could not cover, so new funds
+///   never arrive in the treasury as a lump for time it was dry
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+
+use crate::constants::*;
+use crate::error::StakingError;
+use crate::state::StakePool;
+
+/// Accounts required for funding the reserve.
+///
+/// ## Security Notes
+/// - Reserve is created on first funding as a pool-owned PDA token account
+/// - Funder token account must be for correct mint
+#[derive(Accounts)]
+pub struct FundReserve<'info> {
+    /// The funder (anyone can fund - no admin restriction).
+    #[account(mut)]
+    pub funder: Signer<'info>,
+
+    /// The stake pool.
+    /// SECURITY: PDA + has_one validation.
+    #[account(
+        mut,
+        seeds = [STAKE_POOL_SEED, stake_pool.staking_mint.as_ref()],
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ StakingError::AccountVersionMismatch,
+        bump = stake_pool.bump,
+        has_one = staking_mint @ StakingError::MintMismatch
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+
+    /// The staking token mint.
+    pub staking_mint: Account<'info, Mint>,
+
+    /// Funder's token account.
+    /// SECURITY: Mint and owner validation.
+    #[account(
+        mut,
+        constraint = funder_token_account.mint == staking_mint.key() @ StakingError::MintMismatch,
+        constraint = funder_token_account.owner == funder.key() @ StakingError::UnauthorizedStakeAccess
+    )]
+    pub funder_token_account: Account<'info, TokenAccount>,
+
+    /// Pool's reserve vault.
+    /// SECURITY: PDA with the pool as authority.
+    #[account(
+        init_if_needed,
+        payer = funder,
+        seeds = [RESERVE_VAULT_SEED, stake_pool.key().as_ref()],
+        bump,
+        token::mint = staking_mint,
+        token::authority = stake_pool
+    )]
+    pub reserve_vault: Account<'info, TokenAccount>,
+
+    /// System program.
+    pub system_program: Program<'info, System>,
+
+    /// Token program.
+    pub token_program: Program<'info, Token>,
+
+    /// Rent sysvar.
+    pub rent: Sysvar<'info, Rent>,
+}
+
+/// Accounts required for dripping the reserve into the treasury.
+///
+/// ## Security Notes
+/// - Permissionless; the amount is fixed by the pool's drip rate
+#[derive(Accounts)]
+pub struct DripTreasury<'info> {
+    /// The stake pool.
+    /// SECURITY: PDA + has_one validation.
+    #[account(
+        mut,
+        seeds = [STAKE_POOL_SEED, stake_pool.staking_mint.as_ref()],
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ StakingError::AccountVersionMismatch,
+        bump = stake_pool.bump,
+        has_one = treasury_vault @ StakingError::TreasuryMismatch
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+
+    /// Pool's reserve vault.
+    /// SECURITY: PDA derived from the pool.
+    #[account(
+        mut,
+        seeds = [RESERVE_VAULT_SEED, stake_pool.key().as_ref()],
+        bump
+    )]
+    pub reserve_vault: Account<'info, TokenAccount>,
+
+    /// Pool's treasury vault.
+    #[account(mut)]
+    pub treasury_vault: Account<'info, TokenAccount>,
+
+    /// Token program.
+    pub token_program: Program<'info, Token>,
+}
+
+/// Accounts required for changing the drip rate.
+///
+/// ## Security Notes
+/// - Authority must be signer AND match pool.authority
+/// - Carries the drip accounts so the old rate is settled first
+#[derive(Accounts)]
+pub struct SetDripRate<'info> {
+    /// The admin authority.
+    /// SECURITY: Must be signer AND match pool.authority.
+    #[account(
+        mut,
+        constraint = authority.key() == stake_pool.authority @ StakingError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    /// The stake pool.
+    /// SECURITY: PDA validation + has_one authority and treasury.
+    #[account(
+        mut,
+        seeds = [STAKE_POOL_SEED, stake_pool.staking_mint.as_ref()],
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ StakingError::AccountVersionMismatch,
+        bump = stake_pool.bump,
+        has_one = authority @ StakingError::Unauthorized,
+        has_one = treasury_vault @ StakingError::TreasuryMismatch
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+
+    /// Pool's reserve vault.
+    /// SECURITY: PDA derived from the pool.
+    #[account(
+        mut,
+        seeds = [RESERVE_VAULT_SEED, stake_pool.key().as_ref()],
+        bump
+    )]
+    pub reserve_vault: Account<'info, TokenAccount>,
+
+    /// Pool's treasury vault.
+    #[account(mut)]
+    pub treasury_vault: Account<'info, TokenAccount>,
+
+    /// Token program.
+    pub token_program: Program<'info, Token>,
+}
+
+/// Fund the reserve with reward tokens.
+///
+/// # Security
+/// - Validates amount > 0
+/// - Anyone can fund (permissionless); funds only leave through the drip
+/// - Adds `amount` to the pool's `total_funded`
+/// - Drip time the old balance could not cover is dropped before the
+///   top-up lands, as `drip_treasury` would have dropped it
+///
+/// # Arguments
+/// * `ctx` - FundReserve accounts context
+/// * `amount` - Amount of tokens to park in the reserve
+///
+/// # Returns
+/// Result indicating success or error
+pub fn fund_reserve_handler(ctx: Context<FundReserve>, amount: u64) -> Result<()> {
+    // Emergency mode blocks everything except principal exits
+    require!(!ctx.accounts.stake_pool.emergency, StakingError::EmergencyMode);
+    require!(amount > 0, StakingError::ZeroAmount);
+
+    // The reserve vault is created for the staking mint
+    require!(
+        !ctx.accounts.stake_pool.is_dual_token(),
+        StakingError::SingleTokenPoolOnly
+    );
+
+    let clock = Clock::get()?;
+    let balance_before = ctx.accounts.reserve_vault.amount;
+    ctx.accounts
+        .stake_pool
+        .forfeit_uncovered_drip(clock.unix_timestamp, balance_before);
+
+    let cpi_accounts = Transfer {
+        from: ctx.accounts.funder_token_account.to_account_info(),
+        to: ctx.accounts.reserve_vault.to_account_info(),
+        authority: ctx.accounts.funder.to_account_info(),
+    };
+    let cpi_program = ctx.accounts.token_program.to_account_info();
+    let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+    token::transfer(cpi_ctx, amount)?;
+
+    // Reserve deposits reach the treasury through the drip, so they count
+    // as funding when they arrive
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    stake_pool.total_funded = stake_pool
+        .total_funded
+        .checked_add(amount)
+        .ok_or(StakingError::MathOverflow)?;
+    stake_pool.next_sequence()?;
+
+    ctx.accounts.reserve_vault.reload()?;
+
+    msg!("Reserve funded with {} tokens", amount);
+    msg!("New reserve balance: {}", ctx.accounts.reserve_vault.amount);
+    msg!("Funder: {}", ctx.accounts.funder.key());
+
+    Ok(())
+}
+
+/// Move the reserve tokens due since the last drip into the treasury.
+///
+/// # Security
+/// - Permissionless crank; amount is `drip_rate_per_sec` x elapsed, capped by
+///   the reserve balance
+/// - Time the reserve could not cover is not carried over
+///
+/// # Arguments
+/// * `ctx` - DripTreasury accounts context
+///
+/// # Returns
+/// Result indicating success or error
+pub fn drip_treasury_handler(ctx: Context<DripTreasury>) -> Result<()> {
+    let accounts = ctx.accounts;
+    let dripped = drip(
+        &mut accounts.stake_pool,
+        &accounts.reserve_vault,
+        &accounts.treasury_vault,
+        &accounts.token_program,
+    )?;
+
+    msg!("Dripped {} tokens into the treasury", dripped);
+
+    Ok(())
+}
+
+/// Change the reserve drip rate.
+///
+/// # Security
+/// - Only pool.authority can call this
+/// - Drips at the old rate up to now before switching
+///
+/// # Arguments
+/// * `ctx` - SetDripRate accounts context
+/// * `rate_per_sec` - Tokens moved from reserve to treasury per second
+///
+/// # Returns
+/// Result indicating success or error
+pub fn set_drip_rate_handler(ctx: Context<SetDripRate>, rate_per_sec: u64) -> Result<()> {
+    let accounts = ctx.accounts;
+    let dripped = drip(
+        &mut accounts.stake_pool,
+        &accounts.reserve_vault,
+        &accounts.treasury_vault,
+        &accounts.token_program,
+    )?;
+
+    let stake_pool = &mut accounts.stake_pool;
+    let old_rate = stake_pool.drip_rate_per_sec;
+    stake_pool.drip_rate_per_sec = rate_per_sec;
+
+    msg!("Settled {} tokens at the old rate", dripped);
+    msg!("Drip rate updated: {}/s -> {}/s", old_rate, rate_per_sec);
+    msg!("Admin: {}", accounts.authority.key());
+
+    Ok(())
+}
+
+/// Transfer the pending drip and restart the drip clock at now.
+fn drip<'info>(
+    stake_pool: &mut Account<'info, StakePool>,
+    reserve_vault: &Account<'info, TokenAccount>,
+    treasury_vault: &Account<'info, TokenAccount>,
+    token_program: &Program<'info, Token>,
+) -> Result<u64> {
+    let clock = Clock::get()?;
+    let amount = stake_pool.pending_drip(clock.unix_timestamp, reserve_vault.amount);
+
+    if amount > 0 {
+        let staking_mint_key = stake_pool.staking_mint;
+        let seeds = &[STAKE_POOL_SEED, staking_mint_key.as_ref(), &[stake_pool.bump]];
+        let signer_seeds = &[&seeds[..]];
+
+        let cpi_accounts = Transfer {
+            from: reserve_vault.to_account_info(),
+            to: treasury_vault.to_account_info(),
+            authority: stake_pool.to_account_info(),
+        };
+        let cpi_program = token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+        token::transfer(cpi_ctx, amount)?;
+
+        // Close the open empty-treasury window so accrual resumes from now
+        stake_pool.close_empty_window(clock.unix_timestamp)?;
+    }
+
+    stake_pool.last_drip_at = clock.unix_timestamp;
+    stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+
+    Ok(amount)
+}
@@ -0,0 +1,104 @@
+/// Reward destination instruction handler.
+///
+/// Lets a stake owner pin where `claim_rewards` pays, e.g. a cold-storage
+/// ATA, whichever wallet submits the claim.
+///
+/// ## Security Guarantees
+/// - Only the position owner can set or clear the destination
+/// - The destination must be a token account for the pool's reward mint
+/// - No token movement here
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::TokenAccount;
+
+use crate::constants::*;
+use crate::error::StakingError;
+use crate::events::RewardDestinationChanged;
+use crate::instructions::recovery::cancel_on_owner_signature;
+use crate::state::{StakePool, UserStake};
+
+/// Accounts required for setting or clearing the reward destination.
+///
+/// ## Security Notes
+/// - User must be signer AND match user_stake.owner
+/// - `destination` is deliberately not owner-checked, as for the
+///   `destination` of `claim_rewards`
+#[derive(Accounts)]
+pub struct SetRewardDestination<'info> {
+    /// The position owner.
+    /// SECURITY: Must be signer and match stake owner.
+    pub user: Signer<'info>,
+
+    /// The stake pool; writable for `sequence`.
+    /// SECURITY: PDA validation.
+    #[account(
+        mut,
+        seeds = [STAKE_POOL_SEED, stake_pool.staking_mint.as_ref()],
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ StakingError::AccountVersionMismatch,
+        bump = stake_pool.bump
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+
+    /// User's stake account.
+    /// SECURITY: PDA + owner + pool validation.
+    #[account(
+        mut,
+        seeds = [USER_STAKE_SEED, stake_pool.key().as_ref(), user.key().as_ref()],
+        bump = user_stake.bump,
+        constraint = user_stake.version == USER_STAKE_VERSION @ StakingError::AccountVersionMismatch,
+        constraint = user_stake.owner == user.key() @ StakingError::InvalidStakeOwner,
+        constraint = user_stake.stake_pool == stake_pool.key() @ StakingError::StakePoolMismatch
+    )]
+    pub user_stake: Account<'info, UserStake>,
+
+    /// Token account every claim should pay; omit to clear the preference.
+    /// SECURITY: Reward mint validation only; any owner is accepted.
+    #[account(
+        constraint = destination.mint == stake_pool.reward_mint @ StakingError::MintMismatch
+    )]
+    pub destination: Option<Account<'info, TokenAccount>>,
+}
+
+/// Set or clear the token account claims on the signer's position pay.
+///
+/// # Security
+/// - Replaces any previous destination
+/// - While set, `claim_rewards` must pass it as `destination`, whether the
+///   owner or the claim delegate signs
+///
+/// # Arguments
+/// * `ctx` - SetRewardDestination accounts context
+///
+/// # Returns
+/// Result indicating success or error
+pub fn handler(ctx: Context<SetRewardDestination>) -> Result<()> {
+    let destination = ctx
+        .accounts
+        .destination
+        .as_ref()
+        .map_or(Pubkey::default(), |destination| destination.key());
+    let user_stake = &mut ctx.accounts.user_stake;
+    let clock = Clock::get()?;
+
+    let old_destination = user_stake.reward_destination;
+    user_stake.reward_destination = destination;
+    ctx.accounts.stake_pool.next_sequence()?;
+    cancel_on_owner_signature(&ctx.accounts.stake_pool, user_stake, clock.unix_timestamp);
+
+    emit!(RewardDestinationChanged {
+        stake_pool: ctx.accounts.stake_pool.key(),
+        owner: user_stake.owner,
+        old_destination,
+        destination,
+        sequence: ctx.accounts.stake_pool.sequence,
+        timestamp: clock.unix_timestamp,
+    });
+
+    if destination == Pubkey::default() {
+        msg!("Reward destination cleared");
+    } else {
+        msg!("Reward destination: {}", destination);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,210 @@
+/// Snapshot instruction handlers.
+///
+/// Periodic on-chain records of pool totals, plus per-position records
+/// against the latest one, so airdrops and governance can read stake at an
+/// epoch without an archive node.
+///
+/// ## Security Guarantees
+/// - Both instructions are permissionless; the caller pays rent
+/// - At most one pool snapshot per `snapshot_interval_secs`, numbered
+///   consecutively
+/// - A position can be recorded once per epoch, only against the latest
+///   snapshot and only if nothing was deposited into it since
+/// - No token movement here
+
+use anchor_lang::prelude::*;
+
+use crate::constants::*;
+use crate::error::StakingError;
+use crate::events::SnapshotTaken;
+use crate::state::{Snapshot, StakePool, UserSnapshot, UserStake};
+
+/// Accounts required for taking a pool snapshot.
+///
+/// ## Security Notes
+/// - `epoch` must be the pool's `snapshot_count`, which seeds the new PDA
+#[derive(Accounts)]
+#[instruction(epoch: u64)]
+pub struct TakeSnapshot<'info> {
+    /// Anyone; pays rent for the snapshot account.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// The stake pool; writable for the snapshot counters and `sequence`.
+    /// SECURITY: PDA validation.
+    #[account(
+        mut,
+        seeds = [STAKE_POOL_SEED, stake_pool.staking_mint.as_ref()],
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ StakingError::AccountVersionMismatch,
+        bump = stake_pool.bump
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+
+    /// New snapshot PDA for (pool, epoch).
+    #[account(
+        init,
+        payer = payer,
+        space = Snapshot::LEN,
+        seeds = [SNAPSHOT_SEED, stake_pool.key().as_ref(), &epoch.to_le_bytes()],
+        bump
+    )]
+    pub snapshot: Account<'info, Snapshot>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required for recording a position against a snapshot.
+///
+/// ## Security Notes
+/// - The position is read, never written; its owner need not sign
+/// - PDA seeds allow one record per (pool, epoch, owner)
+#[derive(Accounts)]
+#[instruction(epoch: u64)]
+pub struct RecordUserSnapshot<'info> {
+    /// Anyone; pays rent for the record.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// The stake pool.
+    /// SECURITY: PDA validation.
+    #[account(
+        seeds = [STAKE_POOL_SEED, stake_pool.staking_mint.as_ref()],
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ StakingError::AccountVersionMismatch,
+        bump = stake_pool.bump
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+
+    /// The pool snapshot being recorded against.
+    /// SECURITY: PDA for (pool, epoch).
+    #[account(
+        seeds = [SNAPSHOT_SEED, stake_pool.key().as_ref(), &epoch.to_le_bytes()],
+        bump = snapshot.bump
+    )]
+    pub snapshot: Account<'info, Snapshot>,
+
+    /// The position to record.
+    /// SECURITY: PDA + pool validation.
+    #[account(
+        seeds = [USER_STAKE_SEED, stake_pool.key().as_ref(), user_stake.owner.as_ref()],
+        bump = user_stake.bump,
+        constraint = user_stake.version == USER_STAKE_VERSION @ StakingError::AccountVersionMismatch,
+        constraint = user_stake.stake_pool == stake_pool.key() @ StakingError::StakePoolMismatch
+    )]
+    pub user_stake: Account<'info, UserStake>,
+
+    /// New record PDA for (pool, epoch, owner).
+    #[account(
+        init,
+        payer = payer,
+        space = UserSnapshot::LEN,
+        seeds = [
+            USER_SNAPSHOT_SEED,
+            stake_pool.key().as_ref(),
+            &epoch.to_le_bytes(),
+            user_stake.owner.as_ref()
+        ],
+        bump
+    )]
+    pub user_snapshot: Account<'info, UserSnapshot>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Write the pool totals for the next snapshot epoch.
+///
+/// # Security
+/// - Fails with `SnapshotsDisabled` until the authority sets an interval
+/// - The first snapshot can be taken at any time; each later one once
+///   `snapshot_interval_secs` has passed since the previous one
+///
+/// # Arguments
+/// * `ctx` - TakeSnapshot accounts context
+/// * `epoch` - Index of the new snapshot; must equal `snapshot_count`
+///
+/// # Returns
+/// Result indicating success or error
+pub fn take_handler(ctx: Context<TakeSnapshot>, epoch: u64) -> Result<()> {
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    require!(stake_pool.snapshot_interval_secs > 0, StakingError::SnapshotsDisabled);
+    require!(epoch == stake_pool.snapshot_count, StakingError::SnapshotEpochMismatch);
+    require!(
+        stake_pool.is_snapshot_due(clock.unix_timestamp),
+        StakingError::SnapshotNotDue
+    );
+
+    let snapshot = &mut ctx.accounts.snapshot;
+    snapshot.stake_pool = stake_pool.key();
+    snapshot.epoch = epoch;
+    snapshot.total_staked = stake_pool.total_staked;
+    snapshot.tier_staked = stake_pool.tier_staked;
+    snapshot.staker_count = stake_pool.staker_count;
+    snapshot.timestamp = clock.unix_timestamp;
+    snapshot.bump = ctx.bumps.snapshot;
+
+    stake_pool.snapshot_count = epoch.checked_add(1).ok_or(StakingError::MathOverflow)?;
+    stake_pool.last_snapshot_at = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+
+    emit!(SnapshotTaken {
+        stake_pool: stake_pool.key(),
+        epoch,
+        total_staked: snapshot.total_staked,
+        staker_count: snapshot.staker_count,
+        sequence: stake_pool.sequence,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!("Snapshot {}: {} staked by {} stakers", epoch, snapshot.total_staked, snapshot.staker_count);
+
+    Ok(())
+}
+
+/// Record a position's stake against the latest snapshot.
+///
+/// # Security
+/// - Only the latest epoch accepts records (`SnapshotEpochMismatch`)
+/// - Rejected with `StakedAfterSnapshot` once the position has taken a
+///   deposit at or after the snapshot's second; unstakes since then only
+///   lower the record, while compounded rewards are not deposits and count
+///
+/// # Arguments
+/// * `ctx` - RecordUserSnapshot accounts context
+/// * `epoch` - Snapshot epoch to record against
+///
+/// # Returns
+/// Result indicating success or error
+pub fn record_user_handler(ctx: Context<RecordUserSnapshot>, epoch: u64) -> Result<()> {
+    let stake_pool = &ctx.accounts.stake_pool;
+    let snapshot = &ctx.accounts.snapshot;
+    let user_stake = &ctx.accounts.user_stake;
+    let clock = Clock::get()?;
+
+    require!(
+        stake_pool.snapshot_count.checked_sub(1) == Some(epoch),
+        StakingError::SnapshotEpochMismatch
+    );
+    require!(
+        user_stake.is_active && user_stake.staked_amount > 0,
+        StakingError::NoActiveStake
+    );
+    require!(
+        user_stake.last_stake_time < snapshot.timestamp,
+        StakingError::StakedAfterSnapshot
+    );
+
+    let user_snapshot = &mut ctx.accounts.user_snapshot;
+    user_snapshot.stake_pool = stake_pool.key();
+    user_snapshot.epoch = epoch;
+    user_snapshot.owner = user_stake.owner;
+    user_snapshot.staked_amount = user_stake.staked_amount;
+    user_snapshot.tier = user_stake.tier as u8;
+    user_snapshot.stake_start_time = user_stake.stake_start_time;
+    user_snapshot.timestamp = clock.unix_timestamp;
+    user_snapshot.bump = ctx.bumps.user_snapshot;
+
+    msg!("Snapshot {}: recorded {} staked by {}", epoch, user_stake.staked_amount, user_stake.owner);
+
+    Ok(())
+}
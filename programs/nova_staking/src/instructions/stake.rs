@@ -8,13 +8,74 @@ use crate::tier::Tier;
 /// - Mint validation prevents wrong token staking
 /// - Vault validation ensures tokens go to correct PDA
 /// - All math uses checked operations
+/// - `stake` must be a top-level instruction unless the pool sets `allow_cpi`
+/// - In receipt mode the position owner receives one receipt token per token
+///   received by the vault
 
+use anchor_lang::prelude::borsh;
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::set_return_data;
+use anchor_lang::solana_program::sysvar::instructions as sysvar_instructions;
 use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
 
 use crate::constants::*;
 use crate::error::StakingError;
-use crate::state::{StakePool, UserStake};
+use crate::events::{SolvencyProjection, StakeEvent};
+use crate::instructions::receipt::mint_receipts;
+use crate::instructions::recovery::cancel_on_owner_signature;
+use crate::math::{bps_seconds, per_token_rewards, rewards_from_rate_seconds};
+use crate::state::{
+    PositionEventKind, PositionHistory, PositionRecord, RewardMode, StakePool, StakerRecord,
+    UserStake, WhitelistEntry,
+};
+
+/// How a top-up into an existing position interacts with its lock.
+///
+/// Each policy must be enabled in `StakePool::allowed_topup_policies`, which
+/// the authority chooses at `initialize` and may change later. A pool that
+/// wants a single behavior enables only that policy. There is deliberately no
+/// policy that leaves a running lock untouched: a top-up could then ride a
+/// lock that has already ended and leave immediately.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TopUpLockPolicy {
+    /// Keep the position's lock length and move its end to the stake-weighted
+    /// average of the running lock's end and a full lock for the deposit
+    /// (`UserStake::weighted_lock_start`).
+    KeepOriginal,
+    /// Reset `stake_start_time` so the whole position starts a fresh lock at
+    /// the pool's current lock length.
+    ExtendAll,
+    /// Lock only the new amount. Requires per-deposit lot accounting.
+    NewLotOnly,
+}
+
+impl TopUpLockPolicy {
+    pub const KEEP_ORIGINAL_BIT: u8 = 1 << 0;
+    pub const EXTEND_ALL_BIT: u8 = 1 << 1;
+    pub const NEW_LOT_ONLY_BIT: u8 = 1 << 2;
+    pub const ALL_BITS: u8 = Self::KEEP_ORIGINAL_BIT | Self::EXTEND_ALL_BIT | Self::NEW_LOT_ONLY_BIT;
+
+    pub fn mask_bit(self) -> u8 {
+        match self {
+            TopUpLockPolicy::KeepOriginal => Self::KEEP_ORIGINAL_BIT,
+            TopUpLockPolicy::ExtendAll => Self::EXTEND_ALL_BIT,
+            TopUpLockPolicy::NewLotOnly => Self::NEW_LOT_ONLY_BIT,
+        }
+    }
+
+    /// Reject unknown bits and policies that cannot be enabled yet.
+    pub fn validate_mask(mask: u8) -> Result<()> {
+        require!(
+            mask & !Self::ALL_BITS == 0,
+            StakingError::InvalidTopUpPolicyMask
+        );
+        require!(
+            mask & Self::NEW_LOT_ONLY_BIT == 0,
+            StakingError::TopUpPolicyUnavailable
+        );
+        Ok(())
+    }
+}
 
 /// Accounts required for staking.
 ///
@@ -31,7 +92,9 @@ pub struct Stake<'info> {
     /// The stake pool.
     /// SECURITY: PDA verification + has_one constraints
     #[account(
+        mut,
         seeds = [STAKE_POOL_SEED, stake_pool.staking_mint.as_ref()],
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ StakingError::AccountVersionMismatch,
         bump = stake_pool.bump,
         has_one = staking_vault @ StakingError::VaultMismatch,
         has_one = staking_mint @ StakingError::MintMismatch
@@ -49,6 +112,17 @@ pub struct Stake<'info> {
     )]
     pub user_stake: Account<'info, UserStake>,
 
+    /// The user's staker record (created on their first stake).
+    /// SECURITY: PDA derived from pool + user; never closed.
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = StakerRecord::LEN,
+        seeds = [STAKER_RECORD_SEED, stake_pool.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub staker_record: Box<Account<'info, StakerRecord>>,
+
     /// The staking token mint.
     /// SECURITY: Validated against pool's locked mint.
     #[account(
@@ -83,47 +157,433 @@ pub struct Stake<'info> {
 
     /// Rent sysvar.
     pub rent: Sysvar<'info, Rent>,
+
+    /// Instructions sysvar, read to reject CPI calls unless the pool allows them.
+    /// CHECK: Address-checked against the sysvar id.
+    #[account(address = sysvar_instructions::ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
+
+    /// Optional position history; the change is recorded when provided.
+    /// SECURITY: PDA derived from the signer, so only the owner's own history.
+    #[account(
+        mut,
+        seeds = [POSITION_HISTORY_SEED, stake_pool.key().as_ref(), user.key().as_ref()],
+        bump = position_history.bump
+    )]
+    pub position_history: Option<Box<Account<'info, PositionHistory>>>,
+
+    /// Pool treasury; read for the solvency check and required while
+    /// `require_solvency` is set.
+    /// SECURITY: Must match pool's stored treasury.
+    #[account(
+        constraint = treasury_vault.key() == stake_pool.treasury_vault @ StakingError::TreasuryMismatch
+    )]
+    pub treasury_vault: Option<Box<Account<'info, TokenAccount>>>,
+
+    /// Receipt mint; required in receipt mode.
+    /// SECURITY: Must match the pool's receipt mint.
+    #[account(
+        mut,
+        address = stake_pool.receipt_mint @ StakingError::MintMismatch
+    )]
+    pub receipt_mint: Option<Box<Account<'info, Mint>>>,
+
+    /// Receives the receipt tokens; required in receipt mode.
+    /// SECURITY: Receipt mint and owner validation.
+    #[account(
+        mut,
+        constraint = receipt_account.mint == stake_pool.receipt_mint @ StakingError::MintMismatch,
+        constraint = receipt_account.owner == user.key() @ StakingError::UnauthorizedStakeAccess
+    )]
+    pub receipt_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    /// The user's whitelist entry; required while `whitelist_enabled` is set.
+    /// SECURITY: PDA derived from pool + signer.
+    #[account(
+        seeds = [WHITELIST_SEED, stake_pool.key().as_ref(), user.key().as_ref()],
+        bump = whitelist_entry.bump
+    )]
+    pub whitelist_entry: Option<Box<Account<'info, WhitelistEntry>>>,
+}
+
+/// Accounts required for staking on behalf of a beneficiary.
+///
+/// ## Security Notes
+/// - Same validations as `Stake`, but the position is derived from and owned
+///   by `beneficiary`; `payer` only provides the tokens and rent
+/// - `beneficiary` need not sign; it only receives a position
+#[derive(Accounts)]
+pub struct StakeFor<'info> {
+    /// Provides the staked tokens and the rent for a new position.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// Owner of the position.
+    /// CHECK: Only its key is used, to derive and own the position.
+    pub beneficiary: UncheckedAccount<'info>,
+
+    /// The stake pool.
+    /// SECURITY: PDA verification + has_one constraints
+    #[account(
+        mut,
+        seeds = [STAKE_POOL_SEED, stake_pool.staking_mint.as_ref()],
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ StakingError::AccountVersionMismatch,
+        bump = stake_pool.bump,
+        has_one = staking_vault @ StakingError::VaultMismatch,
+        has_one = staking_mint @ StakingError::MintMismatch
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+
+    /// Beneficiary's stake account (created if first time staking).
+    /// SECURITY: PDA derived from pool + beneficiary.
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = UserStake::LEN,
+        seeds = [USER_STAKE_SEED, stake_pool.key().as_ref(), beneficiary.key().as_ref()],
+        bump
+    )]
+    pub user_stake: Account<'info, UserStake>,
+
+    /// The beneficiary's staker record (created on their first stake).
+    /// SECURITY: PDA derived from pool + beneficiary; never closed.
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = StakerRecord::LEN,
+        seeds = [STAKER_RECORD_SEED, stake_pool.key().as_ref(), beneficiary.key().as_ref()],
+        bump
+    )]
+    pub staker_record: Box<Account<'info, StakerRecord>>,
+
+    /// The staking token mint.
+    /// SECURITY: Validated against pool's locked mint.
+    #[account(
+        constraint = staking_mint.key() == stake_pool.staking_mint @ StakingError::MintMismatch
+    )]
+    pub staking_mint: Account<'info, Mint>,
+
+    /// Payer's token account for the staking token.
+    /// SECURITY: Mint and owner validation.
+    #[account(
+        mut,
+        constraint = payer_token_account.mint == staking_mint.key() @ StakingError::MintMismatch,
+        constraint = payer_token_account.owner == payer.key() @ StakingError::UnauthorizedStakeAccess
+    )]
+    pub payer_token_account: Account<'info, TokenAccount>,
+
+    /// Pool's staking vault.
+    /// SECURITY: Must match pool's stored vault address.
+    #[account(
+        mut,
+        constraint = staking_vault.key() == stake_pool.staking_vault @ StakingError::VaultMismatch,
+        constraint = staking_vault.owner == stake_pool.key() @ StakingError::InvalidVaultOwner,
+        constraint = staking_vault.mint == staking_mint.key() @ StakingError::InvalidTokenAccountMint
+    )]
+    pub staking_vault: Account<'info, TokenAccount>,
+
+    /// System program.
+    pub system_program: Program<'info, System>,
+
+    /// Token program.
+    pub token_program: Program<'info, Token>,
+
+    /// Instructions sysvar, read to reject CPI calls unless the pool allows them.
+    /// CHECK: Address-checked against the sysvar id.
+    #[account(address = sysvar_instructions::ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
+
+    /// Optional position history; the change is recorded when provided.
+    /// SECURITY: PDA derived from the beneficiary, so only their own history.
+    #[account(
+        mut,
+        seeds = [POSITION_HISTORY_SEED, stake_pool.key().as_ref(), beneficiary.key().as_ref()],
+        bump = position_history.bump
+    )]
+    pub position_history: Option<Box<Account<'info, PositionHistory>>>,
+
+    /// Pool treasury; read for the solvency check and required while
+    /// `require_solvency` is set.
+    /// SECURITY: Must match pool's stored treasury.
+    #[account(
+        constraint = treasury_vault.key() == stake_pool.treasury_vault @ StakingError::TreasuryMismatch
+    )]
+    pub treasury_vault: Option<Box<Account<'info, TokenAccount>>>,
+
+    /// Receipt mint; required in receipt mode.
+    /// SECURITY: Must match the pool's receipt mint.
+    #[account(
+        mut,
+        address = stake_pool.receipt_mint @ StakingError::MintMismatch
+    )]
+    pub receipt_mint: Option<Box<Account<'info, Mint>>>,
+
+    /// Receives the receipt tokens; required in receipt mode.
+    /// SECURITY: Receipt mint and owner validation.
+    #[account(
+        mut,
+        constraint = receipt_account.mint == stake_pool.receipt_mint @ StakingError::MintMismatch,
+        constraint = receipt_account.owner == beneficiary.key() @ StakingError::UnauthorizedStakeAccess
+    )]
+    pub receipt_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    /// The beneficiary's whitelist entry; required while `whitelist_enabled`
+    /// is set.
+    /// SECURITY: PDA derived from pool + beneficiary.
+    #[account(
+        seeds = [WHITELIST_SEED, stake_pool.key().as_ref(), beneficiary.key().as_ref()],
+        bump = whitelist_entry.bump
+    )]
+    pub whitelist_entry: Option<Box<Account<'info, WhitelistEntry>>>,
 }
 
 /// Stake tokens into the pool.
 ///
 /// # Security
 /// - Validates pool is not paused
+/// - Requires the user's whitelist entry while `whitelist_enabled` is set
 /// - Validates amount > 0
-/// - Validates tier is valid (0, 1, or 2)
+/// - Validates tier is valid (0, 1, 2, or 3 once configured)
 /// - Uses checked math for all calculations
 /// - Validates mint matches pool's staking mint
 ///
 /// # Arguments
 /// * `ctx` - Stake accounts context
 /// * `amount` - Amount of tokens to stake
-/// * `tier` - Staking tier (0=Flex, 1=Core, 2=Prime)
+/// * `tier` - Staking tier (0=Flex, 1=Core, 2=Prime, 3=Permanent)
+/// * `lock_policy` - Lock handling for top-ups (ignored for first stakes)
+/// * `expected_apy_bps` - Optional quoted APY; rejects if the live tier APY is lower
+/// * `referrer` - Optional referrer wallet; only accepted on the user's first stake
+/// * `label` - Optional position label; only accepted on the user's first stake
+///
+/// # Returns
+/// Result indicating success or error; the applied policy is written to return data
+pub fn handler(
+    ctx: Context<Stake>,
+    amount: u64,
+    tier: u8,
+    lock_policy: TopUpLockPolicy,
+    expected_apy_bps: Option<u16>,
+    referrer: Option<Pubkey>,
+    label: Option<[u8; 32]>,
+) -> Result<()> {
+    ctx.accounts
+        .stake_pool
+        .require_top_level(&ctx.accounts.instructions_sysvar)?;
+
+    let user_stake_bump = ctx.bumps.user_stake;
+    let staker_record_bump = ctx.bumps.staker_record;
+    let accounts = ctx.accounts;
+    process_stake(
+        &mut accounts.stake_pool,
+        &mut accounts.user_stake,
+        user_stake_bump,
+        &mut accounts.staker_record,
+        staker_record_bump,
+        accounts.user.key(),
+        accounts.user.to_account_info(),
+        accounts.user_token_account.to_account_info(),
+        &accounts.staking_vault,
+        &accounts.token_program,
+        accounts.position_history.as_deref_mut(),
+        accounts.treasury_vault.as_deref().map(|vault| vault.amount),
+        accounts.receipt_mint.as_deref(),
+        accounts.receipt_account.as_deref(),
+        accounts.whitelist_entry.is_some(),
+        amount,
+        tier,
+        lock_policy,
+        expected_apy_bps,
+        referrer,
+        label,
+    )?;
+
+    // Only the owner's own stake cancels a recovery; `stake_for` is unsigned
+    // by the owner
+    cancel_on_owner_signature(
+        &accounts.stake_pool,
+        &mut accounts.user_stake,
+        Clock::get()?.unix_timestamp,
+    );
+    Ok(())
+}
+
+/// Stake tokens into a position owned by `beneficiary`.
+///
+/// # Security
+/// - Same checks as `stake`; an open position in another tier is rejected
+/// - The whitelist entry checked is the beneficiary's, not the payer's
+/// - Reactivating an exited position keeps everything still owed to it, as
+///   with `stake`
+/// - Top-ups use `KeepOriginal`: a third party can never restart the
+///   beneficiary's lock, only move its end by the weight of the deposit
+/// - No referrer or label; only the beneficiary's own first `stake` can set
+///   them, and the label later through `set_stake_label`
+///
+/// # Arguments
+/// * `ctx` - StakeFor accounts context
+/// * `amount` - Amount of tokens to stake
+/// * `tier` - Staking tier (0=Flex, 1=Core, 2=Prime, 3=Permanent)
+/// * `expected_apy_bps` - Optional quoted APY; rejects if the live tier APY is lower
 ///
 /// # Returns
-/// Result indicating success or error
-pub fn handler(ctx: Context<Stake>, amount: u64, tier: u8) -> Result<()> {
-    let stake_pool = &ctx.accounts.stake_pool;
-    let user_stake = &ctx.accounts.user_stake;
+/// Result indicating success or error; the applied policy is written to return data
+pub fn stake_for_handler(
+    ctx: Context<StakeFor>,
+    amount: u64,
+    tier: u8,
+    expected_apy_bps: Option<u16>,
+) -> Result<()> {
+    ctx.accounts
+        .stake_pool
+        .require_top_level(&ctx.accounts.instructions_sysvar)?;
+
+    let user_stake_bump = ctx.bumps.user_stake;
+    let staker_record_bump = ctx.bumps.staker_record;
+    let accounts = ctx.accounts;
+
+    msg!(
+        "Staking for {} (paid by {})",
+        accounts.beneficiary.key(),
+        accounts.payer.key()
+    );
+    process_stake(
+        &mut accounts.stake_pool,
+        &mut accounts.user_stake,
+        user_stake_bump,
+        &mut accounts.staker_record,
+        staker_record_bump,
+        accounts.beneficiary.key(),
+        accounts.payer.to_account_info(),
+        accounts.payer_token_account.to_account_info(),
+        &accounts.staking_vault,
+        &accounts.token_program,
+        accounts.position_history.as_deref_mut(),
+        accounts.treasury_vault.as_deref().map(|vault| vault.amount),
+        accounts.receipt_mint.as_deref(),
+        accounts.receipt_account.as_deref(),
+        accounts.whitelist_entry.is_some(),
+        amount,
+        tier,
+        TopUpLockPolicy::KeepOriginal,
+        expected_apy_bps,
+        None,
+        None,
+    )
+}
+
+/// Shared by `stake` and `stake_for`: `owner` gets the position, `funder`
+/// signs for the transfer out of `source`. `treasury_balance` is the liquid
+/// treasury, when the treasury account was passed. Receipts go to
+/// `receipt_account` in receipt mode. `whitelisted` is whether the owner's
+/// `WhitelistEntry` was passed.
+#[allow(clippy::too_many_arguments)]
+fn process_stake<'info>(
+    stake_pool: &mut Account<'info, StakePool>,
+    user_stake: &mut Account<'info, UserStake>,
+    user_stake_bump: u8,
+    staker_record: &mut Account<'info, StakerRecord>,
+    staker_record_bump: u8,
+    owner: Pubkey,
+    funder: AccountInfo<'info>,
+    source: AccountInfo<'info>,
+    staking_vault: &Account<'info, TokenAccount>,
+    token_program: &Program<'info, Token>,
+    position_history: Option<&mut Account<'info, PositionHistory>>,
+    treasury_balance: Option<u64>,
+    receipt_mint: Option<&Account<'info, Mint>>,
+    receipt_account: Option<&Account<'info, TokenAccount>>,
+    whitelisted: bool,
+    amount: u64,
+    tier: u8,
+    lock_policy: TopUpLockPolicy,
+    expected_apy_bps: Option<u16>,
+    referrer: Option<Pubkey>,
+    label: Option<[u8; 32]>,
+) -> Result<()> {
+    // Emergency mode blocks everything except principal exits
+    require!(!stake_pool.emergency, StakingError::EmergencyMode);
+
+    // A whitelisted pool only takes deposits into approved wallets' positions
+    require!(
+        !stake_pool.whitelist_enabled || whitelisted,
+        StakingError::NotWhitelisted
+    );
 
     // === INPUT VALIDATION ===
     
-    // Validate pool is not paused
-    require!(!stake_pool.paused, StakingError::StakingPaused);
-
     // Validate amount is non-zero
     require!(amount > 0, StakingError::ZeroAmount);
 
-    // Validate tier is valid
+    // Validate tier is valid; everything below works on the enum
+    let tier = Tier::try_from(tier)?;
+
+    // Validate neither the pool nor the tier is paused, once an expired
+    // `set_paused` pause has been lifted
+    stake_pool.expire_pause(Clock::get()?.unix_timestamp)?;
+    require!(stake_pool.is_accepting_stakes(tier), StakingError::StakingPaused);
+
+    // Permanent tier opens once the authority gives it an APY
     require!(
-        tier == Tier::FLEX as u8 || tier == Tier::CORE as u8 || tier == Tier::PRIME as u8,
-        StakingError::InvalidTier
+        tier != Tier::Permanent || stake_pool.permanent_apy > 0,
+        StakingError::PermanentTierDisabled
     );
 
+    // Reject if an APY change or decay step landed between the user's quote
+    // and this stake
+    if let Some(expected_apy_bps) = expected_apy_bps {
+        require!(
+            tier.apy(stake_pool, Clock::get()?.unix_timestamp) >= expected_apy_bps,
+            StakingError::ApyChangedSinceQuote
+        );
+    }
+
     // === STATE VALIDATION ===
     
-    // If user has existing active stake, they must use the same tier
-    if user_stake.is_active && user_stake.staked_amount > 0 {
+    // If user has existing active stake (or a queued withdrawal), they must
+    // use the same tier and a top-up lock policy the pool allows
+    if user_stake.is_active
+        && (user_stake.staked_amount > 0 || user_stake.has_pending_unstake())
+    {
         require!(user_stake.tier == tier, StakingError::CannotChangeTier);
+        require!(
+            stake_pool.allows_topup_policy(lock_policy),
+            StakingError::TopUpPolicyNotAllowed
+        );
+    }
+
+    // A referrer is recorded once, on the very first stake; self-referral is rejected
+    if let Some(referrer) = referrer {
+        require!(
+            user_stake.referrer == Pubkey::default(),
+            StakingError::ReferrerAlreadySet
+        );
+        require!(
+            user_stake.owner == Pubkey::default(),
+            StakingError::ReferrerOnlyOnFirstStake
+        );
+        require!(
+            referrer != Pubkey::default() && referrer != owner,
+            StakingError::InvalidReferrer
+        );
+    }
+
+    // A label comes with a brand-new position; afterwards only
+    // set_stake_label changes it
+    if let Some(label) = &label {
+        require!(
+            user_stake.owner == Pubkey::default(),
+            StakingError::LabelOnlyOnFirstStake
+        );
+        UserStake::validate_label(label)?;
+    }
+
+    // Opening a position must clear the pool minimum; top-ups may be smaller
+    if !user_stake.is_active {
+        require!(
+            amount >= stake_pool.min_stake_amount,
+            StakingError::StakeBelowMinimum
+        );
     }
 
     let clock = Clock::get()?;
@@ -134,53 +594,202 @@ pub fn handler(ctx: Context<Stake>, amount: u64, tier: u8) -> Result<()> {
         StakingError::InvalidTimestamp
     );
 
+    // An ended pool only lets positions exit and claim what accrued
+    require!(!stake_pool.has_ended(clock.unix_timestamp), StakingError::PoolEnded);
+
+    // Numbered before any event below is emitted
+    stake_pool.next_sequence()?;
+
+    // === DAILY INFLOW CAP ===
+
+    // Reject deposits beyond the rolling 24h cap (0 = disabled); headroom goes
+    // into return data so clients can retry with a smaller amount
+    if stake_pool.max_daily_inflow > 0 {
+        let headroom = stake_pool
+            .max_daily_inflow
+            .saturating_sub(stake_pool.inflow_used(clock.unix_timestamp));
+        if amount > headroom {
+            set_return_data(&headroom.to_le_bytes());
+            msg!("Daily inflow cap reached, remaining headroom: {}", headroom);
+            return err!(StakingError::DailyInflowCapExceeded);
+        }
+    }
+
+    // === POOL CAP ===
+
+    // Reject deposits that would push total_staked over the pool cap; the
+    // remaining capacity goes into return data like the inflow headroom
+    if let Some(remaining) = stake_pool.remaining_capacity() {
+        if amount > remaining {
+            set_return_data(&remaining.to_le_bytes());
+            msg!("Pool cap reached, remaining capacity: {}", remaining);
+            return err!(StakingError::PoolCapReached);
+        }
+    }
+
+    // === SOLVENCY ===
+
+    // Reject deposits whose yearly rewards at current APYs, summed over all
+    // tiers, could not be covered by the emission budget or the treasury
+    if stake_pool.require_solvency {
+        let treasury_balance = treasury_balance.ok_or(StakingError::MissingTreasuryVault)?;
+        let projected = stake_pool.projected_annual_rewards(clock.unix_timestamp, Some((tier, amount)))?;
+        let remaining_emission_capacity = stake_pool.remaining_emission_capacity();
+        let treasury_limit = stake_pool.solvency_treasury_limit(treasury_balance);
+
+        emit!(SolvencyProjection {
+            stake_pool: stake_pool.key(),
+            projected_annual_rewards: projected,
+            remaining_emission_capacity,
+            treasury_limit,
+            sequence: stake_pool.sequence,
+            timestamp: clock.unix_timestamp,
+        });
+        msg!(
+            "Projected annual rewards: {} (emission capacity {}, treasury limit {})",
+            projected,
+            remaining_emission_capacity,
+            treasury_limit
+        );
+
+        require!(
+            projected <= remaining_emission_capacity && projected <= treasury_limit,
+            StakingError::PoolInsolvent
+        );
+    }
+
     // Calculate pending rewards before updating stake (uses checked math)
-    let pending = calculate_pending_rewards(
-        &ctx.accounts.user_stake,
-        &ctx.accounts.stake_pool,
-        clock.unix_timestamp,
-    )?;
+    let pending = calculate_pending_rewards(user_stake, stake_pool, clock.unix_timestamp)?;
 
     // === TOKEN TRANSFER ===
     
-    // Transfer tokens from user to vault
+    // Transfer tokens from the funder to the vault
     let cpi_accounts = Transfer {
-        from: ctx.accounts.user_token_account.to_account_info(),
-        to: ctx.accounts.staking_vault.to_account_info(),
-        authority: ctx.accounts.user.to_account_info(),
+        from: source,
+        to: staking_vault.to_account_info(),
+        authority: funder,
     };
-    let cpi_program = ctx.accounts.token_program.to_account_info();
+    let cpi_program = token_program.to_account_info();
     let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
     token::transfer(cpi_ctx, amount)?;
 
+    // One receipt per token staked, so receipts never exceed principal
+    mint_receipts(stake_pool, receipt_mint, receipt_account, token_program, amount)?;
+
     // === STATE UPDATE ===
-    
-    let user_stake = &mut ctx.accounts.user_stake;
-    let stake_pool = &mut ctx.accounts.stake_pool;
+
+    // Settle the reward-per-token accumulator before balances change
+    update_rewards(stake_pool, user_stake, clock.unix_timestamp)?;
+
+    // A zeroed owner means the account was just created by init_if_needed
+    let is_new_account = user_stake.owner == Pubkey::default();
+
+    // The record outlives the position, so a wallet that closed its account
+    // and stakes again is not a new staker. Wallets that staked before
+    // records existed get one now but already hold an account, whose
+    // early-bird stamp the record takes over
+    let first_stake = staker_record.wallet == Pubkey::default();
+    if first_stake {
+        staker_record.set_inner(StakerRecord {
+            stake_pool: stake_pool.key(),
+            wallet: owner,
+            first_staked_at: clock.unix_timestamp,
+            early_bird_until: user_stake.early_bird_until,
+            bump: staker_record_bump,
+        });
+    }
+    let new_staker = is_new_account && first_stake;
+
+    if is_new_account {
+        user_stake.version = USER_STAKE_VERSION;
+        user_stake.label = label.unwrap_or_default();
+    }
+    require!(
+        user_stake.version == USER_STAKE_VERSION,
+        StakingError::AccountVersionMismatch
+    );
 
     // Initialize user stake if first time
     if !user_stake.is_active {
-        user_stake.owner = ctx.accounts.user.key();
+        user_stake.owner = owner;
         user_stake.stake_pool = stake_pool.key();
         user_stake.tier = tier;
         user_stake.stake_start_time = clock.unix_timestamp;
-        user_stake.last_claim_time = clock.unix_timestamp;
-        user_stake.total_rewards_claimed = 0;
-        user_stake.pending_rewards = 0;
+        user_stake.loyalty_start_time = clock.unix_timestamp;
+        user_stake.lock_seconds = stake_pool.get_lock_period_for_tier(tier);
+
+        // An exited position keeps what it is still owed: pending and bonus
+        // rewards stay, and the share of `over_cap_rewards` the cap now
+        // funds moves into pending as on a top-up, the rest staying held
+        user_stake.pending_rewards = user_stake
+            .pending_rewards
+            .checked_add(pending)
+            .ok_or(StakingError::MathOverflow)?;
+        stake_pool.pending_liability = stake_pool
+            .pending_liability
+            .checked_add(pending)
+            .ok_or(StakingError::MathOverflow)?;
+        user_stake.mark_settled(stake_pool, clock.unix_timestamp);
         user_stake.is_active = true;
-        user_stake.bump = ctx.bumps.user_stake;
+        user_stake.bump = user_stake_bump;
+        user_stake.unlocked_withdrawn = 0;
+        user_stake.auto_renew = false;
+        if let Some(referrer) = referrer {
+            user_stake.referrer = referrer;
+            msg!("Referrer recorded: {}", referrer);
+        }
+
+        // First-ever stakers take an early-bird slot while any remain;
+        // slots are never returned, even if the staker later exits. A
+        // returning wallet's new account gets its original stamp back
+        if new_staker && stake_pool.early_bird_slots_remaining() > 0 {
+            user_stake.early_bird_until = clock
+                .unix_timestamp
+                .checked_add(stake_pool.early_bird_duration_secs)
+                .ok_or(StakingError::MathOverflow)?;
+            staker_record.early_bird_until = user_stake.early_bird_until;
+            stake_pool.early_bird_slots_used = stake_pool
+                .early_bird_slots_used
+                .checked_add(1)
+                .ok_or(StakingError::MathOverflow)?;
+            msg!(
+                "Early-bird slot {} assigned until {}",
+                stake_pool.early_bird_slots_used,
+                user_stake.early_bird_until
+            );
+        } else if is_new_account {
+            user_stake.early_bird_until = staker_record.early_bird_until;
+        }
         
-        // Update staker count with overflow check
-        stake_pool.staker_count = stake_pool.staker_count
-            .checked_add(1)
-            .ok_or(StakingError::MathOverflow)?;
+        // Update staker counts with overflow check
+        stake_pool.add_staker(tier)?;
     } else {
         // Store pending rewards before adding new stake
         user_stake.pending_rewards = user_stake
             .pending_rewards
             .checked_add(pending)
             .ok_or(StakingError::MathOverflow)?;
-        user_stake.last_claim_time = clock.unix_timestamp;
+        user_stake.mark_settled(stake_pool, clock.unix_timestamp);
+        stake_pool.pending_liability = stake_pool
+            .pending_liability
+            .checked_add(pending)
+            .ok_or(StakingError::MathOverflow)?;
+
+        match lock_policy {
+            TopUpLockPolicy::ExtendAll => {
+                user_stake.stake_start_time = clock.unix_timestamp;
+                user_stake.lock_seconds = stake_pool.get_lock_period_for_tier(tier);
+                user_stake.unlocked_withdrawn = 0;
+            }
+            // The deposit shares the running lock by weight, so it cannot
+            // skip a lock that has already (almost) run out
+            TopUpLockPolicy::KeepOriginal if user_stake.get_lock_period() > 0 => {
+                user_stake.roll_auto_renew(clock.unix_timestamp);
+                user_stake.stake_start_time =
+                    user_stake.weighted_lock_start(amount, clock.unix_timestamp);
+            }
+            _ => {}
+        }
     }
 
     // Update staked amount with overflow check
@@ -188,23 +797,77 @@ pub fn handler(ctx: Context<Stake>, amount: u64, tier: u8) -> Result<()> {
         .staked_amount
         .checked_add(amount)
         .ok_or(StakingError::MathOverflow)?;
+    user_stake.sync_bonus_debt(stake_pool.bonus_per_token)?;
+    user_stake.last_stake_time = clock.unix_timestamp;
 
     // Update pool totals with overflow check
-    stake_pool.total_staked = stake_pool
-        .total_staked
-        .checked_add(amount)
-        .ok_or(StakingError::MathOverflow)?;
+    stake_pool.add_staked(tier, amount)?;
+    stake_pool.record_deposit(amount, new_staker)?;
     stake_pool.last_updated = clock.unix_timestamp;
 
-    msg!("Staked {} tokens in tier {}", amount, tier);
+    if stake_pool.max_daily_inflow > 0 {
+        stake_pool.record_inflow(clock.unix_timestamp, amount)?;
+    }
+
+    if let Some(position_history) = position_history {
+        position_history.push(PositionRecord {
+            timestamp: clock.unix_timestamp,
+            kind: PositionEventKind::Stake,
+            tier: tier as u8,
+            amount,
+            staked_after: user_stake.staked_amount,
+        });
+    }
+
+    emit!(StakeEvent {
+        stake_pool: stake_pool.key(),
+        user: user_stake.owner,
+        tier: tier as u8,
+        amount,
+        staked_after: user_stake.staked_amount,
+        total_staked: stake_pool.total_staked,
+        tier_total_staked: stake_pool.tier_staked[tier as usize],
+        tier_staker_count: stake_pool.tier_staker_count[tier as usize],
+        remaining_capacity: stake_pool.remaining_capacity(),
+        cumulative_staked: stake_pool.cumulative_staked,
+        unique_stakers_ever: stake_pool.unique_stakers_ever,
+        label: user_stake.label,
+        sequence: stake_pool.sequence,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!("Staked {} tokens in tier {:?}", amount, tier);
     msg!("Total staked by user: {}", user_stake.staked_amount);
+    if let Some(remaining) = stake_pool.remaining_capacity() {
+        msg!(
+            "Pool capacity remaining: {} of {}",
+            remaining,
+            stake_pool.max_total_staked
+        );
+    }
+    msg!("Top-up lock policy: {:?}", lock_policy);
+
+    set_return_data(&lock_policy.try_to_vec()?);
 
     Ok(())
 }
 
 /// Calculate pending rewards for a user stake using safe integer math.
 ///
-/// Formula: rewards = staked_amount * apy * time_elapsed / (BASIS_POINTS * SECONDS_PER_YEAR)
+/// Formula: rewards = staked_amount * sum(apy * seconds) / (BASIS_POINTS * SECONDS_PER_YEAR)
+///
+/// The sum runs over the accruing part of `[last_claim_time, current_time)`;
+/// time-limited bonuses add their own `bonus_bps * overlapping_seconds` term.
+/// Under `downgrade_after_lock` the base APY is the tier's up to the lock end
+/// and Flex's after it.
+///
+/// Accrual held back by the cap at the last settlement (`over_cap_rewards`)
+/// is added back, and the total is limited to the position's
+/// `StakePool::funded_share`. Settled rewards therefore never outgrow the
+/// emission cap: once the pool's accrued liability exceeds the unreserved
+/// budget, every position gets its pro-rata share of it and accrual
+/// flat-lines until the admin raises the cap. `update_rewards` keeps the
+/// rest on the position.
 ///
 /// # Security
 /// - All arithmetic uses checked_* operations
@@ -223,71 +886,820 @@ pub fn calculate_pending_rewards(
     user_stake: &UserStake,
     stake_pool: &StakePool,
     current_time: i64,
+) -> Result<u64> {
+    let accrued = accrued_before_cap(user_stake, stake_pool, current_time)?;
+    Ok(stake_pool.funded_share(accrued, current_time))
+}
+
+/// Accrual since the last settlement plus what the cap held back then.
+fn accrued_before_cap(user_stake: &UserStake, stake_pool: &StakePool, now: i64) -> Result<u64> {
+    uncapped_pending_rewards(user_stake, stake_pool, now)?
+        .checked_add(user_stake.over_cap_rewards)
+        .ok_or_else(|| error!(StakingError::MathOverflow))
+}
+
+/// `calculate_pending_rewards` before the emission cap.
+fn uncapped_pending_rewards(
+    user_stake: &UserStake,
+    stake_pool: &StakePool,
+    current_time: i64,
 ) -> Result<u64> {
     // Early return for inactive or zero stake
     if !user_stake.is_active || user_stake.staked_amount == 0 {
         return Ok(0);
     }
 
+    // Reward-per-token pools share the emission rate instead of paying APYs
+    if stake_pool.reward_mode == RewardMode::RewardPerToken {
+        return accumulated_rewards(user_stake, stake_pool, current_time);
+    }
+
     // === SAFE TIME CALCULATION ===
-    
+
+    // Nothing accrues past the pool's rewards end time
+    let current_time = stake_pool.accrual_cutoff(current_time);
+
     // Validate timestamps are positive
     require!(current_time >= 0, StakingError::InvalidTimestamp);
     require!(user_stake.last_claim_time >= 0, StakingError::InvalidTimestamp);
-    
-    // Calculate time elapsed (i64 subtraction)
-    let time_diff: i64 = current_time
-        .checked_sub(user_stake.last_claim_time)
-        .ok_or(StakingError::MathUnderflow)?;
+
+    // Time the pool spent halted, or with an empty treasury under
+    // accrue_only_when_funded, since the last settlement earns nothing. It is
+    // taken off the start of the interval: the total is exact, but a rate
+    // change or bonus expiry inside the interval is priced as if the halt
+    // or empty window came first
+    let start = user_stake
+        .last_claim_time
+        .saturating_add(user_stake.halted_seconds(stake_pool, current_time))
+        .saturating_add(user_stake.unfunded_seconds(stake_pool, current_time));
+
+    // Abandoned positions stop accruing once the horizon is reached (0 = unlimited)
+    let end = if stake_pool.max_accrual_horizon_secs > 0 {
+        current_time.min(start.saturating_add(stake_pool.max_accrual_horizon_secs))
+    } else {
+        current_time
+    };
     
     // If no time has passed or negative (clock skew), return 0
-    if time_diff <= 0 {
+    if end <= start {
         return Ok(0);
     }
-    
-    // Safe i64 to u64 conversion (we know it's positive)
-    let time_elapsed: u64 = time_diff as u64;
 
-    // === SAFE REWARD CALCULATION ===
-    
-    // Get APY for this tier (returns 0 for invalid tier)
-    let apy = stake_pool.get_apy_for_tier(user_stake.tier);
-    if apy == 0 {
+    // Intervals shorter than the pool minimum earn nothing (flash stakes)
+    if end - start < stake_pool.min_accrual_seconds {
         return Ok(0);
     }
-    
-    // Use u128 for intermediate calculations to prevent overflow
-    // Max values: staked_amount (u64::MAX) * apy (5000) * time (u64::MAX)
-    // This could overflow u64 but not u128
-    let staked: u128 = user_stake.staked_amount as u128;
-    let apy_128: u128 = apy as u128;
-    let time_128: u128 = time_elapsed as u128;
-    let year_seconds: u128 = SECONDS_PER_YEAR as u128;
-    let basis_points: u128 = BASIS_POINTS_DENOMINATOR as u128;
-
-    // Numerator: staked * apy * time
-    let numerator = staked
-        .checked_mul(apy_128)
+
+    // === RATE INTEGRATION ===
+
+    // Accumulate apy_bps * seconds; bonuses covering only part of the interval
+    // contribute just their overlapping seconds, which splits the interval at
+    // each bonus boundary without changing the single final division
+
+    // An ended lock earns the Flex APY from its lock end until the next relock
+    let downgrade_at = if stake_pool.downgrade_after_lock {
+        user_stake.downgrade_time()
+    } else {
+        None
+    };
+    let tier_end = downgrade_at.map_or(end, |at| at.clamp(start, end));
+
+    // Base APY, split at each APY change so adjustments are never retroactive
+    let mut rate_seconds = stake_pool
+        .apy_seconds_for_tier(user_stake.tier, start, tier_end)?
+        .checked_add(stake_pool.apy_seconds_for_tier(Tier::Flex, tier_end, end)?)
+        .ok_or(StakingError::MathOverflow)?;
+
+    // Boost campaigns multiply the base APY over the seconds they cover
+    rate_seconds = rate_seconds
+        .checked_add(stake_pool.apy_boost_seconds(user_stake.tier, start, tier_end)?)
         .ok_or(StakingError::MathOverflow)?
-        .checked_mul(time_128)
+        .checked_add(stake_pool.apy_boost_seconds(Tier::Flex, tier_end, end)?)
         .ok_or(StakingError::MathOverflow)?;
 
-    // Denominator: basis_points * year_seconds
-    let denominator = basis_points
-        .checked_mul(year_seconds)
+    // Early-bird bonus until the staker's slot expires
+    if stake_pool.early_bird_bonus_bps > 0 && user_stake.early_bird_until > start {
+        let bonus_end = end.min(user_stake.early_bird_until);
+        rate_seconds = rate_seconds
+            .checked_add(bps_seconds(
+                stake_pool.early_bird_bonus_bps,
+                bonus_end - start,
+            )?)
+            .ok_or(StakingError::MathOverflow)?;
+    }
+
+    // Loyalty bonus, stepping up as each threshold is crossed
+    rate_seconds = rate_seconds
+        .checked_add(stake_pool.loyalty_bps_seconds(
+            user_stake.tier,
+            user_stake.loyalty_start_time,
+            start,
+            end,
+        )?)
         .ok_or(StakingError::MathOverflow)?;
-    
-    // Prevent division by zero (should never happen with constants)
-    require!(denominator > 0, StakingError::DivisionByZero);
 
-    // Calculate rewards
-    let rewards_128 = numerator
-        .checked_div(denominator)
-        .ok_or(StakingError::DivisionByZero)?;
+    // Boost-locked rewards earn boost_bps on top, i.e. the position gets
+    // +boost_bps scaled by the boosted fraction of its stake, until expiry
+    let boost_weight = user_stake.boosted_amount.min(user_stake.staked_amount);
+    let boost_seconds = if stake_pool.boost_bps > 0 && boost_weight > 0 && user_stake.boost_until > start {
+        let boost_end = end.min(user_stake.boost_until);
+        bps_seconds(stake_pool.boost_bps, boost_end - start)?
+    } else {
+        0
+    };
+
+    if rate_seconds == 0 && boost_seconds == 0 {
+        return Ok(0);
+    }
+
+    // === SAFE REWARD CALCULATION ===
+
+    // u128 numerator, single rounding-down division; see `math`
+    rewards_from_rate_seconds(user_stake.staked_amount, rate_seconds, boost_weight, boost_seconds)
+}
+
+/// Reward-per-token share of a position since it was last settled:
+/// `staked_amount * (reward_per_token(now) - reward_per_token_paid) / PRECISION`.
+fn accumulated_rewards(user_stake: &UserStake, stake_pool: &StakePool, current_time: i64) -> Result<u64> {
+    let per_token = stake_pool
+        .reward_per_token(current_time)?
+        .checked_sub(user_stake.reward_per_token_paid)
+        .ok_or(StakingError::MathUnderflow)?;
+    per_token_rewards(user_stake.staked_amount, per_token)
+}
+
+/// Checkpoint the reward-per-token accumulator for the pool and `user_stake`,
+/// settle the position's donation share (`settle_donations`) and take its
+/// accrual out of the pool's `accrued_liability`.
+///
+/// Callers first fold `calculate_pending_rewards` into the position (it
+/// returns the share being checkpointed here), then call this before
+/// changing any balance. What the emission cap held back from that share
+/// stays on the position as `over_cap_rewards`. The accumulator part is a
+/// no-op in APY pools.
+pub fn update_rewards(stake_pool: &mut StakePool, user_stake: &mut UserStake, now: i64) -> Result<()> {
+    // Callers already priced this through `calculate_pending_rewards`;
+    // `emergency_withdraw` must never be blocked by it
+    let accrued = accrued_before_cap(user_stake, stake_pool, now).unwrap_or(user_stake.over_cap_rewards);
+    let funded = stake_pool.funded_share(accrued, now);
+    stake_pool.accrue_liability(now);
+    stake_pool.accrued_liability = stake_pool.accrued_liability.saturating_sub(funded);
+    user_stake.over_cap_rewards = accrued - funded;
+
+    settle_donations(stake_pool, user_stake)?;
+    if stake_pool.reward_mode != RewardMode::RewardPerToken {
+        return Ok(());
+    }
+    let earned = if user_stake.is_active {
+        accumulated_rewards(user_stake, stake_pool, now)?
+    } else {
+        0
+    };
+    stake_pool.update_reward_per_token(now)?;
+    user_stake.reward_per_token_paid = stake_pool.reward_per_token_stored;
+    user_stake.rewards_earned = user_stake.rewards_earned.saturating_add(earned);
+    Ok(())
+}
+
+/// Credit the position's share of donations to its pending rewards as
+/// bonus, moving it from `donation_liability` to `bonus_liability`.
+///
+/// Runs before `staked_amount` changes; `UserStake::sync_bonus_debt` must
+/// follow the change.
+pub fn settle_donations(stake_pool: &mut StakePool, user_stake: &mut UserStake) -> Result<()> {
+    let owed = user_stake.pending_donation(stake_pool.bonus_per_token)?;
+    user_stake.sync_bonus_debt(stake_pool.bonus_per_token)?;
+    if owed == 0 {
+        return Ok(());
+    }
+    user_stake.pending_rewards = user_stake
+        .pending_rewards
+        .checked_add(owed)
+        .ok_or(StakingError::MathOverflow)?;
+    user_stake.bonus_rewards = user_stake
+        .bonus_rewards
+        .checked_add(owed)
+        .ok_or(StakingError::MathOverflow)?;
+    stake_pool.donation_liability = stake_pool.donation_liability.saturating_sub(owed);
+    stake_pool.bonus_liability = stake_pool
+        .bonus_liability
+        .checked_add(owed)
+        .ok_or(StakingError::MathOverflow)?;
+    stake_pool.pending_liability = stake_pool
+        .pending_liability
+        .checked_add(owed)
+        .ok_or(StakingError::MathOverflow)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::ApyBoost;
+
+    const STAKED: u64 = 1_000_000_000;
+
+    fn setup() -> (UserStake, StakePool) {
+        let mut pool =
+            StakePool::try_deserialize_unchecked(&mut &[0u8; StakePool::LEN][..]).unwrap();
+        pool.flex_apy = 1_000;
+        pool.boost_bps = 1_000;
+        pool.emission_cap = u64::MAX;
+
+        let mut stake =
+            UserStake::try_deserialize_unchecked(&mut &[0u8; UserStake::LEN][..]).unwrap();
+        stake.is_active = true;
+        stake.staked_amount = STAKED;
+        stake.tier = Tier::Flex;
+        (stake, pool)
+    }
+
+    #[test]
+    fn intervals_below_min_accrual_earn_nothing() {
+        let (stake, mut pool) = setup();
+        pool.min_accrual_seconds = 60;
+        assert_eq!(calculate_pending_rewards(&stake, &pool, 59).unwrap(), 0);
+
+        // At the threshold the whole interval accrues, not just the excess
+        let expected = STAKED as u128 * 1_000 * 60
+            / (SECONDS_PER_YEAR as u128 * BASIS_POINTS_DENOMINATOR as u128);
+        assert_eq!(calculate_pending_rewards(&stake, &pool, 60).unwrap(), expected as u64);
+
+        pool.min_accrual_seconds = 0;
+        assert!(calculate_pending_rewards(&stake, &pool, 1).unwrap() > 0);
+    }
+
+    fn halt(pool: &mut StakePool, from: i64, to: i64) {
+        pool.accrue_while_paused = false;
+        pool.pause_flags = StakePool::PAUSE_STAKE_BIT;
+        pool.sync_pause_clock(from).unwrap();
+        pool.pause_flags = 0;
+        pool.sync_pause_clock(to).unwrap();
+    }
+
+    #[test]
+    fn accrual_stops_at_rewards_end() {
+        let (mut stake, mut pool) = setup();
+        let day = SECONDS_PER_DAY;
+        pool.rewards_end_time = 10 * day;
+
+        let at_end = calculate_pending_rewards(&stake, &pool, 10 * day).unwrap();
+        assert!(at_end > 0);
+        assert_eq!(calculate_pending_rewards(&stake, &pool, 400 * day).unwrap(), at_end);
+        assert!(pool.has_ended(10 * day));
+        assert!(!pool.has_ended(10 * day - 1));
+
+        // Settled after the end, the position has nothing left to accrue
+        stake.last_claim_time = 20 * day;
+        assert_eq!(calculate_pending_rewards(&stake, &pool, 30 * day).unwrap(), 0);
+        stake.last_claim_time = 10 * day;
+        assert_eq!(calculate_pending_rewards(&stake, &pool, 30 * day).unwrap(), 0);
+    }
+
+    #[test]
+    fn halted_time_between_claims_earns_nothing() {
+        let (stake, mut pool) = setup();
+        let (_, unpaused) = setup();
+        let day = SECONDS_PER_DAY;
+
+        halt(&mut pool, 10 * day, 20 * day);
+        assert_eq!(
+            calculate_pending_rewards(&stake, &pool, 30 * day).unwrap(),
+            calculate_pending_rewards(&stake, &unpaused, 20 * day).unwrap()
+        );
+
+        // Two halts before the next claim are both excluded
+        halt(&mut pool, 40 * day, 45 * day);
+        assert_eq!(
+            calculate_pending_rewards(&stake, &pool, 50 * day).unwrap(),
+            calculate_pending_rewards(&stake, &unpaused, 35 * day).unwrap()
+        );
+    }
+
+    fn run_dry(pool: &mut StakePool, from: i64, to: i64) {
+        pool.treasury_empty_since = Some(from);
+        pool.close_empty_window(to).unwrap();
+    }
+
+    #[test]
+    fn every_empty_window_between_claims_earns_nothing() {
+        let (mut stake, mut pool) = setup();
+        let (fresh, funded) = setup();
+        let day = SECONDS_PER_DAY;
+        pool.accrue_only_when_funded = true;
+
+        // Two empty windows before the next claim are both excluded
+        run_dry(&mut pool, 10 * day, 20 * day);
+        run_dry(&mut pool, 40 * day, 45 * day);
+        assert_eq!(
+            calculate_pending_rewards(&stake, &pool, 50 * day).unwrap(),
+            calculate_pending_rewards(&fresh, &funded, 35 * day).unwrap()
+        );
+
+        // Settled inside an open window, nothing accrues until it closes
+        pool.treasury_empty_since = Some(60 * day);
+        stake.mark_settled(&pool, 65 * day);
+        assert_eq!(calculate_pending_rewards(&stake, &pool, 70 * day).unwrap(), 0);
+        pool.close_empty_window(70 * day).unwrap();
+        assert_eq!(
+            calculate_pending_rewards(&stake, &pool, 80 * day).unwrap(),
+            calculate_pending_rewards(&fresh, &funded, 10 * day).unwrap()
+        );
+
+        // Without the option, empty windows accrue like any other time
+        pool.accrue_only_when_funded = false;
+        assert_eq!(
+            calculate_pending_rewards(&stake, &pool, 80 * day).unwrap(),
+            calculate_pending_rewards(&fresh, &funded, 15 * day).unwrap()
+        );
+    }
+
+    #[test]
+    fn settling_during_a_halt_leaves_no_gap_after_unpause() {
+        let (mut stake, mut pool) = setup();
+        let (_, unpaused) = setup();
+        let day = SECONDS_PER_DAY;
+        let ten_days = calculate_pending_rewards(&stake, &unpaused, 10 * day).unwrap();
+
+        pool.accrue_while_paused = false;
+        pool.pause_flags = StakePool::PAUSE_STAKE_BIT;
+        pool.sync_pause_clock(10 * day).unwrap();
+
+        // Nothing accrues while halted, before or after settling in it
+        assert_eq!(calculate_pending_rewards(&stake, &pool, 15 * day).unwrap(), ten_days);
+        stake.mark_settled(&pool, 15 * day);
+        assert_eq!(calculate_pending_rewards(&stake, &pool, 18 * day).unwrap(), 0);
+
+        pool.pause_flags = 0;
+        pool.sync_pause_clock(20 * day).unwrap();
+        assert_eq!(calculate_pending_rewards(&stake, &pool, 30 * day).unwrap(), ten_days);
+    }
+
+    #[test]
+    fn pausing_with_accrual_on_keeps_accruing() {
+        let (stake, mut pool) = setup();
+        let (_, unpaused) = setup();
+        pool.accrue_while_paused = true;
+        pool.pause_flags = StakePool::PAUSE_STAKE_BIT;
+        pool.sync_pause_clock(SECONDS_PER_DAY).unwrap();
+
+        assert_eq!(
+            calculate_pending_rewards(&stake, &pool, 10 * SECONDS_PER_DAY).unwrap(),
+            calculate_pending_rewards(&stake, &unpaused, 10 * SECONDS_PER_DAY).unwrap()
+        );
+    }
+
+    #[test]
+    fn apy_change_does_not_reprice_elapsed_time() {
+        let (mut stake, mut pool) = setup();
+        let half_year = SECONDS_PER_YEAR / 2;
+
+        // 10% for the first half, 30% for the second: 5% + 15%
+        pool.checkpoint_apy(half_year).unwrap();
+        pool.flex_apy = 3_000;
+        assert_eq!(calculate_pending_rewards(&stake, &pool, SECONDS_PER_YEAR).unwrap(), 200_000_000);
+
+        // Settled at the change, only the new rate applies afterwards
+        stake.last_claim_time = half_year;
+        assert_eq!(calculate_pending_rewards(&stake, &pool, SECONDS_PER_YEAR).unwrap(), 150_000_000);
+    }
+
+    #[test]
+    fn accrual_flat_lines_at_the_emission_cap() {
+        let (stake, mut pool) = setup();
+        let quarter = SECONDS_PER_YEAR / 4;
+
+        // 10% a year on 1e9 is 25_000_000 a quarter; the cap leaves room for
+        // 60_000_000 once payouts and settled rewards are reserved
+        pool.emission_cap = 100_000_000;
+        pool.total_distributed = 30_000_000;
+        pool.pending_liability = 10_000_000;
+        assert_eq!(calculate_pending_rewards(&stake, &pool, quarter).unwrap(), 25_000_000);
+        assert_eq!(calculate_pending_rewards(&stake, &pool, 3 * quarter).unwrap(), 60_000_000);
+        assert_eq!(calculate_pending_rewards(&stake, &pool, 4 * quarter).unwrap(), 60_000_000);
+
+        // Raising the cap lets the unsettled interval accrue again
+        pool.emission_cap = 200_000_000;
+        assert_eq!(calculate_pending_rewards(&stake, &pool, 4 * quarter).unwrap(), 100_000_000);
+
+        // A cap fully reserved by settled rewards leaves nothing to accrue
+        pool.pending_liability = 170_000_000;
+        assert_eq!(calculate_pending_rewards(&stake, &pool, 4 * quarter).unwrap(), 0);
+    }
+
+    #[test]
+    fn capped_accrual_is_shared_and_kept_until_the_cap_rises() {
+        let (mut a, mut pool) = setup();
+        let mut b = a.clone();
+        let start = 1_000;
+        let now = start + SECONDS_PER_YEAR / 4;
+        a.last_claim_time = start;
+        b.last_claim_time = start;
+        pool.add_staked(Tier::Flex, 2 * STAKED).unwrap();
+        pool.liability_accrued_at = start;
+        pool.emission_cap = 30_000_000;
+
+        let settle = |pool: &mut StakePool, stake: &mut UserStake| {
+            let pending = calculate_pending_rewards(stake, pool, now).unwrap();
+            update_rewards(pool, stake, now).unwrap();
+            stake.pending_rewards += pending;
+            stake.mark_settled(pool, now);
+            pool.pending_liability += pending;
+        };
+
+        // Each accrued 25_000_000 against 30_000_000 of budget, so each is
+        // quoted its pro-rata share rather than the whole budget
+        assert_eq!(pool.accrued_liability_at(now), 50_000_000);
+        assert_eq!(calculate_pending_rewards(&a, &pool, now).unwrap(), 15_000_000);
+        assert_eq!(calculate_pending_rewards(&b, &pool, now).unwrap(), 15_000_000);
+
+        // Settling while capped keeps the held-back part on the position
+        settle(&mut pool, &mut a);
+        assert_eq!(a.pending_rewards, 15_000_000);
+        assert_eq!(a.over_cap_rewards, 10_000_000);
+        assert_eq!(pool.accrued_liability, 35_000_000);
+
+        settle(&mut pool, &mut b);
+        assert_eq!(b.pending_rewards, 10_714_285);
+        assert!(pool.pending_liability <= pool.emission_cap);
+
+        // Raising the cap pays what was held back
+        pool.emission_cap = 1_000_000_000;
+        assert_eq!(calculate_pending_rewards(&a, &pool, now).unwrap(), 10_000_000);
+        settle(&mut pool, &mut a);
+        assert_eq!(a.pending_rewards, 25_000_000);
+        assert_eq!(a.over_cap_rewards, 0);
+    }
+
+    #[test]
+    fn ended_lock_earns_flex_apy_when_downgrade_is_on() {
+        let (mut stake, mut pool) = setup();
+        let quarter = SECONDS_PER_YEAR / 4;
+        pool.prime_apy = 3_000;
+        stake.tier = Tier::Prime;
+        stake.lock_seconds = quarter;
+
+        // Without the flag the whole year earns 30%
+        assert_eq!(calculate_pending_rewards(&stake, &pool, SECONDS_PER_YEAR).unwrap(), 300_000_000);
+
+        // A quarter at 30% then three at 10%: 7.5% + 7.5%
+        pool.downgrade_after_lock = true;
+        assert_eq!(calculate_pending_rewards(&stake, &pool, SECONDS_PER_YEAR).unwrap(), 150_000_000);
+
+        // An interval entirely after the lock end is all Flex, one before it all Prime
+        stake.last_claim_time = quarter;
+        assert_eq!(calculate_pending_rewards(&stake, &pool, 2 * quarter).unwrap(), 25_000_000);
+        stake.last_claim_time = 0;
+        assert_eq!(calculate_pending_rewards(&stake, &pool, quarter).unwrap(), 75_000_000);
+
+        // Auto-renew keeps the lock, and the rate, running
+        stake.auto_renew = true;
+        assert_eq!(calculate_pending_rewards(&stake, &pool, SECONDS_PER_YEAR).unwrap(), 300_000_000);
+    }
+
+    #[test]
+    fn boost_applies_only_to_the_overlapping_seconds() {
+        let (stake, mut pool) = setup();
+        let half_window = 15 * SECONDS_PER_DAY;
+
+        // A year at 10% is 100_000_000; doubling its last 15 days adds 15/365
+        // of that. The campaign runs past the claim, which only sees its first half
+        pool.schedule_apy_boost(
+            ApyBoost {
+                multiplier_bps: 20_000,
+                start_time: SECONDS_PER_YEAR - half_window,
+                end_time: SECONDS_PER_YEAR + half_window,
+            },
+            0,
+        )
+        .unwrap();
+        assert_eq!(
+            calculate_pending_rewards(&stake, &pool, SECONDS_PER_YEAR).unwrap(),
+            100_000_000 + 100_000_000 * 15 / 365
+        );
+    }
+
+    #[test]
+    fn boost_that_ended_before_the_claim_is_still_paid() {
+        let (mut stake, mut pool) = setup();
+        let month = 30 * SECONDS_PER_DAY;
+
+        // 1.5x over the second month: half of 30/365 of the year's 10% on top
+        pool.schedule_apy_boost(
+            ApyBoost { multiplier_bps: 15_000, start_time: month, end_time: 2 * month },
+            0,
+        )
+        .unwrap();
+        assert_eq!(
+            calculate_pending_rewards(&stake, &pool, SECONDS_PER_YEAR).unwrap(),
+            100_000_000 + 50_000_000 * 30 / 365
+        );
+
+        // Settled after the campaign, nothing more is boosted
+        stake.last_claim_time = 2 * month;
+        assert_eq!(
+            calculate_pending_rewards(&stake, &pool, SECONDS_PER_YEAR).unwrap(),
+            100_000_000 * 305 / 365
+        );
+    }
+
+    #[test]
+    fn decay_is_priced_per_interval_not_at_claim_time() {
+        let (stake, mut pool) = setup();
+        pool.decay_interval_seconds = SECONDS_PER_YEAR / 2;
+        pool.decay_bps_per_interval = 500;
+
+        // 10% for the first half, 5% for the second: 5% + 2.5%
+        assert_eq!(pool.get_apy_for_tier(Tier::Flex, SECONDS_PER_YEAR), 0);
+        assert_eq!(calculate_pending_rewards(&stake, &pool, SECONDS_PER_YEAR).unwrap(), 75_000_000);
+    }
+
+    fn loyalty_pool() -> (UserStake, StakePool) {
+        let (mut stake, mut pool) = setup();
+        pool.loyalty_threshold_secs[..2].copy_from_slice(&[SECONDS_PER_YEAR / 2, SECONDS_PER_YEAR]);
+        pool.loyalty_bonus_bps[..2].copy_from_slice(&[100, 200]);
+        stake.loyalty_start_time = 1;
+        stake.last_claim_time = 1;
+        (stake, pool)
+    }
+
+    #[test]
+    fn loyalty_bonus_is_priced_per_threshold() {
+        let (mut stake, pool) = loyalty_pool();
+
+        // Below the first threshold only the tier APY accrues
+        assert_eq!(
+            calculate_pending_rewards(&stake, &pool, 1 + SECONDS_PER_YEAR / 2).unwrap(),
+            50_000_000
+        );
+
+        // 10% all year, +1% for the second half: 10% + 0.5%
+        assert_eq!(
+            calculate_pending_rewards(&stake, &pool, 1 + SECONDS_PER_YEAR).unwrap(),
+            105_000_000
+        );
+
+        // Settled at the first threshold, the next half year is all +1%,
+        // then +2% once the second threshold is crossed: 5.5% + 6%
+        stake.last_claim_time = 1 + SECONDS_PER_YEAR / 2;
+        assert_eq!(
+            calculate_pending_rewards(&stake, &pool, 1 + 3 * SECONDS_PER_YEAR / 2).unwrap(),
+            115_000_000
+        );
+    }
+
+    #[test]
+    fn loyalty_bonus_is_capped_at_max_apy() {
+        let (stake, mut pool) = loyalty_pool();
+        pool.flex_apy = MAX_APY - 150;
+
+        // +100bp fits, the +200bp level is held to +150bp
+        let base = STAKED as u128 * (MAX_APY as u128 - 150) / BASIS_POINTS_DENOMINATOR as u128;
+        // Two years: +100bp for 1.5 of them, the extra +50bp for the last one
+        let bonus = STAKED as u128 * (150 + 50) / BASIS_POINTS_DENOMINATOR as u128;
+        let end = 1 + 2 * SECONDS_PER_YEAR;
+        assert_eq!(calculate_pending_rewards(&stake, &pool, end).unwrap(), (2 * base + bonus) as u64);
+    }
+
+    #[test]
+    fn loyalty_needs_an_open_run() {
+        let (mut stake, pool) = loyalty_pool();
+        stake.loyalty_start_time = 0;
+        assert_eq!(
+            calculate_pending_rewards(&stake, &pool, 1 + SECONDS_PER_YEAR).unwrap(),
+            100_000_000
+        );
+    }
+
+    #[test]
+    fn boost_scales_with_boosted_fraction_until_expiry() {
+        let (mut stake, pool) = setup();
+        assert_eq!(calculate_pending_rewards(&stake, &pool, SECONDS_PER_YEAR).unwrap(), 100_000_000);
 
-    // Safe conversion back to u64
-    let rewards = u64::try_from(rewards_128)
-        .map_err(|_| StakingError::ConversionOverflow)?;
+        // Half the stake boosted for half the year: +10% * 50% * 50%
+        stake.boosted_amount = STAKED / 2;
+        stake.boost_until = SECONDS_PER_YEAR / 2;
+        assert_eq!(calculate_pending_rewards(&stake, &pool, SECONDS_PER_YEAR).unwrap(), 125_000_000);
+    }
+
+    #[test]
+    fn boost_weight_is_capped_at_the_stake() {
+        let (mut stake, pool) = setup();
+        stake.boosted_amount = STAKED * 3;
+        stake.boost_until = SECONDS_PER_YEAR;
+        assert_eq!(calculate_pending_rewards(&stake, &pool, SECONDS_PER_YEAR).unwrap(), 200_000_000);
+    }
+
+    #[test]
+    fn compounded_rewards_accrue_going_forward() {
+        let (mut stake, pool) = setup();
+        let first = calculate_pending_rewards(&stake, &pool, SECONDS_PER_YEAR).unwrap();
+        assert_eq!(first, 100_000_000);
+
+        // Compounding settles up to now and adds the rewards to the principal
+        stake.staked_amount += first;
+        stake.last_claim_time = SECONDS_PER_YEAR;
+        assert_eq!(
+            calculate_pending_rewards(&stake, &pool, 2 * SECONDS_PER_YEAR).unwrap(),
+            110_000_000
+        );
+    }
+
+    fn reward_per_token_pool(rate: u64) -> StakePool {
+        let (_, mut pool) = setup();
+        pool.reward_mode = RewardMode::RewardPerToken;
+        pool.reward_rate_per_second = rate;
+        pool
+    }
+
+    #[test]
+    fn reward_per_token_stops_at_rewards_end() {
+        let mut pool = reward_per_token_pool(1_000);
+        pool.add_staked(Tier::Flex, STAKED).unwrap();
+        pool.rewards_end_time = 10;
+        let at_end = pool.reward_per_token(10).unwrap();
+        assert_eq!(pool.reward_per_token(1_000).unwrap(), at_end);
+
+        pool.update_reward_per_token(1_000).unwrap();
+        assert_eq!(pool.reward_per_token(2_000).unwrap(), at_end);
+    }
+
+    #[test]
+    fn reward_per_token_freezes_while_halted() {
+        let mut pool = reward_per_token_pool(1_000);
+        pool.add_staked(Tier::Flex, STAKED).unwrap();
+        let per_ten_seconds = pool.reward_per_token(10).unwrap();
+
+        halt(&mut pool, 10, 20);
+        assert_eq!(pool.reward_per_token_stored, per_ten_seconds);
+        assert_eq!(pool.reward_per_token(30).unwrap(), 2 * per_ten_seconds);
+    }
+
+    fn empty_position() -> UserStake {
+        let (mut stake, _) = setup();
+        stake.is_active = false;
+        stake.staked_amount = 0;
+        stake
+    }
+
+    /// What every handler does first: fold the accrued share, then checkpoint.
+    fn settle(pool: &mut StakePool, stake: &mut UserStake, now: i64) {
+        let pending = calculate_pending_rewards(stake, pool, now).unwrap();
+        update_rewards(pool, stake, now).unwrap();
+        stake.pending_rewards += pending;
+    }
+
+    fn join(pool: &mut StakePool, stake: &mut UserStake, amount: u64, now: i64) {
+        settle(pool, stake, now);
+        stake.is_active = true;
+        stake.staked_amount += amount;
+        pool.add_staked(Tier::Flex, amount).unwrap();
+    }
+
+    fn leave(pool: &mut StakePool, stake: &mut UserStake, now: i64) {
+        settle(pool, stake, now);
+        pool.remove_staked(Tier::Flex, stake.staked_amount).unwrap();
+        stake.staked_amount = 0;
+        stake.is_active = false;
+    }
+
+    #[test]
+    fn accumulator_splits_the_rate_between_joining_and_leaving_stakers() {
+        let mut pool = reward_per_token_pool(100);
+        let (mut a, mut b, mut c) = (empty_position(), empty_position(), empty_position());
+
+        join(&mut pool, &mut a, 100, 0);
+        join(&mut pool, &mut b, 300, 10);
+        leave(&mut pool, &mut a, 20);
+        join(&mut pool, &mut c, 100, 30);
+        for stake in [&mut a, &mut b, &mut c] {
+            settle(&mut pool, stake, 40);
+        }
 
-    Ok(rewards)
+        // 0-10: A alone; 10-20: A 1/4, B 3/4; 20-30: B alone; 30-40: B 3/4, C 1/4.
+        // B's solo stretch is 1_000 / 300 per token, which rounds down to 999.
+        assert_eq!(a.pending_rewards, 1_000 + 250);
+        assert_eq!(b.pending_rewards, 750 + 999 + 750);
+        assert_eq!(c.pending_rewards, 250);
+        // Rounding only ever leaves dust in the pool, never pays out more
+        let earned = a.rewards_earned + b.rewards_earned + c.rewards_earned;
+        assert!((100 * 40 - 3..=100 * 40).contains(&earned));
+    }
+
+    #[test]
+    fn accumulator_settles_once_per_interval() {
+        let mut pool = reward_per_token_pool(100);
+        let mut a = empty_position();
+        join(&mut pool, &mut a, 100, 0);
+
+        // Settling twice at the same time, or reading after a settle, adds nothing
+        settle(&mut pool, &mut a, 10);
+        settle(&mut pool, &mut a, 10);
+        assert_eq!(a.pending_rewards, 1_000);
+        assert_eq!(calculate_pending_rewards(&a, &pool, 10).unwrap(), 0);
+        assert_eq!(calculate_pending_rewards(&a, &pool, 15).unwrap(), 500);
+    }
+
+    #[test]
+    fn empty_pool_time_is_not_distributed() {
+        let mut pool = reward_per_token_pool(100);
+        let mut a = empty_position();
+
+        join(&mut pool, &mut a, 100, 1_000);
+        settle(&mut pool, &mut a, 1_010);
+        assert_eq!(a.pending_rewards, 1_000);
+
+        // A later joiner gets nothing for time before it staked
+        let mut b = empty_position();
+        join(&mut pool, &mut b, 100, 1_010);
+        assert_eq!(calculate_pending_rewards(&b, &pool, 1_010).unwrap(), 0);
+        assert_eq!(calculate_pending_rewards(&b, &pool, 1_020).unwrap(), 500);
+    }
+
+    #[test]
+    fn rate_change_is_not_retroactive() {
+        let mut pool = reward_per_token_pool(100);
+        let mut a = empty_position();
+        join(&mut pool, &mut a, 100, 0);
+
+        pool.update_reward_per_token(10).unwrap();
+        pool.reward_rate_per_second = 300;
+        assert_eq!(calculate_pending_rewards(&a, &pool, 20).unwrap(), 1_000 + 3_000);
+    }
+
+    #[test]
+    fn accumulator_never_pays_more_than_was_emitted() {
+        let mut pool = reward_per_token_pool(7);
+        let mut stakes = [empty_position(), empty_position(), empty_position()];
+        for (i, (stake, amount)) in stakes.iter_mut().zip([333, 667, 1]).enumerate() {
+            join(&mut pool, stake, amount, i as i64 * 3);
+        }
+        leave(&mut pool, &mut stakes[1], 11);
+        for stake in stakes.iter_mut() {
+            settle(&mut pool, stake, 29);
+        }
+
+        let paid: u64 = stakes.iter().map(|stake| stake.pending_rewards).sum();
+        assert!(paid <= 7 * 29);
+        // Rounding loses at most one token per settlement
+        assert!(paid + 10 >= 7 * 29);
+    }
+
+    #[test]
+    fn apy_pools_ignore_the_accumulator() {
+        let (mut stake, mut pool) = setup();
+        pool.reward_rate_per_second = 1_000;
+        update_rewards(&mut pool, &mut stake, SECONDS_PER_YEAR).unwrap();
+        assert_eq!(pool.last_update_time, 0);
+        assert_eq!(stake.reward_per_token_paid, 0);
+        assert_eq!(calculate_pending_rewards(&stake, &pool, SECONDS_PER_YEAR).unwrap(), 100_000_000);
+    }
+
+    #[test]
+    fn expired_boost_adds_nothing() {
+        let (mut stake, pool) = setup();
+        stake.boosted_amount = STAKED;
+        stake.boost_until = 100;
+        stake.last_claim_time = 100;
+        assert_eq!(
+            calculate_pending_rewards(&stake, &pool, SECONDS_PER_YEAR + 100).unwrap(),
+            100_000_000
+        );
+    }
+
+    #[test]
+    fn donations_follow_stake_at_donation_time() {
+        let (mut alice, mut pool) = setup();
+        let (mut bob, _) = setup();
+        bob.staked_amount = 0;
+        pool.add_staked(Tier::Flex, STAKED).unwrap();
+        assert_eq!(pool.record_donation(1_000).unwrap(), 1_000);
+
+        // Bob joins with three times Alice's stake after the first donation
+        update_rewards(&mut pool, &mut bob, 0).unwrap();
+        bob.staked_amount = 3 * STAKED;
+        bob.sync_bonus_debt(pool.bonus_per_token).unwrap();
+        pool.add_staked(Tier::Flex, 3 * STAKED).unwrap();
+        assert_eq!(bob.pending_donation(pool.bonus_per_token).unwrap(), 0);
+
+        pool.record_donation(4_000).unwrap();
+        assert_eq!(alice.pending_donation(pool.bonus_per_token).unwrap(), 2_000);
+        assert_eq!(bob.pending_donation(pool.bonus_per_token).unwrap(), 3_000);
+
+        // Alice exits: her share is settled as credited bonus
+        update_rewards(&mut pool, &mut alice, 0).unwrap();
+        assert_eq!(alice.pending_rewards, 2_000);
+        assert_eq!(alice.bonus_rewards, 2_000);
+        alice.staked_amount = 0;
+        alice.sync_bonus_debt(pool.bonus_per_token).unwrap();
+        pool.remove_staked(Tier::Flex, STAKED).unwrap();
+
+        pool.record_donation(3_000).unwrap();
+        assert_eq!(alice.pending_donation(pool.bonus_per_token).unwrap(), 0);
+        assert_eq!(bob.pending_donation(pool.bonus_per_token).unwrap(), 6_000);
+        assert_eq!(pool.donation_liability, 6_000);
+        assert_eq!(pool.bonus_liability, 2_000);
+        assert_eq!(pool.pending_liability, 2_000);
+        assert_eq!(pool.total_donated, 8_000);
+    }
 }
@@ -0,0 +1,87 @@
+/// Stake label instruction handler.
+///
+/// Lets a stake owner tag their position (e.g. "ops reserve") so accounting
+/// can attribute positions staked from a shared wallet.
+///
+/// ## Security Guarantees
+/// - Only the position owner can change the label
+/// - The label is informational; nothing else reads it
+/// - No token movement here
+
+use anchor_lang::prelude::*;
+
+use crate::constants::*;
+use crate::error::StakingError;
+use crate::events::StakeLabelChanged;
+use crate::instructions::recovery::cancel_on_owner_signature;
+use crate::state::{StakePool, UserStake};
+
+/// Accounts required for relabelling a position.
+///
+/// ## Security Notes
+/// - User must be signer AND match user_stake.owner
+#[derive(Accounts)]
+pub struct SetStakeLabel<'info> {
+    /// The position owner.
+    /// SECURITY: Must be signer and match stake owner.
+    pub user: Signer<'info>,
+
+    /// The stake pool; writable for `sequence`.
+    /// SECURITY: PDA validation.
+    #[account(
+        mut,
+        seeds = [STAKE_POOL_SEED, stake_pool.staking_mint.as_ref()],
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ StakingError::AccountVersionMismatch,
+        bump = stake_pool.bump
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+
+    /// User's stake account.
+    /// SECURITY: PDA + owner + pool validation.
+    #[account(
+        mut,
+        seeds = [USER_STAKE_SEED, stake_pool.key().as_ref(), user.key().as_ref()],
+        bump = user_stake.bump,
+        constraint = user_stake.version == USER_STAKE_VERSION @ StakingError::AccountVersionMismatch,
+        constraint = user_stake.owner == user.key() @ StakingError::InvalidStakeOwner,
+        constraint = user_stake.stake_pool == stake_pool.key() @ StakingError::StakePoolMismatch
+    )]
+    pub user_stake: Account<'info, UserStake>,
+}
+
+/// Replace the label on the signer's position.
+///
+/// # Security
+/// - The label must be UTF-8 text, zero-padded on the right
+/// - All zeros clears it
+///
+/// # Arguments
+/// * `ctx` - SetStakeLabel accounts context
+/// * `label` - New label
+///
+/// # Returns
+/// Result indicating success or error
+pub fn handler(ctx: Context<SetStakeLabel>, label: [u8; 32]) -> Result<()> {
+    UserStake::validate_label(&label)?;
+
+    let user_stake = &mut ctx.accounts.user_stake;
+    let clock = Clock::get()?;
+
+    let old_label = user_stake.label;
+    user_stake.label = label;
+    ctx.accounts.stake_pool.next_sequence()?;
+    cancel_on_owner_signature(&ctx.accounts.stake_pool, user_stake, clock.unix_timestamp);
+
+    emit!(StakeLabelChanged {
+        stake_pool: ctx.accounts.stake_pool.key(),
+        owner: user_stake.owner,
+        old_label,
+        label,
+        sequence: ctx.accounts.stake_pool.sequence,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!("Stake label updated");
+
+    Ok(())
+}
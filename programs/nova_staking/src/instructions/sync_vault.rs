@@ -0,0 +1,150 @@
+/// Sync vault instruction handler.
+///
+/// Tokens transferred straight into the staking vault are not anyone's
+/// principal, so `total_staked` never sees them. This instruction measures
+/// that surplus and, when the authority asks, moves it into the treasury.
+///
+/// ## Security Guarantees
+/// - Permissionless to measure; only the authority can sweep
+/// - Never changes `total_staked`; the vault always keeps
+///   `StakePool::staking_vault_principal`
+/// - Swept tokens count as treasury funding, keeping `total_funded` in step
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::set_return_data;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+
+use crate::constants::*;
+use crate::error::StakingError;
+use crate::events::VaultSynced;
+use crate::state::StakePool;
+
+/// Accounts required for syncing the staking vault.
+///
+/// ## Security Notes
+/// - `caller` must be the pool authority to sweep
+/// - Vaults must match the pool's stored vaults
+#[derive(Accounts)]
+pub struct SyncVault<'info> {
+    /// Anyone; must be pool.authority when sweeping.
+    pub caller: Signer<'info>,
+
+    /// The stake pool.
+    /// SECURITY: PDA + has_one validation.
+    #[account(
+        mut,
+        seeds = [STAKE_POOL_SEED, stake_pool.staking_mint.as_ref()],
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ StakingError::AccountVersionMismatch,
+        bump = stake_pool.bump,
+        has_one = staking_vault @ StakingError::VaultMismatch
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+
+    /// Pool's staking vault.
+    #[account(mut)]
+    pub staking_vault: Account<'info, TokenAccount>,
+
+    /// Pool treasury; required when sweeping.
+    /// SECURITY: Must match pool's stored treasury.
+    #[account(
+        mut,
+        constraint = treasury_vault.key() == stake_pool.treasury_vault @ StakingError::TreasuryMismatch
+    )]
+    pub treasury_vault: Option<Box<Account<'info, TokenAccount>>>,
+
+    /// Token program.
+    pub token_program: Program<'info, Token>,
+}
+
+/// Record the staking vault's surplus over principal, optionally sweeping it.
+///
+/// # Security
+/// - Surplus is the vault balance above `total_staked` plus queued
+///   withdrawals, so unpaid unstake requests are never swept
+/// - Sweeping needs the authority's signature and a single-token pool, since
+///   the treasury of a dual-token pool holds the reward mint
+///
+/// # Arguments
+/// * `ctx` - SyncVault accounts context
+/// * `sweep` - Move the surplus into the treasury
+///
+/// # Returns
+/// Result indicating success or error; the remaining `unaccounted_principal`
+/// is written to return data
+pub fn handler(ctx: Context<SyncVault>, sweep: bool) -> Result<()> {
+    let clock = Clock::get()?;
+    let stake_pool = &ctx.accounts.stake_pool;
+    let surplus = ctx
+        .accounts
+        .staking_vault
+        .amount
+        .saturating_sub(stake_pool.staking_vault_principal());
+
+    // === SWEEP ===
+
+    let mut swept = 0;
+    if sweep {
+        require_keys_eq!(
+            ctx.accounts.caller.key(),
+            stake_pool.authority,
+            StakingError::Unauthorized
+        );
+        require!(!stake_pool.is_dual_token(), StakingError::SingleTokenPoolOnly);
+        let treasury_vault = ctx
+            .accounts
+            .treasury_vault
+            .as_ref()
+            .ok_or(StakingError::MissingTreasuryVault)?;
+
+        if surplus > 0 {
+            let staking_mint_key = stake_pool.staking_mint;
+            let seeds = &[STAKE_POOL_SEED, staking_mint_key.as_ref(), &[stake_pool.bump]];
+            let signer_seeds = &[&seeds[..]];
+
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.staking_vault.to_account_info(),
+                to: treasury_vault.to_account_info(),
+                authority: ctx.accounts.stake_pool.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+            token::transfer(cpi_ctx, surplus)?;
+            swept = surplus;
+        }
+    }
+
+    // === STATE UPDATE ===
+
+    ctx.accounts.staking_vault.reload()?;
+    let vault_balance = ctx.accounts.staking_vault.amount;
+
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    stake_pool.unaccounted_principal = surplus - swept;
+    stake_pool.total_funded = stake_pool
+        .total_funded
+        .checked_add(swept)
+        .ok_or(StakingError::MathOverflow)?;
+    stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+
+    emit!(VaultSynced {
+        stake_pool: stake_pool.key(),
+        vault_balance,
+        total_staked: stake_pool.total_staked,
+        unaccounted_principal: stake_pool.unaccounted_principal,
+        swept,
+        sequence: stake_pool.sequence,
+        timestamp: clock.unix_timestamp,
+    });
+
+    set_return_data(&stake_pool.unaccounted_principal.to_le_bytes());
+
+    msg!("Vault balance: {}, total staked: {}", vault_balance, stake_pool.total_staked);
+    msg!(
+        "Unaccounted principal: {}, swept: {}",
+        stake_pool.unaccounted_principal,
+        swept
+    );
+
+    Ok(())
+}
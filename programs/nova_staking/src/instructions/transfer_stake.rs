@@ -0,0 +1,187 @@
+/// Transfer stake ownership instruction handler.
+///
+/// Moves an open position to a new wallet, e.g. when rotating keys, without
+/// waiting out its lock.
+///
+/// ## Security Guarantees
+/// - Both the current and the new owner must sign
+/// - The position moves whole: principal, tier, lock, accrual state and
+///   unclaimed rewards carry over unchanged
+/// - Pool totals and staker counts are untouched; no tokens move
+/// - The new owner cannot already hold an open position in the pool
+
+use anchor_lang::prelude::*;
+
+use crate::constants::*;
+use crate::error::StakingError;
+use crate::events::{RecoveryCancelled, StakeOwnershipTransferred};
+use crate::state::{StakePool, UserStake};
+
+/// Accounts required for transferring a position.
+///
+/// ## Security Notes
+/// - `owner` must be signer AND match user_stake.owner
+/// - `new_owner` must be signer; it pays for the new position account
+/// - The old position is closed to `owner`
+#[derive(Accounts)]
+pub struct TransferStakeOwnership<'info> {
+    /// The current owner; receives the old account's rent.
+    /// SECURITY: Must be signer and match stake owner.
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// The wallet taking over the position.
+    /// SECURITY: Must be signer, so a position cannot be pushed onto a key
+    /// that did not agree to it.
+    #[account(
+        mut,
+        constraint = new_owner.key() != owner.key() @ StakingError::InvalidNewOwner
+    )]
+    pub new_owner: Signer<'info>,
+
+    /// The stake pool; writable for `sequence`.
+    /// SECURITY: PDA validation.
+    #[account(
+        mut,
+        seeds = [STAKE_POOL_SEED, stake_pool.staking_mint.as_ref()],
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ StakingError::AccountVersionMismatch,
+        bump = stake_pool.bump
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+
+    /// Position being transferred, closed to the current owner.
+    /// SECURITY: PDA + owner + pool validation.
+    #[account(
+        mut,
+        close = owner,
+        seeds = [USER_STAKE_SEED, stake_pool.key().as_ref(), owner.key().as_ref()],
+        bump = user_stake.bump,
+        constraint = user_stake.version == USER_STAKE_VERSION @ StakingError::AccountVersionMismatch,
+        constraint = user_stake.owner == owner.key() @ StakingError::InvalidStakeOwner,
+        constraint = user_stake.stake_pool == stake_pool.key() @ StakingError::StakePoolMismatch
+    )]
+    pub user_stake: Account<'info, UserStake>,
+
+    /// The new owner's position, created if it does not exist yet.
+    /// SECURITY: PDA derived from pool + new owner.
+    #[account(
+        init_if_needed,
+        payer = new_owner,
+        space = UserStake::LEN,
+        seeds = [USER_STAKE_SEED, stake_pool.key().as_ref(), new_owner.key().as_ref()],
+        bump
+    )]
+    pub new_user_stake: Account<'info, UserStake>,
+
+    /// System program.
+    pub system_program: Program<'info, System>,
+}
+
+/// Transfer the signer's position to `new_owner`.
+///
+/// # Security
+/// - Requires an open position (`NoActiveStake`)
+/// - A new owner with an open position in another tier fails with
+///   `CannotChangeTier`; in the same tier with `PositionNotEmpty`, since
+///   merging would change `staker_count`. A closed-out account may be reused
+///   once its rewards are claimed (`RewardsOutstanding`)
+/// - The claim delegate, reward destination and recovery address were chosen
+///   by the old owner and are cleared, cancelling any pending recovery; the
+///   label and referrer carry over
+/// - Claim and position histories stay with the old owner's key
+/// - In receipt mode the receipts do not move; the new owner needs them to
+///   unstake
+///
+/// # Arguments
+/// * `ctx` - TransferStakeOwnership accounts context
+///
+/// # Returns
+/// Result indicating success or error
+pub fn handler(ctx: Context<TransferStakeOwnership>) -> Result<()> {
+    let clock = Clock::get()?;
+    let new_owner = ctx.accounts.new_owner.key();
+    let old_owner = ctx.accounts.owner.key();
+
+    let source = &ctx.accounts.user_stake;
+    require!(source.is_active, StakingError::NoActiveStake);
+    let pending_recovery = source.has_pending_recovery().then_some(source.recovery_address);
+
+    move_position(
+        source,
+        &mut ctx.accounts.new_user_stake,
+        new_owner,
+        ctx.bumps.new_user_stake,
+        clock.unix_timestamp,
+    )?;
+
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    stake_pool.next_sequence()?;
+
+    if let Some(recovery_address) = pending_recovery {
+        emit!(RecoveryCancelled {
+            stake_pool: stake_pool.key(),
+            owner: old_owner,
+            recovery_address,
+            sequence: stake_pool.sequence,
+            timestamp: clock.unix_timestamp,
+        });
+    }
+
+    let position = &ctx.accounts.new_user_stake;
+    emit!(StakeOwnershipTransferred {
+        stake_pool: stake_pool.key(),
+        old_owner,
+        new_owner,
+        tier: position.tier as u8,
+        staked_amount: position.staked_amount,
+        pending_rewards: position.pending_rewards,
+        sequence: stake_pool.sequence,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!("Position of {} transferred to {}", old_owner, new_owner);
+    msg!("Staked: {}, pending rewards: {}", position.staked_amount, position.pending_rewards);
+
+    Ok(())
+}
+
+/// Copy `source` into `destination`, the position PDA of `new_owner`, after
+/// checking the new owner has no open position of their own. Settings chosen
+/// by the old owner (claim delegate, reward destination, recovery) are
+/// cleared, and the move counts as a deposit at `now`, so a position already
+/// recorded against the current snapshot cannot be recorded again under the
+/// new owner. Shared by `transfer_stake_ownership` and `complete_recovery`.
+pub fn move_position(
+    source: &UserStake,
+    destination: &mut Account<UserStake>,
+    new_owner: Pubkey,
+    bump: u8,
+    now: i64,
+) -> Result<()> {
+    // A zeroed owner means the account was just created by init_if_needed
+    if destination.owner != Pubkey::default() {
+        require!(
+            !destination.is_active || destination.tier == source.tier,
+            StakingError::CannotChangeTier
+        );
+        require!(
+            !destination.is_active
+                && destination.staked_amount == 0
+                && !destination.has_pending_unstake(),
+            StakingError::PositionNotEmpty
+        );
+        require!(!destination.holds_rewards(), StakingError::RewardsOutstanding);
+    }
+
+    let mut position = source.clone();
+    position.owner = new_owner;
+    position.bump = bump;
+    position.claim_delegate = Pubkey::default();
+    position.reward_destination = Pubkey::default();
+    position.recovery_address = Pubkey::default();
+    position.recovery_delay_seconds = 0;
+    position.recovery_started_at = 0;
+    position.last_stake_time = now;
+    destination.set_inner(position);
+    Ok(())
+}
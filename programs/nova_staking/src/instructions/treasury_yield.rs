@@ -0,0 +1,256 @@
+/// Treasury yield instruction handlers.
+///
+/// Lets the authority deploy part of an idle treasury into a whitelisted
+/// external yield program and recall it when claims need liquidity.
+///
+/// ## Adapter Interface
+/// Adapters expose `deposit(amount)` and `withdraw(amount)` (Anchor
+/// discriminators). Deploying transfers the tokens into `adapter_vault`, a
+/// token account the adapter controls, then calls `deposit` with
+/// `[adapter_vault, ...]` so the adapter can check and record it. Recalling
+/// calls `withdraw` with `[adapter_vault, treasury_vault, ...]`; the adapter
+/// signs for its own vault. The adapter's other accounts follow from
+/// `remaining_accounts`.
+///
+/// ## Security Guarantees
+/// - Only pool.authority can deploy or recall
+/// - Adapter program must be on the pool's whitelist
+/// - The pool PDA never signs an adapter call, so adapters cannot move
+///   principal or treasury funds; deployed tokens leave the treasury only
+///   through our own transfer
+/// - Total deployed is capped at `max_treasury_deploy_bps` of the treasury
+/// - Treasury balance deltas are checked after every adapter call
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hash;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+
+use crate::constants::*;
+use crate::error::StakingError;
+use crate::state::StakePool;
+
+/// Accounts required for moving treasury funds to or from a yield adapter.
+///
+/// ## Security Notes
+/// - Authority must be signer AND match pool.authority
+/// - Treasury must be the pool's treasury vault
+/// - Adapter must be whitelisted on the pool
+/// - The adapter vault holds the treasury mint and is not owned by the pool
+#[derive(Accounts)]
+pub struct TreasuryYield<'info> {
+    /// The admin authority.
+    /// SECURITY: Must be signer AND match pool.authority.
+    #[account(
+        mut,
+        constraint = authority.key() == stake_pool.authority @ StakingError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    /// The stake pool.
+    /// SECURITY: PDA validation + has_one authority and treasury.
+    #[account(
+        mut,
+        seeds = [STAKE_POOL_SEED, stake_pool.staking_mint.as_ref()],
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ StakingError::AccountVersionMismatch,
+        bump = stake_pool.bump,
+        has_one = authority @ StakingError::Unauthorized,
+        has_one = treasury_vault @ StakingError::TreasuryMismatch
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+
+    /// Pool treasury vault.
+    /// SECURITY: Must be owned by the pool.
+    #[account(
+        mut,
+        constraint = treasury_vault.owner == stake_pool.key() @ StakingError::InvalidTreasuryOwner
+    )]
+    pub treasury_vault: Account<'info, TokenAccount>,
+
+    /// External yield program.
+    /// CHECK: Must be executable and on the pool's adapter whitelist.
+    #[account(
+        executable,
+        constraint = stake_pool.is_yield_adapter(&adapter_program.key())
+            @ StakingError::YieldAdapterNotWhitelisted
+    )]
+    pub adapter_program: UncheckedAccount<'info>,
+
+    /// Token account the adapter holds deployed funds in.
+    /// SECURITY: Treasury mint; never one of the pool's own vaults.
+    #[account(
+        mut,
+        constraint = adapter_vault.mint == treasury_vault.mint @ StakingError::MintMismatch,
+        constraint = adapter_vault.owner != stake_pool.key() @ StakingError::InvalidAdapterVault
+    )]
+    pub adapter_vault: Account<'info, TokenAccount>,
+
+    /// Token program.
+    pub token_program: Program<'info, Token>,
+}
+
+/// Deploy idle treasury funds into a whitelisted yield adapter.
+///
+/// # Security
+/// - Only pool.authority can call this
+/// - Funds may only sit in one adapter at a time
+/// - Total deployed capped at `max_treasury_deploy_bps` of liquid + deployed
+///
+/// # Arguments
+/// * `ctx` - TreasuryYield accounts context; adapter accounts in remaining_accounts
+/// * `amount` - Amount of treasury tokens to deploy
+///
+/// # Returns
+/// Result indicating success or error
+pub fn deploy_treasury_handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, TreasuryYield<'info>>,
+    amount: u64,
+) -> Result<()> {
+    require!(amount > 0, StakingError::ZeroAmount);
+
+    let stake_pool = &ctx.accounts.stake_pool;
+    let adapter = ctx.accounts.adapter_program.key();
+    require!(
+        stake_pool.treasury_deployed == 0 || stake_pool.deployed_adapter == adapter,
+        StakingError::TreasuryAlreadyDeployed
+    );
+
+    let liquid = ctx.accounts.treasury_vault.amount;
+    let new_deployed = stake_pool
+        .treasury_deployed
+        .checked_add(amount)
+        .ok_or(StakingError::MathOverflow)?;
+    require!(
+        amount <= liquid && new_deployed <= stake_pool.max_treasury_deployable(liquid),
+        StakingError::TreasuryDeployCapExceeded
+    );
+
+    // Our own transfer moves the funds; the adapter only gets to look
+    let staking_mint_key = stake_pool.staking_mint;
+    let seeds = &[STAKE_POOL_SEED, staking_mint_key.as_ref(), &[stake_pool.bump]];
+    let signer_seeds = &[&seeds[..]];
+    let cpi_accounts = Transfer {
+        from: ctx.accounts.treasury_vault.to_account_info(),
+        to: ctx.accounts.adapter_vault.to_account_info(),
+        authority: stake_pool.to_account_info(),
+    };
+    token::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        ),
+        amount,
+    )?;
+
+    invoke_adapter(&ctx, "deposit", amount, false)?;
+
+    ctx.accounts.treasury_vault.reload()?;
+    let moved = liquid.saturating_sub(ctx.accounts.treasury_vault.amount);
+    require!(moved == amount, StakingError::AdapterTransferMismatch);
+
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+    stake_pool.treasury_deployed = new_deployed;
+    stake_pool.deployed_adapter = adapter;
+    stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+
+    msg!("Deployed {} treasury tokens to {}", amount, adapter);
+    msg!("Total deployed: {}", stake_pool.treasury_deployed);
+    msg!("Admin: {}", ctx.accounts.authority.key());
+
+    Ok(())
+}
+
+/// Recall treasury funds (and any yield) from the deployed adapter.
+///
+/// # Security
+/// - Only pool.authority can call this
+/// - Adapter must be the one currently holding the funds
+/// - Anything returned above the deployed principal is treated as yield
+///
+/// # Arguments
+/// * `ctx` - TreasuryYield accounts context; adapter accounts in remaining_accounts
+/// * `amount` - Amount to withdraw from the adapter
+///
+/// # Returns
+/// Result indicating success or error
+pub fn recall_treasury_handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, TreasuryYield<'info>>,
+    amount: u64,
+) -> Result<()> {
+    require!(amount > 0, StakingError::ZeroAmount);
+    require!(
+        ctx.accounts.stake_pool.deployed_adapter == ctx.accounts.adapter_program.key(),
+        StakingError::InvalidYieldAdapter
+    );
+
+    let liquid_before = ctx.accounts.treasury_vault.amount;
+
+    invoke_adapter(&ctx, "withdraw", amount, true)?;
+
+    ctx.accounts.treasury_vault.reload()?;
+    let returned = ctx.accounts.treasury_vault.amount.saturating_sub(liquid_before);
+    require!(returned == amount, StakingError::AdapterTransferMismatch);
+
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+    let principal = returned.min(stake_pool.treasury_deployed);
+    stake_pool.treasury_deployed -= principal;
+    if stake_pool.treasury_deployed == 0 {
+        stake_pool.deployed_adapter = Pubkey::default();
+    }
+    stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+
+    // Recalled funds refill an empty treasury just like a deposit
+    stake_pool.close_empty_window(clock.unix_timestamp)?;
+
+    msg!("Recalled {} treasury tokens ({} yield)", returned, returned - principal);
+    msg!("Still deployed: {}", stake_pool.treasury_deployed);
+    msg!("Admin: {}", ctx.accounts.authority.key());
+
+    Ok(())
+}
+
+/// CPI into the adapter's `ix_name(amount)` with `[adapter_vault]`, plus
+/// `treasury_vault` as destination when `to_treasury`. Unsigned: the pool
+/// PDA is never lent to adapter code.
+fn invoke_adapter<'info>(
+    ctx: &Context<'_, '_, 'info, 'info, TreasuryYield<'info>>,
+    ix_name: &str,
+    amount: u64,
+    to_treasury: bool,
+) -> Result<()> {
+    let adapter_vault = &ctx.accounts.adapter_vault;
+    let treasury_vault = &ctx.accounts.treasury_vault;
+
+    let mut data = hash(format!("global:{}", ix_name).as_bytes()).to_bytes()[..8].to_vec();
+    data.extend_from_slice(&amount.to_le_bytes());
+
+    let mut metas = vec![AccountMeta::new(adapter_vault.key(), false)];
+    let mut infos = vec![adapter_vault.to_account_info()];
+    if to_treasury {
+        metas.push(AccountMeta::new(treasury_vault.key(), false));
+        infos.push(treasury_vault.to_account_info());
+    }
+    metas.extend(ctx.remaining_accounts.iter().map(|account| AccountMeta {
+        pubkey: account.key(),
+        is_signer: account.is_signer,
+        is_writable: account.is_writable,
+    }));
+    let instruction = Instruction {
+        program_id: ctx.accounts.adapter_program.key(),
+        accounts: metas,
+        data,
+    };
+
+    infos.extend(ctx.remaining_accounts.iter().cloned());
+    infos.push(ctx.accounts.adapter_program.to_account_info());
+
+    invoke(&instruction, &infos)?;
+
+    Ok(())
+}
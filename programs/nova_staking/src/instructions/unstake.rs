@@ -3,17 +3,37 @@
 /// Handles withdrawing staked tokens from the pool with security validations.
 ///
 /// ## Security Guarantees
-/// - Lock period enforcement for Core/Prime tiers
+/// - Lock period enforcement for Core/Prime tiers (cliff-plus-linear release)
+/// - Optional minimum stake duration for Flex
+/// - Per-tier unstake fee paid from the withdrawn principal into the treasury,
+///   or burned when the pool's penalty destination is Burn
+/// - `early_unstake` skips a running Core/Prime lock for a per-tier penalty,
+///   routed like the unstake fee
+/// - `emergency_withdraw` returns the whole principal in emergency mode,
+///   fee-free, and forfeits unclaimed rewards
+/// - While a Flex withdrawal cooldown is set, Flex principal leaves through
+///   `request_unstake` and, once the cooldown has passed, `withdraw`
 /// - Owner validation prevents unauthorized unstaking
 /// - Vault validation ensures tokens come from correct PDA
+/// - Destination is the owner's canonical ATA (created if missing) or, via
+///   `unstake_to`, an explicit token account owned by the user
+/// - In receipt mode every exit first burns one receipt token per token of
+///   principal released, from an account the owner signs for
 
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+use anchor_lang::solana_program::program::set_return_data;
+use anchor_lang::solana_program::sysvar::instructions as sysvar_instructions;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::{self, Burn, Mint, Token, TokenAccount, Transfer};
 
 use crate::constants::*;
 use crate::error::StakingError;
-use crate::instructions::stake::calculate_pending_rewards;
-use crate::state::{StakePool, UserStake};
+use crate::events::{EarlyUnstaked, EmergencyWithdrawn, PenaltyBurned, PermanentUnstaked, UnstakeEvent};
+use crate::instructions::receipt::burn_receipts;
+use crate::instructions::recovery::cancel_on_owner_signature;
+use crate::instructions::stake::{calculate_pending_rewards, update_rewards};
+use crate::state::{PenaltyDestination, PositionEventKind, PositionHistory, PositionRecord, StakePool, UserStake};
+use crate::tier::Tier;
 
 /// Accounts required for unstaking.
 ///
@@ -32,6 +52,7 @@ pub struct Unstake<'info> {
     #[account(
         mut,
         seeds = [STAKE_POOL_SEED, stake_pool.staking_mint.as_ref()],
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ StakingError::AccountVersionMismatch,
         bump = stake_pool.bump,
         has_one = staking_vault @ StakingError::VaultMismatch,
         has_one = staking_mint @ StakingError::MintMismatch
@@ -44,26 +65,144 @@ pub struct Unstake<'info> {
         mut,
         seeds = [USER_STAKE_SEED, stake_pool.key().as_ref(), user.key().as_ref()],
         bump = user_stake.bump,
+        constraint = user_stake.version == USER_STAKE_VERSION @ StakingError::AccountVersionMismatch,
         constraint = user_stake.owner == user.key() @ StakingError::InvalidStakeOwner,
         constraint = user_stake.stake_pool == stake_pool.key() @ StakingError::StakePoolMismatch
     )]
     pub user_stake: Account<'info, UserStake>,
 
-    /// The staking token mint.
+    /// The staking token mint; mutable so burned fees reduce supply.
     /// SECURITY: Must match pool's locked mint.
     #[account(
+        mut,
         constraint = staking_mint.key() == stake_pool.staking_mint @ StakingError::MintMismatch
     )]
     pub staking_mint: Account<'info, Mint>,
 
-    /// User's token account for receiving unstaked tokens.
+    /// User's associated token account for receiving unstaked tokens.
+    /// Created in the same transaction (rent paid by the user) if it was closed.
+    /// SECURITY: Canonical ATA for (user, staking_mint).
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = staking_mint,
+        associated_token::authority = user
+    )]
+    pub user_token_account: Account<'info, TokenAccount>,
+
+    /// Pool's staking vault.
+    /// SECURITY: Must match pool's stored vault + owner validation.
+    #[account(
+        mut,
+        constraint = staking_vault.key() == stake_pool.staking_vault @ StakingError::VaultMismatch,
+        constraint = staking_vault.owner == stake_pool.key() @ StakingError::InvalidVaultOwner,
+        constraint = staking_vault.mint == staking_mint.key() @ StakingError::InvalidTokenAccountMint
+    )]
+    pub staking_vault: Account<'info, TokenAccount>,
+
+    /// Token program.
+    pub token_program: Program<'info, Token>,
+
+    /// Associated token program, for creating the destination ATA.
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    /// System program, for creating the destination ATA.
+    pub system_program: Program<'info, System>,
+
+    /// Instructions sysvar, read to reject CPI calls unless the pool allows them.
+    /// CHECK: Address-checked against the sysvar id.
+    #[account(address = sysvar_instructions::ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
+
+    /// Optional position history; the change is recorded when provided.
+    /// SECURITY: PDA derived from the signer, so only the owner's own history.
+    #[account(
+        mut,
+        seeds = [POSITION_HISTORY_SEED, stake_pool.key().as_ref(), user.key().as_ref()],
+        bump = position_history.bump
+    )]
+    pub position_history: Option<Box<Account<'info, PositionHistory>>>,
+
+    /// Pool treasury; receives the tier's unstake fee and is required while one
+    /// is set (unless fees are burned).
+    /// SECURITY: Must match pool's stored treasury + owner validation.
+    #[account(
+        mut,
+        constraint = treasury_vault.key() == stake_pool.treasury_vault @ StakingError::TreasuryMismatch,
+        constraint = treasury_vault.owner == stake_pool.key() @ StakingError::InvalidTreasuryOwner
+    )]
+    pub treasury_vault: Option<Box<Account<'info, TokenAccount>>>,
+
+    /// Receipt mint; required in receipt mode.
+    /// SECURITY: Must match the pool's receipt mint.
+    #[account(
+        mut,
+        address = stake_pool.receipt_mint @ StakingError::MintMismatch
+    )]
+    pub receipt_mint: Option<Box<Account<'info, Mint>>>,
+
+    /// Receipts burned for the released principal; required in receipt mode.
+    /// SECURITY: Receipt mint and owner validation; the user signs the burn.
+    #[account(
+        mut,
+        constraint = receipt_account.mint == stake_pool.receipt_mint @ StakingError::MintMismatch,
+        constraint = receipt_account.owner == user.key() @ StakingError::UnauthorizedStakeAccess
+    )]
+    pub receipt_account: Option<Box<Account<'info, TokenAccount>>>,
+}
+
+/// Accounts required for unstaking into an explicit token account.
+///
+/// ## Security Notes
+/// - Same validations as `Unstake`, but the destination is any token account
+///   for the staking mint owned by the user (not necessarily the ATA)
+#[derive(Accounts)]
+pub struct UnstakeTo<'info> {
+    /// The user unstaking tokens.
+    /// SECURITY: Must be signer and match stake owner.
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// The stake pool.
+    /// SECURITY: PDA + has_one validations.
+    #[account(
+        mut,
+        seeds = [STAKE_POOL_SEED, stake_pool.staking_mint.as_ref()],
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ StakingError::AccountVersionMismatch,
+        bump = stake_pool.bump,
+        has_one = staking_vault @ StakingError::VaultMismatch,
+        has_one = staking_mint @ StakingError::MintMismatch
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+
+    /// User's stake account.
+    /// SECURITY: PDA + owner validation + pool validation.
+    #[account(
+        mut,
+        seeds = [USER_STAKE_SEED, stake_pool.key().as_ref(), user.key().as_ref()],
+        bump = user_stake.bump,
+        constraint = user_stake.version == USER_STAKE_VERSION @ StakingError::AccountVersionMismatch,
+        constraint = user_stake.owner == user.key() @ StakingError::InvalidStakeOwner,
+        constraint = user_stake.stake_pool == stake_pool.key() @ StakingError::StakePoolMismatch
+    )]
+    pub user_stake: Account<'info, UserStake>,
+
+    /// The staking token mint; mutable so burned fees reduce supply.
+    /// SECURITY: Must match pool's locked mint.
+    #[account(
+        mut,
+        constraint = staking_mint.key() == stake_pool.staking_mint @ StakingError::MintMismatch
+    )]
+    pub staking_mint: Account<'info, Mint>,
+
+    /// Token account receiving the unstaked tokens.
     /// SECURITY: Mint and owner validation.
     #[account(
         mut,
-        constraint = user_token_account.mint == staking_mint.key() @ StakingError::MintMismatch,
-        constraint = user_token_account.owner == user.key() @ StakingError::UnauthorizedStakeAccess
+        constraint = destination.mint == staking_mint.key() @ StakingError::MintMismatch,
+        constraint = destination.owner == user.key() @ StakingError::UnauthorizedStakeAccess
     )]
-    pub user_token_account: Account<'info, TokenAccount>,
+    pub destination: Account<'info, TokenAccount>,
 
     /// Pool's staking vault.
     /// SECURITY: Must match pool's stored vault + owner validation.
@@ -77,12 +216,54 @@ pub struct Unstake<'info> {
 
     /// Token program.
     pub token_program: Program<'info, Token>,
+
+    /// Instructions sysvar, read to reject CPI calls unless the pool allows them.
+    /// CHECK: Address-checked against the sysvar id.
+    #[account(address = sysvar_instructions::ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
+
+    /// Optional position history; the change is recorded when provided.
+    /// SECURITY: PDA derived from the signer, so only the owner's own history.
+    #[account(
+        mut,
+        seeds = [POSITION_HISTORY_SEED, stake_pool.key().as_ref(), user.key().as_ref()],
+        bump = position_history.bump
+    )]
+    pub position_history: Option<Box<Account<'info, PositionHistory>>>,
+
+    /// Pool treasury; receives the tier's unstake fee and is required while one
+    /// is set (unless fees are burned).
+    /// SECURITY: Must match pool's stored treasury + owner validation.
+    #[account(
+        mut,
+        constraint = treasury_vault.key() == stake_pool.treasury_vault @ StakingError::TreasuryMismatch,
+        constraint = treasury_vault.owner == stake_pool.key() @ StakingError::InvalidTreasuryOwner
+    )]
+    pub treasury_vault: Option<Box<Account<'info, TokenAccount>>>,
+
+    /// Receipt mint; required in receipt mode.
+    /// SECURITY: Must match the pool's receipt mint.
+    #[account(
+        mut,
+        address = stake_pool.receipt_mint @ StakingError::MintMismatch
+    )]
+    pub receipt_mint: Option<Box<Account<'info, Mint>>>,
+
+    /// Receipts burned for the released principal; required in receipt mode.
+    /// SECURITY: Receipt mint and owner validation; the user signs the burn.
+    #[account(
+        mut,
+        constraint = receipt_account.mint == stake_pool.receipt_mint @ StakingError::MintMismatch,
+        constraint = receipt_account.owner == user.key() @ StakingError::UnauthorizedStakeAccess
+    )]
+    pub receipt_account: Option<Box<Account<'info, TokenAccount>>>,
 }
 
 /// Unstake tokens from the pool.
 ///
 /// # Security
 /// - Enforces lock periods for Core (90 days) and Prime (180 days) tiers
+/// - Lock periods are skipped while the pool is in emergency mode
 /// - Validates signer is stake owner
 /// - Uses checked math for all calculations
 /// - PDA signer for vault transfer
@@ -92,25 +273,340 @@ pub struct Unstake<'info> {
 /// * `amount` - Amount of tokens to unstake
 ///
 /// # Returns
-/// Result indicating success or error
+/// Result indicating success or error; the net amount received is written to return data
 pub fn handler(ctx: Context<Unstake>, amount: u64) -> Result<()> {
+    ctx.accounts
+        .stake_pool
+        .require_top_level(&ctx.accounts.instructions_sysvar)?;
+
+    let accounts = ctx.accounts;
+    process_unstake(
+        &mut accounts.user_stake,
+        &mut accounts.stake_pool,
+        &accounts.staking_mint,
+        &accounts.staking_vault,
+        accounts.user_token_account.to_account_info(),
+        &accounts.token_program,
+        accounts.treasury_vault.as_deref(),
+        accounts.position_history.as_deref_mut(),
+        accounts.receipt_mint.as_deref(),
+        accounts.receipt_account.as_deref(),
+        accounts.user.to_account_info(),
+        amount,
+        UnstakeMode::Standard,
+    )
+}
+
+/// Unstake tokens from the pool into an explicit token account.
+///
+/// # Security
+/// - Same checks as `unstake`; destination must be owned by the signer
+///
+/// # Arguments
+/// * `ctx` - UnstakeTo accounts context
+/// * `amount` - Amount of tokens to unstake
+///
+/// # Returns
+/// Result indicating success or error; the net amount received is written to return data
+pub fn unstake_to_handler(ctx: Context<UnstakeTo>, amount: u64) -> Result<()> {
+    ctx.accounts
+        .stake_pool
+        .require_top_level(&ctx.accounts.instructions_sysvar)?;
+
+    let accounts = ctx.accounts;
+    process_unstake(
+        &mut accounts.user_stake,
+        &mut accounts.stake_pool,
+        &accounts.staking_mint,
+        &accounts.staking_vault,
+        accounts.destination.to_account_info(),
+        &accounts.token_program,
+        accounts.treasury_vault.as_deref(),
+        accounts.position_history.as_deref_mut(),
+        accounts.receipt_mint.as_deref(),
+        accounts.receipt_account.as_deref(),
+        accounts.user.to_account_info(),
+        amount,
+        UnstakeMode::Standard,
+    )
+}
+
+/// Unstake from a running Core or Prime lock by paying the tier's early-exit penalty.
+///
+/// # Security
+/// - Only while the lock is running; afterwards `unstake` is penalty-free
+/// - Requires a non-zero penalty for the tier, so early exits are opt-in per pool
+/// - Penalty rounds up and goes to the treasury, or is burned when the
+///   pool's penalty destination is Burn
+/// - Pending rewards are settled into the position and stay claimable
+///
+/// # Arguments
+/// * `ctx` - Unstake accounts context
+/// * `amount` - Amount of tokens to unstake
+///
+/// # Returns
+/// Result indicating success or error; the net amount received is written to return data
+pub fn early_unstake_handler(ctx: Context<Unstake>, amount: u64) -> Result<()> {
+    ctx.accounts
+        .stake_pool
+        .require_top_level(&ctx.accounts.instructions_sysvar)?;
+
+    let accounts = ctx.accounts;
+    process_unstake(
+        &mut accounts.user_stake,
+        &mut accounts.stake_pool,
+        &accounts.staking_mint,
+        &accounts.staking_vault,
+        accounts.user_token_account.to_account_info(),
+        &accounts.token_program,
+        accounts.treasury_vault.as_deref(),
+        accounts.position_history.as_deref_mut(),
+        accounts.receipt_mint.as_deref(),
+        accounts.receipt_account.as_deref(),
+        accounts.user.to_account_info(),
+        amount,
+        UnstakeMode::Early,
+    )
+}
+
+/// Withdraw the whole position while the pool is in emergency mode.
+///
+/// # Security
+/// - Only while `emergency` is set; locks and the Flex cooldown are ignored
+/// - The permanent tier still waits for the sunset, as with `unstake`
+/// - No unstake fee; unclaimed rewards, boost-locked rewards and credited
+///   bonuses are forfeited, so nothing is owed from a possibly compromised treasury
+/// - In receipt mode the full amount of receipts is still burned
+///
+/// # Arguments
+/// * `ctx` - Unstake accounts context
+///
+/// # Returns
+/// Result indicating success or error; the withdrawn amount is written to return data
+pub fn emergency_withdraw_handler(ctx: Context<Unstake>) -> Result<()> {
+    ctx.accounts
+        .stake_pool
+        .require_top_level(&ctx.accounts.instructions_sysvar)?;
+
     let user_stake = &ctx.accounts.user_stake;
     let stake_pool = &ctx.accounts.stake_pool;
     let clock = Clock::get()?;
 
+    require!(stake_pool.emergency, StakingError::EmergencyModeInactive);
+    require!(
+        user_stake.is_active
+            && (user_stake.staked_amount > 0 || user_stake.has_pending_unstake()),
+        StakingError::NoActiveStake
+    );
+    if user_stake.tier == Tier::Permanent {
+        require!(
+            stake_pool.is_sunset_reached(clock.unix_timestamp),
+            StakingError::SunsetNotReached
+        );
+    }
+
+    // Queued Flex principal is paid out too; it already left total_staked
+    let staked = user_stake.staked_amount;
+    let amount = staked
+        .checked_add(user_stake.unstake_requested_amount)
+        .ok_or(StakingError::MathOverflow)?;
+    // Forfeited rewards are only reported, so a reward math error must never
+    // block the principal exit
+    let forfeited = user_stake
+        .pending_rewards
+        .saturating_add(
+            calculate_pending_rewards(user_stake, stake_pool, clock.unix_timestamp).unwrap_or(0),
+        )
+        .saturating_add(user_stake.boosted_amount);
+
+    // Receipts are burned even here; the principal backing them is leaving
+    burn_receipts(
+        stake_pool,
+        ctx.accounts.receipt_mint.as_deref(),
+        ctx.accounts.receipt_account.as_deref(),
+        ctx.accounts.user.to_account_info(),
+        &ctx.accounts.token_program,
+        amount,
+    )?;
+
+    // === PDA SIGNER TRANSFER ===
+
+    let staking_mint_key = stake_pool.staking_mint;
+    let seeds = &[
+        STAKE_POOL_SEED,
+        staking_mint_key.as_ref(),
+        &[stake_pool.bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+
+    let cpi_accounts = Transfer {
+        from: ctx.accounts.staking_vault.to_account_info(),
+        to: ctx.accounts.user_token_account.to_account_info(),
+        authority: ctx.accounts.stake_pool.to_account_info(),
+    };
+    let cpi_program = ctx.accounts.token_program.to_account_info();
+    let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+    token::transfer(cpi_ctx, amount)?;
+
+    // === STATE UPDATE ===
+
+    let user_stake = &mut ctx.accounts.user_stake;
+    let stake_pool = &mut ctx.accounts.stake_pool;
+
+    // Settle the reward-per-token accumulator before balances change
+    update_rewards(stake_pool, user_stake, clock.unix_timestamp)?;
+
+    stake_pool.bonus_liability = stake_pool
+        .bonus_liability
+        .saturating_sub(user_stake.bonus_rewards);
+    user_stake.bonus_rewards = 0;
+    stake_pool.accrued_liability = stake_pool
+        .accrued_liability
+        .saturating_sub(user_stake.over_cap_rewards);
+    user_stake.over_cap_rewards = 0;
+    stake_pool.pending_liability = stake_pool
+        .pending_liability
+        .saturating_sub(user_stake.pending_rewards)
+        .saturating_sub(user_stake.boosted_amount);
+    user_stake.pending_rewards = 0;
+    user_stake.boosted_amount = 0;
+    user_stake.boost_until = 0;
+    user_stake.mark_settled(stake_pool, clock.unix_timestamp);
+    user_stake.staked_amount = 0;
+    user_stake.bonus_debt = 0;
+    user_stake.unlocked_withdrawn = 0;
+    user_stake.unstake_requested_amount = 0;
+    user_stake.unstake_requested_at = 0;
+    user_stake.auto_renew = false;
+    user_stake.is_active = false;
+
+    stake_pool.remove_staker(user_stake.tier)?;
+    stake_pool.remove_staked(user_stake.tier, staked)?;
+    stake_pool.total_unstake_requested = stake_pool
+        .total_unstake_requested
+        .checked_sub(amount - staked)
+        .ok_or(StakingError::MathUnderflow)?;
+    stake_pool.record_exit(amount)?;
+    stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+    cancel_on_owner_signature(stake_pool, user_stake, clock.unix_timestamp);
+
+    if let Some(position_history) = ctx.accounts.position_history.as_mut() {
+        position_history.push(PositionRecord {
+            timestamp: clock.unix_timestamp,
+            kind: PositionEventKind::Unstake,
+            tier: user_stake.tier as u8,
+            amount,
+            staked_after: 0,
+        });
+    }
+
+    emit!(EmergencyWithdrawn {
+        stake_pool: stake_pool.key(),
+        owner: user_stake.owner,
+        amount,
+        forfeited_rewards: forfeited,
+        cumulative_unstaked: stake_pool.cumulative_unstaked,
+        sequence: stake_pool.sequence,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!("Emergency withdrew {} tokens", amount);
+    msg!("Forfeited rewards: {}", forfeited);
+
+    set_return_data(&amount.to_le_bytes());
+
+    Ok(())
+}
+
+/// Withdraw Flex principal queued by `request_unstake` once the cooldown has passed.
+///
+/// # Security
+/// - Requires a pending request; the whole requested amount is paid out
+/// - The cooldown is skipped while the pool is in emergency mode
+/// - The Flex unstake fee applies as with `unstake`
+/// - Rewards on the remaining stake are settled into the position
+///
+/// # Arguments
+/// * `ctx` - Unstake accounts context
+///
+/// # Returns
+/// Result indicating success or error; the net amount received is written to return data
+pub fn withdraw_handler(ctx: Context<Unstake>) -> Result<()> {
+    ctx.accounts
+        .stake_pool
+        .require_top_level(&ctx.accounts.instructions_sysvar)?;
+
+    require!(
+        ctx.accounts.user_stake.has_pending_unstake(),
+        StakingError::NoUnstakeRequest
+    );
+
+    let accounts = ctx.accounts;
+    let amount = accounts.user_stake.unstake_requested_amount;
+    process_unstake(
+        &mut accounts.user_stake,
+        &mut accounts.stake_pool,
+        &accounts.staking_mint,
+        &accounts.staking_vault,
+        accounts.user_token_account.to_account_info(),
+        &accounts.token_program,
+        accounts.treasury_vault.as_deref(),
+        accounts.position_history.as_deref_mut(),
+        accounts.receipt_mint.as_deref(),
+        accounts.receipt_account.as_deref(),
+        accounts.user.to_account_info(),
+        amount,
+        UnstakeMode::Queued,
+    )
+}
+
+/// Which exit path `process_unstake` is serving.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum UnstakeMode {
+    /// `unstake` / `unstake_to`: subject to locks and the Flex minimum stake
+    Standard,
+    /// `early_unstake`: skips a running lock for a penalty
+    Early,
+    /// `withdraw`: pays out the Flex withdrawal queue
+    Queued,
+}
+
+#[allow(clippy::too_many_arguments)]
+fn process_unstake<'info>(
+    user_stake: &mut Account<'info, UserStake>,
+    stake_pool: &mut Account<'info, StakePool>,
+    staking_mint: &Account<'info, Mint>,
+    staking_vault: &Account<'info, TokenAccount>,
+    destination: AccountInfo<'info>,
+    token_program: &Program<'info, Token>,
+    treasury_vault: Option<&Account<'info, TokenAccount>>,
+    position_history: Option<&mut Account<'info, PositionHistory>>,
+    receipt_mint: Option<&Account<'info, Mint>>,
+    receipt_account: Option<&Account<'info, TokenAccount>>,
+    receipt_holder: AccountInfo<'info>,
+    amount: u64,
+    mode: UnstakeMode,
+) -> Result<()> {
+    let clock = Clock::get()?;
+
     // === INPUT VALIDATION ===
     
+    require!(!stake_pool.is_unstake_paused(), StakingError::UnstakingPaused);
+
     // Validate amount is non-zero
     require!(amount > 0, StakingError::ZeroAmount);
 
     // Validate user has active stake
     require!(user_stake.is_active, StakingError::NoActiveStake);
 
-    // Validate sufficient balance
-    require!(
-        user_stake.staked_amount >= amount,
-        StakingError::InsufficientStakedBalance
-    );
+    // Validate sufficient balance; queued withdrawals draw on the request
+    let available = if mode == UnstakeMode::Queued {
+        user_stake.unstake_requested_amount
+    } else {
+        user_stake.staked_amount
+    };
+    require!(available >= amount, StakingError::InsufficientStakedBalance);
 
     // === LOCK PERIOD ENFORCEMENT ===
     
@@ -119,15 +615,126 @@ pub fn handler(ctx: Context<Unstake>, amount: u64) -> Result<()> {
     
     // Check lock period for Core and Prime tiers
     // Flex tier (tier 0) has no lock period
-    require!(
-        user_stake.is_lock_ended(clock.unix_timestamp, 0),
-        StakingError::LockPeriodNotEnded
+    // Locked tiers release principal on their cliff-plus-linear schedule
+    // Emergency mode releases principal regardless of locks, except the
+    // permanent tier, which only opens at the declared sunset
+    // Auto-renewed locks are measured from the cycle running now
+    if mode != UnstakeMode::Queued {
+        user_stake.roll_auto_renew(clock.unix_timestamp);
+    }
+
+    // No tier may leave right after a deposit; queued principal was checked
+    // at request time
+    if mode != UnstakeMode::Queued && !stake_pool.emergency {
+        let remaining = stake_pool
+            .min_stake_duration_remaining(user_stake.last_stake_time, clock.unix_timestamp);
+        if remaining > 0 {
+            set_return_data(&remaining.to_le_bytes());
+            msg!("Minimum stake duration: {}s remaining", remaining);
+            return err!(StakingError::StakeTooRecent);
+        }
+    }
+    let is_permanent = user_stake.tier == Tier::Permanent;
+    let lock_ended = user_stake.is_lock_ended(
+        clock.unix_timestamp,
+        user_stake.get_lock_period(),
+        stake_pool.unlock_grace_secs,
     );
+    if mode == UnstakeMode::Queued {
+        // Queued principal already left the position; only the cooldown remains
+        let remaining = stake_pool
+            .withdrawal_cooldown_remaining(user_stake.unstake_requested_at, clock.unix_timestamp);
+        if remaining > 0 && !stake_pool.emergency {
+            set_return_data(&remaining.to_le_bytes());
+            msg!("Withdrawal cooldown: {}s remaining", remaining);
+            return err!(StakingError::WithdrawalCooldownActive);
+        }
+    } else if mode == UnstakeMode::Early {
+        // Early exits trade the schedule for a penalty, so they need a running lock
+        require!(
+            stake_pool.early_unstake_penalty_for_tier(user_stake.tier) > 0,
+            StakingError::EarlyUnstakeDisabled
+        );
+        require!(!lock_ended, StakingError::LockAlreadyEnded);
+    } else if is_permanent {
+        require!(
+            stake_pool.is_sunset_reached(clock.unix_timestamp),
+            StakingError::SunsetNotReached
+        );
+    } else if !stake_pool.emergency && user_stake.tier == Tier::Flex {
+        // With a withdrawal cooldown set, Flex exits go through the queue
+        require!(
+            stake_pool.flex_cooldown_seconds == 0,
+            StakingError::UnstakeRequestRequired
+        );
+
+        // A Flex lock only exists when the pool configured one
+        require!(lock_ended, StakingError::LockPeriodNotEnded);
+
+        // Otherwise Flex only has a minimum stake duration
+        let remaining =
+            stake_pool.flex_cooldown_remaining(user_stake.stake_start_time, clock.unix_timestamp);
+        if remaining > 0 {
+            set_return_data(&remaining.to_le_bytes());
+            msg!("Flex cooldown: {}s remaining", remaining);
+            return err!(StakingError::FlexCooldownActive);
+        }
+    } else if !stake_pool.emergency {
+        // An auto-renewing lock never ends; it must be turned off first
+        if mode == UnstakeMode::Standard && user_stake.auto_renew {
+            msg!("Auto-renew is on; turn it off to unlock at {}", user_stake.lock_end_time());
+            return err!(StakingError::LockPeriodNotEnded);
+        }
+
+        // Inside the grace window the whole position is released early
+        let (cliff_secs, linear_secs) =
+            stake_pool.release_schedule_for_lock(user_stake.tier, user_stake.get_lock_period());
+        let withdrawable = if lock_ended {
+            user_stake.staked_amount
+        } else {
+            user_stake.withdrawable_principal(clock.unix_timestamp, cliff_secs, linear_secs)
+        };
+        require!(amount <= withdrawable, StakingError::LockPeriodNotEnded);
+    }
+
+    // === UNSTAKE FEE ===
+
+    // The fee comes out of the withdrawn principal; the position drops by the full amount
+    let (net_amount, tier_fee) = stake_pool.split_unstake_fee(user_stake.tier, amount)?;
+
+    // Early exits also pay the penalty on the full amount, routed with the fee
+    let early_penalty = if mode == UnstakeMode::Early {
+        stake_pool.split_early_unstake_penalty(user_stake.tier, amount)?.1
+    } else {
+        0
+    };
+    let net_amount = net_amount
+        .checked_sub(early_penalty)
+        .ok_or(StakingError::MathUnderflow)?;
+    let unstake_fee = tier_fee
+        .checked_add(early_penalty)
+        .ok_or(StakingError::MathOverflow)?;
+    let burn_fee = stake_pool.penalty_destination == PenaltyDestination::Burn;
+    if unstake_fee > 0 && !burn_fee {
+        require!(treasury_vault.is_some(), StakingError::MissingTreasuryVault);
+    }
 
     // === CALCULATE PENDING REWARDS ===
     
     let pending = calculate_pending_rewards(user_stake, stake_pool, clock.unix_timestamp)?;
 
+    // === RECEIPT BURN ===
+
+    // The whole amount leaves the position, fee included
+    burn_receipts(
+        stake_pool,
+        receipt_mint,
+        receipt_account,
+        receipt_holder,
+        token_program,
+        amount,
+    )?;
+
     // === PDA SIGNER TRANSFER ===
     
     // Create PDA signer seeds for vault transfer
@@ -141,50 +748,166 @@ pub fn handler(ctx: Context<Unstake>, amount: u64) -> Result<()> {
 
     // Transfer tokens from vault to user
     let cpi_accounts = Transfer {
-        from: ctx.accounts.staking_vault.to_account_info(),
-        to: ctx.accounts.user_token_account.to_account_info(),
-        authority: ctx.accounts.stake_pool.to_account_info(),
+        from: staking_vault.to_account_info(),
+        to: destination,
+        authority: stake_pool.to_account_info(),
     };
-    let cpi_program = ctx.accounts.token_program.to_account_info();
+    let cpi_program = token_program.to_account_info();
     let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
-    token::transfer(cpi_ctx, amount)?;
+    token::transfer(cpi_ctx, net_amount)?;
+
+    // Burn the unstake fee from the vault, or transfer it to the treasury
+    if unstake_fee > 0 && burn_fee {
+        let cpi_accounts = Burn {
+            mint: staking_mint.to_account_info(),
+            from: staking_vault.to_account_info(),
+            authority: stake_pool.to_account_info(),
+        };
+        let cpi_program = token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+        token::burn(cpi_ctx, unstake_fee)?;
+    } else if unstake_fee > 0 {
+        if let Some(treasury_vault) = treasury_vault {
+            let cpi_accounts = Transfer {
+                from: staking_vault.to_account_info(),
+                to: treasury_vault.to_account_info(),
+                authority: stake_pool.to_account_info(),
+            };
+            let cpi_program = token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+            token::transfer(cpi_ctx, unstake_fee)?;
+        }
+    }
 
     // === STATE UPDATE ===
-    
-    let user_stake = &mut ctx.accounts.user_stake;
-    let stake_pool = &mut ctx.accounts.stake_pool;
+
+    // Settle the reward-per-token accumulator before balances change
+    update_rewards(stake_pool, user_stake, clock.unix_timestamp)?;
 
     // Store pending rewards (checked add)
     user_stake.pending_rewards = user_stake
         .pending_rewards
         .checked_add(pending)
         .ok_or(StakingError::MathOverflow)?;
-    user_stake.last_claim_time = clock.unix_timestamp;
+    user_stake.mark_settled(stake_pool, clock.unix_timestamp);
+    stake_pool.pending_liability = stake_pool
+        .pending_liability
+        .checked_add(pending)
+        .ok_or(StakingError::MathOverflow)?;
 
-    // Update staked amount (checked sub)
-    user_stake.staked_amount = user_stake
-        .staked_amount
-        .checked_sub(amount)
-        .ok_or(StakingError::MathUnderflow)?;
+    // Update staked amount (checked sub); queued principal left it at request time
+    if mode == UnstakeMode::Queued {
+        user_stake.unstake_requested_amount = 0;
+        user_stake.unstake_requested_at = 0;
+    } else {
+        user_stake.staked_amount = user_stake
+            .staked_amount
+            .checked_sub(amount)
+            .ok_or(StakingError::MathUnderflow)?;
+        user_stake.sync_bonus_debt(stake_pool.bonus_per_token)?;
+    }
 
-    // If fully unstaked, mark as inactive and decrement staker count
-    if user_stake.staked_amount == 0 {
+    // Track principal released early so later linear tranches are not double-counted;
+    // penalised exits leave the schedule entirely, so the rest keeps vesting
+    if !lock_ended && mode == UnstakeMode::Standard {
+        user_stake.unlocked_withdrawn = user_stake
+            .unlocked_withdrawn
+            .checked_add(amount)
+            .ok_or(StakingError::MathOverflow)?;
+    }
+
+    // If fully unstaked, mark as inactive and decrement staker count;
+    // a pending withdrawal keeps the position open until it is paid out
+    if user_stake.staked_amount == 0 && !user_stake.has_pending_unstake() {
         user_stake.is_active = false;
-        stake_pool.staker_count = stake_pool.staker_count
-            .checked_sub(1)
-            .ok_or(StakingError::MathUnderflow)?;
+        user_stake.auto_renew = false;
+        stake_pool.remove_staker(user_stake.tier)?;
     }
 
-    // Update pool totals (checked sub)
-    stake_pool.total_staked = stake_pool
-        .total_staked
-        .checked_sub(amount)
-        .ok_or(StakingError::MathUnderflow)?;
+    // Update pool totals (checked sub); queued principal was removed at request time
+    if mode != UnstakeMode::Queued {
+        stake_pool.remove_staked(user_stake.tier, amount)?;
+    } else {
+        stake_pool.total_unstake_requested = stake_pool
+            .total_unstake_requested
+            .checked_sub(amount)
+            .ok_or(StakingError::MathUnderflow)?;
+    }
+    stake_pool.record_exit(amount)?;
     stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+    cancel_on_owner_signature(stake_pool, user_stake, clock.unix_timestamp);
+
+    emit!(UnstakeEvent {
+        stake_pool: stake_pool.key(),
+        owner: user_stake.owner,
+        tier: user_stake.tier as u8,
+        amount,
+        fee: unstake_fee,
+        net: net_amount,
+        remaining: user_stake.staked_amount,
+        total_staked: stake_pool.total_staked,
+        tier_total_staked: stake_pool.tier_staked[user_stake.tier as usize],
+        tier_staker_count: stake_pool.tier_staker_count[user_stake.tier as usize],
+        cumulative_unstaked: stake_pool.cumulative_unstaked,
+        sequence: stake_pool.sequence,
+        timestamp: clock.unix_timestamp,
+    });
+
+    if mode == UnstakeMode::Early {
+        emit!(EarlyUnstaked {
+            stake_pool: stake_pool.key(),
+            owner: user_stake.owner,
+            tier: user_stake.tier as u8,
+            amount,
+            penalty: early_penalty,
+            penalty_bps: stake_pool.early_unstake_penalty_for_tier(user_stake.tier),
+            sequence: stake_pool.sequence,
+            timestamp: clock.unix_timestamp,
+        });
+    }
+
+    if unstake_fee > 0 && burn_fee {
+        emit!(PenaltyBurned {
+            stake_pool: stake_pool.key(),
+            owner: user_stake.owner,
+            amount: unstake_fee,
+            sequence: stake_pool.sequence,
+            timestamp: clock.unix_timestamp,
+        });
+    }
+
+    if is_permanent {
+        emit!(PermanentUnstaked {
+            stake_pool: stake_pool.key(),
+            owner: user_stake.owner,
+            amount,
+            remaining: user_stake.staked_amount,
+            sequence: stake_pool.sequence,
+        });
+    }
+
+    if let Some(position_history) = position_history {
+        position_history.push(PositionRecord {
+            timestamp: clock.unix_timestamp,
+            kind: PositionEventKind::Unstake,
+            tier: user_stake.tier as u8,
+            amount,
+            staked_after: user_stake.staked_amount,
+        });
+    }
 
     msg!("Unstaked {} tokens", amount);
+    if mode == UnstakeMode::Early {
+        msg!("Early unstake penalty: {}", early_penalty);
+    }
+    if unstake_fee > 0 {
+        msg!("Unstake fee: {} (received {})", unstake_fee, net_amount);
+    }
     msg!("Remaining staked: {}", user_stake.staked_amount);
     msg!("Pending rewards: {}", user_stake.pending_rewards);
 
+    set_return_data(&net_amount.to_le_bytes());
+
     Ok(())
 }
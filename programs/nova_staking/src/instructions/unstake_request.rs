@@ -0,0 +1,237 @@
+/// Flex withdrawal queue handlers.
+///
+/// While the pool sets `flex_cooldown_seconds`, Flex principal leaves in two
+/// steps: `request_unstake` moves it out of the position, and `withdraw`
+/// pays it out once the cooldown has passed.
+///
+/// ## Security Guarantees
+/// - Owner validation ensures only the stake owner can queue or cancel
+/// - Queued principal stops accruing the moment it is requested
+/// - Cancelling settles rewards first, so accrual restarts from the cancel
+///   and the queued time is never paid retroactively
+
+use anchor_lang::prelude::*;
+
+use crate::constants::*;
+use crate::error::StakingError;
+use crate::events::{UnstakeRequestCancelled, UnstakeRequested};
+use crate::instructions::recovery::cancel_on_owner_signature;
+use crate::instructions::stake::{calculate_pending_rewards, update_rewards};
+use crate::state::{StakePool, UserStake};
+use crate::tier::Tier;
+
+/// Accounts required for queueing or cancelling a Flex withdrawal.
+///
+/// ## Security Notes
+/// - User must be signer AND match user_stake.owner
+#[derive(Accounts)]
+pub struct UnstakeRequest<'info> {
+    /// The position owner.
+    /// SECURITY: Must be signer and match stake owner.
+    pub user: Signer<'info>,
+
+    /// The stake pool; `total_staked` excludes queued principal.
+    /// SECURITY: PDA validation.
+    #[account(
+        mut,
+        seeds = [STAKE_POOL_SEED, stake_pool.staking_mint.as_ref()],
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ StakingError::AccountVersionMismatch,
+        bump = stake_pool.bump
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+
+    /// User's stake account.
+    /// SECURITY: PDA + owner + pool validation.
+    #[account(
+        mut,
+        seeds = [USER_STAKE_SEED, stake_pool.key().as_ref(), user.key().as_ref()],
+        bump = user_stake.bump,
+        constraint = user_stake.version == USER_STAKE_VERSION @ StakingError::AccountVersionMismatch,
+        constraint = user_stake.owner == user.key() @ StakingError::InvalidStakeOwner,
+        constraint = user_stake.stake_pool == stake_pool.key() @ StakingError::StakePoolMismatch
+    )]
+    pub user_stake: Account<'info, UserStake>,
+}
+
+/// Queue Flex principal for withdrawal after the pool's cooldown.
+///
+/// # Security
+/// - Flex only, one request at a time; blocked while unstaking is paused
+/// - The Flex minimum stake duration and any Flex lock are enforced here
+///   rather than at `withdraw`
+/// - Rewards up to now are settled into `pending_rewards`; the requested
+///   amount then leaves `staked_amount` and `total_staked`, so it earns nothing
+///
+/// # Arguments
+/// * `ctx` - UnstakeRequest accounts context
+/// * `amount` - Amount of principal to queue
+///
+/// # Returns
+/// Result indicating success or error
+pub fn request_handler(ctx: Context<UnstakeRequest>, amount: u64) -> Result<()> {
+    let user_stake = &ctx.accounts.user_stake;
+    let stake_pool = &ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    require!(!stake_pool.is_unstake_paused(), StakingError::UnstakingPaused);
+    require!(amount > 0, StakingError::ZeroAmount);
+    require!(user_stake.is_active, StakingError::NoActiveStake);
+    require!(user_stake.tier == Tier::Flex, StakingError::UnstakeRequestFlexOnly);
+    require!(
+        !user_stake.has_pending_unstake(),
+        StakingError::UnstakeRequestPending
+    );
+    require!(
+        user_stake.staked_amount >= amount,
+        StakingError::InsufficientStakedBalance
+    );
+
+    let remaining =
+        stake_pool.flex_cooldown_remaining(user_stake.stake_start_time, clock.unix_timestamp);
+    require!(remaining == 0, StakingError::FlexCooldownActive);
+    require!(
+        stake_pool.min_stake_duration_remaining(user_stake.last_stake_time, clock.unix_timestamp) == 0,
+        StakingError::StakeTooRecent
+    );
+
+    // A configured Flex lock has to end before principal can be queued
+    require!(
+        user_stake.is_lock_ended(
+            clock.unix_timestamp,
+            user_stake.get_lock_period(),
+            stake_pool.unlock_grace_secs,
+        ),
+        StakingError::LockPeriodNotEnded
+    );
+
+    let pending = calculate_pending_rewards(user_stake, stake_pool, clock.unix_timestamp)?;
+
+    // === STATE UPDATE ===
+
+    let user_stake = &mut ctx.accounts.user_stake;
+    let stake_pool = &mut ctx.accounts.stake_pool;
+
+    // Settle the reward-per-token accumulator before balances change
+    update_rewards(stake_pool, user_stake, clock.unix_timestamp)?;
+
+    user_stake.pending_rewards = user_stake
+        .pending_rewards
+        .checked_add(pending)
+        .ok_or(StakingError::MathOverflow)?;
+    user_stake.mark_settled(stake_pool, clock.unix_timestamp);
+    stake_pool.pending_liability = stake_pool
+        .pending_liability
+        .checked_add(pending)
+        .ok_or(StakingError::MathOverflow)?;
+
+    user_stake.staked_amount = user_stake
+        .staked_amount
+        .checked_sub(amount)
+        .ok_or(StakingError::MathUnderflow)?;
+    user_stake.sync_bonus_debt(stake_pool.bonus_per_token)?;
+    user_stake.unstake_requested_amount = amount;
+    user_stake.unstake_requested_at = clock.unix_timestamp;
+
+    stake_pool.remove_staked(user_stake.tier, amount)?;
+    stake_pool.total_unstake_requested = stake_pool
+        .total_unstake_requested
+        .checked_add(amount)
+        .ok_or(StakingError::MathOverflow)?;
+    stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+    cancel_on_owner_signature(stake_pool, user_stake, clock.unix_timestamp);
+
+    let available_at = clock
+        .unix_timestamp
+        .saturating_add(stake_pool.flex_cooldown_seconds);
+
+    emit!(UnstakeRequested {
+        stake_pool: stake_pool.key(),
+        owner: user_stake.owner,
+        amount,
+        staked_after: user_stake.staked_amount,
+        available_at,
+        sequence: stake_pool.sequence,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!("Queued {} tokens for withdrawal", amount);
+    msg!("Withdrawable at: {}", available_at);
+    msg!("Remaining staked: {}", user_stake.staked_amount);
+
+    Ok(())
+}
+
+/// Return a queued Flex withdrawal to the position.
+///
+/// # Security
+/// - Requires a pending request; blocked in emergency mode like any restake
+/// - Rewards on the remaining stake are settled first, then the queued
+///   amount accrues again from now
+/// - Not subject to the pool cap or pauses; the principal never left the vault
+///
+/// # Arguments
+/// * `ctx` - UnstakeRequest accounts context
+///
+/// # Returns
+/// Result indicating success or error
+pub fn cancel_handler(ctx: Context<UnstakeRequest>) -> Result<()> {
+    let user_stake = &ctx.accounts.user_stake;
+    let stake_pool = &ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    require!(!stake_pool.emergency, StakingError::EmergencyMode);
+    require!(user_stake.has_pending_unstake(), StakingError::NoUnstakeRequest);
+
+    let pending = calculate_pending_rewards(user_stake, stake_pool, clock.unix_timestamp)?;
+
+    // === STATE UPDATE ===
+
+    let user_stake = &mut ctx.accounts.user_stake;
+    let stake_pool = &mut ctx.accounts.stake_pool;
+
+    // Settle the reward-per-token accumulator before balances change
+    update_rewards(stake_pool, user_stake, clock.unix_timestamp)?;
+    let amount = user_stake.unstake_requested_amount;
+
+    user_stake.pending_rewards = user_stake
+        .pending_rewards
+        .checked_add(pending)
+        .ok_or(StakingError::MathOverflow)?;
+    user_stake.mark_settled(stake_pool, clock.unix_timestamp);
+    stake_pool.pending_liability = stake_pool
+        .pending_liability
+        .checked_add(pending)
+        .ok_or(StakingError::MathOverflow)?;
+
+    user_stake.staked_amount = user_stake
+        .staked_amount
+        .checked_add(amount)
+        .ok_or(StakingError::MathOverflow)?;
+    user_stake.sync_bonus_debt(stake_pool.bonus_per_token)?;
+    user_stake.unstake_requested_amount = 0;
+    user_stake.unstake_requested_at = 0;
+
+    stake_pool.add_staked(user_stake.tier, amount)?;
+    stake_pool.total_unstake_requested = stake_pool
+        .total_unstake_requested
+        .checked_sub(amount)
+        .ok_or(StakingError::MathUnderflow)?;
+    stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+    cancel_on_owner_signature(stake_pool, user_stake, clock.unix_timestamp);
+
+    emit!(UnstakeRequestCancelled {
+        stake_pool: stake_pool.key(),
+        owner: user_stake.owner,
+        amount,
+        staked_after: user_stake.staked_amount,
+        sequence: stake_pool.sequence,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!("Cancelled withdrawal of {} tokens", amount);
+    msg!("Total staked by user: {}", user_stake.staked_amount);
+
+    Ok(())
+}
@@ -0,0 +1,82 @@
+/// View pending rewards instruction handler.
+///
+/// Read-only quote of what a position can claim, meant to be simulated so
+/// frontends use the on-chain reward math instead of re-implementing it.
+///
+/// ## Security Guarantees
+/// - No signer and no writable accounts; nothing is modified
+
+use anchor_lang::prelude::*;
+
+use crate::constants::*;
+use crate::error::StakingError;
+use crate::instructions::stake::calculate_pending_rewards;
+use crate::state::{PendingRewardsView, StakePool, UserStake};
+use crate::tier::Tier;
+
+/// Accounts required for viewing pending rewards.
+///
+/// ## Security Notes
+/// - Position must belong to the pool
+#[derive(Accounts)]
+pub struct ViewPendingRewards<'info> {
+    /// The stake pool.
+    /// SECURITY: PDA validation.
+    #[account(
+        seeds = [STAKE_POOL_SEED, stake_pool.staking_mint.as_ref()],
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ StakingError::AccountVersionMismatch,
+        bump = stake_pool.bump
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+
+    /// Position to quote.
+    #[account(
+        constraint = user_stake.stake_pool == stake_pool.key() @ StakingError::StakePoolMismatch,
+        constraint = user_stake.version == USER_STAKE_VERSION @ StakingError::AccountVersionMismatch
+    )]
+    pub user_stake: Account<'info, UserStake>,
+}
+
+/// Quote the position's claimable rewards, lock end and tier APY.
+///
+/// Claimable is stored pending plus newly accrued plus any released boost
+/// and unsettled donations, the same total `claim_rewards` starts from.
+///
+/// # Arguments
+/// * `ctx` - ViewPendingRewards accounts context
+///
+/// # Returns
+/// The quote, delivered via return data
+pub fn handler(ctx: Context<ViewPendingRewards>) -> Result<PendingRewardsView> {
+    let user_stake = &ctx.accounts.user_stake;
+    let stake_pool = &ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    let newly_accrued = calculate_pending_rewards(user_stake, stake_pool, clock.unix_timestamp)?;
+    let claimable = user_stake
+        .pending_rewards
+        .checked_add(newly_accrued)
+        .ok_or(StakingError::MathOverflow)?
+        .checked_add(user_stake.releasable_boost(clock.unix_timestamp))
+        .ok_or(StakingError::MathOverflow)?
+        .checked_add(user_stake.pending_donation(stake_pool.bonus_per_token)?)
+        .ok_or(StakingError::MathOverflow)?;
+
+    let lock_end_time = if user_stake.tier == Tier::Permanent {
+        stake_pool.sunset_at
+    } else {
+        user_stake.current_lock_end(clock.unix_timestamp)
+    };
+
+    // An ended lock under `downgrade_after_lock` earns the Flex rate
+    let rate_tier = match user_stake.downgrade_time() {
+        Some(at) if stake_pool.downgrade_after_lock && clock.unix_timestamp >= at => Tier::Flex,
+        _ => user_stake.tier,
+    };
+
+    Ok(PendingRewardsView {
+        claimable,
+        lock_end_time,
+        apy_bps: rate_tier.apy(stake_pool, clock.unix_timestamp),
+    })
+}
@@ -0,0 +1,169 @@
+/// Staking whitelist instruction handlers.
+///
+/// While a pool's `whitelist_enabled` is set, `stake` and `stake_for` only
+/// accept deposits into positions whose owner has a `WhitelistEntry`. The
+/// authority adds and removes entries here and toggles the mode with
+/// `set_whitelist_enabled`.
+///
+/// ## Security Guarantees
+/// - Only pool.authority can add or remove entries
+/// - One entry per (pool, wallet); adding twice fails
+/// - Removal only blocks new deposits: unstaking, claiming and every other
+///   instruction on an existing position ignore the whitelist
+/// - Every change emits `WhitelistUpdated`
+
+use anchor_lang::prelude::*;
+
+use crate::constants::*;
+use crate::error::StakingError;
+use crate::events::WhitelistUpdated;
+use crate::state::{StakePool, WhitelistEntry};
+
+/// Accounts required for adding a wallet to the whitelist.
+///
+/// ## Security Notes
+/// - Authority must be signer AND match stake_pool.authority
+/// - `wallet` seeds the new entry; `init` fails if it is already listed
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct AddToWhitelist<'info> {
+    /// The admin authority; pays rent for the entry.
+    /// SECURITY: Must be signer AND match pool.authority.
+    #[account(
+        mut,
+        constraint = authority.key() == stake_pool.authority @ StakingError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    /// The stake pool; writable for `sequence`.
+    /// SECURITY: PDA validation.
+    #[account(
+        mut,
+        seeds = [STAKE_POOL_SEED, stake_pool.staking_mint.as_ref()],
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ StakingError::AccountVersionMismatch,
+        bump = stake_pool.bump
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+
+    /// New entry PDA for (pool, wallet).
+    #[account(
+        init,
+        payer = authority,
+        space = WhitelistEntry::LEN,
+        seeds = [WHITELIST_SEED, stake_pool.key().as_ref(), wallet.as_ref()],
+        bump
+    )]
+    pub whitelist_entry: Account<'info, WhitelistEntry>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required for removing a wallet from the whitelist.
+///
+/// ## Security Notes
+/// - Authority must be signer AND match stake_pool.authority
+/// - The entry is closed and its rent returned to the authority
+#[derive(Accounts)]
+pub struct RemoveFromWhitelist<'info> {
+    /// The admin authority; receives the entry's rent.
+    /// SECURITY: Must be signer AND match pool.authority.
+    #[account(
+        mut,
+        constraint = authority.key() == stake_pool.authority @ StakingError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    /// The stake pool; writable for `sequence`.
+    /// SECURITY: PDA validation.
+    #[account(
+        mut,
+        seeds = [STAKE_POOL_SEED, stake_pool.staking_mint.as_ref()],
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ StakingError::AccountVersionMismatch,
+        bump = stake_pool.bump
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+
+    /// Entry being removed, closed to the authority.
+    /// SECURITY: PDA for (pool, wallet).
+    #[account(
+        mut,
+        close = authority,
+        seeds = [WHITELIST_SEED, stake_pool.key().as_ref(), whitelist_entry.wallet.as_ref()],
+        bump = whitelist_entry.bump
+    )]
+    pub whitelist_entry: Account<'info, WhitelistEntry>,
+}
+
+/// Allow `wallet` to stake while the whitelist is enabled.
+///
+/// # Security
+/// - Only pool.authority can call this
+/// - Entries can be added whether or not the whitelist is enabled, so a
+///   private beta can be prepared before it is switched on
+///
+/// # Arguments
+/// * `ctx` - AddToWhitelist accounts context
+/// * `wallet` - Wallet to allow
+///
+/// # Returns
+/// Result indicating success or error
+pub fn add_handler(ctx: Context<AddToWhitelist>, wallet: Pubkey) -> Result<()> {
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    ctx.accounts.whitelist_entry.set_inner(WhitelistEntry {
+        stake_pool: stake_pool.key(),
+        wallet,
+        added_at: clock.unix_timestamp,
+        bump: ctx.bumps.whitelist_entry,
+    });
+    stake_pool.next_sequence()?;
+
+    emit!(WhitelistUpdated {
+        stake_pool: stake_pool.key(),
+        wallet,
+        whitelisted: true,
+        authority: ctx.accounts.authority.key(),
+        sequence: stake_pool.sequence,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!("Whitelisted {}", wallet);
+    msg!("Admin: {}", ctx.accounts.authority.key());
+
+    Ok(())
+}
+
+/// Stop `whitelist_entry.wallet` from making new deposits while the
+/// whitelist is enabled.
+///
+/// # Security
+/// - Only pool.authority can call this
+/// - An open position stays fully usable for everything but deposits
+///
+/// # Arguments
+/// * `ctx` - RemoveFromWhitelist accounts context
+///
+/// # Returns
+/// Result indicating success or error
+pub fn remove_handler(ctx: Context<RemoveFromWhitelist>) -> Result<()> {
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    let wallet = ctx.accounts.whitelist_entry.wallet;
+    let clock = Clock::get()?;
+
+    stake_pool.next_sequence()?;
+
+    emit!(WhitelistUpdated {
+        stake_pool: stake_pool.key(),
+        wallet,
+        whitelisted: false,
+        authority: ctx.accounts.authority.key(),
+        sequence: stake_pool.sequence,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!("Removed {} from whitelist", wallet);
+    msg!("Admin: {}", ctx.accounts.authority.key());
+
+    Ok(())
+}
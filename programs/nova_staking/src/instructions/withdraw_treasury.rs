@@ -0,0 +1,163 @@
+/// Withdraw treasury instruction handler.
+///
+/// Lets the authority recover surplus reward tokens from the treasury once a
+/// campaign is over.
+///
+/// ## Security Guarantees
+/// - Admin-only, and only while the pool is paused
+/// - Never takes the treasury below `pending_liability + referral_liability`,
+///   so every settled reward and referral share stays payable
+/// - Rewards still accruing on open positions are not reserved; pausing the
+///   pool first is what keeps that window small
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::set_return_data;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+
+use crate::constants::*;
+use crate::error::StakingError;
+use crate::events::TreasuryWithdrawn;
+use crate::state::StakePool;
+
+/// Accounts required for withdrawing surplus treasury funds.
+///
+/// ## Security Notes
+/// - Authority must be signer AND match stake_pool.authority
+/// - Treasury must match pool's treasury vault
+/// - Destination only needs the reward mint; the authority picks the owner
+#[derive(Accounts)]
+pub struct WithdrawTreasury<'info> {
+    /// The admin authority.
+    /// SECURITY: Must be signer AND match pool.authority.
+    #[account(
+        constraint = authority.key() == stake_pool.authority @ StakingError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    /// The stake pool.
+    /// SECURITY: PDA + has_one validations.
+    #[account(
+        mut,
+        seeds = [STAKE_POOL_SEED, stake_pool.staking_mint.as_ref()],
+        constraint = stake_pool.version == STAKE_POOL_VERSION @ StakingError::AccountVersionMismatch,
+        bump = stake_pool.bump,
+        has_one = authority @ StakingError::Unauthorized,
+        has_one = treasury_vault @ StakingError::TreasuryMismatch,
+        has_one = staking_mint @ StakingError::MintMismatch
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+
+    /// The staking token mint.
+    /// SECURITY: Must match pool's locked mint.
+    #[account(
+        constraint = staking_mint.key() == stake_pool.staking_mint @ StakingError::MintMismatch
+    )]
+    pub staking_mint: Account<'info, Mint>,
+
+    /// Pool's treasury vault.
+    /// SECURITY: Must match pool's stored treasury + owner validation.
+    #[account(
+        mut,
+        constraint = treasury_vault.key() == stake_pool.treasury_vault @ StakingError::TreasuryMismatch,
+        constraint = treasury_vault.owner == stake_pool.key() @ StakingError::InvalidTreasuryOwner,
+        constraint = treasury_vault.mint == stake_pool.reward_mint @ StakingError::InvalidTokenAccountMint
+    )]
+    pub treasury_vault: Account<'info, TokenAccount>,
+
+    /// Token account receiving the surplus.
+    /// SECURITY: Reward mint validation.
+    #[account(
+        mut,
+        constraint = destination.mint == stake_pool.reward_mint @ StakingError::InvalidTokenAccountMint
+    )]
+    pub destination: Account<'info, TokenAccount>,
+
+    /// Token program.
+    pub token_program: Program<'info, Token>,
+}
+
+/// Transfer surplus reward tokens out of the treasury.
+///
+/// # Security
+/// - Requires staking to be paused; emergency mode alone is not enough
+/// - The treasury must keep at least `StakePool::treasury_floor`; on
+///   rejection the withdrawable surplus is written to return data
+/// - PDA signer for treasury transfer
+///
+/// # Arguments
+/// * `ctx` - WithdrawTreasury accounts context
+/// * `amount` - Amount of tokens to withdraw
+///
+/// # Returns
+/// Result indicating success or error
+pub fn handler(ctx: Context<WithdrawTreasury>, amount: u64) -> Result<()> {
+    // An expired `set_paused` pause is lifted before the pause checks
+    ctx.accounts.stake_pool.expire_pause(Clock::get()?.unix_timestamp)?;
+    let stake_pool = &ctx.accounts.stake_pool;
+    let clock = Clock::get()?;
+
+    // === INPUT VALIDATION ===
+
+    require!(amount > 0, StakingError::ZeroAmount);
+    require!(stake_pool.is_stake_paused(), StakingError::PoolNotPaused);
+
+    // === LIABILITY FLOOR ===
+
+    let floor = stake_pool.treasury_floor();
+    let surplus = ctx.accounts.treasury_vault.amount.saturating_sub(floor);
+    if amount > surplus {
+        set_return_data(&surplus.to_le_bytes());
+        msg!("Treasury surplus above liabilities: {}", surplus);
+        return err!(StakingError::TreasuryBelowLiabilities);
+    }
+
+    // === PDA SIGNER TRANSFER ===
+
+    let staking_mint_key = stake_pool.staking_mint;
+    let seeds = &[
+        STAKE_POOL_SEED,
+        staking_mint_key.as_ref(),
+        &[stake_pool.bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+
+    let cpi_accounts = Transfer {
+        from: ctx.accounts.treasury_vault.to_account_info(),
+        to: ctx.accounts.destination.to_account_info(),
+        authority: ctx.accounts.stake_pool.to_account_info(),
+    };
+    let cpi_program = ctx.accounts.token_program.to_account_info();
+    let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+    token::transfer(cpi_ctx, amount)?;
+
+    // === STATE UPDATE ===
+
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    stake_pool.last_updated = clock.unix_timestamp;
+    stake_pool.next_sequence()?;
+
+    ctx.accounts.treasury_vault.reload()?;
+    let remaining = ctx.accounts.treasury_vault.amount;
+    if remaining == 0
+        && stake_pool.treasury_deployed == 0
+        && stake_pool.treasury_empty_since.is_none()
+    {
+        stake_pool.treasury_empty_since = Some(clock.unix_timestamp);
+        msg!("Treasury drained to zero");
+    }
+
+    emit!(TreasuryWithdrawn {
+        stake_pool: stake_pool.key(),
+        destination: ctx.accounts.destination.key(),
+        amount,
+        remaining,
+        liability_floor: floor,
+        sequence: stake_pool.sequence,
+        timestamp: clock.unix_timestamp,
+    });
+
+    msg!("Withdrew {} tokens from the treasury", amount);
+    msg!("Treasury balance: {} (floor {})", remaining, floor);
+
+    Ok(())
+}
@@ -1,16 +1,596 @@
 pub mod error;
 pub mod tier;
 pub mod constants;
+pub mod events;
+pub mod math;
+pub mod oracle;
 declare_id!("EA2KPnKE3oRUg6f9mvGMcQT6ZvdwPoCdv8ThLWFtz72X");
 use anchor_lang::prelude::*;
 
 pub mod instructions;
 pub mod state;
 
+use instructions::admin::*;
+use instructions::admin_multisig::*;
+use instructions::auto_renew::*;
+use instructions::change_tier::*;
+use instructions::claim_delegate::*;
+use instructions::claim_fee::*;
+use instructions::claim_history::*;
+use instructions::close_user_stake::*;
+use instructions::compound_rewards::*;
+use instructions::credit_bonus::*;
+use instructions::donate_to_stakers::*;
+use instructions::gc_user_stake::*;
+use instructions::migrate::*;
+use instructions::oracle_apy::*;
+use instructions::pool_registry::*;
+use instructions::pending_params::*;
+use instructions::position_history::*;
+use instructions::referral::*;
+use instructions::refresh_pool::*;
+use instructions::reserve::*;
+use instructions::reward_destination::*;
+use instructions::snapshot::*;
+use instructions::stake::*;
+use instructions::stake_label::*;
+use instructions::sync_vault::*;
+use instructions::transfer_stake::*;
+use instructions::treasury_yield::*;
+use instructions::unstake::*;
+use instructions::unstake_request::*;
+use instructions::view_pending_rewards::*;
+use instructions::whitelist::*;
+use instructions::withdraw_treasury::*;
+use instructions::recover_tokens::*;
+use instructions::recovery::*;
+use state::{
+    AdminAction, AdminRole, ClaimHistoryPage, OracleApyConfig, PenaltyDestination, PendingRewardsView,
+    PositionHistoryPage,
+};
+use instructions::boost_lock::*;
+
 
 
 #[program]
 pub mod nova_staking {
+    use super::*;
 
     // program entrypoints stay unchanged
+
+    pub fn set_accrue_only_when_funded(ctx: Context<AdminControl>, enabled: bool) -> Result<()> {
+        instructions::admin::set_accrue_only_when_funded_handler(ctx, enabled)
+    }
+
+    pub fn set_accrue_while_paused(ctx: Context<AdminControl>, enabled: bool) -> Result<()> {
+        instructions::admin::set_accrue_while_paused_handler(ctx, enabled)
+    }
+
+    pub fn set_max_accrual_horizon(ctx: Context<AdminControl>, horizon_secs: i64) -> Result<()> {
+        instructions::admin::set_max_accrual_horizon_handler(ctx, horizon_secs)
+    }
+
+    pub fn set_max_daily_inflow(ctx: Context<AdminControl>, max_daily_inflow: u64) -> Result<()> {
+        instructions::admin::set_max_daily_inflow_handler(ctx, max_daily_inflow)
+    }
+
+    pub fn set_emergency(ctx: Context<AdminControl>) -> Result<()> {
+        instructions::admin::set_emergency_handler(ctx)
+    }
+
+    pub fn clear_emergency(ctx: Context<AdminControl>) -> Result<()> {
+        instructions::admin::clear_emergency_handler(ctx)
+    }
+
+    pub fn change_tier(ctx: Context<ChangeTier>, new_tier: u8) -> Result<()> {
+        instructions::change_tier::handler(ctx, new_tier)
+    }
+
+    pub fn upgrade_tier(ctx: Context<ChangeTier>, new_tier: u8) -> Result<()> {
+        instructions::change_tier::upgrade_handler(ctx, new_tier)
+    }
+
+    pub fn set_auto_renew(ctx: Context<SetAutoRenew>, enabled: bool) -> Result<()> {
+        instructions::auto_renew::handler(ctx, enabled)
+    }
+
+    pub fn set_allowed_topup_policies(ctx: Context<AdminControl>, mask: u8) -> Result<()> {
+        instructions::admin::set_allowed_topup_policies_handler(ctx, mask)
+    }
+
+    pub fn set_relock_bonus(ctx: Context<AdminControl>, bonus_bps: u16, grace_secs: i64) -> Result<()> {
+        instructions::admin::set_relock_bonus_handler(ctx, bonus_bps, grace_secs)
+    }
+
+    pub fn set_early_bird(
+        ctx: Context<AdminControl>,
+        slots: u64,
+        bonus_bps: u16,
+        duration_secs: i64,
+    ) -> Result<()> {
+        instructions::admin::set_early_bird_handler(ctx, slots, bonus_bps, duration_secs)
+    }
+
+    pub fn set_claim_streak(
+        ctx: Context<AdminControl>,
+        step_bps: u16,
+        cap_bps: u16,
+        window_secs: i64,
+    ) -> Result<()> {
+        instructions::admin::set_claim_streak_handler(ctx, step_bps, cap_bps, window_secs)
+    }
+
+    pub fn set_apy_decay(
+        ctx: Context<AdminControl>,
+        interval_secs: i64,
+        bps_per_interval: u16,
+        min_apy_floor: u16,
+    ) -> Result<()> {
+        instructions::admin::set_apy_decay_handler(
+            ctx,
+            interval_secs,
+            bps_per_interval,
+            min_apy_floor,
+        )
+    }
+
+    pub fn set_lock_periods(
+        ctx: Context<AdminControl>,
+        flex_lock_secs: i64,
+        core_lock_secs: i64,
+        prime_lock_secs: i64,
+    ) -> Result<()> {
+        instructions::admin::set_lock_periods_handler(
+            ctx,
+            flex_lock_secs,
+            core_lock_secs,
+            prime_lock_secs,
+        )
+    }
+
+    pub fn set_release_schedule(
+        ctx: Context<AdminControl>,
+        tier: u8,
+        cliff_secs: i64,
+        linear_secs: i64,
+    ) -> Result<()> {
+        instructions::admin::set_release_schedule_handler(ctx, tier, cliff_secs, linear_secs)
+    }
+
+    pub fn set_reward_cliff(ctx: Context<AdminControl>, tier: u8, cliff_secs: i64) -> Result<()> {
+        instructions::admin::set_reward_cliff_handler(ctx, tier, cliff_secs)
+    }
+
+    pub fn set_permanent_apy(ctx: Context<AdminControl>, permanent_apy: u16) -> Result<()> {
+        instructions::admin::set_permanent_apy_handler(ctx, permanent_apy)
+    }
+
+    pub fn declare_sunset(ctx: Context<AdminControl>, sunset_at: i64) -> Result<()> {
+        instructions::admin::declare_sunset_handler(ctx, sunset_at)
+    }
+
+    pub fn set_rewards_end_time(ctx: Context<AdminControl>, end_time: i64) -> Result<()> {
+        instructions::admin::set_rewards_end_time_handler(ctx, end_time)
+    }
+
+    pub fn boost_lock(ctx: Context<BoostLock>, amount: u64, duration_secs: i64) -> Result<()> {
+        instructions::boost_lock::handler(ctx, amount, duration_secs)
+    }
+
+    pub fn set_boost(
+        ctx: Context<AdminControl>,
+        boost_bps: u16,
+        max_duration_secs: i64,
+    ) -> Result<()> {
+        instructions::admin::set_boost_handler(ctx, boost_bps, max_duration_secs)
+    }
+
+    pub fn enable_claim_history(ctx: Context<EnableClaimHistory>) -> Result<()> {
+        instructions::claim_history::enable_handler(ctx)
+    }
+
+    pub fn view_claim_history(
+        ctx: Context<ViewClaimHistory>,
+        page: u16,
+    ) -> Result<ClaimHistoryPage> {
+        instructions::claim_history::view_handler(ctx, page)
+    }
+
+    pub fn enable_position_history(ctx: Context<EnablePositionHistory>) -> Result<()> {
+        instructions::position_history::enable_handler(ctx)
+    }
+
+    pub fn view_position_history(
+        ctx: Context<ViewPositionHistory>,
+        page: u16,
+    ) -> Result<PositionHistoryPage> {
+        instructions::position_history::view_handler(ctx, page)
+    }
+
+    pub fn claim_referral_rewards(ctx: Context<ClaimReferralRewards>) -> Result<()> {
+        instructions::referral::claim_referral_rewards_handler(ctx)
+    }
+
+    pub fn set_referral_share(ctx: Context<AdminControl>, share_bps: u16) -> Result<()> {
+        instructions::admin::set_referral_share_handler(ctx, share_bps)
+    }
+
+    pub fn set_param_timelock(ctx: Context<AdminControl>, timelock_seconds: i64) -> Result<()> {
+        instructions::admin::set_param_timelock_handler(ctx, timelock_seconds)
+    }
+
+    pub fn propose_claim_fee(ctx: Context<ProposeClaimFee>, fee_bps: u16) -> Result<()> {
+        instructions::claim_fee::propose_claim_fee_handler(ctx, fee_bps)
+    }
+
+    pub fn apply_claim_fee(ctx: Context<ApplyClaimFee>) -> Result<()> {
+        instructions::claim_fee::apply_claim_fee_handler(ctx)
+    }
+
+    pub fn cancel_claim_fee(ctx: Context<AdminControl>) -> Result<()> {
+        instructions::claim_fee::cancel_claim_fee_handler(ctx)
+    }
+
+    pub fn migrate_stake_pool(ctx: Context<MigrateStakePool>) -> Result<()> {
+        instructions::migrate::migrate_stake_pool_handler(ctx)
+    }
+
+    pub fn migrate_user_stake(ctx: Context<MigrateUserStake>) -> Result<()> {
+        instructions::migrate::migrate_user_stake_handler(ctx)
+    }
+
+    pub fn apply_pending_params(ctx: Context<ApplyPendingParams>) -> Result<()> {
+        instructions::pending_params::apply_handler(ctx)
+    }
+
+    pub fn cancel_pending_params(ctx: Context<AdminControl>) -> Result<()> {
+        instructions::pending_params::cancel_handler(ctx)
+    }
+
+    pub fn set_claim_rate_limit(
+        ctx: Context<AdminControl>,
+        max_claims: u32,
+        epoch_secs: i64,
+    ) -> Result<()> {
+        instructions::admin::set_claim_rate_limit_handler(ctx, max_claims, epoch_secs)
+    }
+
+    pub fn set_epoch_emission_limit(
+        ctx: Context<AdminControl>,
+        max_emission: u64,
+        epoch_secs: i64,
+    ) -> Result<()> {
+        instructions::admin::set_epoch_emission_limit_handler(ctx, max_emission, epoch_secs)
+    }
+
+    pub fn unstake_to(ctx: Context<UnstakeTo>, amount: u64) -> Result<()> {
+        instructions::unstake::unstake_to_handler(ctx, amount)
+    }
+
+    pub fn set_unstake_fee(ctx: Context<AdminControl>, tier: u8, fee_bps: u16) -> Result<()> {
+        instructions::admin::set_unstake_fee_handler(ctx, tier, fee_bps)
+    }
+
+    pub fn set_penalty_destination(
+        ctx: Context<SetPenaltyDestination>,
+        destination: PenaltyDestination,
+    ) -> Result<()> {
+        instructions::admin::set_penalty_destination_handler(ctx, destination)
+    }
+
+    pub fn set_flex_min_stake(ctx: Context<AdminControl>, min_stake_secs: i64) -> Result<()> {
+        instructions::admin::set_flex_min_stake_handler(ctx, min_stake_secs)
+    }
+
+    pub fn set_min_durations(
+        ctx: Context<AdminControl>,
+        min_accrual_seconds: i64,
+        min_stake_duration_seconds: i64,
+    ) -> Result<()> {
+        instructions::admin::set_min_durations_handler(
+            ctx,
+            min_accrual_seconds,
+            min_stake_duration_seconds,
+        )
+    }
+
+    pub fn set_loyalty_schedule(
+        ctx: Context<AdminControl>,
+        threshold_secs: Vec<i64>,
+        bonus_bps: Vec<u16>,
+    ) -> Result<()> {
+        instructions::admin::set_loyalty_schedule_handler(ctx, threshold_secs, bonus_bps)
+    }
+
+    pub fn set_yield_adapter(ctx: Context<AdminControl>, slot: u8, adapter: Pubkey) -> Result<()> {
+        instructions::admin::set_yield_adapter_handler(ctx, slot, adapter)
+    }
+
+    pub fn set_max_treasury_deploy(ctx: Context<AdminControl>, max_deploy_bps: u16) -> Result<()> {
+        instructions::admin::set_max_treasury_deploy_handler(ctx, max_deploy_bps)
+    }
+
+    pub fn deploy_treasury<'info>(
+        ctx: Context<'_, '_, 'info, 'info, TreasuryYield<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        instructions::treasury_yield::deploy_treasury_handler(ctx, amount)
+    }
+
+    pub fn recall_treasury<'info>(
+        ctx: Context<'_, '_, 'info, 'info, TreasuryYield<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        instructions::treasury_yield::recall_treasury_handler(ctx, amount)
+    }
+
+    pub fn fund_reserve(ctx: Context<FundReserve>, amount: u64) -> Result<()> {
+        instructions::reserve::fund_reserve_handler(ctx, amount)
+    }
+
+    pub fn drip_treasury(ctx: Context<DripTreasury>) -> Result<()> {
+        instructions::reserve::drip_treasury_handler(ctx)
+    }
+
+    pub fn set_drip_rate(ctx: Context<SetDripRate>, rate_per_sec: u64) -> Result<()> {
+        instructions::reserve::set_drip_rate_handler(ctx, rate_per_sec)
+    }
+
+    pub fn credit_bonus<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CreditBonus<'info>>,
+        amount_per_user: u64,
+    ) -> Result<()> {
+        instructions::credit_bonus::handler(ctx, amount_per_user)
+    }
+
+    pub fn set_unlock_grace(ctx: Context<AdminControl>, grace_secs: i64) -> Result<()> {
+        instructions::admin::set_unlock_grace_handler(ctx, grace_secs)
+    }
+
+    pub fn compound_rewards(ctx: Context<CompoundRewards>) -> Result<()> {
+        instructions::compound_rewards::handler(ctx)
+    }
+
+    pub fn early_unstake(ctx: Context<Unstake>, amount: u64) -> Result<()> {
+        instructions::unstake::early_unstake_handler(ctx, amount)
+    }
+
+    pub fn set_early_unstake_penalty(
+        ctx: Context<AdminControl>,
+        tier: u8,
+        penalty_bps: u16,
+    ) -> Result<()> {
+        instructions::admin::set_early_unstake_penalty_handler(ctx, tier, penalty_bps)
+    }
+
+    pub fn propose_authority(ctx: Context<AdminControl>, new_authority: Pubkey) -> Result<()> {
+        instructions::admin::propose_authority_handler(ctx, new_authority)
+    }
+
+    pub fn accept_authority(ctx: Context<AcceptAuthority>) -> Result<()> {
+        instructions::admin::accept_authority_handler(ctx)
+    }
+
+    pub fn cancel_authority_transfer(ctx: Context<AdminControl>) -> Result<()> {
+        instructions::admin::cancel_authority_transfer_handler(ctx)
+    }
+
+    pub fn create_admin_multisig(
+        ctx: Context<CreateAdminMultisig>,
+        signers: Vec<Pubkey>,
+        threshold: u8,
+        proposal_ttl_seconds: i64,
+    ) -> Result<()> {
+        instructions::admin_multisig::create_handler(ctx, signers, threshold, proposal_ttl_seconds)
+    }
+
+    pub fn propose_admin_action(ctx: Context<ProposeAdminAction>, action: AdminAction) -> Result<()> {
+        instructions::admin_multisig::propose_handler(ctx, action)
+    }
+
+    pub fn approve_admin_action(ctx: Context<ApproveAdminAction>) -> Result<()> {
+        instructions::admin_multisig::approve_handler(ctx)
+    }
+
+    pub fn close_user_stake(ctx: Context<CloseUserStake>) -> Result<()> {
+        instructions::close_user_stake::handler(ctx)
+    }
+
+    pub fn view_pending_rewards(ctx: Context<ViewPendingRewards>) -> Result<PendingRewardsView> {
+        instructions::view_pending_rewards::handler(ctx)
+    }
+
+    pub fn emergency_withdraw(ctx: Context<Unstake>) -> Result<()> {
+        instructions::unstake::emergency_withdraw_handler(ctx)
+    }
+
+    pub fn set_tier_paused(ctx: Context<PauserControl>, tier: u8, paused: bool) -> Result<()> {
+        instructions::admin::set_tier_paused_handler(ctx, tier, paused)
+    }
+
+    pub fn set_pause_flags(ctx: Context<PauserControl>, flags: u8) -> Result<()> {
+        instructions::admin::set_pause_flags_handler(ctx, flags)
+    }
+
+    pub fn set_min_stake_amount(ctx: Context<AdminControl>, min_stake_amount: u64) -> Result<()> {
+        instructions::admin::set_min_stake_amount_handler(ctx, min_stake_amount)
+    }
+
+    pub fn set_max_total_staked(ctx: Context<AdminControl>, max_total_staked: u64) -> Result<()> {
+        instructions::admin::set_max_total_staked_handler(ctx, max_total_staked)
+    }
+
+    pub fn set_reward_rate(ctx: Context<AdminControl>, reward_rate_per_second: u64) -> Result<()> {
+        instructions::admin::set_reward_rate_handler(ctx, reward_rate_per_second)
+    }
+
+    pub fn set_solvency_check(
+        ctx: Context<AdminControl>,
+        require_solvency: bool,
+        treasury_multiple_bps: u32,
+    ) -> Result<()> {
+        instructions::admin::set_solvency_check_handler(ctx, require_solvency, treasury_multiple_bps)
+    }
+
+    pub fn set_flex_cooldown(ctx: Context<AdminControl>, cooldown_secs: i64) -> Result<()> {
+        instructions::admin::set_flex_cooldown_handler(ctx, cooldown_secs)
+    }
+
+    pub fn request_unstake(ctx: Context<UnstakeRequest>, amount: u64) -> Result<()> {
+        instructions::unstake_request::request_handler(ctx, amount)
+    }
+
+    pub fn cancel_unstake_request(ctx: Context<UnstakeRequest>) -> Result<()> {
+        instructions::unstake_request::cancel_handler(ctx)
+    }
+
+    pub fn withdraw(ctx: Context<Unstake>) -> Result<()> {
+        instructions::unstake::withdraw_handler(ctx)
+    }
+
+    pub fn withdraw_treasury(ctx: Context<WithdrawTreasury>, amount: u64) -> Result<()> {
+        instructions::withdraw_treasury::handler(ctx, amount)
+    }
+
+    pub fn recover_tokens(ctx: Context<RecoverTokens>, amount: u64) -> Result<()> {
+        instructions::recover_tokens::handler(ctx, amount)
+    }
+
+    pub fn refresh_pool(ctx: Context<RefreshPool>) -> Result<()> {
+        instructions::refresh_pool::handler(ctx)
+    }
+
+    pub fn stake_for(
+        ctx: Context<StakeFor>,
+        amount: u64,
+        tier: u8,
+        expected_apy_bps: Option<u16>,
+    ) -> Result<()> {
+        instructions::stake::stake_for_handler(ctx, amount, tier, expected_apy_bps)
+    }
+
+    pub fn set_claim_delegate(ctx: Context<SetClaimDelegate>, delegate: Pubkey) -> Result<()> {
+        instructions::claim_delegate::set_handler(ctx, delegate)
+    }
+
+    pub fn revoke_claim_delegate(ctx: Context<SetClaimDelegate>) -> Result<()> {
+        instructions::claim_delegate::revoke_handler(ctx)
+    }
+
+    pub fn set_stake_label(ctx: Context<SetStakeLabel>, label: [u8; 32]) -> Result<()> {
+        instructions::stake_label::handler(ctx, label)
+    }
+
+    pub fn set_reward_destination(ctx: Context<SetRewardDestination>) -> Result<()> {
+        instructions::reward_destination::handler(ctx)
+    }
+
+    pub fn initialize_registry(ctx: Context<InitializeRegistry>) -> Result<()> {
+        instructions::pool_registry::initialize_registry_handler(ctx)
+    }
+
+    pub fn remove_from_registry(ctx: Context<RegistryControl>, stake_pool: Pubkey) -> Result<()> {
+        instructions::pool_registry::remove_from_registry_handler(ctx, stake_pool)
+    }
+
+    pub fn sync_vault(ctx: Context<SyncVault>, sweep: bool) -> Result<()> {
+        instructions::sync_vault::handler(ctx, sweep)
+    }
+
+    pub fn donate_to_stakers(ctx: Context<DonateToStakers>, amount: u64) -> Result<()> {
+        instructions::donate_to_stakers::handler(ctx, amount)
+    }
+
+    pub fn set_role(ctx: Context<AdminControl>, role: AdminRole, holder: Pubkey) -> Result<()> {
+        instructions::admin::set_role_handler(ctx, role, holder)
+    }
+
+    pub fn set_snapshot_interval(ctx: Context<AdminControl>, interval_secs: i64) -> Result<()> {
+        instructions::admin::set_snapshot_interval_handler(ctx, interval_secs)
+    }
+
+    pub fn take_snapshot(ctx: Context<TakeSnapshot>, epoch: u64) -> Result<()> {
+        instructions::snapshot::take_handler(ctx, epoch)
+    }
+
+    pub fn record_user_snapshot(ctx: Context<RecordUserSnapshot>, epoch: u64) -> Result<()> {
+        instructions::snapshot::record_user_handler(ctx, epoch)
+    }
+
+    pub fn set_oracle_config(ctx: Context<AdminControl>, config: OracleApyConfig) -> Result<()> {
+        instructions::admin::set_oracle_config_handler(ctx, config)
+    }
+
+    pub fn update_apy_from_oracle(ctx: Context<UpdateApyFromOracle>) -> Result<()> {
+        instructions::oracle_apy::handler(ctx)
+    }
+
+    pub fn transfer_stake_ownership(ctx: Context<TransferStakeOwnership>) -> Result<()> {
+        instructions::transfer_stake::handler(ctx)
+    }
+
+    pub fn set_recovery_address(
+        ctx: Context<SetRecoveryAddress>,
+        recovery_address: Pubkey,
+        delay_seconds: i64,
+    ) -> Result<()> {
+        instructions::recovery::set_handler(ctx, recovery_address, delay_seconds)
+    }
+
+    pub fn cancel_recovery(ctx: Context<SetRecoveryAddress>) -> Result<()> {
+        instructions::recovery::cancel_handler(ctx)
+    }
+
+    pub fn initiate_recovery(ctx: Context<InitiateRecovery>) -> Result<()> {
+        instructions::recovery::initiate_handler(ctx)
+    }
+
+    pub fn complete_recovery(ctx: Context<CompleteRecovery>) -> Result<()> {
+        instructions::recovery::complete_handler(ctx)
+    }
+
+    pub fn initialize_fee_vault(ctx: Context<InitializeFeeVault>) -> Result<()> {
+        instructions::claim_fee::initialize_fee_vault_handler(ctx)
+    }
+
+    pub fn collect_fees(ctx: Context<CollectFees>) -> Result<()> {
+        instructions::claim_fee::collect_fees_handler(ctx)
+    }
+
+    pub fn set_apy_boost(
+        ctx: Context<AdminControl>,
+        multiplier_bps: u16,
+        start_time: i64,
+        end_time: i64,
+    ) -> Result<()> {
+        instructions::admin::set_apy_boost_handler(ctx, multiplier_bps, start_time, end_time)
+    }
+
+    pub fn set_gc_inactivity(ctx: Context<AdminControl>, inactivity_secs: i64) -> Result<()> {
+        instructions::admin::set_gc_inactivity_handler(ctx, inactivity_secs)
+    }
+
+    pub fn gc_user_stake(ctx: Context<GcUserStake>) -> Result<()> {
+        instructions::gc_user_stake::handler(ctx)
+    }
+
+    pub fn set_min_claim_interval(ctx: Context<AdminControl>, interval_secs: i64) -> Result<()> {
+        instructions::admin::set_min_claim_interval_handler(ctx, interval_secs)
+    }
+
+    pub fn set_downgrade_after_lock(ctx: Context<AdminControl>, enabled: bool) -> Result<()> {
+        instructions::admin::set_downgrade_after_lock_handler(ctx, enabled)
+    }
+
+    pub fn set_whitelist_enabled(ctx: Context<AdminControl>, enabled: bool) -> Result<()> {
+        instructions::admin::set_whitelist_enabled_handler(ctx, enabled)
+    }
+
+    pub fn add_to_whitelist(ctx: Context<AddToWhitelist>, wallet: Pubkey) -> Result<()> {
+        instructions::whitelist::add_handler(ctx, wallet)
+    }
+
+    pub fn remove_from_whitelist(ctx: Context<RemoveFromWhitelist>) -> Result<()> {
+        instructions::whitelist::remove_handler(ctx)
+    }
 }
@@ -0,0 +1,256 @@
+//! Reward, fee and lock arithmetic on plain integers.
+//!
+//! Handlers and account methods gather the inputs; everything here is pure,
+//! so clients can link the crate and reproduce on-chain results exactly.
+
+use anchor_lang::prelude::*;
+
+use crate::constants::{BASIS_POINTS_DENOMINATOR, MAX_APY, PRECISION, SECONDS_PER_YEAR};
+use crate::error::StakingError;
+
+/// Rate weight `bps * seconds` as u128; non-positive durations contribute zero.
+pub fn bps_seconds(bps: u16, seconds: i64) -> Result<u128> {
+    if seconds <= 0 {
+        return Ok(0);
+    }
+    (bps as u128)
+        .checked_mul(seconds as u128)
+        .ok_or_else(|| error!(StakingError::MathOverflow))
+}
+
+/// APY rewards for `staked` tokens that accrued `rate_seconds` (the sum of
+/// `apy_bps * seconds` over the interval), plus `boosted` tokens earning
+/// `boost_seconds` on top:
+/// `(staked * rate_seconds + boosted * boost_seconds) / (BASIS_POINTS * SECONDS_PER_YEAR)`,
+/// rounded down.
+///
+/// The numerator is taken in u128. With every input at most u64::MAX tokens
+/// and a few times MAX_APY over decades, it cannot overflow; a result too
+/// large for u64 fails with `ConversionOverflow`.
+pub fn rewards_from_rate_seconds(
+    staked: u64,
+    rate_seconds: u128,
+    boosted: u64,
+    boost_seconds: u128,
+) -> Result<u64> {
+    let numerator = (staked as u128)
+        .checked_mul(rate_seconds)
+        .ok_or(StakingError::MathOverflow)?
+        .checked_add(
+            (boosted as u128)
+                .checked_mul(boost_seconds)
+                .ok_or(StakingError::MathOverflow)?,
+        )
+        .ok_or(StakingError::MathOverflow)?;
+
+    let denominator = (BASIS_POINTS_DENOMINATOR as u128)
+        .checked_mul(SECONDS_PER_YEAR as u128)
+        .ok_or(StakingError::MathOverflow)?;
+    require!(denominator > 0, StakingError::DivisionByZero);
+
+    let rewards = numerator
+        .checked_div(denominator)
+        .ok_or(StakingError::DivisionByZero)?;
+    u64::try_from(rewards).map_err(|_| error!(StakingError::ConversionOverflow))
+}
+
+/// Reward-per-token share of `staked` tokens for an accumulator increase of
+/// `per_token`: `staked * per_token / PRECISION`, rounded down.
+pub fn per_token_rewards(staked: u64, per_token: u128) -> Result<u64> {
+    let rewards = (staked as u128)
+        .checked_mul(per_token)
+        .ok_or(StakingError::MathOverflow)?
+        / PRECISION;
+    u64::try_from(rewards).map_err(|_| error!(StakingError::ConversionOverflow))
+}
+
+/// `(amount - fee, fee)` with the fee rounded down; used for claim fees,
+/// unstake fees and early-exit penalties.
+pub fn split_bps(amount: u64, fee_bps: u16) -> Result<(u64, u64)> {
+    let fee = (amount as u128)
+        .checked_mul(fee_bps as u128)
+        .ok_or(StakingError::MathOverflow)?
+        / BASIS_POINTS_DENOMINATOR as u128;
+    let fee = u64::try_from(fee).map_err(|_| StakingError::ConversionOverflow)?;
+    let net = amount.checked_sub(fee).ok_or(StakingError::MathUnderflow)?;
+    Ok((net, fee))
+}
+
+/// Amount-weighted mean of two lock ends, rounded up: `existing` tokens
+/// locked until `running_end` and `added` tokens until `deposit_end`.
+/// Negative ends count as 0; with no tokens at all the deposit end is kept.
+pub fn weighted_lock_end(running_end: i64, existing: u64, deposit_end: i64, added: u64) -> i64 {
+    let total = existing as u128 + added as u128;
+    if total == 0 {
+        return deposit_end;
+    }
+
+    let weighted =
+        running_end.max(0) as u128 * existing as u128 + deposit_end.max(0) as u128 * added as u128;
+    i64::try_from(weighted.div_ceil(total)).unwrap_or(i64::MAX)
+}
+
+/// APY that keeps `base_bps` at `reference_price` worth the same in quote
+/// terms at `price`: `base_bps * reference_price / price`, rounded down and
+/// kept within `[floor, MAX_APY]`. Both prices use the same decimals;
+/// `price` must be positive.
+pub fn oracle_apy(base_bps: u16, reference_price: u64, price: u64, floor: u16) -> u16 {
+    let scaled = base_bps as u128 * reference_price as u128 / price.max(1) as u128;
+    scaled.min(MAX_APY as u128).max(floor.min(MAX_APY) as u128) as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    const TEN_YEARS: i64 = 10 * SECONDS_PER_YEAR;
+
+    /// Straight u128 evaluation of `staked * apy * seconds / (BPS * YEAR)`.
+    fn reference(staked: u64, apy_bps: u16, seconds: i64) -> u128 {
+        staked as u128 * apy_bps as u128 * seconds.max(0) as u128
+            / (BASIS_POINTS_DENOMINATOR as u128 * SECONDS_PER_YEAR as u128)
+    }
+
+    fn simple(staked: u64, apy_bps: u16, seconds: i64) -> Result<u64> {
+        rewards_from_rate_seconds(staked, bps_seconds(apy_bps, seconds)?, 0, 0)
+    }
+
+    #[test]
+    fn max_stake_at_max_apy_for_ten_years_only_overflows_the_result() {
+        // u64::MAX at 20% for 10 years is twice u64::MAX: the u128 math
+        // holds, only the final conversion refuses
+        assert_eq!(
+            simple(u64::MAX, MAX_APY, TEN_YEARS).unwrap_err(),
+            error!(StakingError::ConversionOverflow)
+        );
+        assert_eq!(
+            simple(u64::MAX / 2, MAX_APY, TEN_YEARS).unwrap() as u128,
+            reference(u64::MAX / 2, MAX_APY, TEN_YEARS)
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn nothing_accrues_without_elapsed_time(
+            staked in any::<u64>(),
+            apy in 0..=MAX_APY,
+            seconds in i64::MIN..=0,
+        ) {
+            prop_assert_eq!(simple(staked, apy, seconds).unwrap(), 0);
+        }
+
+        #[test]
+        fn matches_the_u128_reference_or_reports_conversion_overflow(
+            staked in any::<u64>(),
+            apy in 0..=MAX_APY,
+            seconds in 0..=TEN_YEARS,
+        ) {
+            let expected = reference(staked, apy, seconds);
+            match simple(staked, apy, seconds) {
+                Ok(rewards) => prop_assert_eq!(rewards as u128, expected),
+                Err(err) => {
+                    prop_assert!(expected > u64::MAX as u128);
+                    prop_assert_eq!(err, error!(StakingError::ConversionOverflow));
+                }
+            }
+        }
+
+        #[test]
+        fn never_decreases_with_time(
+            staked in any::<u64>(),
+            apy in 0..=MAX_APY,
+            a in 0..=TEN_YEARS,
+            b in 0..=TEN_YEARS,
+        ) {
+            let (earlier, later) = (a.min(b), a.max(b));
+            if let Ok(later) = simple(staked, apy, later) {
+                prop_assert!(simple(staked, apy, earlier).unwrap() <= later);
+            }
+        }
+
+        #[test]
+        fn never_decreases_with_amount(
+            a in any::<u64>(),
+            b in any::<u64>(),
+            apy in 0..=MAX_APY,
+            seconds in 0..=TEN_YEARS,
+        ) {
+            let (smaller, larger) = (a.min(b), a.max(b));
+            if let Ok(larger) = simple(larger, apy, seconds) {
+                prop_assert!(simple(smaller, apy, seconds).unwrap() <= larger);
+            }
+        }
+
+        #[test]
+        fn boost_adds_the_boosted_share_exactly(
+            staked in any::<u64>(),
+            boosted in any::<u64>(),
+            rate in 0..=MAX_APY,
+            boost in 0..=MAX_APY,
+            seconds in 0..=TEN_YEARS,
+        ) {
+            let boosted = boosted.min(staked);
+            let expected = (staked as u128 * rate as u128 + boosted as u128 * boost as u128)
+                * seconds as u128
+                / (BASIS_POINTS_DENOMINATOR as u128 * SECONDS_PER_YEAR as u128);
+            let actual = rewards_from_rate_seconds(
+                staked,
+                bps_seconds(rate, seconds).unwrap(),
+                boosted,
+                bps_seconds(boost, seconds).unwrap(),
+            );
+            match actual {
+                Ok(rewards) => prop_assert_eq!(rewards as u128, expected),
+                Err(_) => prop_assert!(expected > u64::MAX as u128),
+            }
+        }
+
+        #[test]
+        fn per_token_share_rounds_down(staked in any::<u64>(), per_token in 0..=PRECISION * 1_000) {
+            let expected = staked as u128 * per_token / PRECISION;
+            match per_token_rewards(staked, per_token) {
+                Ok(rewards) => prop_assert_eq!(rewards as u128, expected),
+                Err(_) => prop_assert!(expected > u64::MAX as u128),
+            }
+        }
+
+        #[test]
+        fn oracle_apy_stays_in_bounds_and_falls_as_price_rises(
+            base in 0..=MAX_APY,
+            floor in 0..=MAX_APY,
+            reference in 1..=u64::MAX,
+            a in 1..=u64::MAX,
+            b in 1..=u64::MAX,
+        ) {
+            let (lower, higher) = (a.min(b), a.max(b));
+            let at_lower = oracle_apy(base, reference, lower, floor);
+            let at_higher = oracle_apy(base, reference, higher, floor);
+            prop_assert!(at_higher <= at_lower);
+            prop_assert!(at_higher >= floor && at_lower <= MAX_APY);
+            prop_assert_eq!(oracle_apy(base, reference, reference, 0), base);
+        }
+
+        #[test]
+        fn split_never_creates_or_loses_tokens(amount in any::<u64>(), bps in 0..=10_000u16) {
+            let (net, fee) = split_bps(amount, bps).unwrap();
+            prop_assert_eq!(net as u128 + fee as u128, amount as u128);
+            prop_assert!(fee as u128 * BASIS_POINTS_DENOMINATOR as u128 <= amount as u128 * bps as u128);
+        }
+
+        #[test]
+        fn weighted_end_stays_between_both_ends(
+            running_end in 0..=i64::MAX / 2,
+            span in 0..=TEN_YEARS,
+            existing in any::<u64>(),
+            added in any::<u64>(),
+        ) {
+            let deposit_end = running_end + span;
+            let end = weighted_lock_end(running_end, existing, deposit_end, added);
+            prop_assert!(end >= running_end && end <= deposit_end);
+            if added > 0 && span > 0 {
+                prop_assert!(end > running_end);
+            }
+        }
+    }
+}
@@ -0,0 +1,168 @@
+//! Reader for Pyth v2 price accounts.
+//!
+//! Only the fields `update_apy_from_oracle` needs are decoded: the aggregate
+//! price, confidence and status, the exponent and the publish time. Offsets
+//! follow the on-chain `PriceAccount` layout; the header is checked so a
+//! product or mapping account, or anything else, is refused.
+
+use anchor_lang::prelude::*;
+
+use crate::constants::BASIS_POINTS_DENOMINATOR;
+use crate::error::StakingError;
+
+pub const PYTH_MAGIC: u32 = 0xa1b2_c3d4;
+pub const PYTH_VERSION: u32 = 2;
+pub const PYTH_PRICE_ACCOUNT_TYPE: u32 = 3;
+pub const PYTH_STATUS_TRADING: u32 = 1;
+
+/// Decimals prices are normalized to before they are compared with the
+/// pool's `reference_price`.
+pub const PRICE_DECIMALS: i32 = 8;
+
+const MAGIC_OFFSET: usize = 0;
+const VERSION_OFFSET: usize = 4;
+const ACCOUNT_TYPE_OFFSET: usize = 8;
+const EXPO_OFFSET: usize = 20;
+const PUBLISH_TIME_OFFSET: usize = 96;
+const PRICE_OFFSET: usize = 208;
+const CONF_OFFSET: usize = 216;
+const STATUS_OFFSET: usize = 224;
+
+/// Bytes up to and including the aggregate price.
+pub const PYTH_PRICE_ACCOUNT_MIN_LEN: usize = 240;
+
+/// Aggregate price from a Pyth price account.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PythPrice {
+    pub price: i64,
+    pub conf: u64,
+    pub expo: i32,
+    pub publish_time: i64,
+    pub status: u32,
+}
+
+fn read<const N: usize>(data: &[u8], offset: usize) -> [u8; N] {
+    data[offset..offset + N].try_into().unwrap()
+}
+
+impl PythPrice {
+    /// Decode a price account; anything that is not a v2 price account fails
+    /// with `OraclePriceInvalid`.
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        require!(
+            data.len() >= PYTH_PRICE_ACCOUNT_MIN_LEN
+                && u32::from_le_bytes(read(data, MAGIC_OFFSET)) == PYTH_MAGIC
+                && u32::from_le_bytes(read(data, VERSION_OFFSET)) == PYTH_VERSION
+                && u32::from_le_bytes(read(data, ACCOUNT_TYPE_OFFSET)) == PYTH_PRICE_ACCOUNT_TYPE,
+            StakingError::OraclePriceInvalid
+        );
+
+        Ok(Self {
+            price: i64::from_le_bytes(read(data, PRICE_OFFSET)),
+            conf: u64::from_le_bytes(read(data, CONF_OFFSET)),
+            expo: i32::from_le_bytes(read(data, EXPO_OFFSET)),
+            publish_time: i64::from_le_bytes(read(data, PUBLISH_TIME_OFFSET)),
+            status: u32::from_le_bytes(read(data, STATUS_OFFSET)),
+        })
+    }
+
+    /// `(price, conf)` rescaled to `PRICE_DECIMALS`. Only positive prices
+    /// from a trading feed are usable.
+    pub fn normalized(&self) -> Result<(u64, u64)> {
+        require!(
+            self.status == PYTH_STATUS_TRADING && self.price > 0,
+            StakingError::OraclePriceInvalid
+        );
+        let shift = self.expo.saturating_add(PRICE_DECIMALS);
+        require!(shift.abs() <= 18, StakingError::OraclePriceInvalid);
+
+        let scale = 10u128.pow(shift.unsigned_abs());
+        let rescale = |value: u64| -> Result<u64> {
+            let value = if shift >= 0 {
+                value as u128 * scale
+            } else {
+                value as u128 / scale
+            };
+            u64::try_from(value).map_err(|_| error!(StakingError::ConversionOverflow))
+        };
+        let price = rescale(self.price as u64)?;
+        require!(price > 0, StakingError::OraclePriceInvalid);
+        Ok((price, rescale(self.conf)?))
+    }
+
+    /// `normalized`, after refusing a price published more than
+    /// `max_staleness_secs` before `now` or with a confidence interval wider
+    /// than `max_confidence_bps` of the price.
+    pub fn checked(&self, now: i64, max_staleness_secs: i64, max_confidence_bps: u16) -> Result<(u64, u64)> {
+        require!(
+            now.saturating_sub(self.publish_time) <= max_staleness_secs,
+            StakingError::OracleStale
+        );
+        let (price, conf) = self.normalized()?;
+        require!(
+            conf as u128 * BASIS_POINTS_DENOMINATOR as u128 <= price as u128 * max_confidence_bps as u128,
+            StakingError::OracleConfidenceTooWide
+        );
+        Ok((price, conf))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account(price: i64, conf: u64, expo: i32, publish_time: i64, status: u32) -> Vec<u8> {
+        let mut data = vec![0u8; PYTH_PRICE_ACCOUNT_MIN_LEN];
+        data[MAGIC_OFFSET..4].copy_from_slice(&PYTH_MAGIC.to_le_bytes());
+        data[VERSION_OFFSET..8].copy_from_slice(&PYTH_VERSION.to_le_bytes());
+        data[ACCOUNT_TYPE_OFFSET..12].copy_from_slice(&PYTH_PRICE_ACCOUNT_TYPE.to_le_bytes());
+        data[EXPO_OFFSET..24].copy_from_slice(&expo.to_le_bytes());
+        data[PUBLISH_TIME_OFFSET..104].copy_from_slice(&publish_time.to_le_bytes());
+        data[PRICE_OFFSET..216].copy_from_slice(&price.to_le_bytes());
+        data[CONF_OFFSET..224].copy_from_slice(&conf.to_le_bytes());
+        data[STATUS_OFFSET..228].copy_from_slice(&status.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn parses_and_normalizes_the_aggregate_price() {
+        let parsed = PythPrice::parse(&account(12_345, 67, -5, 1_000, PYTH_STATUS_TRADING)).unwrap();
+        assert_eq!(parsed.publish_time, 1_000);
+        // 0.12345 with 5 decimals is 12_345_000 with 8
+        assert_eq!(parsed.normalized().unwrap(), (12_345_000, 67_000));
+
+        let coarse = PythPrice::parse(&account(2_000_000_000, 10, -10, 0, PYTH_STATUS_TRADING)).unwrap();
+        assert_eq!(coarse.normalized().unwrap(), (20_000_000, 0));
+    }
+
+    #[test]
+    fn rejects_other_accounts_and_unusable_prices() {
+        let mut wrong_type = account(1, 0, -8, 0, PYTH_STATUS_TRADING);
+        wrong_type[ACCOUNT_TYPE_OFFSET] = 2;
+        assert!(PythPrice::parse(&wrong_type).is_err());
+        assert!(PythPrice::parse(&account(1, 0, -8, 0, 1)[..200]).is_err());
+
+        for (price, status) in [(0, PYTH_STATUS_TRADING), (-5, PYTH_STATUS_TRADING), (100, 0)] {
+            let parsed = PythPrice::parse(&account(price, 0, -8, 0, status)).unwrap();
+            assert_eq!(parsed.normalized().unwrap_err(), error!(StakingError::OraclePriceInvalid));
+        }
+        // Rounds to zero at 8 decimals
+        let dust = PythPrice::parse(&account(5, 0, -12, 0, PYTH_STATUS_TRADING)).unwrap();
+        assert!(dust.normalized().is_err());
+    }
+
+    #[test]
+    fn refuses_stale_and_wide_confidence_prices() {
+        // $1.00 with a 1% confidence interval, published at t = 1_000
+        let quote = PythPrice::parse(&account(100_000_000, 1_000_000, -8, 1_000, PYTH_STATUS_TRADING)).unwrap();
+
+        assert_eq!(quote.checked(1_060, 60, 100).unwrap(), (100_000_000, 1_000_000));
+        assert_eq!(quote.checked(1_061, 60, 100).unwrap_err(), error!(StakingError::OracleStale));
+        assert_eq!(
+            quote.checked(1_000, 60, 99).unwrap_err(),
+            error!(StakingError::OracleConfidenceTooWide)
+        );
+        // A publish time ahead of the validator clock is not stale
+        assert!(quote.checked(900, 60, 100).is_ok());
+    }
+}
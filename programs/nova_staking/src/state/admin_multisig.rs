@@ -0,0 +1,158 @@
+use anchor_lang::prelude::borsh;
+use anchor_lang::prelude::*;
+
+use crate::constants::MAX_MULTISIG_SIGNERS;
+
+/// Signer set that replaces the single authority key for the actions in
+/// `AdminAction` once `create_admin_multisig` has run.
+#[account]
+pub struct AdminMultisig {
+    pub stake_pool: Pubkey,
+
+    /// First `signer_count` entries are used; the rest stay default.
+    pub signers: [Pubkey; MAX_MULTISIG_SIGNERS],
+    pub signer_count: u8,
+    /// Approvals needed before a proposal executes.
+    pub threshold: u8,
+    /// How long a proposal can collect approvals.
+    pub proposal_ttl_seconds: i64,
+    /// Nonce of the next proposal; part of its PDA seeds.
+    pub proposal_count: u64,
+
+    pub bump: u8,
+}
+
+impl AdminMultisig {
+    pub const LEN: usize = 8 + 32 + (32 * MAX_MULTISIG_SIGNERS) + 1 + 1 + 8 + 8 + 1;
+
+    /// Slot of `key` in the signer set, if it is a signer.
+    pub fn signer_index(&self, key: &Pubkey) -> Option<usize> {
+        self.signers[..self.signer_count as usize]
+            .iter()
+            .position(|signer| signer == key)
+    }
+}
+
+/// Admin change carried by a proposal; applied exactly as the single-key
+/// instruction of the same name would.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AdminAction {
+    /// Replace `pause_flags`, as `set_pause_flags`.
+    SetPaused { flags: u8 },
+    /// As `adjust_apy`.
+    AdjustApy {
+        flex_apy: u16,
+        core_apy: u16,
+        prime_apy: u16,
+    },
+    /// As `update_emission_cap`.
+    UpdateCap { new_cap: u64 },
+    /// As `propose_authority`; the new key still has to accept.
+    TransferAuthority { new_authority: Pubkey },
+    /// As `set_apy_decay`.
+    SetApyDecay {
+        interval_secs: i64,
+        bps_per_interval: u16,
+        min_apy_floor: u16,
+    },
+    /// As `set_reward_rate`.
+    SetRewardRate { reward_rate_per_second: u64 },
+    /// As `set_apy_boost`.
+    SetApyBoost {
+        multiplier_bps: u16,
+        start_time: i64,
+        end_time: i64,
+    },
+    /// As `set_emergency` when `enabled`, `clear_emergency` otherwise.
+    SetEmergency { enabled: bool },
+    /// As `set_tier_paused`.
+    SetTierPaused { tier: u8, paused: bool },
+    /// As `set_permanent_apy`.
+    SetPermanentApy { permanent_apy: u16 },
+    /// As `cancel_pending_params`.
+    CancelPendingParams,
+    /// As `set_rewards_end_time`.
+    SetRewardsEndTime { end_time: i64 },
+}
+
+/// One proposed `AdminAction` and the approvals it has collected.
+#[account]
+pub struct AdminProposal {
+    pub multisig: Pubkey,
+    pub nonce: u64,
+    pub proposer: Pubkey,
+    pub action: AdminAction,
+
+    /// Bit `i` is set once `signers[i]` has approved.
+    pub approvals: u16,
+    pub created_at: i64,
+    pub expires_at: i64,
+    /// Set when the threshold is reached; no further approvals are accepted.
+    pub executed: bool,
+
+    pub bump: u8,
+}
+
+impl AdminProposal {
+    /// Largest `AdminAction` variant is `TransferAuthority`.
+    pub const LEN: usize = 8 + 32 + 8 + 32 + (1 + 32) + 2 + 8 + 8 + 1 + 1;
+
+    pub fn approval_count(&self) -> u8 {
+        self.approvals.count_ones() as u8
+    }
+
+    pub fn has_approved(&self, index: usize) -> bool {
+        self.approvals & (1 << index) != 0
+    }
+
+    pub fn is_expired(&self, now: i64) -> bool {
+        now >= self.expires_at
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn multisig(signers: &[Pubkey]) -> AdminMultisig {
+        let mut multisig =
+            AdminMultisig::try_deserialize_unchecked(&mut &[0u8; AdminMultisig::LEN][..]).unwrap();
+        multisig.signers[..signers.len()].copy_from_slice(signers);
+        multisig.signer_count = signers.len() as u8;
+        multisig
+    }
+
+    #[test]
+    fn signer_index_ignores_unused_slots() {
+        let a = Pubkey::new_unique();
+        let b = Pubkey::new_unique();
+        let multisig = multisig(&[a, b]);
+        assert_eq!(multisig.signer_index(&a), Some(0));
+        assert_eq!(multisig.signer_index(&b), Some(1));
+        assert_eq!(multisig.signer_index(&Pubkey::new_unique()), None);
+        // Unused slots hold the default key, which is never a signer
+        assert_eq!(multisig.signer_index(&Pubkey::default()), None);
+    }
+
+    #[test]
+    fn approvals_count_each_signer_once() {
+        let mut proposal =
+            AdminProposal::try_deserialize_unchecked(&mut &[0u8; AdminProposal::LEN][..]).unwrap();
+        for index in [3, 0, 3] {
+            proposal.approvals |= 1 << index;
+        }
+        assert_eq!(proposal.approval_count(), 2);
+        assert!(proposal.has_approved(0));
+        assert!(!proposal.has_approved(1));
+        assert!(proposal.has_approved(3));
+    }
+
+    #[test]
+    fn proposal_expires_at_its_deadline() {
+        let mut proposal =
+            AdminProposal::try_deserialize_unchecked(&mut &[0u8; AdminProposal::LEN][..]).unwrap();
+        proposal.expires_at = 100;
+        assert!(!proposal.is_expired(99));
+        assert!(proposal.is_expired(100));
+    }
+}
@@ -0,0 +1,132 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::{CLAIM_HISTORY_LEN, CLAIM_HISTORY_PAGE_SIZE};
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ClaimRecord {
+    pub timestamp: i64,
+    pub amount: u64,
+    pub running_total: u64,
+}
+
+#[account]
+pub struct ClaimHistory {
+    pub owner: Pubkey,
+    pub stake_pool: Pubkey,
+
+    /// Slot the next record is written to.
+    pub head: u16,
+    /// Records currently held (at most CLAIM_HISTORY_LEN).
+    pub count: u16,
+    /// Claims recorded since the history was enabled, including overwritten ones.
+    pub total_recorded: u64,
+
+    pub bump: u8,
+
+    pub entries: [ClaimRecord; CLAIM_HISTORY_LEN],
+}
+
+/// One page of `view_claim_history`, oldest record first.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct ClaimHistoryPage {
+    pub page: u16,
+    pub total_recorded: u64,
+    pub entries: Vec<ClaimRecord>,
+}
+
+impl ClaimHistory {
+    pub const LEN: usize = 8 + 32 + 32 + 2 + 2 + 8 + 1 + (24 * CLAIM_HISTORY_LEN);
+
+    /// Append a record, overwriting the oldest once the ring is full.
+    pub fn push(&mut self, record: ClaimRecord) {
+        self.entries[self.head as usize] = record;
+        self.head = ((self.head as usize + 1) % CLAIM_HISTORY_LEN) as u16;
+        self.count = (self.count as usize + 1).min(CLAIM_HISTORY_LEN) as u16;
+        self.total_recorded = self.total_recorded.saturating_add(1);
+    }
+
+    /// Record at `index` in chronological order (0 = oldest held).
+    pub fn get(&self, index: usize) -> Option<&ClaimRecord> {
+        if index >= self.count as usize {
+            return None;
+        }
+        let oldest = if (self.count as usize) < CLAIM_HISTORY_LEN {
+            0
+        } else {
+            self.head as usize
+        };
+        Some(&self.entries[(oldest + index) % CLAIM_HISTORY_LEN])
+    }
+
+    pub fn page(&self, page: u16) -> ClaimHistoryPage {
+        let first = page as usize * CLAIM_HISTORY_PAGE_SIZE;
+        ClaimHistoryPage {
+            page,
+            total_recorded: self.total_recorded,
+            entries: (first..first + CLAIM_HISTORY_PAGE_SIZE)
+                .map_while(|i| self.get(i).copied())
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(n: u64) -> ClaimRecord {
+        ClaimRecord {
+            timestamp: n as i64,
+            amount: n,
+            running_total: n * (n + 1) / 2,
+        }
+    }
+
+    fn empty_history() -> ClaimHistory {
+        ClaimHistory::try_deserialize_unchecked(&mut &[0u8; ClaimHistory::LEN][..]).unwrap()
+    }
+
+    #[test]
+    fn appends_in_order() {
+        let mut history = empty_history();
+        for n in 1..=3 {
+            history.push(record(n));
+        }
+        assert_eq!(history.count, 3);
+        assert_eq!(history.get(0), Some(&record(1)));
+        assert_eq!(history.get(2), Some(&record(3)));
+        assert_eq!(history.get(3), None);
+    }
+
+    #[test]
+    fn wraps_after_capacity() {
+        let mut history = empty_history();
+        let total = CLAIM_HISTORY_LEN as u64 + 5;
+        for n in 1..=total {
+            history.push(record(n));
+        }
+
+        assert_eq!(history.count as usize, CLAIM_HISTORY_LEN);
+        assert_eq!(history.total_recorded, total);
+        // Oldest five were overwritten
+        assert_eq!(history.get(0), Some(&record(6)));
+        assert_eq!(history.get(CLAIM_HISTORY_LEN - 1), Some(&record(total)));
+    }
+
+    #[test]
+    fn pages_cover_the_ring_oldest_first() {
+        let mut history = empty_history();
+        for n in 1..=(CLAIM_HISTORY_LEN as u64 + 1) {
+            history.push(record(n));
+        }
+
+        let first = history.page(0);
+        assert_eq!(first.entries.len(), CLAIM_HISTORY_PAGE_SIZE);
+        assert_eq!(first.entries[0], record(2));
+
+        let last_page = (CLAIM_HISTORY_LEN / CLAIM_HISTORY_PAGE_SIZE - 1) as u16;
+        let last = history.page(last_page);
+        assert_eq!(last.entries.last(), Some(&record(CLAIM_HISTORY_LEN as u64 + 1)));
+        assert!(history.page(last_page + 1).entries.is_empty());
+    }
+}
@@ -1,5 +1,21 @@
+pub mod admin_multisig;
+pub mod claim_history;
+pub mod pool_registry;
+pub mod position_history;
+pub mod referral_account;
+pub mod snapshot;
+pub mod staker_record;
 pub mod stake_pool;
 pub mod user_stake;
+pub mod whitelist_entry;
 
+pub use admin_multisig::*;
+pub use claim_history::*;
+pub use pool_registry::*;
+pub use position_history::*;
+pub use referral_account::*;
+pub use snapshot::*;
+pub use staker_record::*;
 pub use stake_pool::*;
 pub use user_stake::*;
+pub use whitelist_entry::*;
@@ -0,0 +1,145 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::{MAX_REGISTRY_ENTRIES, REGISTRY_GROWTH, REGISTRY_INITIAL_CAPACITY};
+use crate::error::StakingError;
+
+/// One registered pool.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct RegistryEntry {
+    pub stake_pool: Pubkey,
+    pub staking_mint: Pubkey,
+}
+
+impl RegistryEntry {
+    pub const LEN: usize = 32 + 32;
+}
+
+/// Singleton list of every pool created through `initialize`, so clients can
+/// enumerate pools without knowing their mints.
+#[account]
+pub struct PoolRegistry {
+    /// May remove decommissioned pools.
+    pub authority: Pubkey,
+
+    /// Entries the account currently has room for; grows by
+    /// `REGISTRY_GROWTH` up to `MAX_REGISTRY_ENTRIES`.
+    pub capacity: u32,
+    pub entries: Vec<RegistryEntry>,
+
+    pub bump: u8,
+}
+
+impl PoolRegistry {
+    /// Account size with room for `capacity` entries.
+    pub const fn space_for(capacity: u32) -> usize {
+        8 + 32 + 4 + (4 + RegistryEntry::LEN * capacity as usize) + 1
+    }
+
+    pub const INITIAL_LEN: usize = Self::space_for(REGISTRY_INITIAL_CAPACITY);
+
+    /// Capacity needed to push one more entry. Stays at the limit once there,
+    /// so the push itself reports `RegistryFull`.
+    pub fn capacity_after_push(&self) -> u32 {
+        if (self.entries.len() as u32) < self.capacity {
+            self.capacity
+        } else {
+            self.capacity
+                .saturating_add(REGISTRY_GROWTH)
+                .min(MAX_REGISTRY_ENTRIES)
+        }
+    }
+
+    /// Append a pool. The account must already be sized for
+    /// `capacity_after_push`.
+    pub fn register(&mut self, stake_pool: Pubkey, staking_mint: Pubkey) -> Result<()> {
+        require!(
+            !self.entries.iter().any(|e| e.stake_pool == stake_pool),
+            StakingError::PoolAlreadyRegistered
+        );
+        if self.entries.len() as u32 >= self.capacity {
+            let grown = self.capacity_after_push();
+            require!(grown > self.capacity, StakingError::RegistryFull);
+            self.capacity = grown;
+        }
+        self.entries.push(RegistryEntry {
+            stake_pool,
+            staking_mint,
+        });
+        Ok(())
+    }
+
+    /// Drop a pool, keeping the order of the rest. Capacity is kept for
+    /// future pools.
+    pub fn remove(&mut self, stake_pool: &Pubkey) -> Result<RegistryEntry> {
+        let index = self
+            .entries
+            .iter()
+            .position(|e| e.stake_pool == *stake_pool)
+            .ok_or(StakingError::PoolNotRegistered)?;
+        Ok(self.entries.remove(index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry(capacity: u32) -> PoolRegistry {
+        PoolRegistry {
+            authority: Pubkey::default(),
+            capacity,
+            entries: Vec::new(),
+            bump: 0,
+        }
+    }
+
+    #[test]
+    fn grows_only_when_full() {
+        let mut reg = registry(REGISTRY_INITIAL_CAPACITY);
+        for _ in 0..REGISTRY_INITIAL_CAPACITY {
+            assert_eq!(reg.capacity_after_push(), REGISTRY_INITIAL_CAPACITY);
+            reg.register(Pubkey::new_unique(), Pubkey::new_unique()).unwrap();
+        }
+        assert_eq!(
+            reg.capacity_after_push(),
+            REGISTRY_INITIAL_CAPACITY + REGISTRY_GROWTH
+        );
+        reg.register(Pubkey::new_unique(), Pubkey::new_unique()).unwrap();
+        assert_eq!(reg.capacity, REGISTRY_INITIAL_CAPACITY + REGISTRY_GROWTH);
+        assert_eq!(reg.entries.len() as u32, REGISTRY_INITIAL_CAPACITY + 1);
+    }
+
+    #[test]
+    fn serialized_size_fits_capacity() {
+        let mut reg = registry(0);
+        for _ in 0..REGISTRY_INITIAL_CAPACITY + 1 {
+            reg.register(Pubkey::new_unique(), Pubkey::new_unique()).unwrap();
+        }
+        let data = reg.try_to_vec().unwrap();
+        assert!(8 + data.len() <= PoolRegistry::space_for(reg.capacity));
+    }
+
+    #[test]
+    fn full_registry_rejects_new_pools() {
+        let mut reg = registry(MAX_REGISTRY_ENTRIES);
+        reg.entries = vec![RegistryEntry::default(); MAX_REGISTRY_ENTRIES as usize];
+        assert_eq!(reg.capacity_after_push(), MAX_REGISTRY_ENTRIES);
+        assert!(reg.register(Pubkey::new_unique(), Pubkey::new_unique()).is_err());
+    }
+
+    #[test]
+    fn duplicates_and_unknown_pools_are_rejected() {
+        let mut reg = registry(REGISTRY_INITIAL_CAPACITY);
+        let (a, b, c) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        reg.register(a, Pubkey::new_unique()).unwrap();
+        reg.register(b, Pubkey::new_unique()).unwrap();
+        reg.register(c, Pubkey::new_unique()).unwrap();
+        assert!(reg.register(a, Pubkey::new_unique()).is_err());
+
+        assert_eq!(reg.remove(&b).unwrap().stake_pool, b);
+        assert!(reg.remove(&b).is_err());
+        let left: Vec<Pubkey> = reg.entries.iter().map(|e| e.stake_pool).collect();
+        assert_eq!(left, vec![a, c]);
+        assert_eq!(reg.capacity, REGISTRY_INITIAL_CAPACITY);
+    }
+}
@@ -0,0 +1,135 @@
+use anchor_lang::prelude::borsh;
+use anchor_lang::prelude::*;
+
+use crate::constants::{POSITION_HISTORY_LEN, POSITION_HISTORY_PAGE_SIZE};
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PositionEventKind {
+    Stake,
+    Unstake,
+    ChangeTier,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PositionRecord {
+    pub timestamp: i64,
+    pub kind: PositionEventKind,
+    /// Tier after the event.
+    pub tier: u8,
+    /// Principal moved (zero for tier changes).
+    pub amount: u64,
+    /// Staked amount after the event.
+    pub staked_after: u64,
+}
+
+#[account]
+pub struct PositionHistory {
+    pub owner: Pubkey,
+    pub stake_pool: Pubkey,
+
+    /// Slot the next record is written to.
+    pub head: u16,
+    /// Records currently held (at most POSITION_HISTORY_LEN).
+    pub count: u16,
+    /// Events recorded since the history was enabled, including overwritten ones.
+    pub total_recorded: u64,
+
+    pub bump: u8,
+
+    pub entries: [PositionRecord; POSITION_HISTORY_LEN],
+}
+
+/// One page of `view_position_history`, oldest record first.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct PositionHistoryPage {
+    pub page: u16,
+    pub total_recorded: u64,
+    pub entries: Vec<PositionRecord>,
+}
+
+impl PositionHistory {
+    pub const LEN: usize = 8 + 32 + 32 + 2 + 2 + 8 + 1 + (26 * POSITION_HISTORY_LEN);
+
+    /// Append a record, overwriting the oldest once the ring is full.
+    pub fn push(&mut self, record: PositionRecord) {
+        self.entries[self.head as usize] = record;
+        self.head = ((self.head as usize + 1) % POSITION_HISTORY_LEN) as u16;
+        self.count = (self.count as usize + 1).min(POSITION_HISTORY_LEN) as u16;
+        self.total_recorded = self.total_recorded.saturating_add(1);
+    }
+
+    /// Record at `index` in chronological order (0 = oldest held).
+    pub fn get(&self, index: usize) -> Option<&PositionRecord> {
+        if index >= self.count as usize {
+            return None;
+        }
+        let oldest = if (self.count as usize) < POSITION_HISTORY_LEN {
+            0
+        } else {
+            self.head as usize
+        };
+        Some(&self.entries[(oldest + index) % POSITION_HISTORY_LEN])
+    }
+
+    pub fn page(&self, page: u16) -> PositionHistoryPage {
+        let first = page as usize * POSITION_HISTORY_PAGE_SIZE;
+        PositionHistoryPage {
+            page,
+            total_recorded: self.total_recorded,
+            entries: (first..first + POSITION_HISTORY_PAGE_SIZE)
+                .map_while(|i| self.get(i).copied())
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(n: u64) -> PositionRecord {
+        PositionRecord {
+            timestamp: n as i64,
+            kind: if n.is_multiple_of(2) { PositionEventKind::Unstake } else { PositionEventKind::Stake },
+            tier: 0,
+            amount: n,
+            staked_after: n,
+        }
+    }
+
+    fn empty_history() -> PositionHistory {
+        PositionHistory::try_deserialize_unchecked(&mut &[0u8; PositionHistory::LEN][..]).unwrap()
+    }
+
+    #[test]
+    fn wraps_after_capacity() {
+        let mut history = empty_history();
+        let total = POSITION_HISTORY_LEN as u64 * 2 + 3;
+        for n in 1..=total {
+            history.push(record(n));
+        }
+
+        assert_eq!(history.count as usize, POSITION_HISTORY_LEN);
+        assert_eq!(history.total_recorded, total);
+        assert_eq!(history.get(0), Some(&record(total - POSITION_HISTORY_LEN as u64 + 1)));
+        assert_eq!(history.get(POSITION_HISTORY_LEN - 1), Some(&record(total)));
+        assert_eq!(history.get(POSITION_HISTORY_LEN), None);
+    }
+
+    #[test]
+    fn pages_stay_in_order_across_the_wrap() {
+        let mut history = empty_history();
+        for n in 1..=(POSITION_HISTORY_LEN as u64 + 10) {
+            history.push(record(n));
+        }
+
+        let mut previous = 0;
+        for page in 0..(POSITION_HISTORY_LEN / POSITION_HISTORY_PAGE_SIZE) as u16 {
+            for entry in history.page(page).entries {
+                assert!(entry.timestamp > previous);
+                previous = entry.timestamp;
+            }
+        }
+        assert_eq!(previous, POSITION_HISTORY_LEN as i64 + 10);
+    }
+}
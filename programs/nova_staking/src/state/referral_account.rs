@@ -0,0 +1,19 @@
+use anchor_lang::prelude::*;
+
+#[account]
+pub struct ReferralAccount {
+    pub referrer: Pubkey,
+    pub stake_pool: Pubkey,
+
+    /// Total referral share credited to this referrer across all referees.
+    pub lifetime_earnings: u64,
+
+    pub bump: u8,
+
+    /// Credited share not yet withdrawn through `claim_referral_rewards`.
+    pub unclaimed: u64,
+}
+
+impl ReferralAccount {
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 1 + 8;
+}
@@ -0,0 +1,39 @@
+use anchor_lang::prelude::*;
+
+/// Pool totals at one snapshot epoch, written by `take_snapshot`.
+#[account]
+pub struct Snapshot {
+    pub stake_pool: Pubkey,
+    /// Index of this snapshot; the pool's `snapshot_count` when it was taken.
+    pub epoch: u64,
+    pub total_staked: u64,
+    pub tier_staked: [u64; 4],
+    pub staker_count: u64,
+    pub timestamp: i64,
+    pub bump: u8,
+}
+
+impl Snapshot {
+    pub const LEN: usize = 8 + 32 + 8 + 8 + (8 * 4) + 8 + 8 + 1;
+}
+
+/// One position's stake against a snapshot epoch, written by
+/// `record_user_snapshot`.
+#[account]
+pub struct UserSnapshot {
+    pub stake_pool: Pubkey,
+    pub epoch: u64,
+    pub owner: Pubkey,
+    /// Staked amount when recorded. No deposit may have landed since the
+    /// snapshot, though rewards compounded since then are included.
+    pub staked_amount: u64,
+    pub tier: u8,
+    pub stake_start_time: i64,
+    /// When the record was written.
+    pub timestamp: i64,
+    pub bump: u8,
+}
+
+impl UserSnapshot {
+    pub const LEN: usize = 8 + 32 + 8 + 32 + 8 + 1 + 8 + 8 + 1;
+}
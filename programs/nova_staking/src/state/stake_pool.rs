@@ -1,4 +1,127 @@
+use anchor_lang::prelude::borsh;
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::sysvar::instructions::{
+    load_current_index_checked, load_instruction_at_checked,
+};
+
+use crate::constants::{
+    BASIS_POINTS_DENOMINATOR, MAX_APY, MAX_APY_BOOSTS, MAX_APY_BOOST_DURATION,
+    MAX_APY_BOOST_MULTIPLIER_BPS, MAX_APY_CHECKPOINTS, MAX_LOYALTY_LEVELS, MAX_YIELD_ADAPTERS,
+    PRECISION, RATE_SETTER_WINDOW, SECONDS_PER_DAY, SECONDS_PER_YEAR, STAKE_POOL_VERSION,
+};
+use crate::error::StakingError;
+use crate::instructions::stake::TopUpLockPolicy;
+use crate::math::{bps_seconds, split_bps};
+use crate::tier::Tier;
+
+/// Where unstake fees (and other principal penalties) end up.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PenaltyDestination {
+    /// Moved to the treasury vault, funding future rewards.
+    Treasury,
+    /// Burned from the staking vault, reducing supply.
+    Burn,
+    /// Nowhere: a dual-token pool whose staking mint has a freeze
+    /// authority can neither burn nor use its treasury, so it charges no
+    /// unstake fees or early unstake penalties.
+    Unset,
+}
+
+/// How a pool accrues rewards; fixed at `initialize`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RewardMode {
+    /// Each position earns its tier APY on its own stake.
+    Apy,
+    /// `reward_rate_per_second` is shared pro-rata by all staked tokens
+    /// through a global reward-per-token accumulator; tier APYs are unused.
+    RewardPerToken,
+}
+
+/// Narrow admin powers the authority can hand to other keys with `set_role`.
+/// The authority itself always holds every role.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AdminRole {
+    /// `set_paused`, `set_pause_flags` and `set_tier_paused`.
+    Pauser,
+    /// `adjust_apy`, moving each tier by at most `MAX_RATE_SETTER_APY_DELTA`
+    /// per `RATE_SETTER_WINDOW`.
+    RateSetter,
+}
+
+/// Tier APYs and decay schedule that were in force until `replaced_at`, kept
+/// so an APY change never reprices time that already elapsed.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, Debug)]
+pub struct ApyCheckpoint {
+    pub replaced_at: i64,
+    /// Basis points indexed by tier (Flex, Core, Prime, Permanent).
+    pub apys: [u16; 4],
+    pub decay_interval_seconds: i64,
+    pub decay_bps_per_interval: u16,
+    pub min_apy_floor: u16,
+}
+
+impl ApyCheckpoint {
+    pub const LEN: usize = 8 + (2 * 4) + 8 + 2 + 2;
+}
+
+/// A time-limited multiplier on the tier APYs over `[start_time, end_time)`,
+/// e.g. 20_000 for a double-rewards weekend.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct ApyBoost {
+    /// Basis points of the base APY paid while the boost runs (10_000 = 1x)
+    pub multiplier_bps: u16,
+    pub start_time: i64,
+    pub end_time: i64,
+}
+
+impl ApyBoost {
+    pub const LEN: usize = 2 + 8 + 8;
+
+    /// Basis points added on top of the base APY.
+    pub fn extra_bps(&self) -> u16 {
+        self.multiplier_bps.saturating_sub(BASIS_POINTS_DENOMINATOR as u16)
+    }
+}
+
+/// Parameters for `update_apy_from_oracle`; a default `price_account` means
+/// oracle mode is off.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct OracleApyConfig {
+    /// Pyth price account of the staking token
+    pub price_account: Pubkey,
+    /// Price, at `oracle::PRICE_DECIMALS`, at which tiers earn `base_apys`
+    pub reference_price: u64,
+    /// Flex, Core and Prime APY at `reference_price`, in basis points
+    pub base_apys: [u16; 3],
+    /// Lowest APY the oracle may set, however high the price
+    pub min_apy: u16,
+    /// Oldest publish time accepted, in seconds before now
+    pub max_staleness_secs: i64,
+    /// Widest confidence interval accepted, in basis points of the price
+    pub max_confidence_bps: u16,
+    /// Minimum spacing of updates; each one uses an APY checkpoint
+    pub update_interval_secs: i64,
+}
+
+impl OracleApyConfig {
+    pub const LEN: usize = 32 + 8 + (2 * 3) + 2 + 8 + 2 + 8;
+
+    pub fn is_enabled(&self) -> bool {
+        self.price_account != Pubkey::default()
+    }
+}
+
+/// `base` reduced by `step` for every whole `interval` in `elapsed`, never
+/// going below `floor` (or `base`, if that is already lower).
+pub fn decayed_apy(base: u16, elapsed: i64, interval: i64, step: u16, floor: u16) -> u16 {
+    if interval <= 0 || step == 0 || elapsed <= 0 {
+        return base;
+    }
+    let cut = (elapsed / interval).saturating_mul(step as i64);
+    (base as i64)
+        .saturating_sub(cut)
+        .max(floor.min(base) as i64) as u16
+}
 
 #[account]
 pub struct StakePool {
@@ -16,7 +139,8 @@ pub struct StakePool {
     pub total_staked: u64,
     pub staker_count: u64,
 
-    pub paused: bool,
+    /// Bitfield of paused actions; see `PAUSE_*_BIT`
+    pub pause_flags: u8,
 
     pub last_updated: i64,
     pub created_at: i64,
@@ -24,23 +148,2681 @@ pub struct StakePool {
     pub vault_bump: u8,
     pub treasury_bump: u8,
     pub bump: u8,
+
+    pub accrue_only_when_funded: bool,
+    /// Start of the open empty-treasury window
+    pub treasury_empty_since: Option<i64>,
+    /// Seconds in empty-treasury windows that have closed, the latest at
+    /// `last_empty_window_end`; see `unfunded_seconds_at`
+    pub cumulative_unfunded_secs: i64,
+    pub last_empty_window_end: i64,
+
+    pub max_accrual_horizon_secs: i64,
+
+    pub max_daily_inflow: u64,
+    pub inflow_window_start: i64,
+    pub inflow_in_window: u64,
+
+    pub emergency: bool,
+
+    pub allowed_topup_policies: u8,
+
+    pub relock_bonus_bps: u16,
+    pub relock_grace_secs: i64,
+
+    pub early_bird_slots: u64,
+    pub early_bird_slots_used: u64,
+    pub early_bird_bonus_bps: u16,
+    pub early_bird_duration_secs: i64,
+
+    pub streak_step_bps: u16,
+    pub streak_cap_bps: u16,
+    pub streak_window_secs: i64,
+
+    pub core_cliff_secs: i64,
+    pub core_linear_secs: i64,
+    pub prime_cliff_secs: i64,
+    pub prime_linear_secs: i64,
+
+    pub flex_reward_cliff_secs: i64,
+    pub core_reward_cliff_secs: i64,
+    pub prime_reward_cliff_secs: i64,
+
+    pub permanent_apy: u16,
+    pub sunset_at: i64,
+
+    pub boost_bps: u16,
+    pub max_boost_duration_secs: i64,
+
+    pub referral_share_bps: u16,
+
+    pub param_timelock_seconds: i64,
+    pub claim_fee_bps: u16,
+    pub fee_vault: Pubkey,
+    pub pending_claim_fee_bps: u16,
+    pub pending_fee_vault: Pubkey,
+    pub pending_claim_fee_effective_at: i64,
+
+    /// Timelocked `adjust_apy` / `update_emission_cap` values, applied by
+    /// `apply_pending_params`; an `effective_at` of 0 means nothing is queued
+    pub pending_apy: [u16; 3],
+    pub pending_apy_effective_at: i64,
+    pub pending_emission_cap: u64,
+    pub pending_emission_cap_effective_at: i64,
+
+    /// Anti flash-staking guards (0 = off): APY accrual intervals shorter
+    /// than `min_accrual_seconds` earn nothing, and no unstake is allowed
+    /// within `min_stake_duration_seconds` of a position's latest deposit
+    pub min_accrual_seconds: i64,
+    pub min_stake_duration_seconds: i64,
+
+    pub max_claims_per_epoch: u32,
+    pub claim_epoch_secs: i64,
+    pub epoch_start: i64,
+    pub claims_this_epoch: u32,
+
+    pub unstake_fee_bps: [u16; 3],
+    pub penalty_destination: PenaltyDestination,
+
+    pub flex_min_stake_secs: i64,
+
+    pub yield_adapters: [Pubkey; MAX_YIELD_ADAPTERS],
+    pub max_treasury_deploy_bps: u16,
+    pub treasury_deployed: u64,
+    pub deployed_adapter: Pubkey,
+
+    pub drip_rate_per_sec: u64,
+    pub last_drip_at: i64,
+
+    pub bonus_liability: u64,
+
+    pub unlock_grace_secs: i64,
+
+    pub early_unstake_penalty_bps: [u16; 2],
+
+    pub pending_authority: Pubkey,
+
+    pub flex_paused: bool,
+    pub core_paused: bool,
+    pub prime_paused: bool,
+
+    /// Smallest amount that may open a position (0 = no minimum); top-ups
+    /// to an open position are exempt
+    pub min_stake_amount: u64,
+
+    /// Ceiling on `total_staked` enforced on new stakes (0 = unlimited)
+    pub max_total_staked: u64,
+
+    /// Delay between `request_unstake` and `withdraw` for Flex (0 = disabled,
+    /// plain `unstake` stays available)
+    pub flex_cooldown_seconds: i64,
+
+    /// Referral shares credited but not yet claimed; already counted in
+    /// `total_distributed` and kept back from the treasury for referrers
+    pub referral_liability: u64,
+
+    /// Number of APY changes so far; change `n` is kept in
+    /// `apy_history[n % MAX_APY_CHECKPOINTS]`
+    pub apy_epoch: u64,
+    pub apy_history: [ApyCheckpoint; MAX_APY_CHECKPOINTS],
+
+    /// Settled rewards owed to positions: every stored `pending_rewards` plus
+    /// boost-locked rewards. Includes credited bonuses
+    pub pending_liability: u64,
+
+    /// Mint rewards are paid in; equal to `staking_mint` for single-token pools
+    pub reward_mint: Pubkey,
+
+    /// Lock given to new positions per tier; each position stores the lock it
+    /// started with, so changes never move an existing lock
+    pub flex_lock_seconds: i64,
+    pub core_lock_seconds: i64,
+    pub prime_lock_seconds: i64,
+
+    /// Every tier APY drops by `decay_bps_per_interval` per whole
+    /// `decay_interval_seconds` since `created_at`, down to `min_apy_floor`.
+    /// An interval of 0 disables decay
+    pub decay_interval_seconds: i64,
+    pub decay_bps_per_interval: u16,
+    pub min_apy_floor: u16,
+
+    /// Snapshot written by `refresh_pool` at `last_refresh_at`; see
+    /// `projected_outstanding_rewards()`
+    pub last_refresh_at: i64,
+    pub projected_outstanding_rewards: u64,
+    pub snapshot_emission_capacity: u64,
+    pub snapshot_treasury_balance: u64,
+
+    /// Principal earning in each tier, indexed by `Tier`; sums to
+    /// `total_staked`, so queued withdrawals are not included
+    pub tier_staked: [u64; 4],
+
+    /// When set, `stake` rejects deposits whose yearly rewards at current
+    /// APYs would exceed the remaining emission capacity or
+    /// `solvency_treasury_multiple_bps` of the treasury
+    pub require_solvency: bool,
+    pub solvency_treasury_multiple_bps: u32,
+
+    /// Accounting mode; the fields below are only used in `RewardPerToken`
+    /// pools. `reward_per_token_stored` is scaled by `PRECISION` and current
+    /// as of `last_update_time`
+    pub reward_mode: RewardMode,
+    pub reward_per_token_stored: u128,
+    pub reward_rate_per_second: u64,
+    pub last_update_time: i64,
+
+    /// Flex principal waiting out its cooldown in `request_unstake`; still in
+    /// the staking vault but no longer part of `total_staked`
+    pub total_unstake_requested: u64,
+
+    /// `AdminMultisig` PDA, or default while the pool is single-key; when
+    /// set, the actions in `AdminAction` need threshold approval
+    pub admin_multisig: Pubkey,
+
+    /// Layout version, `STAKE_POOL_VERSION` for pools created now; older
+    /// accounts are upgraded by `migrate_stake_pool`. New fields go after it.
+    pub version: u8,
+
+    /// Loyalty schedule: after `loyalty_threshold_secs[i]` of continuous
+    /// stake a position earns `loyalty_bonus_bps[i]` on top of its tier APY.
+    /// Thresholds and bonuses are strictly increasing; unused levels are 0
+    pub loyalty_threshold_secs: [i64; MAX_LOYALTY_LEVELS],
+    pub loyalty_bonus_bps: [u16; MAX_LOYALTY_LEVELS],
+
+    /// Reward tokens ever deposited through `fund_treasury` or
+    /// `fund_reserve`; direct transfers to the vaults are not counted
+    pub total_funded: u64,
+
+    /// Rewards (including streak bonuses and referral shares) that claims
+    /// and compounding may pay out per `epoch_length_seconds` (0 = off).
+    /// The epoch starts at the first payout after the previous one elapsed
+    pub max_emission_per_epoch: u64,
+    pub epoch_length_seconds: i64,
+    pub current_epoch_start: i64,
+    pub distributed_this_epoch: u64,
+
+    /// Open positions in each tier, indexed by `Tier`; sums to
+    /// `staker_count` for pools created at v5 or later
+    pub tier_staker_count: [u64; 4],
+
+    /// Whether `stake`, `unstake` and `claim_rewards` may be called through
+    /// CPI; set at `initialize`. Admin instructions are always CPI-callable
+    pub allow_cpi: bool,
+
+    /// Staking vault balance above `staking_vault_principal` as of the last
+    /// `sync_vault`, e.g. from direct transfers; not owed to any staker
+    pub unaccounted_principal: u64,
+
+    /// Donated tokens per staked token, scaled by `PRECISION`; only grows.
+    /// A position is owed `staked_amount * bonus_per_token - bonus_debt`
+    pub bonus_per_token: u128,
+    /// Donations not yet settled into any position; reserved in the
+    /// treasury. Settled shares move to `bonus_liability`
+    pub donation_liability: u64,
+    pub total_donated: u64,
+
+    /// Holders of the `AdminRole`s; equal to `authority` until `set_role`
+    pub pauser: Pubkey,
+    pub rate_setter: Pubkey,
+
+    /// Lifetime counters; unlike `total_staked` they never go down.
+    /// Principal deposited by `stake`/`stake_for` (compounding excluded)
+    pub cumulative_staked: u64,
+    /// Principal that left the pool, before fees
+    pub cumulative_unstaked: u64,
+    pub cumulative_claims_count: u64,
+    /// `UserStake` accounts created by a first stake
+    pub unique_stakers_ever: u64,
+
+    /// Whether positions keep accruing while staking is paused
+    pub accrue_while_paused: bool,
+    /// Start of the running halt: staking paused with accrual off
+    pub paused_at: Option<i64>,
+    /// Seconds spent in halts that have ended; see `paused_seconds_at`
+    pub cumulative_paused_secs: i64,
+
+    /// When accrual stops for good and new stakes are refused (0 = never)
+    pub rewards_end_time: i64,
+
+    /// Advanced once by every instruction that changes the pool or one of
+    /// its positions; carried by every event, never reset
+    pub sequence: u64,
+
+    /// When a `set_paused` stake pause lifts itself (0 = never)
+    pub pause_expiry: i64,
+
+    /// Minimum spacing of `take_snapshot` calls (0 = snapshots disabled)
+    pub snapshot_interval_secs: i64,
+    /// Snapshots taken so far; the next snapshot's epoch
+    pub snapshot_count: u64,
+    pub last_snapshot_at: i64,
+
+    /// Oracle-driven APY; see `update_apy_from_oracle`
+    pub oracle: OracleApyConfig,
+    pub last_oracle_update: i64,
+
+    /// Mint of the transferable receipts for staked principal, created at
+    /// `initialize` (default = receipt mode off)
+    pub receipt_mint: Pubkey,
+
+    /// Boost campaigns in ring order, `apy_boost_count` ever scheduled; see
+    /// `set_apy_boost`
+    pub apy_boosts: [ApyBoost; MAX_APY_BOOSTS],
+    pub apy_boost_count: u64,
+
+    /// Idle time after which anyone may close an empty position with
+    /// `gc_user_stake` (0 = disabled)
+    pub gc_inactivity_secs: i64,
+
+    /// Seconds a position must wait after its last settlement before
+    /// `claim_rewards` (0 = no limit)
+    pub min_claim_interval_seconds: i64,
+
+    /// Whether Core and Prime positions earn the Flex APY once their lock
+    /// has ended, until they relock
+    pub downgrade_after_lock: bool,
+
+    /// Whether `stake` and `stake_for` require the position owner's
+    /// `WhitelistEntry`; exits and claims never do
+    pub whitelist_enabled: bool,
+
+    /// Decay schedule queued by `set_apy_decay` while the timelock is set
+    /// (`pending_decay_effective_at` 0 = nothing queued)
+    pub pending_decay_interval_seconds: i64,
+    pub pending_decay_bps_per_interval: u16,
+    pub pending_min_apy_floor: u16,
+    pub pending_decay_effective_at: i64,
+
+    /// Start of the rate setter's current window (0 = none open) and the
+    /// live APYs when it opened; its `adjust_apy` calls are bounded
+    /// against these, not the APYs its own earlier calls set
+    pub rate_setter_window_start: i64,
+    pub rate_setter_window_apys: [u16; 3],
+
+    /// Rewards open positions have accrued since they last settled, as of
+    /// `liability_accrued_at` (0 = not tracked yet). Estimated from the tier
+    /// totals at the base rates and brought forward lazily; settlements
+    /// take out what they move into `pending_liability`
+    pub accrued_liability: u64,
+    pub liability_accrued_at: i64,
+
+    /// Per-tier `apy_bps * seconds` of the checkpoints pushed out of
+    /// `apy_history`, covering `[created_at, apy_folded_until)`, and the
+    /// seconds behind them; time before the retained history is
+    /// priced at their ratio
+    pub folded_apy_seconds: [u128; 4],
+    pub folded_accruing_seconds: i64,
+    pub apy_folded_until: i64,
+
+    /// Timelocked `set_permanent_apy` value, the Permanent tier's
+    /// counterpart of `pending_apy`; an `effective_at` of 0 means nothing
+    /// is queued
+    pub pending_permanent_apy: u16,
+    pub pending_permanent_apy_effective_at: i64,
+
+    /// Timelocked `set_rewards_end_time` value; an `effective_at` of 0 means
+    /// nothing is queued
+    pub pending_rewards_end_time: i64,
+    pub pending_rewards_end_time_effective_at: i64,
 }
 
 impl StakePool {
+    pub const PAUSE_STAKE_BIT: u8 = 1 << 0;
+    pub const PAUSE_CLAIM_BIT: u8 = 1 << 1;
+    pub const PAUSE_UNSTAKE_BIT: u8 = 1 << 2;
+    pub const PAUSE_ALL_BITS: u8 = Self::PAUSE_STAKE_BIT | Self::PAUSE_CLAIM_BIT | Self::PAUSE_UNSTAKE_BIT;
+
     pub const LEN: usize = 8
         + (32 * 4)
         + (2 * 3)
         + (8 * 4)
         + 1
         + 8
-        + 3 + 8;
+        + 3 + 8
+        + 1 + (1 + 8) + (8 * 2)
+        + 8
+        + 8 + 8 + 8
+        + 1
+        + 1
+        + 2 + 8
+        + 8 + 8 + 2 + 8
+        + 2 + 2 + 8
+        + (8 * 4)
+        + (8 * 3)
+        + 2 + 8
+        + 2 + 8
+        + 2
+        + 8 + 2 + 32 + 2 + 32 + 8
+        + 4 + 8 + 8 + 4
+        + (2 * 3)
+        + 1
+        + 8
+        + (32 * MAX_YIELD_ADAPTERS) + 2 + 8 + 32
+        + 8 + 8
+        + 8
+        + 8
+        + (2 * 2)
+        + 32
+        + 1 + 1 + 1
+        + 8
+        + 8
+        + 8
+        + 8
+        + 8 + (ApyCheckpoint::LEN * MAX_APY_CHECKPOINTS)
+        + 8
+        + 32
+        + (8 * 3)
+        + 8 + 2 + 2
+        + 8 + (8 * 3)
+        + (8 * 4)
+        + 1 + 4
+        + 1 + 16 + 8 + 8
+        + 8
+        + 32
+        + (2 * 3) + 8 + 8 + 8
+        + 8 + 8
+        + 1
+        + ((8 + 2) * MAX_LOYALTY_LEVELS)
+        + 8
+        + 8 + 8 + 8 + 8
+        + (8 * 4)
+        + 1
+        + 8
+        + 16 + 8 + 8
+        + 32 + 32
+        + (8 * 4)
+        + 1 + (1 + 8) + 8
+        + 8
+        + 8
+        + 8
+        + 8 + 8 + 8
+        + OracleApyConfig::LEN + 8
+        + 32
+        + (ApyBoost::LEN * MAX_APY_BOOSTS) + 8
+        + 8
+        + 8
+        + 1
+        + 1
+        + 8 + 2 + 2 + 8
+        + 8 + (2 * 3)
+        + 8 + 8
+        + (16 * 4) + 8 + 8
+        + 2 + 8
+        + 8 + 8;
+
+    /// Configured APY for a tier, before decay.
+    pub fn base_apy_for_tier(&self, tier: Tier) -> u16 {
+        match tier {
+            Tier::Flex => self.flex_apy,
+            Tier::Core => self.core_apy,
+            Tier::Prime => self.prime_apy,
+            Tier::Permanent => self.permanent_apy,
+        }
+    }
+
+    /// APY a tier earns at `now`, after the decay schedule.
+    pub fn get_apy_for_tier(&self, tier: Tier, now: i64) -> u16 {
+        decayed_apy(
+            self.base_apy_for_tier(tier),
+            now.saturating_sub(self.created_at),
+            self.decay_interval_seconds,
+            self.decay_bps_per_interval,
+            self.min_apy_floor,
+        )
+    }
+
+    pub fn is_apy_decaying(&self) -> bool {
+        self.decay_interval_seconds > 0 && self.decay_bps_per_interval > 0
+    }
+
+    /// Current `(decay_interval_seconds, decay_bps_per_interval, min_apy_floor)`.
+    pub fn decay_schedule(&self) -> (i64, u16, u16) {
+        (self.decay_interval_seconds, self.decay_bps_per_interval, self.min_apy_floor)
+    }
+
+    /// Replace the Flex, Core, Prime and Permanent base APYs and the decay
+    /// schedule at `now`, checkpointing the old ones. Resubmitting the
+    /// current values records nothing, so repeated no-op changes cannot push
+    /// older rates out of `apy_history`.
+    pub fn set_apy_schedule(
+        &mut self,
+        apys: [u16; 4],
+        (interval, step, floor): (i64, u16, u16),
+        now: i64,
+    ) -> Result<()> {
+        let current = [self.flex_apy, self.core_apy, self.prime_apy, self.permanent_apy];
+        if apys == current && (interval, step, floor) == self.decay_schedule() {
+            return Ok(());
+        }
+
+        self.checkpoint_apy(now)?;
+        [self.flex_apy, self.core_apy, self.prime_apy, self.permanent_apy] = apys;
+        self.decay_interval_seconds = interval;
+        self.decay_bps_per_interval = step;
+        self.min_apy_floor = floor;
+        Ok(())
+    }
+
+    /// Record the current tier APYs and decay schedule as replaced at `now`.
+    /// Call before any of them is overwritten. Once the history is full the
+    /// oldest checkpoint is folded into `folded_apy_seconds` rather than
+    /// dropped.
+    pub fn checkpoint_apy(&mut self, now: i64) -> Result<()> {
+        self.accrue_liability(now);
+        let slot = (self.apy_epoch % MAX_APY_CHECKPOINTS as u64) as usize;
+        if self.apy_epoch >= MAX_APY_CHECKPOINTS as u64 {
+            self.fold_apy_checkpoint(self.apy_history[slot])?;
+        }
+        self.apy_history[slot] = ApyCheckpoint {
+            replaced_at: now,
+            apys: [self.flex_apy, self.core_apy, self.prime_apy, self.permanent_apy],
+            decay_interval_seconds: self.decay_interval_seconds,
+            decay_bps_per_interval: self.decay_bps_per_interval,
+            min_apy_floor: self.min_apy_floor,
+        };
+        self.apy_epoch = self.apy_epoch.saturating_add(1);
+        Ok(())
+    }
+
+    /// Add the span `evicted` priced, from `apy_folded_until` to its
+    /// `replaced_at`, to the folded totals.
+    fn fold_apy_checkpoint(&mut self, evicted: ApyCheckpoint) -> Result<()> {
+        let from = self.apy_folded_until.max(self.created_at);
+        let to = evicted.replaced_at.max(from);
+        let schedule = (
+            evicted.decay_interval_seconds,
+            evicted.decay_bps_per_interval,
+            evicted.min_apy_floor,
+        );
+        for (tier, folded) in self.folded_apy_seconds.into_iter().enumerate() {
+            self.folded_apy_seconds[tier] = folded
+                .checked_add(self.decaying_apy_seconds(evicted.apys[tier], schedule, from, to)?)
+                .ok_or(StakingError::MathOverflow)?;
+        }
+        self.folded_accruing_seconds = self
+            .folded_accruing_seconds
+            .checked_add(to - from)
+            .ok_or(StakingError::MathOverflow)?;
+        self.apy_folded_until = to;
+        Ok(())
+    }
+
+    /// Sum of `apy_bps * seconds` for `tier` over `[start, end)`,
+    /// splitting the interval at every recorded APY change and every decay
+    /// step.
+    ///
+    /// Time before `apy_folded_until` is priced at the time-weighted average
+    /// of the checkpoints folded out of the history, so only positions
+    /// untouched across more than `MAX_APY_CHECKPOINTS` changes are
+    /// approximated, and never at a rate set after that time.
+    pub fn apy_seconds_for_tier(&self, tier: Tier, start: i64, end: i64) -> Result<u128> {
+        let retained = self.apy_epoch.min(MAX_APY_CHECKPOINTS as u64);
+        let mut total: u128 = 0;
+        let mut segment_start = start;
+
+        let folded_end = self.apy_folded_until.min(end);
+        if segment_start < folded_end && self.folded_accruing_seconds > 0 {
+            total = self.folded_apy_seconds[tier as usize]
+                .checked_mul((folded_end - segment_start) as u128)
+                .ok_or(StakingError::MathOverflow)?
+                / self.folded_accruing_seconds as u128;
+        }
+        segment_start = segment_start.max(folded_end);
+
+        for epoch in (self.apy_epoch - retained)..self.apy_epoch {
+            if segment_start >= end {
+                break;
+            }
+            let checkpoint = &self.apy_history[(epoch % MAX_APY_CHECKPOINTS as u64) as usize];
+            if checkpoint.replaced_at <= segment_start {
+                continue;
+            }
+            let segment_end = checkpoint.replaced_at.min(end);
+            total = total
+                .checked_add(self.decaying_apy_seconds(
+                    checkpoint.apys[tier as usize],
+                    (
+                        checkpoint.decay_interval_seconds,
+                        checkpoint.decay_bps_per_interval,
+                        checkpoint.min_apy_floor,
+                    ),
+                    segment_start,
+                    segment_end,
+                )?)
+                .ok_or(StakingError::MathOverflow)?;
+            segment_start = segment_end;
+        }
+
+        if segment_start < end {
+            total = total
+                .checked_add(self.decaying_apy_seconds(
+                    self.base_apy_for_tier(tier),
+                    (
+                        self.decay_interval_seconds,
+                        self.decay_bps_per_interval,
+                        self.min_apy_floor,
+                    ),
+                    segment_start,
+                    end,
+                )?)
+                .ok_or(StakingError::MathOverflow)?;
+        }
+
+        Ok(total)
+    }
+
+    /// The most recently scheduled boost campaign, if any.
+    pub fn latest_apy_boost(&self) -> Option<ApyBoost> {
+        let latest = self.apy_boost_count.checked_sub(1)?;
+        Some(self.apy_boosts[(latest % MAX_APY_BOOSTS as u64) as usize])
+    }
+
+    /// Record a boost campaign. It may not start before `now`, so no elapsed
+    /// time is repriced, nor before the previous campaign ends, so campaigns
+    /// never overlap and their extras simply add up.
+    pub fn schedule_apy_boost(&mut self, boost: ApyBoost, now: i64) -> Result<()> {
+        require!(
+            boost.multiplier_bps > BASIS_POINTS_DENOMINATOR as u16
+                && boost.multiplier_bps <= MAX_APY_BOOST_MULTIPLIER_BPS,
+            StakingError::InvalidApyBoost
+        );
+        require!(
+            boost.start_time >= now
+                && boost.end_time > boost.start_time
+                && boost.end_time - boost.start_time <= MAX_APY_BOOST_DURATION,
+            StakingError::InvalidApyBoost
+        );
+        if let Some(latest) = self.latest_apy_boost() {
+            require!(boost.start_time >= latest.end_time, StakingError::ApyBoostOverlap);
+        }
+
+        let slot = (self.apy_boost_count % MAX_APY_BOOSTS as u64) as usize;
+        self.apy_boosts[slot] = boost;
+        self.apy_boost_count = self.apy_boost_count.saturating_add(1);
+        Ok(())
+    }
+
+    /// Extra `apy_bps * seconds` from boost campaigns for `tier` over
+    /// `[start, end)`: each campaign adds `extra_bps` of the base APY integral
+    /// over just the seconds it overlaps, so an interval settled long after a
+    /// campaign ended is still boosted for exactly that window.
+    ///
+    /// Only the last `MAX_APY_BOOSTS` campaigns are kept; a position untouched
+    /// across more than that misses the oldest.
+    pub fn apy_boost_seconds(&self, tier: Tier, start: i64, end: i64) -> Result<u128> {
+        let retained = self.apy_boost_count.min(MAX_APY_BOOSTS as u64);
+        let mut total: u128 = 0;
+
+        for index in (self.apy_boost_count - retained)..self.apy_boost_count {
+            let boost = &self.apy_boosts[(index % MAX_APY_BOOSTS as u64) as usize];
+            let overlap_start = start.max(boost.start_time);
+            let overlap_end = end.min(boost.end_time);
+            if overlap_start >= overlap_end {
+                continue;
+            }
+            let extra = self
+                .apy_seconds_for_tier(tier, overlap_start, overlap_end)?
+                .checked_mul(boost.extra_bps() as u128)
+                .ok_or(StakingError::MathOverflow)?
+                .checked_div(BASIS_POINTS_DENOMINATOR as u128)
+                .ok_or(StakingError::DivisionByZero)?;
+            total = total.checked_add(extra).ok_or(StakingError::MathOverflow)?;
+        }
+
+        Ok(total)
+    }
+
+    /// `apy_bps * seconds` over `[start, end)` for a `base` rate under
+    /// the decay schedule `(interval, step, floor)`, one piece per decay step.
+    /// Once the floor is reached the rest is a single piece, so the loop runs
+    /// at most `MAX_DECAY_STEPS + 1` times.
+    fn decaying_apy_seconds(
+        &self,
+        base: u16,
+        (interval, step, floor): (i64, u16, u16),
+        start: i64,
+        end: i64,
+    ) -> Result<u128> {
+        let mut total: u128 = 0;
+        let mut from = start;
+
+        while from < end {
+            let elapsed = from.saturating_sub(self.created_at).max(0);
+            let rate = decayed_apy(base, elapsed, interval, step, floor);
+            let to = if interval <= 0 || step == 0 || rate <= floor.min(base) {
+                end
+            } else {
+                let next_step = (elapsed / interval)
+                    .saturating_add(1)
+                    .saturating_mul(interval);
+                self.created_at.saturating_add(next_step).min(end)
+            };
+            total = total
+                .checked_add(bps_seconds(rate, to - from)?)
+                .ok_or(StakingError::MathOverflow)?;
+            from = to;
+        }
+
+        Ok(total)
+    }
+
+    /// Principal release schedule `(cliff_secs, linear_secs)` for a tier.
+    /// Flex and Permanent have no schedule, so nothing is held back.
+    pub fn get_release_schedule_for_tier(&self, tier: Tier) -> (i64, i64) {
+        match tier {
+            Tier::Core => (self.core_cliff_secs, self.core_linear_secs),
+            Tier::Prime => (self.prime_cliff_secs, self.prime_linear_secs),
+            Tier::Flex | Tier::Permanent => (0, 0),
+        }
+    }
+
+    /// Minimum position age before accrued rewards may be claimed.
+    pub fn get_reward_cliff_for_tier(&self, tier: Tier) -> i64 {
+        match tier {
+            Tier::Core => self.core_reward_cliff_secs,
+            Tier::Prime => self.prime_reward_cliff_secs,
+            Tier::Flex | Tier::Permanent => self.flex_reward_cliff_secs,
+        }
+    }
+
+    /// Lock a position entering `tier` now signs up for. Permanent waits
+    /// for the sunset instead.
+    pub fn get_lock_period_for_tier(&self, tier: Tier) -> i64 {
+        match tier {
+            Tier::Flex => self.flex_lock_seconds,
+            Tier::Core => self.core_lock_seconds,
+            Tier::Prime => self.prime_lock_seconds,
+            Tier::Permanent => 0,
+        }
+    }
+
+    /// Lock period a release schedule for `tier` must add up to.
+    pub fn lock_period_for_tier(&self, tier: Tier) -> Option<i64> {
+        match tier {
+            Tier::Core | Tier::Prime => Some(self.get_lock_period_for_tier(tier)),
+            Tier::Flex | Tier::Permanent => None,
+        }
+    }
+
+    /// Release schedule for a position locked for `lock_seconds`. Positions
+    /// that started under a different lock than the current schedule covers
+    /// keep a hard cliff at their own lock end.
+    pub fn release_schedule_for_lock(&self, tier: Tier, lock_seconds: i64) -> (i64, i64) {
+        let (cliff_secs, linear_secs) = self.get_release_schedule_for_tier(tier);
+        if cliff_secs.saturating_add(linear_secs) == lock_seconds {
+            (cliff_secs, linear_secs)
+        } else {
+            (lock_seconds, 0)
+        }
+    }
+
+    /// Whether new stakes are paused pool-wide.
+    pub fn is_stake_paused(&self) -> bool {
+        self.pause_flags & Self::PAUSE_STAKE_BIT != 0
+    }
+
+    /// Whether a `set_paused` pause has reached `pause_expiry` at `now` and
+    /// only waits for `expire_pause` to clear the stake bit.
+    pub fn is_pause_expired(&self, now: i64) -> bool {
+        self.pause_expiry > 0 && now >= self.pause_expiry && self.is_stake_paused()
+    }
+
+    /// Clear a stake pause whose expiry has been reached. Instructions that
+    /// check the stake bit call this first; the halt clock is closed at the
+    /// expiry itself, so accrual resumes from that second however late the
+    /// flag is cleared.
+    pub fn expire_pause(&mut self, now: i64) -> Result<()> {
+        if !self.is_pause_expired(now) {
+            return Ok(());
+        }
+        let expiry = self.pause_expiry;
+        self.pause_flags &= !Self::PAUSE_STAKE_BIT;
+        self.sync_pause_clock(expiry)?;
+        self.pause_expiry = 0;
+        msg!("Stake pause expired at {}", expiry);
+        Ok(())
+    }
+
+    /// `now`, or `pause_expiry` when that comes first; a running halt never
+    /// extends past it.
+    fn halt_end(&self, now: i64) -> i64 {
+        if self.pause_expiry > 0 {
+            now.min(self.pause_expiry)
+        } else {
+            now
+        }
+    }
+
+    /// Whether reward claims are paused.
+    pub fn is_claim_paused(&self) -> bool {
+        self.pause_flags & Self::PAUSE_CLAIM_BIT != 0
+    }
+
+    /// Whether unstaking is paused; `emergency_withdraw` is never paused.
+    pub fn is_unstake_paused(&self) -> bool {
+        self.pause_flags & Self::PAUSE_UNSTAKE_BIT != 0
+    }
+
+    /// Pausing both claims and unstakes leaves no way out except
+    /// `emergency_withdraw`, so `flags` may only do that in emergency mode.
+    pub fn allows_pause_flags(flags: u8, emergency: bool) -> bool {
+        let both = Self::PAUSE_CLAIM_BIT | Self::PAUSE_UNSTAKE_BIT;
+        emergency || flags & both != both
+    }
+
+    /// Whether new stake may enter `tier`. The global pause overrides the
+    /// per-tier flags; the permanent tier is gated by its APY instead.
+    pub fn is_accepting_stakes(&self, tier: Tier) -> bool {
+        let tier_paused = match tier {
+            Tier::Flex => self.flex_paused,
+            Tier::Core => self.core_paused,
+            Tier::Prime => self.prime_paused,
+            Tier::Permanent => false,
+        };
+        !self.is_stake_paused() && !tier_paused
+    }
+
+    /// Whether a sunset has been declared and reached, releasing the permanent tier.
+    pub fn is_sunset_reached(&self, now: i64) -> bool {
+        self.sunset_at != 0 && now >= self.sunset_at
+    }
+
+    /// Early-bird slots still available to first-time stakers.
+    pub fn early_bird_slots_remaining(&self) -> u64 {
+        self.early_bird_slots.saturating_sub(self.early_bird_slots_used)
+    }
+
+    /// Claim streaks are tracked only while both a step and a window are configured.
+    pub fn streak_enabled(&self) -> bool {
+        self.streak_step_bps > 0 && self.streak_window_secs > 0
+    }
+
+    /// `(streak_count, streak_deadline)` after a claim at `now`.
+    ///
+    /// Windows of `streak_window_secs` run back to back from the claim that
+    /// started the streak, and `streak_deadline` is the end of the window
+    /// after the one last counted. A claim in that next window extends the
+    /// streak by one; further claims in a window already counted change
+    /// nothing; a claim after the deadline starts over at zero.
+    pub fn next_streak(&self, streak_count: u16, streak_deadline: i64, now: i64) -> Result<(u16, i64)> {
+        let window = self.streak_window_secs;
+        if streak_deadline == 0 || now > streak_deadline {
+            let deadline = window
+                .checked_mul(2)
+                .and_then(|span| now.checked_add(span))
+                .ok_or(StakingError::MathOverflow)?;
+            return Ok((0, deadline));
+        }
+        if now <= streak_deadline.saturating_sub(window) {
+            return Ok((streak_count, streak_deadline));
+        }
+        let deadline = streak_deadline
+            .checked_add(window)
+            .ok_or(StakingError::MathOverflow)?;
+        Ok((streak_count.saturating_add(1), deadline))
+    }
+
+    /// Payout bonus for a streak of `streak_count` consecutive on-time claims.
+    pub fn streak_bonus_bps(&self, streak_count: u16) -> u16 {
+        let bps = (self.streak_step_bps as u32).saturating_mul(streak_count as u32);
+        bps.min(self.streak_cap_bps as u32) as u16
+    }
+
+    /// Split a gross claim into `(net, fee)`. The fee rounds down, so it never
+    /// exceeds the claim and tiny claims pay no fee.
+    pub fn split_claim_fee(&self, gross: u64) -> Result<(u64, u64)> {
+        split_bps(gross, self.claim_fee_bps)
+    }
+
+    /// Whether any tier charges an unstake fee or early unstake penalty.
+    pub fn charges_principal_fees(&self) -> bool {
+        self.unstake_fee_bps.iter().any(|&fee| fee > 0)
+            || self.early_unstake_penalty_bps.iter().any(|&penalty| penalty > 0)
+    }
+
+    /// Split an unstake of `amount` from `tier` into `(net, fee)` using the
+    /// tier's unstake fee. The permanent tier has no fee slot and pays none.
+    pub fn split_unstake_fee(&self, tier: Tier, amount: u64) -> Result<(u64, u64)> {
+        let fee_bps = self.unstake_fee_bps.get(tier as usize).copied().unwrap_or(0);
+        split_bps(amount, fee_bps)
+    }
 
-    pub fn get_apy_for_tier(&self, tier: u8) -> u16 {
+    /// Early-exit penalty for a locked tier. Flex and Permanent have none,
+    /// and a zero penalty means early exits are disabled for the tier.
+    pub fn early_unstake_penalty_for_tier(&self, tier: Tier) -> u16 {
         match tier {
-            0 => self.flex_apy,
-            1 => self.core_apy,
-            2 => self.prime_apy,
-            _ => self.flex_apy,
+            Tier::Core => self.early_unstake_penalty_bps[0],
+            Tier::Prime => self.early_unstake_penalty_bps[1],
+            Tier::Flex | Tier::Permanent => 0,
+        }
+    }
+
+    /// Split an early exit of `amount` from `tier` into `(net, penalty)`. The
+    /// penalty rounds up, so splitting an exit into tiny pieces cannot dodge it.
+    pub fn split_early_unstake_penalty(&self, tier: Tier, amount: u64) -> Result<(u64, u64)> {
+        let denominator = BASIS_POINTS_DENOMINATOR as u128;
+        let penalty = (amount as u128)
+            .checked_mul(self.early_unstake_penalty_for_tier(tier) as u128)
+            .ok_or(StakingError::MathOverflow)?
+            .checked_add(denominator - 1)
+            .ok_or(StakingError::MathOverflow)?
+            / denominator;
+        let penalty = u64::try_from(penalty).map_err(|_| StakingError::ConversionOverflow)?;
+        let net = amount.checked_sub(penalty).ok_or(StakingError::MathUnderflow)?;
+        Ok((net, penalty))
+    }
+
+    /// Emission budget not yet paid out.
+    pub fn remaining_emission_capacity(&self) -> u64 {
+        self.emission_cap.saturating_sub(self.total_distributed)
+    }
+
+    /// Emission budget neither paid out nor owed as settled rewards: the most
+    /// any position can still accrue.
+    pub fn unreserved_emission_capacity(&self) -> u64 {
+        self.remaining_emission_capacity()
+            .saturating_sub(self.pending_liability)
+    }
+
+    /// `accrued_liability` brought forward to `now` at the current tier
+    /// totals and base rates, skipping halted time, unfunded time under
+    /// `accrue_only_when_funded` and anything past the rewards end. Bonuses
+    /// on top of the base rate are not counted.
+    pub fn accrued_liability_at(&self, now: i64) -> u64 {
+        let until = self.accrual_cutoff(now);
+        let halted = self.paused_at.map_or(0, |since| {
+            self.halt_end(until)
+                .saturating_sub(since.max(self.liability_accrued_at))
+                .max(0)
+        });
+        // Closing a window accrues first, so only the open one can overlap
+        let unfunded = match self.treasury_empty_since {
+            Some(since) if self.accrue_only_when_funded => until
+                .saturating_sub(since.max(self.liability_accrued_at))
+                .max(0),
+            _ => 0,
+        };
+        let elapsed = until
+            .saturating_sub(self.liability_accrued_at)
+            .saturating_sub(halted)
+            .saturating_sub(unfunded);
+        if self.liability_accrued_at == 0 || elapsed <= 0 {
+            return self.accrued_liability;
+        }
+        // An overflowing projection can only mean more demand than any cap
+        let yearly = self.projected_annual_rewards(now, None).unwrap_or(u64::MAX);
+        let accrued = (yearly as u128)
+            .saturating_mul(elapsed as u128)
+            / SECONDS_PER_YEAR as u128;
+        self.accrued_liability
+            .saturating_add(u64::try_from(accrued).unwrap_or(u64::MAX))
+    }
+
+    /// Store `accrued_liability` as of `now`. Must run before the tier
+    /// totals, rates or halt clock change, like `update_reward_per_token`.
+    pub fn accrue_liability(&mut self, now: i64) {
+        self.accrued_liability = self.accrued_liability_at(now);
+        self.liability_accrued_at = self.liability_accrued_at.max(now);
+    }
+
+    /// Part of a position's `accrued` rewards it can settle at `now`: all of
+    /// them while the pool's accrued liability fits in the unreserved
+    /// emission budget, otherwise their pro-rata share of that budget. The
+    /// rest stays owed until the cap is raised.
+    pub fn funded_share(&self, accrued: u64, now: i64) -> u64 {
+        let capacity = self.unreserved_emission_capacity();
+        let demand = self.accrued_liability_at(now).max(accrued);
+        if demand <= capacity {
+            return accrued;
+        }
+        // accrued <= demand, so the share is at most capacity
+        (accrued as u128 * capacity as u128 / demand as u128) as u64
+    }
+
+    /// Whether the admin has enabled `policy` for top-ups into existing positions.
+    pub fn allows_topup_policy(&self, policy: TopUpLockPolicy) -> bool {
+        self.allowed_topup_policies & policy.mask_bit() != 0
+    }
+
+    /// Deposits already counted against the daily inflow cap at `now`.
+    ///
+    /// Returns zero once the current 24h window has elapsed; the window itself is
+    /// only rolled forward by `record_inflow`.
+    pub fn inflow_used(&self, now: i64) -> u64 {
+        if now.saturating_sub(self.inflow_window_start) >= SECONDS_PER_DAY {
+            0
+        } else {
+            self.inflow_in_window
+        }
+    }
+
+    /// Stake the pool can still take before hitting `max_total_staked`, or
+    /// `None` when uncapped. Zero once a lowered cap sits below the current
+    /// total.
+    pub fn remaining_capacity(&self) -> Option<u64> {
+        if self.max_total_staked == 0 {
+            None
+        } else {
+            Some(self.max_total_staked.saturating_sub(self.total_staked))
+        }
+    }
+
+    /// Bring an account read from an older layout up to
+    /// `STAKE_POOL_VERSION`. Fields added since read as zero after the
+    /// account is grown; each step below sets anything that needs more.
+    pub fn migrate(&mut self) -> Result<()> {
+        require!(
+            self.version <= STAKE_POOL_VERSION,
+            StakingError::AccountVersionMismatch
+        );
+        // v0 -> v1: only `version` was added
+        if self.version == 0 {
+            self.version = 1;
+        }
+        // v1 -> v2: loyalty schedule, which stays disabled
+        if self.version == 1 {
+            self.version = 2;
+        }
+        // v2 -> v3: earlier funding was not recorded, so start from what has
+        // been paid out, the least that must have come in
+        if self.version == 2 {
+            self.total_funded = self.total_distributed;
+            self.version = 3;
+        }
+        // v3 -> v4: per-epoch emission limit, which stays disabled
+        if self.version == 3 {
+            self.version = 4;
+        }
+        // v4 -> v5: per-tier staker counts, which cannot be rebuilt on-chain
+        // and start at zero
+        if self.version == 4 {
+            self.version = 5;
+        }
+        // v5 -> v6: CPI guard, which starts closed
+        if self.version == 5 {
+            self.allow_cpi = false;
+            self.version = 6;
+        }
+        // v6 -> v7: unaccounted principal, measured by the next `sync_vault`
+        if self.version == 6 {
+            self.version = 7;
         }
+        // v7 -> v8: donation accumulator, empty until the first donation
+        if self.version == 7 {
+            self.version = 8;
+        }
+        // v8 -> v9: admin roles, all held by the authority as before
+        if self.version == 8 {
+            self.pauser = self.authority;
+            self.rate_setter = self.authority;
+            self.version = 9;
+        }
+        // v9 -> v10: lifetime counters, which count from the migration; the
+        // open positions are the only stakers known to exist
+        if self.version == 9 {
+            self.unique_stakers_ever = self.staker_count;
+            self.version = 10;
+        }
+        // v10 -> v11: pauses keep accruing, as before
+        if self.version == 10 {
+            self.accrue_while_paused = true;
+            self.version = 11;
+        }
+        // v11 -> v12: no rewards end time
+        if self.version == 11 {
+            self.version = 12;
+        }
+        // v12 -> v13: the sequence starts at zero
+        if self.version == 12 {
+            self.version = 13;
+        }
+        // v13 -> v14: pauses carry no expiry
+        if self.version == 13 {
+            self.version = 14;
+        }
+        // v14 -> v15: snapshots stay disabled until an interval is set
+        if self.version == 14 {
+            self.version = 15;
+        }
+        // v15 -> v16: oracle mode off
+        if self.version == 15 {
+            self.version = 16;
+        }
+        // v16 -> v17: no receipts; receipt mode is only chosen at `initialize`
+        if self.version == 16 {
+            self.version = 17;
+        }
+        // v17 -> v18: no boost campaigns
+        if self.version == 17 {
+            self.version = 18;
+        }
+        // v18 -> v19: garbage collection stays off
+        if self.version == 18 {
+            self.version = 19;
+        }
+        // v19 -> v20: no minimum claim interval
+        if self.version == 19 {
+            self.version = 20;
+        }
+        // v20 -> v21: ended locks keep their tier APY
+        if self.version == 20 {
+            self.version = 21;
+        }
+        // v21 -> v22: staking stays open to everyone
+        if self.version == 21 {
+            self.version = 22;
+        }
+        // v22 -> v23: no decay change queued
+        if self.version == 22 {
+            self.version = 23;
+        }
+        // v23 -> v24: the rate setter's first call opens a window
+        if self.version == 23 {
+            self.version = 24;
+        }
+        // v24 -> v25: the accrued liability is counted from the next update;
+        // positions settling after it hold accrual from before it, which
+        // `funded_share` still caps
+        if self.version == 24 {
+            self.version = 25;
+        }
+        // v25 -> v26: nothing folded yet; the first checkpoint pushed out of
+        // the history folds from `created_at`
+        if self.version == 25 {
+            self.version = 26;
+        }
+        // v26 -> v27: no Permanent APY change queued
+        if self.version == 26 {
+            self.version = 27;
+        }
+        // v27 -> v28: no rewards end time queued
+        if self.version == 27 {
+            self.version = 28;
+        }
+        // v28 -> v29: `cumulative_unfunded_secs` takes the slot of the last
+        // closed window's start; positions snapshot the total from v9 on, so
+        // it starts at zero and only windows closed from here are counted
+        if self.version == 28 {
+            self.cumulative_unfunded_secs = 0;
+            self.version = 29;
+        }
+        Ok(())
+    }
+
+    /// `bonus_bps * seconds` of loyalty bonus over `[start, end)` for a
+    /// `tier` position continuously staked since `loyalty_start`.
+    ///
+    /// Each level adds the step over the previous one for the seconds past
+    /// its threshold, so a threshold crossed mid-interval only pays the
+    /// higher bonus from that point on. Bonuses are capped so the tier's
+    /// base APY plus bonus stays within MAX_APY. Positions without a
+    /// `loyalty_start` earn nothing.
+    pub fn loyalty_bps_seconds(
+        &self,
+        tier: Tier,
+        loyalty_start: i64,
+        start: i64,
+        end: i64,
+    ) -> Result<u128> {
+        if loyalty_start <= 0 {
+            return Ok(0);
+        }
+        let cap = MAX_APY.saturating_sub(self.base_apy_for_tier(tier));
+        let mut total: u128 = 0;
+        let mut previous: u16 = 0;
+
+        for (threshold, bonus_bps) in self
+            .loyalty_threshold_secs
+            .iter()
+            .zip(self.loyalty_bonus_bps)
+        {
+            let bonus_bps = bonus_bps.min(cap);
+            if bonus_bps <= previous {
+                continue;
+            }
+            let from = loyalty_start.saturating_add(*threshold).max(start);
+            total = total
+                .checked_add(bps_seconds(bonus_bps - previous, end.saturating_sub(from))?)
+                .ok_or(StakingError::MathOverflow)?;
+            previous = bonus_bps;
+        }
+
+        Ok(total)
+    }
+
+    /// Check that recorded funding covers everything distributed. A
+    /// treasury holding more than `total_funded - total_distributed` is
+    /// fine: third parties can transfer to the vault directly.
+    pub fn treasury_consistency(&self) -> Result<()> {
+        require!(
+            self.total_funded >= self.total_distributed,
+            StakingError::TreasuryAccountingMismatch
+        );
+        Ok(())
+    }
+
+    /// Reject a user instruction invoked through CPI unless `allow_cpi` is
+    /// set: the instruction currently executing at the top level of the
+    /// transaction must belong to this program.
+    pub fn require_top_level(&self, instructions_sysvar: &AccountInfo) -> Result<()> {
+        if self.allow_cpi {
+            return Ok(());
+        }
+        let index = load_current_index_checked(instructions_sysvar)?;
+        let current = load_instruction_at_checked(index as usize, instructions_sysvar)?;
+        require_keys_eq!(current.program_id, crate::ID, StakingError::CpiNotAllowed);
+        Ok(())
+    }
+
+    /// Whether pause flags, APYs, the emission cap and authority transfers
+    /// go through `propose_admin_action` instead of the authority key.
+    pub fn has_admin_multisig(&self) -> bool {
+        self.admin_multisig != Pubkey::default()
+    }
+
+    /// Current holder of `role`.
+    pub fn role_holder(&self, role: AdminRole) -> Pubkey {
+        match role {
+            AdminRole::Pauser => self.pauser,
+            AdminRole::RateSetter => self.rate_setter,
+        }
+    }
+
+    /// Whether `key` may act as `role`; the authority always can.
+    pub fn has_role(&self, role: AdminRole, key: &Pubkey) -> bool {
+        *key == self.authority || *key == self.role_holder(role)
+    }
+
+    /// Hand `role` to `holder`, returning the previous holder.
+    pub fn set_role(&mut self, role: AdminRole, holder: Pubkey) -> Pubkey {
+        let slot = match role {
+            AdminRole::Pauser => &mut self.pauser,
+            AdminRole::RateSetter => &mut self.rate_setter,
+        };
+        std::mem::replace(slot, holder)
+    }
+
+    /// Whether rewards are paid in a different token than the one staked.
+    /// Flows that move tokens between the staking vault and the treasury
+    /// (compounding, unstake fees to the treasury, the reserve) need one mint.
+    pub fn is_dual_token(&self) -> bool {
+        self.reward_mint != self.staking_mint
+    }
+
+    /// Whether deposits mint receipt tokens that exits must burn.
+    pub fn has_receipt_mint(&self) -> bool {
+        self.receipt_mint != Pubkey::default()
+    }
+
+    /// Treasury balance that `withdraw_treasury` must leave behind: settled
+    /// rewards owed to positions plus unclaimed referral shares.
+    pub fn treasury_floor(&self) -> u64 {
+        self.pending_liability
+            .saturating_add(self.referral_liability)
+            .saturating_add(self.donation_liability)
+    }
+
+    /// Spread a donation of `amount` over everything staked. Returns the part
+    /// owed to stakers; the rounding remainder is left unreserved.
+    pub fn record_donation(&mut self, amount: u64) -> Result<u64> {
+        require!(self.total_staked > 0, StakingError::NoStakers);
+        let total_staked = self.total_staked as u128;
+        let per_token = (amount as u128)
+            .checked_mul(PRECISION)
+            .ok_or(StakingError::MathOverflow)?
+            / total_staked;
+        // per_token * total_staked <= amount * PRECISION, so this fits in u64
+        let distributed = (per_token * total_staked / PRECISION) as u64;
+
+        self.bonus_per_token = self
+            .bonus_per_token
+            .checked_add(per_token)
+            .ok_or(StakingError::MathOverflow)?;
+        self.donation_liability = self
+            .donation_liability
+            .checked_add(distributed)
+            .ok_or(StakingError::MathOverflow)?;
+        self.total_donated = self
+            .total_donated
+            .checked_add(amount)
+            .ok_or(StakingError::MathOverflow)?;
+        self.total_funded = self
+            .total_funded
+            .checked_add(amount)
+            .ok_or(StakingError::MathOverflow)?;
+        Ok(distributed)
+    }
+
+    /// Principal the staking vault must keep for stakers: everything staked
+    /// plus queued withdrawals not yet paid out.
+    pub fn staking_vault_principal(&self) -> u64 {
+        self.total_staked.saturating_add(self.total_unstake_requested)
+    }
+
+    /// Settled liabilities plus one year of accrual at the APYs in force at
+    /// `now`. The accrual part is capped at the remaining emission capacity,
+    /// since no more than that can be paid.
+    pub fn projected_outstanding_rewards(&self, now: i64) -> Result<u64> {
+        let yearly = self.projected_annual_rewards(now, None)?;
+        Ok(self
+            .treasury_floor()
+            .saturating_add(yearly.min(self.remaining_emission_capacity())))
+    }
+
+    /// One year of accrual on each tier's stake at its APY in force at `now`,
+    /// optionally with `extra` tokens added to a tier first. Bonuses are not
+    /// included.
+    pub fn projected_annual_rewards(&self, now: i64, extra: Option<(Tier, u64)>) -> Result<u64> {
+        // A shared rate pays the same total however much is staked
+        if self.reward_mode == RewardMode::RewardPerToken {
+            let staked = self.total_staked.saturating_add(extra.map_or(0, |(_, amount)| amount));
+            if staked == 0 {
+                return Ok(0);
+            }
+            return Ok(self.reward_rate_per_second.saturating_mul(SECONDS_PER_YEAR as u64));
+        }
+
+        let mut yearly: u128 = 0;
+        for tier in [Tier::Flex, Tier::Core, Tier::Prime, Tier::Permanent] {
+            let mut staked = self.tier_staked[tier as usize] as u128;
+            if let Some((extra_tier, amount)) = extra {
+                if extra_tier == tier {
+                    staked = staked.checked_add(amount as u128).ok_or(StakingError::MathOverflow)?;
+                }
+            }
+            let rate = staked
+                .checked_mul(self.get_apy_for_tier(tier, now) as u128)
+                .ok_or(StakingError::MathOverflow)?;
+            yearly = yearly.checked_add(rate).ok_or(StakingError::MathOverflow)?;
+        }
+        let yearly = u64::try_from(yearly / BASIS_POINTS_DENOMINATOR as u128)
+            .map_err(|_| StakingError::ConversionOverflow)?;
+        Ok(yearly)
+    }
+
+    /// Most yearly rewards the solvency check allows against a treasury of
+    /// `treasury_balance` liquid tokens; deployed funds count as treasury.
+    pub fn solvency_treasury_limit(&self, treasury_balance: u64) -> u64 {
+        let backing = (treasury_balance as u128).saturating_add(self.treasury_deployed as u128);
+        let limit = backing.saturating_mul(self.solvency_treasury_multiple_bps as u128)
+            / BASIS_POINTS_DENOMINATOR as u128;
+        u64::try_from(limit).unwrap_or(u64::MAX)
+    }
+
+    /// Accumulator value at `now`: the stored value plus the rate spread over
+    /// `total_staked` since `last_update_time`. Time with nothing staked
+    /// adds nothing, so those emissions are never paid.
+    pub fn reward_per_token(&self, now: i64) -> Result<u128> {
+        // Nothing is distributed while accrual is halted or after the end
+        let until = self.accrual_cutoff(now);
+        let halted = self.paused_at.map_or(0, |since| {
+            self.halt_end(until)
+                .saturating_sub(since.max(self.last_update_time))
+                .max(0)
+        });
+        let elapsed = until
+            .saturating_sub(self.last_update_time)
+            .saturating_sub(halted);
+        if self.total_staked == 0 || elapsed <= 0 {
+            return Ok(self.reward_per_token_stored);
+        }
+        let added = (self.reward_rate_per_second as u128)
+            .checked_mul(elapsed as u128)
+            .ok_or(StakingError::MathOverflow)?
+            .checked_mul(PRECISION)
+            .ok_or(StakingError::MathOverflow)?
+            / self.total_staked as u128;
+        self.reward_per_token_stored
+            .checked_add(added)
+            .ok_or_else(|| error!(StakingError::MathOverflow))
+    }
+
+    /// Store the accumulator as of `now`. Must run before `total_staked` or
+    /// the rate changes; a no-op in APY pools.
+    pub fn update_reward_per_token(&mut self, now: i64) -> Result<()> {
+        if self.reward_mode != RewardMode::RewardPerToken {
+            return Ok(());
+        }
+        self.reward_per_token_stored = self.reward_per_token(now)?;
+        self.last_update_time = self.last_update_time.max(now);
+        Ok(())
+    }
+
+    /// Advance `sequence` for the instruction being processed; its events
+    /// carry the new value.
+    pub fn next_sequence(&mut self) -> Result<u64> {
+        self.sequence = self
+            .sequence
+            .checked_add(1)
+            .ok_or(StakingError::MathOverflow)?;
+        Ok(self.sequence)
+    }
+
+    /// Whether a position last settled at `last_claim_time` has been idle
+    /// long enough at `now` for `gc_user_stake`.
+    pub fn is_gc_due(&self, last_claim_time: i64, now: i64) -> bool {
+        self.gc_inactivity_secs > 0
+            && now >= last_claim_time.saturating_add(self.gc_inactivity_secs)
+    }
+
+    /// APYs the rate setter's change at `now` is bounded against, opening a
+    /// new window from the live rates once the last one has run out.
+    pub fn rate_setter_baseline(&mut self, now: i64) -> [u16; 3] {
+        if self.rate_setter_window_start == 0
+            || now >= self.rate_setter_window_start.saturating_add(RATE_SETTER_WINDOW)
+        {
+            self.rate_setter_window_start = now;
+            self.rate_setter_window_apys = [self.flex_apy, self.core_apy, self.prime_apy];
+        }
+        self.rate_setter_window_apys
+    }
+
+    /// Whether an APY, decay or emission cap change is queued.
+    pub fn has_pending_params(&self) -> bool {
+        self.pending_apy_effective_at != 0
+            || self.pending_permanent_apy_effective_at != 0
+            || self.pending_rewards_end_time_effective_at != 0
+            || self.pending_decay_effective_at != 0
+            || self.pending_emission_cap_effective_at != 0
+    }
+
+    /// Drop the queued decay schedule.
+    pub fn clear_pending_decay(&mut self) {
+        self.pending_decay_interval_seconds = 0;
+        self.pending_decay_bps_per_interval = 0;
+        self.pending_min_apy_floor = 0;
+        self.pending_decay_effective_at = 0;
+    }
+
+    /// Tier APYs the pool is heading to: the queued change if one is
+    /// pending, the live rates otherwise.
+    pub fn scheduled_apys(&self) -> [u16; 3] {
+        if self.pending_apy_effective_at != 0 {
+            self.pending_apy
+        } else {
+            [self.flex_apy, self.core_apy, self.prime_apy]
+        }
+    }
+
+    /// Whether an oracle update may run at `now`: oracle mode is on and the
+    /// update interval has passed since the last one.
+    pub fn is_oracle_update_due(&self, now: i64) -> bool {
+        self.oracle.is_enabled()
+            && (self.last_oracle_update == 0
+                || now >= self.last_oracle_update.saturating_add(self.oracle.update_interval_secs))
+    }
+
+    /// Whether `take_snapshot` may run at `now`: snapshots are enabled and
+    /// either none was taken yet or a full interval has passed since the last.
+    pub fn is_snapshot_due(&self, now: i64) -> bool {
+        self.snapshot_interval_secs > 0
+            && (self.snapshot_count == 0
+                || now >= self.last_snapshot_at.saturating_add(self.snapshot_interval_secs))
+    }
+
+    /// `now`, clamped to `rewards_end_time` when one is set; accrual never
+    /// runs past it.
+    pub fn accrual_cutoff(&self, now: i64) -> i64 {
+        if self.rewards_end_time > 0 {
+            now.min(self.rewards_end_time)
+        } else {
+            now
+        }
+    }
+
+    /// Whether `rewards_end_time` has passed at `now`.
+    pub fn has_ended(&self, now: i64) -> bool {
+        self.rewards_end_time > 0 && now >= self.rewards_end_time
+    }
+
+    /// Seconds accrual has been halted by pauses up to `now`, counting the
+    /// running halt up to any `pause_expiry`; only grows. Positions snapshot it when they settle.
+    pub fn paused_seconds_at(&self, now: i64) -> i64 {
+        let running = self
+            .paused_at
+            .map_or(0, |since| self.halt_end(now).saturating_sub(since).max(0));
+        self.cumulative_paused_secs.saturating_add(running)
+    }
+
+    /// Start or stop the halt clock after `pause_flags` or
+    /// `accrue_while_paused` changed. The reward-per-token accumulator is
+    /// settled first, so it never grows over halted time.
+    pub fn sync_pause_clock(&mut self, now: i64) -> Result<()> {
+        self.update_reward_per_token(now)?;
+        self.accrue_liability(now);
+        let halted = self.is_stake_paused() && !self.accrue_while_paused;
+        match (self.paused_at, halted) {
+            (None, true) => self.paused_at = Some(now),
+            (Some(since), false) => {
+                self.cumulative_paused_secs = self
+                    .cumulative_paused_secs
+                    .checked_add(now.saturating_sub(since).max(0))
+                    .ok_or(StakingError::MathOverflow)?;
+                self.paused_at = None;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Count `amount` of new principal in `tier`.
+    pub fn add_staked(&mut self, tier: Tier, amount: u64) -> Result<()> {
+        self.total_staked = self
+            .total_staked
+            .checked_add(amount)
+            .ok_or(StakingError::MathOverflow)?;
+        let tier_staked = &mut self.tier_staked[tier as usize];
+        *tier_staked = tier_staked.checked_add(amount).ok_or(StakingError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Stop counting `amount` of principal in `tier`.
+    pub fn remove_staked(&mut self, tier: Tier, amount: u64) -> Result<()> {
+        self.total_staked = self
+            .total_staked
+            .checked_sub(amount)
+            .ok_or(StakingError::MathUnderflow)?;
+        let tier_staked = &mut self.tier_staked[tier as usize];
+        *tier_staked = tier_staked.checked_sub(amount).ok_or(StakingError::MathUnderflow)?;
+        Ok(())
+    }
+
+    /// Move `amount` of principal between tiers; `total_staked` is unchanged.
+    pub fn move_staked(&mut self, from: Tier, to: Tier, amount: u64) -> Result<()> {
+        self.remove_staked(from, amount)?;
+        self.add_staked(to, amount)
+    }
+
+    /// Count a newly opened position in `tier`.
+    pub fn add_staker(&mut self, tier: Tier) -> Result<()> {
+        self.staker_count = self
+            .staker_count
+            .checked_add(1)
+            .ok_or(StakingError::MathOverflow)?;
+        let count = &mut self.tier_staker_count[tier as usize];
+        *count = count.checked_add(1).ok_or(StakingError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Count a deposit in the lifetime statistics; `new_staker` on the
+    /// wallet's first stake into the pool, per its `StakerRecord`.
+    pub fn record_deposit(&mut self, amount: u64, new_staker: bool) -> Result<()> {
+        self.cumulative_staked = self
+            .cumulative_staked
+            .checked_add(amount)
+            .ok_or(StakingError::MathOverflow)?;
+        if new_staker {
+            self.unique_stakers_ever = self
+                .unique_stakers_ever
+                .checked_add(1)
+                .ok_or(StakingError::MathOverflow)?;
+        }
+        Ok(())
+    }
+
+    /// Count principal paid out of the pool in the lifetime statistics.
+    pub fn record_exit(&mut self, amount: u64) -> Result<()> {
+        self.cumulative_unstaked = self
+            .cumulative_unstaked
+            .checked_add(amount)
+            .ok_or(StakingError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Count a successful `claim_rewards` in the lifetime statistics.
+    pub fn record_lifetime_claim(&mut self) -> Result<()> {
+        self.cumulative_claims_count = self
+            .cumulative_claims_count
+            .checked_add(1)
+            .ok_or(StakingError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Stop counting a closed position in `tier`. The tier count saturates:
+    /// positions opened before the v5 migration were never counted in it.
+    pub fn remove_staker(&mut self, tier: Tier) -> Result<()> {
+        self.staker_count = self
+            .staker_count
+            .checked_sub(1)
+            .ok_or(StakingError::MathUnderflow)?;
+        let count = &mut self.tier_staker_count[tier as usize];
+        *count = count.saturating_sub(1);
+        Ok(())
+    }
+
+    /// Move an open position between tiers; `staker_count` is unchanged.
+    pub fn move_staker(&mut self, from: Tier, to: Tier) -> Result<()> {
+        let from_count = &mut self.tier_staker_count[from as usize];
+        *from_count = from_count.saturating_sub(1);
+        let to_count = &mut self.tier_staker_count[to as usize];
+        *to_count = to_count.checked_add(1).ok_or(StakingError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Add a deposit to the current inflow window, starting a new window if the
+    /// previous one has elapsed. Unstakes never give headroom back.
+    pub fn record_inflow(&mut self, now: i64, amount: u64) -> Result<()> {
+        if now.saturating_sub(self.inflow_window_start) >= SECONDS_PER_DAY {
+            self.inflow_window_start = now;
+            self.inflow_in_window = 0;
+        }
+        self.inflow_in_window = self
+            .inflow_in_window
+            .checked_add(amount)
+            .ok_or(StakingError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Seconds left before a Flex position started at `stake_start_time` may
+    /// unstake (0 once the cooldown has passed or when it is disabled).
+    pub fn flex_cooldown_remaining(&self, stake_start_time: i64, now: i64) -> i64 {
+        stake_start_time
+            .saturating_add(self.flex_min_stake_secs)
+            .saturating_sub(now)
+            .max(0)
+    }
+
+    /// Seconds left before a position last deposited into at `last_stake_time`
+    /// may unstake (0 once the minimum duration has passed or when disabled).
+    pub fn min_stake_duration_remaining(&self, last_stake_time: i64, now: i64) -> i64 {
+        last_stake_time
+            .saturating_add(self.min_stake_duration_seconds)
+            .saturating_sub(now)
+            .max(0)
+    }
+
+    /// Seconds left before a Flex withdrawal requested at `requested_at` may
+    /// be executed (0 once the queue delay has passed or when it is disabled).
+    /// Uses the current setting, so changing it also moves queued requests.
+    pub fn withdrawal_cooldown_remaining(&self, requested_at: i64, now: i64) -> i64 {
+        requested_at
+            .saturating_add(self.flex_cooldown_seconds)
+            .saturating_sub(now)
+            .max(0)
+    }
+
+    /// Reserve tokens due to the treasury at `now`: the drip rate times the time
+    /// since the last drip, limited to what the reserve holds.
+    pub fn pending_drip(&self, now: i64, reserve_balance: u64) -> u64 {
+        let elapsed = now.saturating_sub(self.last_drip_at).max(0) as u64;
+        self.drip_rate_per_sec
+            .saturating_mul(elapsed)
+            .min(reserve_balance)
+    }
+
+    /// Drop the part of the time since the last drip that `reserve_balance`
+    /// cannot cover, by moving `last_drip_at` forward. Called before a
+    /// top-up, so new funds never pay for time the reserve was dry.
+    pub fn forfeit_uncovered_drip(&mut self, now: i64, reserve_balance: u64) {
+        if self.drip_rate_per_sec == 0 {
+            return;
+        }
+        let covered_secs = i64::try_from(reserve_balance / self.drip_rate_per_sec).unwrap_or(i64::MAX);
+        self.last_drip_at = self.last_drip_at.max(now.saturating_sub(covered_secs));
+    }
+
+    /// Whether `program` is on the pool's yield adapter whitelist.
+    pub fn is_yield_adapter(&self, program: &Pubkey) -> bool {
+        *program != Pubkey::default() && self.yield_adapters.contains(program)
+    }
+
+    /// Most that may be deployed in total given `liquid` treasury tokens;
+    /// deployed funds count toward the treasury the cap is taken from.
+    pub fn max_treasury_deployable(&self, liquid: u64) -> u64 {
+        let backing = (liquid as u128).saturating_add(self.treasury_deployed as u128);
+        let cap = backing.saturating_mul(self.max_treasury_deploy_bps as u128)
+            / BASIS_POINTS_DENOMINATOR as u128;
+        u64::try_from(cap).unwrap_or(u64::MAX)
+    }
+
+    /// Claims already counted against the per-epoch claim limit at `now`.
+    ///
+    /// Returns zero once the current epoch has elapsed; the epoch itself is only
+    /// rolled forward by `record_claim`.
+    pub fn claims_used(&self, now: i64) -> u32 {
+        if now.saturating_sub(self.epoch_start) >= self.claim_epoch_secs {
+            0
+        } else {
+            self.claims_this_epoch
+        }
+    }
+
+    /// Seconds a position last settled at `last_claim_time` must still wait
+    /// at `now` before it may claim; 0 once `min_claim_interval_seconds`
+    /// has passed, and always 0 when the interval is disabled.
+    pub fn claim_interval_remaining(&self, last_claim_time: i64, now: i64) -> i64 {
+        last_claim_time
+            .saturating_add(self.min_claim_interval_seconds)
+            .saturating_sub(now)
+            .max(0)
+    }
+
+    /// Whether the claim limit (0 = disabled) leaves room for another claim.
+    pub fn is_claim_allowed(&self, now: i64) -> bool {
+        self.max_claims_per_epoch == 0 || self.claims_used(now) < self.max_claims_per_epoch
+    }
+
+    /// Count a claim in the current epoch, starting a new epoch if the previous
+    /// one has elapsed.
+    pub fn record_claim(&mut self, now: i64) -> Result<()> {
+        if now.saturating_sub(self.epoch_start) >= self.claim_epoch_secs {
+            self.epoch_start = now;
+            self.claims_this_epoch = 0;
+        }
+        self.claims_this_epoch = self
+            .claims_this_epoch
+            .checked_add(1)
+            .ok_or(StakingError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Rewards already paid against the per-epoch emission limit at `now`.
+    ///
+    /// Returns zero once the current epoch has elapsed; the epoch itself is only
+    /// rolled forward by `record_epoch_emission`.
+    pub fn epoch_emission_used(&self, now: i64) -> u64 {
+        if now.saturating_sub(self.current_epoch_start) >= self.epoch_length_seconds {
+            0
+        } else {
+            self.distributed_this_epoch
+        }
+    }
+
+    /// What the emission limit still lets claims pay out this epoch;
+    /// unbounded while the limit is disabled.
+    pub fn epoch_emission_headroom(&self, now: i64) -> u64 {
+        if self.max_emission_per_epoch == 0 {
+            return u64::MAX;
+        }
+        self.max_emission_per_epoch
+            .saturating_sub(self.epoch_emission_used(now))
+    }
+
+    /// Count `amount` against the current epoch, starting a new epoch if the
+    /// previous one has elapsed.
+    pub fn record_epoch_emission(&mut self, now: i64, amount: u64) -> Result<()> {
+        if now.saturating_sub(self.current_epoch_start) >= self.epoch_length_seconds {
+            self.current_epoch_start = now;
+            self.distributed_this_epoch = 0;
+        }
+        self.distributed_this_epoch = self
+            .distributed_this_epoch
+            .checked_add(amount)
+            .ok_or(StakingError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Whether a position last settled at `last_claim_time` has stopped accruing
+    /// because the accrual horizon has been exceeded (0 = unlimited).
+    pub fn is_accrual_clamped(&self, last_claim_time: i64, now: i64) -> bool {
+        self.max_accrual_horizon_secs > 0
+            && now.saturating_sub(last_claim_time) > self.max_accrual_horizon_secs
+    }
+
+    /// Seconds the treasury has been known to be empty up to `now`, counting
+    /// the open window; only grows. Positions snapshot it when they settle,
+    /// so every window since, however many, is excluded from their accrual.
+    ///
+    /// Windows only open and close when an instruction touches the pool: a
+    /// draining claim, compound or withdrawal opens one, and funding closes it.
+    pub fn unfunded_seconds_at(&self, now: i64) -> i64 {
+        let open = self
+            .treasury_empty_since
+            .map_or(0, |since| now.saturating_sub(since).max(0));
+        self.cumulative_unfunded_secs.saturating_add(open)
+    }
+
+    /// Close the open empty-treasury window at `now`, if any, returning
+    /// when it opened. `accrued_liability` is brought forward first, so it
+    /// never grows over the closed window.
+    pub fn close_empty_window(&mut self, now: i64) -> Result<Option<i64>> {
+        let Some(since) = self.treasury_empty_since else {
+            return Ok(None);
+        };
+        self.accrue_liability(now);
+        self.cumulative_unfunded_secs = self
+            .cumulative_unfunded_secs
+            .checked_add(now.saturating_sub(since).max(0))
+            .ok_or(StakingError::MathOverflow)?;
+        self.last_empty_window_end = now;
+        self.treasury_empty_since = None;
+        Ok(Some(since))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::MIN_GC_INACTIVITY;
+
+    fn zeroed_pool() -> StakePool {
+        StakePool::try_deserialize_unchecked(&mut &[0u8; StakePool::LEN][..]).unwrap()
+    }
+
+    #[test]
+    fn v0_pool_migrates_after_growing() {
+        let mut pool = zeroed_pool();
+        pool.total_staked = 1_234;
+        pool.version = STAKE_POOL_VERSION;
+        // `None` serializes to a single byte; fill the options so the
+        // account is the full `LEN` it is allocated with
+        pool.treasury_empty_since = Some(0);
+        pool.paused_at = Some(0);
+        let mut data = Vec::new();
+        pool.try_serialize(&mut data).unwrap();
+        assert_eq!(data.len(), StakePool::LEN);
+
+        // A v0 account is the v1 layout without the trailing version byte;
+        // v2 appended the loyalty schedule, v3 `total_funded`, v4 the epoch
+        // emission limit, v5 the per-tier staker counts, v6 `allow_cpi`,
+        // v7 `unaccounted_principal`, v8 the donation accumulator, v9 the
+        // admin roles, v10 the lifetime counters, v11 the halt clock, v12
+        // `rewards_end_time`, v13 `sequence`, v14 `pause_expiry`, v15 the
+        // snapshot schedule, v16 the oracle configuration, v17 the receipt
+        // mint, v18 the boost campaigns, v19 `gc_inactivity_secs`, v20
+        // `min_claim_interval_seconds`, v21 `downgrade_after_lock`, v22
+        // `whitelist_enabled`, v23 the queued decay schedule, v24 the rate
+        // setter window, v25 the accrued liability, v26 the folded APY
+        // history, v27 the queued Permanent APY and v28 the queued rewards
+        // end time
+        let v1_len = StakePool::LEN
+            - (8 + 2) * MAX_LOYALTY_LEVELS
+            - 8
+            - (8 * 4)
+            - (8 * 4)
+            - 1
+            - 8
+            - (16 + 8 + 8)
+            - (32 * 2)
+            - (8 * 4)
+            - (1 + (1 + 8) + 8)
+            - 8
+            - 8
+            - 8
+            - (8 * 3)
+            - (OracleApyConfig::LEN + 8)
+            - 32
+            - (ApyBoost::LEN * MAX_APY_BOOSTS + 8)
+            - 8
+            - 8
+            - 1
+            - 1
+            - (8 + 2 + 2 + 8)
+            - (8 + 2 * 3)
+            - (8 + 8)
+            - (16 * 4 + 8 + 8)
+            - (2 + 8)
+            - (8 + 8);
+        data.truncate(v1_len - 1);
+        assert!(StakePool::try_deserialize(&mut &data[..]).is_err());
+
+        // `realloc` zero-fills the new tail, which reads as version 0
+        data.resize(StakePool::LEN, 0);
+        let mut migrated = StakePool::try_deserialize(&mut &data[..]).unwrap();
+        assert_eq!(migrated.version, 0);
+        migrated.migrate().unwrap();
+        assert_eq!(migrated.version, STAKE_POOL_VERSION);
+        assert_eq!(migrated.total_staked, 1_234);
+
+        // Migrating a current account changes nothing; newer ones are refused
+        migrated.migrate().unwrap();
+        assert_eq!(migrated.version, STAKE_POOL_VERSION);
+        migrated.version = STAKE_POOL_VERSION + 1;
+        assert!(migrated.migrate().is_err());
+    }
+
+    #[test]
+    fn v2_migration_backfills_total_funded() {
+        let mut pool = zeroed_pool();
+        pool.version = 2;
+        pool.total_distributed = 500;
+        pool.migrate().unwrap();
+        assert_eq!(pool.version, STAKE_POOL_VERSION);
+        assert_eq!(pool.total_funded, 500);
+        assert!(pool.treasury_consistency().is_ok());
+    }
+
+    #[test]
+    fn v8_migration_gives_every_role_to_the_authority() {
+        let mut pool = zeroed_pool();
+        pool.version = 8;
+        pool.authority = Pubkey::new_unique();
+        pool.migrate().unwrap();
+        assert_eq!(pool.pauser, pool.authority);
+        assert_eq!(pool.rate_setter, pool.authority);
+    }
+
+    #[test]
+    fn v9_migration_seeds_unique_stakers_from_open_positions() {
+        let mut pool = zeroed_pool();
+        pool.version = 9;
+        pool.staker_count = 42;
+        pool.migrate().unwrap();
+        assert_eq!(pool.unique_stakers_ever, 42);
+        assert_eq!(pool.cumulative_staked, 0);
+    }
+
+    #[test]
+    fn sequence_only_moves_forward() {
+        let mut pool = zeroed_pool();
+        assert_eq!(pool.next_sequence().unwrap(), 1);
+        assert_eq!(pool.next_sequence().unwrap(), 2);
+        assert_eq!(pool.sequence, 2);
+
+        // Never wraps back to a number indexers have already seen
+        pool.sequence = u64::MAX;
+        assert!(pool.next_sequence().is_err());
+        assert_eq!(pool.sequence, u64::MAX);
+    }
+
+    #[test]
+    fn halt_clock_runs_only_while_paused_with_accrual_off() {
+        let mut pool = zeroed_pool();
+        pool.pause_flags = StakePool::PAUSE_STAKE_BIT;
+        pool.accrue_while_paused = true;
+        pool.sync_pause_clock(100).unwrap();
+        assert_eq!(pool.paused_at, None);
+
+        // Turning accrual off mid-pause starts the clock there
+        pool.accrue_while_paused = false;
+        pool.sync_pause_clock(150).unwrap();
+        assert_eq!(pool.paused_seconds_at(170), 20);
+
+        pool.pause_flags = 0;
+        pool.sync_pause_clock(200).unwrap();
+        assert_eq!(pool.paused_at, None);
+        assert_eq!(pool.paused_seconds_at(1_000), 50);
+
+        // Only the stake bit halts accrual
+        pool.pause_flags = StakePool::PAUSE_CLAIM_BIT;
+        pool.sync_pause_clock(300).unwrap();
+        assert_eq!(pool.paused_seconds_at(1_000), 50);
+    }
+
+    #[test]
+    fn pause_expires_on_the_expiry_second() {
+        let mut pool = zeroed_pool();
+        pool.pause_flags = StakePool::PAUSE_STAKE_BIT;
+        pool.pause_expiry = 200;
+        pool.sync_pause_clock(100).unwrap();
+
+        pool.expire_pause(199).unwrap();
+        assert!(pool.is_stake_paused());
+        assert!(pool.is_pause_expired(200));
+
+        // The halt is capped at the expiry before and after the lazy clear
+        assert_eq!(pool.paused_seconds_at(500), 100);
+        pool.expire_pause(500).unwrap();
+        assert!(!pool.is_stake_paused());
+        assert_eq!((pool.pause_expiry, pool.paused_at), (0, None));
+        assert_eq!(pool.paused_seconds_at(1_000), 100);
+    }
+
+    #[test]
+    fn late_pause_expiry_does_not_lose_accrual() {
+        let mut pool = zeroed_pool();
+        pool.reward_mode = RewardMode::RewardPerToken;
+        pool.reward_rate_per_second = 1;
+        pool.total_staked = 1;
+        pool.pause_flags = StakePool::PAUSE_STAKE_BIT;
+        pool.pause_expiry = 200;
+        pool.sync_pause_clock(100).unwrap();
+
+        // [0, 100) and [200, 300) accrue, whether or not the flag was cleared
+        let expected = 200 * PRECISION;
+        assert_eq!(pool.reward_per_token(300).unwrap(), expected);
+        pool.expire_pause(250).unwrap();
+        assert_eq!(pool.reward_per_token(300).unwrap(), expected);
+    }
+
+    #[test]
+    fn snapshots_are_due_once_per_interval() {
+        let mut pool = zeroed_pool();
+        assert!(!pool.is_snapshot_due(1_000));
+
+        pool.snapshot_interval_secs = 100;
+        assert!(pool.is_snapshot_due(0));
+
+        pool.snapshot_count = 1;
+        pool.last_snapshot_at = 1_000;
+        assert!(!pool.is_snapshot_due(1_099));
+        assert!(pool.is_snapshot_due(1_100));
+    }
+
+    #[test]
+    fn oracle_updates_are_due_once_per_interval() {
+        let mut pool = zeroed_pool();
+        assert!(!pool.is_oracle_update_due(1_000));
+
+        pool.oracle.price_account = Pubkey::new_unique();
+        pool.oracle.update_interval_secs = 100;
+        assert!(pool.is_oracle_update_due(0));
+
+        pool.last_oracle_update = 1_000;
+        assert!(!pool.is_oracle_update_due(1_099));
+        assert!(pool.is_oracle_update_due(1_100));
+    }
+
+    #[test]
+    fn rate_setter_window_holds_its_baseline() {
+        let mut pool = zeroed_pool();
+        pool.flex_apy = 500;
+        pool.core_apy = 1_000;
+        pool.prime_apy = 1_500;
+        assert_eq!(pool.rate_setter_baseline(1_000), [500, 1_000, 1_500]);
+
+        // Later calls in the window compare against where it opened
+        pool.flex_apy = 700;
+        assert_eq!(pool.rate_setter_baseline(1_000 + RATE_SETTER_WINDOW - 1), [500, 1_000, 1_500]);
+        assert_eq!(pool.rate_setter_baseline(1_000 + RATE_SETTER_WINDOW), [700, 1_000, 1_500]);
+        assert_eq!(pool.rate_setter_window_start, 1_000 + RATE_SETTER_WINDOW);
+    }
+
+    #[test]
+    fn streak_counts_one_claim_per_window() {
+        let mut pool = zeroed_pool();
+        pool.streak_window_secs = 100;
+
+        // The first claim opens the window [1_000, 1_100]
+        assert_eq!(pool.next_streak(0, 0, 1_000).unwrap(), (0, 1_200));
+        // Claiming again in the same window counts for nothing
+        assert_eq!(pool.next_streak(0, 1_200, 1_099).unwrap(), (0, 1_200));
+        // The next window extends the streak once
+        assert_eq!(pool.next_streak(0, 1_200, 1_101).unwrap(), (1, 1_300));
+        assert_eq!(pool.next_streak(1, 1_300, 1_150).unwrap(), (1, 1_300));
+        assert_eq!(pool.next_streak(1, 1_300, 1_300).unwrap(), (2, 1_400));
+        // Skipping a whole window starts over
+        assert_eq!(pool.next_streak(2, 1_400, 1_401).unwrap(), (0, 1_601));
+    }
+
+    #[test]
+    fn scheduled_apys_prefer_a_queued_change() {
+        let mut pool = zeroed_pool();
+        pool.flex_apy = 500;
+        pool.core_apy = 1_000;
+        pool.prime_apy = 1_500;
+        assert_eq!(pool.scheduled_apys(), [500, 1_000, 1_500]);
+
+        pool.pending_apy = [600, 1_100, 1_600];
+        pool.pending_apy_effective_at = 2_000;
+        assert_eq!(pool.scheduled_apys(), [600, 1_100, 1_600]);
+    }
+
+    #[test]
+    fn claim_interval_opens_at_the_boundary_second() {
+        let mut pool = zeroed_pool();
+        assert_eq!(pool.claim_interval_remaining(1_000, 1_000), 0);
+
+        pool.min_claim_interval_seconds = 60;
+        assert_eq!(pool.claim_interval_remaining(1_000, 1_000), 60);
+        assert_eq!(pool.claim_interval_remaining(1_000, 1_059), 1);
+        assert_eq!(pool.claim_interval_remaining(1_000, 1_060), 0);
+        assert_eq!(pool.claim_interval_remaining(1_000, 5_000), 0);
+    }
+
+    #[test]
+    fn gc_waits_for_the_full_inactivity_period() {
+        let mut pool = zeroed_pool();
+        assert!(!pool.is_gc_due(0, i64::MAX));
+
+        pool.gc_inactivity_secs = MIN_GC_INACTIVITY;
+        assert!(!pool.is_gc_due(1_000, 1_000 + MIN_GC_INACTIVITY - 1));
+        assert!(pool.is_gc_due(1_000, 1_000 + MIN_GC_INACTIVITY));
+    }
+
+    #[test]
+    fn boost_campaigns_are_never_retroactive_or_overlapping() {
+        let mut pool = zeroed_pool();
+        let boost = |start_time, end_time| ApyBoost { multiplier_bps: 20_000, start_time, end_time };
+
+        assert!(pool.schedule_apy_boost(boost(999, 2_000), 1_000).is_err());
+        assert!(pool.schedule_apy_boost(boost(1_000, 1_000), 1_000).is_err());
+        assert!(pool
+            .schedule_apy_boost(boost(1_000, 1_000 + MAX_APY_BOOST_DURATION + 1), 1_000)
+            .is_err());
+        let no_op = ApyBoost { multiplier_bps: 10_000, ..boost(1_000, 2_000) };
+        assert!(pool.schedule_apy_boost(no_op, 1_000).is_err());
+
+        pool.schedule_apy_boost(boost(1_000, 2_000), 1_000).unwrap();
+        assert!(pool.schedule_apy_boost(boost(1_999, 3_000), 1_000).is_err());
+        pool.schedule_apy_boost(boost(2_000, 3_000), 1_000).unwrap();
+        assert_eq!(pool.latest_apy_boost(), Some(boost(2_000, 3_000)));
+        assert_eq!(pool.apy_boost_count, 2);
+    }
+
+    #[test]
+    fn lifetime_counters_survive_a_full_exit() {
+        let mut pool = zeroed_pool();
+        pool.record_deposit(1_000, true).unwrap();
+        pool.record_exit(1_000).unwrap();
+        // Re-staking into the same account is not a new staker
+        pool.record_deposit(500, false).unwrap();
+        pool.record_lifetime_claim().unwrap();
+
+        assert_eq!(pool.cumulative_staked, 1_500);
+        assert_eq!(pool.cumulative_unstaked, 1_000);
+        assert_eq!(pool.unique_stakers_ever, 1);
+        assert_eq!(pool.cumulative_claims_count, 1);
+
+        pool.cumulative_staked = u64::MAX;
+        assert!(pool.record_deposit(1, false).is_err());
+    }
+
+    #[test]
+    fn roles_are_held_by_their_holder_and_the_authority() {
+        let mut pool = zeroed_pool();
+        let authority = Pubkey::new_unique();
+        pool.authority = authority;
+        let ops = Pubkey::new_unique();
+        assert_eq!(pool.set_role(AdminRole::Pauser, ops), Pubkey::default());
+
+        assert!(pool.has_role(AdminRole::Pauser, &ops));
+        assert!(pool.has_role(AdminRole::Pauser, &authority));
+        assert!(!pool.has_role(AdminRole::RateSetter, &ops));
+        assert!(!pool.has_role(AdminRole::Pauser, &Pubkey::new_unique()));
+        assert_eq!(pool.role_holder(AdminRole::Pauser), ops);
+    }
+
+    #[test]
+    fn treasury_consistency_requires_funding_to_cover_payouts() {
+        let mut pool = zeroed_pool();
+        assert!(pool.treasury_consistency().is_ok());
+
+        pool.total_funded = 1_000;
+        pool.total_distributed = 1_000;
+        assert!(pool.treasury_consistency().is_ok());
+
+        // Paying out tokens that only arrived by direct transfer
+        pool.total_distributed = 1_001;
+        assert!(pool.treasury_consistency().is_err());
+    }
+
+    #[test]
+    fn staking_vault_principal_includes_queued_withdrawals() {
+        let mut pool = zeroed_pool();
+        pool.add_staked(Tier::Flex, 700).unwrap();
+        pool.add_staked(Tier::Core, 300).unwrap();
+        pool.remove_staked(Tier::Flex, 200).unwrap();
+        pool.total_unstake_requested = 200;
+        assert_eq!(pool.staking_vault_principal(), 1_000);
+
+        pool.total_staked = u64::MAX;
+        assert_eq!(pool.staking_vault_principal(), u64::MAX);
+    }
+
+    #[test]
+    fn claim_fee_rounds_down_on_tiny_claims() {
+        let mut pool = zeroed_pool();
+        pool.claim_fee_bps = 500;
+        assert_eq!(pool.split_claim_fee(0).unwrap(), (0, 0));
+        assert_eq!(pool.split_claim_fee(1).unwrap(), (1, 0));
+        // 19 * 5% = 0.95
+        assert_eq!(pool.split_claim_fee(19).unwrap(), (19, 0));
+        assert_eq!(pool.split_claim_fee(20).unwrap(), (19, 1));
+        assert_eq!(pool.split_claim_fee(39).unwrap(), (38, 1));
+    }
+
+    #[test]
+    fn claim_fee_never_exceeds_the_claim() {
+        let mut pool = zeroed_pool();
+        pool.claim_fee_bps = 500;
+        for gross in [0, 1, 7, 10_000, u64::MAX / 3, u64::MAX] {
+            let (net, fee) = pool.split_claim_fee(gross).unwrap();
+            assert!(fee <= gross);
+            assert_eq!(net + fee, gross);
+        }
+    }
+
+    #[test]
+    fn zero_fee_passes_the_claim_through() {
+        let pool = zeroed_pool();
+        assert_eq!(pool.split_claim_fee(12_345).unwrap(), (12_345, 0));
+    }
+
+    #[test]
+    fn unstake_fee_uses_the_tier_slot() {
+        let mut pool = zeroed_pool();
+        pool.unstake_fee_bps = [10, 50, 100];
+        assert_eq!(pool.split_unstake_fee(Tier::Flex, 1_000_000).unwrap(), (999_000, 1_000));
+        assert_eq!(pool.split_unstake_fee(Tier::Core, 1_000_000).unwrap(), (995_000, 5_000));
+        assert_eq!(pool.split_unstake_fee(Tier::Prime, 1_000_000).unwrap(), (990_000, 10_000));
+        assert_eq!(pool.split_unstake_fee(Tier::Permanent, 1_000_000).unwrap(), (1_000_000, 0));
+    }
+
+    #[test]
+    fn unstake_fee_rounds_down_on_single_lamports() {
+        let mut pool = zeroed_pool();
+        pool.unstake_fee_bps = [100; 3];
+        assert_eq!(pool.split_unstake_fee(Tier::Flex, 1).unwrap(), (1, 0));
+        assert_eq!(pool.split_unstake_fee(Tier::Flex, 99).unwrap(), (99, 0));
+        assert_eq!(pool.split_unstake_fee(Tier::Flex, 100).unwrap(), (99, 1));
+    }
+
+    #[test]
+    fn early_unstake_penalty_uses_the_locked_tier_slot() {
+        let mut pool = zeroed_pool();
+        pool.early_unstake_penalty_bps = [1_000, 2_000];
+        assert_eq!(
+            pool.split_early_unstake_penalty(Tier::Core, 1_000_000).unwrap(),
+            (900_000, 100_000)
+        );
+        assert_eq!(
+            pool.split_early_unstake_penalty(Tier::Prime, 1_000_000).unwrap(),
+            (800_000, 200_000)
+        );
+        assert_eq!(pool.split_early_unstake_penalty(Tier::Flex, 1_000_000).unwrap(), (1_000_000, 0));
+        assert_eq!(
+            pool.split_early_unstake_penalty(Tier::Permanent, 1_000_000).unwrap(),
+            (1_000_000, 0)
+        );
+    }
+
+    #[test]
+    fn early_unstake_penalty_rounds_up() {
+        let mut pool = zeroed_pool();
+        pool.early_unstake_penalty_bps = [1_000, 2_000];
+        assert_eq!(pool.split_early_unstake_penalty(Tier::Core, 1).unwrap(), (0, 1));
+        assert_eq!(pool.split_early_unstake_penalty(Tier::Core, 9).unwrap(), (8, 1));
+        assert_eq!(pool.split_early_unstake_penalty(Tier::Core, 10).unwrap(), (9, 1));
+        assert_eq!(pool.split_early_unstake_penalty(Tier::Core, 11).unwrap(), (9, 2));
+        assert_eq!(pool.split_early_unstake_penalty(Tier::Prime, 0).unwrap(), (0, 0));
+    }
+
+    #[test]
+    fn early_unstake_penalty_handles_the_full_supply() {
+        let mut pool = zeroed_pool();
+        pool.early_unstake_penalty_bps = [crate::constants::MAX_EARLY_UNSTAKE_PENALTY_BPS; 2];
+        let (net, penalty) = pool.split_early_unstake_penalty(Tier::Prime, u64::MAX).unwrap();
+        assert_eq!(net + penalty, u64::MAX);
+    }
+
+    #[test]
+    fn apy_seconds_split_at_each_change() {
+        let mut pool = zeroed_pool();
+        pool.flex_apy = 1_000;
+        assert_eq!(pool.apy_seconds_for_tier(Tier::Flex, 0, 100).unwrap(), 100_000);
+
+        pool.checkpoint_apy(40).unwrap();
+        pool.flex_apy = 2_000;
+        pool.checkpoint_apy(70).unwrap();
+        pool.flex_apy = 0;
+
+        // 40s at 10%, 30s at 20%, 30s at 0%
+        assert_eq!(pool.apy_seconds_for_tier(Tier::Flex, 0, 100).unwrap(), 100_000);
+        // Starting after a change only sees the later rates
+        assert_eq!(pool.apy_seconds_for_tier(Tier::Flex, 50, 100).unwrap(), 40_000);
+        assert_eq!(pool.apy_seconds_for_tier(Tier::Flex, 80, 100).unwrap(), 0);
+        // Other tiers keep their own history
+        assert_eq!(pool.apy_seconds_for_tier(Tier::Core, 0, 100).unwrap(), 0);
+    }
+
+    #[test]
+    fn apy_history_folds_oldest_checkpoint_when_full() {
+        let mut pool = zeroed_pool();
+        for i in 0..=MAX_APY_CHECKPOINTS as i64 {
+            pool.flex_apy = (i as u16 + 1) * 100;
+            pool.checkpoint_apy(i * 10 + 10).unwrap();
+        }
+        pool.flex_apy = 0;
+
+        // The 100bp period before t=10 was folded out of the history but
+        // still prices [0, 10)
+        assert_eq!(pool.apy_epoch, MAX_APY_CHECKPOINTS as u64 + 1);
+        assert_eq!(pool.apy_folded_until, 10);
+        assert_eq!(pool.folded_apy_seconds[Tier::Flex as usize], 1_000);
+        assert_eq!(pool.apy_seconds_for_tier(Tier::Flex, 0, 20).unwrap(), 1_000 + 2_000);
+        assert_eq!(pool.apy_seconds_for_tier(Tier::Flex, 10, 30).unwrap(), 2_000 + 3_000);
+    }
+
+    #[test]
+    fn apy_changes_beyond_the_history_do_not_reprice_elapsed_time() {
+        let mut pool = zeroed_pool();
+        pool.flex_apy = 1_000;
+        let decay = pool.decay_schedule();
+
+        // A cut at t=1000, then the same rates resubmitted: only one checkpoint
+        pool.set_apy_schedule([100, 0, 0, 0], decay, 1_000).unwrap();
+        for _ in 0..2 * MAX_APY_CHECKPOINTS {
+            pool.set_apy_schedule([100, 0, 0, 0], decay, 1_000).unwrap();
+        }
+        assert_eq!(pool.apy_epoch, 1);
+
+        // Real changes past the history's length fold the 10% period instead
+        // of repricing it at the new rate
+        for i in 0..=MAX_APY_CHECKPOINTS as u16 {
+            pool.set_apy_schedule([101 + i % 2, 0, 0, 0], decay, 1_000).unwrap();
+        }
+        pool.set_apy_schedule([100, 0, 0, 0], decay, 1_000).unwrap();
+        assert!(pool.apy_epoch > MAX_APY_CHECKPOINTS as u64);
+        assert_eq!(pool.apy_seconds_for_tier(Tier::Flex, 0, 1_000).unwrap(), 1_000_000);
+        assert_eq!(pool.apy_seconds_for_tier(Tier::Flex, 500, 2_000).unwrap(), 500_000 + 100_000);
+    }
+
+    #[test]
+    fn decay_steps_down_to_the_floor() {
+        let mut pool = zeroed_pool();
+        pool.created_at = 1_000;
+        pool.flex_apy = 1_000;
+        pool.permanent_apy = 200;
+        pool.decay_interval_seconds = 100;
+        pool.decay_bps_per_interval = 300;
+        pool.min_apy_floor = 300;
+
+        assert_eq!(pool.get_apy_for_tier(Tier::Flex, 1_000), 1_000);
+        assert_eq!(pool.get_apy_for_tier(Tier::Flex, 1_099), 1_000);
+        assert_eq!(pool.get_apy_for_tier(Tier::Flex, 1_100), 700);
+        assert_eq!(pool.get_apy_for_tier(Tier::Flex, 1_200), 400);
+        assert_eq!(pool.get_apy_for_tier(Tier::Flex, 1_300), 300);
+        assert_eq!(pool.get_apy_for_tier(Tier::Flex, i64::MAX), 300);
+        // A rate already under the floor is left alone
+        assert_eq!(pool.get_apy_for_tier(Tier::Permanent, 5_000), 200);
+        assert_eq!(pool.base_apy_for_tier(Tier::Flex), 1_000);
+    }
+
+    #[test]
+    fn apy_seconds_integrate_across_decay_steps() {
+        let mut pool = zeroed_pool();
+        pool.created_at = 1_000;
+        pool.flex_apy = 1_000;
+        pool.decay_interval_seconds = 100;
+        pool.decay_bps_per_interval = 300;
+        pool.min_apy_floor = 300;
+
+        // 50s at 1000, 100s at 700, 100s at 400, 250s at the 300 floor
+        assert_eq!(
+            pool.apy_seconds_for_tier(Tier::Flex, 1_050, 1_550).unwrap(),
+            50_000 + 70_000 + 40_000 + 75_000
+        );
+        // Splitting the interval gives the same total
+        assert_eq!(
+            pool.apy_seconds_for_tier(Tier::Flex, 1_050, 1_180).unwrap()
+                + pool.apy_seconds_for_tier(Tier::Flex, 1_180, 1_550).unwrap(),
+            235_000
+        );
+    }
+
+    #[test]
+    fn decay_change_does_not_reprice_elapsed_time() {
+        let mut pool = zeroed_pool();
+        pool.flex_apy = 1_000;
+        pool.decay_interval_seconds = 100;
+        pool.decay_bps_per_interval = 100;
+
+        // Decay cleared at t=250: [0,100) 1000, [100,200) 900, [200,250) 800,
+        // then the undecayed base from t=250
+        pool.checkpoint_apy(250).unwrap();
+        pool.decay_interval_seconds = 0;
+        pool.decay_bps_per_interval = 0;
+        assert_eq!(
+            pool.apy_seconds_for_tier(Tier::Flex, 0, 300).unwrap(),
+            100_000 + 90_000 + 40_000 + 50_000
+        );
+    }
+
+    #[test]
+    fn unfunded_clock_counts_every_empty_window() {
+        let mut pool = zeroed_pool();
+        pool.treasury_empty_since = Some(100);
+        assert_eq!(pool.unfunded_seconds_at(130), 30);
+        assert_eq!(pool.close_empty_window(150).unwrap(), Some(100));
+        assert_eq!(pool.close_empty_window(160).unwrap(), None);
+
+        // A second window adds to the first instead of replacing it
+        pool.treasury_empty_since = Some(300);
+        assert_eq!(pool.unfunded_seconds_at(320), 70);
+        pool.close_empty_window(400).unwrap();
+        assert_eq!(pool.unfunded_seconds_at(1_000), 150);
+        assert_eq!(pool.last_empty_window_end, 400);
+    }
+
+    #[test]
+    fn withdrawal_cooldown_counts_from_request() {
+        let mut pool = zeroed_pool();
+        assert_eq!(pool.withdrawal_cooldown_remaining(100, 100), 0);
+
+        pool.flex_cooldown_seconds = 60;
+        assert_eq!(pool.withdrawal_cooldown_remaining(100, 100), 60);
+        assert_eq!(pool.withdrawal_cooldown_remaining(100, 159), 1);
+        assert_eq!(pool.withdrawal_cooldown_remaining(100, 160), 0);
+        assert_eq!(pool.withdrawal_cooldown_remaining(100, 1_000), 0);
+    }
+
+    #[test]
+    fn release_schedule_only_applies_to_matching_locks() {
+        let mut pool = zeroed_pool();
+        pool.core_lock_seconds = 1_000;
+        pool.core_cliff_secs = 400;
+        pool.core_linear_secs = 600;
+        assert_eq!(pool.release_schedule_for_lock(Tier::Core, 1_000), (400, 600));
+
+        // A position from before the lock was shortened keeps its own cliff
+        assert_eq!(pool.release_schedule_for_lock(Tier::Core, 5_000), (5_000, 0));
+        assert_eq!(pool.lock_period_for_tier(Tier::Core), Some(1_000));
+        assert_eq!(pool.lock_period_for_tier(Tier::Flex), None);
+    }
+
+    #[test]
+    fn treasury_floor_covers_owed_rewards_and_referrals() {
+        let mut pool = zeroed_pool();
+        assert_eq!(pool.treasury_floor(), 0);
+
+        // Credited bonuses are already part of pending_liability
+        pool.pending_liability = 700;
+        pool.bonus_liability = 200;
+        pool.referral_liability = 50;
+        assert_eq!(pool.treasury_floor(), 750);
+
+        // Unsettled donations are not in pending_liability yet
+        pool.donation_liability = 25;
+        assert_eq!(pool.treasury_floor(), 775);
+
+        pool.pending_liability = u64::MAX;
+        assert_eq!(pool.treasury_floor(), u64::MAX);
+    }
+
+    #[test]
+    fn projection_prices_each_tier_and_respects_the_emission_cap() {
+        let mut pool = zeroed_pool();
+        pool.add_staked(Tier::Flex, 500_000).unwrap();
+        pool.add_staked(Tier::Prime, 500_000).unwrap();
+        pool.flex_apy = 400;
+        pool.prime_apy = 1_200;
+        pool.emission_cap = 1_000_000;
+        pool.pending_liability = 500;
+        pool.referral_liability = 50;
+        assert_eq!(pool.projected_outstanding_rewards(0).unwrap(), 550 + 20_000 + 60_000);
+
+        // Accrual cannot exceed what the cap still allows
+        pool.total_distributed = 990_000;
+        assert_eq!(pool.projected_outstanding_rewards(0).unwrap(), 550 + 10_000);
+
+        // Decay lowers the projection
+        pool.total_distributed = 0;
+        pool.decay_interval_seconds = 100;
+        pool.decay_bps_per_interval = 300;
+        assert_eq!(pool.projected_outstanding_rewards(100).unwrap(), 550 + 5_000 + 45_000);
+    }
+
+    #[test]
+    fn tier_totals_follow_stake_moves() {
+        let mut pool = zeroed_pool();
+        pool.add_staked(Tier::Flex, 700).unwrap();
+        pool.add_staked(Tier::Core, 300).unwrap();
+        pool.move_staked(Tier::Flex, Tier::Prime, 200).unwrap();
+        pool.remove_staked(Tier::Core, 100).unwrap();
+        assert_eq!(pool.tier_staked, [500, 200, 200, 0]);
+        assert_eq!(pool.total_staked, 900);
+
+        // Removing more than a tier holds fails even if the total could cover it
+        assert!(pool.remove_staked(Tier::Core, 201).is_err());
+    }
+
+    #[test]
+    fn solvency_projection_includes_the_new_stake() {
+        let mut pool = zeroed_pool();
+        pool.core_apy = 800;
+        pool.prime_apy = 1_200;
+        pool.add_staked(Tier::Core, 1_000_000).unwrap();
+        assert_eq!(pool.projected_annual_rewards(0, None).unwrap(), 80_000);
+        assert_eq!(
+            pool.projected_annual_rewards(0, Some((Tier::Prime, 100_000))).unwrap(),
+            80_000 + 12_000
+        );
+
+        // Deployed funds back the treasury limit
+        pool.solvency_treasury_multiple_bps = 15_000;
+        pool.treasury_deployed = 20_000;
+        assert_eq!(pool.solvency_treasury_limit(40_000), 90_000);
+    }
+
+    #[test]
+    fn pause_bits_are_independent() {
+        let mut pool = zeroed_pool();
+        pool.pause_flags = StakePool::PAUSE_CLAIM_BIT;
+        assert!(pool.is_claim_paused());
+        assert!(!pool.is_stake_paused());
+        assert!(!pool.is_unstake_paused());
+        assert!(pool.is_accepting_stakes(Tier::Flex));
+
+        pool.pause_flags = StakePool::PAUSE_UNSTAKE_BIT | StakePool::PAUSE_STAKE_BIT;
+        assert!(pool.is_unstake_paused());
+        assert!(!pool.is_claim_paused());
+        assert!(!pool.is_accepting_stakes(Tier::Flex));
+    }
+
+    #[test]
+    fn claims_and_unstakes_pause_together_only_in_emergency() {
+        let both = StakePool::PAUSE_CLAIM_BIT | StakePool::PAUSE_UNSTAKE_BIT;
+        assert!(!StakePool::allows_pause_flags(both, false));
+        assert!(!StakePool::allows_pause_flags(StakePool::PAUSE_ALL_BITS, false));
+        assert!(StakePool::allows_pause_flags(both, true));
+        assert!(StakePool::allows_pause_flags(StakePool::PAUSE_CLAIM_BIT | StakePool::PAUSE_STAKE_BIT, false));
+        assert!(StakePool::allows_pause_flags(StakePool::PAUSE_UNSTAKE_BIT | StakePool::PAUSE_STAKE_BIT, false));
+    }
+
+    #[test]
+    fn remaining_capacity_tracks_cap() {
+        let mut pool = zeroed_pool();
+        pool.total_staked = 900;
+        assert_eq!(pool.remaining_capacity(), None);
+
+        pool.max_total_staked = 1_000;
+        assert_eq!(pool.remaining_capacity(), Some(100));
+
+        // Lowering the cap below the total leaves no room but is not an error
+        pool.max_total_staked = 500;
+        assert_eq!(pool.remaining_capacity(), Some(0));
+    }
+
+    #[test]
+    fn global_pause_overrides_tier_flags() {
+        let mut pool = zeroed_pool();
+        assert!(pool.is_accepting_stakes(Tier::Flex));
+        pool.prime_paused = true;
+        assert!(pool.is_accepting_stakes(Tier::Flex));
+        assert!(pool.is_accepting_stakes(Tier::Core));
+        assert!(!pool.is_accepting_stakes(Tier::Prime));
+
+        pool.prime_paused = false;
+        pool.pause_flags = StakePool::PAUSE_STAKE_BIT;
+        for tier in [Tier::Flex, Tier::Core, Tier::Prime, Tier::Permanent] {
+            assert!(!pool.is_accepting_stakes(tier));
+        }
+    }
+
+    #[test]
+    fn unstake_fee_defaults_to_zero() {
+        let pool = zeroed_pool();
+        for tier in [Tier::Flex, Tier::Core, Tier::Prime, Tier::Permanent] {
+            assert_eq!(pool.split_unstake_fee(tier, 12_345).unwrap(), (12_345, 0));
+        }
+    }
+
+    #[test]
+    fn flex_cooldown_ends_at_the_boundary() {
+        let mut pool = zeroed_pool();
+        pool.flex_min_stake_secs = 60;
+        assert_eq!(pool.flex_cooldown_remaining(1_000, 1_000), 60);
+        assert_eq!(pool.flex_cooldown_remaining(1_000, 1_059), 1);
+        assert_eq!(pool.flex_cooldown_remaining(1_000, 1_060), 0);
+        assert_eq!(pool.flex_cooldown_remaining(1_000, 5_000), 0);
+    }
+
+    #[test]
+    fn flex_cooldown_counts_from_the_original_stake() {
+        let mut pool = zeroed_pool();
+        // Enabled long after the position opened: nothing left to wait
+        pool.flex_min_stake_secs = 7 * SECONDS_PER_DAY;
+        assert_eq!(pool.flex_cooldown_remaining(1_000, 1_000 + 8 * SECONDS_PER_DAY), 0);
+        pool.flex_min_stake_secs = 0;
+        assert_eq!(pool.flex_cooldown_remaining(1_000, 1_000), 0);
+    }
+
+    #[test]
+    fn min_stake_duration_counts_from_the_latest_deposit() {
+        let mut pool = zeroed_pool();
+        assert_eq!(pool.min_stake_duration_remaining(1_000, 1_000), 0);
+        pool.min_stake_duration_seconds = 30;
+        assert_eq!(pool.min_stake_duration_remaining(1_000, 1_000), 30);
+        assert_eq!(pool.min_stake_duration_remaining(1_000, 1_029), 1);
+        assert_eq!(pool.min_stake_duration_remaining(1_000, 1_030), 0);
+    }
+
+    #[test]
+    fn drip_sums_across_irregular_intervals() {
+        let mut pool = zeroed_pool();
+        pool.drip_rate_per_sec = 7;
+        pool.last_drip_at = 1_000;
+
+        let mut reserve = 1_000_000;
+        let mut dripped = 0;
+        for now in [1_001, 1_013, 1_014, 1_100, 1_337] {
+            let amount = pool.pending_drip(now, reserve);
+            reserve -= amount;
+            dripped += amount;
+            pool.last_drip_at = now;
+        }
+        assert_eq!(dripped, 7 * 337);
+    }
+
+    #[test]
+    fn drip_is_limited_by_the_reserve() {
+        let mut pool = zeroed_pool();
+        pool.drip_rate_per_sec = 100;
+        pool.last_drip_at = 1_000;
+        assert_eq!(pool.pending_drip(1_010, 250), 250);
+        assert_eq!(pool.pending_drip(999, 250), 0);
+        pool.drip_rate_per_sec = 0;
+        assert_eq!(pool.pending_drip(2_000, 250), 0);
+    }
+
+    #[test]
+    fn top_up_never_pays_for_time_the_reserve_was_dry() {
+        let mut pool = zeroed_pool();
+        pool.drip_rate_per_sec = 100;
+        pool.last_drip_at = 1_000;
+
+        // 250 covers 2 of the 10 seconds; the other 8 are forfeited
+        pool.forfeit_uncovered_drip(1_010, 250);
+        assert_eq!(pool.last_drip_at, 1_008);
+        assert_eq!(pool.pending_drip(1_010, 1_000_000), 200);
+
+        // A reserve that covers the whole interval keeps it
+        pool.forfeit_uncovered_drip(1_010, 1_000_000);
+        assert_eq!(pool.last_drip_at, 1_008);
+
+        pool.forfeit_uncovered_drip(2_000, 0);
+        assert_eq!(pool.pending_drip(2_000, 1_000_000), 0);
+    }
+
+    #[test]
+    fn treasury_deploy_cap_counts_deployed_funds() {
+        let mut pool = zeroed_pool();
+        assert_eq!(pool.max_treasury_deployable(1_000), 0);
+
+        pool.max_treasury_deploy_bps = 5_000;
+        assert_eq!(pool.max_treasury_deployable(1_000), 500);
+        // 400 already out of a 1_000 treasury: the cap stays at 500 in total
+        pool.treasury_deployed = 400;
+        assert_eq!(pool.max_treasury_deployable(600), 500);
+    }
+
+    #[test]
+    fn default_key_is_never_a_yield_adapter() {
+        let mut pool = zeroed_pool();
+        assert!(!pool.is_yield_adapter(&Pubkey::default()));
+        let adapter = Pubkey::new_unique();
+        pool.yield_adapters[2] = adapter;
+        assert!(pool.is_yield_adapter(&adapter));
+        assert!(!pool.is_yield_adapter(&Pubkey::new_unique()));
+    }
+
+    #[test]
+    fn claim_limit_fills_and_rolls_over() {
+        let mut pool = zeroed_pool();
+        pool.max_claims_per_epoch = 2;
+        pool.claim_epoch_secs = 100;
+
+        assert!(pool.is_claim_allowed(1_000));
+        pool.record_claim(1_000).unwrap();
+        pool.record_claim(1_050).unwrap();
+        assert!(!pool.is_claim_allowed(1_099));
+
+        // The epoch is anchored at its first claim, not the last
+        assert!(pool.is_claim_allowed(1_100));
+        pool.record_claim(1_100).unwrap();
+        assert_eq!(pool.epoch_start, 1_100);
+        assert_eq!(pool.claims_used(1_150), 1);
+    }
+
+    #[test]
+    fn zero_claim_limit_is_disabled() {
+        let mut pool = zeroed_pool();
+        pool.claim_epoch_secs = 100;
+        for now in 1_000..1_010 {
+            assert!(pool.is_claim_allowed(now));
+            pool.record_claim(now).unwrap();
+        }
+    }
+
+    #[test]
+    fn tier_staker_counts_follow_positions_across_tiers() {
+        let mut pool = zeroed_pool();
+        pool.add_staker(Tier::Flex).unwrap();
+        pool.add_staker(Tier::Flex).unwrap();
+        pool.add_staker(Tier::Core).unwrap();
+        pool.move_staker(Tier::Flex, Tier::Prime).unwrap();
+
+        // Full exit from Prime, then the same owner re-stakes into Core
+        pool.remove_staker(Tier::Prime).unwrap();
+        pool.add_staker(Tier::Core).unwrap();
+
+        assert_eq!(pool.tier_staker_count, [1, 2, 0, 0]);
+        assert_eq!(pool.staker_count, pool.tier_staker_count.iter().sum::<u64>());
+    }
+
+    #[test]
+    fn uncounted_positions_do_not_underflow_tier_counts() {
+        let mut pool = zeroed_pool();
+        pool.staker_count = 1;
+        pool.remove_staker(Tier::Core).unwrap();
+        assert_eq!(pool.staker_count, 0);
+        assert_eq!(pool.tier_staker_count, [0; 4]);
+        assert!(pool.remove_staker(Tier::Core).is_err());
+    }
+
+    #[test]
+    fn epoch_emission_fills_and_rolls_over() {
+        let mut pool = zeroed_pool();
+        assert_eq!(pool.epoch_emission_headroom(1_000), u64::MAX);
+
+        pool.max_emission_per_epoch = 500;
+        pool.epoch_length_seconds = 100;
+        pool.record_epoch_emission(1_000, 300).unwrap();
+        pool.record_epoch_emission(1_050, 200).unwrap();
+        assert_eq!(pool.epoch_emission_headroom(1_099), 0);
+
+        // The counter resets lazily: reads see a fresh budget, writes roll
+        // the epoch forward from the first payout in it
+        assert_eq!(pool.epoch_emission_headroom(1_100), 500);
+        assert_eq!(pool.distributed_this_epoch, 500);
+        pool.record_epoch_emission(1_120, 50).unwrap();
+        assert_eq!(pool.current_epoch_start, 1_120);
+        assert_eq!(pool.epoch_emission_headroom(1_219), 450);
+    }
+
+    #[test]
+    fn permanent_tier_stays_locked_without_a_sunset() {
+        let pool = zeroed_pool();
+        assert!(!pool.is_sunset_reached(0));
+        assert!(!pool.is_sunset_reached(i64::MAX));
+    }
+
+    #[test]
+    fn permanent_tier_unlocks_at_the_sunset() {
+        let mut pool = zeroed_pool();
+        pool.sunset_at = 1_000;
+        assert!(!pool.is_sunset_reached(999));
+        assert!(pool.is_sunset_reached(1_000));
     }
 }
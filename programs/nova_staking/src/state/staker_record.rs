@@ -0,0 +1,21 @@
+use anchor_lang::prelude::*;
+
+/// Created on a wallet's first stake into a pool and never closed, so what
+/// the pool grants once per wallet survives `close_user_stake` and
+/// `gc_user_stake`.
+#[account]
+pub struct StakerRecord {
+    pub stake_pool: Pubkey,
+    pub wallet: Pubkey,
+    /// When the wallet first staked; the record's creation for wallets that
+    /// staked before records existed.
+    pub first_staked_at: i64,
+    /// End of the wallet's early-bird bonus (0 if it never took a slot),
+    /// restored onto every position the wallet opens later.
+    pub early_bird_until: i64,
+    pub bump: u8,
+}
+
+impl StakerRecord {
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 8 + 1;
+}
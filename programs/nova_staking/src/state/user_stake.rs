@@ -1,5 +1,24 @@
 use anchor_lang::prelude::*;
 
+use crate::constants::{PRECISION, USER_STAKE_VERSION};
+use crate::error::StakingError;
+use crate::math::weighted_lock_end;
+use crate::state::StakePool;
+use crate::tier::Tier;
+
+/// Result of `view_pending_rewards`, read from a simulated transaction.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct PendingRewardsView {
+    /// What `claim_rewards` would pay now, before streak bonus and claim fee.
+    pub claimable: u64,
+    /// When the lock ends, following auto-renew cycles; the sunset for the
+    /// permanent tier (0 if undeclared).
+    pub lock_end_time: i64,
+    /// Current base APY the position earns, in basis points; Flex's once a
+    /// pool with `downgrade_after_lock` has downgraded an ended lock.
+    pub apy_bps: u16,
+}
+
 #[account]
 pub struct UserStake {
     pub owner: Pubkey,
@@ -12,15 +31,751 @@ pub struct UserStake {
     pub stake_start_time: i64,
     pub last_claim_time: i64,
 
-    pub tier: u8,
+    pub tier: Tier,
     pub is_active: bool,
     pub bump: u8,
+
+    pub completed_lock_cycles: u16,
+    pub early_bird_until: i64,
+
+    pub streak_count: u16,
+    pub streak_deadline: i64,
+
+    pub unlocked_withdrawn: u64,
+
+    pub boosted_amount: u64,
+    pub boost_until: i64,
+
+    pub referrer: Pubkey,
+
+    pub bonus_rewards: u64,
+
+    /// Flex principal queued by `request_unstake`; already out of
+    /// `staked_amount`, so it earns nothing while it waits
+    pub unstake_requested_amount: u64,
+    pub unstake_requested_at: i64,
+
+    /// Roll the Core/Prime lock into a new cycle each time it ends
+    pub auto_renew: bool,
+
+    /// Lock this position signed up for, copied from the pool when the lock
+    /// started
+    pub lock_seconds: i64,
+
+    /// Key allowed to claim rewards into the owner's account; default = none
+    pub claim_delegate: Pubkey,
+
+    /// Reward-per-token pools only: accumulator value the position was last
+    /// settled at, and the accumulator rewards settled over the account's life
+    pub reward_per_token_paid: u128,
+    pub rewards_earned: u64,
+
+    /// Time of the latest deposit (new position or top-up); drives the
+    /// pool's `min_stake_duration_seconds`
+    pub last_stake_time: i64,
+
+    /// Layout version, `USER_STAKE_VERSION` for positions opened now; older
+    /// accounts are upgraded by `migrate_user_stake`. New fields go after it.
+    pub version: u8,
+
+    /// Start of the current run of continuous stake, for the pool's loyalty
+    /// schedule. Set when the position opens and kept through top-ups and
+    /// tier changes; a full exit closes the run
+    pub loyalty_start_time: i64,
+
+    /// `staked_amount * StakePool::bonus_per_token` as of the last
+    /// settlement; donations made since are owed on top of it
+    pub bonus_debt: u128,
+
+    /// `StakePool::paused_seconds_at(last_claim_time)`; halted seconds
+    /// since then are excluded from accrual
+    pub paused_secs_snapshot: i64,
+
+    /// Owner-chosen tag, UTF-8 zero-padded on the right; all zeros means
+    /// none. See `validate_label`
+    pub label: [u8; 32],
+
+    /// Reward-mint token account every claim must pay; default = none.
+    /// Set through `set_reward_destination`
+    pub reward_destination: Pubkey,
+
+    /// Key that may take over the position `recovery_delay_seconds` after
+    /// starting a recovery, unless the owner signs for the position in the
+    /// meantime; default = none. Set through `set_recovery_address`
+    pub recovery_address: Pubkey,
+    pub recovery_delay_seconds: i64,
+    /// When `initiate_recovery` ran (0 = no recovery pending)
+    pub recovery_started_at: i64,
+
+    /// Accrual the emission cap could not fund when the position last
+    /// settled; still counted in the pool's `accrued_liability` and settled
+    /// again, pro rata, once the cap has room
+    pub over_cap_rewards: u64,
+
+    /// `StakePool::unfunded_seconds_at(last_claim_time)`; seconds with an
+    /// empty treasury since then are excluded from accrual while the pool
+    /// has `accrue_only_when_funded` set
+    pub unfunded_secs_snapshot: i64,
 }
 
 impl UserStake {
-    pub const LEN: usize = 8 + 32 + 32 + 8 + 8 + 8 + 8 + 8 + 1 + 1 + 1;
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 8 + 8 + 8 + 8 + 1 + 1 + 1
+        + 2 + 8
+        + 2 + 8
+        + 8
+        + 8 + 8
+        + 32
+        + 8
+        + 8 + 8
+        + 1
+        + 8
+        + 32
+        + 16 + 8
+        + 8
+        + 1
+        + 8
+        + 16
+        + 8
+        + 32
+        + 32
+        + 32 + 8 + 8
+        + 8
+        + 8;
+
+    /// Bring an account read from an older layout up to
+    /// `USER_STAKE_VERSION`; see `StakePool::migrate`.
+    pub fn migrate(&mut self) -> Result<()> {
+        require!(
+            self.version <= USER_STAKE_VERSION,
+            StakingError::AccountVersionMismatch
+        );
+        // v0 -> v1: only `version` was added
+        if self.version == 0 {
+            self.version = 1;
+        }
+        // v1 -> v2: open positions count their loyalty from the current lock
+        // start, the latest point they are known to have been staked since
+        if self.version == 1 {
+            if self.is_active {
+                self.loyalty_start_time = self.stake_start_time;
+            }
+            self.version = 2;
+        }
+        // v2 -> v3: no donations predate v3 positions, so nothing is owed yet
+        // and the debt starts at zero
+        if self.version == 2 {
+            self.version = 3;
+        }
+        // v3 -> v4: positions this old last settled before any pool could
+        // halt accrual, so the snapshot starts at zero
+        if self.version == 3 {
+            self.version = 4;
+        }
+        // v4 -> v5: no label
+        if self.version == 4 {
+            self.version = 5;
+        }
+        // v5 -> v6: claims keep paying the account passed with them
+        if self.version == 5 {
+            self.version = 6;
+        }
+        // v6 -> v7: no recovery address
+        if self.version == 6 {
+            self.version = 7;
+        }
+        // v7 -> v8: nothing has been held back by the emission cap yet
+        if self.version == 7 {
+            self.version = 8;
+        }
+        // v8 -> v9: the snapshot starts at zero, as the pool's unfunded total
+        // does at its v29 upgrade; a position settled while the treasury was
+        // already empty then is charged that window from its start
+        if self.version == 8 {
+            self.version = 9;
+        }
+        Ok(())
+    }
+
+    /// The stored claim destination, if the owner set one.
+    pub fn reward_destination(&self) -> Option<Pubkey> {
+        (self.reward_destination != Pubkey::default()).then_some(self.reward_destination)
+    }
+
+    /// Check that `label` is UTF-8 text followed only by zero padding, so
+    /// indexers can decode every label the same way.
+    pub fn validate_label(label: &[u8; 32]) -> Result<()> {
+        let len = label.iter().rposition(|&b| b != 0).map_or(0, |last| last + 1);
+        let text = &label[..len];
+        require!(
+            !text.contains(&0) && std::str::from_utf8(text).is_ok(),
+            StakingError::InvalidStakeLabel
+        );
+        Ok(())
+    }
+
+    /// Record a settlement at `now`: accrual restarts here, net of any time
+    /// the pool spends halted or unfunded afterwards.
+    pub fn mark_settled(&mut self, stake_pool: &StakePool, now: i64) {
+        self.last_claim_time = now;
+        self.paused_secs_snapshot = stake_pool.paused_seconds_at(now);
+        self.unfunded_secs_snapshot = stake_pool.unfunded_seconds_at(now);
+    }
+
+    /// Seconds since `last_claim_time` that the pool spent halted, at `now`.
+    pub fn halted_seconds(&self, stake_pool: &StakePool, now: i64) -> i64 {
+        stake_pool
+            .paused_seconds_at(now)
+            .saturating_sub(self.paused_secs_snapshot)
+            .max(0)
+    }
+
+    /// Seconds since `last_claim_time` that the treasury was empty, at `now`;
+    /// zero unless the pool has `accrue_only_when_funded` set.
+    pub fn unfunded_seconds(&self, stake_pool: &StakePool, now: i64) -> i64 {
+        if !stake_pool.accrue_only_when_funded {
+            return 0;
+        }
+        stake_pool
+            .unfunded_seconds_at(now)
+            .saturating_sub(self.unfunded_secs_snapshot)
+            .max(0)
+    }
+
+    /// Share of donations made since `bonus_debt` was last synced.
+    pub fn pending_donation(&self, bonus_per_token: u128) -> Result<u64> {
+        let accrued = (self.staked_amount as u128)
+            .checked_mul(bonus_per_token)
+            .ok_or(StakingError::MathOverflow)?;
+        u64::try_from(accrued.saturating_sub(self.bonus_debt) / PRECISION)
+            .map_err(|_| error!(StakingError::ConversionOverflow))
+    }
+
+    /// Mark donations up to `bonus_per_token` as settled for the current
+    /// `staked_amount`; call after every change to it.
+    pub fn sync_bonus_debt(&mut self, bonus_per_token: u128) -> Result<()> {
+        self.bonus_debt = (self.staked_amount as u128)
+            .checked_mul(bonus_per_token)
+            .ok_or(StakingError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Whether any settled reward is still owed: pending, bonus or boost-locked.
+    pub fn holds_rewards(&self) -> bool {
+        self.pending_rewards > 0
+            || self.bonus_rewards > 0
+            || self.boosted_amount > 0
+            || self.over_cap_rewards > 0
+    }
+
+    /// Whether `signer` may claim for this position: the owner or a set delegate.
+    pub fn can_claim(&self, signer: &Pubkey) -> bool {
+        *signer == self.owner
+            || (self.claim_delegate != Pubkey::default() && *signer == self.claim_delegate)
+    }
+
+    pub fn get_lock_period(&self) -> i64 {
+        self.lock_seconds
+    }
+
+    /// When the position's lock ends; the stake time itself for unlocked positions.
+    pub fn lock_end_time(&self) -> i64 {
+        self.stake_start_time.saturating_add(self.get_lock_period())
+    }
+
+    /// When a pool with `downgrade_after_lock` drops the position to the
+    /// Flex APY: the end of a Core or Prime lock. None while auto-renew keeps
+    /// the lock rolling and for tiers without a lock.
+    pub fn downgrade_time(&self) -> Option<i64> {
+        let locked_tier = matches!(self.tier, Tier::Core | Tier::Prime);
+        (locked_tier && !self.auto_renew && self.get_lock_period() > 0).then(|| self.lock_end_time())
+    }
+
+    /// Lock cycles that have rolled over by `now` under auto-renew; zero when
+    /// auto-renew is off or the position has no fixed lock.
+    pub fn elapsed_renewals(&self, now: i64) -> i64 {
+        let lock_period = self.get_lock_period();
+        if !self.auto_renew || lock_period <= 0 {
+            return 0;
+        }
+        now.saturating_sub(self.stake_start_time).max(0) / lock_period
+    }
+
+    /// When the lock running at `now` ends, following auto-renew cycles.
+    pub fn current_lock_end(&self, now: i64) -> i64 {
+        let renewals = self.elapsed_renewals(now);
+        self.lock_end_time()
+            .saturating_add(renewals.saturating_mul(self.get_lock_period()))
+    }
+
+    /// Move `stake_start_time` to the start of the auto-renew cycle running at
+    /// `now`, counting each rolled-over lock as a completed cycle.
+    pub fn roll_auto_renew(&mut self, now: i64) {
+        let renewals = self.elapsed_renewals(now);
+        if renewals == 0 {
+            return;
+        }
+        self.stake_start_time = self
+            .stake_start_time
+            .saturating_add(renewals.saturating_mul(self.get_lock_period()));
+        self.completed_lock_cycles = self
+            .completed_lock_cycles
+            .saturating_add(u16::try_from(renewals).unwrap_or(u16::MAX));
+        self.unlocked_withdrawn = 0;
+    }
+
+    /// Lock start after a `KeepOriginal` top-up of `amount` at `now`.
+    ///
+    /// The new lock end is the stake-weighted average of the running lock's
+    /// end (`now`, if it has ended) and a full lock from `now`, rounded up.
+    /// Locked token-seconds are conserved, so a large deposit into a lock
+    /// that has run out serves close to a full lock of its own.
+    ///
+    /// While the running lock has not ended this is the amount-weighted mean
+    /// of the old start and `now`. Edge cases:
+    /// - Rounding up means any deposit made after the position's start moves
+    ///   the end at least one second later, even 1 base unit onto a huge
+    ///   stake. Repeated dust top-ups (or `stake_for` gifts) can therefore
+    ///   nudge a lock by about a second each.
+    /// - The end never moves earlier, and never past a full lock from `now`.
+    /// - Products are taken in u128, so no amount or timestamp can overflow.
+    pub fn weighted_lock_start(&self, amount: u64, now: i64) -> i64 {
+        if self.staked_amount == 0 && amount == 0 {
+            return now;
+        }
+
+        let lock = self.get_lock_period();
+        let running_end = self.lock_end_time().max(now);
+        let deposit_end = now.saturating_add(lock);
+        weighted_lock_end(running_end, self.staked_amount, deposit_end, amount).saturating_sub(lock)
+    }
+
+    /// Whether a lock of `lock_seconds` has ended at `now`, treating the last
+    /// `grace_secs` before the exact end as ended to absorb clock drift.
+    pub fn is_lock_ended(&self, now: i64, lock_seconds: i64, grace_secs: i64) -> bool {
+        now.saturating_sub(self.stake_start_time) >= lock_seconds.saturating_sub(grace_secs)
+    }
+
+    /// Whether the recovery address has started a recovery that the owner
+    /// has not cancelled.
+    pub fn has_pending_recovery(&self) -> bool {
+        self.recovery_started_at != 0
+    }
+
+    /// Whether a pending recovery may complete at `now`.
+    pub fn is_recovery_ready(&self, now: i64) -> bool {
+        self.has_pending_recovery()
+            && now >= self.recovery_started_at.saturating_add(self.recovery_delay_seconds)
+    }
+
+    /// Drop a pending recovery, returning the recovery address that started
+    /// it; `None` if nothing was pending.
+    pub fn cancel_recovery(&mut self) -> Option<Pubkey> {
+        if !self.has_pending_recovery() {
+            return None;
+        }
+        self.recovery_started_at = 0;
+        Some(self.recovery_address)
+    }
+
+    /// Whether principal is waiting in the Flex withdrawal queue.
+    pub fn has_pending_unstake(&self) -> bool {
+        self.unstake_requested_amount > 0
+    }
+
+    /// Whether boost-locked rewards are still committed at `now`.
+    pub fn is_boost_active(&self, now: i64) -> bool {
+        self.boosted_amount > 0 && now < self.boost_until
+    }
+
+    /// Boost-locked rewards that have expired and can return to the claimable balance.
+    pub fn releasable_boost(&self, now: i64) -> u64 {
+        if self.is_boost_active(now) {
+            0
+        } else {
+            self.boosted_amount
+        }
+    }
+
+    /// Whether rewards may be claimed at `now` given the tier's reward cliff.
+    /// Fully exited positions always release what they accrued.
+    pub fn is_reward_cliff_passed(&self, now: i64, cliff_secs: i64) -> bool {
+        !self.is_active || now.saturating_sub(self.stake_start_time) >= cliff_secs
+    }
+
+    /// Principal that may be withdrawn at `now` under a cliff-plus-linear schedule.
+    ///
+    /// Nothing unlocks before `cliff_secs`; afterwards
+    /// `(staked_amount + unlocked_withdrawn) * (elapsed - cliff) / linear_secs`
+    /// is unlocked, rounded down, minus what was already taken during this lock.
+    /// Everything unlocks once `cliff_secs + linear_secs` has passed.
+    pub fn withdrawable_principal(&self, now: i64, cliff_secs: i64, linear_secs: i64) -> u64 {
+        let lock_seconds = cliff_secs.saturating_add(linear_secs);
+        if lock_seconds <= 0 || self.is_lock_ended(now, lock_seconds, 0) {
+            return self.staked_amount;
+        }
+
+        let elapsed = now.saturating_sub(self.stake_start_time).max(0);
+        if elapsed < cliff_secs || linear_secs <= 0 {
+            return 0;
+        }
+
+        let vesting = (elapsed - cliff_secs) as u128;
+        let principal = (self.staked_amount as u128) + (self.unlocked_withdrawn as u128);
+        let unlocked = principal * vesting / (linear_secs as u128);
+        unlocked
+            .saturating_sub(self.unlocked_withdrawn as u128)
+            .min(self.staked_amount as u128) as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::{CORE_LOCK_PERIOD, SECONDS_PER_DAY};
+
+    const LOCK: i64 = CORE_LOCK_PERIOD;
+    const PRINCIPAL: u64 = 1_000_003;
+
+    fn position(staked_amount: u64, unlocked_withdrawn: u64) -> UserStake {
+        UserStake {
+            owner: Pubkey::default(),
+            stake_pool: Pubkey::default(),
+            staked_amount,
+            pending_rewards: 0,
+            total_rewards_claimed: 0,
+            stake_start_time: 0,
+            last_claim_time: 0,
+            tier: Tier::Core,
+            is_active: true,
+            bump: 0,
+            completed_lock_cycles: 0,
+            early_bird_until: 0,
+            streak_count: 0,
+            streak_deadline: 0,
+            unlocked_withdrawn,
+            boosted_amount: 0,
+            boost_until: 0,
+            referrer: Pubkey::default(),
+            bonus_rewards: 0,
+            unstake_requested_amount: 0,
+            unstake_requested_at: 0,
+            auto_renew: false,
+            lock_seconds: LOCK,
+            claim_delegate: Pubkey::default(),
+            reward_per_token_paid: 0,
+            rewards_earned: 0,
+            last_stake_time: 0,
+            version: USER_STAKE_VERSION,
+            loyalty_start_time: 0,
+            bonus_debt: 0,
+            paused_secs_snapshot: 0,
+            label: [0; 32],
+            reward_destination: Pubkey::default(),
+            recovery_address: Pubkey::default(),
+            recovery_delay_seconds: 0,
+            recovery_started_at: 0,
+            over_cap_rewards: 0,
+            unfunded_secs_snapshot: 0,
+        }
+    }
+
+    fn label(text: &[u8]) -> [u8; 32] {
+        let mut label = [0; 32];
+        label[..text.len()].copy_from_slice(text);
+        label
+    }
+
+    #[test]
+    fn labels_must_be_zero_padded_utf8() {
+        assert!(UserStake::validate_label(&[0; 32]).is_ok());
+        assert!(UserStake::validate_label(&label(b"ops reserve")).is_ok());
+        assert!(UserStake::validate_label(&label("réserve ✓".as_bytes())).is_ok());
+        assert!(UserStake::validate_label(&[b'x'; 32]).is_ok());
+
+        // Invalid UTF-8, and a zero byte before the text ends
+        assert!(UserStake::validate_label(&label(&[0xff, 0xfe])).is_err());
+        assert!(UserStake::validate_label(&label(b"ops\0reserve")).is_err());
+        // A multi-byte character cut off by the 32-byte limit
+        let mut cut = [b'a'; 32];
+        cut[31] = 0xc3;
+        assert!(UserStake::validate_label(&cut).is_err());
+    }
+
+    #[test]
+    fn owner_activity_cancels_a_recovery_before_it_is_ready() {
+        let mut stake = position(PRINCIPAL, 0);
+        stake.recovery_address = Pubkey::new_unique();
+        stake.recovery_delay_seconds = 100;
+        assert!(stake.cancel_recovery().is_none());
+
+        stake.recovery_started_at = 1_000;
+        assert!(!stake.is_recovery_ready(1_099));
+        assert!(stake.is_recovery_ready(1_100));
+
+        // The owner signs in the last second; the recovery can no longer complete
+        assert_eq!(stake.cancel_recovery(), Some(stake.recovery_address));
+        assert!(!stake.has_pending_recovery());
+        assert!(!stake.is_recovery_ready(1_100));
+
+        // A new attempt starts the full delay again
+        stake.recovery_started_at = 1_100;
+        assert!(!stake.is_recovery_ready(1_199));
+    }
+
+    #[test]
+    fn only_owner_or_set_delegate_can_claim() {
+        let mut stake = position(PRINCIPAL, 0);
+        stake.owner = Pubkey::new_unique();
+        let bot = Pubkey::new_unique();
+
+        assert!(stake.can_claim(&stake.owner));
+        assert!(!stake.can_claim(&bot));
+        // An unset delegate never matches the default key
+        assert!(!stake.can_claim(&Pubkey::default()));
+
+        stake.claim_delegate = bot;
+        assert!(stake.can_claim(&bot));
+        assert!(!stake.can_claim(&Pubkey::new_unique()));
+    }
+
+    #[test]
+    fn linear_rounds_down_at_one_percent() {
+        let stake = position(PRINCIPAL, 0);
+        // 1_000_003 * 1% = 10_000.03
+        assert_eq!(stake.withdrawable_principal(LOCK / 100, 0, LOCK), 10_000);
+    }
+
+    #[test]
+    fn linear_rounds_down_at_half() {
+        let stake = position(PRINCIPAL, 0);
+        // 1_000_003 * 50% = 500_001.5
+        assert_eq!(stake.withdrawable_principal(LOCK / 2, 0, LOCK), 500_001);
+    }
+
+    #[test]
+    fn linear_rounds_down_at_ninety_nine_percent() {
+        let stake = position(PRINCIPAL, 0);
+        // 1_000_003 * 99% = 990_002.97
+        assert_eq!(stake.withdrawable_principal(LOCK * 99 / 100, 0, LOCK), 990_002);
+    }
+
+    #[test]
+    fn linear_subtracts_amounts_already_withdrawn() {
+        // Took the 1% tranche, then waits until 50%
+        let stake = position(PRINCIPAL - 10_000, 10_000);
+        assert_eq!(stake.withdrawable_principal(LOCK / 2, 0, LOCK), 490_001);
+        assert_eq!(stake.withdrawable_principal(LOCK / 100, 0, LOCK), 0);
+    }
+
+    #[test]
+    fn remainder_unlocks_at_lock_end() {
+        let stake = position(PRINCIPAL - 990_002, 990_002);
+        // One base unit is still locked a second before the end
+        assert_eq!(stake.withdrawable_principal(LOCK - 1, 0, LOCK), 10_000);
+        assert_eq!(stake.withdrawable_principal(LOCK, 0, LOCK), 10_001);
+    }
+
+    #[test]
+    fn default_schedule_is_a_hard_cliff() {
+        let stake = position(PRINCIPAL, 0);
+        assert_eq!(stake.withdrawable_principal(LOCK - 1, LOCK, 0), 0);
+        assert_eq!(stake.withdrawable_principal(LOCK, LOCK, 0), PRINCIPAL);
+    }
+
+    #[test]
+    fn cliff_then_linear() {
+        // Nothing for 30 days, then linear over the remaining 60
+        let cliff = 30 * SECONDS_PER_DAY;
+        let linear = 60 * SECONDS_PER_DAY;
+        let stake = position(PRINCIPAL, 0);
+        assert_eq!(stake.withdrawable_principal(cliff - 1, cliff, linear), 0);
+        assert_eq!(stake.withdrawable_principal(cliff, cliff, linear), 0);
+        assert_eq!(stake.withdrawable_principal(cliff + linear / 2, cliff, linear), 500_001);
+        assert_eq!(stake.withdrawable_principal(cliff + linear, cliff, linear), PRINCIPAL);
+    }
+
+    #[test]
+    fn top_up_cannot_ride_an_ended_lock() {
+        // 1 token locked at 0; once the lock has run, a large top-up used to
+        // inherit the ended lock and could leave the same day
+        let mut stake = position(1, 0);
+        assert!(stake.is_lock_ended(LOCK, LOCK, 0));
+
+        let top_up = 1_000_000;
+        stake.stake_start_time = stake.weighted_lock_start(top_up, LOCK);
+        stake.staked_amount += top_up;
+        assert!(!stake.is_lock_ended(LOCK + SECONDS_PER_DAY, LOCK, 0));
+
+        // The ended 1-token share pulls the end in by LOCK / 1_000_001 seconds
+        assert_eq!(stake.lock_end_time(), 2 * LOCK - 7);
+        assert!(stake.is_lock_ended(2 * LOCK - 7, LOCK, 0));
+    }
+
+    #[test]
+    fn top_up_mid_lock_conserves_locked_time() {
+        let mut stake = position(PRINCIPAL, 0);
+        let now = LOCK / 2;
+        stake.stake_start_time = stake.weighted_lock_start(PRINCIPAL, now);
+
+        // Half a lock left on one share, a full lock on the other
+        assert_eq!(stake.lock_end_time(), now + (LOCK / 2 + LOCK) / 2);
+
+        // Empty positions (e.g. only a queued withdrawal) start a full lock
+        let empty = position(0, 0);
+        assert_eq!(empty.weighted_lock_start(0, now), now);
+        assert_eq!(empty.weighted_lock_start(PRINCIPAL, now), now);
+    }
+
+    #[test]
+    fn tiny_top_up_moves_a_huge_lock_by_one_second() {
+        let mut stake = position(u64::MAX / 2, 0);
+        let end = stake.lock_end_time();
+
+        // The deposit's share of the extra lock rounds up to a whole second
+        stake.stake_start_time = stake.weighted_lock_start(1, LOCK / 2);
+        assert_eq!(stake.lock_end_time(), end + 1);
+
+        // A deposit in the same second as the start changes nothing
+        let fresh = position(u64::MAX / 2, 0);
+        assert_eq!(fresh.weighted_lock_start(1, 0), 0);
+    }
+
+    #[test]
+    fn weighted_lock_end_stays_between_old_end_and_full_lock() {
+        for &(existing, added, now) in &[
+            (1u64, u64::MAX, 0i64),
+            (PRINCIPAL, 1, LOCK - 1),
+            (PRINCIPAL, PRINCIPAL, 3 * LOCK),
+            (u64::MAX, u64::MAX, i64::MAX / 4),
+        ] {
+            let stake = position(existing, 0);
+            let end = stake.weighted_lock_start(added, now) + LOCK;
+            assert!(end >= stake.lock_end_time().max(now));
+            assert!(end <= now + LOCK);
+        }
+    }
+
+    #[test]
+    fn lock_end_time_follows_the_stored_lock() {
+        let mut stake = position(PRINCIPAL, 0);
+        stake.stake_start_time = 1_000;
+        assert_eq!(stake.lock_end_time(), 1_000 + LOCK);
+        stake.lock_seconds = 0;
+        assert_eq!(stake.lock_end_time(), 1_000);
+    }
+
+    #[test]
+    fn downgrade_time_is_the_end_of_a_fixed_lock() {
+        let mut stake = position(PRINCIPAL, 0);
+        stake.stake_start_time = 1_000;
+        assert_eq!(stake.downgrade_time(), Some(1_000 + LOCK));
+
+        stake.auto_renew = true;
+        assert_eq!(stake.downgrade_time(), None);
+        stake.auto_renew = false;
+        stake.tier = Tier::Flex;
+        assert_eq!(stake.downgrade_time(), None);
+    }
+
+    #[test]
+    fn auto_renew_rolls_over_each_cycle() {
+        let mut stake = position(PRINCIPAL, 0);
+        stake.stake_start_time = 1_000;
+        assert_eq!(stake.elapsed_renewals(1_000 + 3 * LOCK), 0);
+
+        stake.auto_renew = true;
+        assert_eq!(stake.elapsed_renewals(1_000 + LOCK - 1), 0);
+        assert_eq!(stake.current_lock_end(1_000 + LOCK - 1), 1_000 + LOCK);
+        assert_eq!(stake.elapsed_renewals(1_000 + LOCK), 1);
+        assert_eq!(stake.current_lock_end(1_000 + LOCK), 1_000 + 2 * LOCK);
+        assert_eq!(stake.elapsed_renewals(1_000 + 3 * LOCK + 5), 3);
+        assert_eq!(stake.current_lock_end(1_000 + 3 * LOCK + 5), 1_000 + 4 * LOCK);
+    }
+
+    #[test]
+    fn rolling_moves_the_start_to_the_current_cycle() {
+        let mut stake = position(PRINCIPAL, 0);
+        stake.stake_start_time = 1_000;
+        stake.auto_renew = true;
+        stake.completed_lock_cycles = 1;
+
+        stake.roll_auto_renew(1_000 + 2 * LOCK + 7);
+        assert_eq!(stake.stake_start_time, 1_000 + 2 * LOCK);
+        assert_eq!(stake.completed_lock_cycles, 3);
+        assert!(!stake.is_lock_ended(1_000 + 2 * LOCK + 7, LOCK, 0));
+
+        // Turned off mid-cycle, the lock still ends at the cycle boundary
+        stake.auto_renew = false;
+        assert!(!stake.is_lock_ended(1_000 + 3 * LOCK - 1, LOCK, 0));
+        assert!(stake.is_lock_ended(1_000 + 3 * LOCK, LOCK, 0));
+        stake.roll_auto_renew(1_000 + 5 * LOCK);
+        assert_eq!(stake.stake_start_time, 1_000 + 2 * LOCK);
+    }
+
+    #[test]
+    fn auto_renew_ignores_unlocked_positions() {
+        let mut stake = position(PRINCIPAL, 0);
+        stake.auto_renew = true;
+        stake.tier = Tier::Flex;
+        stake.lock_seconds = 0;
+        assert_eq!(stake.elapsed_renewals(10 * LOCK), 0);
+    }
+
+    #[test]
+    fn lock_ends_exactly_at_lock_end_without_grace() {
+        let stake = position(PRINCIPAL, 0);
+        assert!(!stake.is_lock_ended(LOCK - 1, LOCK, 0));
+        assert!(stake.is_lock_ended(LOCK, LOCK, 0));
+    }
+
+    #[test]
+    fn grace_window_boundaries() {
+        let grace = 3_600;
+        let stake = position(PRINCIPAL, 0);
+        assert!(stake.is_lock_ended(LOCK, LOCK, grace));
+        assert!(stake.is_lock_ended(LOCK - grace, LOCK, grace));
+        assert!(stake.is_lock_ended(LOCK - (grace - 1), LOCK, grace));
+        assert!(!stake.is_lock_ended(LOCK - (grace + 1), LOCK, grace));
+    }
+
+    #[test]
+    fn reward_cliff_boundary() {
+        let cliff = 30 * SECONDS_PER_DAY;
+        let mut stake = position(PRINCIPAL, 0);
+        assert!(!stake.is_reward_cliff_passed(cliff - 1, cliff));
+        assert!(stake.is_reward_cliff_passed(cliff, cliff));
+
+        // Exited positions are never held back
+        stake.is_active = false;
+        stake.staked_amount = 0;
+        assert!(stake.is_reward_cliff_passed(cliff - 1, cliff));
+    }
+
+    #[test]
+    fn withdrawable_is_monotonic_and_bounded() {
+        let schedules = [(LOCK, 0), (0, LOCK), (LOCK / 3, LOCK - LOCK / 3), (LOCK - 1, 1)];
+        let positions = [
+            position(PRINCIPAL, 0),
+            position(PRINCIPAL - 10_000, 10_000),
+            position(1, 0),
+            position(u64::MAX - 1, 1),
+        ];
+
+        // Step by a prime so samples land on irregular offsets, plus the boundaries
+        let mut times: Vec<i64> = (-7_919..=LOCK + 7_919).step_by(7_919).collect();
+        times.extend([LOCK / 3 - 1, LOCK / 3, LOCK - 1, LOCK]);
+        times.sort_unstable();
 
-    pub fn is_lock_ended(&self, now: i64, lock_seconds: i64) -> bool {
-        now.saturating_sub(self.stake_start_time) >= lock_seconds
+        for (cliff, linear) in schedules {
+            for stake in positions.iter() {
+                let mut previous = 0;
+                for &now in times.iter() {
+                    let withdrawable = stake.withdrawable_principal(now, cliff, linear);
+                    assert!(withdrawable <= stake.staked_amount);
+                    assert!(withdrawable >= previous, "not monotonic at {now}");
+                    previous = withdrawable;
+                }
+            }
+        }
     }
 }
@@ -0,0 +1,17 @@
+use anchor_lang::prelude::*;
+
+/// Marks `wallet` as allowed to stake into `stake_pool` while the pool's
+/// `whitelist_enabled` is set. Created by `add_to_whitelist`, closed by
+/// `remove_from_whitelist`.
+#[account]
+pub struct WhitelistEntry {
+    pub stake_pool: Pubkey,
+    pub wallet: Pubkey,
+    /// When the wallet was added.
+    pub added_at: i64,
+    pub bump: u8,
+}
+
+impl WhitelistEntry {
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 1;
+}
@@ -1,6 +1,115 @@
-#[derive(Clone, Copy, PartialEq, Eq)]
+use anchor_lang::prelude::borsh;
+use anchor_lang::prelude::*;
+
+use crate::constants::{CORE_LOCK_PERIOD, PRIME_LOCK_PERIOD};
+use crate::error::StakingError;
+use crate::state::StakePool;
+
+/// Staking tier. Borsh encodes it as a single byte holding the variant
+/// index, so accounts written with the old raw `u8` read back unchanged.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
 pub enum Tier {
-    FLEX = 0,
-    CORE = 1,
-    PRIME = 2,
+    Flex,
+    Core,
+    Prime,
+    /// Locked until the authority declares a program-wide sunset.
+    Permanent,
+}
+
+impl Tier {
+    /// Default lock for the tier, copied into the pool at `initialize`. Flex
+    /// has none and Permanent waits for the sunset rather than a clock.
+    pub fn lock_period(self) -> i64 {
+        match self {
+            Tier::Core => CORE_LOCK_PERIOD,
+            Tier::Prime => PRIME_LOCK_PERIOD,
+            Tier::Flex | Tier::Permanent => 0,
+        }
+    }
+
+    /// Whether moving from `self` to `to` is an upgrade. Tiers are ordered by
+    /// commitment: Flex < Core < Prime < Permanent.
+    pub fn is_upgrade_to(self, to: Tier) -> bool {
+        (to as u8) > (self as u8)
+    }
+
+    /// APY in basis points this tier earns on `pool` at `now`, after decay.
+    pub fn apy(self, pool: &StakePool, now: i64) -> u16 {
+        pool.get_apy_for_tier(self, now)
+    }
+}
+
+impl TryFrom<u8> for Tier {
+    type Error = anchor_lang::error::Error;
+
+    fn try_from(value: u8) -> Result<Self> {
+        match value {
+            0 => Ok(Tier::Flex),
+            1 => Ok(Tier::Core),
+            2 => Ok(Tier::Prime),
+            3 => Ok(Tier::Permanent),
+            _ => err!(StakingError::InvalidTier),
+        }
+    }
+}
+
+impl From<Tier> for u8 {
+    fn from(tier: Tier) -> u8 {
+        tier as u8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_every_wire_value() {
+        for (value, tier) in [(0, Tier::Flex), (1, Tier::Core), (2, Tier::Prime), (3, Tier::Permanent)] {
+            assert_eq!(Tier::try_from(value).unwrap(), tier);
+            assert_eq!(u8::from(tier), value);
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_wire_values() {
+        for value in [4u8, 42, u8::MAX] {
+            assert_eq!(
+                Tier::try_from(value).unwrap_err(),
+                error!(StakingError::InvalidTier)
+            );
+        }
+    }
+
+    #[test]
+    fn borsh_round_trip_is_one_byte() {
+        for tier in [Tier::Flex, Tier::Core, Tier::Prime, Tier::Permanent] {
+            let bytes = tier.try_to_vec().unwrap();
+            assert_eq!(bytes, vec![tier as u8]);
+            assert_eq!(Tier::try_from_slice(&bytes).unwrap(), tier);
+        }
+    }
+
+    #[test]
+    fn borsh_rejects_unknown_bytes() {
+        assert!(Tier::try_from_slice(&[4]).is_err());
+    }
+
+    #[test]
+    fn lock_periods_follow_the_tier() {
+        assert_eq!(Tier::Flex.lock_period(), 0);
+        assert_eq!(Tier::Core.lock_period(), CORE_LOCK_PERIOD);
+        assert_eq!(Tier::Prime.lock_period(), PRIME_LOCK_PERIOD);
+        assert_eq!(Tier::Permanent.lock_period(), 0);
+    }
+
+    #[test]
+    fn upgrades_only_go_up() {
+        assert!(Tier::Flex.is_upgrade_to(Tier::Core));
+        assert!(Tier::Core.is_upgrade_to(Tier::Prime));
+        assert!(Tier::Prime.is_upgrade_to(Tier::Permanent));
+        assert!(!Tier::Core.is_upgrade_to(Tier::Core));
+        assert!(!Tier::Prime.is_upgrade_to(Tier::Core));
+        assert!(!Tier::Permanent.is_upgrade_to(Tier::Flex));
+    }
 }
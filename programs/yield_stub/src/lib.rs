@@ -0,0 +1,67 @@
+//! Test-only yield adapter.
+//!
+//! Implements the adapter interface nova_staking calls when deploying idle
+//! treasury funds. nova_staking transfers deployed tokens into the stub's
+//! vault itself, then calls `deposit(amount)` with `[vault, vault_authority]`.
+//! `withdraw(amount)` takes `[vault, destination, vault_authority,
+//! token_program]` and pays out of the vault under the stub's own PDA. Yield
+//! is simulated by transferring extra tokens into the vault. There is no
+//! per-depositor accounting; never deploy this outside localnet.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+
+declare_id!("DTK1niUG3Lcz4ALxAYaWYRAefT67Z16CDpM1BkiXEcF2");
+
+pub const VAULT_AUTHORITY_SEED: &[u8] = b"vault_authority";
+
+#[program]
+pub mod yield_stub {
+    use super::*;
+
+    pub fn deposit(ctx: Context<AdapterDeposit>, amount: u64) -> Result<()> {
+        msg!("Deposit of {} recorded, vault holds {}", amount, ctx.accounts.vault.amount);
+        Ok(())
+    }
+
+    pub fn withdraw(ctx: Context<AdapterWithdraw>, amount: u64) -> Result<()> {
+        let seeds = &[VAULT_AUTHORITY_SEED, &[ctx.bumps.vault_authority]];
+        let signer_seeds = &[&seeds[..]];
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.vault.to_account_info(),
+            to: ctx.accounts.destination.to_account_info(),
+            authority: ctx.accounts.vault_authority.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        );
+        token::transfer(cpi_ctx, amount)
+    }
+}
+
+#[derive(Accounts)]
+pub struct AdapterDeposit<'info> {
+    #[account(mut, constraint = vault.owner == vault_authority.key())]
+    pub vault: Account<'info, TokenAccount>,
+
+    /// CHECK: PDA that owns the vault.
+    #[account(seeds = [VAULT_AUTHORITY_SEED], bump)]
+    pub vault_authority: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AdapterWithdraw<'info> {
+    #[account(mut, constraint = vault.owner == vault_authority.key())]
+    pub vault: Account<'info, TokenAccount>,
+
+    #[account(mut, constraint = destination.mint == vault.mint)]
+    pub destination: Account<'info, TokenAccount>,
+
+    /// CHECK: PDA that owns the vault; only used as a signer.
+    #[account(seeds = [VAULT_AUTHORITY_SEED], bump)]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
@@ -21,19 +21,28 @@ import {
   Keypair,
   PublicKey,
   SystemProgram,
+  Transaction,
   SYSVAR_RENT_PUBKEY,
+  SYSVAR_INSTRUCTIONS_PUBKEY,
+  TransactionInstruction,
   LAMPORTS_PER_SOL,
 } from "@solana/web3.js";
 import {
   TOKEN_PROGRAM_ID,
+  TOKEN_2022_PROGRAM_ID,
   createMint,
   createAccount,
   mintTo,
   getAccount,
+  getMint,
+  transfer,
+  closeAccount,
   Account as TokenAccount,
 } from "@solana/spl-token";
 import { expect } from "chai";
 import { NovaStaking } from "../target/types/nova_staking";
+import { YieldStub } from "../target/types/yield_stub";
+import { CpiProbe } from "../target/types/cpi_probe";
 
 // ============================================
 // CONSTANTS (must match program)
@@ -44,12 +53,35 @@ const STAKE_POOL_SEED = Buffer.from("stake_pool");
 const USER_STAKE_SEED = Buffer.from("user_stake");
 const POOL_VAULT_SEED = Buffer.from("pool_vault");
 const TREASURY_VAULT_SEED = Buffer.from("treasury_vault");
+const POOL_REGISTRY_SEED = Buffer.from("pool_registry");
+const RECEIPT_MINT_SEED = Buffer.from("receipt_mint");
+const FEE_VAULT_SEED = Buffer.from("fee_vault");
+const STAKER_RECORD_SEED = Buffer.from("staker_record");
 
 // Tier constants
 const TIER_FLEX = 0;
 const TIER_CORE = 1;
 const TIER_PRIME = 2;
 
+// UserStake.tier decodes as an Anchor enum object; map it back to its wire value
+const TIER_VARIANTS = ["flex", "core", "prime", "permanent"];
+const tierIndex = (tier: object): number => TIER_VARIANTS.indexOf(Object.keys(tier)[0]);
+
+// Top-up lock policies (Anchor enum encoding)
+const TOPUP_KEEP_ORIGINAL = { keepOriginal: {} };
+const TOPUP_EXTEND_ALL = { extendAll: {} };
+const TOPUP_NEW_LOT_ONLY = { newLotOnly: {} };
+const TOPUP_KEEP_ORIGINAL_BIT = 1;
+const TOPUP_EXTEND_ALL_BIT = 2;
+const DEFAULT_TOPUP_MASK = TOPUP_KEEP_ORIGINAL_BIT | TOPUP_EXTEND_ALL_BIT;
+
+const PAUSE_STAKE = 1 << 0;
+const PAUSE_CLAIM = 1 << 1;
+const PAUSE_UNSTAKE = 1 << 2;
+
+const REWARD_MODE_APY = { apy: {} };
+const REWARD_MODE_REWARD_PER_TOKEN = { rewardPerToken: {} };
+
 // Lock periods in seconds
 const SECONDS_PER_DAY = 86400;
 const CORE_LOCK_PERIOD = 90 * SECONDS_PER_DAY;   // 7,776,000 seconds
@@ -82,6 +114,8 @@ describe("Nova Staking Program - Comprehensive Tests", () => {
   anchor.setProvider(provider);
 
   const program = anchor.workspace.NovaStaking as Program<NovaStaking>;
+  const yieldStub = anchor.workspace.YieldStub as Program<YieldStub>;
+  const cpiProbe = anchor.workspace.CpiProbe as Program<CpiProbe>;
   const connection = provider.connection;
 
   // Test keypairs
@@ -110,6 +144,10 @@ describe("Nova Staking Program - Comprehensive Tests", () => {
   let flexUserStakePda: PublicKey;
   let coreUserStakePda: PublicKey;
   let primeUserStakePda: PublicKey;
+  const [poolRegistryPda] = PublicKey.findProgramAddressSync(
+    [POOL_REGISTRY_SEED],
+    program.programId
+  );
 
   // ============================================
   // HELPER FUNCTIONS
@@ -150,6 +188,16 @@ describe("Nova Staking Program - Comprehensive Tests", () => {
     );
   }
 
+  /**
+   * Derive a wallet's staker record, which stake and stake_for create
+   */
+  function stakerRecordPda(pool: PublicKey, wallet: PublicKey): PublicKey {
+    return PublicKey.findProgramAddressSync(
+      [STAKER_RECORD_SEED, pool.toBuffer(), wallet.toBuffer()],
+      program.programId
+    )[0];
+  }
+
   /**
    * Airdrop SOL to a keypair
    */
@@ -208,6 +256,213 @@ describe("Nova Staking Program - Comprehensive Tests", () => {
     return `${amount.div(divisor).toString()}.${amount.mod(divisor).toString().padStart(DECIMALS, '0')}`;
   }
 
+  /**
+   * Isolated pool on a fresh mint (keeps feature tests independent of the main pool)
+   */
+  interface IsolatedPool {
+    mint: PublicKey;
+    /** Same as `mint` unless the pool pays rewards in a separate token */
+    rewardMint: PublicKey;
+    pool: PublicKey;
+    vault: PublicKey;
+    treasury: PublicKey;
+    adminTokenAccount: PublicKey;
+    /** Admin's reward-mint account; funds the treasury */
+    adminRewardAccount: PublicKey;
+    /** Receipt mint, for pools created in receipt mode */
+    receiptMint: PublicKey | null;
+  }
+
+  interface Staker {
+    user: Keypair;
+    tokenAccount: PublicKey;
+    /** Receives claims; same as `tokenAccount` on single-token pools */
+    rewardAccount: PublicKey;
+    userStake: PublicKey;
+  }
+
+  /**
+   * Create and initialize a pool on a new mint; admin holds TREASURY_FUND tokens
+   * of the staking mint (and of the reward mint for dual-token pools)
+   */
+  async function createIsolatedPool(
+    emissionCap: BN = EMISSION_CAP,
+    freezeAuthority: PublicKey | null = null,
+    dualToken: boolean = false,
+    rewardMode: object = REWARD_MODE_APY,
+    allowCpi: boolean = false,
+    topupMask: number = DEFAULT_TOPUP_MASK,
+    receiptMode: boolean = false
+  ): Promise<IsolatedPool> {
+    const mint = await createMint(connection, admin, admin.publicKey, freezeAuthority, DECIMALS);
+    const rewardMint = dualToken
+      ? await createMint(connection, admin, admin.publicKey, null, DECIMALS)
+      : mint;
+    const [pool] = PublicKey.findProgramAddressSync(
+      [STAKE_POOL_SEED, mint.toBuffer()],
+      program.programId
+    );
+    const [vault] = PublicKey.findProgramAddressSync(
+      [POOL_VAULT_SEED, pool.toBuffer()],
+      program.programId
+    );
+    const [treasury] = PublicKey.findProgramAddressSync(
+      [TREASURY_VAULT_SEED, pool.toBuffer()],
+      program.programId
+    );
+    const receiptMint = receiptMode
+      ? PublicKey.findProgramAddressSync([RECEIPT_MINT_SEED, pool.toBuffer()], program.programId)[0]
+      : null;
+
+    await program.methods
+      .initialize(
+        emissionCap, FLEX_APY, CORE_APY, PRIME_APY, new BN(0), rewardMode as any, allowCpi, topupMask
+      )
+      .accounts({
+        authority: admin.publicKey,
+        stakePool: pool,
+        stakingMint: mint,
+        rewardMint,
+        stakingVault: vault,
+        treasuryVault: treasury,
+        poolRegistry: poolRegistryPda,
+        receiptMint,
+        systemProgram: SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        rent: SYSVAR_RENT_PUBKEY,
+      })
+      .signers([admin])
+      .rpc();
+
+    const adminToken = await createAccount(connection, admin, mint, admin.publicKey);
+    await mintTo(connection, admin, mint, adminToken, admin, BigInt(TREASURY_FUND.toString()));
+
+    let adminReward = adminToken;
+    if (dualToken) {
+      adminReward = await createAccount(connection, admin, rewardMint, admin.publicKey);
+      await mintTo(connection, admin, rewardMint, adminReward, admin, BigInt(TREASURY_FUND.toString()));
+    }
+
+    return {
+      mint,
+      rewardMint,
+      pool,
+      vault,
+      treasury,
+      adminTokenAccount: adminToken,
+      adminRewardAccount: adminReward,
+      receiptMint,
+    };
+  }
+
+  /**
+   * Create a funded user with a token account for the isolated pool's mint
+   */
+  async function createStaker(ctx: IsolatedPool): Promise<Staker> {
+    const user = Keypair.generate();
+    await airdropSol(user.publicKey, 10);
+    const tokenAccount = await createAccount(connection, user, ctx.mint, user.publicKey);
+    await mintTo(connection, admin, ctx.mint, tokenAccount, admin, BigInt(MINT_AMOUNT.toString()));
+    const rewardAccount = ctx.rewardMint.equals(ctx.mint)
+      ? tokenAccount
+      : await createAccount(connection, user, ctx.rewardMint, user.publicKey);
+    const [userStake] = PublicKey.findProgramAddressSync(
+      [USER_STAKE_SEED, ctx.pool.toBuffer(), user.publicKey.toBuffer()],
+      program.programId
+    );
+    return { user, tokenAccount, rewardAccount, userStake };
+  }
+
+  async function fundPool(ctx: IsolatedPool, amount: BN): Promise<void> {
+    await program.methods
+      .fundTreasury(amount)
+      .accounts({
+        funder: admin.publicKey,
+        stakePool: ctx.pool,
+        stakingMint: ctx.mint,
+        funderTokenAccount: ctx.adminRewardAccount,
+        treasuryVault: ctx.treasury,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([admin])
+      .rpc();
+  }
+
+  async function stakeInto(
+    ctx: IsolatedPool,
+    staker: Staker,
+    amount: BN,
+    tier: number,
+    lockPolicy: object = TOPUP_KEEP_ORIGINAL,
+    expectedApyBps: number | null = null,
+    referrer: PublicKey | null = null,
+    label: number[] | null = null
+  ): Promise<string> {
+    return program.methods
+      .stake(amount, tier, lockPolicy as any, expectedApyBps, referrer, label)
+      .accounts({
+        user: staker.user.publicKey,
+        stakePool: ctx.pool,
+        userStake: staker.userStake,
+        stakerRecord: stakerRecordPda(ctx.pool, staker.user.publicKey),
+        stakingMint: ctx.mint,
+        userTokenAccount: staker.tokenAccount,
+        stakingVault: ctx.vault,
+        systemProgram: SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+        rent: SYSVAR_RENT_PUBKEY,
+      })
+      .signers([staker.user])
+      .rpc();
+  }
+
+  async function claimFrom(
+    ctx: IsolatedPool,
+    staker: Staker,
+    minExpected: BN | null = null
+  ): Promise<string> {
+    return program.methods
+      .claimRewards(minExpected)
+      .accounts({
+        user: staker.user.publicKey,
+        stakePool: ctx.pool,
+        userStake: staker.userStake,
+        stakingMint: ctx.mint,
+        userTokenAccount: staker.rewardAccount,
+        owner: staker.user.publicKey,
+        rewardMint: ctx.rewardMint,
+        treasuryVault: ctx.treasury,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+      })
+      .signers([staker.user])
+      .rpc();
+  }
+
+  async function unstakeFrom(ctx: IsolatedPool, staker: Staker, amount: BN): Promise<string> {
+    return program.methods
+      .unstake(amount)
+      .accounts({
+        treasuryVault: ctx.treasury,
+        user: staker.user.publicKey,
+        stakePool: ctx.pool,
+        userStake: staker.userStake,
+        stakingMint: ctx.mint,
+        userTokenAccount: staker.tokenAccount,
+        stakingVault: ctx.vault,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+      })
+      .signers([staker.user])
+      .rpc();
+  }
+
+  async function tokenBalance(account: PublicKey): Promise<BN> {
+    const info = await getAccount(connection, account);
+    return new BN(info.amount.toString());
+  }
+
   // ============================================
   // TEST SETUP
   // ============================================
@@ -239,6 +494,17 @@ describe("Nova Staking Program - Comprehensive Tests", () => {
       airdropSol(nonAdmin.publicKey, 10),
     ]);
 
+    // Every initialize appends to the registry, so it has to exist first
+    await program.methods
+      .initializeRegistry()
+      .accounts({
+        authority: admin.publicKey,
+        poolRegistry: poolRegistryPda,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([admin])
+      .rpc();
+
     // Create staking mint (NOVA token)
     stakingMint = await createMint(
       connection,
@@ -287,13 +553,15 @@ describe("Nova Staking Program - Comprehensive Tests", () => {
   describe("1. Initialize Pool", () => {
     it("should initialize pool with correct staking_mint and authority", async () => {
       await program.methods
-        .initialize(EMISSION_CAP, FLEX_APY, CORE_APY, PRIME_APY)
+        .initialize(EMISSION_CAP, FLEX_APY, CORE_APY, PRIME_APY, new BN(0), REWARD_MODE_APY, false, DEFAULT_TOPUP_MASK)
         .accounts({
           authority: admin.publicKey,
           stakePool: stakePoolPda,
           stakingMint: stakingMint,
+          rewardMint: stakingMint,
           stakingVault: stakingVaultPda,
           treasuryVault: treasuryVaultPda,
+          poolRegistry: poolRegistryPda,
           systemProgram: SystemProgram.programId,
           tokenProgram: TOKEN_PROGRAM_ID,
           rent: SYSVAR_RENT_PUBKEY,
@@ -331,7 +599,7 @@ describe("Nova Staking Program - Comprehensive Tests", () => {
       expect(poolState.totalStaked.toNumber()).to.equal(0);
       expect(poolState.totalDistributed.toNumber()).to.equal(0);
       expect(poolState.stakerCount.toNumber()).to.equal(0);
-      expect(poolState.paused).to.equal(false);
+      expect(poolState.pauseFlags).to.equal(0);
 
       console.log("✓ Pool initialized with correct staking_mint and authority");
     });
@@ -339,13 +607,15 @@ describe("Nova Staking Program - Comprehensive Tests", () => {
     it("should reject re-initialization (pool already exists)", async () => {
       try {
         await program.methods
-          .initialize(EMISSION_CAP, FLEX_APY, CORE_APY, PRIME_APY)
+          .initialize(EMISSION_CAP, FLEX_APY, CORE_APY, PRIME_APY, new BN(0), REWARD_MODE_APY, false, DEFAULT_TOPUP_MASK)
           .accounts({
             authority: nonAdmin.publicKey,
             stakePool: stakePoolPda,
             stakingMint: stakingMint,
+            rewardMint: stakingMint,
             stakingVault: stakingVaultPda,
             treasuryVault: treasuryVaultPda,
+            poolRegistry: poolRegistryPda,
             systemProgram: SystemProgram.programId,
             tokenProgram: TOKEN_PROGRAM_ID,
             rent: SYSVAR_RENT_PUBKEY,
@@ -468,16 +738,18 @@ describe("Nova Staking Program - Comprehensive Tests", () => {
         const vaultBalanceBefore = new BN(vaultBefore.amount.toString());
 
         await program.methods
-          .stake(STAKE_AMOUNT, TIER_FLEX)
+          .stake(STAKE_AMOUNT, TIER_FLEX, TOPUP_KEEP_ORIGINAL, null, null, null)
           .accounts({
             user: flexUser.publicKey,
             stakePool: stakePoolPda,
             userStake: flexUserStakePda,
+            stakerRecord: stakerRecordPda(stakePoolPda, flexUser.publicKey),
             stakingMint: stakingMint,
             userTokenAccount: flexUserTokenAccount,
             stakingVault: stakingVaultPda,
             systemProgram: SystemProgram.programId,
             tokenProgram: TOKEN_PROGRAM_ID,
+            instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
             rent: SYSVAR_RENT_PUBKEY,
           })
           .signers([flexUser])
@@ -513,7 +785,7 @@ describe("Nova Staking Program - Comprehensive Tests", () => {
         );
 
         // Verify tier
-        expect(userStake.tier).to.equal(TIER_FLEX, "Tier should be Flex (0)");
+        expect(tierIndex(userStake.tier)).to.equal(TIER_FLEX, "Tier should be Flex (0)");
 
         // Verify is_active
         expect(userStake.isActive).to.equal(true, "Stake should be active");
@@ -543,16 +815,18 @@ describe("Nova Staking Program - Comprehensive Tests", () => {
         const vaultBalanceBefore = new BN(vaultBefore.amount.toString());
 
         await program.methods
-          .stake(STAKE_AMOUNT, TIER_CORE)
+          .stake(STAKE_AMOUNT, TIER_CORE, TOPUP_KEEP_ORIGINAL, null, null, null)
           .accounts({
             user: coreUser.publicKey,
             stakePool: stakePoolPda,
             userStake: coreUserStakePda,
+            stakerRecord: stakerRecordPda(stakePoolPda, coreUser.publicKey),
             stakingMint: stakingMint,
             userTokenAccount: coreUserTokenAccount,
             stakingVault: stakingVaultPda,
             systemProgram: SystemProgram.programId,
             tokenProgram: TOKEN_PROGRAM_ID,
+            instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
             rent: SYSVAR_RENT_PUBKEY,
           })
           .signers([coreUser])
@@ -569,7 +843,7 @@ describe("Nova Staking Program - Comprehensive Tests", () => {
       it("should set user stake fields correctly for Core tier", async () => {
         const userStake = await program.account.userStake.fetch(coreUserStakePda);
 
-        expect(userStake.tier).to.equal(TIER_CORE, "Tier should be Core (1)");
+        expect(tierIndex(userStake.tier)).to.equal(TIER_CORE, "Tier should be Core (1)");
         expect(userStake.stakedAmount.toString()).to.equal(STAKE_AMOUNT.toString());
         expect(userStake.isActive).to.equal(true);
 
@@ -583,16 +857,18 @@ describe("Nova Staking Program - Comprehensive Tests", () => {
         const vaultBalanceBefore = new BN(vaultBefore.amount.toString());
 
         await program.methods
-          .stake(STAKE_AMOUNT, TIER_PRIME)
+          .stake(STAKE_AMOUNT, TIER_PRIME, TOPUP_KEEP_ORIGINAL, null, null, null)
           .accounts({
             user: primeUser.publicKey,
             stakePool: stakePoolPda,
             userStake: primeUserStakePda,
+            stakerRecord: stakerRecordPda(stakePoolPda, primeUser.publicKey),
             stakingMint: stakingMint,
             userTokenAccount: primeUserTokenAccount,
             stakingVault: stakingVaultPda,
             systemProgram: SystemProgram.programId,
             tokenProgram: TOKEN_PROGRAM_ID,
+            instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
             rent: SYSVAR_RENT_PUBKEY,
           })
           .signers([primeUser])
@@ -609,7 +885,7 @@ describe("Nova Staking Program - Comprehensive Tests", () => {
       it("should set user stake fields correctly for Prime tier", async () => {
         const userStake = await program.account.userStake.fetch(primeUserStakePda);
 
-        expect(userStake.tier).to.equal(TIER_PRIME, "Tier should be Prime (2)");
+        expect(tierIndex(userStake.tier)).to.equal(TIER_PRIME, "Tier should be Prime (2)");
         expect(userStake.stakedAmount.toString()).to.equal(STAKE_AMOUNT.toString());
         expect(userStake.isActive).to.equal(true);
 
@@ -653,15 +929,18 @@ describe("Nova Staking Program - Comprehensive Tests", () => {
 
       // Claim rewards
       await program.methods
-        .claimRewards()
+        .claimRewards(null)
         .accounts({
           user: flexUser.publicKey,
           stakePool: stakePoolPda,
           userStake: flexUserStakePda,
           stakingMint: stakingMint,
           userTokenAccount: flexUserTokenAccount,
+          owner: flexUser.publicKey,
+          rewardMint: stakingMint,
           treasuryVault: treasuryVaultPda,
           tokenProgram: TOKEN_PROGRAM_ID,
+          instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
         })
         .signers([flexUser])
         .rpc();
@@ -697,15 +976,18 @@ describe("Nova Staking Program - Comprehensive Tests", () => {
 
       try {
         await program.methods
-          .claimRewards()
+          .claimRewards(null)
           .accounts({
             user: flexUser.publicKey,
             stakePool: stakePoolPda,
             userStake: flexUserStakePda,
             stakingMint: stakingMint,
             userTokenAccount: flexUserTokenAccount,
+            owner: flexUser.publicKey,
+            rewardMint: stakingMint,
             treasuryVault: treasuryVaultPda,
             tokenProgram: TOKEN_PROGRAM_ID,
+            instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
           })
           .signers([flexUser])
           .rpc();
@@ -750,6 +1032,7 @@ describe("Nova Staking Program - Comprehensive Tests", () => {
             userTokenAccount: flexUserTokenAccount,
             stakingVault: stakingVaultPda,
             tokenProgram: TOKEN_PROGRAM_ID,
+            instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
           })
           .signers([flexUser])
           .rpc();
@@ -786,6 +1069,7 @@ describe("Nova Staking Program - Comprehensive Tests", () => {
               userTokenAccount: coreUserTokenAccount,
               stakingVault: stakingVaultPda,
               tokenProgram: TOKEN_PROGRAM_ID,
+              instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
             })
             .signers([coreUser])
             .rpc();
@@ -811,6 +1095,7 @@ describe("Nova Staking Program - Comprehensive Tests", () => {
               userTokenAccount: primeUserTokenAccount,
               stakingVault: stakingVaultPda,
               tokenProgram: TOKEN_PROGRAM_ID,
+              instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
             })
             .signers([primeUser])
             .rpc();
@@ -848,6 +1133,7 @@ describe("Nova Staking Program - Comprehensive Tests", () => {
             userTokenAccount: flexUserTokenAccount,
             stakingVault: stakingVaultPda,
             tokenProgram: TOKEN_PROGRAM_ID,
+            instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
           })
           .signers([flexUser])
           .rpc();
@@ -925,13 +1211,15 @@ describe("Nova Staking Program - Comprehensive Tests", () => {
       const lowEmissionCap = ONE_TOKEN.mul(new BN(10)); // Only 10 tokens
 
       await program.methods
-        .initialize(lowEmissionCap, FLEX_APY, CORE_APY, PRIME_APY)
+        .initialize(lowEmissionCap, FLEX_APY, CORE_APY, PRIME_APY, new BN(0), REWARD_MODE_APY, false, DEFAULT_TOPUP_MASK)
         .accounts({
           authority: admin.publicKey,
           stakePool: lowCapPoolPda,
           stakingMint: lowCapMint,
+          rewardMint: lowCapMint,
           stakingVault: lowCapVaultPda,
           treasuryVault: lowCapTreasuryPda,
+          poolRegistry: poolRegistryPda,
           systemProgram: SystemProgram.programId,
           tokenProgram: TOKEN_PROGRAM_ID,
           rent: SYSVAR_RENT_PUBKEY,
@@ -958,16 +1246,18 @@ describe("Nova Staking Program - Comprehensive Tests", () => {
 
       // Stake
       await program.methods
-        .stake(STAKE_AMOUNT, TIER_FLEX)
+        .stake(STAKE_AMOUNT, TIER_FLEX, TOPUP_KEEP_ORIGINAL, null, null, null)
         .accounts({
           user: emissionTestUser.publicKey,
           stakePool: lowCapPoolPda,
           userStake: emissionTestStakePda,
+          stakerRecord: stakerRecordPda(lowCapPoolPda, emissionTestUser.publicKey),
           stakingMint: lowCapMint,
           userTokenAccount: emissionTestTokenAccount,
           stakingVault: lowCapVaultPda,
           systemProgram: SystemProgram.programId,
           tokenProgram: TOKEN_PROGRAM_ID,
+          instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
           rent: SYSVAR_RENT_PUBKEY,
         })
         .signers([emissionTestUser])
@@ -989,15 +1279,18 @@ describe("Nova Staking Program - Comprehensive Tests", () => {
       // Try to claim - should fail or be capped
       try {
         await program.methods
-          .claimRewards()
+          .claimRewards(null)
           .accounts({
             user: emissionTestUser.publicKey,
             stakePool: lowCapPoolPda,
             userStake: emissionTestStakePda,
             stakingMint: lowCapMint,
             userTokenAccount: emissionTestTokenAccount,
+            owner: emissionTestUser.publicKey,
+            rewardMint: lowCapMint,
             treasuryVault: lowCapTreasuryPda,
             tokenProgram: TOKEN_PROGRAM_ID,
+            instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
           })
           .signers([emissionTestUser])
           .rpc();
@@ -1045,7 +1338,7 @@ describe("Nova Staking Program - Comprehensive Tests", () => {
 
     it("should allow admin to pause staking", async () => {
       await program.methods
-        .setPaused(true)
+        .setPaused(true, null)
         .accounts({
           authority: admin.publicKey,
           stakePool: stakePoolPda,
@@ -1054,7 +1347,7 @@ describe("Nova Staking Program - Comprehensive Tests", () => {
         .rpc();
 
       const poolState = await program.account.stakePool.fetch(stakePoolPda);
-      expect(poolState.paused).to.equal(true, "Pool should be paused");
+      expect(poolState.pauseFlags & PAUSE_STAKE).to.equal(PAUSE_STAKE, "Pool should be paused");
 
       console.log("✓ Admin paused staking");
     });
@@ -1062,16 +1355,18 @@ describe("Nova Staking Program - Comprehensive Tests", () => {
     it("should block new stakes when paused", async () => {
       try {
         await program.methods
-          .stake(STAKE_AMOUNT, TIER_FLEX)
+          .stake(STAKE_AMOUNT, TIER_FLEX, TOPUP_KEEP_ORIGINAL, null, null, null)
           .accounts({
             user: pauseTestUser.publicKey,
             stakePool: stakePoolPda,
             userStake: pauseTestStakePda,
+            stakerRecord: stakerRecordPda(stakePoolPda, pauseTestUser.publicKey),
             stakingMint: stakingMint,
             userTokenAccount: pauseTestTokenAccount,
             stakingVault: stakingVaultPda,
             systemProgram: SystemProgram.programId,
             tokenProgram: TOKEN_PROGRAM_ID,
+            instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
             rent: SYSVAR_RENT_PUBKEY,
           })
           .signers([pauseTestUser])
@@ -1087,7 +1382,7 @@ describe("Nova Staking Program - Comprehensive Tests", () => {
     it("should reject non-admin pause attempt", async () => {
       try {
         await program.methods
-          .setPaused(false)
+          .setPaused(false, null)
           .accounts({
             authority: nonAdmin.publicKey,
             stakePool: stakePoolPda,
@@ -1106,7 +1401,7 @@ describe("Nova Staking Program - Comprehensive Tests", () => {
 
     it("should allow admin to unpause staking", async () => {
       await program.methods
-        .setPaused(false)
+        .setPaused(false, null)
         .accounts({
           authority: admin.publicKey,
           stakePool: stakePoolPda,
@@ -1115,23 +1410,25 @@ describe("Nova Staking Program - Comprehensive Tests", () => {
         .rpc();
 
       const poolState = await program.account.stakePool.fetch(stakePoolPda);
-      expect(poolState.paused).to.equal(false, "Pool should be unpaused");
+      expect(poolState.pauseFlags & PAUSE_STAKE).to.equal(0, "Pool should be unpaused");
 
       console.log("✓ Admin unpaused staking");
     });
 
     it("should allow staking after unpause", async () => {
       await program.methods
-        .stake(STAKE_AMOUNT, TIER_FLEX)
+        .stake(STAKE_AMOUNT, TIER_FLEX, TOPUP_KEEP_ORIGINAL, null, null, null)
         .accounts({
           user: pauseTestUser.publicKey,
           stakePool: stakePoolPda,
           userStake: pauseTestStakePda,
+          stakerRecord: stakerRecordPda(stakePoolPda, pauseTestUser.publicKey),
           stakingMint: stakingMint,
           userTokenAccount: pauseTestTokenAccount,
           stakingVault: stakingVaultPda,
           systemProgram: SystemProgram.programId,
           tokenProgram: TOKEN_PROGRAM_ID,
+          instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
           rent: SYSVAR_RENT_PUBKEY,
         })
         .signers([pauseTestUser])
@@ -1163,16 +1460,18 @@ describe("Nova Staking Program - Comprehensive Tests", () => {
 
       try {
         await program.methods
-          .stake(new BN(0), TIER_FLEX)
+          .stake(new BN(0), TIER_FLEX, TOPUP_KEEP_ORIGINAL, null, null, null)
           .accounts({
             user: testUser.publicKey,
             stakePool: stakePoolPda,
             userStake: testStakePda,
+            stakerRecord: stakerRecordPda(stakePoolPda, testUser.publicKey),
             stakingMint: stakingMint,
             userTokenAccount: testToken,
             stakingVault: stakingVaultPda,
             systemProgram: SystemProgram.programId,
             tokenProgram: TOKEN_PROGRAM_ID,
+            instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
             rent: SYSVAR_RENT_PUBKEY,
           })
           .signers([testUser])
@@ -1198,16 +1497,18 @@ describe("Nova Staking Program - Comprehensive Tests", () => {
 
       try {
         await program.methods
-          .stake(STAKE_AMOUNT, 99) // Invalid tier
+          .stake(STAKE_AMOUNT, 99, TOPUP_KEEP_ORIGINAL, null, null, null) // Invalid tier
           .accounts({
             user: testUser.publicKey,
             stakePool: stakePoolPda,
             userStake: testStakePda,
+            stakerRecord: stakerRecordPda(stakePoolPda, testUser.publicKey),
             stakingMint: stakingMint,
             userTokenAccount: testToken,
             stakingVault: stakingVaultPda,
             systemProgram: SystemProgram.programId,
             tokenProgram: TOKEN_PROGRAM_ID,
+            instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
             rent: SYSVAR_RENT_PUBKEY,
           })
           .signers([testUser])
@@ -1223,16 +1524,18 @@ describe("Nova Staking Program - Comprehensive Tests", () => {
     it("should reject unstaking more than staked", async () => {
       // Re-stake flexUser first
       await program.methods
-        .stake(STAKE_AMOUNT, TIER_FLEX)
+        .stake(STAKE_AMOUNT, TIER_FLEX, TOPUP_KEEP_ORIGINAL, null, null, null)
         .accounts({
           user: flexUser.publicKey,
           stakePool: stakePoolPda,
           userStake: flexUserStakePda,
+          stakerRecord: stakerRecordPda(stakePoolPda, flexUser.publicKey),
           stakingMint: stakingMint,
           userTokenAccount: flexUserTokenAccount,
           stakingVault: stakingVaultPda,
           systemProgram: SystemProgram.programId,
           tokenProgram: TOKEN_PROGRAM_ID,
+          instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
           rent: SYSVAR_RENT_PUBKEY,
         })
         .signers([flexUser])
@@ -1249,6 +1552,7 @@ describe("Nova Staking Program - Comprehensive Tests", () => {
             userTokenAccount: flexUserTokenAccount,
             stakingVault: stakingVaultPda,
             tokenProgram: TOKEN_PROGRAM_ID,
+            instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
           })
           .signers([flexUser])
           .rpc();
@@ -1311,6 +1615,8461 @@ describe("Nova Staking Program - Comprehensive Tests", () => {
     });
   });
 
+  // ============================================
+  // TEST 11: FUNDED-ONLY ACCRUAL
+  // ============================================
+
+  describe("11. Accrue Only When Funded", () => {
+    let ctx: IsolatedPool;
+    let staker: Staker;
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      staker = await createStaker(ctx);
+
+      await program.methods
+        .setAccrueOnlyWhenFunded(true)
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+    });
+
+    it("should accrue nothing while the treasury has never been funded", async () => {
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_FLEX);
+      await advanceTime(5);
+
+      const poolState = await program.account.stakePool.fetch(ctx.pool);
+      expect(poolState.accrueOnlyWhenFunded).to.equal(true);
+      expect(poolState.treasuryEmptySince).to.not.equal(null);
+
+      try {
+        await claimFrom(ctx, staker);
+        expect.fail("Should have thrown NoRewardsAvailable");
+      } catch (error: any) {
+        expect(error.message).to.include("NoRewardsAvailable");
+        console.log("✓ Empty-treasury window accrued zero rewards");
+      }
+    });
+
+    it("should resume accrual after funding and exclude the empty window", async () => {
+      await fundPool(ctx, TREASURY_FUND);
+      const fundedAt = await getCurrentTimestamp();
+
+      const poolState = await program.account.stakePool.fetch(ctx.pool);
+      expect(poolState.treasuryEmptySince).to.equal(null);
+      expect(poolState.lastEmptyWindowEnd.toNumber()).to.be.greaterThan(0);
+
+      await advanceTime(5);
+
+      const balanceBefore = await tokenBalance(staker.tokenAccount);
+      await claimFrom(ctx, staker);
+      const claimedAt = await getCurrentTimestamp();
+      const received = (await tokenBalance(staker.tokenAccount)).sub(balanceBefore);
+
+      expect(received.toNumber()).to.be.greaterThan(0);
+      // Upper bound: only the funded interval may have accrued
+      const maxExpected = calculateExpectedRewards(STAKE_AMOUNT, FLEX_APY, claimedAt - fundedAt + 2);
+      expect(received.lte(maxExpected)).to.equal(true, "Empty window must not accrue");
+
+      console.log(`✓ Accrual resumed after funding (${formatTokens(received)} claimed)`);
+    });
+  });
+
+  // ============================================
+  // TEST 12: ACCRUAL HORIZON
+  // ============================================
+
+  describe("12. Accrual Horizon", () => {
+    const HORIZON_SECS = 2;
+    let ctx: IsolatedPool;
+    let staker: Staker;
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      staker = await createStaker(ctx);
+      await fundPool(ctx, TREASURY_FUND);
+
+      await program.methods
+        .setMaxAccrualHorizon(new BN(HORIZON_SECS))
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_FLEX);
+    });
+
+    it("should clamp accrual to the horizon for an untouched position", async () => {
+      await advanceTime(8);
+
+      const balanceBefore = await tokenBalance(staker.tokenAccount);
+      await claimFrom(ctx, staker);
+      const received = (await tokenBalance(staker.tokenAccount)).sub(balanceBefore);
+
+      const atHorizon = calculateExpectedRewards(STAKE_AMOUNT, FLEX_APY, HORIZON_SECS);
+      expect(received.toString()).to.equal(atHorizon.toString(), "Accrual should stop at the horizon");
+
+      console.log(`✓ Accrual clamped at ${HORIZON_SECS}s (${formatTokens(received)})`);
+    });
+
+    it("should reset the horizon window after a touch", async () => {
+      await advanceTime(8);
+
+      const balanceBefore = await tokenBalance(staker.tokenAccount);
+      await claimFrom(ctx, staker);
+      const received = (await tokenBalance(staker.tokenAccount)).sub(balanceBefore);
+
+      const atHorizon = calculateExpectedRewards(STAKE_AMOUNT, FLEX_APY, HORIZON_SECS);
+      expect(received.toString()).to.equal(atHorizon.toString(), "Window should restart from the last claim");
+
+      console.log("✓ Horizon window reset by claim");
+    });
+
+    it("should reject a negative horizon", async () => {
+      try {
+        await program.methods
+          .setMaxAccrualHorizon(new BN(-1))
+          .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+          .signers([admin])
+          .rpc();
+        expect.fail("Should have thrown InvalidAccrualHorizon");
+      } catch (error: any) {
+        expect(error.message).to.include("InvalidAccrualHorizon");
+        console.log("✓ Negative horizon rejected");
+      }
+    });
+  });
+
+  // ============================================
+  // TEST 13: DAILY INFLOW CAP
+  // ============================================
+
+  describe("13. Daily Stake Inflow Cap", () => {
+    const DAILY_CAP = STAKE_AMOUNT.mul(new BN(2));
+    let ctx: IsolatedPool;
+    let stakerA: Staker;
+    let stakerB: Staker;
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      stakerA = await createStaker(ctx);
+      stakerB = await createStaker(ctx);
+
+      await program.methods
+        .setMaxDailyInflow(DAILY_CAP)
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+    });
+
+    it("should accept deposits up to the daily cap", async () => {
+      await stakeInto(ctx, stakerA, STAKE_AMOUNT, TIER_FLEX);
+      await stakeInto(ctx, stakerB, STAKE_AMOUNT, TIER_FLEX);
+
+      const poolState = await program.account.stakePool.fetch(ctx.pool);
+      expect(poolState.inflowInWindow.toString()).to.equal(DAILY_CAP.toString());
+      console.log("✓ Window filled exactly to the cap");
+    });
+
+    it("should reject a deposit once the window is exhausted", async () => {
+      try {
+        await stakeInto(ctx, stakerA, new BN(1), TIER_FLEX);
+        expect.fail("Should have thrown DailyInflowCapExceeded");
+      } catch (error: any) {
+        expect(error.message).to.include("DailyInflowCapExceeded");
+        console.log("✓ Over-cap deposit rejected");
+      }
+    });
+
+    it("should not refund headroom on unstake within the window", async () => {
+      await unstakeFrom(ctx, stakerA, STAKE_AMOUNT);
+
+      try {
+        await stakeInto(ctx, stakerA, new BN(1), TIER_FLEX);
+        expect.fail("Should have thrown DailyInflowCapExceeded");
+      } catch (error: any) {
+        expect(error.message).to.include("DailyInflowCapExceeded");
+        console.log("✓ Unstake did not restore headroom");
+      }
+    });
+
+    it("should accept deposits again once the cap is disabled", async () => {
+      // Localnet cannot warp a full day; disabling the cap exercises the zero path
+      await program.methods
+        .setMaxDailyInflow(new BN(0))
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+
+      await stakeInto(ctx, stakerA, STAKE_AMOUNT, TIER_FLEX);
+      console.log("✓ Zero cap disables the limit");
+    });
+  });
+
+  // ============================================
+  // TEST 14: EMERGENCY MODE
+  // ============================================
+
+  describe("14. Emergency Mode", () => {
+    let ctx: IsolatedPool;
+    let flexStaker: Staker;
+    let primeStaker: Staker;
+
+    async function expectEmergencyError(action: () => Promise<unknown>, label: string): Promise<void> {
+      try {
+        await action();
+        expect.fail(`${label} should have thrown EmergencyMode`);
+      } catch (error: any) {
+        expect(error.message).to.include("EmergencyMode");
+      }
+    }
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      flexStaker = await createStaker(ctx);
+      primeStaker = await createStaker(ctx);
+      await fundPool(ctx, TREASURY_FUND);
+      await stakeInto(ctx, flexStaker, STAKE_AMOUNT, TIER_FLEX);
+      await stakeInto(ctx, primeStaker, STAKE_AMOUNT, TIER_PRIME);
+    });
+
+    it("should keep Prime locked outside emergency mode", async () => {
+      try {
+        await unstakeFrom(ctx, primeStaker, STAKE_AMOUNT);
+        expect.fail("Should have thrown LockPeriodNotEnded");
+      } catch (error: any) {
+        expect(error.message).to.include("LockPeriodNotEnded");
+      }
+      console.log("✓ Normal mode: lock enforced");
+    });
+
+    it("should reject set/clear emergency from non-admin", async () => {
+      try {
+        await program.methods
+          .setEmergency()
+          .accounts({ authority: nonAdmin.publicKey, stakePool: ctx.pool })
+          .signers([nonAdmin])
+          .rpc();
+        expect.fail("Should have thrown Unauthorized");
+      } catch (error: any) {
+        expect(error.message).to.include("Unauthorized");
+      }
+      console.log("✓ Non-admin cannot enter emergency mode");
+    });
+
+    it("should block stake, claim, and funding in emergency mode", async () => {
+      await program.methods
+        .setEmergency()
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+
+      const poolState = await program.account.stakePool.fetch(ctx.pool);
+      expect(poolState.emergency).to.equal(true);
+
+      await expectEmergencyError(() => stakeInto(ctx, flexStaker, STAKE_AMOUNT, TIER_FLEX), "stake");
+      await expectEmergencyError(() => claimFrom(ctx, flexStaker), "claim");
+      await expectEmergencyError(() => fundPool(ctx, ONE_TOKEN), "fund_treasury");
+
+      console.log("✓ Emergency mode: stake/claim/fund blocked");
+    });
+
+    it("should allow unstake regardless of lock in emergency mode", async () => {
+      await unstakeFrom(ctx, flexStaker, STAKE_AMOUNT);
+
+      const balanceBefore = await tokenBalance(primeStaker.tokenAccount);
+      await unstakeFrom(ctx, primeStaker, STAKE_AMOUNT);
+      const received = (await tokenBalance(primeStaker.tokenAccount)).sub(balanceBefore);
+      expect(received.toString()).to.equal(STAKE_AMOUNT.toString());
+
+      console.log("✓ Emergency mode: Flex and locked Prime principal withdrawn");
+    });
+
+    it("should restore normal behavior after clear_emergency", async () => {
+      await program.methods
+        .clearEmergency()
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+
+      await fundPool(ctx, ONE_TOKEN);
+      await stakeInto(ctx, primeStaker, STAKE_AMOUNT, TIER_PRIME);
+
+      try {
+        await unstakeFrom(ctx, primeStaker, STAKE_AMOUNT);
+        expect.fail("Should have thrown LockPeriodNotEnded");
+      } catch (error: any) {
+        expect(error.message).to.include("LockPeriodNotEnded");
+      }
+      console.log("✓ Cleared: stake/fund work again and locks are enforced");
+    });
+  });
+
+  // ============================================
+  // TEST 15: CHANGE TIER
+  // ============================================
+
+  describe("15. Change Tier", () => {
+    let ctx: IsolatedPool;
+    let staker: Staker;
+
+    async function changeTier(newTier: number): Promise<string> {
+      return program.methods
+        .changeTier(newTier)
+        .accounts({
+          user: staker.user.publicKey,
+          stakePool: ctx.pool,
+          userStake: staker.userStake,
+        })
+        .signers([staker.user])
+        .rpc();
+    }
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      staker = await createStaker(ctx);
+      await fundPool(ctx, TREASURY_FUND);
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_FLEX);
+    });
+
+    it("should relock when moving to the same tier", async () => {
+      const before = await program.account.userStake.fetch(staker.userStake);
+      await advanceTime(3);
+      await changeTier(TIER_FLEX);
+
+      const after = await program.account.userStake.fetch(staker.userStake);
+      expect(tierIndex(after.tier)).to.equal(TIER_FLEX);
+      expect(after.stakeStartTime.toNumber()).to.be.greaterThanOrEqual(before.stakeStartTime.toNumber());
+      console.log("✓ Same-tier change behaves like relock");
+    });
+
+    it("should settle accrual and move an unlocked Flex position up to Core", async () => {
+      await advanceTime(3);
+      const before = await program.account.userStake.fetch(staker.userStake);
+      await changeTier(TIER_CORE);
+      const now = await getCurrentTimestamp();
+
+      const after = await program.account.userStake.fetch(staker.userStake);
+      expect(tierIndex(after.tier)).to.equal(TIER_CORE);
+      expect(after.stakedAmount.toString()).to.equal(STAKE_AMOUNT.toString());
+      expect(after.pendingRewards.gt(before.pendingRewards)).to.equal(true, "Flex accrual should be settled");
+      expect(after.lastClaimTime.toNumber()).to.equal(after.stakeStartTime.toNumber());
+      expect(after.stakeStartTime.toNumber()).to.be.at.most(now + 1);
+      console.log("✓ Flex -> Core upgrade settled rewards and started a new lock");
+    });
+
+    it("should reject moving down while the Core lock is running", async () => {
+      try {
+        await changeTier(TIER_FLEX);
+        expect.fail("Should have thrown LockPeriodNotEnded");
+      } catch (error: any) {
+        expect(error.message).to.include("LockPeriodNotEnded");
+        console.log("✓ Locked position cannot change tier");
+      }
+    });
+
+    it("should reject an invalid tier", async () => {
+      try {
+        await changeTier(7);
+        expect.fail("Should have thrown InvalidTier");
+      } catch (error: any) {
+        expect(error.message).to.include("InvalidTier");
+        console.log("✓ Invalid tier rejected");
+      }
+    });
+  });
+
+  // ============================================
+  // TEST 16: TOP-UP LOCK POLICY
+  // ============================================
+
+  describe("16. Top-Up Lock Policy", () => {
+    let ctx: IsolatedPool;
+    let staker: Staker;
+
+    async function setAllowedPolicies(mask: number): Promise<void> {
+      await program.methods
+        .setAllowedTopupPolicies(mask)
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+    }
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      staker = await createStaker(ctx);
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_CORE);
+    });
+
+    it("should keep the original lock start with KeepOriginal", async () => {
+      const before = await program.account.userStake.fetch(staker.userStake);
+      await advanceTime(3);
+      await stakeInto(ctx, staker, ONE_TOKEN, TIER_CORE, TOPUP_KEEP_ORIGINAL);
+
+      // A 1-token share of the weighted lock rounds up to at most one second
+      const after = await program.account.userStake.fetch(staker.userStake);
+      const shift = after.stakeStartTime.toNumber() - before.stakeStartTime.toNumber();
+      expect(shift).to.be.within(0, 1);
+      console.log("✓ KeepOriginal preserved stake_start_time");
+    });
+
+    it("should restart the lock with ExtendAll", async () => {
+      const before = await program.account.userStake.fetch(staker.userStake);
+      await advanceTime(3);
+      await stakeInto(ctx, staker, ONE_TOKEN, TIER_CORE, TOPUP_EXTEND_ALL);
+
+      const after = await program.account.userStake.fetch(staker.userStake);
+      expect(after.stakeStartTime.toNumber()).to.be.greaterThan(before.stakeStartTime.toNumber());
+      console.log("✓ ExtendAll reset stake_start_time");
+    });
+
+    it("should echo the applied policy in return data", async () => {
+      const sim = await program.methods
+        .stake(ONE_TOKEN, TIER_CORE, TOPUP_EXTEND_ALL as any, null, null, null)
+        .accounts({
+          user: staker.user.publicKey,
+          stakePool: ctx.pool,
+          userStake: staker.userStake,
+          stakerRecord: stakerRecordPda(ctx.pool, staker.user.publicKey),
+          stakingMint: ctx.mint,
+          userTokenAccount: staker.tokenAccount,
+          stakingVault: ctx.vault,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+          rent: SYSVAR_RENT_PUBKEY,
+        })
+        .signers([staker.user])
+        .simulate();
+
+      const returnLog = sim.raw.find((line) => line.startsWith("Program return:"));
+      expect(returnLog).to.not.equal(undefined);
+      const data = Buffer.from(returnLog!.split(" ").pop()!, "base64");
+      expect(data[0]).to.equal(1, "ExtendAll should be encoded as variant 1");
+      console.log("✓ Return data carries the applied policy");
+    });
+
+    it("should reject a policy the pool does not allow", async () => {
+      await setAllowedPolicies(2); // ExtendAll only
+
+      try {
+        await stakeInto(ctx, staker, ONE_TOKEN, TIER_CORE, TOPUP_KEEP_ORIGINAL);
+        expect.fail("Should have thrown TopUpPolicyNotAllowed");
+      } catch (error: any) {
+        expect(error.message).to.include("TopUpPolicyNotAllowed");
+      }
+
+      try {
+        await stakeInto(ctx, staker, ONE_TOKEN, TIER_CORE, TOPUP_NEW_LOT_ONLY);
+        expect.fail("Should have thrown TopUpPolicyNotAllowed");
+      } catch (error: any) {
+        expect(error.message).to.include("TopUpPolicyNotAllowed");
+      }
+      console.log("✓ Disallowed policies rejected");
+    });
+
+    it("should refuse to enable NewLotOnly without lot accounting", async () => {
+      try {
+        await setAllowedPolicies(7);
+        expect.fail("Should have thrown TopUpPolicyUnavailable");
+      } catch (error: any) {
+        expect(error.message).to.include("TopUpPolicyUnavailable");
+        console.log("✓ NewLotOnly cannot be enabled");
+      }
+    });
+  });
+
+  // ============================================
+  // TEST 17: RELOCK ANNIVERSARY BONUS
+  // ============================================
+
+  describe("17. Relock Anniversary Bonus", () => {
+    let ctx: IsolatedPool;
+    let staker: Staker;
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      staker = await createStaker(ctx);
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_FLEX);
+    });
+
+    it("should store bonus configuration", async () => {
+      await program.methods
+        .setRelockBonus(100, new BN(SECONDS_PER_DAY))
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+
+      const poolState = await program.account.stakePool.fetch(ctx.pool);
+      expect(poolState.relockBonusBps).to.equal(100);
+      expect(poolState.relockGraceSecs.toNumber()).to.equal(SECONDS_PER_DAY);
+      console.log("✓ Relock bonus configured");
+    });
+
+    it("should reject a bonus above the maximum", async () => {
+      try {
+        await program.methods
+          .setRelockBonus(501, new BN(0))
+          .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+          .signers([admin])
+          .rpc();
+        expect.fail("Should have thrown InvalidRelockBonus");
+      } catch (error: any) {
+        expect(error.message).to.include("InvalidRelockBonus");
+        console.log("✓ Excessive relock bonus rejected");
+      }
+    });
+
+    it("should not complete a cycle when relocking the lock-free Flex tier", async () => {
+      await program.methods
+        .changeTier(TIER_FLEX)
+        .accounts({
+          user: staker.user.publicKey,
+          stakePool: ctx.pool,
+          userStake: staker.userStake,
+        })
+        .signers([staker.user])
+        .rpc();
+
+      const userStake = await program.account.userStake.fetch(staker.userStake);
+      expect(userStake.completedLockCycles).to.equal(0);
+      console.log("✓ Flex relock earns no anniversary bonus");
+    });
+  });
+
+  // ============================================
+  // TEST 18: EARLY-BIRD BONUS
+  // ============================================
+
+  describe("18. Early-Bird Bonus", () => {
+    const BONUS_BPS = 200;
+    const BONUS_SECS = 2;
+    let ctx: IsolatedPool;
+    let first: Staker;
+    let second: Staker;
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      first = await createStaker(ctx);
+      second = await createStaker(ctx);
+      await fundPool(ctx, TREASURY_FUND);
+
+      await program.methods
+        .setEarlyBird(new BN(1), BONUS_BPS, new BN(BONUS_SECS))
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+    });
+
+    it("should consume the only slot on the first stake", async () => {
+      await stakeInto(ctx, first, STAKE_AMOUNT, TIER_FLEX);
+
+      const userStake = await program.account.userStake.fetch(first.userStake);
+      expect(userStake.earlyBirdUntil.toNumber()).to.equal(
+        userStake.stakeStartTime.toNumber() + BONUS_SECS
+      );
+
+      const poolState = await program.account.stakePool.fetch(ctx.pool);
+      expect(poolState.earlyBirdSlotsUsed.toNumber()).to.equal(1);
+      console.log("✓ Early-bird slot assigned");
+    });
+
+    it("should give the N+1th staker nothing", async () => {
+      await stakeInto(ctx, second, STAKE_AMOUNT, TIER_FLEX);
+
+      const userStake = await program.account.userStake.fetch(second.userStake);
+      expect(userStake.earlyBirdUntil.toNumber()).to.equal(0);
+      console.log("✓ No slot left for the second staker");
+    });
+
+    it("should apply the bonus only until it expires mid-interval", async () => {
+      const before = await program.account.userStake.fetch(first.userStake);
+      await advanceTime(6);
+
+      const balanceBefore = await tokenBalance(first.tokenAccount);
+      await claimFrom(ctx, first);
+      const received = (await tokenBalance(first.tokenAccount)).sub(balanceBefore);
+      const after = await program.account.userStake.fetch(first.userStake);
+
+      const start = before.lastClaimTime.toNumber();
+      const end = after.lastClaimTime.toNumber();
+      const bonusSecs = Math.max(0, Math.min(end, before.earlyBirdUntil.toNumber()) - start);
+      const rateSeconds = new BN(FLEX_APY * (end - start) + BONUS_BPS * bonusSecs);
+      const expected = STAKE_AMOUNT.mul(rateSeconds).div(new BN(BASIS_POINTS).mul(new BN(SECONDS_PER_YEAR)));
+
+      expect(end - start).to.be.greaterThan(BONUS_SECS, "Interval should straddle the bonus expiry");
+      expect(received.toString()).to.equal(expected.toString());
+      console.log(`✓ Bonus split at expiry (${bonusSecs}s of ${end - start}s boosted)`);
+    });
+
+    it("should not hand a closed-and-restaked wallet a new slot", async () => {
+      const original = (await program.account.userStake.fetch(first.userStake)).earlyBirdUntil;
+      await program.methods
+        .setEarlyBird(new BN(2), BONUS_BPS, new BN(BONUS_SECS))
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+
+      await unstakeFrom(ctx, first, STAKE_AMOUNT);
+      await claimFrom(ctx, first);
+      await program.methods
+        .closeUserStake()
+        .accounts({ user: first.user.publicKey, stakePool: ctx.pool, userStake: first.userStake })
+        .signers([first.user])
+        .rpc();
+      await stakeInto(ctx, first, STAKE_AMOUNT, TIER_FLEX);
+
+      const userStake = await program.account.userStake.fetch(first.userStake);
+      expect(userStake.earlyBirdUntil.toString()).to.equal(original.toString());
+      const poolState = await program.account.stakePool.fetch(ctx.pool);
+      expect(poolState.earlyBirdSlotsUsed.toNumber()).to.equal(1);
+      console.log("✓ Returning wallet keeps its original early-bird stamp");
+    });
+  });
+
+  // ============================================
+  // TEST 19: CLAIM STREAK
+  // ============================================
+
+  describe("19. Claim Streak", () => {
+    const STEP_BPS = 10;
+    const CAP_BPS = 15;
+    const WINDOW_SECS = 3;
+    let ctx: IsolatedPool;
+    let staker: Staker;
+
+    async function setStreak(windowSecs: number): Promise<void> {
+      await program.methods
+        .setClaimStreak(STEP_BPS, CAP_BPS, new BN(windowSecs))
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+    }
+
+    // Claims once and checks the payout against the base accrual plus bonus
+    async function claimWithBonus(bonusBps: number): Promise<void> {
+      const before = await program.account.userStake.fetch(staker.userStake);
+      const balanceBefore = await tokenBalance(staker.tokenAccount);
+      await claimFrom(ctx, staker);
+      const received = (await tokenBalance(staker.tokenAccount)).sub(balanceBefore);
+      const after = await program.account.userStake.fetch(staker.userStake);
+
+      const elapsed = after.lastClaimTime.toNumber() - before.lastClaimTime.toNumber();
+      const base = STAKE_AMOUNT.mul(new BN(FLEX_APY * elapsed))
+        .div(new BN(BASIS_POINTS).mul(new BN(SECONDS_PER_YEAR)));
+      const expected = base.add(base.mul(new BN(bonusBps)).div(new BN(BASIS_POINTS)));
+      expect(received.toString()).to.equal(expected.toString());
+    }
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      staker = await createStaker(ctx);
+      await fundPool(ctx, TREASURY_FUND);
+      await setStreak(WINDOW_SECS);
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_FLEX);
+    });
+
+    it("should reject a step above the cap", async () => {
+      try {
+        await program.methods
+          .setClaimStreak(CAP_BPS + 1, CAP_BPS, new BN(SECONDS_PER_DAY))
+          .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+          .signers([admin])
+          .rpc();
+        expect.fail("Should have thrown InvalidStreakConfig");
+      } catch (error: any) {
+        expect(error.message).to.include("InvalidStreakConfig");
+        console.log("✓ Step above cap rejected");
+      }
+    });
+
+    it("should build a streak with on-time claims", async () => {
+      await advanceTime(2);
+      await claimWithBonus(0);
+      let userStake = await program.account.userStake.fetch(staker.userStake);
+      expect(userStake.streakCount).to.equal(0);
+      // The deadline is the end of the window after the first claim's
+      expect(userStake.streakDeadline.toNumber()).to.equal(
+        userStake.lastClaimTime.toNumber() + 2 * WINDOW_SECS
+      );
+
+      await new Promise((resolve) => setTimeout(resolve, WINDOW_SECS * 1000 + 500));
+      await claimWithBonus(STEP_BPS);
+      userStake = await program.account.userStake.fetch(staker.userStake);
+      expect(userStake.streakCount).to.equal(1);
+      console.log("✓ Streak grows on on-time claims");
+    });
+
+    it("should count one claim per window", async () => {
+      const before = await program.account.userStake.fetch(staker.userStake);
+      await claimWithBonus(STEP_BPS);
+
+      const userStake = await program.account.userStake.fetch(staker.userStake);
+      expect(userStake.streakCount).to.equal(1);
+      expect(userStake.streakDeadline.toString()).to.equal(before.streakDeadline.toString());
+      console.log("✓ A second claim in the same window does not grow the streak");
+    });
+
+    it("should cap the streak bonus", async () => {
+      await new Promise((resolve) => setTimeout(resolve, WINDOW_SECS * 1000));
+      await claimWithBonus(CAP_BPS);
+
+      const userStake = await program.account.userStake.fetch(staker.userStake);
+      expect(userStake.streakCount).to.equal(2);
+      console.log(`✓ Bonus capped at ${CAP_BPS}bp`);
+    });
+
+    it("should reset the streak after a missed window", async () => {
+      // Sit out the whole next window
+      await new Promise((resolve) => setTimeout(resolve, 2 * WINDOW_SECS * 1000 + 500));
+      await claimWithBonus(0);
+
+      const userStake = await program.account.userStake.fetch(staker.userStake);
+      expect(userStake.streakCount).to.equal(0);
+      console.log("✓ Missed window resets the streak");
+    });
+  });
+
+  // ============================================
+  // TEST 20: PRINCIPAL RELEASE SCHEDULE
+  // ============================================
+
+  describe("20. Principal Release Schedule", () => {
+    // Roughly one second's worth of Core unlock for STAKE_AMOUNT
+    const SMALL_TRANCHE = new BN(10_000);
+    let ctx: IsolatedPool;
+    let staker: Staker;
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      staker = await createStaker(ctx);
+    });
+
+    it("should reject a schedule for Flex", async () => {
+      try {
+        await program.methods
+          .setReleaseSchedule(TIER_FLEX, new BN(0), new BN(0))
+          .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+          .signers([admin])
+          .rpc();
+        expect.fail("Should have thrown InvalidTier");
+      } catch (error: any) {
+        expect(error.message).to.include("InvalidTier");
+        console.log("✓ Flex has no lock to configure");
+      }
+    });
+
+    it("should reject a schedule that does not add up to the lock", async () => {
+      try {
+        await program.methods
+          .setReleaseSchedule(TIER_CORE, new BN(30 * SECONDS_PER_DAY), new BN(30 * SECONDS_PER_DAY))
+          .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+          .signers([admin])
+          .rpc();
+        expect.fail("Should have thrown InvalidReleaseSchedule");
+      } catch (error: any) {
+        expect(error.message).to.include("InvalidReleaseSchedule");
+        console.log("✓ Mismatched schedule rejected");
+      }
+    });
+
+    it("should default to a hard cliff", async () => {
+      const poolState = await program.account.stakePool.fetch(ctx.pool);
+      expect(poolState.coreCliffSecs.toNumber()).to.equal(90 * SECONDS_PER_DAY);
+      expect(poolState.coreLinearSecs.toNumber()).to.equal(0);
+      console.log("✓ Default schedule is a hard cliff");
+    });
+
+    it("should release a pro-rata tranche during the lock", async () => {
+      await program.methods
+        .setReleaseSchedule(TIER_CORE, new BN(0), new BN(90 * SECONDS_PER_DAY))
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_CORE);
+      await advanceTime(4);
+      await unstakeFrom(ctx, staker, SMALL_TRANCHE);
+
+      const userStake = await program.account.userStake.fetch(staker.userStake);
+      expect(userStake.stakedAmount.toString()).to.equal(STAKE_AMOUNT.sub(SMALL_TRANCHE).toString());
+      expect(userStake.unlockedWithdrawn.toString()).to.equal(SMALL_TRANCHE.toString());
+      console.log("✓ Linear tranche withdrawn early");
+    });
+
+    it("should keep the rest locked", async () => {
+      try {
+        await unstakeFrom(ctx, staker, STAKE_AMOUNT.sub(SMALL_TRANCHE));
+        expect.fail("Should have thrown LockPeriodNotEnded");
+      } catch (error: any) {
+        expect(error.message).to.include("LockPeriodNotEnded");
+        console.log("✓ Unvested principal stays locked");
+      }
+    });
+  });
+
+  // ============================================
+  // TEST 21: REWARD CLIFF
+  // ============================================
+
+  describe("21. Reward Cliff", () => {
+    let ctx: IsolatedPool;
+    let staker: Staker;
+
+    async function setFlexCliff(cliffSecs: number): Promise<void> {
+      await program.methods
+        .setRewardCliff(TIER_FLEX, new BN(cliffSecs))
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+    }
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      staker = await createStaker(ctx);
+      await fundPool(ctx, TREASURY_FUND);
+      await setFlexCliff(30 * SECONDS_PER_DAY);
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_FLEX);
+    });
+
+    it("should reject claims before the cliff", async () => {
+      await advanceTime(2);
+      try {
+        await claimFrom(ctx, staker);
+        expect.fail("Should have thrown RewardCliffNotReached");
+      } catch (error: any) {
+        expect(error.message).to.include("RewardCliffNotReached");
+        console.log("✓ Claim blocked before the cliff");
+      }
+    });
+
+    it("should keep accruing behind the cliff", async () => {
+      const userStake = await program.account.userStake.fetch(staker.userStake);
+      expect(userStake.pendingRewards.toNumber()).to.equal(0);
+
+      await unstakeFrom(ctx, staker, STAKE_AMOUNT);
+      const exited = await program.account.userStake.fetch(staker.userStake);
+      expect(exited.pendingRewards.toNumber()).to.be.greaterThan(0);
+      console.log("✓ Rewards accrued while the cliff was pending");
+    });
+
+    it("should release everything after a full exit", async () => {
+      const before = await program.account.userStake.fetch(staker.userStake);
+      const balanceBefore = await tokenBalance(staker.tokenAccount);
+      await claimFrom(ctx, staker);
+      const received = (await tokenBalance(staker.tokenAccount)).sub(balanceBefore);
+
+      expect(received.toString()).to.equal(before.pendingRewards.toString());
+      console.log("✓ Exited position claims despite the cliff");
+    });
+  });
+
+  // ============================================
+  // TEST 22: PERMANENT TIER AND SUNSET
+  // ============================================
+
+  describe("22. Permanent Tier and Sunset", () => {
+    const TIER_PERMANENT = 3;
+    let ctx: IsolatedPool;
+    let staker: Staker;
+
+    async function declareSunset(sunsetAt: number): Promise<string> {
+      return program.methods
+        .declareSunset(new BN(sunsetAt))
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+    }
+
+    async function chainTime(): Promise<number> {
+      const slot = await connection.getSlot();
+      return (await connection.getBlockTime(slot))!;
+    }
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      staker = await createStaker(ctx);
+    });
+
+    it("should stay closed until an APY is set", async () => {
+      try {
+        await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_PERMANENT);
+        expect.fail("Should have thrown PermanentTierDisabled");
+      } catch (error: any) {
+        expect(error.message).to.include("PermanentTierDisabled");
+      }
+
+      await program.methods
+        .setPermanentApy(PRIME_APY + 400)
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_PERMANENT);
+
+      const userStake = await program.account.userStake.fetch(staker.userStake);
+      expect(tierIndex(userStake.tier)).to.equal(TIER_PERMANENT);
+      console.log("✓ Permanent tier opened");
+    });
+
+    it("should block exits before a sunset", async () => {
+      try {
+        await unstakeFrom(ctx, staker, STAKE_AMOUNT);
+        expect.fail("Should have thrown SunsetNotReached");
+      } catch (error: any) {
+        expect(error.message).to.include("SunsetNotReached");
+        console.log("✓ Exit blocked without a sunset");
+      }
+    });
+
+    it("should require 30 days of notice", async () => {
+      try {
+        await declareSunset((await chainTime()) + SECONDS_PER_DAY);
+        expect.fail("Should have thrown SunsetTooSoon");
+      } catch (error: any) {
+        expect(error.message).to.include("SunsetTooSoon");
+        console.log("✓ Short-notice sunset rejected");
+      }
+    });
+
+    it("should declare the sunset once and never rescind it", async () => {
+      const sunsetAt = (await chainTime()) + 31 * SECONDS_PER_DAY;
+      await declareSunset(sunsetAt);
+
+      try {
+        await declareSunset(sunsetAt + SECONDS_PER_DAY);
+        expect.fail("Should have thrown SunsetAlreadyDeclared");
+      } catch (error: any) {
+        expect(error.message).to.include("SunsetAlreadyDeclared");
+      }
+
+      const poolState = await program.account.stakePool.fetch(ctx.pool);
+      expect(poolState.sunsetAt.toNumber()).to.equal(sunsetAt);
+      console.log("✓ Sunset is final");
+    });
+
+    it("should keep exits blocked until the sunset arrives", async () => {
+      try {
+        await unstakeFrom(ctx, staker, STAKE_AMOUNT);
+        expect.fail("Should have thrown SunsetNotReached");
+      } catch (error: any) {
+        expect(error.message).to.include("SunsetNotReached");
+        console.log("✓ Exit blocked before the sunset");
+      }
+    });
+  });
+
+  // ============================================
+  // TEST 23: BOOST LOCK
+  // ============================================
+
+  describe("23. Boost Lock", () => {
+    const BOOST_BPS = 1000;
+    const BOOST_AMOUNT = new BN(50);
+    const BOOST_SECS = 2;
+    let ctx: IsolatedPool;
+    let staker: Staker;
+
+    async function boostLock(amount: BN, durationSecs: number): Promise<string> {
+      return program.methods
+        .boostLock(amount, new BN(durationSecs))
+        .accounts({
+          user: staker.user.publicKey,
+          stakePool: ctx.pool,
+          userStake: staker.userStake,
+        })
+        .signers([staker.user])
+        .rpc();
+    }
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      staker = await createStaker(ctx);
+      await fundPool(ctx, TREASURY_FUND);
+      await program.methods
+        .setBoost(BOOST_BPS, new BN(60))
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_FLEX);
+    });
+
+    it("should move pending rewards into the boosted bucket", async () => {
+      await advanceTime(2);
+      await boostLock(BOOST_AMOUNT, BOOST_SECS);
+
+      const userStake = await program.account.userStake.fetch(staker.userStake);
+      expect(userStake.boostedAmount.toString()).to.equal(BOOST_AMOUNT.toString());
+      expect(userStake.boostUntil.toNumber()).to.equal(userStake.lastClaimTime.toNumber() + BOOST_SECS);
+      console.log(`✓ Boost-locked ${BOOST_AMOUNT} (pending left: ${userStake.pendingRewards})`);
+    });
+
+    it("should reject a second boost while one is active", async () => {
+      try {
+        await boostLock(new BN(1), BOOST_SECS);
+        expect.fail("Should have thrown BoostAlreadyActive");
+      } catch (error: any) {
+        expect(error.message).to.include("BoostAlreadyActive");
+        console.log("✓ Overlapping boost rejected");
+      }
+    });
+
+    it("should accrue the boosted rate and release at expiry", async () => {
+      await new Promise((resolve) => setTimeout(resolve, (BOOST_SECS + 1) * 1000));
+
+      const before = await program.account.userStake.fetch(staker.userStake);
+      const balanceBefore = await tokenBalance(staker.tokenAccount);
+      await claimFrom(ctx, staker);
+      const received = (await tokenBalance(staker.tokenAccount)).sub(balanceBefore);
+      const after = await program.account.userStake.fetch(staker.userStake);
+
+      const start = before.lastClaimTime.toNumber();
+      const end = after.lastClaimTime.toNumber();
+      const boostSecs = Math.max(0, Math.min(end, before.boostUntil.toNumber()) - start);
+      const numerator = STAKE_AMOUNT.mul(new BN(FLEX_APY * (end - start)))
+        .add(BOOST_AMOUNT.mul(new BN(BOOST_BPS * boostSecs)));
+      const accrued = numerator.div(new BN(BASIS_POINTS).mul(new BN(SECONDS_PER_YEAR)));
+      const expected = before.pendingRewards.add(accrued).add(BOOST_AMOUNT);
+
+      expect(received.toString()).to.equal(expected.toString());
+      expect(after.boostedAmount.toNumber()).to.equal(0);
+      console.log("✓ Boosted rewards released with the claim");
+    });
+  });
+
+  // ============================================
+  // TEST 24: CLAIM HISTORY
+  // ============================================
+
+  describe("24. Claim History", () => {
+    const CLAIM_HISTORY_SEED = Buffer.from("claim_history");
+    let ctx: IsolatedPool;
+    let staker: Staker;
+    let claimHistory: PublicKey;
+
+    async function claimWithHistory(): Promise<string> {
+      return program.methods
+        .claimRewards(null)
+        .accounts({
+          user: staker.user.publicKey,
+          stakePool: ctx.pool,
+          userStake: staker.userStake,
+          stakingMint: ctx.mint,
+          userTokenAccount: staker.tokenAccount,
+          owner: staker.user.publicKey,
+          rewardMint: ctx.rewardMint,
+          treasuryVault: ctx.treasury,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+          claimHistory,
+        })
+        .signers([staker.user])
+        .rpc();
+    }
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      staker = await createStaker(ctx);
+      await fundPool(ctx, TREASURY_FUND);
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_FLEX);
+
+      [claimHistory] = PublicKey.findProgramAddressSync(
+        [CLAIM_HISTORY_SEED, ctx.pool.toBuffer(), staker.user.publicKey.toBuffer()],
+        program.programId
+      );
+      await program.methods
+        .enableClaimHistory()
+        .accounts({
+          user: staker.user.publicKey,
+          stakePool: ctx.pool,
+          userStake: staker.userStake,
+          claimHistory,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([staker.user])
+        .rpc();
+    });
+
+    it("should append records in order", async () => {
+      for (let i = 0; i < 3; i++) {
+        await advanceTime(2);
+        await claimWithHistory();
+      }
+
+      const page = await program.methods
+        .viewClaimHistory(0)
+        .accounts({ stakePool: ctx.pool, claimHistory })
+        .view();
+      const userStake = await program.account.userStake.fetch(staker.userStake);
+
+      expect(page.totalRecorded.toNumber()).to.equal(3);
+      expect(page.entries.length).to.equal(3);
+      for (let i = 1; i < 3; i++) {
+        expect(page.entries[i].timestamp.toNumber()).to.be.at.least(page.entries[i - 1].timestamp.toNumber());
+        expect(page.entries[i].runningTotal.toString()).to.equal(
+          page.entries[i - 1].runningTotal.add(page.entries[i].amount).toString()
+        );
+      }
+      expect(page.entries[2].runningTotal.toString()).to.equal(userStake.totalRewardsClaimed.toString());
+      console.log("✓ Claims recorded oldest first with running totals");
+    });
+
+    it("should claim as before without the history account", async () => {
+      await advanceTime(2);
+      await claimFrom(ctx, staker);
+
+      const history = await program.account.claimHistory.fetch(claimHistory);
+      expect(history.totalRecorded.toNumber()).to.equal(3);
+      console.log("✓ Claim without history leaves it untouched");
+    });
+  });
+
+  // ============================================
+  // TEST 25: POSITION HISTORY
+  // ============================================
+
+  describe("25. Position History", () => {
+    const POSITION_HISTORY_SEED = Buffer.from("position_history");
+    let ctx: IsolatedPool;
+    let staker: Staker;
+    let attacker: Staker;
+    let positionHistory: PublicKey;
+
+    function stakeWithHistory(who: Staker, amount: BN, history: PublicKey): Promise<string> {
+      return program.methods
+        .stake(amount, TIER_FLEX, TOPUP_KEEP_ORIGINAL as any, null, null, null)
+        .accounts({
+          user: who.user.publicKey,
+          stakePool: ctx.pool,
+          userStake: who.userStake,
+          stakerRecord: stakerRecordPda(ctx.pool, who.user.publicKey),
+          stakingMint: ctx.mint,
+          userTokenAccount: who.tokenAccount,
+          stakingVault: ctx.vault,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+          rent: SYSVAR_RENT_PUBKEY,
+          positionHistory: history,
+        })
+        .signers([who.user])
+        .rpc();
+    }
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      staker = await createStaker(ctx);
+      attacker = await createStaker(ctx);
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_FLEX);
+      await stakeInto(ctx, attacker, STAKE_AMOUNT, TIER_FLEX);
+
+      [positionHistory] = PublicKey.findProgramAddressSync(
+        [POSITION_HISTORY_SEED, ctx.pool.toBuffer(), staker.user.publicKey.toBuffer()],
+        program.programId
+      );
+      await program.methods
+        .enablePositionHistory()
+        .accounts({
+          user: staker.user.publicKey,
+          stakePool: ctx.pool,
+          userStake: staker.userStake,
+          positionHistory,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([staker.user])
+        .rpc();
+    });
+
+    it("should record stake, tier change and unstake in order", async () => {
+      await stakeWithHistory(staker, ONE_TOKEN, positionHistory);
+
+      await program.methods
+        .changeTier(TIER_FLEX)
+        .accounts({
+          user: staker.user.publicKey,
+          stakePool: ctx.pool,
+          userStake: staker.userStake,
+          positionHistory,
+        })
+        .signers([staker.user])
+        .rpc();
+
+      await program.methods
+        .unstake(ONE_TOKEN)
+        .accounts({
+          user: staker.user.publicKey,
+          stakePool: ctx.pool,
+          userStake: staker.userStake,
+          stakingMint: ctx.mint,
+          userTokenAccount: staker.tokenAccount,
+          stakingVault: ctx.vault,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+          positionHistory,
+        })
+        .signers([staker.user])
+        .rpc();
+
+      const page = await program.methods
+        .viewPositionHistory(0)
+        .accounts({ stakePool: ctx.pool, positionHistory })
+        .view();
+
+      expect(page.entries.map((e: any) => Object.keys(e.kind)[0])).to.deep.equal([
+        "stake",
+        "changeTier",
+        "unstake",
+      ]);
+      expect(page.entries[0].stakedAfter.toString()).to.equal(STAKE_AMOUNT.add(ONE_TOKEN).toString());
+      expect(page.entries[2].stakedAfter.toString()).to.equal(STAKE_AMOUNT.toString());
+      console.log("✓ Principal movements recorded oldest first");
+    });
+
+    it("should reject appends to someone else's history", async () => {
+      try {
+        await stakeWithHistory(attacker, ONE_TOKEN, positionHistory);
+        expect.fail("Should have thrown ConstraintSeeds");
+      } catch (error: any) {
+        expect(error.message).to.include("ConstraintSeeds");
+      }
+
+      const history = await program.account.positionHistory.fetch(positionHistory);
+      expect(history.totalRecorded.toNumber()).to.equal(3);
+      console.log("✓ Third-party append rejected");
+    });
+  });
+
+  // ============================================
+  // TEST 26: REFERRAL SHARE
+  // ============================================
+
+  describe("26. Referral Share", () => {
+    const REFERRAL_SEED = Buffer.from("referral");
+    const SHARE_BPS = 500;
+    const HORIZON_SECS = 2;
+    let ctx: IsolatedPool;
+    let staker: Staker;
+    let referrer: Keypair;
+    let referrerToken: PublicKey;
+    let referralAccount: PublicKey;
+
+    function claimWithReferral(pool: IsolatedPool, claimer: Staker, account: PublicKey): Promise<string> {
+      return program.methods
+        .claimRewards(null)
+        .accounts({
+          user: claimer.user.publicKey,
+          stakePool: pool.pool,
+          userStake: claimer.userStake,
+          stakingMint: pool.mint,
+          userTokenAccount: claimer.tokenAccount,
+          owner: claimer.user.publicKey,
+          rewardMint: pool.rewardMint,
+          treasuryVault: pool.treasury,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+          referralAccount: account,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([claimer.user])
+        .rpc();
+    }
+
+    function claimReferralRewards(): Promise<string> {
+      return program.methods
+        .claimReferralRewards()
+        .accounts({
+          referrer: referrer.publicKey,
+          stakePool: ctx.pool,
+          referralAccount,
+          stakingMint: ctx.mint,
+          referrerTokenAccount: referrerToken,
+          treasuryVault: ctx.treasury,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([referrer])
+        .rpc();
+    }
+
+    async function setupPool(pool: IsolatedPool): Promise<void> {
+      await fundPool(pool, TREASURY_FUND);
+      await program.methods
+        .setReferralShare(SHARE_BPS)
+        .accounts({ authority: admin.publicKey, stakePool: pool.pool })
+        .signers([admin])
+        .rpc();
+      await program.methods
+        .setMaxAccrualHorizon(new BN(HORIZON_SECS))
+        .accounts({ authority: admin.publicKey, stakePool: pool.pool })
+        .signers([admin])
+        .rpc();
+    }
+
+    function referralPda(pool: IsolatedPool): PublicKey {
+      return PublicKey.findProgramAddressSync(
+        [REFERRAL_SEED, pool.pool.toBuffer(), referrer.publicKey.toBuffer()],
+        program.programId
+      )[0];
+    }
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      staker = await createStaker(ctx);
+      referrer = Keypair.generate();
+      await airdropSol(referrer.publicKey);
+      referrerToken = await createAccount(connection, referrer, ctx.mint, referrer.publicKey);
+      referralAccount = referralPda(ctx);
+      await setupPool(ctx);
+    });
+
+    it("should reject self-referral", async () => {
+      try {
+        await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_FLEX, TOPUP_KEEP_ORIGINAL, null, staker.user.publicKey);
+        expect.fail("Should have thrown InvalidReferrer");
+      } catch (error: any) {
+        expect(error.message).to.include("InvalidReferrer");
+        console.log("✓ Self-referral rejected");
+      }
+    });
+
+    it("should record the referrer on the first stake only", async () => {
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_FLEX, TOPUP_KEEP_ORIGINAL, null, referrer.publicKey);
+      const userStake = await program.account.userStake.fetch(staker.userStake);
+      expect(userStake.referrer.toBase58()).to.equal(referrer.publicKey.toBase58());
+
+      const other = await createStaker(ctx);
+      await stakeInto(ctx, other, STAKE_AMOUNT, TIER_FLEX);
+      try {
+        await stakeInto(ctx, other, ONE_TOKEN, TIER_FLEX, TOPUP_KEEP_ORIGINAL, null, referrer.publicKey);
+        expect.fail("Should have thrown ReferrerOnlyOnFirstStake");
+      } catch (error: any) {
+        expect(error.message).to.include("ReferrerOnlyOnFirstStake");
+        console.log("✓ Referrer recorded on first stake; later referral rejected");
+      }
+    });
+
+    it("should require the referral account once a referrer is set", async () => {
+      await advanceTime(8);
+      try {
+        await claimFrom(ctx, staker);
+        expect.fail("Should have thrown MissingReferralAccounts");
+      } catch (error: any) {
+        expect(error.message).to.include("MissingReferralAccounts");
+        console.log("✓ Referral share cannot be skipped");
+      }
+    });
+
+    it("should credit the referrer on top of the claim within the emission cap", async () => {
+      const poolBefore = await program.account.stakePool.fetch(ctx.pool);
+      const balanceBefore = await tokenBalance(staker.tokenAccount);
+      await claimWithReferral(ctx, staker, referralAccount);
+
+      const received = (await tokenBalance(staker.tokenAccount)).sub(balanceBefore);
+      const expectedPayout = calculateExpectedRewards(STAKE_AMOUNT, FLEX_APY, HORIZON_SECS);
+      const expectedShare = expectedPayout.mul(new BN(SHARE_BPS)).div(new BN(BASIS_POINTS));
+      expect(received.toString()).to.equal(expectedPayout.toString());
+
+      const referral = await program.account.referralAccount.fetch(referralAccount);
+      expect(referral.referrer.toBase58()).to.equal(referrer.publicKey.toBase58());
+      expect(referral.unclaimed.toString()).to.equal(expectedShare.toString());
+      expect(referral.lifetimeEarnings.toString()).to.equal(expectedShare.toString());
+      expect((await tokenBalance(referrerToken)).toNumber()).to.equal(0);
+
+      const poolAfter = await program.account.stakePool.fetch(ctx.pool);
+      expect(poolAfter.totalDistributed.sub(poolBefore.totalDistributed).toString()).to.equal(
+        received.add(expectedShare).toString()
+      );
+      expect(poolAfter.referralLiability.toString()).to.equal(expectedShare.toString());
+      console.log(`✓ Referrer credited ${expectedShare} on a ${received} claim`);
+    });
+
+    it("should let the referrer withdraw the credited share", async () => {
+      const referralBefore = await program.account.referralAccount.fetch(referralAccount);
+      const poolBefore = await program.account.stakePool.fetch(ctx.pool);
+      await claimReferralRewards();
+
+      const paid = await tokenBalance(referrerToken);
+      expect(paid.toString()).to.equal(referralBefore.unclaimed.toString());
+
+      const referral = await program.account.referralAccount.fetch(referralAccount);
+      const poolAfter = await program.account.stakePool.fetch(ctx.pool);
+      expect(referral.unclaimed.toNumber()).to.equal(0);
+      expect(referral.lifetimeEarnings.toString()).to.equal(referralBefore.lifetimeEarnings.toString());
+      expect(poolAfter.referralLiability.toNumber()).to.equal(0);
+      expect(poolAfter.totalDistributed.toString()).to.equal(poolBefore.totalDistributed.toString());
+
+      try {
+        await claimReferralRewards();
+        expect.fail("Should have thrown NoRewardsAvailable");
+      } catch (error: any) {
+        expect(error.message).to.include("NoRewardsAvailable");
+      }
+      console.log(`✓ Referrer withdrew ${paid}; nothing left to claim`);
+    });
+
+    it("should trim the referral share to what the emission cap has left", async () => {
+      const expectedPayout = calculateExpectedRewards(STAKE_AMOUNT, FLEX_APY, HORIZON_SECS);
+      const capped = await createIsolatedPool(expectedPayout.addn(1));
+      const cappedStaker = await createStaker(capped);
+      const cappedReferral = referralPda(capped);
+      await setupPool(capped);
+      await stakeInto(capped, cappedStaker, STAKE_AMOUNT, TIER_FLEX, TOPUP_KEEP_ORIGINAL, null, referrer.publicKey);
+      await advanceTime(8);
+
+      await claimWithReferral(capped, cappedStaker, cappedReferral);
+
+      const referral = await program.account.referralAccount.fetch(cappedReferral);
+      const pool = await program.account.stakePool.fetch(capped.pool);
+      expect(referral.unclaimed.toNumber()).to.equal(1);
+      expect(pool.totalDistributed.toString()).to.equal(pool.emissionCap.toString());
+      console.log("✓ Share limited to the last unit under the cap");
+    });
+  });
+
+  // ============================================
+  // TEST 27: CLAIM FEE
+  // ============================================
+
+  describe("27. Claim Fee", () => {
+    const FEE_BPS = 300;
+    const TIMELOCK_SECS = 2;
+    let ctx: IsolatedPool;
+    let staker: Staker;
+    let feeVault: PublicKey;
+
+    function proposeFee(feeBps: number): Promise<string> {
+      return program.methods
+        .proposeClaimFee(feeBps)
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool, feeVault })
+        .signers([admin])
+        .rpc();
+    }
+
+    function applyFee(): Promise<string> {
+      return program.methods.applyClaimFee().accounts({ stakePool: ctx.pool }).rpc();
+    }
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      staker = await createStaker(ctx);
+      feeVault = await createAccount(connection, admin, ctx.mint, Keypair.generate().publicKey);
+      await fundPool(ctx, TREASURY_FUND);
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_FLEX);
+
+      await program.methods
+        .setParamTimelock(new BN(TIMELOCK_SECS))
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+    });
+
+    it("should not allow the timelock to be shortened", async () => {
+      try {
+        await program.methods
+          .setParamTimelock(new BN(0))
+          .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+          .signers([admin])
+          .rpc();
+        expect.fail("Should have thrown InvalidParamTimelock");
+      } catch (error: any) {
+        expect(error.message).to.include("InvalidParamTimelock");
+        console.log("✓ Timelock cannot be shortened");
+      }
+    });
+
+    it("should hold a proposed fee until the timelock elapses", async () => {
+      await proposeFee(FEE_BPS);
+
+      try {
+        await applyFee();
+        expect.fail("Should have thrown ParamTimelockActive");
+      } catch (error: any) {
+        expect(error.message).to.include("ParamTimelockActive");
+      }
+
+      const poolState = await program.account.stakePool.fetch(ctx.pool);
+      expect(poolState.claimFeeBps).to.equal(0);
+      expect(poolState.pendingClaimFeeBps).to.equal(FEE_BPS);
+      console.log("✓ Pending fee visible but not applied");
+    });
+
+    it("should let the admin cancel a pending fee", async () => {
+      await program.methods
+        .cancelClaimFee()
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+
+      const poolState = await program.account.stakePool.fetch(ctx.pool);
+      expect(poolState.pendingClaimFeeEffectiveAt.toNumber()).to.equal(0);
+      console.log("✓ Pending fee cancelled");
+    });
+
+    it("should apply the fee after the timelock", async () => {
+      await proposeFee(FEE_BPS);
+      await new Promise((resolve) => setTimeout(resolve, (TIMELOCK_SECS + 1) * 1000));
+      await applyFee();
+
+      const poolState = await program.account.stakePool.fetch(ctx.pool);
+      expect(poolState.claimFeeBps).to.equal(FEE_BPS);
+      expect(poolState.feeVault.toBase58()).to.equal(feeVault.toBase58());
+      console.log("✓ Fee applied after timelock");
+    });
+
+    it("should require the fee vault on claims", async () => {
+      try {
+        await claimFrom(ctx, staker);
+        expect.fail("Should have thrown MissingFeeVault");
+      } catch (error: any) {
+        expect(error.message).to.include("MissingFeeVault");
+        console.log("✓ Claim without fee vault rejected");
+      }
+    });
+
+    it("should pay the net to the user and the fee to the vault", async () => {
+      const poolBefore = await program.account.stakePool.fetch(ctx.pool);
+      const userBefore = await tokenBalance(staker.tokenAccount);
+      const vaultBefore = await tokenBalance(feeVault);
+
+      await program.methods
+        .claimRewards(null)
+        .accounts({
+          user: staker.user.publicKey,
+          stakePool: ctx.pool,
+          userStake: staker.userStake,
+          stakingMint: ctx.mint,
+          userTokenAccount: staker.tokenAccount,
+          owner: staker.user.publicKey,
+          rewardMint: ctx.rewardMint,
+          treasuryVault: ctx.treasury,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+          feeVault,
+        })
+        .signers([staker.user])
+        .rpc();
+
+      const net = (await tokenBalance(staker.tokenAccount)).sub(userBefore);
+      const fee = (await tokenBalance(feeVault)).sub(vaultBefore);
+      const poolAfter = await program.account.stakePool.fetch(ctx.pool);
+      const gross = poolAfter.totalDistributed.sub(poolBefore.totalDistributed);
+
+      expect(net.add(fee).toString()).to.equal(gross.toString());
+      expect(fee.toString()).to.equal(gross.mul(new BN(FEE_BPS)).div(new BN(BASIS_POINTS)).toString());
+      console.log(`✓ Gross ${gross} split into net ${net} and fee ${fee}`);
+    });
+  });
+
+  // ============================================
+  // TEST 28: CLAIM RATE LIMIT
+  // ============================================
+
+  describe("28. Claim Rate Limit", () => {
+    const EPOCH_SECS = 4;
+    let ctx: IsolatedPool;
+    let stakers: Staker[];
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      await fundPool(ctx, TREASURY_FUND);
+      stakers = [];
+      for (let i = 0; i < 3; i++) {
+        const staker = await createStaker(ctx);
+        await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_FLEX);
+        stakers.push(staker);
+      }
+      await new Promise((resolve) => setTimeout(resolve, 1000));
+
+      await program.methods
+        .setClaimRateLimit(2, new BN(EPOCH_SECS))
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+    });
+
+    it("should reject claims once the epoch is full", async () => {
+      await claimFrom(ctx, stakers[0]);
+      await claimFrom(ctx, stakers[1]);
+
+      try {
+        await claimFrom(ctx, stakers[2]);
+        expect.fail("Should have thrown ClaimRateLimited");
+      } catch (error: any) {
+        expect(error.message).to.include("ClaimRateLimited");
+      }
+
+      const poolState = await program.account.stakePool.fetch(ctx.pool);
+      expect(poolState.claimsThisEpoch).to.equal(2);
+      console.log("✓ Third claim in the epoch rejected");
+    });
+
+    it("should still allow unstaking while the epoch is full", async () => {
+      const before = await tokenBalance(stakers[2].tokenAccount);
+      await unstakeFrom(ctx, stakers[2], STAKE_AMOUNT);
+      const after = await tokenBalance(stakers[2].tokenAccount);
+
+      expect(after.sub(before).toString()).to.equal(STAKE_AMOUNT.toString());
+      console.log("✓ Unstake exempt from the claim limit");
+    });
+
+    it("should accept claims again after the epoch rolls over", async () => {
+      await new Promise((resolve) => setTimeout(resolve, (EPOCH_SECS + 1) * 1000));
+      await claimFrom(ctx, stakers[2]);
+
+      const poolState = await program.account.stakePool.fetch(ctx.pool);
+      expect(poolState.claimsThisEpoch).to.equal(1);
+      console.log("✓ Claim accepted in the next epoch");
+    });
+  });
+
+  // ============================================
+  // TEST 29: UNSTAKE DESTINATION
+  // ============================================
+
+  describe("29. Unstake Destination", () => {
+    let ctx: IsolatedPool;
+    let staker: Staker;
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      staker = await createStaker(ctx);
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_FLEX);
+    });
+
+    it("should recreate a closed ATA at the user's expense", async () => {
+      // Move the remaining balance out and close the ATA
+      const parking = await createAccount(
+        connection, staker.user, ctx.mint, staker.user.publicKey, Keypair.generate()
+      );
+      const balance = await tokenBalance(staker.tokenAccount);
+      await transfer(
+        connection, staker.user, staker.tokenAccount, parking, staker.user,
+        BigInt(balance.toString())
+      );
+      await closeAccount(
+        connection, staker.user, staker.tokenAccount, staker.user.publicKey, staker.user
+      );
+      expect(await connection.getAccountInfo(staker.tokenAccount)).to.equal(null);
+
+      const lamportsBefore = await connection.getBalance(staker.user.publicKey);
+      await unstakeFrom(ctx, staker, ONE_TOKEN);
+      const lamportsAfter = await connection.getBalance(staker.user.publicKey);
+
+      expect((await tokenBalance(staker.tokenAccount)).toString()).to.equal(ONE_TOKEN.toString());
+      const rent = await connection.getMinimumBalanceForRentExemption(165);
+      expect(lamportsBefore - lamportsAfter).to.be.at.least(rent);
+      console.log("✓ ATA recreated during unstake, rent paid by user");
+    });
+
+    it("should unstake into an explicit non-ATA token account", async () => {
+      const destination = await createAccount(
+        connection, staker.user, ctx.mint, staker.user.publicKey, Keypair.generate()
+      );
+
+      await program.methods
+        .unstakeTo(ONE_TOKEN)
+        .accounts({
+          user: staker.user.publicKey,
+          stakePool: ctx.pool,
+          userStake: staker.userStake,
+          stakingMint: ctx.mint,
+          destination,
+          stakingVault: ctx.vault,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+        })
+        .signers([staker.user])
+        .rpc();
+
+      expect((await tokenBalance(destination)).toString()).to.equal(ONE_TOKEN.toString());
+      console.log("✓ Explicit destination receives unstaked tokens");
+    });
+  });
+
+  // ============================================
+  // TEST 30: EXPECTED APY GUARD
+  // ============================================
+
+  describe("30. Expected APY Guard", () => {
+    let ctx: IsolatedPool;
+    let staker: Staker;
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      staker = await createStaker(ctx);
+    });
+
+    it("should accept a stake at the quoted APY", async () => {
+      await stakeInto(ctx, staker, ONE_TOKEN, TIER_PRIME, TOPUP_KEEP_ORIGINAL, PRIME_APY);
+      console.log("✓ Exact APY match accepted");
+    });
+
+    it("should reject a stake when the live APY is lower than quoted", async () => {
+      try {
+        await stakeInto(ctx, staker, ONE_TOKEN, TIER_PRIME, TOPUP_KEEP_ORIGINAL, PRIME_APY + 1);
+        expect.fail("Should have thrown ApyChangedSinceQuote");
+      } catch (error: any) {
+        expect(error.message).to.include("ApyChangedSinceQuote");
+        console.log("✓ Lower live APY rejected");
+      }
+    });
+
+    it("should accept a stake when the live APY is higher than quoted", async () => {
+      await stakeInto(ctx, staker, ONE_TOKEN, TIER_PRIME, TOPUP_KEEP_ORIGINAL, PRIME_APY - 1);
+      console.log("✓ Higher live APY accepted");
+    });
+  });
+
+  // ============================================
+  // TEST 31: MINIMUM EXPECTED PAYOUT
+  // ============================================
+
+  describe("31. Minimum Expected Payout", () => {
+    const FEE_BPS = 500;
+    const HORIZON_SECS = 2;
+    let ctx: IsolatedPool;
+    let staker: Staker;
+    let feeVault: PublicKey;
+    let expectedNet: BN;
+
+    function claimWithMin(minExpected: BN) {
+      return program.methods
+        .claimRewards(minExpected)
+        .accounts({
+          user: staker.user.publicKey,
+          stakePool: ctx.pool,
+          userStake: staker.userStake,
+          stakingMint: ctx.mint,
+          userTokenAccount: staker.tokenAccount,
+          owner: staker.user.publicKey,
+          rewardMint: ctx.rewardMint,
+          treasuryVault: ctx.treasury,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+          feeVault,
+        })
+        .signers([staker.user]);
+    }
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      staker = await createStaker(ctx);
+      feeVault = await createAccount(connection, admin, ctx.mint, Keypair.generate().publicKey);
+      await fundPool(ctx, TREASURY_FUND);
+
+      // No timelock on a fresh pool, so the fee applies straight away
+      await program.methods
+        .proposeClaimFee(FEE_BPS)
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool, feeVault })
+        .signers([admin])
+        .rpc();
+      await program.methods.applyClaimFee().accounts({ stakePool: ctx.pool }).rpc();
+
+      await program.methods
+        .setMaxAccrualHorizon(new BN(HORIZON_SECS))
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_FLEX);
+      await advanceTime(8);
+
+      const gross = calculateExpectedRewards(STAKE_AMOUNT, FLEX_APY, HORIZON_SECS);
+      expectedNet = gross.sub(gross.mul(new BN(FEE_BPS)).div(new BN(BASIS_POINTS)));
+    });
+
+    it("should reject when fee and clipping pay less than expected", async () => {
+      try {
+        await claimWithMin(expectedNet.add(new BN(1))).rpc();
+        expect.fail("Should have thrown PayoutBelowExpected");
+      } catch (error: any) {
+        expect(error.message).to.include("PayoutBelowExpected");
+        console.log("✓ Claim below expected minimum rejected");
+      }
+    });
+
+    it("should return the net payout in return data", async () => {
+      const sim = await claimWithMin(expectedNet).simulate();
+      const returnLog = sim.raw.find((line) => line.startsWith("Program return:"));
+      expect(returnLog).to.not.equal(undefined);
+      const data = Buffer.from(returnLog!.split(" ").pop()!, "base64");
+      expect(data.readBigUInt64LE(0).toString()).to.equal(expectedNet.toString());
+      console.log("✓ Return data carries the net payout");
+    });
+
+    it("should pay out when the minimum is met", async () => {
+      const balanceBefore = await tokenBalance(staker.tokenAccount);
+      await claimWithMin(expectedNet).rpc();
+      const received = (await tokenBalance(staker.tokenAccount)).sub(balanceBefore);
+
+      expect(received.toString()).to.equal(expectedNet.toString());
+      console.log(`✓ Claimed exactly the expected ${formatTokens(received)}`);
+    });
+  });
+
+  // ============================================
+  // TEST 32: UNSTAKE FEE
+  // ============================================
+
+  describe("32. Unstake Fee", () => {
+    const FLEX_FEE_BPS = 10;
+    let ctx: IsolatedPool;
+    let staker: Staker;
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      staker = await createStaker(ctx);
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_FLEX);
+    });
+
+    it("should charge nothing by default", async () => {
+      const before = await tokenBalance(staker.tokenAccount);
+      await unstakeFrom(ctx, staker, ONE_TOKEN);
+      const received = (await tokenBalance(staker.tokenAccount)).sub(before);
+
+      expect(received.toString()).to.equal(ONE_TOKEN.toString());
+      console.log("✓ Zero fee by default");
+    });
+
+    it("should reject fees above the cap", async () => {
+      try {
+        await program.methods
+          .setUnstakeFee(TIER_CORE, 101)
+          .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+          .signers([admin])
+          .rpc();
+        expect.fail("Should have thrown InvalidUnstakeFee");
+      } catch (error: any) {
+        expect(error.message).to.include("InvalidUnstakeFee");
+        console.log("✓ Fee above 100bp rejected");
+      }
+    });
+
+    it("should route the tier fee to the treasury", async () => {
+      await program.methods
+        .setUnstakeFee(TIER_FLEX, FLEX_FEE_BPS)
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+
+      const stakeBefore = await program.account.userStake.fetch(staker.userStake);
+      const userBefore = await tokenBalance(staker.tokenAccount);
+      const treasuryBefore = await tokenBalance(ctx.treasury);
+
+      await unstakeFrom(ctx, staker, ONE_TOKEN);
+
+      const stakeAfter = await program.account.userStake.fetch(staker.userStake);
+      const received = (await tokenBalance(staker.tokenAccount)).sub(userBefore);
+      const fee = (await tokenBalance(ctx.treasury)).sub(treasuryBefore);
+      const expectedFee = ONE_TOKEN.mul(new BN(FLEX_FEE_BPS)).div(new BN(BASIS_POINTS));
+
+      expect(fee.toString()).to.equal(expectedFee.toString());
+      expect(received.toString()).to.equal(ONE_TOKEN.sub(expectedFee).toString());
+      expect(stakeBefore.stakedAmount.sub(stakeAfter.stakedAmount).toString()).to.equal(
+        ONE_TOKEN.toString(),
+        "Position should drop by the full amount"
+      );
+      console.log(`✓ Fee ${formatTokens(fee)} paid to treasury`);
+    });
+
+    it("should charge no fee on a 1-lamport unstake", async () => {
+      const before = await tokenBalance(staker.tokenAccount);
+      await unstakeFrom(ctx, staker, new BN(1));
+      const received = (await tokenBalance(staker.tokenAccount)).sub(before);
+
+      expect(received.toString()).to.equal("1");
+      console.log("✓ Fee rounds down to zero on dust");
+    });
+
+    it("should require the treasury while a fee is set", async () => {
+      try {
+        await program.methods
+          .unstake(ONE_TOKEN)
+          .accounts({
+            user: staker.user.publicKey,
+            stakePool: ctx.pool,
+            userStake: staker.userStake,
+            stakingMint: ctx.mint,
+            userTokenAccount: staker.tokenAccount,
+            stakingVault: ctx.vault,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+          })
+          .signers([staker.user])
+          .rpc();
+        expect.fail("Should have thrown MissingTreasuryVault");
+      } catch (error: any) {
+        expect(error.message).to.include("MissingTreasuryVault");
+        console.log("✓ Unstake without treasury rejected");
+      }
+    });
+  });
+
+  // ============================================
+  // TEST 33: BURNED PENALTIES
+  // ============================================
+
+  describe("33. Burned Penalties", () => {
+    const FEE_BPS = 100;
+    let ctx: IsolatedPool;
+    let staker: Staker;
+
+    function setDestination(pool: IsolatedPool, destination: object): Promise<string> {
+      return program.methods
+        .setPenaltyDestination(destination as any)
+        .accounts({ authority: admin.publicKey, stakePool: pool.pool, stakingMint: pool.mint })
+        .signers([admin])
+        .rpc();
+    }
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      staker = await createStaker(ctx);
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_FLEX);
+
+      await program.methods
+        .setUnstakeFee(TIER_FLEX, FEE_BPS)
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+      await setDestination(ctx, { burn: {} });
+    });
+
+    it("should burn the fee and keep vault and ledger consistent", async () => {
+      const supplyBefore = (await getMint(connection, ctx.mint)).supply;
+      const vaultBefore = await tokenBalance(ctx.vault);
+      const treasuryBefore = await tokenBalance(ctx.treasury);
+      const poolBefore = await program.account.stakePool.fetch(ctx.pool);
+
+      await unstakeFrom(ctx, staker, ONE_TOKEN);
+
+      const fee = ONE_TOKEN.mul(new BN(FEE_BPS)).div(new BN(BASIS_POINTS));
+      const supplyAfter = (await getMint(connection, ctx.mint)).supply;
+      const poolAfter = await program.account.stakePool.fetch(ctx.pool);
+
+      expect((supplyBefore - supplyAfter).toString()).to.equal(fee.toString());
+      expect(vaultBefore.sub(await tokenBalance(ctx.vault)).toString()).to.equal(ONE_TOKEN.toString());
+      expect((await tokenBalance(ctx.treasury)).toString()).to.equal(treasuryBefore.toString());
+      expect(poolBefore.totalStaked.sub(poolAfter.totalStaked).toString()).to.equal(ONE_TOKEN.toString());
+      expect((await tokenBalance(ctx.vault)).toString()).to.equal(poolAfter.totalStaked.toString());
+      console.log(`✓ Burned ${formatTokens(fee)}, vault matches total_staked`);
+    });
+
+    it("should refuse burning for a mint with a freeze authority", async () => {
+      const freezable = await createIsolatedPool(EMISSION_CAP, admin.publicKey);
+      try {
+        await setDestination(freezable, { burn: {} });
+        expect.fail("Should have thrown BurnNotSupported");
+      } catch (error: any) {
+        expect(error.message).to.include("BurnNotSupported");
+        console.log("✓ Burn refused for freezable mint");
+      }
+    });
+
+    it("should start a freezable dual-token pool with no destination and no fees", async () => {
+      const freezable = await createIsolatedPool(EMISSION_CAP, admin.publicKey, true);
+      const pool = await program.account.stakePool.fetch(freezable.pool);
+      expect(pool.penaltyDestination).to.have.property("unset");
+
+      for (const call of [
+        program.methods.setUnstakeFee(TIER_FLEX, FEE_BPS),
+        program.methods.setEarlyUnstakePenalty(TIER_CORE, FEE_BPS),
+      ]) {
+        try {
+          await call.accounts({ authority: admin.publicKey, stakePool: freezable.pool }).signers([admin]).rpc();
+          expect.fail("Should have thrown NoPenaltyDestination");
+        } catch (error: any) {
+          expect(error.message).to.include("NoPenaltyDestination");
+        }
+      }
+      await program.methods
+        .setUnstakeFee(TIER_FLEX, 0)
+        .accounts({ authority: admin.publicKey, stakePool: freezable.pool })
+        .signers([admin])
+        .rpc();
+      console.log("✓ Fees refused, zero still accepted");
+    });
+  });
+
+  // ============================================
+  // TEST 34: FLEX COOLDOWN
+  // ============================================
+
+  describe("34. Flex Cooldown", () => {
+    const COOLDOWN_SECS = 3;
+    let ctx: IsolatedPool;
+    let veteran: Staker;
+    let newcomer: Staker;
+
+    function setFlexMinStake(secs: number): Promise<string> {
+      return program.methods
+        .setFlexMinStake(new BN(secs))
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+    }
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      veteran = await createStaker(ctx);
+      newcomer = await createStaker(ctx);
+      await stakeInto(ctx, veteran, STAKE_AMOUNT, TIER_FLEX);
+      await new Promise((resolve) => setTimeout(resolve, (COOLDOWN_SECS + 1) * 1000));
+
+      await setFlexMinStake(COOLDOWN_SECS);
+      await stakeInto(ctx, newcomer, STAKE_AMOUNT, TIER_FLEX);
+    });
+
+    it("should reject a Flex unstake inside the cooldown", async () => {
+      try {
+        await unstakeFrom(ctx, newcomer, ONE_TOKEN);
+        expect.fail("Should have thrown FlexCooldownActive");
+      } catch (error: any) {
+        expect(error.message).to.include("FlexCooldownActive");
+        console.log("✓ Unstake inside the cooldown rejected");
+      }
+    });
+
+    it("should not trap stakers who already passed the cooldown", async () => {
+      await unstakeFrom(ctx, veteran, ONE_TOKEN);
+      console.log("✓ Existing staker unaffected by the new cooldown");
+    });
+
+    it("should allow the unstake once the cooldown has passed", async () => {
+      await new Promise((resolve) => setTimeout(resolve, (COOLDOWN_SECS + 1) * 1000));
+      await unstakeFrom(ctx, newcomer, ONE_TOKEN);
+      console.log("✓ Unstake allowed after the cooldown");
+    });
+
+    it("should reject a cooldown above the maximum", async () => {
+      try {
+        await setFlexMinStake(7 * SECONDS_PER_DAY + 1);
+        expect.fail("Should have thrown InvalidFlexMinStake");
+      } catch (error: any) {
+        expect(error.message).to.include("InvalidFlexMinStake");
+        console.log("✓ Cooldown above 7 days rejected");
+      }
+    });
+  });
+
+  // ============================================
+  // TEST 35: TREASURY YIELD
+  // ============================================
+
+  describe("35. Treasury Yield", () => {
+    const FUND = new BN(400);
+    const DEPLOY = new BN(200);
+    const YIELD = new BN(50);
+    const HORIZON_SECS = 2;
+    let ctx: IsolatedPool;
+    let staker: Staker;
+    let stubVault: PublicKey;
+    let adapterAccounts: { pubkey: PublicKey; isSigner: boolean; isWritable: boolean }[];
+
+    function treasuryCall(
+      method: "deployTreasury" | "recallTreasury",
+      amount: BN,
+      adapterVault: PublicKey = stubVault
+    ) {
+      return program.methods[method](amount)
+        .accounts({
+          authority: admin.publicKey,
+          stakePool: ctx.pool,
+          treasuryVault: ctx.treasury,
+          adapterProgram: yieldStub.programId,
+          adapterVault,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .remainingAccounts(method === "deployTreasury" ? adapterAccounts.slice(0, 1) : adapterAccounts)
+        .signers([admin])
+        .rpc();
+    }
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      staker = await createStaker(ctx);
+      await fundPool(ctx, FUND);
+
+      const [vaultAuthority] = PublicKey.findProgramAddressSync(
+        [Buffer.from("vault_authority")],
+        yieldStub.programId
+      );
+      stubVault = await createAccount(connection, admin, ctx.mint, vaultAuthority, Keypair.generate());
+      adapterAccounts = [
+        { pubkey: vaultAuthority, isSigner: false, isWritable: false },
+        { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+      ];
+
+      await program.methods
+        .setYieldAdapter(0, yieldStub.programId)
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+      await program.methods
+        .setMaxTreasuryDeploy(5_000)
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+      // Clamp accrual so the claim amount is deterministic
+      await program.methods
+        .setMaxAccrualHorizon(new BN(HORIZON_SECS))
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_FLEX);
+    });
+
+    it("should refuse to deploy beyond the cap", async () => {
+      try {
+        await treasuryCall("deployTreasury", DEPLOY.add(new BN(1)));
+        expect.fail("Should have thrown TreasuryDeployCapExceeded");
+      } catch (error: any) {
+        expect(error.message).to.include("TreasuryDeployCapExceeded");
+        console.log("✓ Deploy above 50% rejected");
+      }
+    });
+
+    it("should refuse an adapter vault owned by the pool", async () => {
+      try {
+        await treasuryCall("deployTreasury", DEPLOY, ctx.vault);
+        expect.fail("Should have thrown InvalidAdapterVault");
+      } catch (error: any) {
+        expect(error.message).to.include("InvalidAdapterVault");
+        console.log("✓ Pool vaults cannot be deployment targets");
+      }
+    });
+
+    it("should deploy into the adapter and track it", async () => {
+      await treasuryCall("deployTreasury", DEPLOY);
+
+      const poolState = await program.account.stakePool.fetch(ctx.pool);
+      expect(poolState.treasuryDeployed.toString()).to.equal(DEPLOY.toString());
+      expect((await tokenBalance(stubVault)).toString()).to.equal(DEPLOY.toString());
+      expect((await tokenBalance(ctx.treasury)).toString()).to.equal(FUND.sub(DEPLOY).toString());
+      console.log("✓ Treasury deployed to adapter");
+    });
+
+    it("should pay only the liquid treasury and reject claims once it is empty", async () => {
+      await advanceTime(8);
+      const claimable = calculateExpectedRewards(STAKE_AMOUNT, FLEX_APY, HORIZON_SECS);
+      expect(claimable.gt(FUND.sub(DEPLOY))).to.equal(true);
+
+      const before = await tokenBalance(staker.tokenAccount);
+      await claimFrom(ctx, staker);
+      const received = (await tokenBalance(staker.tokenAccount)).sub(before);
+      expect(received.toString()).to.equal(FUND.sub(DEPLOY).toString());
+
+      try {
+        await claimFrom(ctx, staker);
+        expect.fail("Should have thrown TreasuryIlliquid");
+      } catch (error: any) {
+        expect(error.message).to.include("TreasuryIlliquid");
+        console.log("✓ Liquid part paid, then claims rejected as illiquid");
+      }
+    });
+
+    it("should recall principal plus accrued yield", async () => {
+      // Simulate yield landing in the adapter
+      await mintTo(connection, admin, ctx.mint, stubVault, admin, BigInt(YIELD.toString()));
+      await treasuryCall("recallTreasury", DEPLOY.add(YIELD));
+
+      const poolState = await program.account.stakePool.fetch(ctx.pool);
+      expect(poolState.treasuryDeployed.toNumber()).to.equal(0);
+      expect((await tokenBalance(ctx.treasury)).toString()).to.equal(DEPLOY.add(YIELD).toString());
+      console.log("✓ Recalled principal and yield");
+    });
+
+    it("should pay the claim once funds are recalled", async () => {
+      const before = await tokenBalance(staker.tokenAccount);
+      await claimFrom(ctx, staker);
+      const received = (await tokenBalance(staker.tokenAccount)).sub(before);
+
+      expect(received.gt(new BN(0))).to.equal(true);
+      console.log(`✓ Claim paid ${received} after recall`);
+    });
+  });
+
+  // ============================================
+  // TEST 36: RESERVE DRIP
+  // ============================================
+
+  describe("36. Reserve Drip", () => {
+    const RATE = new BN(1_000);
+    const RESERVE = new BN(1_000_000_000);
+    let ctx: IsolatedPool;
+    let reserve: PublicKey;
+
+    function fundReserve(amount: BN): Promise<string> {
+      return program.methods
+        .fundReserve(amount)
+        .accounts({
+          funder: admin.publicKey,
+          stakePool: ctx.pool,
+          stakingMint: ctx.mint,
+          funderTokenAccount: ctx.adminTokenAccount,
+          reserveVault: reserve,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          rent: SYSVAR_RENT_PUBKEY,
+        })
+        .signers([admin])
+        .rpc();
+    }
+
+    async function drip(): Promise<void> {
+      await program.methods
+        .dripTreasury()
+        .accounts({
+          stakePool: ctx.pool,
+          reserveVault: reserve,
+          treasuryVault: ctx.treasury,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+    }
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      [reserve] = PublicKey.findProgramAddressSync(
+        [Buffer.from("reserve_vault"), ctx.pool.toBuffer()],
+        program.programId
+      );
+      await fundReserve(RESERVE);
+
+      await program.methods
+        .setDripRate(RATE)
+        .accounts({
+          authority: admin.publicKey,
+          stakePool: ctx.pool,
+          reserveVault: reserve,
+          treasuryVault: ctx.treasury,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([admin])
+        .rpc();
+    });
+
+    it("should drip rate x elapsed across irregular intervals", async () => {
+      const start = (await program.account.stakePool.fetch(ctx.pool)).lastDripAt;
+
+      for (const waitMs of [1000, 2500, 1200]) {
+        await new Promise((resolve) => setTimeout(resolve, waitMs));
+        await drip();
+      }
+
+      const end = (await program.account.stakePool.fetch(ctx.pool)).lastDripAt;
+      const expected = RATE.mul(end.sub(start));
+      expect((await tokenBalance(ctx.treasury)).toString()).to.equal(expected.toString());
+      expect(RESERVE.sub(await tokenBalance(reserve)).toString()).to.equal(expected.toString());
+      console.log(`✓ Dripped ${expected} over ${end.sub(start)}s`);
+    });
+
+    it("should keep dripping after a mid-stream top-up", async () => {
+      await fundReserve(RESERVE);
+      const treasuryBefore = await tokenBalance(ctx.treasury);
+      const start = (await program.account.stakePool.fetch(ctx.pool)).lastDripAt;
+
+      await new Promise((resolve) => setTimeout(resolve, 2000));
+      await drip();
+
+      const end = (await program.account.stakePool.fetch(ctx.pool)).lastDripAt;
+      const dripped = (await tokenBalance(ctx.treasury)).sub(treasuryBefore);
+      expect(dripped.toString()).to.equal(RATE.mul(end.sub(start)).toString());
+      console.log("✓ Top-up does not disturb the drip schedule");
+    });
+  });
+
+  // ============================================
+  // TEST 37: BONUS CREDIT
+  // ============================================
+
+  describe("37. Bonus Credit", () => {
+    const BONUS = ONE_TOKEN.mul(new BN(10));
+    const BATCH = 20;
+    let ctx: IsolatedPool;
+    let stakers: Staker[];
+
+    function creditBonus(pool: PublicKey, treasury: PublicKey, positions: PublicKey[]) {
+      return program.methods
+        .creditBonus(BONUS)
+        .accounts({ authority: admin.publicKey, stakePool: pool, treasuryVault: treasury })
+        .remainingAccounts(positions.map((pubkey) => ({ pubkey, isSigner: false, isWritable: true })))
+        .signers([admin])
+        .rpc();
+    }
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      await fundPool(ctx, TREASURY_FUND);
+      stakers = [];
+      for (let i = 0; i < BATCH; i++) {
+        const staker = await createStaker(ctx);
+        await stakeInto(ctx, staker, ONE_TOKEN, TIER_FLEX);
+        stakers.push(staker);
+      }
+    });
+
+    it("should credit every position in a 20-account batch", async () => {
+      await creditBonus(ctx.pool, ctx.treasury, stakers.map((s) => s.userStake));
+
+      for (const staker of stakers) {
+        const userStake = await program.account.userStake.fetch(staker.userStake);
+        expect(userStake.pendingRewards.gte(BONUS)).to.equal(true);
+        expect(userStake.bonusRewards.toString()).to.equal(BONUS.toString());
+      }
+      const poolState = await program.account.stakePool.fetch(ctx.pool);
+      expect(poolState.bonusLiability.toString()).to.equal(BONUS.muln(BATCH).toString());
+      console.log(`✓ Credited ${formatTokens(BONUS)} to ${BATCH} positions`);
+    });
+
+    it("should reject a foreign-pool position in the batch", async () => {
+      const other = await createIsolatedPool();
+      const outsider = await createStaker(other);
+      await stakeInto(other, outsider, ONE_TOKEN, TIER_FLEX);
+
+      try {
+        await creditBonus(ctx.pool, ctx.treasury, [stakers[0].userStake, outsider.userStake]);
+        expect.fail("Should have thrown StakePoolMismatch");
+      } catch (error: any) {
+        expect(error.message).to.include("StakePoolMismatch");
+        console.log("✓ Foreign-pool position rejected");
+      }
+    });
+
+    it("should release the liability when the bonus is claimed", async () => {
+      const before = await tokenBalance(stakers[0].tokenAccount);
+      await claimFrom(ctx, stakers[0]);
+      const received = (await tokenBalance(stakers[0].tokenAccount)).sub(before);
+
+      const poolState = await program.account.stakePool.fetch(ctx.pool);
+      expect(received.gte(BONUS)).to.equal(true);
+      expect(poolState.bonusLiability.toString()).to.equal(BONUS.muln(BATCH - 1).toString());
+      console.log("✓ Claimed bonus released from liability");
+    });
+  });
+
+  // ============================================
+  // TEST 38: EVENTS
+  // ============================================
+
+  describe("38. Events", () => {
+    const eventParser = new anchor.EventParser(program.programId, new anchor.BorshCoder(program.idl));
+    let ctx: IsolatedPool;
+    let staker: Staker;
+
+    async function eventsOf(signature: string): Promise<anchor.Event[]> {
+      await connection.confirmTransaction(signature, "confirmed");
+      const tx = await connection.getTransaction(signature, {
+        commitment: "confirmed",
+        maxSupportedTransactionVersion: 0,
+      });
+      return Array.from(eventParser.parseLogs(tx!.meta!.logMessages!));
+    }
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      staker = await createStaker(ctx);
+      await fundPool(ctx, TREASURY_FUND);
+    });
+
+    it("should emit StakeEvent", async () => {
+      const events = await eventsOf(await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_CORE));
+      const event = events.find((e) => e.name === "StakeEvent");
+
+      expect(event).to.not.equal(undefined);
+      expect(event!.data.user.toBase58()).to.equal(staker.user.publicKey.toBase58());
+      expect(event!.data.stakePool.toBase58()).to.equal(ctx.pool.toBase58());
+      expect(event!.data.tier).to.equal(TIER_CORE);
+      expect(event!.data.amount.toString()).to.equal(STAKE_AMOUNT.toString());
+      expect(event!.data.stakedAfter.toString()).to.equal(STAKE_AMOUNT.toString());
+      expect(event!.data.totalStaked.toString()).to.equal(STAKE_AMOUNT.toString());
+      console.log("✓ StakeEvent emitted");
+    });
+
+    it("should emit ClaimEvent", async () => {
+      await advanceTime(4);
+      const events = await eventsOf(await claimFrom(ctx, staker));
+      const event = events.find((e) => e.name === "ClaimEvent");
+      const userStake = await program.account.userStake.fetch(staker.userStake);
+
+      expect(event).to.not.equal(undefined);
+      expect(event!.data.user.toBase58()).to.equal(staker.user.publicKey.toBase58());
+      expect(event!.data.tier).to.equal(TIER_CORE);
+      expect(event!.data.net.gt(new BN(0))).to.equal(true);
+      expect(event!.data.totalClaimed.toString()).to.equal(userStake.totalRewardsClaimed.toString());
+      console.log("✓ ClaimEvent emitted");
+    });
+
+    it("should emit UnstakeEvent", async () => {
+      // Core is locked; emergency mode releases principal for the test
+      await program.methods
+        .setEmergency()
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+
+      const events = await eventsOf(await unstakeFrom(ctx, staker, ONE_TOKEN));
+      const event = events.find((e) => e.name === "UnstakeEvent");
+
+      expect(event).to.not.equal(undefined);
+      expect(event!.data.owner.toBase58()).to.equal(staker.user.publicKey.toBase58());
+      expect(event!.data.amount.toString()).to.equal(ONE_TOKEN.toString());
+      expect(event!.data.remaining.toString()).to.equal(STAKE_AMOUNT.sub(ONE_TOKEN).toString());
+      expect(event!.data.totalStaked.toString()).to.equal(STAKE_AMOUNT.sub(ONE_TOKEN).toString());
+      console.log("✓ UnstakeEvent emitted");
+    });
+  });
+
+  // ============================================
+  // TEST 39: UNLOCK GRACE
+  // ============================================
+
+  describe("39. Unlock Grace", () => {
+    let ctx: IsolatedPool;
+    let staker: Staker;
+
+    function setUnlockGrace(secs: number): Promise<string> {
+      return program.methods
+        .setUnlockGrace(new BN(secs))
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+    }
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      staker = await createStaker(ctx);
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_CORE);
+    });
+
+    it("should store the grace window", async () => {
+      await setUnlockGrace(3600);
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.unlockGraceSecs.toNumber()).to.equal(3600);
+      console.log("✓ Unlock grace set to 1 hour");
+    });
+
+    it("should keep a fresh Core position locked", async () => {
+      try {
+        await unstakeFrom(ctx, staker, ONE_TOKEN);
+        expect.fail("Should have thrown LockPeriodNotEnded");
+      } catch (error: any) {
+        expect(error.message).to.include("LockPeriodNotEnded");
+        console.log("✓ Grace only applies near the lock end");
+      }
+    });
+
+    it("should reject a grace window above the maximum", async () => {
+      try {
+        await setUnlockGrace(SECONDS_PER_DAY + 1);
+        expect.fail("Should have thrown InvalidUnlockGrace");
+      } catch (error: any) {
+        expect(error.message).to.include("InvalidUnlockGrace");
+        console.log("✓ Grace above 1 day rejected");
+      }
+    });
+
+    it("should reject a negative grace window", async () => {
+      try {
+        await setUnlockGrace(-1);
+        expect.fail("Should have thrown InvalidUnlockGrace");
+      } catch (error: any) {
+        expect(error.message).to.include("InvalidUnlockGrace");
+        console.log("✓ Negative grace rejected");
+      }
+    });
+  });
+
+  // ============================================
+  // TEST 40: COMPOUND REWARDS
+  // ============================================
+
+  describe("40. Compound Rewards", () => {
+    const HORIZON_SECS = 2;
+    let ctx: IsolatedPool;
+    let staker: Staker;
+    let firstCompound: BN;
+
+    function compound(): Promise<string> {
+      return program.methods
+        .compoundRewards()
+        .accounts({
+          user: staker.user.publicKey,
+          stakePool: ctx.pool,
+          userStake: staker.userStake,
+          stakingMint: ctx.mint,
+          stakingVault: ctx.vault,
+          treasuryVault: ctx.treasury,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+        })
+        .signers([staker.user])
+        .rpc();
+    }
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      staker = await createStaker(ctx);
+      await fundPool(ctx, TREASURY_FUND);
+
+      // Clamp accrual so each compound earns an exact, predictable amount
+      await program.methods
+        .setMaxAccrualHorizon(new BN(HORIZON_SECS))
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_FLEX);
+    });
+
+    it("should move rewards from the treasury into the stake", async () => {
+      await advanceTime(8);
+      const walletBefore = await tokenBalance(staker.tokenAccount);
+      const treasuryBefore = await tokenBalance(ctx.treasury);
+
+      await compound();
+
+      firstCompound = calculateExpectedRewards(STAKE_AMOUNT, FLEX_APY, HORIZON_SECS);
+      const userStake = await program.account.userStake.fetch(staker.userStake);
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(userStake.stakedAmount.toString()).to.equal(STAKE_AMOUNT.add(firstCompound).toString());
+      expect(userStake.pendingRewards.toNumber()).to.equal(0);
+      expect(pool.totalStaked.toString()).to.equal(userStake.stakedAmount.toString());
+      expect(pool.totalDistributed.toString()).to.equal(firstCompound.toString());
+      expect(treasuryBefore.sub(await tokenBalance(ctx.treasury)).toString()).to.equal(firstCompound.toString());
+      expect((await tokenBalance(ctx.vault)).toString()).to.equal(pool.totalStaked.toString());
+      expect((await tokenBalance(staker.tokenAccount)).toString()).to.equal(walletBefore.toString());
+      console.log(`✓ Compounded ${formatTokens(firstCompound)} without touching the wallet`);
+    });
+
+    it("should accrue rewards on the compounded amount", async () => {
+      await advanceTime(8);
+      await compound();
+
+      const second = calculateExpectedRewards(STAKE_AMOUNT.add(firstCompound), FLEX_APY, HORIZON_SECS);
+      const userStake = await program.account.userStake.fetch(staker.userStake);
+      expect(second.gt(firstCompound)).to.equal(true);
+      expect(userStake.stakedAmount.toString()).to.equal(
+        STAKE_AMOUNT.add(firstCompound).add(second).toString()
+      );
+      console.log(`✓ Second compound earned ${formatTokens(second)} on the larger stake`);
+    });
+
+    it("should respect the minimum claim interval", async () => {
+      await program.methods
+        .setMinClaimInterval(new BN(60))
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+      try {
+        await compound();
+        expect.fail("Should have thrown ClaimTooSoon");
+      } catch (error: any) {
+        expect(error.message).to.include("ClaimTooSoon");
+        console.log("✓ Compounding waits out the claim interval");
+      } finally {
+        await program.methods
+          .setMinClaimInterval(new BN(0))
+          .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+          .signers([admin])
+          .rpc();
+      }
+    });
+
+    it("should compound the payable part and defer the rest", async () => {
+      const EPOCH_BUDGET = new BN(1);
+      await program.methods
+        .setEpochEmissionLimit(EPOCH_BUDGET, new BN(60))
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+      await advanceTime(8);
+      const before = await program.account.userStake.fetch(staker.userStake);
+
+      await compound();
+
+      const userStake = await program.account.userStake.fetch(staker.userStake);
+      expect(userStake.stakedAmount.toString()).to.equal(
+        before.stakedAmount.add(EPOCH_BUDGET).toString()
+      );
+      expect(userStake.pendingRewards.gt(new BN(0))).to.equal(true);
+      console.log(`✓ Compounded ${EPOCH_BUDGET} and deferred ${userStake.pendingRewards}`);
+    });
+  });
+
+  // ============================================
+  // TEST 41: EARLY UNSTAKE
+  // ============================================
+
+  describe("41. Early Unstake", () => {
+    const CORE_PENALTY_BPS = 1000;
+    const PRIME_PENALTY_BPS = 2000;
+    let ctx: IsolatedPool;
+    let coreStaker: Staker;
+    let primeStaker: Staker;
+    let flexStaker: Staker;
+
+    function setPenalty(tier: number, penaltyBps: number): Promise<string> {
+      return program.methods
+        .setEarlyUnstakePenalty(tier, penaltyBps)
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+    }
+
+    function earlyUnstake(staker: Staker, amount: BN): Promise<string> {
+      return program.methods
+        .earlyUnstake(amount)
+        .accounts({
+          treasuryVault: ctx.treasury,
+          user: staker.user.publicKey,
+          stakePool: ctx.pool,
+          userStake: staker.userStake,
+          stakingMint: ctx.mint,
+          userTokenAccount: staker.tokenAccount,
+          stakingVault: ctx.vault,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+        })
+        .signers([staker.user])
+        .rpc();
+    }
+
+    function ceilPenalty(amount: BN, penaltyBps: number): BN {
+      return amount.mul(new BN(penaltyBps)).add(new BN(BASIS_POINTS - 1)).div(new BN(BASIS_POINTS));
+    }
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      await fundPool(ctx, TREASURY_FUND);
+      coreStaker = await createStaker(ctx);
+      primeStaker = await createStaker(ctx);
+      flexStaker = await createStaker(ctx);
+      await stakeInto(ctx, coreStaker, STAKE_AMOUNT, TIER_CORE);
+      await stakeInto(ctx, primeStaker, STAKE_AMOUNT, TIER_PRIME);
+      await stakeInto(ctx, flexStaker, STAKE_AMOUNT, TIER_FLEX);
+    });
+
+    it("should reject early exits while the penalty is unset", async () => {
+      try {
+        await earlyUnstake(coreStaker, ONE_TOKEN);
+        expect.fail("Should have thrown EarlyUnstakeDisabled");
+      } catch (error: any) {
+        expect(error.message).to.include("EarlyUnstakeDisabled");
+        console.log("✓ Early unstake disabled by default");
+      }
+    });
+
+    it("should charge the penalty and keep pending rewards", async () => {
+      await setPenalty(TIER_CORE, CORE_PENALTY_BPS);
+      await setPenalty(TIER_PRIME, PRIME_PENALTY_BPS);
+      await advanceTime(4);
+
+      const amount = ONE_TOKEN.mul(new BN(1000));
+      const walletBefore = await tokenBalance(coreStaker.tokenAccount);
+      const treasuryBefore = await tokenBalance(ctx.treasury);
+      await earlyUnstake(coreStaker, amount);
+
+      const penalty = ceilPenalty(amount, CORE_PENALTY_BPS);
+      const received = (await tokenBalance(coreStaker.tokenAccount)).sub(walletBefore);
+      expect(received.toString()).to.equal(amount.sub(penalty).toString());
+      expect((await tokenBalance(ctx.treasury)).sub(treasuryBefore).toString()).to.equal(penalty.toString());
+
+      const userStake = await program.account.userStake.fetch(coreStaker.userStake);
+      expect(userStake.stakedAmount.toString()).to.equal(STAKE_AMOUNT.sub(amount).toString());
+      expect(userStake.pendingRewards.gt(new BN(0))).to.equal(true);
+
+      const rewardsBefore = await tokenBalance(coreStaker.tokenAccount);
+      await claimFrom(ctx, coreStaker);
+      const claimed = (await tokenBalance(coreStaker.tokenAccount)).sub(rewardsBefore);
+      expect(claimed.gte(userStake.pendingRewards)).to.equal(true);
+      console.log(`✓ Penalty ${formatTokens(penalty)} to treasury, rewards still claimable`);
+    });
+
+    it("should round the penalty up on tiny exits", async () => {
+      const walletBefore = await tokenBalance(primeStaker.tokenAccount);
+      await earlyUnstake(primeStaker, new BN(9));
+
+      const received = (await tokenBalance(primeStaker.tokenAccount)).sub(walletBefore);
+      expect(ceilPenalty(new BN(9), PRIME_PENALTY_BPS).toNumber()).to.equal(2);
+      expect(received.toNumber()).to.equal(7);
+      console.log("✓ 9 base units at 20% pay a 2 unit penalty");
+    });
+
+    it("should reject early exits from Flex", async () => {
+      try {
+        await earlyUnstake(flexStaker, ONE_TOKEN);
+        expect.fail("Should have thrown EarlyUnstakeDisabled");
+      } catch (error: any) {
+        expect(error.message).to.include("EarlyUnstakeDisabled");
+        console.log("✓ Flex has no early exit");
+      }
+    });
+
+    it("should keep the normal unstake locked", async () => {
+      try {
+        await unstakeFrom(ctx, coreStaker, ONE_TOKEN);
+        expect.fail("Should have thrown LockPeriodNotEnded");
+      } catch (error: any) {
+        expect(error.message).to.include("LockPeriodNotEnded");
+        console.log("✓ Penalty-free unstake still waits for the lock");
+      }
+    });
+
+    it("should reject a penalty above the maximum or on Flex", async () => {
+      try {
+        await setPenalty(TIER_CORE, 3001);
+        expect.fail("Should have thrown InvalidEarlyUnstakePenalty");
+      } catch (error: any) {
+        expect(error.message).to.include("InvalidEarlyUnstakePenalty");
+      }
+      try {
+        await setPenalty(TIER_FLEX, 1000);
+        expect.fail("Should have thrown InvalidTier");
+      } catch (error: any) {
+        expect(error.message).to.include("InvalidTier");
+      }
+      console.log("✓ Penalty capped at 30% and limited to locked tiers");
+    });
+  });
+
+  // ============================================
+  // TEST 42: TWO-STEP AUTHORITY TRANSFER
+  // ============================================
+
+  describe("42. Two-Step Authority Transfer", () => {
+    let ctx: IsolatedPool;
+    let newAdmin: Keypair;
+
+    function propose(authority: Keypair, newAuthority: PublicKey): Promise<string> {
+      return program.methods
+        .proposeAuthority(newAuthority)
+        .accounts({ authority: authority.publicKey, stakePool: ctx.pool })
+        .signers([authority])
+        .rpc();
+    }
+
+    function accept(signer: Keypair): Promise<string> {
+      return program.methods
+        .acceptAuthority()
+        .accounts({ newAuthority: signer.publicKey, stakePool: ctx.pool })
+        .signers([signer])
+        .rpc();
+    }
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      newAdmin = Keypair.generate();
+      await airdropSol(newAdmin.publicKey);
+    });
+
+    it("should no longer expose the single-step transfer", async () => {
+      expect((program.methods as any).transferAuthority).to.equal(undefined);
+      console.log("✓ transfer_authority removed from the program");
+    });
+
+    it("should leave the authority unchanged after a proposal", async () => {
+      await propose(admin, newAdmin.publicKey);
+
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.authority.toString()).to.equal(admin.publicKey.toString());
+      expect(pool.pendingAuthority.toString()).to.equal(newAdmin.publicKey.toString());
+      console.log("✓ Proposal recorded as pending");
+    });
+
+    it("should reject an accept from anyone but the pending authority", async () => {
+      const intruder = Keypair.generate();
+      await airdropSol(intruder.publicKey);
+      try {
+        await accept(intruder);
+        expect.fail("Should have thrown Unauthorized");
+      } catch (error: any) {
+        expect(error.message).to.include("Unauthorized");
+        console.log("✓ Unauthorized accept rejected");
+      }
+    });
+
+    it("should clear the proposal on cancel", async () => {
+      await program.methods
+        .cancelAuthorityTransfer()
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.pendingAuthority.toString()).to.equal(PublicKey.default.toString());
+      try {
+        await accept(newAdmin);
+        expect.fail("Should have thrown Unauthorized");
+      } catch (error: any) {
+        expect(error.message).to.include("Unauthorized");
+      }
+      console.log("✓ Cancelled proposal cannot be accepted");
+    });
+
+    it("should hand over control once accepted", async () => {
+      await propose(admin, newAdmin.publicKey);
+      await accept(newAdmin);
+
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.authority.toString()).to.equal(newAdmin.publicKey.toString());
+      expect(pool.pendingAuthority.toString()).to.equal(PublicKey.default.toString());
+
+      try {
+        await propose(admin, admin.publicKey);
+        expect.fail("Should have thrown Unauthorized");
+      } catch (error: any) {
+        expect(error.message).to.include("Unauthorized");
+      }
+      await propose(newAdmin, admin.publicKey);
+      console.log("✓ New authority in control, old one locked out");
+    });
+  });
+
+  // ============================================
+  // TEST 43: CLOSE USER STAKE
+  // ============================================
+
+  describe("43. Close User Stake", () => {
+    let ctx: IsolatedPool;
+    let staker: Staker;
+
+    function closePosition(): Promise<string> {
+      return program.methods
+        .closeUserStake()
+        .accounts({
+          user: staker.user.publicKey,
+          stakePool: ctx.pool,
+          userStake: staker.userStake,
+        })
+        .signers([staker.user])
+        .rpc();
+    }
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      await fundPool(ctx, TREASURY_FUND);
+      staker = await createStaker(ctx);
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_FLEX);
+    });
+
+    it("should refuse to close a position holding principal", async () => {
+      try {
+        await closePosition();
+        expect.fail("Should have thrown PositionNotEmpty");
+      } catch (error: any) {
+        expect(error.message).to.include("PositionNotEmpty");
+        console.log("✓ Open position cannot be closed");
+      }
+    });
+
+    it("should refuse to close with unclaimed rewards", async () => {
+      await advanceTime(2);
+      await unstakeFrom(ctx, staker, STAKE_AMOUNT);
+      try {
+        await closePosition();
+        expect.fail("Should have thrown RewardsOutstanding");
+      } catch (error: any) {
+        expect(error.message).to.include("RewardsOutstanding");
+        console.log("✓ Exited position with pending rewards cannot be closed");
+      }
+    });
+
+    it("should close and refund rent without touching the staker count", async () => {
+      await claimFrom(ctx, staker);
+      const stakersBefore = (await program.account.stakePool.fetch(ctx.pool)).stakerCount;
+      const rent = (await connection.getAccountInfo(staker.userStake))!.lamports;
+      const lamportsBefore = await connection.getBalance(staker.user.publicKey);
+
+      await closePosition();
+
+      expect(await connection.getAccountInfo(staker.userStake)).to.equal(null);
+      const refunded = (await connection.getBalance(staker.user.publicKey)) - lamportsBefore;
+      expect(refunded).to.be.greaterThan(rent - 10_000);
+      const stakersAfter = (await program.account.stakePool.fetch(ctx.pool)).stakerCount;
+      expect(stakersAfter.toString()).to.equal(stakersBefore.toString());
+      console.log(`✓ Position closed, ${refunded} lamports refunded`);
+    });
+
+    it("should let the user stake again with a fresh position", async () => {
+      const before = await program.account.stakePool.fetch(ctx.pool);
+      const stakersBefore = before.stakerCount;
+      await stakeInto(ctx, staker, ONE_TOKEN, TIER_CORE);
+
+      const userStake = await program.account.userStake.fetch(staker.userStake);
+      expect(userStake.stakedAmount.toString()).to.equal(ONE_TOKEN.toString());
+      expect(tierIndex(userStake.tier)).to.equal(TIER_CORE);
+      expect(userStake.totalRewardsClaimed.toNumber()).to.equal(0);
+      expect(userStake.pendingRewards.toNumber()).to.equal(0);
+      const stakersAfter = (await program.account.stakePool.fetch(ctx.pool)).stakerCount;
+      expect(stakersAfter.sub(stakersBefore).toNumber()).to.equal(1);
+
+      // The staker record survived the close, so this is not a new staker
+      const after = await program.account.stakePool.fetch(ctx.pool);
+      expect(after.uniqueStakersEver.toString()).to.equal(before.uniqueStakersEver.toString());
+      console.log("✓ Re-staked into a fresh position");
+    });
+  });
+
+  // ============================================
+  // TEST 44: VIEW PENDING REWARDS
+  // ============================================
+
+  describe("44. View Pending Rewards", () => {
+    const HORIZON_SECS = 2;
+    let ctx: IsolatedPool;
+    let staker: Staker;
+
+    function viewPending() {
+      return program.methods
+        .viewPendingRewards()
+        .accounts({ stakePool: ctx.pool, userStake: staker.userStake })
+        .view();
+    }
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      staker = await createStaker(ctx);
+      await fundPool(ctx, TREASURY_FUND);
+
+      // Clamp accrual so the quote and the claim land on the same amount
+      await program.methods
+        .setMaxAccrualHorizon(new BN(HORIZON_SECS))
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_CORE);
+    });
+
+    it("should quote lock end and tier APY", async () => {
+      const quote = await viewPending();
+      const userStake = await program.account.userStake.fetch(staker.userStake);
+
+      expect(quote.lockEndTime.toNumber()).to.equal(userStake.stakeStartTime.toNumber() + CORE_LOCK_PERIOD);
+      expect(quote.apyBps).to.equal(CORE_APY);
+      console.log(`✓ Lock ends at ${quote.lockEndTime}, APY ${quote.apyBps}bp`);
+    });
+
+    it("should match the amount a real claim pays", async () => {
+      await advanceTime(8);
+      const quote = await viewPending();
+
+      const before = await tokenBalance(staker.tokenAccount);
+      await claimFrom(ctx, staker);
+      const received = (await tokenBalance(staker.tokenAccount)).sub(before);
+
+      expect(quote.claimable.toString()).to.equal(received.toString());
+      expect(quote.claimable.toString()).to.equal(
+        calculateExpectedRewards(STAKE_AMOUNT, CORE_APY, HORIZON_SECS).toString()
+      );
+      console.log(`✓ Quote ${formatTokens(quote.claimable)} matches the claim`);
+    });
+  });
+
+  // ============================================
+  // TEST 45: EMERGENCY WITHDRAW
+  // ============================================
+
+  describe("45. Emergency Withdraw", () => {
+    let ctx: IsolatedPool;
+    let primeStaker: Staker;
+
+    function emergencyWithdraw(staker: Staker): Promise<string> {
+      return program.methods
+        .emergencyWithdraw()
+        .accounts({
+          treasuryVault: ctx.treasury,
+          user: staker.user.publicKey,
+          stakePool: ctx.pool,
+          userStake: staker.userStake,
+          stakingMint: ctx.mint,
+          userTokenAccount: staker.tokenAccount,
+          stakingVault: ctx.vault,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+        })
+        .signers([staker.user])
+        .rpc();
+    }
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      await fundPool(ctx, TREASURY_FUND);
+      primeStaker = await createStaker(ctx);
+      await stakeInto(ctx, primeStaker, STAKE_AMOUNT, TIER_PRIME);
+    });
+
+    it("should refuse to bypass the lock outside emergency mode", async () => {
+      try {
+        await emergencyWithdraw(primeStaker);
+        expect.fail("Should have thrown EmergencyModeInactive");
+      } catch (error: any) {
+        expect(error.message).to.include("EmergencyModeInactive");
+        console.log("✓ Lock holds while the flag is off");
+      }
+    });
+
+    it("should return the full principal and forfeit rewards in emergency mode", async () => {
+      await advanceTime(2);
+      await program.methods
+        .setEmergency()
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+
+      const before = await tokenBalance(primeStaker.tokenAccount);
+      await emergencyWithdraw(primeStaker);
+      const received = (await tokenBalance(primeStaker.tokenAccount)).sub(before);
+      expect(received.toString()).to.equal(STAKE_AMOUNT.toString());
+
+      const userStake = await program.account.userStake.fetch(primeStaker.userStake);
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(userStake.stakedAmount.toNumber()).to.equal(0);
+      expect(userStake.pendingRewards.toNumber()).to.equal(0);
+      expect(userStake.isActive).to.equal(false);
+      expect(pool.totalStaked.toNumber()).to.equal(0);
+      expect(pool.stakerCount.toNumber()).to.equal(0);
+      console.log(`✓ Withdrew ${formatTokens(received)} from a locked Prime position`);
+    });
+
+    it("should reject a second withdraw from the emptied position", async () => {
+      try {
+        await emergencyWithdraw(primeStaker);
+        expect.fail("Should have thrown NoActiveStake");
+      } catch (error: any) {
+        expect(error.message).to.include("NoActiveStake");
+        console.log("✓ Nothing left to withdraw");
+      }
+    });
+  });
+
+  // ==========================================================================
+  // 46. PER-TIER PAUSE
+  // ==========================================================================
+
+  describe("46. Per-Tier Pause", () => {
+    let ctx: IsolatedPool;
+    let staker: Staker;
+
+    function setTierPaused(tier: number, paused: boolean): Promise<string> {
+      return program.methods
+        .setTierPaused(tier, paused)
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+    }
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      await fundPool(ctx, TREASURY_FUND);
+      staker = await createStaker(ctx);
+    });
+
+    it("should block stakes into a paused tier only", async () => {
+      await setTierPaused(TIER_PRIME, true);
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.primePaused).to.equal(true);
+      expect(pool.pauseFlags).to.equal(0);
+
+      try {
+        await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_PRIME);
+        expect.fail("Should have thrown StakingPaused");
+      } catch (error: any) {
+        expect(error.message).to.include("StakingPaused");
+      }
+
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_FLEX);
+      const userStake = await program.account.userStake.fetch(staker.userStake);
+      expect(userStake.stakedAmount.toString()).to.equal(STAKE_AMOUNT.toString());
+      console.log("✓ Prime paused while Flex keeps accepting stakes");
+    });
+
+    it("should reject pausing the permanent tier on its own", async () => {
+      try {
+        await setTierPaused(3, true);
+        expect.fail("Should have thrown InvalidTier");
+      } catch (error: any) {
+        expect(error.message).to.include("InvalidTier");
+        console.log("✓ Permanent tier follows the global pause only");
+      }
+    });
+
+    it("should resume the tier when unpaused", async () => {
+      await setTierPaused(TIER_PRIME, false);
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.primePaused).to.equal(false);
+      console.log("✓ Prime resumed");
+    });
+  });
+
+  // ==========================================================================
+  // 47. MINIMUM STAKE AMOUNT
+  // ==========================================================================
+
+  describe("47. Minimum Stake Amount", () => {
+    let ctx: IsolatedPool;
+    let staker: Staker;
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      staker = await createStaker(ctx);
+      await program.methods
+        .setMinStakeAmount(STAKE_AMOUNT)
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+    });
+
+    it("should reject an opening stake below the minimum", async () => {
+      try {
+        await stakeInto(ctx, staker, STAKE_AMOUNT.sub(ONE_TOKEN), TIER_FLEX);
+        expect.fail("Should have thrown StakeBelowMinimum");
+      } catch (error: any) {
+        expect(error.message).to.include("StakeBelowMinimum");
+        console.log("✓ Dust opening stake rejected");
+      }
+    });
+
+    it("should allow a top-up below the minimum once the position is open", async () => {
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_FLEX);
+      await stakeInto(ctx, staker, ONE_TOKEN, TIER_FLEX);
+
+      const userStake = await program.account.userStake.fetch(staker.userStake);
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(userStake.stakedAmount.toString()).to.equal(STAKE_AMOUNT.add(ONE_TOKEN).toString());
+      expect(pool.stakerCount.toNumber()).to.equal(1);
+      console.log("✓ Top-up of 1 token accepted on an open position");
+    });
+
+    it("should apply the minimum again after the position is fully exited", async () => {
+      await unstakeFrom(ctx, staker, STAKE_AMOUNT.add(ONE_TOKEN));
+      try {
+        await stakeInto(ctx, staker, ONE_TOKEN, TIER_FLEX);
+        expect.fail("Should have thrown StakeBelowMinimum");
+      } catch (error: any) {
+        expect(error.message).to.include("StakeBelowMinimum");
+        console.log("✓ Re-opening a position counts as a first stake");
+      }
+    });
+  });
+
+  // ==========================================================================
+  // 48. POOL-WIDE STAKING CAP
+  // ==========================================================================
+
+  describe("48. Pool-Wide Staking Cap", () => {
+    let ctx: IsolatedPool;
+    let stakerA: Staker;
+    let stakerB: Staker;
+    let stakerC: Staker;
+
+    function setMaxTotalStaked(cap: BN): Promise<string> {
+      return program.methods
+        .setMaxTotalStaked(cap)
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+    }
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      stakerA = await createStaker(ctx);
+      stakerB = await createStaker(ctx);
+      stakerC = await createStaker(ctx);
+      await setMaxTotalStaked(STAKE_AMOUNT.muln(2));
+    });
+
+    it("should accept a stake that fills the cap exactly", async () => {
+      await stakeInto(ctx, stakerA, STAKE_AMOUNT, TIER_FLEX);
+      await stakeInto(ctx, stakerB, STAKE_AMOUNT, TIER_FLEX);
+
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.totalStaked.toString()).to.equal(pool.maxTotalStaked.toString());
+      console.log("✓ Pool filled to exactly 100%");
+    });
+
+    it("should reject any stake once the cap is reached", async () => {
+      try {
+        await stakeInto(ctx, stakerC, new BN(1), TIER_FLEX);
+        expect.fail("Should have thrown PoolCapReached");
+      } catch (error: any) {
+        expect(error.message).to.include("PoolCapReached");
+        console.log("✓ 1 base unit over the cap rejected");
+      }
+    });
+
+    it("should let only one of two racing stakes through the last slot", async () => {
+      await setMaxTotalStaked(STAKE_AMOUNT.muln(3));
+
+      const results = await Promise.allSettled([
+        stakeInto(ctx, stakerA, STAKE_AMOUNT, TIER_FLEX),
+        stakeInto(ctx, stakerC, STAKE_AMOUNT, TIER_FLEX),
+      ]);
+      const fulfilled = results.filter((r) => r.status === "fulfilled");
+      const rejected = results.filter((r) => r.status === "rejected") as PromiseRejectedResult[];
+      expect(fulfilled.length).to.equal(1);
+      expect(rejected.length).to.equal(1);
+      expect(rejected[0].reason.message).to.include("PoolCapReached");
+
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.totalStaked.toString()).to.equal(STAKE_AMOUNT.muln(3).toString());
+      console.log("✓ Exactly one racing stake landed; cap held");
+    });
+
+    it("should leave existing stakes untouched when the cap is lowered", async () => {
+      await setMaxTotalStaked(STAKE_AMOUNT);
+
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.totalStaked.toString()).to.equal(STAKE_AMOUNT.muln(3).toString());
+
+      await unstakeFrom(ctx, stakerB, STAKE_AMOUNT);
+      try {
+        await stakeInto(ctx, stakerB, new BN(1), TIER_FLEX);
+        expect.fail("Should have thrown PoolCapReached");
+      } catch (error: any) {
+        expect(error.message).to.include("PoolCapReached");
+      }
+      console.log("✓ Lowered cap blocks deposits but not unstakes");
+    });
+
+    it("should lift the limit when the cap is set to zero", async () => {
+      await setMaxTotalStaked(new BN(0));
+      await stakeInto(ctx, stakerB, STAKE_AMOUNT, TIER_FLEX);
+      console.log("✓ Zero cap disables the limit");
+    });
+  });
+
+  // ==========================================================================
+  // 49. FLEX WITHDRAWAL COOLDOWN QUEUE
+  // ==========================================================================
+
+  describe("49. Flex Withdrawal Cooldown Queue", () => {
+    const COOLDOWN_SECS = 3;
+    const HALF = STAKE_AMOUNT.divn(2);
+    let ctx: IsolatedPool;
+    let staker: Staker;
+
+    function requestUnstake(amount: BN): Promise<string> {
+      return program.methods
+        .requestUnstake(amount)
+        .accounts({ user: staker.user.publicKey, stakePool: ctx.pool, userStake: staker.userStake })
+        .signers([staker.user])
+        .rpc();
+    }
+
+    function cancelUnstakeRequest(): Promise<string> {
+      return program.methods
+        .cancelUnstakeRequest()
+        .accounts({ user: staker.user.publicKey, stakePool: ctx.pool, userStake: staker.userStake })
+        .signers([staker.user])
+        .rpc();
+    }
+
+    function withdraw(): Promise<string> {
+      return program.methods
+        .withdraw()
+        .accounts({
+          treasuryVault: ctx.treasury,
+          user: staker.user.publicKey,
+          stakePool: ctx.pool,
+          userStake: staker.userStake,
+          stakingMint: ctx.mint,
+          userTokenAccount: staker.tokenAccount,
+          stakingVault: ctx.vault,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+        })
+        .signers([staker.user])
+        .rpc();
+    }
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      await fundPool(ctx, TREASURY_FUND);
+      staker = await createStaker(ctx);
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_FLEX);
+      await program.methods
+        .setFlexCooldown(new BN(COOLDOWN_SECS))
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+    });
+
+    it("should route Flex exits through the queue while a cooldown is set", async () => {
+      try {
+        await unstakeFrom(ctx, staker, ONE_TOKEN);
+        expect.fail("Should have thrown UnstakeRequestRequired");
+      } catch (error: any) {
+        expect(error.message).to.include("UnstakeRequestRequired");
+        console.log("✓ Direct Flex unstake refused");
+      }
+    });
+
+    it("should move the requested amount out of the earning stake", async () => {
+      await requestUnstake(HALF);
+
+      const userStake = await program.account.userStake.fetch(staker.userStake);
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(userStake.stakedAmount.toString()).to.equal(STAKE_AMOUNT.sub(HALF).toString());
+      expect(userStake.unstakeRequestedAmount.toString()).to.equal(HALF.toString());
+      expect(userStake.lastClaimTime.toNumber()).to.equal(userStake.unstakeRequestedAt.toNumber());
+      expect(pool.totalStaked.toString()).to.equal(STAKE_AMOUNT.sub(HALF).toString());
+      console.log(`✓ Queued ${formatTokens(HALF)}; only the rest keeps accruing`);
+    });
+
+    it("should refuse a second request and an early withdraw", async () => {
+      try {
+        await requestUnstake(ONE_TOKEN);
+        expect.fail("Should have thrown UnstakeRequestPending");
+      } catch (error: any) {
+        expect(error.message).to.include("UnstakeRequestPending");
+      }
+
+      try {
+        await withdraw();
+        expect.fail("Should have thrown WithdrawalCooldownActive");
+      } catch (error: any) {
+        expect(error.message).to.include("WithdrawalCooldownActive");
+      }
+      console.log("✓ One request at a time, paid only after the cooldown");
+    });
+
+    it("should restore accrual when the request is cancelled", async () => {
+      await cancelUnstakeRequest();
+
+      const userStake = await program.account.userStake.fetch(staker.userStake);
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(userStake.stakedAmount.toString()).to.equal(STAKE_AMOUNT.toString());
+      expect(userStake.unstakeRequestedAmount.toNumber()).to.equal(0);
+      expect(pool.totalStaked.toString()).to.equal(STAKE_AMOUNT.toString());
+      console.log("✓ Cancelled request returned to the position");
+    });
+
+    it("should pay out the request once the cooldown has passed", async () => {
+      await requestUnstake(HALF);
+      await new Promise((resolve) => setTimeout(resolve, (COOLDOWN_SECS + 1) * 1000));
+
+      const before = await tokenBalance(staker.tokenAccount);
+      await withdraw();
+      const received = (await tokenBalance(staker.tokenAccount)).sub(before);
+      expect(received.toString()).to.equal(HALF.toString());
+
+      const userStake = await program.account.userStake.fetch(staker.userStake);
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(userStake.unstakeRequestedAmount.toNumber()).to.equal(0);
+      expect(userStake.stakedAmount.toString()).to.equal(STAKE_AMOUNT.sub(HALF).toString());
+      expect(userStake.isActive).to.equal(true);
+      expect(pool.totalStaked.toString()).to.equal(STAKE_AMOUNT.sub(HALF).toString());
+      console.log(`✓ Withdrew ${formatTokens(received)} after ${COOLDOWN_SECS}s`);
+    });
+
+    it("should close out the position when the last principal is withdrawn", async () => {
+      await requestUnstake(STAKE_AMOUNT.sub(HALF));
+      await new Promise((resolve) => setTimeout(resolve, (COOLDOWN_SECS + 1) * 1000));
+      await withdraw();
+
+      const userStake = await program.account.userStake.fetch(staker.userStake);
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(userStake.isActive).to.equal(false);
+      expect(pool.stakerCount.toNumber()).to.equal(0);
+      expect(pool.totalStaked.toNumber()).to.equal(0);
+      console.log("✓ Full withdrawal closed the position");
+    });
+  });
+
+  // ==========================================================================
+  // 50. APY CHECKPOINTS
+  // ==========================================================================
+
+  describe("50. APY Checkpoints", () => {
+    let ctx: IsolatedPool;
+    let staker: Staker;
+
+    function adjustApy(flexApy: number): Promise<string> {
+      return program.methods
+        .adjustApy(flexApy, CORE_APY, PRIME_APY)
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+    }
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      await fundPool(ctx, TREASURY_FUND);
+      staker = await createStaker(ctx);
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_FLEX);
+    });
+
+    it("should pay each segment at the rate in force during it", async () => {
+      const stakedAt = (await program.account.userStake.fetch(staker.userStake)).lastClaimTime.toNumber();
+      await advanceTime(4);
+      await adjustApy(FLEX_APY * 3);
+      const changedAt = (await program.account.stakePool.fetch(ctx.pool)).lastUpdated.toNumber();
+      await advanceTime(4);
+
+      const before = await tokenBalance(staker.tokenAccount);
+      await claimFrom(ctx, staker);
+      const received = (await tokenBalance(staker.tokenAccount)).sub(before);
+      const claimedAt = (await program.account.userStake.fetch(staker.userStake)).lastClaimTime.toNumber();
+
+      // sum(apy * seconds) over both segments, divided once like the program
+      const rateSeconds = new BN(FLEX_APY)
+        .muln(changedAt - stakedAt)
+        .add(new BN(FLEX_APY * 3).muln(claimedAt - changedAt));
+      const expected = STAKE_AMOUNT.mul(rateSeconds).div(new BN(BASIS_POINTS).mul(new BN(SECONDS_PER_YEAR)));
+      expect(received.toString()).to.equal(expected.toString());
+
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.apyEpoch.toNumber()).to.equal(1);
+      expect(pool.apyHistory[0].replacedAt.toNumber()).to.equal(changedAt);
+      expect(pool.apyHistory[0].apys[TIER_FLEX]).to.equal(FLEX_APY);
+      console.log(`✓ Claimed ${formatTokens(received)} across ${changedAt - stakedAt}s + ${claimedAt - changedAt}s segments`);
+    });
+
+    it("should not claw back accrued rewards when the APY drops to zero", async () => {
+      const startedAt = (await program.account.userStake.fetch(staker.userStake)).lastClaimTime.toNumber();
+      await advanceTime(4);
+      await adjustApy(0);
+      const changedAt = (await program.account.stakePool.fetch(ctx.pool)).lastUpdated.toNumber();
+
+      const before = await tokenBalance(staker.tokenAccount);
+      await claimFrom(ctx, staker);
+      const received = (await tokenBalance(staker.tokenAccount)).sub(before);
+
+      const expected = calculateExpectedRewards(STAKE_AMOUNT, FLEX_APY * 3, changedAt - startedAt);
+      expect(received.toString()).to.equal(expected.toString());
+      expect(received.gtn(0)).to.equal(true);
+      console.log(`✓ Rewards earned before the cut were kept (${formatTokens(received)})`);
+    });
+  });
+
+  // ==========================================================================
+  // 51. UPGRADE TIER
+  // ==========================================================================
+
+  describe("51. Upgrade Tier", () => {
+    let ctx: IsolatedPool;
+    let staker: Staker;
+
+    function upgradeTier(newTier: number): Promise<string> {
+      return program.methods
+        .upgradeTier(newTier)
+        .accounts({
+          user: staker.user.publicKey,
+          stakePool: ctx.pool,
+          userStake: staker.userStake,
+        })
+        .signers([staker.user])
+        .rpc();
+    }
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      await fundPool(ctx, TREASURY_FUND);
+      staker = await createStaker(ctx);
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_CORE);
+    });
+
+    it("should move Core to Prime mid-lock and settle at the Core APY", async () => {
+      const before = await program.account.userStake.fetch(staker.userStake);
+      await advanceTime(3);
+      await upgradeTier(TIER_PRIME);
+
+      const after = await program.account.userStake.fetch(staker.userStake);
+      const upgradedAt = after.stakeStartTime.toNumber();
+      const expected = calculateExpectedRewards(
+        STAKE_AMOUNT,
+        CORE_APY,
+        upgradedAt - before.lastClaimTime.toNumber()
+      );
+
+      expect(tierIndex(after.tier)).to.equal(TIER_PRIME);
+      expect(after.pendingRewards.toString()).to.equal(expected.toString());
+      expect(after.lastClaimTime.toNumber()).to.equal(upgradedAt);
+      expect(upgradedAt).to.be.greaterThan(before.stakeStartTime.toNumber());
+      expect(after.stakedAmount.toString()).to.equal(STAKE_AMOUNT.toString());
+      console.log(`✓ Settled ${formatTokens(after.pendingRewards)} at Core APY on upgrade`);
+      console.log(`✓ Prime lock ends at ${upgradedAt + PRIME_LOCK_PERIOD}`);
+    });
+
+    it("should keep the new Prime lock in force", async () => {
+      try {
+        await unstakeFrom(ctx, staker, STAKE_AMOUNT);
+        expect.fail("Should have thrown LockPeriodNotEnded");
+      } catch (error: any) {
+        expect(error.message).to.include("LockPeriodNotEnded");
+        console.log("✓ Unstake blocked until the Prime lock ends");
+      }
+    });
+
+    it("should reject downgrades and same-tier upgrades", async () => {
+      for (const tier of [TIER_FLEX, TIER_CORE, TIER_PRIME]) {
+        try {
+          await upgradeTier(tier);
+          expect.fail("Should have thrown CannotChangeTier");
+        } catch (error: any) {
+          expect(error.message).to.include("CannotChangeTier");
+        }
+      }
+      const userStake = await program.account.userStake.fetch(staker.userStake);
+      expect(tierIndex(userStake.tier)).to.equal(TIER_PRIME);
+      console.log("✓ Downgrades rejected with CannotChangeTier");
+    });
+  });
+
+  // ==========================================================================
+  // 52. AUTO-RENEW
+  // ==========================================================================
+
+  describe("52. Auto-Renew", () => {
+    let ctx: IsolatedPool;
+    let coreStaker: Staker;
+    let flexStaker: Staker;
+
+    function setAutoRenew(staker: Staker, enabled: boolean): Promise<string> {
+      return program.methods
+        .setAutoRenew(enabled)
+        .accounts({
+          user: staker.user.publicKey,
+          stakePool: ctx.pool,
+          userStake: staker.userStake,
+        })
+        .signers([staker.user])
+        .rpc();
+    }
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      await fundPool(ctx, TREASURY_FUND);
+      coreStaker = await createStaker(ctx);
+      flexStaker = await createStaker(ctx);
+      await stakeInto(ctx, coreStaker, STAKE_AMOUNT, TIER_CORE);
+      await stakeInto(ctx, flexStaker, STAKE_AMOUNT, TIER_FLEX);
+    });
+
+    it("should enable auto-renew on a Core position without moving the lock", async () => {
+      const before = await program.account.userStake.fetch(coreStaker.userStake);
+      await setAutoRenew(coreStaker, true);
+
+      const after = await program.account.userStake.fetch(coreStaker.userStake);
+      expect(after.autoRenew).to.equal(true);
+      expect(after.stakeStartTime.toNumber()).to.equal(before.stakeStartTime.toNumber());
+      expect(after.completedLockCycles).to.equal(before.completedLockCycles);
+      console.log("✓ Auto-renew enabled inside the first cycle");
+    });
+
+    it("should block unstake and change_tier while auto-renew is on", async () => {
+      try {
+        await unstakeFrom(ctx, coreStaker, STAKE_AMOUNT);
+        expect.fail("Should have thrown LockPeriodNotEnded");
+      } catch (error: any) {
+        expect(error.message).to.include("LockPeriodNotEnded");
+      }
+
+      try {
+        await program.methods
+          .changeTier(TIER_CORE)
+          .accounts({
+            user: coreStaker.user.publicKey,
+            stakePool: ctx.pool,
+            userStake: coreStaker.userStake,
+          })
+          .signers([coreStaker.user])
+          .rpc();
+        expect.fail("Should have thrown LockPeriodNotEnded");
+      } catch (error: any) {
+        expect(error.message).to.include("LockPeriodNotEnded");
+      }
+      console.log("✓ Auto-renewing lock never ends on its own");
+    });
+
+    it("should keep the running cycle when auto-renew is turned off", async () => {
+      const before = await program.account.userStake.fetch(coreStaker.userStake);
+      await setAutoRenew(coreStaker, false);
+
+      const after = await program.account.userStake.fetch(coreStaker.userStake);
+      expect(after.autoRenew).to.equal(false);
+      expect(after.stakeStartTime.toNumber()).to.equal(before.stakeStartTime.toNumber());
+
+      try {
+        await unstakeFrom(ctx, coreStaker, STAKE_AMOUNT);
+        expect.fail("Should have thrown LockPeriodNotEnded");
+      } catch (error: any) {
+        expect(error.message).to.include("LockPeriodNotEnded");
+      }
+      console.log(`✓ Unlocks at the cycle end ${after.stakeStartTime.toNumber() + CORE_LOCK_PERIOD}`);
+    });
+
+    it("should reject auto-renew on Flex", async () => {
+      try {
+        await setAutoRenew(flexStaker, true);
+        expect.fail("Should have thrown AutoRenewRequiresLock");
+      } catch (error: any) {
+        expect(error.message).to.include("AutoRenewRequiresLock");
+        console.log("✓ Flex has no lock to renew");
+      }
+    });
+  });
+
+  // ==========================================================================
+  // 53. WITHDRAW TREASURY
+  // ==========================================================================
+
+  describe("53. Withdraw Treasury", () => {
+    let ctx: IsolatedPool;
+    let staker: Staker;
+
+    function withdrawTreasury(amount: BN): Promise<string> {
+      return program.methods
+        .withdrawTreasury(amount)
+        .accounts({
+          authority: admin.publicKey,
+          stakePool: ctx.pool,
+          stakingMint: ctx.mint,
+          treasuryVault: ctx.treasury,
+          destination: ctx.adminTokenAccount,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([admin])
+        .rpc();
+    }
+
+    function setPaused(paused: boolean): Promise<string> {
+      return program.methods
+        .setPaused(paused, null)
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+    }
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      await fundPool(ctx, TREASURY_FUND);
+      staker = await createStaker(ctx);
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_FLEX);
+      await advanceTime(3);
+      // A top-up settles accrual into stored pending_rewards
+      await stakeInto(ctx, staker, ONE_TOKEN, TIER_FLEX);
+    });
+
+    it("should track stored pending rewards pool-side", async () => {
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      const userStake = await program.account.userStake.fetch(staker.userStake);
+      expect(userStake.pendingRewards.gtn(0)).to.equal(true);
+      expect(pool.pendingLiability.toString()).to.equal(userStake.pendingRewards.toString());
+      console.log(`✓ Pending liability: ${formatTokens(pool.pendingLiability)}`);
+    });
+
+    it("should reject withdrawals while the pool is live", async () => {
+      try {
+        await withdrawTreasury(ONE_TOKEN);
+        expect.fail("Should have thrown PoolNotPaused");
+      } catch (error: any) {
+        expect(error.message).to.include("PoolNotPaused");
+        console.log("✓ Treasury withdrawal requires a paused pool");
+      }
+    });
+
+    it("should refuse to dip below the liability floor", async () => {
+      await setPaused(true);
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      const floor = pool.pendingLiability.add(pool.referralLiability);
+      const surplus = (await tokenBalance(ctx.treasury)).sub(floor);
+
+      try {
+        await withdrawTreasury(surplus.addn(1));
+        expect.fail("Should have thrown TreasuryBelowLiabilities");
+      } catch (error: any) {
+        expect(error.message).to.include("TreasuryBelowLiabilities");
+        console.log("✓ One token unit past the surplus is rejected");
+      }
+    });
+
+    it("should withdraw exactly the surplus and leave the floor behind", async () => {
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      const floor = pool.pendingLiability.add(pool.referralLiability);
+      const surplus = (await tokenBalance(ctx.treasury)).sub(floor);
+      const adminBefore = await tokenBalance(ctx.adminTokenAccount);
+
+      await withdrawTreasury(surplus);
+
+      expect((await tokenBalance(ctx.treasury)).toString()).to.equal(floor.toString());
+      expect((await tokenBalance(ctx.adminTokenAccount)).sub(adminBefore).toString()).to.equal(surplus.toString());
+
+      try {
+        await withdrawTreasury(new BN(1));
+        expect.fail("Should have thrown TreasuryBelowLiabilities");
+      } catch (error: any) {
+        expect(error.message).to.include("TreasuryBelowLiabilities");
+      }
+      await setPaused(false);
+      console.log(`✓ Withdrew ${formatTokens(surplus)}, ${formatTokens(floor)} kept for stakers`);
+    });
+  });
+
+  // ==========================================================================
+  // 54. DUAL-TOKEN POOLS
+  // ==========================================================================
+
+  describe("54. Dual-Token Pools", () => {
+    let single: IsolatedPool;
+    let dual: IsolatedPool;
+    let staker: Staker;
+
+    before(async () => {
+      single = await createIsolatedPool();
+      dual = await createIsolatedPool(EMISSION_CAP, null, true);
+      await fundPool(dual, TREASURY_FUND);
+      staker = await createStaker(dual);
+      await stakeInto(dual, staker, STAKE_AMOUNT, TIER_FLEX);
+    });
+
+    it("should store the staking mint as reward mint on single-token pools", async () => {
+      const pool = await program.account.stakePool.fetch(single.pool);
+      expect(pool.rewardMint.toBase58()).to.equal(single.mint.toBase58());
+      expect(pool.penaltyDestination).to.have.property("treasury");
+      console.log("✓ Single-token pool unchanged");
+    });
+
+    it("should create the treasury for the reward mint", async () => {
+      const pool = await program.account.stakePool.fetch(dual.pool);
+      const treasury = await getAccount(connection, dual.treasury);
+      expect(pool.rewardMint.toBase58()).to.equal(dual.rewardMint.toBase58());
+      expect(treasury.mint.toBase58()).to.equal(dual.rewardMint.toBase58());
+      expect(pool.penaltyDestination).to.have.property("burn");
+      expect((await tokenBalance(dual.vault)).toString()).to.equal(STAKE_AMOUNT.toString());
+      console.log("✓ Staking vault holds NOVA, treasury holds the reward token");
+    });
+
+    it("should reject funding the treasury with the staking token", async () => {
+      try {
+        await program.methods
+          .fundTreasury(ONE_TOKEN)
+          .accounts({
+            funder: admin.publicKey,
+            stakePool: dual.pool,
+            stakingMint: dual.mint,
+            funderTokenAccount: dual.adminTokenAccount,
+            treasuryVault: dual.treasury,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([admin])
+          .rpc();
+        expect.fail("Should have thrown MintMismatch");
+      } catch (error: any) {
+        expect(error.message).to.include("MintMismatch");
+        console.log("✓ Treasury only accepts the reward mint");
+      }
+    });
+
+    it("should pay claims in the reward token", async () => {
+      await advanceTime(3);
+      const stakingBefore = await tokenBalance(staker.tokenAccount);
+      const rewardBefore = await tokenBalance(staker.rewardAccount);
+
+      await claimFrom(dual, staker);
+
+      const received = (await tokenBalance(staker.rewardAccount)).sub(rewardBefore);
+      expect(received.gtn(0)).to.equal(true);
+      expect((await tokenBalance(staker.tokenAccount)).toString()).to.equal(stakingBefore.toString());
+      console.log(`✓ Claimed ${formatTokens(received)} reward tokens`);
+    });
+
+    it("should reject claims into a staking-token account", async () => {
+      await advanceTime(2);
+      try {
+        await claimFrom(dual, { ...staker, rewardAccount: staker.tokenAccount });
+        expect.fail("Should have thrown MintMismatch");
+      } catch (error: any) {
+        expect(error.message).to.include("MintMismatch");
+        console.log("✓ Claim destination must hold the reward mint");
+      }
+    });
+
+    it("should refuse to compound rewards in another token", async () => {
+      try {
+        await program.methods
+          .compoundRewards()
+          .accounts({
+            user: staker.user.publicKey,
+            stakePool: dual.pool,
+            userStake: staker.userStake,
+            stakingMint: dual.mint,
+            stakingVault: dual.vault,
+            treasuryVault: dual.treasury,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+          })
+          .signers([staker.user])
+          .rpc();
+        expect.fail("Should have thrown SingleTokenPoolOnly");
+      } catch (error: any) {
+        expect(error.message).to.include("SingleTokenPoolOnly");
+        console.log("✓ Compounding needs a single-token pool");
+      }
+    });
+
+    it("should return principal in the staking token", async () => {
+      const before = await tokenBalance(staker.tokenAccount);
+      await unstakeFrom(dual, staker, STAKE_AMOUNT);
+      const returned = (await tokenBalance(staker.tokenAccount)).sub(before);
+      expect(returned.toString()).to.equal(STAKE_AMOUNT.toString());
+      console.log(`✓ Unstaked ${formatTokens(returned)} NOVA`);
+    });
+  });
+
+  // ==========================================================================
+  // 55. CONFIGURABLE LOCK PERIODS
+  // ==========================================================================
+
+  describe("55. Configurable Lock Periods", () => {
+    const SHORT_CORE_LOCK = 2;
+    let ctx: IsolatedPool;
+    let earlyStaker: Staker;
+    let lateStaker: Staker;
+
+    function setLockPeriods(flex: number, core: number, prime: number): Promise<string> {
+      return program.methods
+        .setLockPeriods(new BN(flex), new BN(core), new BN(prime))
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+    }
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      await fundPool(ctx, TREASURY_FUND);
+      earlyStaker = await createStaker(ctx);
+      lateStaker = await createStaker(ctx);
+    });
+
+    it("should start with the default lock periods", async () => {
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.flexLockSeconds.toNumber()).to.equal(0);
+      expect(pool.coreLockSeconds.toNumber()).to.equal(CORE_LOCK_PERIOD);
+      expect(pool.primeLockSeconds.toNumber()).to.equal(PRIME_LOCK_PERIOD);
+
+      await stakeInto(ctx, earlyStaker, STAKE_AMOUNT, TIER_CORE);
+      const userStake = await program.account.userStake.fetch(earlyStaker.userStake);
+      expect(userStake.lockSeconds.toNumber()).to.equal(CORE_LOCK_PERIOD);
+      console.log("✓ Core position stored a 90-day lock");
+    });
+
+    it("should reject a Core or Prime lock of zero", async () => {
+      try {
+        await setLockPeriods(0, 0, PRIME_LOCK_PERIOD);
+        expect.fail("Should have thrown InvalidLockPeriod");
+      } catch (error: any) {
+        expect(error.message).to.include("InvalidLockPeriod");
+        console.log("✓ Locked tiers must keep a lock");
+      }
+    });
+
+    it("should give new positions the new lock only", async () => {
+      await setLockPeriods(0, SHORT_CORE_LOCK, PRIME_LOCK_PERIOD);
+      await stakeInto(ctx, lateStaker, STAKE_AMOUNT, TIER_CORE);
+
+      const early = await program.account.userStake.fetch(earlyStaker.userStake);
+      const late = await program.account.userStake.fetch(lateStaker.userStake);
+      expect(early.lockSeconds.toNumber()).to.equal(CORE_LOCK_PERIOD);
+      expect(late.lockSeconds.toNumber()).to.equal(SHORT_CORE_LOCK);
+
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.coreCliffSecs.toNumber()).to.equal(SHORT_CORE_LOCK);
+      expect(pool.coreLinearSecs.toNumber()).to.equal(0);
+      console.log(`✓ New Core stake locked for ${SHORT_CORE_LOCK}s, the old one keeps 90 days`);
+    });
+
+    it("should release the short lock while the old one holds", async () => {
+      await new Promise((resolve) => setTimeout(resolve, (SHORT_CORE_LOCK + 1) * 1000));
+
+      const before = await tokenBalance(lateStaker.tokenAccount);
+      await unstakeFrom(ctx, lateStaker, STAKE_AMOUNT);
+      const returned = (await tokenBalance(lateStaker.tokenAccount)).sub(before);
+      expect(returned.toString()).to.equal(STAKE_AMOUNT.toString());
+
+      try {
+        await unstakeFrom(ctx, earlyStaker, STAKE_AMOUNT);
+        expect.fail("Should have thrown LockPeriodNotEnded");
+      } catch (error: any) {
+        expect(error.message).to.include("LockPeriodNotEnded");
+      }
+      console.log("✓ Existing stakes keep the lock they signed up for");
+    });
+  });
+
+  // ==========================================================================
+  // 56. SCHEDULED APY DECAY
+  // ==========================================================================
+
+  describe("56. Scheduled APY Decay", () => {
+    const DECAY_INTERVAL = 2;
+    const DECAY_STEP = 20;
+    const DECAY_FLOOR = 100;
+    let ctx: IsolatedPool;
+    let staker: Staker;
+    let createdAt: number;
+
+    function setApyDecay(interval: number, step: number, floor: number): Promise<string> {
+      return program.methods
+        .setApyDecay(new BN(interval), step, floor)
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+    }
+
+    // Independent model of the schedule: whole intervals since pool creation
+    function decayedApy(base: number, at: number): number {
+      const steps = Math.floor(Math.max(at - createdAt, 0) / DECAY_INTERVAL);
+      return Math.max(base - steps * DECAY_STEP, Math.min(DECAY_FLOOR, base));
+    }
+
+    function decayedRateSeconds(base: number, from: number, to: number): BN {
+      let total = new BN(0);
+      for (let t = from; t < to; t++) {
+        total = total.addn(decayedApy(base, t));
+      }
+      return total;
+    }
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      await fundPool(ctx, TREASURY_FUND);
+      staker = await createStaker(ctx);
+      createdAt = (await program.account.stakePool.fetch(ctx.pool)).createdAt.toNumber();
+    });
+
+    it("should reject schedules that decay too slowly or are half set", async () => {
+      for (const [interval, step] of [[DECAY_INTERVAL, DECAY_STEP - 1], [DECAY_INTERVAL, 0], [0, DECAY_STEP]]) {
+        try {
+          await setApyDecay(interval, step, DECAY_FLOOR);
+          expect.fail("Should have thrown InvalidDecaySchedule");
+        } catch (error: any) {
+          expect(error.message).to.include("InvalidDecaySchedule");
+        }
+      }
+      console.log("✓ Invalid decay schedules rejected");
+    });
+
+    it("should lower the quoted APY once an interval passes", async () => {
+      await setApyDecay(DECAY_INTERVAL, DECAY_STEP, DECAY_FLOOR);
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.decayIntervalSeconds.toNumber()).to.equal(DECAY_INTERVAL);
+      expect(pool.decayBpsPerInterval).to.equal(DECAY_STEP);
+      expect(pool.minApyFloor).to.equal(DECAY_FLOOR);
+      expect(pool.flexApy).to.equal(FLEX_APY);
+
+      await new Promise((resolve) => setTimeout(resolve, (DECAY_INTERVAL + 1) * 1000));
+      try {
+        await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_FLEX, TOPUP_KEEP_ORIGINAL, FLEX_APY);
+        expect.fail("Should have thrown ApyChangedSinceQuote");
+      } catch (error: any) {
+        expect(error.message).to.include("ApyChangedSinceQuote");
+      }
+      console.log("✓ A quote at the undecayed base rate is stale");
+    });
+
+    it("should pay each decay interval at its own rate", async () => {
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_FLEX);
+      const stakedAt = (await program.account.userStake.fetch(staker.userStake)).lastClaimTime.toNumber();
+      await new Promise((resolve) => setTimeout(resolve, (DECAY_INTERVAL * 3 + 1) * 1000));
+
+      const before = await tokenBalance(staker.rewardAccount);
+      await claimFrom(ctx, staker);
+      const received = (await tokenBalance(staker.rewardAccount)).sub(before);
+      const claimedAt = (await program.account.userStake.fetch(staker.userStake)).lastClaimTime.toNumber();
+
+      const expected = STAKE_AMOUNT.mul(decayedRateSeconds(FLEX_APY, stakedAt, claimedAt)).div(
+        new BN(BASIS_POINTS).mul(new BN(SECONDS_PER_YEAR))
+      );
+      expect(received.toString()).to.equal(expected.toString());
+      expect(decayedApy(FLEX_APY, claimedAt)).to.be.lessThan(decayedApy(FLEX_APY, stakedAt));
+      console.log(
+        `✓ Claimed ${formatTokens(received)} while the Flex APY fell ` +
+          `${decayedApy(FLEX_APY, stakedAt)}bp -> ${decayedApy(FLEX_APY, claimedAt)}bp`
+      );
+    });
+
+    it("should clear the schedule", async () => {
+      await setApyDecay(0, 0, 0);
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.decayIntervalSeconds.toNumber()).to.equal(0);
+      expect(pool.decayBpsPerInterval).to.equal(0);
+      expect(pool.minApyFloor).to.equal(0);
+
+      await stakeInto(ctx, staker, ONE_TOKEN, TIER_FLEX, TOPUP_KEEP_ORIGINAL, FLEX_APY);
+      console.log("✓ Base APY is quoted again after clearing the decay");
+    });
+  });
+
+  // ==========================================================================
+  // 57. REFRESH POOL
+  // ==========================================================================
+
+  describe("57. Refresh Pool", () => {
+    let ctx: IsolatedPool;
+    let staker: Staker;
+
+    function refreshPool(): Promise<string> {
+      return program.methods
+        .refreshPool()
+        .accounts({ stakePool: ctx.pool, treasuryVault: ctx.treasury })
+        .rpc();
+    }
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      await fundPool(ctx, TREASURY_FUND);
+      staker = await createStaker(ctx);
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_PRIME);
+      await advanceTime(2);
+      await claimFrom(ctx, staker);
+    });
+
+    it("should store a snapshot matching the pool state", async () => {
+      await refreshPool();
+
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      const treasury = await tokenBalance(ctx.treasury);
+
+      // Independent projection: settled liabilities + a year at the top rate,
+      // the year capped by the remaining emission capacity
+      const capacity = pool.emissionCap.sub(pool.totalDistributed);
+      const topApy = Math.max(pool.flexApy, pool.coreApy, pool.primeApy, pool.permanentApy);
+      const yearly = BN.min(pool.totalStaked.muln(topApy).divn(BASIS_POINTS), capacity);
+      const expected = pool.pendingLiability.add(pool.referralLiability).add(yearly);
+
+      expect(pool.projectedOutstandingRewards.toString()).to.equal(expected.toString());
+      expect(pool.snapshotEmissionCapacity.toString()).to.equal(capacity.toString());
+      expect(pool.snapshotTreasuryBalance.toString()).to.equal(treasury.toString());
+      expect(pool.lastRefreshAt.toNumber()).to.equal(pool.lastUpdated.toNumber());
+      console.log(`✓ Projected outstanding rewards: ${formatTokens(pool.projectedOutstandingRewards)}`);
+    });
+
+    it("should be callable by any fee payer and emit the snapshot", async () => {
+      const eventParser = new anchor.EventParser(program.programId, new anchor.BorshCoder(program.idl));
+      const feePayer = Keypair.generate();
+      await airdropSol(feePayer.publicKey, 1);
+
+      const tx = await program.methods
+        .refreshPool()
+        .accounts({ stakePool: ctx.pool, treasuryVault: ctx.treasury })
+        .transaction();
+      const signature = await anchor.web3.sendAndConfirmTransaction(connection, tx, [feePayer], {
+        commitment: "confirmed",
+      });
+      const confirmed = await connection.getTransaction(signature, {
+        commitment: "confirmed",
+        maxSupportedTransactionVersion: 0,
+      });
+      const event = Array.from(eventParser.parseLogs(confirmed!.meta!.logMessages!)).find(
+        (e) => e.name === "PoolSnapshot"
+      );
+
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(event).to.not.be.undefined;
+      expect(event!.data.stakePool.toString()).to.equal(ctx.pool.toString());
+      expect(event!.data.totalStaked.toString()).to.equal(pool.totalStaked.toString());
+      expect(event!.data.projectedOutstandingRewards.toString()).to.equal(
+        pool.projectedOutstandingRewards.toString()
+      );
+      expect(event!.data.treasuryBalance.toString()).to.equal(pool.snapshotTreasuryBalance.toString());
+      console.log("✓ PoolSnapshot emitted for a refresh paid by an unrelated wallet");
+    });
+
+    it("should reject a treasury that is not the pool's", async () => {
+      try {
+        await program.methods
+          .refreshPool()
+          .accounts({ stakePool: ctx.pool, treasuryVault: ctx.vault })
+          .rpc();
+        expect.fail("Should have thrown TreasuryMismatch");
+      } catch (error: any) {
+        expect(error.message).to.include("TreasuryMismatch");
+        console.log("✓ Foreign treasury rejected");
+      }
+    });
+  });
+
+  // ==========================================================================
+  // 58. CLAIM TO DESTINATION
+  // ==========================================================================
+
+  describe("58. Claim To Destination", () => {
+    let ctx: IsolatedPool;
+    let owner: Staker;
+    let hotWallet: Keypair;
+    let hotWalletAta: PublicKey;
+
+    function claimTo(signer: Keypair, staker: Staker, destination: PublicKey): Promise<string> {
+      return program.methods
+        .claimRewards(null)
+        .accounts({
+          user: signer.publicKey,
+          stakePool: ctx.pool,
+          userStake: staker.userStake,
+          stakingMint: ctx.mint,
+          userTokenAccount: staker.rewardAccount,
+          owner: staker.user.publicKey,
+          rewardMint: ctx.rewardMint,
+          treasuryVault: ctx.treasury,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+          destination,
+        })
+        .signers([signer])
+        .rpc();
+    }
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      await fundPool(ctx, TREASURY_FUND);
+      owner = await createStaker(ctx);
+      await stakeInto(ctx, owner, STAKE_AMOUNT, TIER_FLEX);
+
+      hotWallet = Keypair.generate();
+      hotWalletAta = await createAccount(connection, admin, ctx.rewardMint, hotWallet.publicKey);
+    });
+
+    it("should send rewards to a token account the owner does not own", async () => {
+      await advanceTime(2);
+      const ownerBefore = await tokenBalance(owner.rewardAccount);
+      const hotBefore = await tokenBalance(hotWalletAta);
+
+      await claimTo(owner.user, owner, hotWalletAta);
+
+      const ownerAfter = await tokenBalance(owner.rewardAccount);
+      const received = (await tokenBalance(hotWalletAta)).sub(hotBefore);
+      expect(ownerAfter.toString()).to.equal(ownerBefore.toString());
+      expect(received.gtn(0)).to.equal(true);
+
+      const userStake = await program.account.userStake.fetch(owner.userStake);
+      expect(userStake.totalRewardsClaimed.toString()).to.equal(received.toString());
+      console.log(`✓ ${formatTokens(received)} landed in the hot wallet's ATA`);
+    });
+
+    it("should reject a destination for another mint", async () => {
+      const otherMint = await createMint(connection, admin, admin.publicKey, null, DECIMALS);
+      const wrongMintAccount = await createAccount(connection, admin, otherMint, hotWallet.publicKey);
+      await advanceTime(2);
+      try {
+        await claimTo(owner.user, owner, wrongMintAccount);
+        expect.fail("Should have thrown MintMismatch");
+      } catch (error: any) {
+        expect(error.message).to.include("MintMismatch");
+        console.log("✓ Destination must hold the reward mint");
+      }
+    });
+
+    it("should not let anyone but the owner pick the destination", async () => {
+      const attacker = await createStaker(ctx);
+      try {
+        await claimTo(attacker.user, owner, attacker.rewardAccount);
+        expect.fail("Should have rejected a non-owner signer");
+      } catch (error: any) {
+        expect(error.message).to.match(/ConstraintSeeds|InvalidStakeOwner|UnauthorizedStakeAccess/);
+        console.log("✓ Non-owner cannot redirect the owner's rewards");
+      }
+    });
+
+    it("should keep the self-claim path unchanged", async () => {
+      await advanceTime(2);
+      const hotBefore = await tokenBalance(hotWalletAta);
+      const ownerBefore = await tokenBalance(owner.rewardAccount);
+
+      await claimFrom(ctx, owner);
+
+      expect((await tokenBalance(owner.rewardAccount)).gt(ownerBefore)).to.equal(true);
+      expect((await tokenBalance(hotWalletAta)).toString()).to.equal(hotBefore.toString());
+      console.log("✓ Without a destination, rewards go to the owner's account");
+    });
+  });
+
+  // ==========================================================================
+  // 59. STAKE FOR A BENEFICIARY
+  // ==========================================================================
+
+  describe("59. Stake For", () => {
+    let ctx: IsolatedPool;
+    let dao: Staker;
+    let contributor: Staker;
+
+    function stakeFor(amount: BN, tier: number): Promise<string> {
+      return program.methods
+        .stakeFor(amount, tier, null)
+        .accounts({
+          payer: dao.user.publicKey,
+          beneficiary: contributor.user.publicKey,
+          stakePool: ctx.pool,
+          userStake: contributor.userStake,
+          stakerRecord: stakerRecordPda(ctx.pool, contributor.user.publicKey),
+          stakingMint: ctx.mint,
+          payerTokenAccount: dao.tokenAccount,
+          stakingVault: ctx.vault,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+        })
+        .signers([dao.user])
+        .rpc();
+    }
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      await fundPool(ctx, TREASURY_FUND);
+      dao = await createStaker(ctx);
+      contributor = await createStaker(ctx);
+    });
+
+    it("should open a position owned by the beneficiary with the payer's tokens", async () => {
+      const daoBefore = await tokenBalance(dao.tokenAccount);
+      const contributorBefore = await tokenBalance(contributor.tokenAccount);
+
+      await stakeFor(STAKE_AMOUNT, TIER_FLEX);
+
+      const userStake = await program.account.userStake.fetch(contributor.userStake);
+      expect(userStake.owner.toString()).to.equal(contributor.user.publicKey.toString());
+      expect(userStake.stakedAmount.toString()).to.equal(STAKE_AMOUNT.toString());
+      expect(userStake.referrer.toString()).to.equal(PublicKey.default.toString());
+      expect(daoBefore.sub(await tokenBalance(dao.tokenAccount)).toString()).to.equal(STAKE_AMOUNT.toString());
+      expect((await tokenBalance(contributor.tokenAccount)).toString()).to.equal(contributorBefore.toString());
+      console.log("✓ Position owned by the contributor, funded by the DAO");
+    });
+
+    it("should top up the beneficiary's position in the same tier", async () => {
+      const before = (await program.account.userStake.fetch(contributor.userStake)).stakeStartTime.toNumber();
+      await stakeFor(ONE_TOKEN, TIER_FLEX);
+      const userStake = await program.account.userStake.fetch(contributor.userStake);
+      expect(userStake.stakedAmount.toString()).to.equal(STAKE_AMOUNT.add(ONE_TOKEN).toString());
+      expect(userStake.stakeStartTime.toNumber()).to.equal(before);
+      console.log("✓ Top-up kept the original lock start");
+    });
+
+    it("should reject a stake into a different tier than the open position", async () => {
+      try {
+        await stakeFor(STAKE_AMOUNT, TIER_CORE);
+        expect.fail("Should have thrown CannotChangeTier");
+      } catch (error: any) {
+        expect(error.message).to.include("CannotChangeTier");
+        console.log("✓ Beneficiary's existing tier is enforced");
+      }
+    });
+
+    it("should let only the beneficiary claim and unstake", async () => {
+      await advanceTime(2);
+      try {
+        await program.methods
+          .unstake(ONE_TOKEN)
+          .accounts({
+            user: dao.user.publicKey,
+            stakePool: ctx.pool,
+            userStake: contributor.userStake,
+            stakingMint: ctx.mint,
+            userTokenAccount: dao.tokenAccount,
+            stakingVault: ctx.vault,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+          })
+          .signers([dao.user])
+          .rpc();
+        expect.fail("Payer should not control the position");
+      } catch (error: any) {
+        expect(error.message).to.match(/ConstraintSeeds|InvalidStakeOwner/);
+      }
+
+      const before = await tokenBalance(contributor.rewardAccount);
+      await claimFrom(ctx, contributor);
+      expect((await tokenBalance(contributor.rewardAccount)).gt(before)).to.equal(true);
+
+      await unstakeFrom(ctx, contributor, ONE_TOKEN);
+      const userStake = await program.account.userStake.fetch(contributor.userStake);
+      expect(userStake.stakedAmount.toString()).to.equal(STAKE_AMOUNT.toString());
+      console.log("✓ Beneficiary controls claims and unstakes");
+    });
+
+    it("should carry an exited position's rewards through a reactivating stake", async () => {
+      await advanceTime(2);
+      await unstakeFrom(ctx, contributor, STAKE_AMOUNT);
+      const exited = await program.account.userStake.fetch(contributor.userStake);
+      expect(exited.isActive).to.equal(false);
+      expect(exited.pendingRewards.gtn(0)).to.equal(true);
+
+      await stakeFor(ONE_TOKEN, TIER_FLEX);
+      const reopened = await program.account.userStake.fetch(contributor.userStake);
+      expect(reopened.isActive).to.equal(true);
+      expect(reopened.pendingRewards.toString()).to.equal(exited.pendingRewards.toString());
+      expect(reopened.totalRewardsClaimed.toString()).to.equal(exited.totalRewardsClaimed.toString());
+
+      // The owner's own stake carries them the same way
+      await unstakeFrom(ctx, contributor, ONE_TOKEN);
+      const exitedAgain = await program.account.userStake.fetch(contributor.userStake);
+      await stakeInto(ctx, contributor, ONE_TOKEN, TIER_FLEX);
+      const restaked = await program.account.userStake.fetch(contributor.userStake);
+      expect(restaked.pendingRewards.toString()).to.equal(exitedAgain.pendingRewards.toString());
+
+      const before = await tokenBalance(contributor.rewardAccount);
+      await claimFrom(ctx, contributor);
+      const received = (await tokenBalance(contributor.rewardAccount)).sub(before);
+      expect(received.gte(exitedAgain.pendingRewards)).to.equal(true);
+      console.log(`✓ ${formatTokens(received)} owed rewards survived both reactivations`);
+    });
+  });
+
+  // ==========================================================================
+  // 60. CLAIM DELEGATE
+  // ==========================================================================
+
+  describe("60. Claim Delegate", () => {
+    let ctx: IsolatedPool;
+    let owner: Staker;
+    let bot: Staker;
+
+    function claimAs(signer: Keypair, userTokenAccount: PublicKey, destination: PublicKey | null = null) {
+      return program.methods
+        .claimRewards(null)
+        .accounts({
+          user: signer.publicKey,
+          stakePool: ctx.pool,
+          userStake: owner.userStake,
+          stakingMint: ctx.mint,
+          userTokenAccount,
+          owner: owner.user.publicKey,
+          rewardMint: ctx.rewardMint,
+          treasuryVault: ctx.treasury,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+          destination,
+        })
+        .signers([signer])
+        .rpc();
+    }
+
+    function delegateAccounts() {
+      return { user: owner.user.publicKey, stakePool: ctx.pool, userStake: owner.userStake };
+    }
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      await fundPool(ctx, TREASURY_FUND);
+      owner = await createStaker(ctx);
+      bot = await createStaker(ctx);
+      await stakeInto(ctx, owner, STAKE_AMOUNT, TIER_FLEX);
+    });
+
+    it("should reject a claim from a key that is not a delegate", async () => {
+      await advanceTime(2);
+      try {
+        await claimAs(bot.user, owner.rewardAccount);
+        expect.fail("Should have thrown InvalidStakeOwner");
+      } catch (error: any) {
+        expect(error.message).to.include("InvalidStakeOwner");
+        console.log("✓ Unrelated key cannot claim");
+      }
+    });
+
+    it("should let the delegate claim into the owner's account", async () => {
+      await program.methods
+        .setClaimDelegate(bot.user.publicKey)
+        .accounts(delegateAccounts())
+        .signers([owner.user])
+        .rpc();
+      const userStake = await program.account.userStake.fetch(owner.userStake);
+      expect(userStake.claimDelegate.toString()).to.equal(bot.user.publicKey.toString());
+
+      await advanceTime(2);
+      const ownerBefore = await tokenBalance(owner.rewardAccount);
+      const botBefore = await tokenBalance(bot.rewardAccount);
+      await claimAs(bot.user, owner.rewardAccount);
+
+      expect((await tokenBalance(owner.rewardAccount)).gt(ownerBefore)).to.equal(true);
+      expect((await tokenBalance(bot.rewardAccount)).toString()).to.equal(botBefore.toString());
+      console.log("✓ Delegate claim paid the owner");
+    });
+
+    it("should stop the delegate from redirecting rewards", async () => {
+      await advanceTime(2);
+      try {
+        await claimAs(bot.user, bot.rewardAccount);
+        expect.fail("Should have rejected the delegate's own account");
+      } catch (error: any) {
+        // Not the owner's ATA
+        expect(error.message).to.match(/ConstraintTokenOwner|AccountNotAssociatedTokenAccount/);
+      }
+      try {
+        await claimAs(bot.user, owner.rewardAccount, bot.rewardAccount);
+        expect.fail("Should have thrown DelegateCannotRedirect");
+      } catch (error: any) {
+        expect(error.message).to.include("DelegateCannotRedirect");
+      }
+      console.log("✓ Delegate cannot pay itself");
+    });
+
+    it("should never let the delegate unstake", async () => {
+      try {
+        await program.methods
+          .unstake(ONE_TOKEN)
+          .accounts({
+            user: bot.user.publicKey,
+            stakePool: ctx.pool,
+            userStake: owner.userStake,
+            stakingMint: ctx.mint,
+            userTokenAccount: owner.tokenAccount,
+            stakingVault: ctx.vault,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+          })
+          .signers([bot.user])
+          .rpc();
+        expect.fail("Delegate should not unstake");
+      } catch (error: any) {
+        expect(error.message).to.match(/ConstraintSeeds|InvalidStakeOwner/);
+        console.log("✓ Delegate cannot unstake");
+      }
+    });
+
+    it("should revoke the delegate", async () => {
+      await program.methods
+        .revokeClaimDelegate()
+        .accounts(delegateAccounts())
+        .signers([owner.user])
+        .rpc();
+      const userStake = await program.account.userStake.fetch(owner.userStake);
+      expect(userStake.claimDelegate.toString()).to.equal(PublicKey.default.toString());
+
+      await advanceTime(2);
+      try {
+        await claimAs(bot.user, owner.rewardAccount);
+        expect.fail("Should have thrown InvalidStakeOwner");
+      } catch (error: any) {
+        expect(error.message).to.include("InvalidStakeOwner");
+      }
+
+      // The owner still claims as before
+      await claimFrom(ctx, owner);
+      console.log("✓ Revoked delegate can no longer claim");
+    });
+  });
+
+  // ==========================================================================
+  // 61. PARTIAL CLAIMS
+  // ==========================================================================
+
+  describe("61. Partial Claims", () => {
+    const LIMIT = new BN(100);
+
+    async function claimAndMeasure(ctx: IsolatedPool, staker: Staker) {
+      const startedAt = (await program.account.userStake.fetch(staker.userStake)).lastClaimTime.toNumber();
+      const before = await tokenBalance(staker.rewardAccount);
+      await claimFrom(ctx, staker);
+      const received = (await tokenBalance(staker.rewardAccount)).sub(before);
+      const userStake = await program.account.userStake.fetch(staker.userStake);
+      const accrued = calculateExpectedRewards(
+        STAKE_AMOUNT,
+        FLEX_APY,
+        userStake.lastClaimTime.toNumber() - startedAt
+      );
+      return { received, accrued, pending: userStake.pendingRewards };
+    }
+
+    it("should pay up to the remaining emission cap and defer the rest", async () => {
+      const ctx = await createIsolatedPool(LIMIT);
+      await fundPool(ctx, TREASURY_FUND);
+      const staker = await createStaker(ctx);
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_FLEX);
+      await advanceTime(3);
+
+      const { received, accrued, pending } = await claimAndMeasure(ctx, staker);
+      expect(accrued.gt(LIMIT)).to.equal(true);
+      expect(received.toString()).to.equal(LIMIT.toString());
+      expect(pending.toString()).to.equal(accrued.sub(LIMIT).toString());
+
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.totalDistributed.toString()).to.equal(LIMIT.toString());
+      expect(pool.pendingLiability.toString()).to.equal(pending.toString());
+      console.log(`✓ Paid ${received} under the cap, deferred ${pending}`);
+
+      // Fully blocked: the cap is used up, so nothing is payable
+      try {
+        await claimFrom(ctx, staker);
+        expect.fail("Should have thrown EmissionCapExceeded");
+      } catch (error: any) {
+        expect(error.message).to.include("EmissionCapExceeded");
+        console.log("✓ Claim rejected once the cap is exhausted");
+      }
+    });
+
+    it("should pay what the treasury holds and the rest once refunded", async () => {
+      const ctx = await createIsolatedPool();
+      await fundPool(ctx, LIMIT);
+      const staker = await createStaker(ctx);
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_FLEX);
+      await advanceTime(3);
+
+      const first = await claimAndMeasure(ctx, staker);
+      expect(first.accrued.gt(LIMIT)).to.equal(true);
+      expect(first.received.toString()).to.equal(LIMIT.toString());
+      expect(first.pending.toString()).to.equal(first.accrued.sub(LIMIT).toString());
+      expect((await tokenBalance(ctx.treasury)).toNumber()).to.equal(0);
+
+      try {
+        await claimFrom(ctx, staker);
+        expect.fail("Should have thrown InsufficientTreasuryFunds");
+      } catch (error: any) {
+        expect(error.message).to.include("InsufficientTreasuryFunds");
+      }
+
+      await fundPool(ctx, TREASURY_FUND);
+      const second = await claimAndMeasure(ctx, staker);
+      expect(second.received.gte(first.pending)).to.equal(true);
+      expect(second.pending.toNumber()).to.equal(0);
+      console.log(`✓ Deferred ${first.pending} paid after the treasury was refunded`);
+    });
+  });
+
+  // ==========================================================================
+  // 62. SOLVENCY CHECK
+  // ==========================================================================
+
+  describe("62. Solvency Check", () => {
+    // At the 4% Flex APY this treasury covers exactly STAKE_AMOUNT at 1x
+    const TREASURY = STAKE_AMOUNT.muln(FLEX_APY).divn(BASIS_POINTS);
+
+    function setSolvencyCheck(ctx: IsolatedPool, enabled: boolean, multipleBps: number) {
+      return program.methods
+        .setSolvencyCheck(enabled, multipleBps)
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+    }
+
+    function stakeChecked(ctx: IsolatedPool, staker: Staker, amount: BN) {
+      return program.methods
+        .stake(amount, TIER_FLEX, TOPUP_KEEP_ORIGINAL as any, null, null, null)
+        .accounts({
+          user: staker.user.publicKey,
+          stakePool: ctx.pool,
+          userStake: staker.userStake,
+          stakerRecord: stakerRecordPda(ctx.pool, staker.user.publicKey),
+          stakingMint: ctx.mint,
+          userTokenAccount: staker.tokenAccount,
+          stakingVault: ctx.vault,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+          rent: SYSVAR_RENT_PUBKEY,
+          treasuryVault: ctx.treasury,
+        })
+        .signers([staker.user])
+        .rpc();
+    }
+
+    it("should reject enabling the check with a zero multiple", async () => {
+      const ctx = await createIsolatedPool();
+      try {
+        await setSolvencyCheck(ctx, true, 0);
+        expect.fail("Should have thrown InvalidSolvencyMultiple");
+      } catch (error: any) {
+        expect(error.message).to.include("InvalidSolvencyMultiple");
+        console.log("✓ Zero treasury multiple rejected");
+      }
+    });
+
+    it("should accept a stake up to the treasury limit and reject one token more", async () => {
+      const ctx = await createIsolatedPool();
+      await fundPool(ctx, TREASURY);
+      await setSolvencyCheck(ctx, true, BASIS_POINTS);
+      const staker = await createStaker(ctx);
+
+      await stakeChecked(ctx, staker, STAKE_AMOUNT);
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.tierStaked[TIER_FLEX].toString()).to.equal(STAKE_AMOUNT.toString());
+      console.log(`✓ ${formatTokens(STAKE_AMOUNT)} accepted against ${formatTokens(TREASURY)} treasury`);
+
+      try {
+        await stakeChecked(ctx, staker, ONE_TOKEN);
+        expect.fail("Should have thrown PoolInsolvent");
+      } catch (error: any) {
+        expect(error.message).to.include("PoolInsolvent");
+        console.log("✓ One more token rejected as insolvent");
+      }
+
+      // A larger multiple makes room again
+      await setSolvencyCheck(ctx, true, BASIS_POINTS * 2);
+      await stakeChecked(ctx, staker, ONE_TOKEN);
+      console.log("✓ Accepted after raising the treasury multiple");
+    });
+
+    it("should reject stakes beyond the remaining emission capacity", async () => {
+      const ctx = await createIsolatedPool(TREASURY);
+      await fundPool(ctx, TREASURY_FUND);
+      await setSolvencyCheck(ctx, true, BASIS_POINTS);
+      const staker = await createStaker(ctx);
+
+      await stakeChecked(ctx, staker, STAKE_AMOUNT);
+      try {
+        await stakeChecked(ctx, staker, ONE_TOKEN);
+        expect.fail("Should have thrown PoolInsolvent");
+      } catch (error: any) {
+        expect(error.message).to.include("PoolInsolvent");
+        console.log("✓ Emission cap bounds the projection");
+      }
+    });
+
+    it("should require the treasury account while the check is on", async () => {
+      const ctx = await createIsolatedPool();
+      await fundPool(ctx, TREASURY_FUND);
+      await setSolvencyCheck(ctx, true, BASIS_POINTS);
+      const staker = await createStaker(ctx);
+
+      try {
+        await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_FLEX);
+        expect.fail("Should have thrown MissingTreasuryVault");
+      } catch (error: any) {
+        expect(error.message).to.include("MissingTreasuryVault");
+      }
+
+      await setSolvencyCheck(ctx, false, 0);
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_FLEX);
+      console.log("✓ Treasury account only needed while the check is on");
+    });
+  });
+
+  // ==========================================================================
+  // 63. REWARD-PER-TOKEN MODE
+  // ==========================================================================
+
+  describe("63. Reward-Per-Token Mode", () => {
+    const PRECISION = new BN("1000000000000");
+    const RATE = ONE_TOKEN; // 1 token per second across all stakers
+
+    function setRewardRate(ctx: IsolatedPool, rate: BN) {
+      return program.methods
+        .setRewardRate(rate)
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+    }
+
+    async function rewardPerTokenPool(): Promise<IsolatedPool> {
+      const ctx = await createIsolatedPool(EMISSION_CAP, null, false, REWARD_MODE_REWARD_PER_TOKEN);
+      await fundPool(ctx, TREASURY_FUND);
+      await setRewardRate(ctx, RATE);
+      return ctx;
+    }
+
+    /** Claim and check the payout equals the position's accumulator share */
+    async function claimShare(ctx: IsolatedPool, staker: Staker): Promise<BN> {
+      const before = await program.account.userStake.fetch(staker.userStake);
+      const balanceBefore = await tokenBalance(staker.rewardAccount);
+      await claimFrom(ctx, staker);
+      const after = await program.account.userStake.fetch(staker.userStake);
+      const received = (await tokenBalance(staker.rewardAccount)).sub(balanceBefore);
+
+      const expected = before.stakedAmount
+        .mul(after.rewardPerTokenPaid.sub(before.rewardPerTokenPaid))
+        .div(PRECISION)
+        .add(before.pendingRewards);
+      expect(received.toString()).to.equal(expected.toString());
+      return received;
+    }
+
+    it("should keep APY pools out of reward-per-token settings", async () => {
+      const ctx = await createIsolatedPool();
+      try {
+        await setRewardRate(ctx, RATE);
+        expect.fail("Should have thrown RewardModeMismatch");
+      } catch (error: any) {
+        expect(error.message).to.include("RewardModeMismatch");
+      }
+
+      const staker = await createStaker(ctx);
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_FLEX);
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      const userStake = await program.account.userStake.fetch(staker.userStake);
+      expect(pool.rewardPerTokenStored.toNumber()).to.equal(0);
+      expect(userStake.rewardPerTokenPaid.toNumber()).to.equal(0);
+      console.log("✓ APY pool accounting untouched");
+    });
+
+    it("should start a new position at the current accumulator", async () => {
+      const ctx = await rewardPerTokenPool();
+      const first = await createStaker(ctx);
+      await stakeInto(ctx, first, STAKE_AMOUNT, TIER_FLEX);
+      await advanceTime(3);
+
+      const late = await createStaker(ctx);
+      await stakeInto(ctx, late, STAKE_AMOUNT, TIER_FLEX);
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      const userStake = await program.account.userStake.fetch(late.userStake);
+      expect(pool.rewardPerTokenStored.gtn(0)).to.equal(true);
+      expect(userStake.rewardPerTokenPaid.toString()).to.equal(pool.rewardPerTokenStored.toString());
+      console.log("✓ Late joiner earns nothing for time before its stake");
+    });
+
+    it("should split the rate between stakers joining and leaving at different times", async () => {
+      const ctx = await rewardPerTokenPool();
+      const a = await createStaker(ctx);
+      const b = await createStaker(ctx);
+      const c = await createStaker(ctx);
+
+      await stakeInto(ctx, a, STAKE_AMOUNT, TIER_FLEX);
+      const startedAt = (await program.account.stakePool.fetch(ctx.pool)).lastUpdateTime;
+      await advanceTime(2);
+      await stakeInto(ctx, b, STAKE_AMOUNT.muln(3), TIER_FLEX);
+      await advanceTime(2);
+
+      // A leaves; its accrued share stays claimable
+      await unstakeFrom(ctx, a, STAKE_AMOUNT);
+      await advanceTime(2);
+      await stakeInto(ctx, c, STAKE_AMOUNT, TIER_FLEX);
+      await advanceTime(2);
+
+      let paid = await claimShare(ctx, a);
+      paid = paid.add(await claimShare(ctx, b));
+      paid = paid.add(await claimShare(ctx, c));
+
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      const emitted = RATE.mul(pool.lastUpdateTime.sub(startedAt));
+      expect(paid.lte(emitted)).to.equal(true);
+      expect(pool.totalStaked.toString()).to.equal(STAKE_AMOUNT.muln(4).toString());
+      expect(pool.tierStaked[TIER_FLEX].toString()).to.equal(pool.totalStaked.toString());
+      console.log(`✓ Paid ${formatTokens(paid)} of ${formatTokens(emitted)} emitted`);
+
+      // With A gone, B earns three times what C earns from here on
+      await advanceTime(3);
+      const toB = await claimShare(ctx, b);
+      const toC = await claimShare(ctx, c);
+      expect(toB.gt(toC)).to.equal(true);
+      console.log(`✓ B (3x stake) earned ${formatTokens(toB)}, C earned ${formatTokens(toC)}`);
+    });
+
+    it("should not reprice elapsed time when the rate changes", async () => {
+      const ctx = await rewardPerTokenPool();
+      const staker = await createStaker(ctx);
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_FLEX);
+      await advanceTime(2);
+
+      const before = await program.account.stakePool.fetch(ctx.pool);
+      await setRewardRate(ctx, new BN(0));
+      const after = await program.account.stakePool.fetch(ctx.pool);
+      expect(after.rewardPerTokenStored.gt(before.rewardPerTokenStored)).to.equal(true);
+
+      // Nothing accrues at a zero rate, but the earlier share is still paid
+      await advanceTime(2);
+      const received = await claimShare(ctx, staker);
+      const settled = await program.account.stakePool.fetch(ctx.pool);
+      expect(settled.rewardPerTokenStored.toString()).to.equal(after.rewardPerTokenStored.toString());
+      expect(received.gtn(0)).to.equal(true);
+      console.log(`✓ ${formatTokens(received)} earned before the rate change paid out`);
+    });
+  });
+
+  // ==========================================================================
+  // 64. PAUSE FLAGS
+  // ==========================================================================
+
+  describe("64. Pause Flags", () => {
+    let ctx: IsolatedPool;
+    let staker: Staker;
+
+    function setPauseFlags(flags: number) {
+      return program.methods
+        .setPauseFlags(flags)
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+    }
+
+    function adminCall(method: "setPaused" | "setEmergency" | "clearEmergency", ...args: any[]) {
+      return (program.methods as any)
+        [method](...args)
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+    }
+
+    async function expectError(action: Promise<unknown>, code: string) {
+      try {
+        await action;
+        expect.fail(`Should have thrown ${code}`);
+      } catch (error: any) {
+        expect(error.message).to.include(code);
+      }
+    }
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      await fundPool(ctx, TREASURY_FUND);
+      staker = await createStaker(ctx);
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_FLEX);
+      await advanceTime(2);
+    });
+
+    afterEach(async () => {
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      if (pool.emergency) {
+        await setPauseFlags(0);
+        await adminCall("clearEmergency");
+      } else if (pool.pauseFlags !== 0) {
+        await setPauseFlags(0);
+      }
+    });
+
+    it("should keep set_paused as a stake-only shorthand", async () => {
+      await setPauseFlags(PAUSE_CLAIM);
+      await adminCall("setPaused", true);
+      let pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.pauseFlags).to.equal(PAUSE_STAKE | PAUSE_CLAIM);
+
+      await adminCall("setPaused", false);
+      pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.pauseFlags).to.equal(PAUSE_CLAIM);
+      console.log("✓ set_paused only touches the stake bit");
+    });
+
+    it("should block only stakes under the stake bit", async () => {
+      await setPauseFlags(PAUSE_STAKE);
+      await expectError(stakeInto(ctx, staker, ONE_TOKEN, TIER_FLEX), "StakingPaused");
+      await claimFrom(ctx, staker);
+      await unstakeFrom(ctx, staker, ONE_TOKEN);
+      console.log("✓ Claims and unstakes continue while staking is paused");
+    });
+
+    it("should block claims but not unstakes under the claim bit", async () => {
+      await setPauseFlags(PAUSE_CLAIM);
+      await expectError(claimFrom(ctx, staker), "ClaimsPaused");
+      await unstakeFrom(ctx, staker, ONE_TOKEN);
+      await stakeInto(ctx, staker, ONE_TOKEN, TIER_FLEX);
+      console.log("✓ Treasury drain stopped while principal stays withdrawable");
+    });
+
+    it("should block unstakes but not claims under the unstake bit", async () => {
+      await setPauseFlags(PAUSE_UNSTAKE);
+      await expectError(unstakeFrom(ctx, staker, ONE_TOKEN), "UnstakingPaused");
+      await claimFrom(ctx, staker);
+      console.log("✓ Unstakes paused, claims continue");
+    });
+
+    it("should reject unknown bits", async () => {
+      await expectError(setPauseFlags(1 << 3), "InvalidPauseFlags");
+    });
+
+    it("should never pause claims and unstakes together outside emergency mode", async () => {
+      await expectError(setPauseFlags(PAUSE_CLAIM | PAUSE_UNSTAKE), "CannotPauseAllExits");
+      await expectError(
+        setPauseFlags(PAUSE_STAKE | PAUSE_CLAIM | PAUSE_UNSTAKE),
+        "CannotPauseAllExits"
+      );
+
+      // Emergency mode keeps emergency_withdraw open, so everything may pause
+      await adminCall("setEmergency");
+      await setPauseFlags(PAUSE_STAKE | PAUSE_CLAIM | PAUSE_UNSTAKE);
+      await expectError(adminCall("clearEmergency"), "CannotPauseAllExits");
+      console.log("✓ Full pause only while emergency_withdraw is available");
+    });
+
+    it("should leave emergency_withdraw open under every pause bit", async () => {
+      await adminCall("setEmergency");
+      await setPauseFlags(PAUSE_STAKE | PAUSE_CLAIM | PAUSE_UNSTAKE);
+      const before = await tokenBalance(staker.tokenAccount);
+
+      await program.methods
+        .emergencyWithdraw()
+        .accounts({
+          treasuryVault: ctx.treasury,
+          user: staker.user.publicKey,
+          stakePool: ctx.pool,
+          userStake: staker.userStake,
+          stakingMint: ctx.mint,
+          userTokenAccount: staker.tokenAccount,
+          stakingVault: ctx.vault,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+        })
+        .signers([staker.user])
+        .rpc();
+
+      expect((await tokenBalance(staker.tokenAccount)).gt(before)).to.equal(true);
+      console.log("✓ Principal recovered with every pause bit set");
+    });
+  });
+
+  // ==========================================================================
+  // 65. TOKEN RECOVERY
+  // ==========================================================================
+
+  describe("65. Token Recovery", () => {
+    const DONATION = ONE_TOKEN.muln(50);
+    let ctx: IsolatedPool;
+    let staker: Staker;
+
+    function recoverTokens(source: PublicKey, destination: PublicKey, amount: BN, signer = admin) {
+      return program.methods
+        .recoverTokens(amount)
+        .accounts({
+          authority: signer.publicKey,
+          stakePool: ctx.pool,
+          source,
+          destination,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([signer])
+        .rpc();
+    }
+
+    async function expectError(action: Promise<unknown>, code: string) {
+      try {
+        await action;
+        expect.fail(`Should have thrown ${code}`);
+      } catch (error: any) {
+        expect(error.message).to.include(code);
+      }
+    }
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      await fundPool(ctx, TREASURY_FUND);
+      staker = await createStaker(ctx);
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_FLEX);
+    });
+
+    it("should recover a foreign mint in full", async () => {
+      const strayMint = await createMint(connection, admin, admin.publicKey, null, 6);
+      const strayAccount = await createAccount(connection, admin, strayMint, ctx.pool, Keypair.generate());
+      const adminStray = await createAccount(connection, admin, strayMint, admin.publicKey);
+      await mintTo(connection, admin, strayMint, strayAccount, admin, BigInt(DONATION.toString()));
+
+      await expectError(recoverTokens(strayAccount, adminStray, DONATION, nonAdmin), "Unauthorized");
+      await recoverTokens(strayAccount, adminStray, DONATION);
+      expect((await tokenBalance(adminStray)).toString()).to.equal(DONATION.toString());
+      expect((await tokenBalance(strayAccount)).toNumber()).to.equal(0);
+      console.log(`✓ Recovered ${formatTokens(DONATION)} of a foreign mint`);
+    });
+
+    it("should never release treasury funds", async () => {
+      await expectError(recoverTokens(ctx.treasury, ctx.adminTokenAccount, ONE_TOKEN), "RecoveryNotAllowed");
+      console.log("✓ Treasury left to withdraw_treasury");
+    });
+
+    it("should only release staking-mint tokens above principal", async () => {
+      await expectError(recoverTokens(ctx.vault, ctx.adminTokenAccount, ONE_TOKEN), "RecoveryExceedsSurplus");
+
+      await mintTo(connection, admin, ctx.mint, ctx.vault, admin, BigInt(DONATION.toString()));
+      await expectError(
+        recoverTokens(ctx.vault, ctx.adminTokenAccount, DONATION.addn(1)),
+        "RecoveryExceedsSurplus"
+      );
+      await recoverTokens(ctx.vault, ctx.adminTokenAccount, DONATION);
+
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      expect((await tokenBalance(ctx.vault)).toString()).to.equal(pool.totalStaked.toString());
+      console.log("✓ Donation recovered, vault back at total_staked");
+    });
+
+    it("should keep queued withdrawals in the vault", async () => {
+      const queued = STAKE_AMOUNT.divn(4);
+      await program.methods
+        .requestUnstake(queued)
+        .accounts({ user: staker.user.publicKey, stakePool: ctx.pool, userStake: staker.userStake })
+        .signers([staker.user])
+        .rpc();
+
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.totalUnstakeRequested.toString()).to.equal(queued.toString());
+      expect(pool.totalStaked.toString()).to.equal(STAKE_AMOUNT.sub(queued).toString());
+      await expectError(recoverTokens(ctx.vault, ctx.adminTokenAccount, ONE_TOKEN), "RecoveryExceedsSurplus");
+
+      await program.methods
+        .cancelUnstakeRequest()
+        .accounts({ user: staker.user.publicKey, stakePool: ctx.pool, userStake: staker.userStake })
+        .signers([staker.user])
+        .rpc();
+      const cancelled = await program.account.stakePool.fetch(ctx.pool);
+      expect(cancelled.totalUnstakeRequested.toNumber()).to.equal(0);
+      console.log("✓ Queued principal counted as owed");
+    });
+
+    it("should leave stakers able to withdraw everything", async () => {
+      const before = await tokenBalance(staker.tokenAccount);
+      await unstakeFrom(ctx, staker, STAKE_AMOUNT);
+      const received = (await tokenBalance(staker.tokenAccount)).sub(before);
+      expect(received.toString()).to.equal(STAKE_AMOUNT.toString());
+      console.log(`✓ Staker withdrew the full ${formatTokens(STAKE_AMOUNT)}`);
+    });
+  });
+
+  // ==========================================================================
+  // 66. ADMIN MULTISIG
+  // ==========================================================================
+
+  describe("66. Admin Multisig", () => {
+    let ctx: IsolatedPool;
+    let signers: Keypair[];
+    let outsider: Keypair;
+    let multisig: PublicKey;
+
+    function multisigPda(pool: PublicKey): PublicKey {
+      return PublicKey.findProgramAddressSync(
+        [Buffer.from("admin_multisig"), pool.toBuffer()],
+        program.programId
+      )[0];
+    }
+
+    function proposalPda(multisigKey: PublicKey, nonce: BN): PublicKey {
+      return PublicKey.findProgramAddressSync(
+        [Buffer.from("admin_proposal"), multisigKey.toBuffer(), nonce.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      )[0];
+    }
+
+    function createMultisig(pool: PublicKey, keys: PublicKey[], threshold: number, ttl: number) {
+      return program.methods
+        .createAdminMultisig(keys, threshold, new BN(ttl))
+        .accounts({
+          authority: admin.publicKey,
+          stakePool: pool,
+          adminMultisig: multisigPda(pool),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+    }
+
+    async function propose(pool: PublicKey, proposer: Keypair, action: object): Promise<PublicKey> {
+      const multisigKey = multisigPda(pool);
+      const state = await program.account.adminMultisig.fetch(multisigKey);
+      const proposal = proposalPda(multisigKey, state.proposalCount);
+      await program.methods
+        .proposeAdminAction(action as any)
+        .accounts({
+          proposer: proposer.publicKey,
+          stakePool: pool,
+          adminMultisig: multisigKey,
+          proposal,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([proposer])
+        .rpc();
+      return proposal;
+    }
+
+    function approve(pool: PublicKey, proposal: PublicKey, approver: Keypair) {
+      return program.methods
+        .approveAdminAction()
+        .accounts({
+          approver: approver.publicKey,
+          stakePool: pool,
+          adminMultisig: multisigPda(pool),
+          proposal,
+        })
+        .signers([approver])
+        .rpc();
+    }
+
+    async function expectError(action: Promise<unknown>, code: string) {
+      try {
+        await action;
+        expect.fail(`Should have thrown ${code}`);
+      } catch (error: any) {
+        expect(error.message).to.include(code);
+      }
+    }
+
+    before(async () => {
+      signers = [Keypair.generate(), Keypair.generate(), Keypair.generate()];
+      outsider = Keypair.generate();
+      for (const key of [...signers, outsider]) {
+        await airdropSol(key.publicKey);
+      }
+      ctx = await createIsolatedPool();
+      multisig = multisigPda(ctx.pool);
+    });
+
+    it("should reject invalid signer sets", async () => {
+      const keys = signers.map((s) => s.publicKey);
+      await expectError(createMultisig(ctx.pool, keys, 4, 3600), "InvalidMultisigConfig");
+      await expectError(createMultisig(ctx.pool, keys, 0, 3600), "InvalidMultisigConfig");
+      await expectError(createMultisig(ctx.pool, [keys[0], keys[0]], 1, 3600), "InvalidMultisigConfig");
+      console.log("✓ Bad threshold and duplicate signers rejected");
+    });
+
+    it("should move sensitive actions off the single key", async () => {
+      await createMultisig(ctx.pool, signers.map((s) => s.publicKey), 2, 3600);
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.adminMultisig.toBase58()).to.equal(multisig.toBase58());
+
+      await expectError(
+        program.methods
+          .updateEmissionCap(EMISSION_CAP.muln(2))
+          .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+          .signers([admin])
+          .rpc(),
+        "MultisigRequired"
+      );
+      await expectError(
+        program.methods
+          .setPaused(true, null)
+          .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+          .signers([admin])
+          .rpc(),
+        "MultisigRequired"
+      );
+      console.log("✓ Single-key cap and pause changes rejected");
+    });
+
+    it("should keep emergency, tier pauses, Permanent APY and cancels off the single key", async () => {
+      const adminCall = (method: string, ...args: any[]) =>
+        (program.methods as any)
+          [method](...args)
+          .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+          .signers([admin])
+          .rpc();
+      await expectError(adminCall("setEmergency"), "MultisigRequired");
+      await expectError(adminCall("clearEmergency"), "MultisigRequired");
+      await expectError(adminCall("setTierPaused", 1, true), "MultisigRequired");
+      await expectError(adminCall("setPermanentApy", 0), "MultisigRequired");
+      await expectError(adminCall("cancelPendingParams"), "MultisigRequired");
+      await expectError(
+        adminCall("setRewardsEndTime", new BN(Math.floor(Date.now() / 1000) + 86_400)),
+        "MultisigRequired"
+      );
+
+      // The multisig can still pause a tier
+      const proposal = await propose(ctx.pool, signers[0], {
+        setTierPaused: { tier: 1, paused: true },
+      });
+      await approve(ctx.pool, proposal, signers[0]);
+      await approve(ctx.pool, proposal, signers[1]);
+      let pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.corePaused).to.equal(true);
+      expect(pool.emergency).to.equal(false);
+
+      const resume = await propose(ctx.pool, signers[1], {
+        setTierPaused: { tier: 1, paused: false },
+      });
+      await approve(ctx.pool, resume, signers[1]);
+      await approve(ctx.pool, resume, signers[2]);
+      pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.corePaused).to.equal(false);
+      console.log("✓ Emergency, tier pause, Permanent APY and cancel need the multisig");
+    });
+
+    it("should execute on the threshold approval in any order", async () => {
+      const newCap = EMISSION_CAP.muln(2);
+      const proposal = await propose(ctx.pool, signers[0], { updateCap: { newCap } });
+
+      // Proposing is not approving, and outsiders cannot approve
+      await expectError(approve(ctx.pool, proposal, outsider), "NotMultisigSigner");
+      await approve(ctx.pool, proposal, signers[2]);
+      await expectError(approve(ctx.pool, proposal, signers[2]), "AlreadyApproved");
+      let pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.emissionCap.toString()).to.equal(EMISSION_CAP.toString());
+
+      await approve(ctx.pool, proposal, signers[1]);
+      pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.emissionCap.toString()).to.equal(newCap.toString());
+      const state = await program.account.adminProposal.fetch(proposal);
+      expect(state.executed).to.equal(true);
+      console.log("✓ Cap updated on the second approval (signers 3 then 2)");
+    });
+
+    it("should never replay an executed proposal", async () => {
+      const proposal = await propose(ctx.pool, signers[1], { setPaused: { flags: PAUSE_STAKE } });
+      await approve(ctx.pool, proposal, signers[0]);
+      await approve(ctx.pool, proposal, signers[1]);
+      let pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.pauseFlags).to.equal(PAUSE_STAKE);
+
+      // Resume through a fresh proposal, then try to re-run the old one
+      const resume = await propose(ctx.pool, signers[2], { setPaused: { flags: 0 } });
+      await approve(ctx.pool, resume, signers[2]);
+      await approve(ctx.pool, resume, signers[0]);
+      await expectError(approve(ctx.pool, proposal, signers[2]), "ProposalAlreadyExecuted");
+      pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.pauseFlags).to.equal(0);
+
+      const state = await program.account.adminMultisig.fetch(multisig);
+      expect(state.proposalCount.toNumber()).to.equal(5);
+      console.log("✓ Executed proposal stays executed; nonces never reused");
+    });
+
+    it("should route decay, reward rate and boosts through proposals", async () => {
+      await expectError(
+        program.methods
+          .setApyDecay(new BN(3600), 100, 0)
+          .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+          .signers([admin])
+          .rpc(),
+        "MultisigRequired"
+      );
+      await expectError(
+        program.methods
+          .setApyBoost(20_000, new BN(0), new BN(0))
+          .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+          .signers([admin])
+          .rpc(),
+        "MultisigRequired"
+      );
+
+      const proposal = await propose(ctx.pool, signers[1], {
+        setApyDecay: { intervalSecs: new BN(3600), bpsPerInterval: 100, minApyFloor: 0 },
+      });
+      await approve(ctx.pool, proposal, signers[1]);
+      await approve(ctx.pool, proposal, signers[2]);
+
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.decayIntervalSeconds.toNumber()).to.equal(3600);
+      expect(pool.decayBpsPerInterval).to.equal(100);
+      console.log("✓ Decay schedule set by the multisig, not the single key");
+    });
+
+    it("should hand authority over only after the new key accepts", async () => {
+      const newAuthority = Keypair.generate();
+      const proposal = await propose(ctx.pool, signers[0], {
+        transferAuthority: { newAuthority: newAuthority.publicKey },
+      });
+      await approve(ctx.pool, proposal, signers[0]);
+      await approve(ctx.pool, proposal, signers[1]);
+
+      let pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.authority.toBase58()).to.equal(admin.publicKey.toBase58());
+      expect(pool.pendingAuthority.toBase58()).to.equal(newAuthority.publicKey.toBase58());
+
+      await program.methods
+        .acceptAuthority()
+        .accounts({ newAuthority: newAuthority.publicKey, stakePool: ctx.pool })
+        .signers([newAuthority])
+        .rpc();
+      pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.authority.toBase58()).to.equal(newAuthority.publicKey.toBase58());
+      console.log("✓ Authority transferred through the multisig");
+    });
+
+    it("should reject approvals on expired proposals", async () => {
+      const expiring = await createIsolatedPool();
+      await createMultisig(expiring.pool, signers.map((s) => s.publicKey), 2, 1);
+      const proposal = await propose(expiring.pool, signers[0], {
+        adjustApy: { flexApy: FLEX_APY, coreApy: CORE_APY, primeApy: PRIME_APY },
+      });
+      await new Promise((resolve) => setTimeout(resolve, 2000));
+      await expectError(approve(expiring.pool, proposal, signers[0]), "ProposalExpired");
+      console.log("✓ Stale proposal can no longer be approved");
+    });
+  });
+
+  // ==========================================================================
+  // 67. PARAMETER TIMELOCK
+  // ==========================================================================
+
+  describe("67. Parameter Timelock", () => {
+    const TIMELOCK_SECS = 2;
+    let ctx: IsolatedPool;
+
+    function adminCall(method: string, ...args: any[]) {
+      return (program.methods as any)
+        [method](...args)
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+    }
+
+    function applyPendingParams() {
+      return program.methods.applyPendingParams().accounts({ stakePool: ctx.pool }).rpc();
+    }
+
+    async function expectError(action: Promise<unknown>, code: string) {
+      try {
+        await action;
+        expect.fail(`Should have thrown ${code}`);
+      } catch (error: any) {
+        expect(error.message).to.include(code);
+      }
+    }
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      await adminCall("setParamTimelock", new BN(TIMELOCK_SECS));
+    });
+
+    it("should queue an APY cut instead of applying it", async () => {
+      await adminCall("adjustApy", FLEX_APY / 2, CORE_APY / 2, PRIME_APY / 2);
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.flexApy).to.equal(FLEX_APY);
+      expect(pool.primeApy).to.equal(PRIME_APY);
+      expect(pool.pendingApy).to.deep.equal([FLEX_APY / 2, CORE_APY / 2, PRIME_APY / 2]);
+      expect(pool.pendingApyEffectiveAt.toNumber()).to.be.greaterThan(0);
+
+      await expectError(applyPendingParams(), "ParamTimelockActive");
+      console.log(`✓ Cut visible on-chain until ${pool.pendingApyEffectiveAt.toNumber()}`);
+    });
+
+    it("should let the admin cancel before the timelock expires", async () => {
+      await adminCall("updateEmissionCap", EMISSION_CAP.divn(2));
+      await adminCall("cancelPendingParams");
+
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.pendingApyEffectiveAt.toNumber()).to.equal(0);
+      expect(pool.pendingEmissionCapEffectiveAt.toNumber()).to.equal(0);
+      expect(pool.emissionCap.toString()).to.equal(EMISSION_CAP.toString());
+
+      await new Promise((resolve) => setTimeout(resolve, (TIMELOCK_SECS + 1) * 1000));
+      await expectError(applyPendingParams(), "NoPendingChange");
+      await expectError(adminCall("cancelPendingParams"), "NoPendingChange");
+      console.log("✓ Cancelled changes never apply");
+    });
+
+    it("should apply queued changes permissionlessly once due", async () => {
+      const newCap = EMISSION_CAP.divn(2);
+      await adminCall("adjustApy", FLEX_APY / 2, CORE_APY / 2, PRIME_APY / 2);
+      await adminCall("updateEmissionCap", newCap);
+      await new Promise((resolve) => setTimeout(resolve, (TIMELOCK_SECS + 1) * 1000));
+
+      await applyPendingParams();
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.flexApy).to.equal(FLEX_APY / 2);
+      expect(pool.coreApy).to.equal(CORE_APY / 2);
+      expect(pool.primeApy).to.equal(PRIME_APY / 2);
+      expect(pool.emissionCap.toString()).to.equal(newCap.toString());
+      expect(pool.pendingApyEffectiveAt.toNumber()).to.equal(0);
+      expect(pool.pendingEmissionCapEffectiveAt.toNumber()).to.equal(0);
+      console.log("✓ APY and cap applied by a non-admin crank");
+    });
+
+    it("should queue a decay schedule behind the timelock", async () => {
+      await adminCall("setApyDecay", new BN(3600), 100, 0);
+      let pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.decayIntervalSeconds.toNumber()).to.equal(0);
+      expect(pool.pendingDecayIntervalSeconds.toNumber()).to.equal(3600);
+      expect(pool.pendingDecayBpsPerInterval).to.equal(100);
+      await expectError(applyPendingParams(), "ParamTimelockActive");
+
+      await new Promise((resolve) => setTimeout(resolve, (TIMELOCK_SECS + 1) * 1000));
+      await applyPendingParams();
+      pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.decayIntervalSeconds.toNumber()).to.equal(3600);
+      expect(pool.decayBpsPerInterval).to.equal(100);
+      expect(pool.pendingDecayEffectiveAt.toNumber()).to.equal(0);
+      console.log("✓ Decay applied only after the timelock");
+    });
+
+    it("should queue a Permanent APY cut behind the timelock", async () => {
+      const wait = () => new Promise((resolve) => setTimeout(resolve, (TIMELOCK_SECS + 1) * 1000));
+      await adminCall("setPermanentApy", 1_000);
+      let pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.permanentApy).to.equal(0);
+      expect(pool.pendingPermanentApy).to.equal(1_000);
+      await wait();
+      await applyPendingParams();
+
+      await adminCall("setPermanentApy", 500);
+      pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.permanentApy).to.equal(1_000);
+      await expectError(applyPendingParams(), "ParamTimelockActive");
+
+      await wait();
+      await applyPendingParams();
+      pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.permanentApy).to.equal(500);
+      expect(pool.pendingPermanentApyEffectiveAt.toNumber()).to.equal(0);
+      console.log("✓ Permanent APY applied only after the timelock");
+    });
+
+    it("should queue a rewards end time behind the timelock", async () => {
+      const now = Math.floor(Date.now() / 1000);
+      // An end inside the timelock could never be applied
+      await expectError(
+        adminCall("setRewardsEndTime", new BN(now + 1)),
+        "InvalidRewardsEndTime"
+      );
+
+      const endTime = now + 86_400;
+      await adminCall("setRewardsEndTime", new BN(endTime));
+      let pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.rewardsEndTime.toNumber()).to.equal(0);
+      expect(pool.pendingRewardsEndTime.toNumber()).to.equal(endTime);
+      await expectError(applyPendingParams(), "ParamTimelockActive");
+
+      await new Promise((resolve) => setTimeout(resolve, (TIMELOCK_SECS + 1) * 1000));
+      await applyPendingParams();
+      pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.rewardsEndTime.toNumber()).to.equal(endTime);
+      expect(pool.pendingRewardsEndTimeEffectiveAt.toNumber()).to.equal(0);
+      console.log("✓ End time applied only after the timelock");
+    });
+
+    it("should keep pausing immediate", async () => {
+      await adminCall("setPaused", true);
+      let pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.pauseFlags & PAUSE_STAKE).to.equal(PAUSE_STAKE);
+
+      await adminCall("setPaused", false);
+      pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.pauseFlags).to.equal(0);
+      console.log("✓ Pause unaffected by the timelock");
+    });
+  });
+
+  // ==========================================================================
+  // 68. FLASH STAKE GUARDS
+  // ==========================================================================
+
+  describe("68. Flash Stake Guards", () => {
+    const MIN_ACCRUAL_SECS = 30;
+    const MIN_DURATION_SECS = 2;
+
+    function setMinDurations(ctx: IsolatedPool, minAccrual: number, minDuration: number) {
+      return program.methods
+        .setMinDurations(new BN(minAccrual), new BN(minDuration))
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+    }
+
+    it("should reject out-of-range settings", async () => {
+      const ctx = await createIsolatedPool();
+      try {
+        await setMinDurations(ctx, -1, 0);
+        expect.fail("Should have thrown InvalidMinDuration");
+      } catch (error: any) {
+        expect(error.message).to.include("InvalidMinDuration");
+        console.log("✓ Negative minimum rejected");
+      }
+    });
+
+    it("should pay nothing for a flash stake", async () => {
+      const ctx = await createIsolatedPool();
+      await fundPool(ctx, TREASURY_FUND);
+      await setMinDurations(ctx, MIN_ACCRUAL_SECS, 0);
+      const staker = await createStaker(ctx);
+
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_FLEX);
+      await advanceTime(2);
+      const before = await tokenBalance(staker.rewardAccount);
+      try {
+        await claimFrom(ctx, staker);
+      } catch {
+        // Nothing to claim is also acceptable
+      }
+      const received = (await tokenBalance(staker.rewardAccount)).sub(before);
+      expect(received.toNumber()).to.equal(0);
+      console.log("✓ Interval below min_accrual_seconds earned nothing");
+    });
+
+    it("should hold every tier until the minimum duration after a deposit", async () => {
+      const ctx = await createIsolatedPool();
+      await setMinDurations(ctx, 0, MIN_DURATION_SECS);
+      const staker = await createStaker(ctx);
+
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_FLEX);
+      const userStake = await program.account.userStake.fetch(staker.userStake);
+      expect(userStake.lastStakeTime.toNumber()).to.be.greaterThan(0);
+
+      try {
+        await unstakeFrom(ctx, staker, ONE_TOKEN);
+        expect.fail("Should have thrown StakeTooRecent");
+      } catch (error: any) {
+        expect(error.message).to.include("StakeTooRecent");
+      }
+
+      await new Promise((resolve) => setTimeout(resolve, (MIN_DURATION_SECS + 1) * 1000));
+      await unstakeFrom(ctx, staker, ONE_TOKEN);
+      console.log("✓ Flex unstake allowed once the hold time passed");
+    });
+
+    it("should restart the hold on a top-up", async () => {
+      const ctx = await createIsolatedPool();
+      await setMinDurations(ctx, 0, MIN_DURATION_SECS);
+      const staker = await createStaker(ctx);
+
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_FLEX);
+      await new Promise((resolve) => setTimeout(resolve, (MIN_DURATION_SECS + 1) * 1000));
+      await stakeInto(ctx, staker, ONE_TOKEN, TIER_FLEX);
+
+      try {
+        await unstakeFrom(ctx, staker, ONE_TOKEN);
+        expect.fail("Should have thrown StakeTooRecent");
+      } catch (error: any) {
+        expect(error.message).to.include("StakeTooRecent");
+        console.log("✓ Top-up restarted the hold time");
+      }
+    });
+  });
+
+  // ==========================================================================
+  // 69. ACCOUNT VERSIONING
+  // ==========================================================================
+
+  describe("69. Account Versioning", () => {
+    const STAKE_POOL_VERSION = 29;
+    const USER_STAKE_VERSION = 9;
+    let ctx: IsolatedPool;
+    let staker: Staker;
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      staker = await createStaker(ctx);
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_FLEX);
+    });
+
+    it("should stamp new accounts with the current version", async () => {
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      const userStake = await program.account.userStake.fetch(staker.userStake);
+      expect(pool.version).to.equal(STAKE_POOL_VERSION);
+      expect(userStake.version).to.equal(USER_STAKE_VERSION);
+      console.log("✓ Pool and position created at the current versions");
+    });
+
+    it("should leave current accounts untouched", async () => {
+      const before = await connection.getAccountInfo(ctx.pool);
+      await program.methods
+        .migrateStakePool()
+        .accounts({ payer: admin.publicKey, stakePool: ctx.pool, systemProgram: SystemProgram.programId })
+        .signers([admin])
+        .rpc();
+      await program.methods
+        .migrateUserStake()
+        .accounts({ payer: admin.publicKey, userStake: staker.userStake, systemProgram: SystemProgram.programId })
+        .signers([admin])
+        .rpc();
+
+      const after = await connection.getAccountInfo(ctx.pool);
+      expect(after!.data.length).to.equal(before!.data.length);
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.version).to.equal(STAKE_POOL_VERSION);
+      console.log("✓ Migration of a current account is a no-op");
+    });
+
+    it("should refuse accounts of another type or program", async () => {
+      for (const account of [staker.userStake, ctx.vault]) {
+        try {
+          await program.methods
+            .migrateStakePool()
+            .accounts({ payer: admin.publicKey, stakePool: account, systemProgram: SystemProgram.programId })
+            .signers([admin])
+            .rpc();
+          expect.fail("Should have thrown AccountNotMigratable");
+        } catch (error: any) {
+          expect(error.message).to.include("AccountNotMigratable");
+        }
+      }
+      console.log("✓ Position and token account rejected as pools");
+    });
+  });
+
+  // ============================================
+  // 70. LOYALTY MULTIPLIER
+  // ============================================
+
+  describe("70. Loyalty Multiplier", () => {
+    function setLoyaltySchedule(ctx: IsolatedPool, thresholds: number[], bonuses: number[]) {
+      return program.methods
+        .setLoyaltySchedule(
+          thresholds.map((secs) => new BN(secs)),
+          bonuses
+        )
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+    }
+
+    it("should store a valid schedule and pad unused levels", async () => {
+      const ctx = await createIsolatedPool();
+      await setLoyaltySchedule(ctx, [15_552_000, 31_536_000], [100, 200]);
+
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.loyaltyThresholdSecs.map((secs: BN) => secs.toNumber())).to.deep.equal([
+        15_552_000, 31_536_000, 0, 0,
+      ]);
+      expect(pool.loyaltyBonusBps).to.deep.equal([100, 200, 0, 0]);
+
+      await setLoyaltySchedule(ctx, [], []);
+      const cleared = await program.account.stakePool.fetch(ctx.pool);
+      expect(cleared.loyaltyBonusBps).to.deep.equal([0, 0, 0, 0]);
+      console.log("✓ Schedule stored, then disabled");
+    });
+
+    it("should reject schedules that are not strictly increasing", async () => {
+      const ctx = await createIsolatedPool();
+      const invalid: [number[], number[]][] = [
+        [[100, 100], [100, 200]],
+        [[100, 200], [200, 100]],
+        [[0], [100]],
+        [[100], [100, 200]],
+      ];
+      for (const [thresholds, bonuses] of invalid) {
+        try {
+          await setLoyaltySchedule(ctx, thresholds, bonuses);
+          expect.fail("Should have thrown InvalidLoyaltySchedule");
+        } catch (error: any) {
+          expect(error.message).to.include("InvalidLoyaltySchedule");
+        }
+      }
+      console.log("✓ Malformed schedules rejected");
+    });
+
+    it("should keep the run through top-ups and restart it after a full exit", async () => {
+      const ctx = await createIsolatedPool();
+      const staker = await createStaker(ctx);
+
+      await stakeInto(ctx, staker, STAKE_AMOUNT.div(new BN(2)), TIER_FLEX);
+      const opened = (await program.account.userStake.fetch(staker.userStake)).loyaltyStartTime.toNumber();
+      expect(opened).to.be.greaterThan(0);
+
+      await new Promise((resolve) => setTimeout(resolve, 2000));
+      await stakeInto(ctx, staker, STAKE_AMOUNT.div(new BN(2)), TIER_FLEX);
+      const toppedUp = await program.account.userStake.fetch(staker.userStake);
+      expect(toppedUp.loyaltyStartTime.toNumber()).to.equal(opened);
+
+      await unstakeFrom(ctx, staker, toppedUp.stakedAmount);
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_FLEX);
+      const reopened = await program.account.userStake.fetch(staker.userStake);
+      expect(reopened.loyaltyStartTime.toNumber()).to.be.greaterThan(opened);
+      console.log("✓ Top-up kept the run; full exit restarted it");
+    });
+  });
+
+  // ============================================
+  // 71. FUNDING TOTALS
+  // ============================================
+
+  describe("71. Funding Totals", () => {
+    let ctx: IsolatedPool;
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+    });
+
+    it("should record instruction deposits in total_funded", async () => {
+      await fundPool(ctx, TREASURY_FUND);
+      await fundPool(ctx, ONE_TOKEN);
+
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.totalFunded.toString()).to.equal(TREASURY_FUND.add(ONE_TOKEN).toString());
+      console.log(`✓ total_funded: ${formatTokens(pool.totalFunded)}`);
+    });
+
+    it("should ignore direct transfers to the treasury", async () => {
+      const before = await program.account.stakePool.fetch(ctx.pool);
+      const balanceBefore = await tokenBalance(ctx.treasury);
+
+      await transfer(
+        connection, admin, ctx.adminRewardAccount, ctx.treasury, admin,
+        BigInt(ONE_TOKEN.toString())
+      );
+
+      const after = await program.account.stakePool.fetch(ctx.pool);
+      expect(after.totalFunded.toString()).to.equal(before.totalFunded.toString());
+      expect((await tokenBalance(ctx.treasury)).sub(balanceBefore).toString()).to.equal(ONE_TOKEN.toString());
+      console.log("✓ Direct transfer raised the balance but not total_funded");
+    });
+
+    it("should stay consistent after payouts", async () => {
+      const staker = await createStaker(ctx);
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_FLEX);
+      await advanceTime(2);
+      await claimFrom(ctx, staker);
+
+      await program.methods
+        .refreshPool()
+        .accounts({ stakePool: ctx.pool, treasuryVault: ctx.treasury })
+        .rpc();
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.totalDistributed.gt(new BN(0))).to.be.true;
+      expect(pool.totalFunded.gte(pool.totalDistributed)).to.be.true;
+      console.log("✓ total_funded covers total_distributed");
+    });
+  });
+
+  // ============================================
+  // 72. EPOCH EMISSION LIMIT
+  // ============================================
+
+  describe("72. Epoch Emission Limit", () => {
+    const EPOCH_SECS = 3;
+    const EPOCH_BUDGET = new BN(10);
+
+    function setEpochEmissionLimit(ctx: IsolatedPool, maxEmission: BN, epochSecs: number) {
+      return program.methods
+        .setEpochEmissionLimit(maxEmission, new BN(epochSecs))
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+    }
+
+    it("should reject an enabled limit without a valid epoch", async () => {
+      const ctx = await createIsolatedPool();
+      try {
+        await setEpochEmissionLimit(ctx, EPOCH_BUDGET, 0);
+        expect.fail("Should have thrown InvalidEpochEmissionLimit");
+      } catch (error: any) {
+        expect(error.message).to.include("InvalidEpochEmissionLimit");
+        console.log("✓ Zero-length epoch rejected");
+      }
+    });
+
+    it("should fill claims up to the budget and refill in the next epoch", async () => {
+      const ctx = await createIsolatedPool();
+      await fundPool(ctx, TREASURY_FUND);
+      await setEpochEmissionLimit(ctx, EPOCH_BUDGET, EPOCH_SECS);
+      const staker = await createStaker(ctx);
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_FLEX);
+      await advanceTime(2);
+
+      // Accrued rewards exceed the budget: partial fill, rest deferred
+      let before = await tokenBalance(staker.rewardAccount);
+      await claimFrom(ctx, staker);
+      let received = (await tokenBalance(staker.rewardAccount)).sub(before);
+      expect(received.toString()).to.equal(EPOCH_BUDGET.toString());
+      const userStake = await program.account.userStake.fetch(staker.userStake);
+      expect(userStake.pendingRewards.gt(new BN(0))).to.be.true;
+      console.log(`✓ Paid ${received} and deferred ${userStake.pendingRewards}`);
+
+      try {
+        await claimFrom(ctx, staker);
+        expect.fail("Should have thrown EpochEmissionExceeded");
+      } catch (error: any) {
+        expect(error.message).to.include("EpochEmissionExceeded");
+      }
+      console.log("✓ Spent budget blocks further claims");
+
+      await new Promise((resolve) => setTimeout(resolve, (EPOCH_SECS + 1) * 1000));
+      before = await tokenBalance(staker.rewardAccount);
+      await claimFrom(ctx, staker);
+      received = (await tokenBalance(staker.rewardAccount)).sub(before);
+      expect(received.toString()).to.equal(EPOCH_BUDGET.toString());
+
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.distributedThisEpoch.toString()).to.equal(EPOCH_BUDGET.toString());
+      console.log("✓ Budget refilled after the epoch boundary");
+    });
+  });
+
+  // ============================================
+  // 73. TIER STATISTICS
+  // ============================================
+
+  describe("73. Tier Statistics", () => {
+    it("should match the pool counters to the sum of positions", async () => {
+      const ctx = await createIsolatedPool();
+      const stakers: Staker[] = [];
+      for (const tier of [TIER_FLEX, TIER_FLEX, TIER_CORE, TIER_PRIME]) {
+        const staker = await createStaker(ctx);
+        await stakeInto(ctx, staker, STAKE_AMOUNT, tier);
+        stakers.push(staker);
+      }
+
+      // A full Flex exit followed by a re-stake into Core
+      await unstakeFrom(ctx, stakers[0], STAKE_AMOUNT);
+      await stakeInto(ctx, stakers[0], STAKE_AMOUNT, TIER_CORE);
+
+      const positions = await program.account.userStake.all([
+        { memcmp: { offset: 8 + 32, bytes: ctx.pool.toBase58() } },
+      ]);
+      const staked = [0, 1, 2, 3].map(() => new BN(0));
+      const counts = [0, 0, 0, 0];
+      for (const { account } of positions) {
+        if (!account.isActive) continue;
+        const tier = TIER_VARIANTS.indexOf(Object.keys(account.tier)[0]);
+        staked[tier] = staked[tier].add(account.stakedAmount);
+        counts[tier] += 1;
+      }
+
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.tierStaked.map((amount: BN) => amount.toString())).to.deep.equal(
+        staked.map((amount) => amount.toString())
+      );
+      expect(pool.tierStakerCount.map((count: BN) => count.toNumber())).to.deep.equal(counts);
+      expect(counts).to.deep.equal([1, 2, 1, 0]);
+      expect(pool.stakerCount.toNumber()).to.equal(4);
+      console.log(`✓ Per-tier counts ${counts.join("/")} match the positions`);
+    });
+  });
+
+  // ============================================
+  // 74. TOP-UP LOCK WEIGHTING
+  // ============================================
+
+  describe("74. Top-Up Lock Weighting", () => {
+    const SHORT_CORE_LOCK = 3;
+
+    it("should keep a large top-up locked after the original lock ran out", async () => {
+      const ctx = await createIsolatedPool();
+      await program.methods
+        .setLockPeriods(new BN(0), new BN(SHORT_CORE_LOCK), new BN(PRIME_LOCK_PERIOD))
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+      const staker = await createStaker(ctx);
+
+      // Small stake, wait out its lock, then a large top-up
+      await stakeInto(ctx, staker, ONE_TOKEN, TIER_CORE);
+      await new Promise((resolve) => setTimeout(resolve, (SHORT_CORE_LOCK + 1) * 1000));
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_CORE);
+
+      try {
+        await unstakeFrom(ctx, staker, STAKE_AMOUNT);
+        expect.fail("Should have thrown LockPeriodNotEnded");
+      } catch (error: any) {
+        expect(error.message).to.include("LockPeriodNotEnded");
+      }
+      console.log("✓ Top-up could not leave on the old lock");
+
+      await new Promise((resolve) => setTimeout(resolve, (SHORT_CORE_LOCK + 1) * 1000));
+      await unstakeFrom(ctx, staker, STAKE_AMOUNT.add(ONE_TOKEN));
+      console.log("✓ Whole position released after the weighted lock");
+    });
+  });
+
+  // ============================================
+  // 75. CLAIM INTO A CLOSED ATA
+  // ============================================
+
+  describe("75. Claim Into A Closed ATA", () => {
+    let ctx: IsolatedPool;
+    let staker: Staker;
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      await fundPool(ctx, TREASURY_FUND);
+      staker = await createStaker(ctx);
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_FLEX);
+    });
+
+    it("should recreate the owner's ATA and pay into it", async () => {
+      // Sell-off: move the remaining balance out and close the ATA
+      const parking = await createAccount(
+        connection, staker.user, ctx.mint, staker.user.publicKey, Keypair.generate()
+      );
+      const balance = await tokenBalance(staker.rewardAccount);
+      await transfer(
+        connection, staker.user, staker.rewardAccount, parking, staker.user,
+        BigInt(balance.toString())
+      );
+      await closeAccount(
+        connection, staker.user, staker.rewardAccount, staker.user.publicKey, staker.user
+      );
+      expect(await connection.getAccountInfo(staker.rewardAccount)).to.equal(null);
+
+      await advanceTime(2);
+      await claimFrom(ctx, staker);
+
+      const received = await tokenBalance(staker.rewardAccount);
+      expect(received.gt(new BN(0))).to.be.true;
+      console.log(`✓ ATA recreated during claim, received ${formatTokens(received)}`);
+    });
+
+    it("should reject a non-canonical account for the owner", async () => {
+      const other = await createAccount(
+        connection, staker.user, ctx.mint, staker.user.publicKey, Keypair.generate()
+      );
+      await advanceTime(2);
+      try {
+        await program.methods
+          .claimRewards(null)
+          .accounts({
+            user: staker.user.publicKey,
+            stakePool: ctx.pool,
+            userStake: staker.userStake,
+            stakingMint: ctx.mint,
+            userTokenAccount: other,
+            owner: staker.user.publicKey,
+            rewardMint: ctx.rewardMint,
+            treasuryVault: ctx.treasury,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+          })
+          .signers([staker.user])
+          .rpc();
+        expect.fail("Should have rejected a non-ATA token account");
+      } catch (error: any) {
+        expect(error.message).to.match(/AccountNotAssociatedTokenAccount|ConstraintAssociated/);
+      }
+      console.log("✓ Only the canonical ATA is accepted");
+    });
+  });
+
+  // ============================================
+  // 76. POOL REGISTRY
+  // ============================================
+
+  describe("76. Pool Registry", () => {
+    const REGISTRY_INITIAL_CAPACITY = 8;
+    const REGISTRY_GROWTH = 8;
+    // discriminator + authority + capacity + vec prefix + entries + bump
+    const registrySpace = (capacity: number) => 8 + 32 + 4 + 4 + 64 * capacity + 1;
+
+    const fetchRegistry = () => program.account.poolRegistry.fetch(poolRegistryPda);
+    const registeredPools = async () =>
+      (await fetchRegistry()).entries.map((e: any) => e.stakePool.toBase58());
+
+    it("should list every pool created through initialize", async () => {
+      const created = [];
+      for (let i = 0; i < 3; i++) {
+        created.push(await createIsolatedPool());
+      }
+
+      const registry = await fetchRegistry();
+      const pools = await registeredPools();
+      expect(registry.authority.toBase58()).to.equal(admin.publicKey.toBase58());
+      expect(pools).to.include(stakePoolPda.toBase58());
+      for (const ctx of created) {
+        const entry = registry.entries.find((e: any) => e.stakePool.equals(ctx.pool));
+        expect(entry).to.not.equal(undefined);
+        expect(entry.stakingMint.toBase58()).to.equal(ctx.mint.toBase58());
+      }
+      // Newest pools are appended in creation order
+      expect(pools.slice(-3)).to.deep.equal(created.map((c) => c.pool.toBase58()));
+      console.log(`✓ Registry lists ${pools.length} pools`);
+    });
+
+    it("should realloc when the registry is full", async () => {
+      let registry = await fetchRegistry();
+      expect(registry.capacity).to.be.greaterThan(REGISTRY_INITIAL_CAPACITY);
+      expect(registry.capacity).to.be.at.least(registry.entries.length);
+
+      // Fill the remaining room, which must not resize the account
+      while (registry.entries.length < registry.capacity) {
+        await createIsolatedPool();
+        registry = await fetchRegistry();
+      }
+      const fullCapacity = registry.capacity;
+      let info = await connection.getAccountInfo(poolRegistryPda);
+      expect(info!.data.length).to.equal(registrySpace(fullCapacity));
+
+      // One more pool grows it by REGISTRY_GROWTH entries
+      const extra = await createIsolatedPool();
+      registry = await fetchRegistry();
+      info = await connection.getAccountInfo(poolRegistryPda);
+      expect(registry.capacity).to.equal(fullCapacity + REGISTRY_GROWTH);
+      expect(info!.data.length).to.equal(registrySpace(registry.capacity));
+      expect(registry.entries[registry.entries.length - 1].stakePool.toBase58())
+        .to.equal(extra.pool.toBase58());
+      console.log(`✓ Registry grew from ${fullCapacity} to ${registry.capacity} entries`);
+    });
+
+    it("should let only the registry authority remove a pool", async () => {
+      const ctx = await createIsolatedPool();
+      const before = await fetchRegistry();
+
+      try {
+        await program.methods
+          .removeFromRegistry(ctx.pool)
+          .accounts({ authority: nonAdmin.publicKey, poolRegistry: poolRegistryPda })
+          .signers([nonAdmin])
+          .rpc();
+        expect.fail("Should have thrown Unauthorized");
+      } catch (error: any) {
+        expect(error.message).to.include("Unauthorized");
+      }
+
+      await program.methods
+        .removeFromRegistry(ctx.pool)
+        .accounts({ authority: admin.publicKey, poolRegistry: poolRegistryPda })
+        .signers([admin])
+        .rpc();
+
+      const after = await fetchRegistry();
+      expect(after.entries.length).to.equal(before.entries.length - 1);
+      expect(after.capacity).to.equal(before.capacity);
+      expect(await registeredPools()).to.not.include(ctx.pool.toBase58());
+
+      // The pool itself is untouched
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.stakingMint.toBase58()).to.equal(ctx.mint.toBase58());
+
+      try {
+        await program.methods
+          .removeFromRegistry(ctx.pool)
+          .accounts({ authority: admin.publicKey, poolRegistry: poolRegistryPda })
+          .signers([admin])
+          .rpc();
+        expect.fail("Should have thrown PoolNotRegistered");
+      } catch (error: any) {
+        expect(error.message).to.include("PoolNotRegistered");
+      }
+      console.log("✓ Decommissioned pool removed from the registry");
+    });
+
+    it("should not allow a second registry", async () => {
+      try {
+        await program.methods
+          .initializeRegistry()
+          .accounts({
+            authority: nonAdmin.publicKey,
+            poolRegistry: poolRegistryPda,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([nonAdmin])
+          .rpc();
+        expect.fail("Should have failed to re-create the registry");
+      } catch (error: any) {
+        expect(error.message).to.include("already in use");
+      }
+    });
+  });
+
+  // ============================================
+  // 77. CPI GUARD
+  // ============================================
+
+  describe("77. CPI Guard", () => {
+    let ctx: IsolatedPool;
+    let staker: Staker;
+
+    // Send `ix` through the probe program, which invokes it via CPI
+    async function viaCpi(ix: TransactionInstruction, signer: Keypair): Promise<string> {
+      return cpiProbe.methods
+        .forward(ix.data)
+        .accounts({ targetProgram: ix.programId })
+        .remainingAccounts(ix.keys)
+        .signers([signer])
+        .rpc();
+    }
+
+    const stakeIx = (pool: IsolatedPool, s: Staker, amount: BN) =>
+      program.methods
+        .stake(amount, TIER_FLEX, TOPUP_KEEP_ORIGINAL as any, null, null, null)
+        .accounts({
+          user: s.user.publicKey,
+          stakePool: pool.pool,
+          userStake: s.userStake,
+          stakerRecord: stakerRecordPda(pool.pool, s.user.publicKey),
+          stakingMint: pool.mint,
+          userTokenAccount: s.tokenAccount,
+          stakingVault: pool.vault,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+          rent: SYSVAR_RENT_PUBKEY,
+        })
+        .instruction();
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      await fundPool(ctx, TREASURY_FUND);
+      staker = await createStaker(ctx);
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_FLEX);
+      await advanceTime(2);
+    });
+
+    it("should reject user staking, unstaking, claim and compound calls through CPI", async () => {
+      const ixs = {
+        stake: await stakeIx(ctx, staker, ONE_TOKEN),
+        unstake: await program.methods
+          .unstake(ONE_TOKEN)
+          .accounts({
+            treasuryVault: ctx.treasury,
+            user: staker.user.publicKey,
+            stakePool: ctx.pool,
+            userStake: staker.userStake,
+            stakingMint: ctx.mint,
+            userTokenAccount: staker.tokenAccount,
+            stakingVault: ctx.vault,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+          })
+          .instruction(),
+        claim_rewards: await program.methods
+          .claimRewards(null)
+          .accounts({
+            user: staker.user.publicKey,
+            stakePool: ctx.pool,
+            userStake: staker.userStake,
+            stakingMint: ctx.mint,
+            userTokenAccount: staker.rewardAccount,
+            owner: staker.user.publicKey,
+            rewardMint: ctx.rewardMint,
+            treasuryVault: ctx.treasury,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+          })
+          .instruction(),
+        unstake_to: await program.methods
+          .unstakeTo(ONE_TOKEN)
+          .accounts({
+            user: staker.user.publicKey,
+            stakePool: ctx.pool,
+            userStake: staker.userStake,
+            stakingMint: ctx.mint,
+            destination: staker.tokenAccount,
+            stakingVault: ctx.vault,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+          })
+          .instruction(),
+        compound_rewards: await program.methods
+          .compoundRewards()
+          .accounts({
+            user: staker.user.publicKey,
+            stakePool: ctx.pool,
+            userStake: staker.userStake,
+            stakingMint: ctx.mint,
+            stakingVault: ctx.vault,
+            treasuryVault: ctx.treasury,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+          })
+          .instruction(),
+      };
+
+      for (const [name, ix] of Object.entries(ixs)) {
+        try {
+          await viaCpi(ix, staker.user);
+          expect.fail(`${name} via CPI should have thrown CpiNotAllowed`);
+        } catch (error: any) {
+          expect(error.message).to.include("CpiNotAllowed");
+        }
+      }
+
+      // Called directly, the same position still works
+      await claimFrom(ctx, staker);
+      await unstakeFrom(ctx, staker, ONE_TOKEN);
+      console.log("✓ User instructions rejected through CPI, accepted directly");
+    });
+
+    it("should allow CPI on pools that opted in at initialize", async () => {
+      const open = await createIsolatedPool(EMISSION_CAP, null, false, REWARD_MODE_APY, true);
+      const pool = await program.account.stakePool.fetch(open.pool);
+      expect(pool.allowCpi).to.be.true;
+
+      const wrapped = await createStaker(open);
+      await viaCpi(await stakeIx(open, wrapped, STAKE_AMOUNT), wrapped.user);
+
+      const position = await program.account.userStake.fetch(wrapped.userStake);
+      expect(position.stakedAmount.toString()).to.equal(STAKE_AMOUNT.toString());
+      console.log("✓ Opted-in pool accepts stake through CPI");
+    });
+
+    it("should keep admin instructions CPI-callable", async () => {
+      const ix = await program.methods
+        .setMaxDailyInflow(new BN(0))
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .instruction();
+      await viaCpi(ix, admin);
+      console.log("✓ Admin instruction succeeded through CPI");
+    });
+  });
+
+  // ============================================
+  // 78. SYNC VAULT
+  // ============================================
+
+  describe("78. Sync Vault", () => {
+    let ctx: IsolatedPool;
+    let staker: Staker;
+    const STRAY = ONE_TOKEN.mul(new BN(5));
+
+    const syncVault = (caller: Keypair, sweep: boolean) =>
+      program.methods
+        .syncVault(sweep)
+        .accounts({
+          caller: caller.publicKey,
+          stakePool: ctx.pool,
+          stakingVault: ctx.vault,
+          treasuryVault: ctx.treasury,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([caller])
+        .rpc();
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      await fundPool(ctx, TREASURY_FUND);
+      staker = await createStaker(ctx);
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_FLEX);
+
+      // Sent straight to the vault, bypassing `stake`
+      await transfer(
+        connection, admin, ctx.adminTokenAccount, ctx.vault, admin, BigInt(STRAY.toString())
+      );
+    });
+
+    it("should record the surplus without touching total_staked", async () => {
+      await syncVault(nonAdmin, false);
+
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.unaccountedPrincipal.toString()).to.equal(STRAY.toString());
+      expect(pool.totalStaked.toString()).to.equal(STAKE_AMOUNT.toString());
+      expect((await tokenBalance(ctx.vault)).toString())
+        .to.equal(STAKE_AMOUNT.add(STRAY).toString());
+
+      // A second direct transfer is picked up by the next sync
+      await transfer(
+        connection, admin, ctx.adminTokenAccount, ctx.vault, admin, BigInt(STRAY.toString())
+      );
+      await syncVault(nonAdmin, false);
+      const resynced = await program.account.stakePool.fetch(ctx.pool);
+      expect(resynced.unaccountedPrincipal.toString()).to.equal(STRAY.muln(2).toString());
+      console.log(`✓ Unaccounted principal: ${formatTokens(resynced.unaccountedPrincipal)}`);
+    });
+
+    it("should only let the authority sweep", async () => {
+      try {
+        await syncVault(nonAdmin, true);
+        expect.fail("Should have thrown Unauthorized");
+      } catch (error: any) {
+        expect(error.message).to.include("Unauthorized");
+      }
+      console.log("✓ Non-admin sweep rejected");
+    });
+
+    it("should sweep the surplus into the treasury", async () => {
+      const before = await program.account.stakePool.fetch(ctx.pool);
+      const treasuryBefore = await tokenBalance(ctx.treasury);
+      const surplus = STRAY.muln(2);
+
+      await syncVault(admin, true);
+
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.unaccountedPrincipal.toString()).to.equal("0");
+      expect(pool.totalStaked.toString()).to.equal(STAKE_AMOUNT.toString());
+      expect(pool.totalFunded.toString()).to.equal(before.totalFunded.add(surplus).toString());
+      expect((await tokenBalance(ctx.treasury)).toString())
+        .to.equal(treasuryBefore.add(surplus).toString());
+      expect((await tokenBalance(ctx.vault)).toString()).to.equal(STAKE_AMOUNT.toString());
+
+      // Principal is still fully withdrawable
+      await unstakeFrom(ctx, staker, STAKE_AMOUNT);
+      expect((await tokenBalance(ctx.vault)).toString()).to.equal("0");
+      console.log(`✓ Swept ${formatTokens(surplus)} into the treasury`);
+    });
+  });
+
+  // ============================================
+  // 79. DONATE TO STAKERS
+  // ============================================
+
+  describe("79. Donate To Stakers", () => {
+    const PRECISION = new BN("1000000000000");
+    let ctx: IsolatedPool;
+    let alice: Staker;
+    let bob: Staker;
+    let carol: Staker;
+
+    const donate = (pool: IsolatedPool, amount: BN) =>
+      program.methods
+        .donateToStakers(amount)
+        .accounts({
+          donor: admin.publicKey,
+          stakePool: pool.pool,
+          donorTokenAccount: pool.adminRewardAccount,
+          treasuryVault: pool.treasury,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([admin])
+        .rpc();
+
+    // Unsettled donation share, as the program computes it
+    async function owed(staker: Staker): Promise<BN> {
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      const position = await program.account.userStake.fetch(staker.userStake);
+      return position.stakedAmount
+        .mul(pool.bonusPerToken)
+        .sub(position.bonusDebt)
+        .div(PRECISION);
+    }
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      await fundPool(ctx, TREASURY_FUND.divn(2));
+      alice = await createStaker(ctx);
+      bob = await createStaker(ctx);
+      carol = await createStaker(ctx);
+    });
+
+    it("should reject a donation when nothing is staked", async () => {
+      try {
+        await donate(ctx, ONE_TOKEN);
+        expect.fail("Should have thrown NoStakers");
+      } catch (error: any) {
+        expect(error.message).to.include("NoStakers");
+      }
+    });
+
+    it("should split donations by stake at donation time", async () => {
+      await stakeInto(ctx, alice, STAKE_AMOUNT, TIER_FLEX);
+      await donate(ctx, ONE_TOKEN.muln(10));
+      expect((await owed(alice)).toString()).to.equal(ONE_TOKEN.muln(10).toString());
+
+      // Bob joins with three times Alice's stake and owes nothing yet
+      await stakeInto(ctx, bob, STAKE_AMOUNT.muln(3), TIER_FLEX);
+      expect((await owed(bob)).toString()).to.equal("0");
+
+      await donate(ctx, ONE_TOKEN.muln(40));
+      expect((await owed(alice)).toString()).to.equal(ONE_TOKEN.muln(20).toString());
+      expect((await owed(bob)).toString()).to.equal(ONE_TOKEN.muln(30).toString());
+
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.donationLiability.toString()).to.equal(ONE_TOKEN.muln(50).toString());
+      expect(pool.totalDonated.toString()).to.equal(ONE_TOKEN.muln(50).toString());
+      console.log("✓ Alice owed 20, Bob owed 30 after two donations");
+    });
+
+    it("should keep an exiting staker's share and exclude later donations", async () => {
+      await unstakeFrom(ctx, alice, STAKE_AMOUNT);
+      const exited = await program.account.userStake.fetch(alice.userStake);
+      expect(exited.bonusRewards.toString()).to.equal(ONE_TOKEN.muln(20).toString());
+      expect(exited.bonusDebt.toString()).to.equal("0");
+
+      await donate(ctx, ONE_TOKEN.muln(30));
+      expect((await owed(alice)).toString()).to.equal("0");
+      expect((await owed(bob)).toString()).to.equal(ONE_TOKEN.muln(60).toString());
+
+      // Carol arrives after every donation
+      await stakeInto(ctx, carol, STAKE_AMOUNT, TIER_FLEX);
+      expect((await owed(carol)).toString()).to.equal("0");
+
+      const before = await tokenBalance(alice.rewardAccount);
+      await claimFrom(ctx, alice);
+      const received = (await tokenBalance(alice.rewardAccount)).sub(before);
+      expect(received.gte(ONE_TOKEN.muln(20))).to.be.true;
+      console.log(`✓ Alice claimed ${formatTokens(received)} after exiting`);
+    });
+
+    it("should pay the unsettled share on the next claim", async () => {
+      await advanceTime(2);
+      const before = await tokenBalance(bob.rewardAccount);
+      await claimFrom(ctx, bob);
+      const received = (await tokenBalance(bob.rewardAccount)).sub(before);
+      expect(received.gte(ONE_TOKEN.muln(60))).to.be.true;
+
+      const position = await program.account.userStake.fetch(bob.userStake);
+      expect(position.bonusRewards.toString()).to.equal("0");
+      expect((await owed(bob)).toString()).to.equal("0");
+
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.donationLiability.toString()).to.equal("0");
+      expect(pool.bonusLiability.toString()).to.equal("0");
+      console.log(`✓ Bob claimed ${formatTokens(received)} including his 60 token share`);
+    });
+  });
+
+  // ============================================
+  // 80. TOP-UP POLICY CHOSEN AT INITIALIZE
+  // ============================================
+
+  describe("80. Top-Up Policy Chosen At Initialize", () => {
+    const SHORT_CORE_LOCK = 60;
+    const TOP_UP_DELAY_SECS = 2;
+
+    async function shortLockPool(topupMask: number): Promise<IsolatedPool> {
+      const ctx = await createIsolatedPool(
+        EMISSION_CAP, null, false, REWARD_MODE_APY, false, topupMask
+      );
+      await program.methods
+        .setLockPeriods(new BN(0), new BN(SHORT_CORE_LOCK), new BN(PRIME_LOCK_PERIOD))
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+      return ctx;
+    }
+
+    async function lockEnd(staker: Staker): Promise<number> {
+      const position = await program.account.userStake.fetch(staker.userStake);
+      return position.stakeStartTime.toNumber() + position.lockSeconds.toNumber();
+    }
+
+    async function expectPolicyRejected(
+      ctx: IsolatedPool, staker: Staker, policy: object
+    ): Promise<void> {
+      try {
+        await stakeInto(ctx, staker, ONE_TOKEN, TIER_CORE, policy);
+        expect.fail("Should have thrown TopUpPolicyNotAllowed");
+      } catch (error: any) {
+        expect(error.message).to.include("TopUpPolicyNotAllowed");
+      }
+    }
+
+    const pause = (secs: number) =>
+      new Promise((resolve) => setTimeout(resolve, secs * 1000));
+
+    it("should store the mask passed to initialize", async () => {
+      const ctx = await shortLockPool(TOPUP_EXTEND_ALL_BIT);
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.allowedTopupPolicies).to.equal(TOPUP_EXTEND_ALL_BIT);
+      console.log("✓ Pool created with ExtendAll only");
+    });
+
+    it("should reset the whole lock on an ExtendAll-only pool", async () => {
+      const ctx = await shortLockPool(TOPUP_EXTEND_ALL_BIT);
+      const staker = await createStaker(ctx);
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_CORE, TOPUP_EXTEND_ALL);
+      const firstEnd = await lockEnd(staker);
+
+      await pause(TOP_UP_DELAY_SECS);
+      await stakeInto(ctx, staker, ONE_TOKEN, TIER_CORE, TOPUP_EXTEND_ALL);
+      const position = await program.account.userStake.fetch(staker.userStake);
+
+      expect(position.stakeStartTime.toNumber()).to.equal(position.lastStakeTime.toNumber());
+      expect(await lockEnd(staker)).to.be.greaterThan(firstEnd);
+      await expectPolicyRejected(ctx, staker, TOPUP_KEEP_ORIGINAL);
+      console.log("✓ Full reset applied; weighted top-ups rejected");
+    });
+
+    it("should weight the lock end on a KeepOriginal-only pool", async () => {
+      const ctx = await shortLockPool(TOPUP_KEEP_ORIGINAL_BIT);
+      const staker = await createStaker(ctx);
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_CORE);
+      const before = await program.account.userStake.fetch(staker.userStake);
+      const firstEnd = await lockEnd(staker);
+
+      // Equal top-up: the end moves half as far as the time elapsed
+      await pause(TOP_UP_DELAY_SECS * 2);
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_CORE, TOPUP_KEEP_ORIGINAL);
+      const after = await program.account.userStake.fetch(staker.userStake);
+      const elapsed = after.lastStakeTime.toNumber() - before.stakeStartTime.toNumber();
+      expect(await lockEnd(staker)).to.equal(firstEnd + Math.ceil(elapsed / 2));
+
+      await expectPolicyRejected(ctx, staker, TOPUP_EXTEND_ALL);
+      console.log(`✓ Lock end moved ${Math.ceil(elapsed / 2)}s after ${elapsed}s`);
+    });
+
+    it("should move a huge lock by one second for a tiny top-up", async () => {
+      const ctx = await shortLockPool(TOPUP_KEEP_ORIGINAL_BIT);
+      const staker = await createStaker(ctx);
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_CORE);
+      const firstEnd = await lockEnd(staker);
+
+      await pause(TOP_UP_DELAY_SECS);
+      await stakeInto(ctx, staker, new BN(1), TIER_CORE, TOPUP_KEEP_ORIGINAL);
+      expect(await lockEnd(staker)).to.equal(firstEnd + 1);
+      console.log("✓ 1 base unit rounded the lock end up by one second");
+    });
+
+    it("should reject an invalid mask at initialize", async () => {
+      for (const [mask, code] of [
+        [4, "TopUpPolicyUnavailable"],
+        [8, "InvalidTopUpPolicyMask"],
+      ] as [number, string][]) {
+        try {
+          await createIsolatedPool(EMISSION_CAP, null, false, REWARD_MODE_APY, false, mask);
+          expect.fail(`Should have thrown ${code}`);
+        } catch (error: any) {
+          expect(error.message).to.include(code);
+        }
+      }
+      console.log("✓ Unknown and unavailable policies rejected at initialize");
+    });
+  });
+
+  // ============================================
+  // 81. ADMIN ROLES
+  // ============================================
+
+  describe("81. Admin Roles", () => {
+    const ROLE_PAUSER = { pauser: {} };
+    const ROLE_RATE_SETTER = { rateSetter: {} };
+    const MAX_RATE_SETTER_APY_DELTA = 200;
+
+    let ctx: IsolatedPool;
+    let pauser: Keypair;
+    let rateSetter: Keypair;
+
+    const setRole = (signer: Keypair, role: object, holder: PublicKey) =>
+      program.methods
+        .setRole(role as any, holder)
+        .accounts({ authority: signer.publicKey, stakePool: ctx.pool })
+        .signers([signer])
+        .rpc();
+
+    const setPaused = (signer: Keypair, paused: boolean) =>
+      program.methods
+        .setPaused(paused, null)
+        .accounts({ authority: signer.publicKey, stakePool: ctx.pool })
+        .signers([signer])
+        .rpc();
+
+    const setPauseFlags = (signer: Keypair, flags: number) =>
+      program.methods
+        .setPauseFlags(flags)
+        .accounts({ authority: signer.publicKey, stakePool: ctx.pool })
+        .signers([signer])
+        .rpc();
+
+    const setTierPaused = (signer: Keypair, paused: boolean) =>
+      program.methods
+        .setTierPaused(TIER_CORE, paused)
+        .accounts({ authority: signer.publicKey, stakePool: ctx.pool })
+        .signers([signer])
+        .rpc();
+
+    const adjustApy = (signer: Keypair, flexApy: number) =>
+      program.methods
+        .adjustApy(flexApy, CORE_APY, PRIME_APY)
+        .accounts({ authority: signer.publicKey, stakePool: ctx.pool })
+        .signers([signer])
+        .rpc();
+
+    const updateCap = (signer: Keypair) =>
+      program.methods
+        .updateEmissionCap(EMISSION_CAP.muln(2))
+        .accounts({ authority: signer.publicKey, stakePool: ctx.pool })
+        .signers([signer])
+        .rpc();
+
+    async function expectRejected(call: Promise<string>, code: string): Promise<void> {
+      try {
+        await call;
+        expect.fail(`Should have thrown ${code}`);
+      } catch (error: any) {
+        expect(error.message).to.include(code);
+      }
+    }
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      pauser = Keypair.generate();
+      rateSetter = Keypair.generate();
+      await Promise.all([airdropSol(pauser.publicKey), airdropSol(rateSetter.publicKey)]);
+    });
+
+    it("should start with every role held by the authority", async () => {
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.pauser.toBase58()).to.equal(admin.publicKey.toBase58());
+      expect(pool.rateSetter.toBase58()).to.equal(admin.publicKey.toBase58());
+      console.log("✓ Pauser and rate setter default to the authority");
+    });
+
+    it("should only let the authority assign roles", async () => {
+      await expectRejected(setRole(nonAdmin, ROLE_PAUSER, nonAdmin.publicKey), "Unauthorized");
+      await expectRejected(setRole(admin, ROLE_PAUSER, PublicKey.default), "InvalidRoleHolder");
+
+      await setRole(admin, ROLE_PAUSER, pauser.publicKey);
+      await setRole(admin, ROLE_RATE_SETTER, rateSetter.publicKey);
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.pauser.toBase58()).to.equal(pauser.publicKey.toBase58());
+      expect(pool.rateSetter.toBase58()).to.equal(rateSetter.publicKey.toBase58());
+      console.log("✓ Roles handed to dedicated keys");
+    });
+
+    it("should let the pauser pause but nothing else", async () => {
+      await setPaused(pauser, true);
+      await setPaused(pauser, false);
+      await setPauseFlags(pauser, 1);
+      await setPauseFlags(pauser, 0);
+      await setTierPaused(pauser, true);
+      await setTierPaused(pauser, false);
+
+      await expectRejected(adjustApy(pauser, FLEX_APY + 100), "Unauthorized");
+      await expectRejected(updateCap(pauser), "Unauthorized");
+      await expectRejected(setRole(pauser, ROLE_PAUSER, nonAdmin.publicKey), "Unauthorized");
+      console.log("✓ Pauser limited to the pause instructions");
+    });
+
+    it("should let the rate setter adjust APY within the delta only", async () => {
+      await adjustApy(rateSetter, FLEX_APY + MAX_RATE_SETTER_APY_DELTA);
+      // The window keeps its opening rates, so a second step cannot stack
+      await expectRejected(
+        adjustApy(rateSetter, FLEX_APY + MAX_RATE_SETTER_APY_DELTA + 1),
+        "ApyChangeTooLarge"
+      );
+      await adjustApy(rateSetter, FLEX_APY);
+
+      await expectRejected(setPaused(rateSetter, true), "Unauthorized");
+      await expectRejected(setPauseFlags(rateSetter, 1), "Unauthorized");
+      await expectRejected(setTierPaused(rateSetter, true), "Unauthorized");
+      await expectRejected(updateCap(rateSetter), "Unauthorized");
+      await expectRejected(setRole(rateSetter, ROLE_RATE_SETTER, nonAdmin.publicKey), "Unauthorized");
+      console.log("✓ Rate setter limited to bounded APY changes");
+    });
+
+    it("should keep full control with the authority", async () => {
+      await setPaused(admin, true);
+      await setPaused(admin, false);
+      await setTierPaused(admin, true);
+      await setTierPaused(admin, false);
+      await adjustApy(admin, FLEX_APY + 3 * MAX_RATE_SETTER_APY_DELTA);
+      await adjustApy(admin, FLEX_APY);
+      await updateCap(admin);
+      console.log("✓ Authority holds every role, without the delta limit");
+    });
+
+    it("should reject strangers from every role instruction", async () => {
+      await expectRejected(setPaused(nonAdmin, true), "Unauthorized");
+      await expectRejected(setPauseFlags(nonAdmin, 1), "Unauthorized");
+      await expectRejected(setTierPaused(nonAdmin, true), "Unauthorized");
+      await expectRejected(adjustApy(nonAdmin, FLEX_APY), "Unauthorized");
+      console.log("✓ Keys without a role are rejected");
+    });
+
+    it("should revoke a role by handing it back to the authority", async () => {
+      await setRole(admin, ROLE_PAUSER, admin.publicKey);
+      await expectRejected(setPaused(pauser, true), "Unauthorized");
+      console.log("✓ Former pauser can no longer pause");
+    });
+  });
+
+  // ============================================
+  // 82. LIFETIME STATISTICS
+  // ============================================
+
+  describe("82. Lifetime Statistics", () => {
+    let ctx: IsolatedPool;
+    let staker: Staker;
+
+    const fetchPool = () => program.account.stakePool.fetch(ctx.pool);
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      await fundPool(ctx, TREASURY_FUND);
+      staker = await createStaker(ctx);
+    });
+
+    it("should start every counter at zero", async () => {
+      const pool = await fetchPool();
+      expect(pool.cumulativeStaked.toNumber()).to.equal(0);
+      expect(pool.cumulativeUnstaked.toNumber()).to.equal(0);
+      expect(pool.cumulativeClaimsCount.toNumber()).to.equal(0);
+      expect(pool.uniqueStakersEver.toNumber()).to.equal(0);
+      console.log("✓ Fresh pool has no history");
+    });
+
+    it("should keep lifetime totals across stake, full unstake and restake", async () => {
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_FLEX);
+      await advanceTime(2);
+      await claimFrom(ctx, staker);
+      await unstakeFrom(ctx, staker, STAKE_AMOUNT);
+
+      let pool = await fetchPool();
+      expect(pool.totalStaked.toNumber()).to.equal(0);
+      expect(pool.cumulativeStaked.toString()).to.equal(STAKE_AMOUNT.toString());
+      expect(pool.cumulativeUnstaked.toString()).to.equal(STAKE_AMOUNT.toString());
+      expect(pool.uniqueStakersEver.toNumber()).to.equal(1);
+
+      // Re-activating the same position is not a new staker
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_FLEX);
+      await advanceTime(2);
+      await claimFrom(ctx, staker);
+
+      pool = await fetchPool();
+      expect(pool.cumulativeStaked.toString()).to.equal(STAKE_AMOUNT.muln(2).toString());
+      expect(pool.cumulativeUnstaked.toString()).to.equal(STAKE_AMOUNT.toString());
+      expect(pool.cumulativeClaimsCount.toNumber()).to.equal(2);
+      expect(pool.uniqueStakersEver.toNumber()).to.equal(1);
+      console.log(
+        `✓ ${formatTokens(pool.cumulativeStaked)} staked, ` +
+          `${formatTokens(pool.cumulativeUnstaked)} unstaked, one staker ever`
+      );
+    });
+
+    it("should count a second wallet once", async () => {
+      const other = await createStaker(ctx);
+      await stakeInto(ctx, other, STAKE_AMOUNT, TIER_FLEX);
+      await stakeInto(ctx, other, STAKE_AMOUNT, TIER_FLEX);
+
+      const pool = await fetchPool();
+      expect(pool.uniqueStakersEver.toNumber()).to.equal(2);
+      expect(pool.cumulativeStaked.toString()).to.equal(STAKE_AMOUNT.muln(4).toString());
+      console.log("✓ Top-ups do not count as new stakers");
+    });
+  });
+
+  // ============================================
+  // 83. ACCRUAL WHILE PAUSED
+  // ============================================
+
+  describe("83. Accrual While Paused", () => {
+    const HALT_SECS = 3;
+
+    const pause = (secs: number) =>
+      new Promise((resolve) => setTimeout(resolve, secs * 1000));
+
+    async function setPaused(ctx: IsolatedPool, paused: boolean): Promise<void> {
+      await program.methods
+        .setPaused(paused, null)
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+    }
+
+    async function setAccrueWhilePaused(ctx: IsolatedPool, enabled: boolean): Promise<void> {
+      await program.methods
+        .setAccrueWhilePaused(enabled)
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+    }
+
+    async function pausedPoolWithClaimedStaker(
+      accrueWhilePaused: boolean
+    ): Promise<[IsolatedPool, Staker]> {
+      const ctx = await createIsolatedPool();
+      await fundPool(ctx, TREASURY_FUND);
+      const staker = await createStaker(ctx);
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_FLEX);
+      await setAccrueWhilePaused(ctx, accrueWhilePaused);
+      await pause(2);
+      await setPaused(ctx, true);
+      // Settle inside the pause, then let it run
+      await claimFrom(ctx, staker);
+      await pause(HALT_SECS);
+      return [ctx, staker];
+    }
+
+    it("should accrue through a pause by default", async () => {
+      const ctx = await createIsolatedPool();
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.accrueWhilePaused).to.equal(true);
+
+      const [pausedCtx, staker] = await pausedPoolWithClaimedStaker(true);
+      await claimFrom(pausedCtx, staker);
+      console.log("✓ Rewards kept accruing while paused");
+    });
+
+    it("should accrue nothing while a halt is running", async () => {
+      const [ctx, staker] = await pausedPoolWithClaimedStaker(false);
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.pausedAt).to.not.equal(null);
+
+      try {
+        await claimFrom(ctx, staker);
+        expect.fail("Should have thrown NoRewardsAvailable");
+      } catch (error: any) {
+        expect(error.message).to.include("NoRewardsAvailable");
+      }
+      console.log("✓ Nothing to claim during the halt");
+    });
+
+    it("should resume without a gap after a claim during the pause", async () => {
+      const [ctx, staker] = await pausedPoolWithClaimedStaker(false);
+      await setPaused(ctx, false);
+
+      let pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.pausedAt).to.equal(null);
+      expect(pool.cumulativePausedSecs.toNumber()).to.be.at.least(HALT_SECS);
+
+      // The snapshot taken by the claim inside the pause covers only the
+      // rest of the halt, so accrual picks up right after the unpause
+      const position = await program.account.userStake.fetch(staker.userStake);
+      expect(position.pausedSecsSnapshot.toNumber()).to.be.lessThan(
+        pool.cumulativePausedSecs.toNumber()
+      );
+      await pause(2);
+      const before = await tokenBalance(staker.rewardAccount);
+      await claimFrom(ctx, staker);
+      const earned = (await tokenBalance(staker.rewardAccount)).sub(before);
+      expect(earned.gt(new BN(0))).to.be.true;
+
+      pool = await program.account.stakePool.fetch(ctx.pool);
+      console.log(
+        `✓ Halted ${pool.cumulativePausedSecs.toNumber()}s, then earned ${formatTokens(earned)}`
+      );
+    });
+
+    it("should only let the authority change the setting", async () => {
+      const ctx = await createIsolatedPool();
+      try {
+        await program.methods
+          .setAccrueWhilePaused(false)
+          .accounts({ authority: nonAdmin.publicKey, stakePool: ctx.pool })
+          .signers([nonAdmin])
+          .rpc();
+        expect.fail("Should have thrown Unauthorized");
+      } catch (error: any) {
+        expect(error.message).to.include("Unauthorized");
+      }
+      console.log("✓ Non-admin rejected");
+    });
+  });
+
+  // ============================================
+  // 84. REWARDS END TIME
+  // ============================================
+
+  describe("84. Rewards End Time", () => {
+    const CAMPAIGN_SECS = 4;
+
+    let ctx: IsolatedPool;
+    let staker: Staker;
+
+    const setRewardsEndTime = (endTime: number, signer: Keypair = admin) =>
+      program.methods
+        .setRewardsEndTime(new BN(endTime))
+        .accounts({ authority: signer.publicKey, stakePool: ctx.pool })
+        .signers([signer])
+        .rpc();
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      await fundPool(ctx, TREASURY_FUND);
+      staker = await createStaker(ctx);
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_FLEX);
+    });
+
+    it("should only accept an end time in the future from the authority", async () => {
+      const now = await getCurrentTimestamp();
+      for (const [call, code] of [
+        [setRewardsEndTime(now - 1), "InvalidRewardsEndTime"],
+        [setRewardsEndTime(now + 60, nonAdmin), "Unauthorized"],
+      ] as [Promise<string>, string][]) {
+        try {
+          await call;
+          expect.fail(`Should have thrown ${code}`);
+        } catch (error: any) {
+          expect(error.message).to.include(code);
+        }
+      }
+
+      await setRewardsEndTime(now + CAMPAIGN_SECS);
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.rewardsEndTime.toNumber()).to.equal(now + CAMPAIGN_SECS);
+      console.log("✓ End time scheduled");
+    });
+
+    it("should refuse new stakes once the pool has ended", async () => {
+      await new Promise((resolve) => setTimeout(resolve, (CAMPAIGN_SECS + 2) * 1000));
+      try {
+        await stakeInto(ctx, staker, ONE_TOKEN, TIER_FLEX);
+        expect.fail("Should have thrown PoolEnded");
+      } catch (error: any) {
+        expect(error.message).to.include("PoolEnded");
+      }
+      try {
+        await setRewardsEndTime((await getCurrentTimestamp()) + 60);
+        expect.fail("Should have thrown PoolEnded");
+      } catch (error: any) {
+        expect(error.message).to.include("PoolEnded");
+      }
+      console.log("✓ Stakes and end time changes rejected after the end");
+    });
+
+    it("should pay what accrued up to the end, then nothing more", async () => {
+      // The claim after the end settles the position past the end time
+      await claimFrom(ctx, staker);
+      await advanceTime(2);
+      try {
+        await claimFrom(ctx, staker);
+        expect.fail("Should have thrown NoRewardsAvailable");
+      } catch (error: any) {
+        expect(error.message).to.include("NoRewardsAvailable");
+      }
+      console.log("✓ Accrual stopped at the end time");
+    });
+
+    it("should keep unstaking available after the end", async () => {
+      await unstakeFrom(ctx, staker, STAKE_AMOUNT);
+      const position = await program.account.userStake.fetch(staker.userStake);
+      expect(position.stakedAmount.toNumber()).to.equal(0);
+      console.log("✓ Principal withdrawn after the end");
+    });
+  });
+
+  // ============================================
+  // 85. STAKE LABELS
+  // ============================================
+
+  describe("85. Stake Labels", () => {
+    const toLabel = (text: string | Buffer): number[] => {
+      const bytes = Buffer.alloc(32);
+      (typeof text === "string" ? Buffer.from(text, "utf8") : text).copy(bytes);
+      return Array.from(bytes);
+    };
+
+    const eventParser = new anchor.EventParser(program.programId, new anchor.BorshCoder(program.idl));
+    let ctx: IsolatedPool;
+    let staker: Staker;
+
+    async function eventLabel(signature: string, name: string): Promise<number[]> {
+      await connection.confirmTransaction(signature, "confirmed");
+      const tx = await connection.getTransaction(signature, {
+        commitment: "confirmed",
+        maxSupportedTransactionVersion: 0,
+      });
+      const event = Array.from(eventParser.parseLogs(tx!.meta!.logMessages!)).find((e) => e.name === name);
+      expect(event).to.not.equal(undefined);
+      return event!.data.label as number[];
+    }
+
+    const setLabel = (label: number[], signer: Keypair = staker.user) =>
+      program.methods
+        .setStakeLabel(label)
+        .accounts({
+          user: signer.publicKey,
+          stakePool: ctx.pool,
+          userStake: staker.userStake,
+        })
+        .signers([signer])
+        .rpc();
+
+    const fetchLabel = async () =>
+      (await program.account.userStake.fetch(staker.userStake)).label as number[];
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      await fundPool(ctx, TREASURY_FUND);
+      staker = await createStaker(ctx);
+    });
+
+    it("should take a label on the first stake and emit it", async () => {
+      const signature = await stakeInto(
+        ctx, staker, STAKE_AMOUNT, TIER_FLEX, TOPUP_KEEP_ORIGINAL, null, null, toLabel("grant pool")
+      );
+
+      expect(await fetchLabel()).to.deep.equal(toLabel("grant pool"));
+      expect(await eventLabel(signature, "StakeEvent")).to.deep.equal(toLabel("grant pool"));
+      console.log("✓ Label recorded at first stake");
+    });
+
+    it("should reject a label on a top-up", async () => {
+      try {
+        await stakeInto(ctx, staker, ONE_TOKEN, TIER_FLEX, TOPUP_KEEP_ORIGINAL, null, null, toLabel("other"));
+        expect.fail("Should have thrown LabelOnlyOnFirstStake");
+      } catch (error: any) {
+        expect(error.message).to.include("LabelOnlyOnFirstStake");
+      }
+      await stakeInto(ctx, staker, ONE_TOKEN, TIER_FLEX);
+      expect(await fetchLabel()).to.deep.equal(toLabel("grant pool"));
+      console.log("✓ Top-ups keep the existing label");
+    });
+
+    it("should let only the owner relabel, with valid text", async () => {
+      await setLabel(toLabel("ops reserve"));
+      expect(await fetchLabel()).to.deep.equal(toLabel("ops reserve"));
+
+      for (const [label, signer, code] of [
+        [toLabel("mine"), nonAdmin, "InvalidStakeOwner"],
+        [toLabel(Buffer.from([0xff, 0xfe])), staker.user, "InvalidStakeLabel"],
+        [toLabel(Buffer.from("ops\0reserve")), staker.user, "InvalidStakeLabel"],
+      ] as [number[], Keypair, string][]) {
+        try {
+          await setLabel(label, signer);
+          expect.fail(`Should have thrown ${code}`);
+        } catch (error: any) {
+          expect(error.message).to.match(new RegExp(`${code}|ConstraintSeeds`));
+        }
+      }
+      expect(await fetchLabel()).to.deep.equal(toLabel("ops reserve"));
+      console.log("✓ Relabelled by the owner only");
+    });
+
+    it("should carry the label into claim events and allow clearing it", async () => {
+      await advanceTime(2);
+      const signature = await claimFrom(ctx, staker);
+      expect(await eventLabel(signature, "ClaimEvent")).to.deep.equal(toLabel("ops reserve"));
+
+      await setLabel(toLabel(""));
+      expect(await fetchLabel()).to.deep.equal(toLabel(""));
+      console.log("✓ Label emitted on claim and cleared");
+    });
+  });
+
+  // ============================================
+  // 86. VAULT ACCOUNTING
+  // ============================================
+
+  describe("86. Vault Accounting", () => {
+    let ctx: IsolatedPool;
+    let staker: Staker;
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      staker = await createStaker(ctx);
+    });
+
+    it("should keep stakes and funding equal to the vault deposits", async () => {
+      const vaultBefore = await tokenBalance(ctx.vault);
+      const treasuryBefore = await tokenBalance(ctx.treasury);
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_FLEX);
+      await fundPool(ctx, TREASURY_FUND);
+
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      const received = (await tokenBalance(ctx.vault)).sub(vaultBefore);
+      expect(pool.totalStaked.toString()).to.equal(received.toString());
+      expect(pool.totalFunded.toString()).to.equal(
+        (await tokenBalance(ctx.treasury)).sub(treasuryBefore).toString()
+      );
+      console.log("✓ Books match the vault balances");
+    });
+
+    it("should keep the books balanced after a full exit", async () => {
+      await unstakeFrom(ctx, staker, STAKE_AMOUNT);
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.totalStaked.toNumber()).to.equal(0);
+      expect((await tokenBalance(ctx.vault)).toNumber()).to.equal(0);
+      console.log("✓ Vault emptied with the last position");
+    });
+
+    it("should reject a Token-2022 staking mint", async () => {
+      const mint = await createMint(
+        connection, admin, admin.publicKey, null, DECIMALS, undefined, undefined, TOKEN_2022_PROGRAM_ID
+      );
+      const [pool] = PublicKey.findProgramAddressSync(
+        [STAKE_POOL_SEED, mint.toBuffer()],
+        program.programId
+      );
+      try {
+        await program.methods
+          .initialize(
+            EMISSION_CAP, FLEX_APY, CORE_APY, PRIME_APY, new BN(0), REWARD_MODE_APY as any, false,
+            DEFAULT_TOPUP_MASK
+          )
+          .accounts({
+            authority: admin.publicKey,
+            stakePool: pool,
+            stakingMint: mint,
+            rewardMint: mint,
+            stakingVault: PublicKey.findProgramAddressSync(
+              [POOL_VAULT_SEED, pool.toBuffer()],
+              program.programId
+            )[0],
+            treasuryVault: PublicKey.findProgramAddressSync(
+              [TREASURY_VAULT_SEED, pool.toBuffer()],
+              program.programId
+            )[0],
+            poolRegistry: poolRegistryPda,
+            receiptMint: null,
+            systemProgram: SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            rent: SYSVAR_RENT_PUBKEY,
+          })
+          .signers([admin])
+          .rpc();
+        expect.fail("Should have thrown AccountOwnedByWrongProgram");
+      } catch (error: any) {
+        expect(error.message).to.include("AccountOwnedByWrongProgram");
+        console.log("✓ Token-2022 mints are rejected");
+      }
+    });
+  });
+
+  // ============================================
+  // 87. EVENT SEQUENCE
+  // ============================================
+
+  describe("87. Event Sequence", () => {
+    const eventParser = new anchor.EventParser(program.programId, new anchor.BorshCoder(program.idl));
+    let ctx: IsolatedPool;
+    let staker: Staker;
+
+    async function sequencesOf(signature: string): Promise<number[]> {
+      await connection.confirmTransaction(signature, "confirmed");
+      const tx = await connection.getTransaction(signature, {
+        commitment: "confirmed",
+        maxSupportedTransactionVersion: 0,
+      });
+      return Array.from(eventParser.parseLogs(tx!.meta!.logMessages!)).map((e) =>
+        new BN(e.data.sequence as BN).toNumber()
+      );
+    }
+
+    const poolSequence = async () =>
+      (await program.account.stakePool.fetch(ctx.pool)).sequence.toNumber();
+
+    const setDailyInflow = (cap: BN) =>
+      program.methods
+        .setMaxDailyInflow(cap)
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      await fundPool(ctx, TREASURY_FUND);
+      staker = await createStaker(ctx);
+    });
+
+    it("should advance by one per instruction across an interleaved run", async () => {
+      const steps: [string, () => Promise<string>, boolean][] = [
+        ["stake", () => stakeInto(ctx, staker, STAKE_AMOUNT, TIER_FLEX), true],
+        ["admin setter", () => setDailyInflow(STAKE_AMOUNT.muln(10)), false],
+        ["top-up", () => stakeInto(ctx, staker, ONE_TOKEN, TIER_FLEX), true],
+        ["claim", async () => { await advanceTime(2); return claimFrom(ctx, staker); }, true],
+        ["admin setter", () => setDailyInflow(new BN(0)), false],
+        ["unstake", () => unstakeFrom(ctx, staker, ONE_TOKEN), true],
+        ["fund", async () => { await fundPool(ctx, ONE_TOKEN); return ""; }, false],
+        ["unstake", () => unstakeFrom(ctx, staker, STAKE_AMOUNT), true],
+      ];
+
+      let previous = await poolSequence();
+      for (const [name, run, emits] of steps) {
+        const signature = await run();
+        const current = await poolSequence();
+        expect(current, name).to.equal(previous + 1);
+        if (emits) {
+          const sequences = await sequencesOf(signature);
+          expect(sequences.length, name).to.be.greaterThan(0);
+          sequences.forEach((sequence) => expect(sequence, name).to.equal(current));
+        }
+        previous = current;
+      }
+      console.log(`✓ ${steps.length} instructions numbered consecutively up to ${previous}`);
+    });
+
+    it("should not advance for failed or read-only calls", async () => {
+      const before = await poolSequence();
+      try {
+        await stakeInto(ctx, staker, new BN(0), TIER_FLEX);
+        expect.fail("Should have thrown ZeroAmount");
+      } catch (error: any) {
+        expect(error.message).to.include("ZeroAmount");
+      }
+      await program.methods
+        .viewPendingRewards()
+        .accounts({ stakePool: ctx.pool, userStake: staker.userStake })
+        .view();
+      expect(await poolSequence()).to.equal(before);
+      console.log("✓ Sequence unchanged by a rejected stake and a view");
+    });
+  });
+
+  // ============================================
+  // 88. PAUSE EXPIRY
+  // ============================================
+
+  describe("88. Pause Expiry", () => {
+    let ctx: IsolatedPool;
+    let staker: Staker;
+
+    const setPaused = (paused: boolean, pauseExpiry: BN | null) =>
+      program.methods
+        .setPaused(paused, pauseExpiry)
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      await fundPool(ctx, TREASURY_FUND);
+      staker = await createStaker(ctx);
+    });
+
+    it("should reject an expiry in the past or without pausing", async () => {
+      const now = await getCurrentTimestamp();
+      for (const [paused, expiry] of [
+        [true, now],
+        [false, now + 60],
+      ] as [boolean, number][]) {
+        try {
+          await setPaused(paused, new BN(expiry));
+          expect.fail("Should have thrown InvalidPauseExpiry");
+        } catch (error: any) {
+          expect(error.message).to.include("InvalidPauseExpiry");
+        }
+      }
+      console.log("✓ Expiry must be in the future and paired with a pause");
+    });
+
+    it("should keep a pause without expiry until it is lifted", async () => {
+      await setPaused(true, null);
+      await advanceTime(4);
+      try {
+        await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_FLEX);
+        expect.fail("Should have thrown StakingPaused");
+      } catch (error: any) {
+        expect(error.message).to.include("StakingPaused");
+      }
+      await setPaused(false, null);
+      console.log("✓ Pausing without an expiry is unchanged");
+    });
+
+    it("should lift an expired pause on the next stake", async () => {
+      const expiry = (await getCurrentTimestamp()) + 3;
+      await setPaused(true, new BN(expiry));
+      let pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.pauseExpiry.toNumber()).to.equal(expiry);
+
+      try {
+        await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_FLEX);
+        expect.fail("Should have thrown StakingPaused");
+      } catch (error: any) {
+        expect(error.message).to.include("StakingPaused");
+      }
+
+      while ((await getCurrentTimestamp()) < expiry) {
+        await advanceTime(2);
+      }
+      // Nothing has touched the pool since the expiry: the flag is still set
+      pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.pauseFlags & 1).to.equal(1);
+
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_FLEX);
+      pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.pauseFlags & 1).to.equal(0);
+      expect(pool.pauseExpiry.toNumber()).to.equal(0);
+      expect(pool.totalStaked.toString()).to.equal(STAKE_AMOUNT.toString());
+      console.log("✓ Stake after the expiry cleared the pause and went through");
+    });
+  });
+
+  // ============================================
+  // 89. REWARD DESTINATION
+  // ============================================
+
+  describe("89. Reward Destination", () => {
+    let ctx: IsolatedPool;
+    let owner: Staker;
+    let bot: Keypair;
+    let coldWallet: Keypair;
+    let coldAta: PublicKey;
+
+    const setRewardDestination = (destination: PublicKey | null) =>
+      program.methods
+        .setRewardDestination()
+        .accounts({
+          user: owner.user.publicKey,
+          stakePool: ctx.pool,
+          userStake: owner.userStake,
+          destination,
+        })
+        .signers([owner.user])
+        .rpc();
+
+    function claimAs(signer: Keypair, destination: PublicKey | null): Promise<string> {
+      return program.methods
+        .claimRewards(null)
+        .accounts({
+          user: signer.publicKey,
+          stakePool: ctx.pool,
+          userStake: owner.userStake,
+          stakingMint: ctx.mint,
+          userTokenAccount: owner.rewardAccount,
+          owner: owner.user.publicKey,
+          rewardMint: ctx.rewardMint,
+          treasuryVault: ctx.treasury,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+          destination,
+        })
+        .signers([signer])
+        .rpc();
+    }
+
+    async function expectRejected(call: Promise<string>, code: string): Promise<void> {
+      try {
+        await call;
+        expect.fail(`Should have thrown ${code}`);
+      } catch (error: any) {
+        expect(error.message).to.include(code);
+      }
+    }
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      await fundPool(ctx, TREASURY_FUND);
+      owner = await createStaker(ctx);
+      await stakeInto(ctx, owner, STAKE_AMOUNT, TIER_FLEX);
+
+      bot = Keypair.generate();
+      coldWallet = Keypair.generate();
+      await airdropSol(bot.publicKey);
+      coldAta = await createAccount(connection, admin, ctx.rewardMint, coldWallet.publicKey);
+      await program.methods
+        .setClaimDelegate(bot.publicKey)
+        .accounts({ user: owner.user.publicKey, stakePool: ctx.pool, userStake: owner.userStake })
+        .signers([owner.user])
+        .rpc();
+    });
+
+    it("should pay the owner's account while no destination is stored", async () => {
+      await advanceTime(2);
+      const before = await tokenBalance(owner.rewardAccount);
+      await claimAs(owner.user, null);
+      expect((await tokenBalance(owner.rewardAccount)).gt(before)).to.equal(true);
+      console.log("✓ Claim without a preference paid user_token_account");
+    });
+
+    it("should reject a destination for another mint", async () => {
+      const otherMint = await createMint(connection, admin, admin.publicKey, null, DECIMALS);
+      const wrongMint = await createAccount(connection, admin, otherMint, coldWallet.publicKey);
+      await expectRejected(setRewardDestination(wrongMint), "MintMismatch");
+      console.log("✓ Wrong-mint destination rejected");
+    });
+
+    it("should send every claim to the stored destination", async () => {
+      await setRewardDestination(coldAta);
+      const userStake = await program.account.userStake.fetch(owner.userStake);
+      expect(userStake.rewardDestination.toBase58()).to.equal(coldAta.toBase58());
+
+      // Leaving it out, or naming another account, is refused
+      await advanceTime(2);
+      await expectRejected(claimAs(owner.user, null), "RewardDestinationMismatch");
+      await expectRejected(claimAs(bot, owner.rewardAccount), "RewardDestinationMismatch");
+
+      const ownerBefore = await tokenBalance(owner.rewardAccount);
+      for (const signer of [owner.user, bot]) {
+        const before = await tokenBalance(coldAta);
+        await advanceTime(2);
+        await claimAs(signer, coldAta);
+        expect((await tokenBalance(coldAta)).gt(before)).to.equal(true);
+      }
+      expect((await tokenBalance(owner.rewardAccount)).toString()).to.equal(ownerBefore.toString());
+      console.log("✓ Owner and delegate claims both landed in the cold wallet");
+    });
+
+    it("should fall back to the owner's account once cleared", async () => {
+      await setRewardDestination(null);
+      const userStake = await program.account.userStake.fetch(owner.userStake);
+      expect(userStake.rewardDestination.toBase58()).to.equal(PublicKey.default.toBase58());
+
+      await advanceTime(2);
+      await expectRejected(claimAs(bot, coldAta), "DelegateCannotRedirect");
+      const before = await tokenBalance(owner.rewardAccount);
+      await claimAs(bot, null);
+      expect((await tokenBalance(owner.rewardAccount)).gt(before)).to.equal(true);
+      console.log("✓ Cleared preference: claims pay user_token_account again");
+    });
+  });
+
+  // ============================================
+  // 90. STAKE SNAPSHOTS
+  // ============================================
+
+  describe("90. Stake Snapshots", () => {
+    const INTERVAL = 3;
+    let ctx: IsolatedPool;
+    let early: Staker;
+    let late: Staker;
+
+    const u64Le = (value: number) => new BN(value).toArrayLike(Buffer, "le", 8);
+
+    const snapshotPda = (epoch: number) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("snapshot"), ctx.pool.toBuffer(), u64Le(epoch)],
+        program.programId
+      )[0];
+
+    const userSnapshotPda = (epoch: number, owner: PublicKey) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("user_snapshot"), ctx.pool.toBuffer(), u64Le(epoch), owner.toBuffer()],
+        program.programId
+      )[0];
+
+    const setInterval = (seconds: number) =>
+      program.methods
+        .setSnapshotInterval(new BN(seconds))
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+
+    const takeSnapshot = (epoch: number) =>
+      program.methods
+        .takeSnapshot(new BN(epoch))
+        .accounts({ payer: nonAdmin.publicKey, stakePool: ctx.pool, snapshot: snapshotPda(epoch) })
+        .signers([nonAdmin])
+        .rpc();
+
+    const recordUser = (epoch: number, staker: Staker) =>
+      program.methods
+        .recordUserSnapshot(new BN(epoch))
+        .accounts({
+          payer: nonAdmin.publicKey,
+          stakePool: ctx.pool,
+          snapshot: snapshotPda(epoch),
+          userStake: staker.userStake,
+          userSnapshot: userSnapshotPda(epoch, staker.user.publicKey),
+        })
+        .signers([nonAdmin])
+        .rpc();
+
+    async function expectRejected(call: Promise<string>, code: string): Promise<void> {
+      try {
+        await call;
+        expect.fail(`Should have thrown ${code}`);
+      } catch (error: any) {
+        expect(error.message).to.include(code);
+      }
+    }
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      await fundPool(ctx, TREASURY_FUND);
+      early = await createStaker(ctx);
+      late = await createStaker(ctx);
+      await stakeInto(ctx, early, STAKE_AMOUNT, TIER_FLEX);
+    });
+
+    it("should refuse snapshots until an interval is set", async () => {
+      await expectRejected(takeSnapshot(0), "SnapshotsDisabled");
+      await setInterval(INTERVAL);
+      console.log("✓ Snapshots disabled by default");
+    });
+
+    it("should take at most one snapshot per interval", async () => {
+      // Deposits in the snapshot's own second do not count as before it
+      const stakedAt = (await program.account.userStake.fetch(early.userStake)).lastStakeTime.toNumber();
+      while ((await getCurrentTimestamp()) <= stakedAt) {
+        await advanceTime(2);
+      }
+      await takeSnapshot(0);
+      const snapshot = await program.account.snapshot.fetch(snapshotPda(0));
+      expect(snapshot.epoch.toNumber()).to.equal(0);
+      expect(snapshot.totalStaked.toString()).to.equal(STAKE_AMOUNT.toString());
+      expect(snapshot.stakerCount.toNumber()).to.equal(1);
+      expect(snapshot.tierStaked[TIER_FLEX].toString()).to.equal(STAKE_AMOUNT.toString());
+
+      await expectRejected(takeSnapshot(1), "SnapshotNotDue");
+      await expectRejected(takeSnapshot(2), "SnapshotEpochMismatch");
+      console.log("✓ Second snapshot inside the interval rejected");
+    });
+
+    it("should record positions against the latest epoch only", async () => {
+      await recordUser(0, early);
+      const record = await program.account.userSnapshot.fetch(userSnapshotPda(0, early.user.publicKey));
+      expect(record.stakedAmount.toString()).to.equal(STAKE_AMOUNT.toString());
+      expect(record.epoch.toNumber()).to.equal(0);
+
+      // A deposit after the snapshot cannot be counted in it
+      await stakeInto(ctx, late, STAKE_AMOUNT.muln(2), TIER_FLEX);
+      await expectRejected(recordUser(0, late), "StakedAfterSnapshot");
+
+      const snapshotTime = (await program.account.snapshot.fetch(snapshotPda(0))).timestamp.toNumber();
+      while ((await getCurrentTimestamp()) < snapshotTime + INTERVAL) {
+        await advanceTime(2);
+      }
+      await takeSnapshot(1);
+      const second = await program.account.snapshot.fetch(snapshotPda(1));
+      expect(second.totalStaked.toString()).to.equal(STAKE_AMOUNT.muln(3).toString());
+      expect(second.stakerCount.toNumber()).to.equal(2);
+
+      await expectRejected(recordUser(0, early), "SnapshotEpochMismatch");
+      await recordUser(1, early);
+      await recordUser(1, late);
+      const lateRecord = await program.account.userSnapshot.fetch(
+        userSnapshotPda(1, late.user.publicKey)
+      );
+      expect(lateRecord.stakedAmount.toString()).to.equal(STAKE_AMOUNT.muln(2).toString());
+
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.snapshotCount.toNumber()).to.equal(2);
+      console.log("✓ Two epochs snapshotted, positions recorded against each");
+    });
+
+    it("should not record a transferred position twice in one epoch", async () => {
+      const heir = await createStaker(ctx);
+      await program.methods
+        .transferStakeOwnership()
+        .accounts({
+          owner: early.user.publicKey,
+          newOwner: heir.user.publicKey,
+          stakePool: ctx.pool,
+          userStake: early.userStake,
+          newUserStake: heir.userStake,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([early.user, heir.user])
+        .rpc();
+
+      await expectRejected(recordUser(1, heir), "StakedAfterSnapshot");
+      console.log("✓ Moved position counts as a deposit after the snapshot");
+    });
+  });
+
+  // ============================================
+  // 91. RECEIPT MODE
+  // ============================================
+
+  describe("91. Receipt Mode", () => {
+    let ctx: IsolatedPool;
+    let staker: Staker;
+    let receipts: PublicKey;
+    let elsewhere: PublicKey;
+
+    const stakeWithReceipts = (amount: BN, receiptAccount: PublicKey | null) =>
+      program.methods
+        .stake(amount, TIER_FLEX, TOPUP_KEEP_ORIGINAL as any, null, null, null)
+        .accounts({
+          user: staker.user.publicKey,
+          stakePool: ctx.pool,
+          userStake: staker.userStake,
+          stakerRecord: stakerRecordPda(ctx.pool, staker.user.publicKey),
+          stakingMint: ctx.mint,
+          userTokenAccount: staker.tokenAccount,
+          stakingVault: ctx.vault,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+          rent: SYSVAR_RENT_PUBKEY,
+          receiptMint: receiptAccount ? ctx.receiptMint : null,
+          receiptAccount,
+        })
+        .signers([staker.user])
+        .rpc();
+
+    const unstakeWithReceipts = (amount: BN) =>
+      program.methods
+        .unstake(amount)
+        .accounts({
+          treasuryVault: ctx.treasury,
+          user: staker.user.publicKey,
+          stakePool: ctx.pool,
+          userStake: staker.userStake,
+          stakingMint: ctx.mint,
+          userTokenAccount: staker.tokenAccount,
+          stakingVault: ctx.vault,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+          receiptMint: ctx.receiptMint,
+          receiptAccount: receipts,
+        })
+        .signers([staker.user])
+        .rpc();
+
+    const receiptSupply = async () =>
+      new BN((await getMint(connection, ctx.receiptMint!)).supply.toString());
+
+    async function expectRejected(call: Promise<string>, code: string): Promise<void> {
+      try {
+        await call;
+        expect.fail(`Should have thrown ${code}`);
+      } catch (error: any) {
+        expect(error.message).to.include(code);
+      }
+    }
+
+    before(async () => {
+      ctx = await createIsolatedPool(
+        EMISSION_CAP, null, false, REWARD_MODE_APY, false, DEFAULT_TOPUP_MASK, true
+      );
+      await fundPool(ctx, TREASURY_FUND);
+      staker = await createStaker(ctx);
+      receipts = await createAccount(connection, staker.user, ctx.receiptMint!, staker.user.publicKey);
+      elsewhere = await createAccount(connection, nonAdmin, ctx.receiptMint!, nonAdmin.publicKey);
+    });
+
+    it("should create a receipt mint controlled by the pool", async () => {
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.receiptMint.toBase58()).to.equal(ctx.receiptMint!.toBase58());
+
+      const mint = await getMint(connection, ctx.receiptMint!);
+      expect(mint.mintAuthority!.toBase58()).to.equal(ctx.pool.toBase58());
+      expect(mint.freezeAuthority).to.equal(null);
+      expect(mint.decimals).to.equal(DECIMALS);
+      expect(mint.supply.toString()).to.equal("0");
+      console.log("✓ Receipt mint is a pool PDA");
+    });
+
+    it("should refuse stakes without receipt accounts", async () => {
+      await expectRejected(stakeWithReceipts(STAKE_AMOUNT, null), "MissingReceiptAccounts");
+      console.log("✓ Stake without receipts rejected");
+    });
+
+    it("should mint on stake and burn on unstake, one for one", async () => {
+      await stakeWithReceipts(STAKE_AMOUNT, receipts);
+      expect((await tokenBalance(receipts)).toString()).to.equal(STAKE_AMOUNT.toString());
+      expect((await receiptSupply()).toString()).to.equal(STAKE_AMOUNT.toString());
+
+      const half = STAKE_AMOUNT.divn(2);
+      await unstakeWithReceipts(half);
+      const remaining = STAKE_AMOUNT.sub(half);
+      expect((await tokenBalance(receipts)).toString()).to.equal(remaining.toString());
+      expect((await receiptSupply()).toString()).to.equal(remaining.toString());
+
+      const position = await program.account.userStake.fetch(staker.userStake);
+      expect(position.stakedAmount.toString()).to.equal(remaining.toString());
+      console.log("✓ Receipt supply tracks staked principal");
+    });
+
+    it("should keep principal locked while the receipts are elsewhere", async () => {
+      const remaining = await tokenBalance(receipts);
+      await transfer(
+        connection, staker.user, receipts, elsewhere, staker.user, BigInt(remaining.toString())
+      );
+      await expectRejected(unstakeWithReceipts(remaining), "InsufficientReceipts");
+
+      // Rewards stay with the position owner, not the receipt holder
+      await advanceTime(4);
+      await claimFrom(ctx, staker);
+
+      await transfer(
+        connection, nonAdmin, elsewhere, receipts, nonAdmin, BigInt(remaining.toString())
+      );
+      await unstakeWithReceipts(remaining);
+      expect((await receiptSupply()).toString()).to.equal("0");
+
+      const position = await program.account.userStake.fetch(staker.userStake);
+      expect(position.stakedAmount.toString()).to.equal("0");
+      console.log("✓ Unstake waits for the receipts to come back");
+    });
+  });
+
+  // ============================================
+  // 92. TRANSFER STAKE OWNERSHIP
+  // ============================================
+
+  describe("92. Transfer Stake Ownership", () => {
+    let ctx: IsolatedPool;
+    let from: Staker;
+    let to: Staker;
+    let coreHolder: Staker;
+
+    const transferIx = (source: Staker, dest: Staker) =>
+      program.methods
+        .transferStakeOwnership()
+        .accounts({
+          owner: source.user.publicKey,
+          newOwner: dest.user.publicKey,
+          stakePool: ctx.pool,
+          userStake: source.userStake,
+          newUserStake: dest.userStake,
+          systemProgram: SystemProgram.programId,
+        })
+        .instruction();
+
+    // Send with only `signer`, marking the other owner as a non-signer so
+    // the program, not the client, has to catch it
+    async function sendSignedBy(ix: TransactionInstruction, signer: Keypair): Promise<string> {
+      ix.keys.forEach((key) => {
+        key.isSigner = key.pubkey.equals(signer.publicKey);
+      });
+      return provider.sendAndConfirm(new Transaction().add(ix), [signer]);
+    }
+
+    async function expectRejected(call: Promise<string>, code: string): Promise<void> {
+      try {
+        await call;
+        expect.fail(`Should have thrown ${code}`);
+      } catch (error: any) {
+        // Raw sends report the Anchor error name in the logs only
+        const details = [error.message, ...(error.logs ?? [])].join("\n");
+        expect(details).to.include(code);
+      }
+    }
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      await fundPool(ctx, TREASURY_FUND);
+      from = await createStaker(ctx);
+      to = await createStaker(ctx);
+      coreHolder = await createStaker(ctx);
+      await stakeInto(ctx, from, STAKE_AMOUNT, TIER_PRIME);
+      await stakeInto(ctx, coreHolder, STAKE_AMOUNT, TIER_CORE);
+    });
+
+    it("should require the current owner's signature", async () => {
+      await expectRejected(sendSignedBy(await transferIx(from, to), to.user), "AccountNotSigner");
+      console.log("✓ Transfer without the owner rejected");
+    });
+
+    it("should require the new owner's signature", async () => {
+      await expectRejected(sendSignedBy(await transferIx(from, to), from.user), "AccountNotSigner");
+      expect(await connection.getAccountInfo(to.userStake)).to.equal(null);
+      console.log("✓ Transfer without the new owner rejected");
+    });
+
+    it("should refuse a new owner with an open position in another tier", async () => {
+      await expectRejected(
+        program.methods
+          .transferStakeOwnership()
+          .accounts({
+            owner: from.user.publicKey,
+            newOwner: coreHolder.user.publicKey,
+            stakePool: ctx.pool,
+            userStake: from.userStake,
+            newUserStake: coreHolder.userStake,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([from.user, coreHolder.user])
+          .rpc(),
+        "CannotChangeTier"
+      );
+      console.log("✓ Conflicting tier rejected");
+    });
+
+    it("should move the whole position when both owners sign", async () => {
+      await advanceTime(2);
+      const before = await program.account.userStake.fetch(from.userStake);
+      const poolBefore = await program.account.stakePool.fetch(ctx.pool);
+
+      await program.methods
+        .transferStakeOwnership()
+        .accounts({
+          owner: from.user.publicKey,
+          newOwner: to.user.publicKey,
+          stakePool: ctx.pool,
+          userStake: from.userStake,
+          newUserStake: to.userStake,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([from.user, to.user])
+        .rpc();
+
+      expect(await connection.getAccountInfo(from.userStake)).to.equal(null);
+      const moved = await program.account.userStake.fetch(to.userStake);
+      expect(moved.owner.toBase58()).to.equal(to.user.publicKey.toBase58());
+      expect(moved.stakedAmount.toString()).to.equal(before.stakedAmount.toString());
+      expect(moved.tier).to.deep.equal(before.tier);
+      expect(moved.stakeStartTime.toNumber()).to.equal(before.stakeStartTime.toNumber());
+      expect(moved.lastClaimTime.toNumber()).to.equal(before.lastClaimTime.toNumber());
+      expect(moved.pendingRewards.toString()).to.equal(before.pendingRewards.toString());
+
+      const poolAfter = await program.account.stakePool.fetch(ctx.pool);
+      expect(poolAfter.stakerCount.toNumber()).to.equal(poolBefore.stakerCount.toNumber());
+      expect(poolAfter.totalStaked.toString()).to.equal(poolBefore.totalStaked.toString());
+      console.log("✓ Position moved, staker count unchanged");
+    });
+  });
+
+  // ============================================
+  // 93. RECOVERY ADDRESS
+  // ============================================
+
+  describe("93. Recovery Address", () => {
+    let ctx: IsolatedPool;
+    let owner: Staker;
+    let rescuer: Staker;
+    let stranger: Staker;
+
+    const setRecovery = (address: PublicKey, delaySeconds: number) =>
+      program.methods
+        .setRecoveryAddress(address, new BN(delaySeconds))
+        .accounts({
+          user: owner.user.publicKey,
+          stakePool: ctx.pool,
+          userStake: owner.userStake,
+        })
+        .signers([owner.user])
+        .rpc();
+
+    const initiate = (signer: Staker) =>
+      program.methods
+        .initiateRecovery()
+        .accounts({
+          recoveryAuthority: signer.user.publicKey,
+          stakePool: ctx.pool,
+          userStake: owner.userStake,
+        })
+        .signers([signer.user])
+        .rpc();
+
+    const complete = () =>
+      program.methods
+        .completeRecovery()
+        .accounts({
+          recoveryAuthority: rescuer.user.publicKey,
+          owner: owner.user.publicKey,
+          stakePool: ctx.pool,
+          userStake: owner.userStake,
+          newUserStake: rescuer.userStake,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([rescuer.user])
+        .rpc();
+
+    async function expectRejected(call: Promise<string>, code: string): Promise<void> {
+      try {
+        await call;
+        expect.fail(`Should have thrown ${code}`);
+      } catch (error: any) {
+        expect(error.message).to.include(code);
+      }
+    }
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      await fundPool(ctx, TREASURY_FUND);
+      owner = await createStaker(ctx);
+      rescuer = await createStaker(ctx);
+      stranger = await createStaker(ctx);
+      await stakeInto(ctx, owner, STAKE_AMOUNT, TIER_CORE);
+    });
+
+    it("should refuse the owner as their own recovery address", async () => {
+      await expectRejected(setRecovery(owner.user.publicKey, 60), "InvalidRecoveryConfig");
+      await expectRejected(setRecovery(rescuer.user.publicKey, 0), "InvalidRecoveryConfig");
+      console.log("✓ Invalid recovery configs rejected");
+    });
+
+    it("should only let the recovery address initiate", async () => {
+      await setRecovery(rescuer.user.publicKey, 3600);
+      await expectRejected(initiate(stranger), "NotRecoveryAddress");
+      console.log("✓ Stranger cannot initiate a recovery");
+    });
+
+    it("should not complete before the delay", async () => {
+      await initiate(rescuer);
+      const position = await program.account.userStake.fetch(owner.userStake);
+      expect(position.recoveryStartedAt.toNumber()).to.be.greaterThan(0);
+
+      await expectRejected(initiate(rescuer), "RecoveryAlreadyPending");
+      await expectRejected(complete(), "RecoveryDelayNotElapsed");
+      console.log("✓ Early completion rejected");
+    });
+
+    it("should cancel a pending recovery on any owner-signed instruction", async () => {
+      const label = Array.from(Buffer.alloc(32, 0));
+      label.splice(0, 4, ...Buffer.from("mine"));
+      await program.methods
+        .setStakeLabel(label)
+        .accounts({
+          user: owner.user.publicKey,
+          stakePool: ctx.pool,
+          userStake: owner.userStake,
+        })
+        .signers([owner.user])
+        .rpc();
+
+      const position = await program.account.userStake.fetch(owner.userStake);
+      expect(position.recoveryStartedAt.toNumber()).to.equal(0);
+      expect(position.recoveryAddress.toBase58()).to.equal(rescuer.user.publicKey.toBase58());
+
+      // The recovery address lost the race; it has to start over
+      await expectRejected(complete(), "NoPendingRecovery");
+      console.log("✓ Owner activity cancelled the recovery");
+    });
+
+    it("should move the position once the delay passes unchallenged", async () => {
+      await setRecovery(rescuer.user.publicKey, 1);
+      await initiate(rescuer);
+      await advanceTime(4);
+
+      const before = await program.account.userStake.fetch(owner.userStake);
+      await complete();
+
+      expect(await connection.getAccountInfo(owner.userStake)).to.equal(null);
+      const recovered = await program.account.userStake.fetch(rescuer.userStake);
+      expect(recovered.owner.toBase58()).to.equal(rescuer.user.publicKey.toBase58());
+      expect(recovered.stakedAmount.toString()).to.equal(before.stakedAmount.toString());
+      expect(recovered.recoveryAddress.toBase58()).to.equal(PublicKey.default.toBase58());
+      expect(recovered.recoveryStartedAt.toNumber()).to.equal(0);
+      console.log("✓ Recovery completed after the delay");
+    });
+  });
+
+  // ============================================
+  // 94. FEE VAULT COLLECTION
+  // ============================================
+
+  describe("94. Fee Vault Collection", () => {
+    const FEE_BPS = 250;
+    let ctx: IsolatedPool;
+    let staker: Staker;
+    let feeVault: PublicKey;
+    let destination: PublicKey;
+
+    const collectFees = (authority: Keypair = admin) =>
+      program.methods
+        .collectFees()
+        .accounts({
+          authority: authority.publicKey,
+          stakePool: ctx.pool,
+          feeVault,
+          destination,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([authority])
+        .rpc();
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      staker = await createStaker(ctx);
+      await fundPool(ctx, TREASURY_FUND);
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_FLEX);
+      [feeVault] = PublicKey.findProgramAddressSync(
+        [FEE_VAULT_SEED, ctx.pool.toBuffer()],
+        program.programId
+      );
+      destination = await createAccount(connection, admin, ctx.rewardMint, Keypair.generate().publicKey);
+    });
+
+    it("should create the fee vault PDA owned by the pool", async () => {
+      await program.methods
+        .initializeFeeVault()
+        .accounts({
+          authority: admin.publicKey,
+          stakePool: ctx.pool,
+          rewardMint: ctx.rewardMint,
+          feeVault,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          rent: SYSVAR_RENT_PUBKEY,
+        })
+        .signers([admin])
+        .rpc();
+
+      const vault = await getAccount(connection, feeVault);
+      expect(vault.owner.toBase58()).to.equal(ctx.pool.toBase58());
+      expect(vault.mint.toBase58()).to.equal(ctx.rewardMint.toBase58());
+      console.log("✓ Fee vault created");
+    });
+
+    it("should refuse to sweep an empty vault", async () => {
+      try {
+        await collectFees();
+        expect.fail("Should have thrown NoFeesToCollect");
+      } catch (error: any) {
+        expect(error.message).to.include("NoFeesToCollect");
+        console.log("✓ Empty sweep rejected");
+      }
+    });
+
+    it("should let only the authority sweep collected fees", async () => {
+      await program.methods
+        .proposeClaimFee(FEE_BPS)
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool, feeVault })
+        .signers([admin])
+        .rpc();
+      await program.methods.applyClaimFee().accounts({ stakePool: ctx.pool }).rpc();
+
+      await advanceTime(2);
+      await program.methods
+        .claimRewards(null)
+        .accounts({
+          user: staker.user.publicKey,
+          stakePool: ctx.pool,
+          userStake: staker.userStake,
+          stakingMint: ctx.mint,
+          userTokenAccount: staker.rewardAccount,
+          owner: staker.user.publicKey,
+          rewardMint: ctx.rewardMint,
+          treasuryVault: ctx.treasury,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+          feeVault,
+        })
+        .signers([staker.user])
+        .rpc();
+
+      const fees = await tokenBalance(feeVault);
+      expect(fees.gtn(0)).to.equal(true);
+
+      try {
+        await collectFees(staker.user);
+        expect.fail("Should have thrown Unauthorized");
+      } catch (error: any) {
+        expect(error.message).to.include("Unauthorized");
+      }
+
+      await collectFees();
+      expect((await tokenBalance(feeVault)).toNumber()).to.equal(0);
+      expect((await tokenBalance(destination)).toString()).to.equal(fees.toString());
+      console.log(`✓ Swept ${fees} in fees`);
+    });
+  });
+
+  // ============================================
+  // 95. APY BOOST CAMPAIGNS
+  // ============================================
+
+  describe("95. APY Boost Campaigns", () => {
+    const DOUBLE = 20_000;
+    let ctx: IsolatedPool;
+
+    const setBoost = (multiplierBps: number, startTime: number, endTime: number, authority: Keypair = admin) =>
+      program.methods
+        .setApyBoost(multiplierBps, new BN(startTime), new BN(endTime))
+        .accounts({ authority: authority.publicKey, stakePool: ctx.pool })
+        .signers([authority])
+        .rpc();
+
+    async function expectRejected(call: Promise<string>, code: string): Promise<void> {
+      try {
+        await call;
+        expect.fail(`Should have thrown ${code}`);
+      } catch (error: any) {
+        expect(error.message).to.include(code);
+      }
+    }
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+    });
+
+    it("should refuse a boost that starts in the past", async () => {
+      const now = await getCurrentTimestamp();
+      await expectRejected(setBoost(DOUBLE, now - 3600, now + 3600), "InvalidApyBoost");
+      console.log("✓ Retroactive boost rejected");
+    });
+
+    it("should refuse multipliers outside (1x, 3x]", async () => {
+      const now = await getCurrentTimestamp();
+      await expectRejected(setBoost(10_000, now + 60, now + 3600), "InvalidApyBoost");
+      await expectRejected(setBoost(30_001, now + 60, now + 3600), "InvalidApyBoost");
+      console.log("✓ Out-of-range multipliers rejected");
+    });
+
+    it("should only let the authority schedule a boost", async () => {
+      const outsider = Keypair.generate();
+      await airdropSol(outsider.publicKey, 1);
+      const now = await getCurrentTimestamp();
+      await expectRejected(setBoost(DOUBLE, now + 60, now + 3600, outsider), "Unauthorized");
+      console.log("✓ Non-admin boost rejected");
+    });
+
+    it("should schedule back-to-back campaigns but never overlapping ones", async () => {
+      const now = await getCurrentTimestamp();
+      const start = now + 3600;
+      const end = start + 2 * 86_400;
+      await setBoost(DOUBLE, start, end);
+
+      await expectRejected(setBoost(15_000, end - 1, end + 3600), "ApyBoostOverlap");
+      await setBoost(15_000, end, end + 3600);
+
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.apyBoostCount.toNumber()).to.equal(2);
+      expect(pool.apyBoosts[0].multiplierBps).to.equal(DOUBLE);
+      expect(pool.apyBoosts[0].startTime.toNumber()).to.equal(start);
+      expect(pool.apyBoosts[1].endTime.toNumber()).to.equal(end + 3600);
+      console.log("✓ Campaigns recorded without overlap");
+    });
+  });
+
+  // ============================================
+  // 96. GARBAGE COLLECTION OF IDLE POSITIONS
+  // ============================================
+
+  describe("96. Garbage Collection of Idle Positions", () => {
+    const THIRTY_DAYS = 30 * 86_400;
+    let ctx: IsolatedPool;
+    let holder: Staker;
+    let leaver: Staker;
+    let cranker: Keypair;
+
+    const gc = (owner: Staker) =>
+      program.methods
+        .gcUserStake()
+        .accounts({
+          cranker: cranker.publicKey,
+          owner: owner.user.publicKey,
+          stakePool: ctx.pool,
+          userStake: owner.userStake,
+        })
+        .signers([cranker])
+        .rpc();
+
+    const setGcInactivity = (seconds: number) =>
+      program.methods
+        .setGcInactivity(new BN(seconds))
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+
+    async function expectRejected(call: Promise<string>, code: string): Promise<void> {
+      try {
+        await call;
+        expect.fail(`Should have thrown ${code}`);
+      } catch (error: any) {
+        expect(error.message).to.include(code);
+      }
+    }
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      await fundPool(ctx, TREASURY_FUND);
+      holder = await createStaker(ctx);
+      leaver = await createStaker(ctx);
+      cranker = Keypair.generate();
+      await airdropSol(cranker.publicKey, 1);
+      await stakeInto(ctx, holder, STAKE_AMOUNT, TIER_FLEX);
+      await stakeInto(ctx, leaver, STAKE_AMOUNT, TIER_FLEX);
+    });
+
+    it("should do nothing until the admin enables it", async () => {
+      await expectRejected(gc(holder), "GcDisabled");
+      console.log("✓ Garbage collection off by default");
+    });
+
+    it("should refuse inactivity periods shorter than 30 days", async () => {
+      await expectRejected(setGcInactivity(60), "InvalidGcInactivity");
+      await setGcInactivity(THIRTY_DAYS);
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.gcInactivitySecs.toNumber()).to.equal(THIRTY_DAYS);
+      console.log("✓ Inactivity period enforced");
+    });
+
+    it("should never let a third party close a position holding principal", async () => {
+      await expectRejected(gc(holder), "PositionNotEmpty");
+      expect(await connection.getAccountInfo(holder.userStake)).to.not.equal(null);
+      console.log("✓ Staked position cannot be collected");
+    });
+
+    it("should never let a third party close an emptied but recently active position", async () => {
+      await advanceTime(2);
+      await unstakeFrom(ctx, leaver, STAKE_AMOUNT);
+
+      // Rewards settled by the exit are still owed to the owner
+      const exited = await program.account.userStake.fetch(leaver.userStake);
+      if (exited.pendingRewards.gtn(0)) {
+        await expectRejected(gc(leaver), "RewardsOutstanding");
+        await claimFrom(ctx, leaver);
+      }
+
+      const ownerLamports = await connection.getBalance(leaver.user.publicKey);
+      await expectRejected(gc(leaver), "PositionRecentlyActive");
+      expect(await connection.getAccountInfo(leaver.userStake)).to.not.equal(null);
+      expect(await connection.getBalance(leaver.user.publicKey)).to.equal(ownerLamports);
+      console.log("✓ Recently active position cannot be collected");
+    });
+  });
+
+  // ============================================
+  // 97. MINIMUM CLAIM INTERVAL
+  // ============================================
+
+  describe("97. Minimum Claim Interval", () => {
+    const ONE_HOUR = 3_600;
+    let ctx: IsolatedPool;
+    let staker: Staker;
+
+    const setMinClaimInterval = (seconds: number) =>
+      program.methods
+        .setMinClaimInterval(new BN(seconds))
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+
+    async function expectRejected(call: Promise<string>, code: string): Promise<void> {
+      try {
+        await call;
+        expect.fail(`Should have thrown ${code}`);
+      } catch (error: any) {
+        expect(error.message).to.include(code);
+      }
+    }
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      await fundPool(ctx, TREASURY_FUND);
+      staker = await createStaker(ctx);
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_FLEX);
+    });
+
+    it("should allow back-to-back claims while the interval is 0", async () => {
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.minClaimIntervalSeconds.toNumber()).to.equal(0);
+
+      await advanceTime(2);
+      await claimFrom(ctx, staker);
+      await advanceTime(2);
+      await claimFrom(ctx, staker);
+      console.log("✓ No interval by default");
+    });
+
+    it("should reject intervals longer than 7 days", async () => {
+      await expectRejected(setMinClaimInterval(7 * 86_400 + 1), "InvalidClaimInterval");
+      await expectRejected(setMinClaimInterval(-1), "InvalidClaimInterval");
+      console.log("✓ Interval range enforced");
+    });
+
+    it("should reject a claim inside the interval", async () => {
+      await setMinClaimInterval(ONE_HOUR);
+      await advanceTime(2);
+
+      const before = await tokenBalance(staker.rewardAccount);
+      await expectRejected(claimFrom(ctx, staker), "ClaimTooSoon");
+      expect((await tokenBalance(staker.rewardAccount)).toString()).to.equal(before.toString());
+      console.log("✓ Early claim rejected");
+    });
+
+    it("should never hold back staking or unstaking", async () => {
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_FLEX);
+      await unstakeFrom(ctx, staker, STAKE_AMOUNT);
+
+      const position = await program.account.userStake.fetch(staker.userStake);
+      expect(position.stakedAmount.toString()).to.equal(STAKE_AMOUNT.toString());
+      console.log("✓ Stake and unstake settle without the interval");
+    });
+
+    it("should allow claims again once the interval is cleared", async () => {
+      await setMinClaimInterval(0);
+      await advanceTime(2);
+      await claimFrom(ctx, staker);
+      console.log("✓ Claims resume with interval 0");
+    });
+  });
+
+  // ============================================
+  // 98. DOWNGRADE AFTER LOCK
+  // ============================================
+
+  describe("98. Downgrade After Lock", () => {
+    const SHORT_CORE_LOCK = 2;
+    let ctx: IsolatedPool;
+    let staker: Staker;
+
+    const setDowngradeAfterLock = (enabled: boolean) =>
+      program.methods
+        .setDowngradeAfterLock(enabled)
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+
+    const viewPending = () =>
+      program.methods
+        .viewPendingRewards()
+        .accounts({ stakePool: ctx.pool, userStake: staker.userStake })
+        .view();
+
+    const pause = (secs: number) =>
+      new Promise((resolve) => setTimeout(resolve, secs * 1000));
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      await fundPool(ctx, TREASURY_FUND);
+      await program.methods
+        .setLockPeriods(new BN(0), new BN(SHORT_CORE_LOCK), new BN(PRIME_LOCK_PERIOD))
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+      staker = await createStaker(ctx);
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_CORE);
+    });
+
+    it("should keep the tier APY after the lock by default", async () => {
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.downgradeAfterLock).to.equal(false);
+
+      await pause(SHORT_CORE_LOCK + 1);
+      const quote = await viewPending();
+      expect(quote.apyBps).to.equal(pool.coreApy);
+      console.log("✓ Ended lock keeps Core APY while the flag is off");
+    });
+
+    it("should quote the Flex APY for an ended lock once enabled", async () => {
+      await setDowngradeAfterLock(true);
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.downgradeAfterLock).to.equal(true);
+
+      const quote = await viewPending();
+      expect(quote.apyBps).to.equal(pool.flexApy);
+      console.log("✓ Ended lock earns Flex APY");
+    });
+
+    it("should restore the tier APY on relock", async () => {
+      await program.methods
+        .changeTier(TIER_CORE)
+        .accounts({
+          user: staker.user.publicKey,
+          stakePool: ctx.pool,
+          userStake: staker.userStake,
+        })
+        .signers([staker.user])
+        .rpc();
+
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      const quote = await viewPending();
+      expect(quote.apyBps).to.equal(pool.coreApy);
+      console.log("✓ Relock regains Core APY");
+    });
+
+    it("should settle Flex-rate accrual before auto-renew moves the lock", async () => {
+      await pause(SHORT_CORE_LOCK + 1);
+      const before = await program.account.userStake.fetch(staker.userStake);
+      const accrued = (await viewPending()).claimable.sub(before.pendingRewards);
+      expect(accrued.toNumber()).to.be.greaterThan(0);
+
+      await program.methods
+        .setAutoRenew(true)
+        .accounts({
+          user: staker.user.publicKey,
+          stakePool: ctx.pool,
+          userStake: staker.userStake,
+        })
+        .signers([staker.user])
+        .rpc();
+
+      const after = await program.account.userStake.fetch(staker.userStake);
+      expect(after.autoRenew).to.equal(true);
+      expect(after.lastClaimTime.toNumber()).to.be.greaterThan(before.lastClaimTime.toNumber());
+      expect(after.pendingRewards.gte(before.pendingRewards.add(accrued))).to.be.true;
+      console.log("✓ Ended-lock interval settled at Flex before renewing");
+    });
+  });
+
+  // ============================================
+  // 99. ACCRUAL CAPPED AT THE EMISSION CAP
+  // ============================================
+
+  describe("99. Accrual Capped At The Emission Cap", () => {
+    const TINY_CAP = new BN(1_000);
+    let ctx: IsolatedPool;
+    let staker: Staker;
+
+    const claimable = async (): Promise<BN> =>
+      (
+        await program.methods
+          .viewPendingRewards()
+          .accounts({ stakePool: ctx.pool, userStake: staker.userStake })
+          .view()
+      ).claimable;
+
+    before(async () => {
+      ctx = await createIsolatedPool(TINY_CAP);
+      await fundPool(ctx, TREASURY_FUND);
+      staker = await createStaker(ctx);
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_FLEX);
+    });
+
+    it("should stop accruing once the emission cap is reserved", async () => {
+      await advanceTime(4);
+      const first = await claimable();
+      expect(first.toString()).to.equal(TINY_CAP.toString());
+
+      await advanceTime(4);
+      expect((await claimable()).toString()).to.equal(TINY_CAP.toString());
+      console.log("✓ Accrual flat-lines at the cap");
+    });
+
+    it("should keep what a capped settlement held back", async () => {
+      // A top-up settles the position while the cap is reserved
+      await stakeInto(ctx, staker, STAKE_AMOUNT, TIER_FLEX);
+
+      const position = await program.account.userStake.fetch(staker.userStake);
+      expect(position.pendingRewards.lte(TINY_CAP)).to.equal(true);
+      expect(position.overCapRewards.gt(new BN(0))).to.equal(true);
+      console.log(`✓ ${position.overCapRewards.toString()} held back on the position`);
+    });
+
+    it("should share the capped budget between positions", async () => {
+      const other = await createStaker(ctx);
+      await stakeInto(ctx, other, STAKE_AMOUNT, TIER_FLEX);
+      await advanceTime(4);
+
+      const quote = async (userStake: PublicKey): Promise<BN> =>
+        (
+          await program.methods
+            .viewPendingRewards()
+            .accounts({ stakePool: ctx.pool, userStake })
+            .view()
+        ).claimable;
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      const unreserved = TINY_CAP.sub(pool.totalDistributed).sub(pool.pendingLiability);
+      const quoted = (await quote(staker.userStake)).add(await quote(other.userStake));
+      expect(quoted.lte(unreserved)).to.equal(true);
+      console.log("✓ Quotes across positions stay within the cap");
+    });
+
+    it("should resume accruing after the cap is raised", async () => {
+      await program.methods
+        .updateEmissionCap(EMISSION_CAP)
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+
+      const position = await program.account.userStake.fetch(staker.userStake);
+      expect(
+        (await claimable()).gte(position.overCapRewards.add(position.pendingRewards))
+      ).to.equal(true);
+      expect((await claimable()).gt(TINY_CAP)).to.equal(true);
+      console.log("✓ Accrual resumes under the raised cap, held-back rewards included");
+    });
+  });
+
+  // ============================================
+  // 100. WHITELIST-GATED STAKING
+  // ============================================
+
+  describe("100. Whitelist-Gated Staking", () => {
+    let ctx: IsolatedPool;
+    let approved: Staker;
+    let outsider: Staker;
+
+    const entryFor = (staker: Staker): PublicKey =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("whitelist"), ctx.pool.toBuffer(), staker.user.publicKey.toBuffer()],
+        program.programId
+      )[0];
+
+    const setWhitelistEnabled = (enabled: boolean) =>
+      program.methods
+        .setWhitelistEnabled(enabled)
+        .accounts({ authority: admin.publicKey, stakePool: ctx.pool })
+        .signers([admin])
+        .rpc();
+
+    const addToWhitelist = (staker: Staker) =>
+      program.methods
+        .addToWhitelist(staker.user.publicKey)
+        .accounts({
+          authority: admin.publicKey,
+          stakePool: ctx.pool,
+          whitelistEntry: entryFor(staker),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+
+    const removeFromWhitelist = (staker: Staker) =>
+      program.methods
+        .removeFromWhitelist()
+        .accounts({
+          authority: admin.publicKey,
+          stakePool: ctx.pool,
+          whitelistEntry: entryFor(staker),
+        })
+        .signers([admin])
+        .rpc();
+
+    const stakeWithEntry = (staker: Staker, entry: PublicKey | null) =>
+      program.methods
+        .stake(ONE_TOKEN, TIER_FLEX, TOPUP_KEEP_ORIGINAL as any, null, null, null)
+        .accounts({
+          user: staker.user.publicKey,
+          stakePool: ctx.pool,
+          userStake: staker.userStake,
+          stakerRecord: stakerRecordPda(ctx.pool, staker.user.publicKey),
+          stakingMint: ctx.mint,
+          userTokenAccount: staker.tokenAccount,
+          stakingVault: ctx.vault,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+          rent: SYSVAR_RENT_PUBKEY,
+          whitelistEntry: entry,
+        })
+        .signers([staker.user])
+        .rpc();
+
+    async function expectRejected(call: Promise<string>, code: string): Promise<void> {
+      try {
+        await call;
+        expect.fail(`Should have thrown ${code}`);
+      } catch (error: any) {
+        expect(error.message).to.include(code);
+      }
+    }
+
+    before(async () => {
+      ctx = await createIsolatedPool();
+      await fundPool(ctx, TREASURY_FUND);
+      approved = await createStaker(ctx);
+      outsider = await createStaker(ctx);
+    });
+
+    it("should let anyone stake while the whitelist is off", async () => {
+      const pool = await program.account.stakePool.fetch(ctx.pool);
+      expect(pool.whitelistEnabled).to.equal(false);
+
+      await stakeInto(ctx, outsider, STAKE_AMOUNT, TIER_FLEX);
+      console.log("✓ Open staking by default");
+    });
+
+    it("should only let whitelisted wallets stake once enabled", async () => {
+      await addToWhitelist(approved);
+      await setWhitelistEnabled(true);
+
+      await expectRejected(stakeWithEntry(outsider, null), "NotWhitelisted");
+      await stakeWithEntry(approved, entryFor(approved));
+
+      const entry = await program.account.whitelistEntry.fetch(entryFor(approved));
+      expect(entry.wallet.toBase58()).to.equal(approved.user.publicKey.toBase58());
+      console.log("✓ Only whitelisted wallets stake");
+    });
+
+    it("should reject another wallet's entry", async () => {
+      await expectRejected(stakeWithEntry(outsider, entryFor(approved)), "ConstraintSeeds");
+      console.log("✓ Entry is bound to its wallet");
+    });
+
+    it("should keep exits and claims open after removal mid-stake", async () => {
+      await removeFromWhitelist(approved);
+      expect(await connection.getAccountInfo(entryFor(approved))).to.equal(null);
+
+      await expectRejected(stakeWithEntry(approved, null), "NotWhitelisted");
+
+      await advanceTime(2);
+      await claimFrom(ctx, approved);
+      await unstakeFrom(ctx, approved, ONE_TOKEN);
+      const position = await program.account.userStake.fetch(approved.userStake);
+      expect(position.stakedAmount.toNumber()).to.equal(0);
+      console.log("✓ Removed wallet can still claim and unstake");
+    });
+
+    it("should reopen staking when the whitelist is disabled", async () => {
+      await setWhitelistEnabled(false);
+      await stakeWithEntry(outsider, null);
+      console.log("✓ Whitelist toggle reopens staking");
+    });
+  });
+
   // ============================================
   // FINAL SUMMARY
   // ============================================
@@ -1325,7 +10084,7 @@ describe("Nova Staking Program - Comprehensive Tests", () => {
     console.log(`  Total Staked:      ${formatTokens(poolState.totalStaked)}`);
     console.log(`  Total Distributed: ${formatTokens(poolState.totalDistributed)}`);
     console.log(`  Staker Count:      ${poolState.stakerCount}`);
-    console.log(`  Paused:            ${poolState.paused}`);
+    console.log(`  Pause Flags:       ${poolState.pauseFlags}`);
     console.log(`  APY (Flex/Core/Prime): ${poolState.flexApy}/${poolState.coreApy}/${poolState.primeApy} bp`);
     console.log("=".repeat(60) + "\n");
   });